@@ -29,4 +29,15 @@ fn main() {
 
     script.write_all(new_config.as_bytes()).unwrap();
     println!("cargo:rustc-link-arg=-T{}", &link_script.display());
+
+    // lockdep 沿帧指针记录加锁时的调用栈，没有帧指针时记录的地址没有意义
+    if env::var_os("CARGO_FEATURE_LOCKDEP").is_some() {
+        let flags = env::var("CARGO_ENCODED_RUSTFLAGS").unwrap_or_default();
+        if !flags
+            .split('\x1f')
+            .any(|flag| flag.ends_with("force-frame-pointers=yes"))
+        {
+            panic!("lockdep requires -C force-frame-pointers=yes in RUSTFLAGS, build with make LOCKDEP=y");
+        }
+    }
 }
@@ -0,0 +1,389 @@
+//! 内核崩溃转储
+//!
+//! 内核命令行中指定 `crashdump=<size>[@<addr>]` 后，一段物理内存被保留下来，不交给页帧分配器管理。
+//! 内核 panic 时，依次将 panic 信息、所有核的寄存器、控制台日志、各个核的内核栈以及任务列表写入该区域，然后热重启。
+//! 热重启不会清除内存中的内容，下一次启动时如果在该区域中发现完整的转储，就将其复制出来，
+//! 通过 `/proc/crashdump` 提供给用户态，由 `crashdump` 工具转换为 ELF core 文件供 GDB 离线分析。
+//!
+//! 转储由一个头部和若干条记录组成，每条记录以类型和长度开头，按 8 字节对齐。
+//! 每写完一条记录就更新头部中的长度，因此即使在写入过程中再次出错，已经写入的记录仍然有效。
+use crate::task::{TaskState, INIT_PROCESS};
+use crate::trap::{self, capture_registers, Registers, StopFrame};
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+use arch::hart_id;
+use config::{CPU_NUM, FRAME_SIZE};
+use core::cmp::min;
+use core::fmt::Write;
+use core::hint::spin_loop;
+use core::ops::Range;
+use core::panic::PanicInfo;
+use core::sync::atomic::{AtomicUsize, Ordering};
+use ksync::Mutex;
+use platform::config::CLOCK_FREQ;
+use spin::Once;
+use timer::{get_time_ms, read_timer};
+use vfscore::error::VfsError;
+use vfscore::file::VfsFile;
+use vfscore::inode::{InodeAttr, VfsInode};
+use vfscore::superblock::VfsSuperBlock;
+use vfscore::utils::{VfsFileStat, VfsNodePerm, VfsNodeType};
+use vfscore::VfsResult;
+
+/// 转储头部的魔数
+const DUMP_MAGIC: [u8; 8] = *b"ALIENDMP";
+const DUMP_VERSION: u32 = 1;
+
+/// panic 信息，UTF-8 文本
+const RECORD_MESSAGE: u32 = 1;
+/// 一个核的寄存器：hart id、pc 以及 x0-x31，均为 u64。第一条寄存器记录属于发生 panic 的核
+const RECORD_REGS: u32 = 2;
+/// 控制台日志，UTF-8 文本
+const RECORD_LOG: u32 = 3;
+/// 一段内存：起始地址(u64)以及内存中的数据
+const RECORD_MEMORY: u32 = 4;
+/// 任务列表，UTF-8 文本
+const RECORD_TASKS: u32 = 5;
+
+/// 每个核保存的内核栈的最大长度
+const STACK_DUMP_SIZE: usize = 4 * FRAME_SIZE;
+/// 等待其它核响应核间中断的最长时间(ms)
+const STOP_TIMEOUT_MS: usize = 100;
+
+/// 没有核在保存崩溃转储
+const NO_CRASH: usize = usize::MAX;
+
+extern "C" {
+    fn ekernel();
+}
+
+/// 转储区域的头部
+#[repr(C)]
+struct DumpHeader {
+    magic: [u8; 8],
+    version: u32,
+    cpu_num: u32,
+    /// 包括头部在内已经写入的字节数
+    size: u64,
+    /// 发生崩溃时系统已经运行的时间(ms)
+    time: u64,
+}
+
+const HEADER_SIZE: usize = core::mem::size_of::<DumpHeader>();
+
+/// 保留的转储区域
+static DUMP_REGION: Once<Range<usize>> = Once::new();
+/// 上一次启动时保存的转储
+static PREVIOUS_DUMP: Once<Vec<u8>> = Once::new();
+
+/// 正在保存崩溃转储的核
+static CRASH_HART: AtomicUsize = AtomicUsize::new(NO_CRASH);
+/// 响应了核间中断的核的数量
+static RESPONDED: AtomicUsize = AtomicUsize::new(0);
+/// 其它核响应核间中断时被打断的位置的寄存器
+static HART_REGS: Mutex<[Option<Registers>; CPU_NUM]> = Mutex::new([None; CPU_NUM]);
+
+/// 根据命令行确定转储区域，并将其从页帧分配器管理的内存中移出。需要在内存管理初始化之前调用
+pub fn reserve_dump_region(memory: &Range<usize>) {
+    let (size, addr) = match platform::cmdline::kernel_cmdline().crashdump {
+        Some(crashdump) => crashdump,
+        None => return,
+    };
+    let size = (size + FRAME_SIZE - 1) & !(FRAME_SIZE - 1);
+    let start = match addr {
+        Some(addr) => addr & !(FRAME_SIZE - 1),
+        None => memory.end.saturating_sub(size) & !(FRAME_SIZE - 1),
+    };
+    if start < ekernel as usize || start.saturating_add(size) > memory.end {
+        println!(
+            "crashdump: invalid region {:#x}-{:#x}, ignored",
+            start,
+            start.saturating_add(size)
+        );
+        return;
+    }
+    DUMP_REGION.call_once(|| start..start + size);
+    mem::reserve_frames(start..start + size);
+    println!("crashdump: reserved {:#x}-{:#x}", start, start + size);
+}
+
+/// 映射转储区域，并取出上一次启动时保存的转储，需要在内存管理初始化之后调用
+pub fn init_crash_dump() {
+    let region = match DUMP_REGION.get() {
+        Some(region) => region.clone(),
+        None => return,
+    };
+    if mem::query_kernel_space(region.start).is_none() {
+        mem::map_region_to_kernel(region.start, region.len(), "RWVAD".into());
+    }
+    let header = unsafe { &mut *(region.start as *mut DumpHeader) };
+    let valid = header.magic == DUMP_MAGIC
+        && header.version == DUMP_VERSION
+        && (HEADER_SIZE..=region.len()).contains(&(header.size as usize));
+    if valid {
+        let data =
+            unsafe { core::slice::from_raw_parts(region.start as *const u8, header.size as usize) };
+        PREVIOUS_DUMP.call_once(|| data.to_vec());
+        println!(
+            "crashdump: found a dump of {} bytes from the previous boot",
+            header.size
+        );
+    }
+    // 清除魔数，避免下一次启动时再次读取同一个转储
+    header.magic = [0; 8];
+}
+
+/// 在 `/proc` 中创建 `crashdump` 文件，需要在文件系统初始化之后调用
+pub fn init_crash_dump_file() {
+    if PREVIOUS_DUMP.get().is_none() {
+        return;
+    }
+    let root = vfs::proc::procfs_root().expect("procfs is not initialized");
+    root.add_file_manually("crashdump", Arc::new(CrashDumpFile), "r--------".into())
+        .unwrap();
+}
+
+/// 其它核响应核间中断时调用，如果有核正在保存崩溃转储，记录被打断的位置并等待保存完成
+pub fn handle_ipi(frame: &mut StopFrame) {
+    let crash_hart = CRASH_HART.load(Ordering::Acquire);
+    if crash_hart == NO_CRASH || crash_hart == hart_id() {
+        return;
+    }
+    HART_REGS.lock()[hart_id()] = Some(frame.registers());
+    RESPONDED.fetch_add(1, Ordering::AcqRel);
+    // 保存期间不能修改内存和任务列表
+    while CRASH_HART.load(Ordering::Acquire) == crash_hart {
+        spin_loop();
+    }
+}
+
+/// 内核 panic 时保存崩溃转储，返回是否保存了转储。没有保留转储区域时什么也不做
+pub fn save_crash_dump(info: &PanicInfo) -> bool {
+    let region = match DUMP_REGION.get() {
+        Some(region) => region.clone(),
+        None => return false,
+    };
+    arch::interrupt_disable();
+    let hart = hart_id();
+    if CRASH_HART
+        .compare_exchange(NO_CRASH, hart, Ordering::AcqRel, Ordering::Acquire)
+        .is_err()
+    {
+        return false;
+    }
+    let regs = capture_registers();
+    stop_other_harts();
+    let mut harts = *HART_REGS.lock();
+    harts[hart] = Some(regs);
+
+    let mut writer = DumpWriter::new(region);
+    writer.record(RECORD_MESSAGE, |buf| {
+        let mut out = SliceWriter::new(buf);
+        let _ = write!(out, "{}", info);
+        out.len
+    });
+    // 发生 panic 的核排在最前面
+    let order = core::iter::once(hart).chain((0..CPU_NUM).filter(|other| *other != hart));
+    for hart in order {
+        if let Some(regs) = &harts[hart] {
+            writer.record(RECORD_REGS, |buf| {
+                let values = [hart, regs.pc].into_iter().chain(regs.x);
+                let mut len = 0;
+                for (value, out) in values.zip(buf.chunks_exact_mut(8)) {
+                    out.copy_from_slice(&(value as u64).to_le_bytes());
+                    len += 8;
+                }
+                len
+            });
+        }
+    }
+    writer.record(RECORD_LOG, platform::console::read_log_ring);
+    for regs in harts.iter().flatten() {
+        let stack = mapped_range(regs.x[2], STACK_DUMP_SIZE);
+        if stack.is_empty() {
+            continue;
+        }
+        writer.record(RECORD_MEMORY, |buf| {
+            if buf.len() < 8 {
+                return 0;
+            }
+            let (addr, data) = buf.split_at_mut(8);
+            addr.copy_from_slice(&(stack.start as u64).to_le_bytes());
+            let len = min(data.len(), stack.len());
+            let src = unsafe { core::slice::from_raw_parts(stack.start as *const u8, len) };
+            data[..len].copy_from_slice(src);
+            8 + len
+        });
+    }
+    // 任务列表放在寄存器、日志和栈之后，遍历时只尝试获取任务的锁，锁被占用的任务会被跳过
+    writer.record(RECORD_TASKS, |buf| {
+        let mut out = SliceWriter::new(buf);
+        write_tasks(&mut out);
+        out.len
+    });
+    println!(
+        "crashdump: saved {} bytes at {:#x}",
+        writer.used, writer.region.start
+    );
+    CRASH_HART.store(NO_CRASH, Ordering::Release);
+    true
+}
+
+/// 通过核间中断让其它核记录寄存器，等待它们响应
+fn stop_other_harts() {
+    let count = trap::send_ipi_to_others();
+    let deadline = read_timer() + CLOCK_FREQ / 1000 * STOP_TIMEOUT_MS;
+    while RESPONDED.load(Ordering::Acquire) < count && read_timer() < deadline {
+        spin_loop();
+    }
+}
+
+/// 从 `addr` 开始长度不超过 `len` 且已经映射的内存范围
+fn mapped_range(addr: usize, len: usize) -> Range<usize> {
+    let end = addr.saturating_add(len);
+    let mut mapped = addr;
+    while mapped < end && mem::query_kernel_space(mapped).is_some() {
+        mapped = (mapped & !(FRAME_SIZE - 1)) + FRAME_SIZE;
+    }
+    addr..min(mapped, end)
+}
+
+/// 从 init 进程开始遍历任务树写入任务列表
+///
+/// panic 时任务的锁可能正被持有，等待它会导致转储无法完成，因此只尝试获取锁。
+/// 锁被占用的任务只输出 tid 和 pid，状态记为 busy，它的子任务也无法访问。
+fn write_tasks(out: &mut SliceWriter) {
+    let _ = writeln!(out, "{:>6} {:>6} {:<10} NAME", "TID", "PID", "STATE");
+    let mut stack = vec![INIT_PROCESS.clone()];
+    while let Some(task) = stack.pop() {
+        let inner = match task.try_access_inner() {
+            Some(inner) => inner,
+            None => {
+                let _ = writeln!(
+                    out,
+                    "{:>6} {:>6} {:<10} ?",
+                    task.get_tid(),
+                    task.get_pid(),
+                    "busy"
+                );
+                continue;
+            }
+        };
+        let state = match inner.state {
+            TaskState::Ready => "ready",
+            TaskState::Running => "running",
+            TaskState::Waiting => "waiting",
+            TaskState::Zombie => "zombie",
+            TaskState::Terminated => "terminated",
+        };
+        let _ = writeln!(
+            out,
+            "{:>6} {:>6} {:<10} {}",
+            task.get_tid(),
+            task.get_pid(),
+            state,
+            inner.name
+        );
+        stack.extend(inner.children.iter().cloned());
+    }
+}
+
+/// 向转储区域中依次写入记录
+struct DumpWriter {
+    region: Range<usize>,
+    used: usize,
+}
+
+impl DumpWriter {
+    fn new(region: Range<usize>) -> Self {
+        let header = unsafe { &mut *(region.start as *mut DumpHeader) };
+        // 写完所有字段之后再写入魔数
+        header.magic = [0; 8];
+        header.version = DUMP_VERSION;
+        header.cpu_num = CPU_NUM as u32;
+        header.size = HEADER_SIZE as u64;
+        header.time = get_time_ms() as u64;
+        header.magic = DUMP_MAGIC;
+        Self {
+            region,
+            used: HEADER_SIZE,
+        }
+    }
+
+    /// 写入一条记录，`fill` 向剩余空间中写入记录的内容并返回写入的长度
+    fn record(&mut self, kind: u32, fill: impl FnOnce(&mut [u8]) -> usize) {
+        let start = self.region.start + self.used;
+        let space = self.region.len() - self.used;
+        if space < 8 {
+            return;
+        }
+        let buf = unsafe { core::slice::from_raw_parts_mut((start + 8) as *mut u8, space - 8) };
+        let len = min(fill(buf), space - 8);
+        let head = unsafe { core::slice::from_raw_parts_mut(start as *mut u8, 8) };
+        head[..4].copy_from_slice(&kind.to_le_bytes());
+        head[4..].copy_from_slice(&(len as u32).to_le_bytes());
+        self.used = min(self.used + 8 + ((len + 7) & !7), self.region.len());
+        let header = unsafe { &mut *(self.region.start as *mut DumpHeader) };
+        header.size = self.used as u64;
+    }
+}
+
+/// 向固定大小的缓冲区格式化输出，超出的部分被丢弃
+struct SliceWriter<'a> {
+    buf: &'a mut [u8],
+    len: usize,
+}
+
+impl<'a> SliceWriter<'a> {
+    fn new(buf: &'a mut [u8]) -> Self {
+        Self { buf, len: 0 }
+    }
+}
+
+impl Write for SliceWriter<'_> {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        let len = min(s.len(), self.buf.len() - self.len);
+        self.buf[self.len..self.len + len].copy_from_slice(&s.as_bytes()[..len]);
+        self.len += len;
+        Ok(())
+    }
+}
+
+/// `/proc/crashdump`，上一次启动时保存的崩溃转储
+struct CrashDumpFile;
+
+impl VfsFile for CrashDumpFile {
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> VfsResult<usize> {
+        let dump = PREVIOUS_DUMP.get().unwrap();
+        if offset as usize >= dump.len() {
+            return Ok(0);
+        }
+        let len = min(buf.len(), dump.len() - offset as usize);
+        buf[..len].copy_from_slice(&dump[offset as usize..offset as usize + len]);
+        Ok(len)
+    }
+}
+
+impl VfsInode for CrashDumpFile {
+    fn get_super_block(&self) -> VfsResult<Arc<dyn VfsSuperBlock>> {
+        Err(VfsError::NoSys)
+    }
+    fn node_perm(&self) -> VfsNodePerm {
+        VfsNodePerm::empty()
+    }
+    fn set_attr(&self, _attr: InodeAttr) -> VfsResult<()> {
+        Ok(())
+    }
+
+    fn get_attr(&self) -> VfsResult<VfsFileStat> {
+        Ok(VfsFileStat {
+            st_size: PREVIOUS_DUMP.get().unwrap().len() as u64,
+            ..Default::default()
+        })
+    }
+
+    fn inode_type(&self) -> VfsNodeType {
+        VfsNodeType::File
+    }
+}
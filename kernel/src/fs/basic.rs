@@ -1,23 +1,45 @@
 use super::im2vim;
-use crate::fs::{syscontext_for_vfs, user_path_at};
+use crate::fs::{
+    check_truncate_limit, check_writable, fsize_limit, syscontext_for_vfs, user_abs_path,
+    user_path_at,
+};
 use crate::task::current_task;
+use alloc::string::{String, ToString};
 use alloc::sync::Arc;
 use alloc::vec;
 use constants::io::{
-    FileStat, FsStat, InodeMode, IoVec, MountFlags, OpenFlags, Renameat2Flags, SeekFrom, StatFlags,
+    FileStat, InodeMode, IoVec, MountFlags, OpenFlags, Renameat2Flags, SeekFrom, StatFlags,
 };
 use constants::LinuxErrno;
 use constants::{AlienResult, AT_FDCWD};
-use core::cmp::min;
 use gmanager::ManagerError;
 use log::{info, warn};
 use syscall_table::syscall_func;
+use vfs::dev::loop_device;
 use vfs::kfile::KernelFile;
-use vfs::system_root_fs;
-use vfscore::path::VfsPath;
+use vfs::mount::MountEntry;
+use vfscore::fstype::FileSystemFlags;
+use vfscore::inode::VfsInode;
 use vfscore::utils::{VfsFileStat, VfsFsStat, VfsNodeType, VfsRenameFlag};
 
+/// mount 标志的高 16 位可能是历史遗留的魔数 `MS_MGC_VAL`，需要忽略
+const MS_MGC_MSK: usize = 0xffff0000;
+const MS_MGC_VAL: usize = 0xc0ed0000;
+
 /// 用于将一个设备(通常是存储设备)挂载到一个已经存在的目录上，可以挂载文件系统。
+///
+/// `fs_type` 可以是任意已注册的文件系统(见 `/proc/filesystems`)，需要设备的文件系统会以 `source` 指向的块设备作为参数。
+/// 当 `fs_type` 为空或为 "auto" 时，将根据 `source` 上的超级块自动识别文件系统。
+/// `data` 为挂载选项字符串，会被原样传递给文件系统。
+///
+/// 目前支持的 `flags` 包括：
+/// + MS_RDONLY / MS_NOEXEC: 记录在挂载表中，分别限制写入以及禁止执行；
+/// + MS_NOSUID: 记录在挂载表中，执行该挂载点下的程序时忽略 set-user-ID 和 set-group-ID 位；
+/// + MS_REMOUNT: 修改已存在挂载点的标志和选项；
+/// + MS_BIND: 将 `source` 目录绑定挂载到 `dir` 上；
+/// + MS_MOVE: 将挂载在 `source` 上的文件系统移动到 `dir` 上。
+///
+/// Reference: [mount](https://man7.org/linux/man-pages/man2/mount.2.html)
 #[syscall_func(40)]
 pub fn sys_mount(
     source: *const u8,
@@ -27,51 +49,132 @@ pub fn sys_mount(
     data: *const u8,
 ) -> AlienResult<isize> {
     let task = current_task().unwrap();
-    let source = task.transfer_str(source);
-    let dir = task.transfer_str(dir);
-    let fs_type = task.transfer_str(fs_type);
-    assert!(data.is_null());
-    let flags = MountFlags::from_bits(flags as u32).unwrap();
+    let source = if source.is_null() {
+        String::new()
+    } else {
+        task.transfer_str(source)?
+    };
+    let dir = task.transfer_str(dir)?;
+    let fs_type = if fs_type.is_null() {
+        String::new()
+    } else {
+        task.transfer_str(fs_type)?
+    };
+    let data = if data.is_null() {
+        String::new()
+    } else {
+        task.transfer_str(data)?
+    };
+    let flags = if flags & MS_MGC_MSK == MS_MGC_VAL {
+        flags & !MS_MGC_MSK
+    } else {
+        flags
+    };
+    let flags = MountFlags::from_bits(flags as u32).ok_or(LinuxErrno::EINVAL)?;
     info!(
         "mount special:{:?},dir:{:?},fs_type:{:?},flags:{:?},data:{:?}",
         source, dir, fs_type, flags, data
     );
-    let find = vfs::system_support_fs(&fs_type).ok_or(LinuxErrno::EINVAL)?;
-    let path = VfsPath::new(vfs::system_root_fs(), system_root_fs());
-    let fs_root = match find.fs_name() {
-        name @ ("tmpfs" | "ramfs" | "fat32") => {
-            let fs = vfs::system_support_fs(name).unwrap();
-            let dev = if name.eq("fat32") {
-                let dev = path.join(source)?.open(None)?;
-                Some(dev.inode()?)
-            } else {
-                None
-            };
-            let new_fs = fs.i_mount(0, &dir, dev, &[])?;
-            new_fs
-        }
-        _ => return Err(LinuxErrno::EINVAL),
+    let target = user_abs_path(AT_FDCWD, &dir)?;
+    if flags.contains(MountFlags::MS_REMOUNT) {
+        let flags = flags - MountFlags::MS_REMOUNT - MountFlags::MS_BIND;
+        return if vfs::mount::update_mount(&target, flags, &data) {
+            Ok(0)
+        } else {
+            Err(LinuxErrno::EINVAL)
+        };
+    }
+    let dir_path = user_path_at(AT_FDCWD, &dir)?;
+    if flags.contains(MountFlags::MS_BIND) {
+        let source_abs = user_abs_path(AT_FDCWD, &source)?;
+        let source_dt = user_path_at(AT_FDCWD, &source)?.open(None)?;
+        let source_mount = vfs::mount::mount_of(&source_abs);
+        let fs_type = source_mount
+            .as_ref()
+            .map(|entry| entry.fs_type.clone())
+            .unwrap_or("none".to_string());
+        // 绑定挂载与源挂载点共享同一个块设备
+        let rdev = source_mount.and_then(|entry| entry.rdev);
+        let flags = flags - MountFlags::MS_BIND - MountFlags::MS_REC;
+        dir_path.mount(source_dt.clone(), flags.bits())?;
+        vfs::mount::record_mount(MountEntry {
+            source: source_abs,
+            target,
+            fs_type,
+            flags,
+            options: data,
+            root: source_dt,
+            rdev,
+        });
+        return Ok(0);
+    }
+    if flags.contains(MountFlags::MS_MOVE) {
+        let source_abs = user_abs_path(AT_FDCWD, &source)?;
+        let entry = vfs::mount::find_mount(&source_abs).ok_or(LinuxErrno::EINVAL)?;
+        user_path_at(AT_FDCWD, &source)?.umount()?;
+        dir_path.mount(entry.root.clone(), entry.flags.bits())?;
+        vfs::mount::move_mount(&source_abs, &target);
+        return Ok(0);
+    }
+    let (fs_type, fs, dev) = if fs_type.is_empty() || fs_type == "auto" {
+        // 未指定文件系统时，根据块设备的超级块自动识别
+        let dev = open_block_device(&source)?;
+        let fs_type = vfs::probe::probe_fs_type(&dev).ok_or(LinuxErrno::EINVAL)?;
+        let fs = vfs::system_support_fs(fs_type).ok_or(LinuxErrno::ENODEV)?;
+        (fs_type.to_string(), fs, Some(dev))
+    } else {
+        let fs = vfs::system_support_fs(&fs_type).ok_or(LinuxErrno::ENODEV)?;
+        let dev = if fs.fs_flag().contains(FileSystemFlags::REQUIRES_DEV) {
+            Some(open_block_device(&source)?)
+        } else {
+            None
+        };
+        (fs_type, fs, dev)
+    };
+    let rdev = match &dev {
+        Some(dev) => Some(dev.get_attr()?.st_rdev),
+        None => None,
     };
-    path.join(dir)?.mount(fs_root, flags.bits())?;
+    let fs_root = fs.i_mount(flags.bits(), &target, dev, data.as_bytes())?;
+    dir_path.mount(fs_root.clone(), flags.bits())?;
+    vfs::mount::record_mount(MountEntry {
+        source,
+        target,
+        fs_type,
+        flags,
+        options: data,
+        root: fs_root,
+        rdev,
+    });
     Ok(0)
 }
 
+fn open_block_device(source: &str) -> AlienResult<Arc<dyn VfsInode>> {
+    let dev = user_path_at(AT_FDCWD, source)?.open(None)?.inode()?;
+    if dev.inode_type() != VfsNodeType::BlockDevice {
+        return Err(LinuxErrno::ENOTBLK);
+    }
+    Ok(dev)
+}
+
 /// 用于取消一个目录上的文件挂载(卸载一个文件系统)。
 #[syscall_func(39)]
 pub fn sys_umount(dir: *const u8) -> AlienResult<isize> {
     let process = current_task().unwrap();
-    let dir = process.transfer_str(dir);
+    let dir = process.transfer_str(dir)?;
     info!("umount dir:{:?}", dir);
-    let path = VfsPath::new(vfs::system_root_fs(), system_root_fs());
-    path.join(dir)?.umount()?;
+    let target = user_abs_path(AT_FDCWD, &dir)?;
+    user_path_at(AT_FDCWD, &dir)?.umount()?;
+    let entry = vfs::mount::remove_mount(&target);
+    // 设置了 LO_FLAGS_AUTOCLEAR 的回环设备在最后一次卸载后自动解除绑定
+    if let Some(device) = entry.and_then(|entry| entry.rdev).and_then(loop_device) {
+        device.autoclear();
+    }
     Ok(0)
 }
 
 #[syscall_func(56)]
 pub fn sys_openat(dirfd: isize, path: *const u8, flag: usize, mode: u32) -> AlienResult<isize> {
-    if path.is_null() {
-        return Err(LinuxErrno::EFAULT);
-    }
     let flag = OpenFlags::from_bits_truncate(flag);
     let file_mode = if flag.contains(OpenFlags::O_CREAT) {
         Some(InodeMode::from_bits_truncate(mode))
@@ -80,7 +183,12 @@ pub fn sys_openat(dirfd: isize, path: *const u8, flag: usize, mode: u32) -> Alie
     }
     .map(|x| im2vim(x));
     let process = current_task().unwrap();
-    let path_str = process.transfer_str(path);
+    let path_str = process.transfer_str(path)?;
+    if flag.intersects(
+        OpenFlags::O_WRONLY | OpenFlags::O_RDWR | OpenFlags::O_CREAT | OpenFlags::O_TRUNC,
+    ) {
+        check_writable(dirfd, &path_str)?;
+    }
     let path = user_path_at(dirfd, &path_str)?;
     warn!(
         "open file: dirfd:[{}], {:?},flag:{:?}, mode:{:?}",
@@ -134,20 +242,11 @@ pub fn sys_getdents(fd: usize, buf: *mut u8, len: usize) -> AlienResult<isize> {
     info!("[getdents] fd: {}, buf size: {}", fd, len);
     let process = current_task().unwrap();
     let file = process.get_file(fd).ok_or(LinuxErrno::EBADF)?;
-    let user_bufs = process.transfer_buffer(buf, len);
-    let mut buf = vec![0u8; len];
-    let len = file.readdir(buf.as_mut_slice())?;
+    let mut dirent_buf = vec![0u8; len];
+    let len = file.readdir(dirent_buf.as_mut_slice())?;
     info!("[getdents]: read len: {:?}", len);
-    let mut offset = 0;
     // copy dirent_buf to user space
-    for user_buf in user_bufs {
-        let copy_len = user_buf.len(); // user_bufs len is equal to buf len
-        user_buf.copy_from_slice(&buf[offset..offset + copy_len]);
-        offset += copy_len;
-        if offset >= len {
-            break;
-        }
-    }
+    process.copy_to_user_buffer(&dirent_buf[..len], buf)?;
     Ok(len as _)
 }
 
@@ -163,7 +262,9 @@ pub fn sys_getdents(fd: usize, buf: *mut u8, len: usize) -> AlienResult<isize> {
 #[syscall_func(45)]
 pub fn sys_truncate(path: usize, len: usize) -> AlienResult<isize> {
     let process = current_task().unwrap();
-    let path = process.transfer_str(path as *const u8);
+    let path = process.transfer_str(path as *const u8)?;
+    check_writable(AT_FDCWD, &path)?;
+    check_truncate_limit(len as u64)?;
     let path = user_path_at(AT_FDCWD, &path)?;
     path.truncate(len as u64)?;
     Ok(0)
@@ -182,6 +283,7 @@ pub fn sys_truncate(path: usize, len: usize) -> AlienResult<isize> {
 pub fn sys_ftruncate(fd: usize, len: usize) -> AlienResult<isize> {
     let process = current_task().unwrap();
     let file = process.get_file(fd).ok_or(LinuxErrno::EBADF)?;
+    check_truncate_limit(len as u64)?;
     file.truncate(len as u64)?;
     Ok(0)
 }
@@ -197,7 +299,7 @@ pub fn sys_read(fd: usize, buf: *mut u8, len: usize) -> AlienResult<isize> {
     let process = current_task().unwrap();
     let file = process.get_file(fd).ok_or(LinuxErrno::EBADF)?;
     info!("read file: {:?}, len:{:?}", fd, len);
-    let mut buf = process.transfer_buffer(buf, len);
+    let mut buf = process.transfer_buffer_mut(buf, len)?;
 
     let mut count = 0;
     for b in buf.iter_mut() {
@@ -221,9 +323,10 @@ pub fn sys_read(fd: usize, buf: *mut u8, len: usize) -> AlienResult<isize> {
 pub fn sys_write(fd: usize, buf: *const u8, len: usize) -> AlienResult<isize> {
     let process = current_task().unwrap();
     let file = process.get_file(fd).ok_or(LinuxErrno::EBADF)?;
-    let mut buf = process.transfer_buffer(buf, len);
+    let len = fsize_limit(&file, None, len)?;
+    let buf = process.transfer_buffer(buf, len)?;
     let mut count = 0;
-    for b in buf.iter_mut() {
+    for b in buf.iter() {
         let w = file.write(b)?;
         count += w;
         if w != b.len() {
@@ -237,28 +340,19 @@ pub fn sys_write(fd: usize, buf: *const u8, len: usize) -> AlienResult<isize> {
 /// 一个系统调用，用于获取当前工作目录。
 /// 获取的工作目录将直接保存在 `buf` 所指向的缓冲区中，`len` 用于指明 `buf` 的长度。
 ///
-/// 获取当前目录成功后，返回 `buf` 的首地址。当 `buf` 为空指针时，会导致函数 panic。
+/// 获取当前目录成功后，返回 `buf` 的首地址。当 `buf` 不可写时返回 EFAULT，`len` 不足以保存路径时返回 ERANGE。
 #[syscall_func(17)]
-pub fn sys_getcwd(buf: *mut u8, len: usize) -> isize {
+pub fn sys_getcwd(buf: *mut u8, len: usize) -> AlienResult<isize> {
     info!("getcwd: {:?}, len: {:?}", buf, len);
-    assert!(!buf.is_null());
     let task = current_task().unwrap();
     let cwd = task.access_inner().cwd();
-
-    let mut buf = task.transfer_buffer(buf, len);
-    let mut count = 0;
-    let path = cwd.cwd.path();
-    let mut cwd = path.as_bytes();
-    buf.iter_mut().for_each(|buf| {
-        // fill buf
-        if !cwd.is_empty() {
-            let min = min(cwd.len(), buf.len());
-            buf[..min].copy_from_slice(&cwd[..min]);
-            count += min;
-            cwd = &cwd[min..];
-        }
-    });
-    buf[0].as_ptr() as isize
+    let mut path = cwd.cwd.path().into_bytes();
+    path.push(0);
+    if path.len() > len {
+        return Err(LinuxErrno::ERANGE);
+    }
+    task.copy_to_user_buffer(&path, buf)?;
+    Ok(buf as isize)
 }
 
 /// 一个系统调用，用于切换当前工作目录。`path` 指出要切换到的工作目录。
@@ -267,7 +361,7 @@ pub fn sys_getcwd(buf: *mut u8, len: usize) -> isize {
 #[syscall_func(49)]
 pub fn sys_chdir(path: *const u8) -> AlienResult<isize> {
     let process = current_task().unwrap();
-    let path = process.transfer_str(path);
+    let path = process.transfer_str(path)?;
     let dt = user_path_at(AT_FDCWD, &path)?.open(None)?;
 
     if dt.inode()?.inode_type() != VfsNodeType::Dir {
@@ -306,9 +400,10 @@ pub fn sys_fchdir(fd: usize) -> AlienResult<isize> {
 #[syscall_func(34)]
 pub fn sys_mkdirat(dirfd: isize, path: *const u8, mode: u32) -> AlienResult<isize> {
     let process = current_task().unwrap();
-    let path = process.transfer_str(path);
+    let path = process.transfer_str(path)?;
     let mut mode = InodeMode::from_bits_truncate(mode);
     warn!("mkdirat path: {}, mode: {:?}", path, mode);
+    check_writable(dirfd, &path)?;
     let path = user_path_at(dirfd, &path)?;
     mode |= InodeMode::DIR;
     assert_eq!(mode & InodeMode::TYPE_MASK, InodeMode::DIR);
@@ -350,20 +445,27 @@ pub fn sys_writev(fd: usize, iovec: usize, iovcnt: usize) -> AlienResult<isize>
     let file = process.get_file(fd).ok_or(LinuxErrno::EBADF)?;
     let mut count = 0;
     for i in 0..iovcnt {
-        let mut iov = IoVec::empty();
-        let ptr = unsafe { (iovec as *mut IoVec).add(i) };
-        process.access_inner().copy_from_user(ptr, &mut iov);
+        let ptr = unsafe { (iovec as *const IoVec).add(i) };
+        let iov = process.copy_from_user(ptr)?;
         let base = iov.base;
         if base as usize == 0 {
             // busybox 可能会给stdout两个io_vec，第二个是空地址
             continue;
         }
-        let len = iov.len;
-        let buf = process.transfer_buffer(base, len);
+        let len = match fsize_limit(&file, None, iov.len) {
+            Ok(len) => len,
+            // 已经写入了部分数据时返回写入的长度
+            Err(_) if count > 0 => break,
+            Err(e) => return Err(e),
+        };
+        let buf = process.transfer_buffer(base, len)?;
         for b in buf.iter() {
             let r = file.write(b)?;
             count += r;
         }
+        if len < iov.len {
+            break;
+        }
     }
     Ok(count as isize)
 }
@@ -381,14 +483,14 @@ pub fn sys_readv(fd: usize, iovec: usize, iovcnt: usize) -> AlienResult<isize> {
     let file = task.get_file(fd).ok_or(LinuxErrno::EBADF)?;
     let mut count = 0;
     for i in 0..iovcnt {
-        let ptr = unsafe { (iovec as *mut IoVec).add(i) };
-        let iov = task.transfer_raw_ptr(ptr);
+        let ptr = unsafe { (iovec as *const IoVec).add(i) };
+        let iov = task.copy_from_user(ptr)?;
         let base = iov.base;
         if base as usize == 0 || iov.len == 0 {
             continue;
         }
         let len = iov.len;
-        let mut buf = task.transfer_buffer(base, len);
+        let mut buf = task.transfer_buffer_mut(base, len)?;
         for b in buf.iter_mut() {
             info!("read file: {:?}, len:{:?}", fd, b.len());
             let r = file.read(b)?;
@@ -408,7 +510,7 @@ pub fn sys_readv(fd: usize, iovec: usize, iovcnt: usize) -> AlienResult<isize> {
 pub fn sys_pread(fd: usize, buf: usize, count: usize, offset: u64) -> AlienResult<isize> {
     let task = current_task().unwrap();
     let file = task.get_file(fd).ok_or(LinuxErrno::EBADF)?;
-    let mut buf = task.transfer_buffer(buf as *mut u8, count);
+    let mut buf = task.transfer_buffer_mut(buf as *mut u8, count)?;
     let mut offset = offset;
     let mut count = 0;
     for b in buf.iter_mut() {
@@ -429,7 +531,8 @@ pub fn sys_pread(fd: usize, buf: usize, count: usize, offset: u64) -> AlienResul
 pub fn sys_pwrite(fd: usize, buf: usize, count: usize, offset: u64) -> AlienResult<isize> {
     let task = current_task().unwrap();
     let file = task.get_file(fd).ok_or(LinuxErrno::EBADF)?;
-    let buf = task.transfer_buffer(buf as *mut u8, count);
+    let count = fsize_limit(&file, Some(offset), count)?;
+    let buf = task.transfer_buffer(buf as *const u8, count)?;
     let mut offset = offset;
     let mut count = 0;
     for b in buf.iter() {
@@ -447,7 +550,7 @@ pub fn sys_pwrite(fd: usize, buf: usize, count: usize, offset: u64) -> AlienResu
 /// 获取的信息会保存在 `stat` 指向的 [`FileStat`] 结构中，`flag` 是一组标志位，用于定义相关的操作类型，具体可见 [`StatFlags`]。
 ///
 /// 获取相关信息成功后，函数返回 0；否则函数会返回 -1 表示获取信息出错。
-/// 如果输入的 `stat` 为空指针，那么会导致函数 panic。
+/// 如果输入的 `stat` 不可写，函数返回 EFAULT。
 ///
 /// Reference: https://man7.org/linux/man-pages/man2/newfstatat.2.html
 #[syscall_func(79)]
@@ -458,7 +561,7 @@ pub fn sys_fstateat(
     flag: usize,
 ) -> AlienResult<isize> {
     let process = current_task().unwrap();
-    let path = process.transfer_str(path);
+    let path = process.transfer_str(path)?;
     let flag = StatFlags::from_bits_truncate(flag as u32);
     warn!("sys_fstateat: path: {:?}, flag: {:?}", path, flag);
     let path = user_path_at(dir_fd, &path)?;
@@ -471,9 +574,7 @@ pub fn sys_fstateat(
         (&mut file_stat as *mut FileStat as *mut usize as *mut VfsFileStat).write(attr);
     }
     warn!("sys_fstateat: res: {:?}", file_stat);
-    process
-        .access_inner()
-        .copy_to_user(&file_stat, stat as *mut FileStat);
+    process.copy_to_user(&file_stat, stat as *mut FileStat)?;
     Ok(0)
 }
 
@@ -481,10 +582,9 @@ pub fn sys_fstateat(
 /// `fd` 用于指明要获取信息的文件的文件描述符。
 ///
 /// 获取相关信息成功后，函数返回 0；否则函数会返回 -1 表示获取信息出错。
-/// 如果输入的 `stat` 为空指针，那么会导致函数 panic。
+/// 如果输入的 `stat` 不可写，函数返回 EFAULT。
 #[syscall_func(80)]
 pub fn sys_fstat(fd: usize, stat: *mut u8) -> AlienResult<isize> {
-    assert!(!stat.is_null());
     let process = current_task().unwrap();
     let file = process.get_file(fd).ok_or(LinuxErrno::EBADF)?;
     let attr = file.get_attr()?;
@@ -493,9 +593,7 @@ pub fn sys_fstat(fd: usize, stat: *mut u8) -> AlienResult<isize> {
         (&mut file_stat as *mut FileStat as *mut usize as *mut VfsFileStat).write(attr);
     }
     warn!("sys_fstat: {:?}, res: {:?}", fd, file_stat);
-    process
-        .access_inner()
-        .copy_to_user(&file_stat, stat as *mut FileStat);
+    process.copy_to_user(&file_stat, stat as *mut FileStat)?;
     Ok(0)
 }
 
@@ -507,12 +605,10 @@ pub fn sys_fstat(fd: usize, stat: *mut u8) -> AlienResult<isize> {
 #[syscall_func(44)]
 pub fn sys_fstatfs(fd: isize, buf: *mut u8) -> AlienResult<isize> {
     let process = current_task().unwrap();
-    let buf = process.transfer_raw_ptr(buf as *mut FsStat);
     let file = process.get_file(fd as usize).ok_or(LinuxErrno::EBADF)?;
     let fs_stat = file.inode().get_super_block()?.stat_fs()?;
-    unsafe {
-        (&mut *buf as *mut FsStat as *mut usize as *mut VfsFsStat).write(fs_stat);
-    }
+    // FsStat 与 VfsFsStat 的内存布局相同
+    process.copy_to_user(&fs_stat, buf as *mut VfsFsStat)?;
     warn!("sys_fstatfs: res: {:#x?}", fs_stat);
     Ok(0)
 }
@@ -526,16 +622,14 @@ pub fn sys_fstatfs(fd: isize, buf: *mut u8) -> AlienResult<isize> {
 #[syscall_func(43)]
 pub fn sys_statfs(path: *const u8, statfs: *const u8) -> AlienResult<isize> {
     let process = current_task().unwrap();
-    let buf = process.transfer_raw_ptr(statfs as *mut FsStat);
-    let path = process.transfer_str(path);
+    let path = process.transfer_str(path)?;
 
     let path = user_path_at(AT_FDCWD, &path)?;
     let dt = path.open(None)?;
     let fs_stat = dt.inode()?.get_super_block()?.stat_fs()?;
 
-    unsafe {
-        (&mut *buf as *mut FsStat as *mut usize as *mut VfsFsStat).write(fs_stat);
-    }
+    // FsStat 与 VfsFsStat 的内存布局相同
+    process.copy_to_user(&fs_stat, statfs as *mut VfsFsStat)?;
 
     warn!("sys_statfs: [{:?}] res: {:#x?}", path, fs_stat);
     Ok(0)
@@ -550,13 +644,15 @@ pub fn sys_renameat(
     new_path: *const u8,
 ) -> AlienResult<isize> {
     let process = current_task().unwrap();
-    let old_path = process.transfer_str(old_path);
-    let new_path = process.transfer_str(new_path);
+    let old_path = process.transfer_str(old_path)?;
+    let new_path = process.transfer_str(new_path)?;
 
     info!(
         "renameat2: {:?} {:?} {:?} {:?}",
         old_dirfd, old_path, new_dirfd, new_path
     );
+    check_writable(old_dirfd, &old_path)?;
+    check_writable(new_dirfd, &new_path)?;
     let old_path = user_path_at(old_dirfd, &old_path)?;
     let new_path = user_path_at(new_dirfd, &new_path)?;
     old_path.rename_to(
@@ -581,13 +677,15 @@ pub fn sys_renameat2(
     flag: u32,
 ) -> AlienResult<isize> {
     let process = current_task().unwrap();
-    let old_path = process.transfer_str(old_path);
-    let new_path = process.transfer_str(new_path);
+    let old_path = process.transfer_str(old_path)?;
+    let new_path = process.transfer_str(new_path)?;
     let flag = Renameat2Flags::from_bits_truncate(flag);
     info!(
         "renameat2: {:?} {:?} {:?} {:?}, flag: {:?}",
         old_dirfd, old_path, new_dirfd, new_path, flag
     );
+    check_writable(old_dirfd, &old_path)?;
+    check_writable(new_dirfd, &new_path)?;
     let old_path = user_path_at(old_dirfd, &old_path)?;
     let new_path = user_path_at(new_dirfd, &new_path)?;
 
@@ -632,6 +730,7 @@ pub fn send_file(
         return Err(LinuxErrno::EBADF);
     }
 
+    let count = fsize_limit(&out_file, None, count)?;
     let mut buf = vec![0u8; count];
 
     let nbytes = match offset_ptr {
@@ -639,9 +738,10 @@ pub fn send_file(
         0 => in_file.read(&mut buf)?,
         _ => {
             // offset 非零则要求不更新实际文件，更新这个用户给的值
-            let offset_ptr = task.transfer_raw_ptr(offset_ptr as *mut u64);
-            let nbytes = in_file.read_at(*offset_ptr, &mut buf)?;
-            *offset_ptr += nbytes as u64;
+            let offset_ptr = offset_ptr as *mut u64;
+            let offset = task.copy_from_user(offset_ptr)?;
+            let nbytes = in_file.read_at(offset, &mut buf)?;
+            task.copy_to_user(&(offset + nbytes as u64), offset_ptr)?;
             nbytes
         }
     };
@@ -690,18 +790,22 @@ pub fn copy_file_range(
         in_file.read(&mut buf)?
     } else {
         // offset 非零则要求不更新实际文件，更新这个用户给的值
-        let off_in_ptr = task.transfer_raw_ptr(off_in_ptr as *mut u64);
-        let nr = in_file.read_at(*off_in_ptr, &mut buf)?;
-        *off_in_ptr += nr as u64;
+        let off_in_ptr = off_in_ptr as *mut u64;
+        let off_in = task.copy_from_user(off_in_ptr)?;
+        let nr = in_file.read_at(off_in, &mut buf)?;
+        task.copy_to_user(&(off_in + nr as u64), off_in_ptr)?;
         nr
     };
     info!("sys_copy_file_range: read {} bytes from in_file", r);
     let w = if off_out_ptr == 0 {
+        let r = fsize_limit(&out_file, None, r)?;
         out_file.write(&buf[..r])?
     } else {
-        let off_out_ptr = task.transfer_raw_ptr(off_out_ptr as *mut u64);
-        let wr = out_file.write_at(*off_out_ptr, &buf[..r])?;
-        *off_out_ptr += wr as u64;
+        let off_out_ptr = off_out_ptr as *mut u64;
+        let off_out = task.copy_from_user(off_out_ptr)?;
+        let r = fsize_limit(&out_file, Some(off_out), r)?;
+        let wr = out_file.write_at(off_out, &buf[..r])?;
+        task.copy_to_user(&(off_out + wr as u64), off_out_ptr)?;
         wr
     };
     info!("sys_copy_file_range: write {} bytes to out_file", w);
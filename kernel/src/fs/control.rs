@@ -1,11 +1,17 @@
-use crate::fs::user_path_at;
+use crate::fs::{check_writable, user_path_at};
 use crate::task::current_task;
 use constants::io::{FaccessatFlags, FaccessatMode, Fcntl64Cmd, OpenFlags, TeletypeCommand};
 use constants::LinuxErrno;
 use constants::{AlienResult, AT_FDCWD};
+use knet::socket::{Socket, SocketFile, SocketFileExt};
 use log::{info, warn};
 use syscall_table::syscall_func;
 use timer::TimeSpec;
+use vfs::dev::{
+    loop_device_of, LoopConfig, LoopDevice, LoopInfo64, LOOP_CLR_FD, LOOP_CONFIGURE,
+    LOOP_GET_STATUS64, LOOP_SET_FD, LOOP_SET_STATUS64,
+};
+use vfs::kfile::{File, KernelFile};
 use vfscore::utils::*;
 
 const FD_CLOEXEC: usize = 1;
@@ -20,7 +26,7 @@ const FD_CLOEXEC: usize = 1;
 /// + F_GETFD: 返回 fd 所指向的文件的 flags 中的 `O_CLOSEEXEC`位。
 /// + F_SETFD: 设置 fd 所指向的文件的 flags 的 `O_CLOSEEXEC`位，由参数arg的 `FD_CLOEXEC` 位决定。 设置成功返回 0。
 /// + F_GETFL: 返回 fd 所指向的文件的 flags。
-/// + F_SETFL: 根据 arg 设置 fd 的 flags，可以采用的 arg 可见 [`OpenFlags`]。
+/// + F_SETFL: 根据 arg 设置 fd 的 flags，可以采用的 arg 可见 [`OpenFlags`]。对于套接字，`O_NONBLOCK` 位同时决定其是否阻塞。
 /// + 其它操作类型均会使得函数返回 EINVAL。
 ///
 /// Reference: [fcntl](https:///man7.org/linux/man-pages/man2/fcntl.2.html)
@@ -64,6 +70,13 @@ pub fn fcntl(fd: usize, cmd: usize, arg: usize) -> AlienResult<isize> {
             let flag = OpenFlags::from_bits_truncate(arg);
             info!("fcntl: F_SETFL :{:?}", flag,);
             file.set_open_flag(flag);
+            // 套接字的阻塞状态由协议栈中的套接字自己记录，需要同步修改
+            if let Ok(socket_file) = file.clone().downcast_arc::<SocketFile>() {
+                let socket = socket_file.get_socketdata()?;
+                if matches!(socket.socket, Socket::Tcp(_) | Socket::Udp(_)) {
+                    socket.set_socket_nonblock(flag.contains(OpenFlags::O_NONBLOCK));
+                }
+            }
         }
         Fcntl64Cmd::GETLK | Fcntl64Cmd::SETLK | Fcntl64Cmd::SETLKW => {
             info!("fcntl: GETLK SETLK SETLKW now ignored");
@@ -79,7 +92,8 @@ pub fn fcntl(fd: usize, cmd: usize, arg: usize) -> AlienResult<isize> {
 /// 在一些需要细分的情境下，如果需要扩展新的功能，通常以增设 ioctl() 命令的方式实现。
 ///
 /// `fd` 指明要操作的设备的文件描述符；`cmd` 指明控制操作的类型，
-/// 目前 Alien 支持的 ioctl 操作可见 [`TeletypeCommand`] 和 `rvfs` 中有关 `ioctl` 的支持；
+/// 目前 Alien 支持的 ioctl 操作可见 [`TeletypeCommand`] 和 `rvfs` 中有关 `ioctl` 的支持，
+/// 回环设备(/dev/loopN)额外支持的操作见 [`loop_ioctl`]；
 /// `arg` 指明操作的参数。
 ///
 /// 根据不同的 ioctl 命令，将有不同的返回值。
@@ -89,12 +103,70 @@ pub fn fcntl(fd: usize, cmd: usize, arg: usize) -> AlienResult<isize> {
 pub fn ioctl(fd: usize, cmd: usize, arg: usize) -> AlienResult<isize> {
     let process = current_task().unwrap();
     let file = process.get_file(fd).ok_or(LinuxErrno::EBADF)?;
+    // 套接字、pidfd 等文件没有 inode，只有 KernelFile 才可能是回环设备
+    let loop_dev = file
+        .clone()
+        .downcast_arc::<KernelFile>()
+        .ok()
+        .and_then(|file| loop_device_of(&file.inode()));
+    if let Some(device) = loop_dev {
+        info!(
+            "ioctl: {:?} {:#x} {:#x} on loop{}",
+            fd,
+            cmd,
+            arg,
+            device.number()
+        );
+        return loop_ioctl(&device, cmd as u32, arg);
+    }
+    if is_loop_cmd(cmd as u32) {
+        return Err(LinuxErrno::ENOTTY);
+    }
     let cmd = TeletypeCommand::try_from(cmd as u32).map_err(|_| LinuxErrno::EINVAL)?;
     info!("ioctl: {:?} {:?} {:?}", fd, cmd, arg);
     let res = file.ioctl(cmd as u32, arg)?;
     Ok(res as isize)
 }
 
+fn is_loop_cmd(cmd: u32) -> bool {
+    matches!(
+        cmd,
+        LOOP_SET_FD | LOOP_CONFIGURE | LOOP_CLR_FD | LOOP_SET_STATUS64 | LOOP_GET_STATUS64
+    )
+}
+
+/// 回环设备的 ioctl 操作：
+/// + LOOP_SET_FD: 将设备绑定到文件描述符 `arg` 所指向的文件上；
+/// + LOOP_CONFIGURE: 按照 `arg` 指向的 `LoopConfig` 绑定文件，同时设置偏移与大小限制；
+/// + LOOP_CLR_FD: 解除设备与文件的绑定，设备被挂载时返回 EBUSY；
+/// + LOOP_SET_STATUS64: 按照 `arg` 指向的 `LoopInfo64` 修改偏移、大小限制与标志；
+/// + LOOP_GET_STATUS64: 将设备的状态写入 `arg` 指向的 `LoopInfo64` 中。
+fn loop_ioctl(device: &LoopDevice, cmd: u32, arg: usize) -> AlienResult<isize> {
+    let task = current_task().unwrap();
+    match cmd {
+        LOOP_SET_FD => {
+            let file = task.get_file(arg).ok_or(LinuxErrno::EBADF)?;
+            device.set_fd(file)?;
+        }
+        LOOP_CONFIGURE => {
+            let config = task.copy_from_user(arg as *const LoopConfig)?;
+            let file = task.get_file(config.fd as usize).ok_or(LinuxErrno::EBADF)?;
+            device.configure(file, &config.info)?;
+        }
+        LOOP_CLR_FD => device.clear_fd()?,
+        LOOP_SET_STATUS64 => {
+            let info = task.copy_from_user(arg as *const LoopInfo64)?;
+            device.set_status(&info)?;
+        }
+        LOOP_GET_STATUS64 => {
+            let info = device.status()?;
+            task.copy_to_user(&info, arg as *mut LoopInfo64)?;
+        }
+        _ => return Err(LinuxErrno::EINVAL),
+    }
+    Ok(0)
+}
+
 const UTIME_NOW: usize = 0x3fffffff;
 /// ignore
 #[allow(dead_code)]
@@ -126,13 +198,12 @@ pub fn utimensat(
         let file = task.get_file(fd).ok_or(LinuxErrno::EBADF)?;
         file.dentry()
     } else {
-        let path = task.transfer_str(path);
+        let path = task.transfer_str(path)?;
         let path = user_path_at(fd as isize, &path)?;
         let dt = path.open(None)?;
         dt
     };
 
-    let mut inner = task.access_inner();
     if times.is_null() {
         warn!(
             "utimensat: {:?} {:?} {:?} {:?}",
@@ -150,12 +221,8 @@ pub fn utimensat(
             TimeSpec::now().into(),
         )?;
     } else {
-        let mut atime = TimeSpec::new(0, 0);
-        let mut mtime = TimeSpec::new(0, 0);
-        inner.copy_from_user(times as *const TimeSpec, &mut atime);
-        unsafe {
-            inner.copy_from_user((times as *const TimeSpec).add(1), &mut mtime);
-        }
+        let atime = task.copy_from_user(times as *const TimeSpec)?;
+        let mtime = task.copy_from_user(unsafe { (times as *const TimeSpec).add(1) })?;
         warn!(
             "utimensat: {:?} {:?} {:?} {:?}",
             fd as isize, path, atime, mtime
@@ -196,7 +263,7 @@ pub fn utimensat(
 #[syscall_func(48)]
 pub fn faccessat(dirfd: isize, path: usize, mode: usize, flag: usize) -> AlienResult<isize> {
     let task = current_task().unwrap();
-    let path = task.transfer_str(path as *const u8);
+    let path = task.transfer_str(path as *const u8)?;
     let mode = FaccessatMode::from_bits_truncate(mode as u32);
     let flag = FaccessatFlags::from_bits_truncate(flag as u32);
     info!(
@@ -208,7 +275,7 @@ pub fn faccessat(dirfd: isize, path: usize, mode: usize, flag: usize) -> AlienRe
     Ok(0)
 }
 
-/// 一个系统调用函数，用于修改文件或目录的权限。(待实现)
+/// 一个系统调用函数，用于修改文件或目录的权限。(待实现，目前只检查文件所在的挂载点是否可写)
 ///
 /// 在Alien系统中，每个文件或目录都有一个权限位，
 /// 用于控制该文件或目录的访问权限。sys_chmod函数可以用于修改这些权限位。
@@ -218,11 +285,12 @@ pub fn faccessat(dirfd: isize, path: usize, mode: usize, flag: usize) -> AlienRe
 ///
 /// Reference: [chmod](https:///man7.org/linux/man-pages/man2/chmod.2.html)
 #[syscall_func(52)]
-pub fn chmod(_fd: usize, _mode: usize) -> isize {
-    0
+pub fn chmod(fd: usize, _mode: usize) -> AlienResult<isize> {
+    check_writable(fd as isize, "")?;
+    Ok(0)
 }
 
-/// (待实现)一个系统调用函数，用于修改相对于某目录某位置处文件或目录的权限。
+/// (待实现，目前只检查文件所在的挂载点是否可写)一个系统调用函数，用于修改相对于某目录某位置处文件或目录的权限。
 ///
 /// 当传入的`path`是一个相对地址时，那么`path`会被解析成基于文件描述符`dirfd`
 /// 所指向的目录地址的一个地址；当传入的`path`是一个相对地址并且
@@ -241,8 +309,10 @@ pub fn chmod(_fd: usize, _mode: usize) -> isize {
 ///
 /// Reference: [chmod](https:///man7.org/linux/man-pages/man2/chmod.2.html)
 #[syscall_func(53)]
-pub fn chmodat(_dirfd: usize, _path: usize, _mode: usize, _flags: usize) -> isize {
-    0
+pub fn chmodat(dirfd: isize, path: *const u8, _mode: usize, _flags: usize) -> AlienResult<isize> {
+    let path = current_task().unwrap().transfer_str(path)?;
+    check_writable(dirfd, &path)?;
+    Ok(0)
 }
 
 /// 一个系统调用，用于获取并设置当前进程的 `unmask`。在一个进程中，unmask 用于定义新建文件或目录的默认权限。
@@ -1,5 +1,6 @@
 use crate::fs::user_path_at;
 use crate::task::current_task;
+use alloc::vec;
 use constants::AlienResult;
 use constants::{LinuxErrno, AT_FDCWD};
 use core::cmp::min;
@@ -33,11 +34,12 @@ pub fn sys_setxattr(
     // we ignore flag
     assert_eq!(flag, 0);
     let process = current_task().unwrap();
-    let path = process.transfer_str(path);
-    let name = process.transfer_str(name);
-    let value = process.transfer_buffer(value, size);
+    let path = process.transfer_str(path)?;
+    let name = process.transfer_str(name)?;
+    let mut buf = vec![0u8; size];
+    process.copy_from_user_buffer(value, &mut buf)?;
     let path = user_path_at(AT_FDCWD, &path)?;
-    path.set_xattr(&name, value[0])?;
+    path.set_xattr(&name, &buf)?;
     Ok(0)
 }
 
@@ -71,11 +73,12 @@ pub fn sys_fsetxattr(
     // we ignore flag
     assert_eq!(flag, 0);
     let process = current_task().unwrap();
-    let name = process.transfer_str(name);
-    let value = process.transfer_buffer(value, size);
+    let name = process.transfer_str(name)?;
+    let mut buf = vec![0u8; size];
+    process.copy_from_user_buffer(value, &mut buf)?;
     let file = process.get_file(fd).ok_or(LinuxErrno::EBADF)?;
     let path = VfsPath::new(system_root_fs(), file.dentry());
-    path.set_xattr(&name, value[0])?;
+    path.set_xattr(&name, &buf)?;
     Ok(0)
 }
 
@@ -98,17 +101,12 @@ pub fn sys_getxattr(
     size: usize,
 ) -> AlienResult<isize> {
     let process = current_task().unwrap();
-    let path = process.transfer_str(path);
-    let name = process.transfer_str(name);
-    let mut value = process.transfer_buffer(value, size);
+    let path = process.transfer_str(path)?;
+    let name = process.transfer_str(name)?;
     let path = user_path_at(AT_FDCWD, &path)?;
     let res = path.get_xattr(&name)?;
-    let mut copy = 0;
-    value.iter_mut().for_each(|x| {
-        let min_copy = min(x.len(), res.len() - copy);
-        x.copy_from_slice(&res[copy..copy + min_copy]);
-        copy += min_copy;
-    });
+    let copy = min(size, res.len());
+    process.copy_to_user_buffer(&res[..copy], value as *mut u8)?;
     Ok(copy as _)
 }
 
@@ -138,17 +136,12 @@ pub fn sys_fgetxattr(
     size: usize,
 ) -> AlienResult<isize> {
     let process = current_task().unwrap();
-    let name = process.transfer_str(name);
-    let mut value = process.transfer_buffer(value, size);
+    let name = process.transfer_str(name)?;
     let file = process.get_file(fd).ok_or(LinuxErrno::EBADF)?;
     let path = VfsPath::new(system_root_fs(), file.dentry());
     let res = path.get_xattr(&name)?;
-    let mut copy = 0;
-    value.iter_mut().for_each(|x| {
-        let min_copy = min(x.len(), res.len() - copy);
-        x.copy_from_slice(&res[copy..copy + min_copy]);
-        copy += min_copy;
-    });
+    let copy = min(size, res.len());
+    process.copy_to_user_buffer(&res[..copy], value as *mut u8)?;
     Ok(copy as _)
 }
 
@@ -167,8 +160,8 @@ pub fn sys_fgetxattr(
 #[syscall_func(11)]
 pub fn sys_listxattr(path: *const u8, list: *const u8, size: usize) -> AlienResult<isize> {
     let process = current_task().unwrap();
-    let _path = process.transfer_str(path);
-    let _list = process.transfer_buffer(list, size);
+    let _path = process.transfer_str(path)?;
+    let _list = process.transfer_buffer_mut(list as *mut u8, size)?;
     unimplemented!();
 }
 
@@ -188,7 +181,7 @@ pub fn sys_llistxattr(path: *const u8, list: *const u8, size: usize) -> AlienRes
 #[syscall_func(13)]
 pub fn sys_flistxattr(fd: usize, list: *const u8, size: usize) -> AlienResult<isize> {
     let process = current_task().unwrap();
-    let _list = process.transfer_buffer(list, size);
+    let _list = process.transfer_buffer_mut(list as *mut u8, size)?;
     let _file = process.get_file(fd).ok_or(LinuxErrno::EBADF)?;
     unimplemented!();
 }
@@ -205,8 +198,8 @@ pub fn sys_flistxattr(fd: usize, list: *const u8, size: usize) -> AlienResult<is
 #[syscall_func(14)]
 pub fn sys_removexattr(path: *const u8, name: *const u8) -> AlienResult<isize> {
     let process = current_task().unwrap();
-    let _path = process.transfer_str(path);
-    let _name = process.transfer_str(name);
+    let _path = process.transfer_str(path)?;
+    let _name = process.transfer_str(name)?;
     unimplemented!();
 }
 
@@ -226,7 +219,7 @@ pub fn sys_lremovexattr(path: *const u8, name: *const u8) -> AlienResult<isize>
 #[syscall_func(16)]
 pub fn sys_fremovexattr(fd: usize, name: *const u8) -> AlienResult<isize> {
     let process = current_task().unwrap();
-    let _name = process.transfer_str(name);
+    let _name = process.transfer_str(name)?;
     let _file = process.get_file(fd).ok_or(LinuxErrno::EBADF)?;
     unimplemented!();
 }
@@ -3,7 +3,10 @@ use constants::io::{LinkFlags, OpenFlags, UnlinkatFlags};
 use log::{info, warn};
 use syscall_table::syscall_func;
 
-use crate::{fs::user_path_at, task::current_task};
+use crate::{
+    fs::{check_writable, user_path_at},
+    task::current_task,
+};
 use constants::AlienResult;
 /// 一个系统调用，用于创建相对于一个目录某位置处的一个文件的(硬)链接。
 ///
@@ -35,9 +38,10 @@ pub fn sys_linkat(
 ) -> AlienResult<isize> {
     let flag = LinkFlags::from_bits_truncate(flag as u32);
     let process = current_task().unwrap();
-    let old_name = process.transfer_str(old_name);
+    let old_name = process.transfer_str(old_name)?;
     let old_path = user_path_at(old_fd, &old_name)?;
-    let new_name = process.transfer_str(new_name);
+    let new_name = process.transfer_str(new_name)?;
+    check_writable(new_fd, &new_name)?;
     let new_path = user_path_at(new_fd, &new_name)?;
 
     warn!(
@@ -82,9 +86,10 @@ pub fn sys_linkat(
 #[syscall_func(35)]
 pub fn sys_unlinkat(fd: isize, path: *const u8, flag: usize) -> AlienResult<isize> {
     let task = current_task().unwrap();
-    let path = task.transfer_str(path);
+    let path = task.transfer_str(path)?;
     let flag = UnlinkatFlags::from_bits_truncate(flag as u32);
     info!("unlinkat path: {:?}, flag: {:?}", path, flag);
+    check_writable(fd, &path)?;
     let path = user_path_at(fd, &path)?;
     if flag.contains(UnlinkatFlags::AT_REMOVEDIR) {
         path.rmdir()?;
@@ -106,8 +111,9 @@ pub fn sys_symlinkat(
     new_name: *const u8,
 ) -> AlienResult<isize> {
     let process = current_task().unwrap();
-    let old_name = process.transfer_str(old_name);
-    let new_name = process.transfer_str(new_name);
+    let old_name = process.transfer_str(old_name)?;
+    let new_name = process.transfer_str(new_name)?;
+    check_writable(new_fd, &new_name)?;
     let new_path = user_path_at(new_fd, &new_name)?;
     new_path.symlink(&old_name)?;
     Ok(0)
@@ -122,21 +128,12 @@ pub fn sys_symlinkat(
 #[syscall_func(78)]
 pub fn sys_readlinkat(fd: isize, path: *const u8, buf: *mut u8, size: usize) -> AlienResult<isize> {
     let task = current_task().unwrap();
-    let path = task.transfer_str(path);
+    let path = task.transfer_str(path)?;
     info!("readlink path: {}", path);
     let path = user_path_at(fd, &path)?;
     let dt = path.open2(None, OpenFlags::O_NOFOLLOW)?;
     let mut empty_buf = vec![0u8; size];
     let r = dt.inode()?.readlink(empty_buf.as_mut_slice())?;
-    let buf = task.transfer_buffer(buf, size);
-    let mut w = 0;
-    for buf in buf {
-        let len = buf.len();
-        buf.copy_from_slice(&empty_buf[w..w + len]);
-        w += len;
-        if w >= r {
-            break;
-        }
-    }
+    task.copy_to_user_buffer(&empty_buf[..r], buf)?;
     Ok(r as isize)
 }
@@ -6,11 +6,17 @@ pub mod poll;
 pub mod select;
 pub mod stdio;
 
+use crate::ipc::send_signal;
 use crate::task::{current_task, FsContext};
+use alloc::format;
+use alloc::string::String;
+use alloc::sync::Arc;
 use alloc::vec::Vec;
-use constants::io::InodeMode;
-use constants::{AlienResult, LinuxErrno, AT_FDCWD};
+use constants::io::{InodeMode, MountFlags, OpenFlags, SeekFrom};
+use constants::signal::SignalNumber;
+use constants::{AlienResult, LinuxErrno, PrLimitRes, AT_FDCWD, RLIM_INFINITY};
 use log::info;
+use vfs::kfile::{File, KernelFile};
 use vfs::system_root_fs;
 use vfscore::path::{SysContext, VfsPath};
 use vfscore::utils::{VfsInodeMode, VfsNodeType};
@@ -41,6 +47,104 @@ fn user_path_at(fd: isize, path: &str) -> AlienResult<VfsPath> {
     res.map_err(|e| e.into())
 }
 
+/// 与 [`user_path_at`] 类似，但返回的是规范化后的绝对路径字符串，用于查询挂载表等场景。
+fn user_abs_path(fd: isize, path: &str) -> AlienResult<String> {
+    if path.starts_with("/") {
+        return Ok(vfs::mount::normalize_path(path));
+    }
+    let process = current_task().unwrap();
+    let base = if fd == AT_FDCWD {
+        process.access_inner().fs_info.cwd.path()
+    } else {
+        let file = process.get_file(fd as usize).ok_or(LinuxErrno::EBADF)?;
+        file.dentry().path()
+    };
+    Ok(vfs::mount::normalize_path(&format!("{}/{}", base, path)))
+}
+
+/// 检查路径所在的挂载点是否可写，只读挂载(MS_RDONLY)时返回 EROFS
+fn check_writable(fd: isize, path: &str) -> AlienResult<()> {
+    let path = user_abs_path(fd, path)?;
+    if vfs::mount::path_has_flag(&path, MountFlags::MS_RDONLY) {
+        return Err(LinuxErrno::EROFS);
+    }
+    Ok(())
+}
+
+/// 检查路径所在的挂载点是否允许执行程序，禁止执行(MS_NOEXEC)时返回 EACCES
+pub fn check_executable(path: &str) -> AlienResult<()> {
+    let path = user_abs_path(AT_FDCWD, path)?;
+    if vfs::mount::path_has_flag(&path, MountFlags::MS_NOEXEC) {
+        return Err(LinuxErrno::EACCES);
+    }
+    Ok(())
+}
+
+const S_ISUID: u32 = 0o4000;
+const S_ISGID: u32 = 0o2000;
+const S_IXGRP: u32 = 0o0010;
+
+/// 计算执行 `path` 处的程序后进程的有效用户 id 和有效用户组 id。
+///
+/// 文件设置了 set-user-ID 位时有效用户 id 变为文件的所有者；设置了 set-group-ID 位且组可执行时，
+/// 有效用户组 id 变为文件所属的组。文件位于 nosuid(MS_NOSUID) 挂载点下时忽略这两个位，返回 `(None, None)`。
+pub fn exec_credentials(path: &str) -> AlienResult<(Option<u32>, Option<u32>)> {
+    let abs = user_abs_path(AT_FDCWD, path)?;
+    if vfs::mount::path_has_flag(&abs, MountFlags::MS_NOSUID) {
+        return Ok((None, None));
+    }
+    let attr = user_path_at(AT_FDCWD, path)?
+        .open(None)?
+        .inode()?
+        .get_attr()?;
+    let euid = (attr.st_mode & S_ISUID != 0).then_some(attr.st_uid);
+    let egid = (attr.st_mode & (S_ISGID | S_IXGRP) == S_ISGID | S_IXGRP).then_some(attr.st_gid);
+    Ok((euid, egid))
+}
+
+/// 根据 RLIMIT_FSIZE 限制对普通文件的写入，返回允许写入的长度。
+///
+/// `offset` 为写入的起始位置，为 `None` 时使用文件当前的偏移量。起始位置已经达到限制时返回 EFBIG。
+fn fsize_limit(file: &Arc<dyn File>, offset: Option<u64>, len: usize) -> AlienResult<usize> {
+    let limit = current_fsize_limit();
+    if limit == RLIM_INFINITY || len == 0 {
+        return Ok(len);
+    }
+    let file = match file.clone().downcast_arc::<KernelFile>() {
+        Ok(file) if file.inode().inode_type() == VfsNodeType::File => file,
+        _ => return Ok(len),
+    };
+    let offset = match offset {
+        Some(offset) => offset,
+        None => file.seek(SeekFrom::Current(0))?,
+    };
+    if offset >= limit {
+        return Err(file_too_large());
+    }
+    Ok(len.min((limit - offset) as usize))
+}
+
+/// 检查将文件截断到 `len` 时是否超过 RLIMIT_FSIZE，超过时返回 EFBIG
+fn check_truncate_limit(len: u64) -> AlienResult<()> {
+    if len > current_fsize_limit() {
+        return Err(file_too_large());
+    }
+    Ok(())
+}
+
+fn current_fsize_limit() -> u64 {
+    let task = current_task().unwrap();
+    let limit = task.access_inner().rlimit_cur(PrLimitRes::RlimitFsize);
+    limit
+}
+
+/// 文件长度超过 RLIMIT_FSIZE 时，向当前进程发送 SIGXFSZ
+fn file_too_large() -> LinuxErrno {
+    let task = current_task().unwrap();
+    send_signal(task.get_tid() as usize, SignalNumber::SIGXFSZ as usize);
+    LinuxErrno::EFBIG
+}
+
 pub fn read_all(file_name: &str, buf: &mut Vec<u8>) -> bool {
     let task = current_task();
     // let cwd = if task.is_some() {
@@ -78,6 +182,20 @@ pub fn read_all(file_name: &str, buf: &mut Vec<u8>) -> bool {
     true
 }
 
+/// 以当前任务的身份创建一个只有所有者可以读写的文件用于写入，文件已经存在时将其截断。用于内核生成 core 文件等场景
+pub fn create_file(file_name: &str) -> AlienResult<Arc<dyn File>> {
+    check_writable(AT_FDCWD, file_name)?;
+    let path = user_path_at(AT_FDCWD, file_name)?;
+    let mode = InodeMode::from_bits_truncate(0o600);
+    let dentry = path.open(Some(im2vim(mode)))?;
+    if dentry.inode()?.inode_type() != VfsNodeType::File {
+        return Err(LinuxErrno::EISDIR);
+    }
+    let file = KernelFile::new(dentry, OpenFlags::O_WRONLY);
+    file.truncate(0)?;
+    Ok(Arc::new(file))
+}
+
 /// [InodeMode](InodeMode)转换为[VfsInodeMode](VfsInodeMode)
 fn im2vim(mode: InodeMode) -> VfsInodeMode {
     VfsInodeMode::from_bits_truncate(mode.bits())
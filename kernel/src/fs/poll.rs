@@ -39,12 +39,11 @@ pub fn ppoll(fds_ptr: usize, nfds: usize, time: usize, _mask: usize) -> AlienRes
     unsafe {
         fds.set_len(nfds);
     }
-    task.access_inner()
-        .copy_from_user_buffer(fds_ptr as *const PollFd, fds.as_mut_ptr(), nfds);
+    task.copy_from_user_buffer(fds_ptr as *const PollFd, &mut fds)?;
 
     info!("fds: {:?}", fds);
     let wait_time = if time != 0 {
-        let time_spec = task.transfer_raw_ptr(time as *mut TimeSpec);
+        let time_spec = task.copy_from_user(time as *const TimeSpec)?;
         Some(time_spec.to_clock() + TimeSpec::now().to_clock())
     } else {
         None
@@ -68,8 +67,7 @@ pub fn ppoll(fds_ptr: usize, nfds: usize, time: usize, _mask: usize) -> AlienRes
 
         if res > 0 {
             // copy to user
-            task.access_inner()
-                .copy_to_user_buffer(fds.as_ptr(), fds_ptr as *mut PollFd, nfds);
+            task.copy_to_user_buffer(&fds, fds_ptr as *mut PollFd)?;
             info!("ppoll return {:?}", fds);
             return Ok(res as isize);
         }
@@ -61,17 +61,17 @@ pub fn pselect6(
     let task = current_task().unwrap();
 
     if sigmask != 0 {
-        let mask = task.access_inner().transfer_raw_ptr(sigmask as *mut usize);
-        let mask_num: Vec<SignalNumber> = SimpleBitSet(*mask).into();
-        info!("pselect6: sigmask = {} ---> {:?}, ", *mask, mask_num);
+        let mask = task.copy_from_user(sigmask as *const usize)?;
+        let mask_num: Vec<SignalNumber> = SimpleBitSet(mask).into();
+        info!("pselect6: sigmask = {} ---> {:?}, ", mask, mask_num);
     }
 
     let (wait_time, time_spec) = if timeout != 0 {
-        let time_spec = task.transfer_raw_ptr(timeout as *mut TimeSpec);
+        let time_spec = task.copy_from_user(timeout as *const TimeSpec)?;
         info!("pselect6: timeout = {:#x} ---> {:?}", timeout, time_spec);
         (
             Some(time_spec.to_clock() + TimeSpec::now().to_clock()),
-            Some(time_spec.clone()),
+            Some(time_spec),
         )
     } else {
         (Some(usize::MAX), None)
@@ -81,20 +81,17 @@ pub fn pselect6(
 
     // 这里暂时不考虑 sigmask 的问题
     let ori_readfds = if readfds != 0 {
-        let readfds = task.transfer_raw_ptr(readfds as *mut u64);
-        *readfds
+        task.copy_from_user(readfds as *const u64)?
     } else {
         0
     };
     let ori_writefds = if writefds != 0 {
-        let writefds = task.transfer_raw_ptr(writefds as *mut u64);
-        *writefds
+        task.copy_from_user(writefds as *const u64)?
     } else {
         0
     };
     let ori_exceptfds = if exceptfds != 0 {
-        let exceptfds = task.transfer_raw_ptr(exceptfds as *mut u64);
-        *exceptfds
+        task.copy_from_user(exceptfds as *const u64)?
     } else {
         0
     };
@@ -108,7 +105,7 @@ pub fn pselect6(
         let mut set = 0;
         // 如果设置了监视是否可读的 fd
         if readfds != 0 {
-            let readfds = task.transfer_raw_ptr(readfds as *mut u64);
+            let readfds = task.transfer_raw_ptr(readfds as *mut u64)?;
             trace!(
                 "[tid:{}]pselect6: readfds = {:#b}",
                 task.get_tid(),
@@ -133,7 +130,7 @@ pub fn pselect6(
         }
         // 如果设置了监视是否可写的 fd
         if writefds != 0 {
-            let writefds = task.transfer_raw_ptr(writefds as *mut u64);
+            let writefds = task.transfer_raw_ptr(writefds as *mut u64)?;
             trace!(
                 "[tid:{}]pselect6: writefds = {:#b}",
                 task.get_tid(),
@@ -158,7 +155,7 @@ pub fn pselect6(
         }
         // 如果设置了监视是否异常的 fd
         if exceptfds != 0 {
-            let exceptfds = task.transfer_raw_ptr(exceptfds as *mut u64);
+            let exceptfds = task.transfer_raw_ptr(exceptfds as *mut u64)?;
             trace!(
                 "[tid:{}]pselect6: exceptfds = {:#b}",
                 task.get_tid(),
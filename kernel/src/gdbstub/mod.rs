@@ -0,0 +1,632 @@
+//! 内核 GDB 远程调试
+//!
+//! 内核命令行中指定 `kgdb=<n>` 后，设备树中的第 n 个串口被用作 GDB 远程串行协议(RSP)的通信端口，
+//! 可以通过 `target remote /dev/ttyUSBx` 连接。以下情况会进入调试器：
+//! + 内核执行到断点，包括 GDB 设置的软件断点以及单步执行使用的临时断点；
+//! + 内核运行时 GDB 发送了 Ctrl-C 或者新的数据包(重新连接)，在时钟中断中检查；
+//! + GDB 已经连接或者指定了 `kgdbwait` 时内核 panic，此时只能查看现场，GDB 分离后继续 panic 处理；
+//! + 命令行中指定了 `kgdbwait` 时，在启动过程中等待 GDB 连接。
+//!
+//! 进入调试器的核称为主控核，它通过核间中断让其它核停在中断处理中。GDB 中的线程对应于停止的核，
+//! 线程号为 hart id + 1。单步执行只作用于主控核，通过在下一条指令所有可能到达的位置写入临时断点实现。
+mod packet;
+
+use crate::trap::{self, capture_registers, KernelFrame, Registers, StopFrame};
+use alloc::collections::{BTreeSet, VecDeque};
+use alloc::format;
+use alloc::vec::Vec;
+use arch::{hart_id, C_EBREAK, EBREAK};
+use config::{CPU_NUM, FRAME_SIZE};
+use core::arch::asm;
+use core::hint::spin_loop;
+use core::panic::PanicInfo;
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use ksync::Mutex;
+use packet::{decode_hex, encode_hex, parse_hex, Connection, INTERRUPT};
+use platform::config::CLOCK_FREQ;
+use timer::read_timer;
+
+const SIGINT: u8 = 2;
+const SIGTRAP: u8 = 5;
+const SIGABRT: u8 = 6;
+
+/// GDB 中的寄存器编号，0-31 为通用寄存器
+const REG_PC: usize = 32;
+const REG_NUM: usize = 33;
+
+/// 没有核进入调试器
+const NO_MASTER: usize = usize::MAX;
+
+/// 等待其它核停止的最长时间(ms)
+const STOP_TIMEOUT_MS: usize = 100;
+
+/// 只包含通用寄存器和 pc 的目标描述，GDB 据此确定 `g` 数据包的格式
+const TARGET_XML: &str = concat!(
+    r#"<?xml version="1.0"?><!DOCTYPE target SYSTEM "gdb-target.dtd">"#,
+    r#"<target version="1.0"><architecture>riscv:rv64</architecture>"#,
+    r#"<feature name="org.gnu.gdb.riscv.cpu">"#,
+    r#"<reg name="zero" bitsize="64" type="int" regnum="0"/>"#,
+    r#"<reg name="ra" bitsize="64" type="code_ptr"/>"#,
+    r#"<reg name="sp" bitsize="64" type="data_ptr"/>"#,
+    r#"<reg name="gp" bitsize="64" type="data_ptr"/>"#,
+    r#"<reg name="tp" bitsize="64" type="data_ptr"/>"#,
+    r#"<reg name="t0" bitsize="64" type="int"/>"#,
+    r#"<reg name="t1" bitsize="64" type="int"/>"#,
+    r#"<reg name="t2" bitsize="64" type="int"/>"#,
+    r#"<reg name="fp" bitsize="64" type="data_ptr"/>"#,
+    r#"<reg name="s1" bitsize="64" type="int"/>"#,
+    r#"<reg name="a0" bitsize="64" type="int"/>"#,
+    r#"<reg name="a1" bitsize="64" type="int"/>"#,
+    r#"<reg name="a2" bitsize="64" type="int"/>"#,
+    r#"<reg name="a3" bitsize="64" type="int"/>"#,
+    r#"<reg name="a4" bitsize="64" type="int"/>"#,
+    r#"<reg name="a5" bitsize="64" type="int"/>"#,
+    r#"<reg name="a6" bitsize="64" type="int"/>"#,
+    r#"<reg name="a7" bitsize="64" type="int"/>"#,
+    r#"<reg name="s2" bitsize="64" type="int"/>"#,
+    r#"<reg name="s3" bitsize="64" type="int"/>"#,
+    r#"<reg name="s4" bitsize="64" type="int"/>"#,
+    r#"<reg name="s5" bitsize="64" type="int"/>"#,
+    r#"<reg name="s6" bitsize="64" type="int"/>"#,
+    r#"<reg name="s7" bitsize="64" type="int"/>"#,
+    r#"<reg name="s8" bitsize="64" type="int"/>"#,
+    r#"<reg name="s9" bitsize="64" type="int"/>"#,
+    r#"<reg name="s10" bitsize="64" type="int"/>"#,
+    r#"<reg name="s11" bitsize="64" type="int"/>"#,
+    r#"<reg name="t3" bitsize="64" type="int"/>"#,
+    r#"<reg name="t4" bitsize="64" type="int"/>"#,
+    r#"<reg name="t5" bitsize="64" type="int"/>"#,
+    r#"<reg name="t6" bitsize="64" type="int"/>"#,
+    r#"<reg name="pc" bitsize="64" type="code_ptr"/>"#,
+    r#"</feature></target>"#
+);
+
+/// 是否配置了调试端口
+static ENABLED: AtomicBool = AtomicBool::new(false);
+/// 主控核的 hart id
+static MASTER: AtomicUsize = AtomicUsize::new(NO_MASTER);
+/// 每次恢复执行时加一，停止的核据此判断是否可以继续执行
+static GENERATION: AtomicUsize = AtomicUsize::new(0);
+/// 停止在调试器中的非主控核的数量
+static PARKED: AtomicUsize = AtomicUsize::new(0);
+/// 各个停止的核的寄存器，GDB 修改后的值在恢复执行时写回
+static HARTS: Mutex<[Option<Registers>; CPU_NUM]> = Mutex::new([None; CPU_NUM]);
+/// GDB 已经连接，恢复执行后再次停止时需要主动发送停止原因。panic 时据此决定是否等待调试器
+static CONNECTED: AtomicBool = AtomicBool::new(false);
+/// 时钟中断中从调试端口读出、但不是 Ctrl-C 的字节，进入调试器后先于串口中的数据交给数据包接收
+static PENDING: Mutex<VecDeque<u8>> = Mutex::new(VecDeque::new());
+/// [`PENDING`] 最多保存的字节数，超出后丢弃最早的字节
+const PENDING_MAX: usize = 256;
+
+static GDB_STATE: Mutex<GdbState> = Mutex::new(GdbState::new());
+
+impl Registers {
+    fn get(&self, index: usize) -> usize {
+        match index {
+            REG_PC => self.pc,
+            _ => self.x[index],
+        }
+    }
+
+    fn set(&mut self, index: usize, value: usize) {
+        match index {
+            0 => {}
+            REG_PC => self.pc = value,
+            _ => self.x[index] = value,
+        }
+    }
+}
+
+/// 主控核恢复执行的方式
+enum Resume {
+    Continue,
+    Step,
+    /// panic 之后不能恢复执行
+    Exit,
+}
+
+struct GdbState {
+    /// GDB 设置的断点
+    breakpoints: BTreeSet<usize>,
+    /// 当前写入内存的断点及被覆盖的指令，调试器运行期间全部移除
+    installed: Vec<(usize, u16)>,
+    /// 单步执行写入的临时断点
+    step_breaks: Vec<usize>,
+    /// 正在单步执行的核
+    stepping: Option<usize>,
+    /// 单步越过断点所在的指令，结束后写入所有断点并继续执行，不报告给 GDB
+    step_over: bool,
+    /// `Hg` 选择的核
+    selected: usize,
+}
+
+impl GdbState {
+    const fn new() -> Self {
+        Self {
+            breakpoints: BTreeSet::new(),
+            installed: Vec::new(),
+            step_breaks: Vec::new(),
+            stepping: None,
+            step_over: false,
+            selected: 0,
+        }
+    }
+
+    /// 在 `addr` 处写入 `c.ebreak`，记录被覆盖的指令
+    fn install_at(&mut self, addr: usize) {
+        if self
+            .installed
+            .iter()
+            .any(|(installed, _)| *installed == addr)
+        {
+            return;
+        }
+        if let Some(origin) = read_memory(addr, 2) {
+            if mem::patch_kernel_text(addr, &C_EBREAK.to_le_bytes()) {
+                self.installed
+                    .push((addr, u16::from_le_bytes([origin[0], origin[1]])));
+            }
+        }
+    }
+
+    /// 写入所有断点，`skip` 处的断点除外
+    fn install(&mut self, skip: Option<usize>) {
+        let breakpoints = self.breakpoints.clone();
+        for addr in breakpoints.into_iter().filter(|addr| Some(*addr) != skip) {
+            self.install_at(addr);
+        }
+    }
+
+    /// 恢复所有被断点覆盖的指令
+    fn uninstall(&mut self) {
+        // 与写入顺序相反，保证同一地址最终恢复为原来的指令
+        while let Some((addr, origin)) = self.installed.pop() {
+            mem::patch_kernel_text(addr, &origin.to_le_bytes());
+        }
+    }
+}
+
+/// 初始化调试端口，需要在设备和中断处理初始化之后调用。命令行中指定了 `kgdbwait` 时在此等待 GDB 连接
+pub fn init_gdbstub() {
+    let uart = match devices::DEBUG_UART.get() {
+        Some(uart) => uart,
+        None => return,
+    };
+    if mem::query_kernel_space(uart.base_addr).is_none() {
+        mem::map_region_to_kernel(uart.base_addr, FRAME_SIZE, "RWVAD".into());
+    }
+    ENABLED.store(true, Ordering::Release);
+    println!("kgdb: debug port at {:#x}", uart.base_addr);
+    if platform::cmdline::kernel_cmdline().kgdb_wait {
+        println!("kgdb: waiting for connection");
+        breakpoint();
+    }
+}
+
+/// 执行一条 `ebreak` 指令进入调试器
+pub fn breakpoint() {
+    unsafe { asm!("ebreak") };
+}
+
+/// 内核态执行到断点时调用，没有配置调试端口时返回 false
+pub fn kernel_breakpoint(frame: &mut KernelFrame) -> bool {
+    if !ENABLED.load(Ordering::Acquire) {
+        return false;
+    }
+    enter(StopFrame::Kernel(frame), SIGTRAP);
+    true
+}
+
+/// 在时钟中断中检查 GDB 是否发送了 Ctrl-C 或者新的数据包
+///
+/// 读出的其它字节保存在 [`PENDING`] 中而不是丢弃。目标运行时 GDB 只会在重新连接时发送数据包，
+/// 因此读到数据包的起始字节 `$` 时同样进入调试器，由调试器接收完整的数据包。
+pub fn poll(frame: StopFrame) {
+    if !ENABLED.load(Ordering::Acquire) || MASTER.load(Ordering::Acquire) != NO_MASTER {
+        return;
+    }
+    let uart = devices::DEBUG_UART.get().unwrap();
+    let c = match uart.uart.lock()._read() {
+        Some(c) => c,
+        None => return,
+    };
+    if c == INTERRUPT {
+        enter(frame, SIGINT);
+        return;
+    }
+    let mut pending = PENDING.lock();
+    if pending.len() == PENDING_MAX {
+        pending.pop_front();
+    }
+    pending.push_back(c);
+    drop(pending);
+    if c == b'$' {
+        enter(frame, SIGINT);
+    }
+}
+
+/// 处理主控核发送的核间中断，停止当前核直到调试器恢复执行
+pub fn handle_ipi(mut frame: StopFrame) {
+    let master = MASTER.load(Ordering::Acquire);
+    if master != NO_MASTER && master != hart_id() {
+        park(&mut frame);
+    }
+}
+
+/// 内核 panic 时进入调试器，只能查看现场
+///
+/// 只有 GDB 已经连接或者命令行中指定了 `kgdbwait` 时才等待调试器，否则直接返回，
+/// 使之后的单元测试失败报告和崩溃转储重启能够继续进行。
+pub fn panic_hook(_info: &PanicInfo) {
+    if !ENABLED.load(Ordering::Acquire) {
+        return;
+    }
+    if !CONNECTED.load(Ordering::Acquire) && !platform::cmdline::kernel_cmdline().kgdb_wait {
+        return;
+    }
+    arch::interrupt_disable();
+    let regs = capture_registers();
+    enter(StopFrame::Panic(regs), SIGABRT);
+}
+
+/// 进入调试器，`signal` 为报告给 GDB 的停止原因
+fn enter(mut frame: StopFrame, signal: u8) {
+    let hart = hart_id();
+    if let Err(master) =
+        MASTER.compare_exchange(NO_MASTER, hart, Ordering::AcqRel, Ordering::Acquire)
+    {
+        // 调试器自身发生 panic 时不能再次进入
+        if master != hart {
+            park(&mut frame);
+        }
+        return;
+    }
+    let mut state = GDB_STATE.lock();
+    let regs = frame.registers();
+    let at_step_break = state.step_breaks.contains(&regs.pc);
+    if at_step_break && state.stepping.map_or(false, |stepping| stepping != hart) {
+        // 其它核执行到单步执行的临时断点，等待临时断点移除后重新执行
+        drop(state);
+        MASTER.store(NO_MASTER, Ordering::Release);
+        return;
+    }
+    state.uninstall();
+    let stepped = state.stepping.take().is_some() && at_step_break;
+    state.step_breaks.clear();
+    if stepped && state.step_over {
+        state.step_over = false;
+        state.install(None);
+        drop(state);
+        MASTER.store(NO_MASTER, Ordering::Release);
+        return;
+    }
+    state.step_over = false;
+    stop_other_harts();
+    HARTS.lock()[hart] = Some(regs);
+    state.selected = hart;
+
+    let uart = devices::DEBUG_UART.get().unwrap();
+    let mut uart = uart.uart.lock();
+    let pending = core::mem::take(&mut *PENDING.lock());
+    let mut conn = Connection::new(&mut **uart, pending);
+    let signal = if stepped { SIGTRAP } else { signal };
+    if CONNECTED.load(Ordering::Acquire) {
+        conn.send_packet(&stop_reply(signal, hart));
+    }
+    let resume = loop {
+        let packet = conn.recv_packet();
+        CONNECTED.store(true, Ordering::Release);
+        let mut reply = Vec::new();
+        let resume = handle_packet(&mut state, &packet, signal, &mut reply);
+        // c 与 s 的回复是下一次停止的原因
+        if resume.is_none() || !reply.is_empty() {
+            conn.send_packet(&reply);
+        }
+        if let Some(resume) = resume {
+            break resume;
+        }
+    };
+    drop(uart);
+
+    let mut regs = HARTS.lock()[hart].take().unwrap();
+    match resume {
+        Resume::Continue => {
+            skip_compiled_breakpoint(&state, &mut regs);
+            if state.breakpoints.contains(&regs.pc) {
+                // 先单步越过当前位置的断点，再写入所有断点
+                state.step_over = true;
+                start_step(&mut state, hart, &regs);
+                state.install(Some(regs.pc));
+            } else {
+                state.install(None);
+            }
+        }
+        Resume::Step => {
+            skip_compiled_breakpoint(&state, &mut regs);
+            start_step(&mut state, hart, &regs);
+            state.install(Some(regs.pc));
+        }
+        Resume::Exit => {}
+    }
+    frame.set_registers(&regs);
+    drop(state);
+    GENERATION.fetch_add(1, Ordering::AcqRel);
+    MASTER.store(NO_MASTER, Ordering::Release);
+}
+
+/// 通过核间中断停止其它核，等待它们进入 [`park`]
+fn stop_other_harts() {
+    let count = trap::send_ipi_to_others();
+    if count == 0 {
+        return;
+    }
+    let deadline = read_timer() + CLOCK_FREQ / 1000 * STOP_TIMEOUT_MS;
+    while PARKED.load(Ordering::Acquire) < count && read_timer() < deadline {
+        spin_loop();
+    }
+}
+
+/// 停止当前核，直到主控核恢复执行。GDB 对寄存器的修改在恢复执行时写回
+fn park(frame: &mut StopFrame) {
+    let hart = hart_id();
+    let generation = GENERATION.load(Ordering::Acquire);
+    HARTS.lock()[hart] = Some(frame.registers());
+    PARKED.fetch_add(1, Ordering::AcqRel);
+    while GENERATION.load(Ordering::Acquire) == generation
+        && MASTER.load(Ordering::Acquire) != NO_MASTER
+    {
+        spin_loop();
+    }
+    PARKED.fetch_sub(1, Ordering::AcqRel);
+    if let Some(regs) = HARTS.lock()[hart].take() {
+        frame.set_registers(&regs);
+    }
+    // 主控核可能修改了代码
+    arch::flush_icache();
+}
+
+/// 在单步执行的核下一条指令所有可能到达的位置写入临时断点
+fn start_step(state: &mut GdbState, hart: usize, regs: &Registers) {
+    let insn = match read_memory(regs.pc, 4) {
+        Some(insn) => u32::from_le_bytes([insn[0], insn[1], insn[2], insn[3]]),
+        None => return,
+    };
+    let (next, target) = arch::next_pcs(regs.pc, insn, &regs.x);
+    state.stepping = Some(hart);
+    for addr in core::iter::once(next).chain(target) {
+        state.step_breaks.push(addr);
+        state.install_at(addr);
+    }
+}
+
+/// 编译在代码中的 `ebreak`(如 [`breakpoint`])不是 GDB 设置的断点，恢复执行时需要跳过
+fn skip_compiled_breakpoint(state: &GdbState, regs: &mut Registers) {
+    if state.breakpoints.contains(&regs.pc) {
+        return;
+    }
+    let insn = read_memory(regs.pc, 4)
+        .map(|insn| u32::from_le_bytes([insn[0], insn[1], insn[2], insn[3]]))
+        .unwrap_or(0);
+    if insn as u16 == C_EBREAK {
+        regs.pc += 2;
+    } else if insn == EBREAK {
+        regs.pc += 4;
+    }
+}
+
+/// 读取内核地址空间中的内存，地址没有映射时返回 None
+fn read_memory(addr: usize, len: usize) -> Option<Vec<u8>> {
+    let end = addr.checked_add(len)?;
+    let mut page = addr & !(FRAME_SIZE - 1);
+    while page < end {
+        mem::query_kernel_space(page)?;
+        page += FRAME_SIZE;
+    }
+    Some(
+        (addr..end)
+            .map(|addr| unsafe { (addr as *const u8).read_volatile() })
+            .collect(),
+    )
+}
+
+fn stop_reply(signal: u8, hart: usize) -> Vec<u8> {
+    format!("T{:02x}thread:{:x};", signal, hart + 1).into_bytes()
+}
+
+/// 解析 `<addr>,<len>` 形式的参数
+fn parse_addr_len(args: &[u8]) -> Option<(usize, usize)> {
+    let mut parts = args.splitn(2, |&c| c == b',');
+    let addr = parse_hex(parts.next()?)?;
+    let len = parse_hex(parts.next()?)?;
+    Some((addr, len))
+}
+
+/// 解析线程号，0 和 -1 表示任意线程
+fn parse_thread(args: &[u8]) -> Option<Option<usize>> {
+    match args {
+        b"-1" | b"0" => Some(None),
+        _ => parse_hex(args).and_then(|tid| tid.checked_sub(1)).map(Some),
+    }
+}
+
+/// 处理一个数据包，回复写入 `reply`。需要恢复执行时返回恢复的方式
+fn handle_packet(
+    state: &mut GdbState,
+    packet: &[u8],
+    signal: u8,
+    reply: &mut Vec<u8>,
+) -> Option<Resume> {
+    let master = MASTER.load(Ordering::Acquire);
+    let (&command, args) = match packet.split_first() {
+        Some(split) => split,
+        None => return None,
+    };
+    let mut harts = HARTS.lock();
+    let panicked = signal == SIGABRT;
+    match command {
+        b'?' => reply.extend(stop_reply(signal, master)),
+        b'g' => {
+            if let Some(regs) = harts[state.selected].as_ref() {
+                for index in 0..REG_NUM {
+                    encode_hex(reply, &regs.get(index).to_le_bytes());
+                }
+            } else {
+                reply.extend(b"E01");
+            }
+        }
+        b'G' => match (decode_hex(args), harts[state.selected].as_mut()) {
+            (Some(data), Some(regs)) if data.len() >= REG_NUM * 8 => {
+                for (index, value) in data.chunks(8).take(REG_NUM).enumerate() {
+                    regs.set(index, usize::from_le_bytes(value.try_into().unwrap()));
+                }
+                reply.extend(b"OK");
+            }
+            _ => reply.extend(b"E01"),
+        },
+        b'p' => match (parse_hex(args), harts[state.selected].as_ref()) {
+            (Some(index), Some(regs)) if index < REG_NUM => {
+                encode_hex(reply, &regs.get(index).to_le_bytes())
+            }
+            _ => reply.extend(b"E01"),
+        },
+        b'P' => {
+            let mut parts = args.splitn(2, |&c| c == b'=');
+            let index = parts.next().and_then(parse_hex);
+            let value = parts.next().and_then(decode_hex);
+            match (index, value, harts[state.selected].as_mut()) {
+                (Some(index), Some(value), Some(regs)) if index < REG_NUM && value.len() == 8 => {
+                    regs.set(index, usize::from_le_bytes(value.try_into().unwrap()));
+                    reply.extend(b"OK");
+                }
+                _ => reply.extend(b"E01"),
+            }
+        }
+        b'm' => match parse_addr_len(args).and_then(|(addr, len)| read_memory(addr, len)) {
+            Some(data) => encode_hex(reply, &data),
+            None => reply.extend(b"E14"),
+        },
+        b'M' => {
+            let mut parts = args.splitn(2, |&c| c == b':');
+            let target = parts.next().and_then(parse_addr_len);
+            let data = parts.next().and_then(decode_hex);
+            match (target, data) {
+                (Some((addr, len)), Some(data))
+                    if data.len() == len && read_memory(addr, len).is_some() =>
+                {
+                    if mem::patch_kernel_text(addr, &data) {
+                        reply.extend(b"OK");
+                    } else {
+                        reply.extend(b"E14");
+                    }
+                }
+                _ => reply.extend(b"E01"),
+            }
+        }
+        b'Z' | b'z' if args.starts_with(b"0,") => match parse_addr_len(&args[2..]) {
+            Some((addr, _)) if read_memory(addr, 2).is_some() => {
+                if command == b'Z' {
+                    state.breakpoints.insert(addr);
+                } else {
+                    state.breakpoints.remove(&addr);
+                }
+                reply.extend(b"OK");
+            }
+            _ => reply.extend(b"E22"),
+        },
+        b'H' if args.len() > 1 => match parse_thread(&args[1..]) {
+            Some(None) => reply.extend(b"OK"),
+            Some(Some(hart)) if hart < CPU_NUM && harts[hart].is_some() => {
+                if args[0] == b'g' {
+                    state.selected = hart;
+                }
+                reply.extend(b"OK");
+            }
+            _ => reply.extend(b"E01"),
+        },
+        b'T' => match parse_thread(args) {
+            Some(Some(hart)) if hart < CPU_NUM && harts[hart].is_some() => reply.extend(b"OK"),
+            _ => reply.extend(b"E01"),
+        },
+        b'q' => handle_query(args, &*harts, master, reply),
+        b'c' | b's' => {
+            if panicked {
+                // panic 之后不能继续执行，报告内核以 SIGABRT 终止
+                reply.extend(format!("X{:02x}", SIGABRT).into_bytes());
+                return Some(Resume::Exit);
+            }
+            if !args.is_empty() {
+                match parse_hex(args) {
+                    Some(pc) => harts[master].as_mut().unwrap().pc = pc,
+                    None => {
+                        reply.extend(b"E01");
+                        return None;
+                    }
+                }
+            }
+            if command == b'c' {
+                return Some(Resume::Continue);
+            }
+            let pc = harts[master].as_ref().unwrap().pc;
+            if read_memory(pc, 4).is_none() {
+                reply.extend(b"E14");
+                return None;
+            }
+            return Some(Resume::Step);
+        }
+        b'D' => {
+            state.breakpoints.clear();
+            CONNECTED.store(false, Ordering::Release);
+            reply.extend(b"OK");
+            return Some(if panicked {
+                Resume::Exit
+            } else {
+                Resume::Continue
+            });
+        }
+        b'k' => platform::system_shutdown(),
+        _ => {}
+    }
+    None
+}
+
+/// 处理 `q` 开头的查询数据包
+fn handle_query(
+    args: &[u8],
+    harts: &[Option<Registers>; CPU_NUM],
+    master: usize,
+    reply: &mut Vec<u8>,
+) {
+    if args.starts_with(b"Supported") {
+        reply.extend(b"PacketSize=1000;qXfer:features:read+");
+    } else if args == b"Attached" {
+        reply.extend(b"1");
+    } else if args == b"C" {
+        reply.extend(format!("QC{:x}", master + 1).into_bytes());
+    } else if args == b"fThreadInfo" {
+        reply.push(b'm');
+        let threads = (0..CPU_NUM)
+            .filter(|hart| harts[*hart].is_some())
+            .map(|hart| format!("{:x}", hart + 1))
+            .collect::<Vec<_>>()
+            .join(",");
+        reply.extend(threads.into_bytes());
+    } else if args == b"sThreadInfo" {
+        reply.push(b'l');
+    } else if let Some(tid) = args.strip_prefix(b"ThreadExtraInfo,") {
+        match parse_thread(tid) {
+            Some(Some(hart)) => encode_hex(reply, format!("hart {}", hart).as_bytes()),
+            _ => reply.extend(b"E01"),
+        }
+    } else if let Some(range) = args.strip_prefix(b"Xfer:features:read:target.xml:") {
+        match parse_addr_len(range) {
+            Some((offset, len)) => {
+                let xml = TARGET_XML.as_bytes();
+                let start = offset.min(xml.len());
+                let end = start.saturating_add(len).min(xml.len());
+                reply.push(if end == xml.len() { b'l' } else { b'm' });
+                reply.extend(&xml[start..end]);
+            }
+            None => reply.extend(b"E01"),
+        }
+    }
+}
@@ -0,0 +1,150 @@
+//! GDB 远程串行协议的数据包收发
+//!
+//! 数据包的格式为 `$<data>#<checksum>`，校验和是 `data` 中所有字节之和的低 8 位，以两位十六进制数表示。
+//! 收到数据包后回复 `+` 确认，校验和错误时回复 `-` 要求重传。`data` 中的 `#`、`$`、`}` 和 `*` 需要转义为
+//! `}` 加上原字节异或 0x20。
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+use core::hint::spin_loop;
+use drivers::uart::LowUartDriver;
+
+/// 目标运行时 GDB 发送的中断字符(Ctrl-C)
+pub const INTERRUPT: u8 = 0x03;
+
+const HEX: &[u8; 16] = b"0123456789abcdef";
+
+/// 一个 GDB 连接，以轮询方式访问串口
+pub struct Connection<'a> {
+    uart: &'a mut dyn LowUartDriver,
+    /// 之前已经从串口读出的字节，先于串口中的数据被读取
+    pending: VecDeque<u8>,
+}
+
+impl<'a> Connection<'a> {
+    pub fn new(uart: &'a mut dyn LowUartDriver, pending: VecDeque<u8>) -> Self {
+        Self { uart, pending }
+    }
+
+    fn get_byte(&mut self) -> u8 {
+        if let Some(c) = self.pending.pop_front() {
+            return c;
+        }
+        loop {
+            if let Some(c) = self.uart._read() {
+                return c;
+            }
+            spin_loop();
+        }
+    }
+
+    fn put_byte(&mut self, c: u8) {
+        self.uart._put(c);
+    }
+
+    /// 接收一个数据包，返回转义还原后的内容。数据包之外的字节(如 Ctrl-C)被忽略
+    pub fn recv_packet(&mut self) -> Vec<u8> {
+        loop {
+            while self.get_byte() != b'$' {}
+            let mut data = Vec::new();
+            let mut sum = 0u8;
+            loop {
+                let c = self.get_byte();
+                match c {
+                    b'#' => break,
+                    // 上一个数据包不完整，从新的数据包重新开始
+                    b'$' => {
+                        data.clear();
+                        sum = 0;
+                    }
+                    _ => {
+                        sum = sum.wrapping_add(c);
+                        data.push(c);
+                    }
+                }
+            }
+            let checksum = [self.get_byte(), self.get_byte()];
+            if decode_hex(&checksum).map_or(false, |checksum| checksum[0] == sum) {
+                self.put_byte(b'+');
+                return unescape(data);
+            }
+            self.put_byte(b'-');
+        }
+    }
+
+    /// 发送一个数据包，直到 GDB 确认收到
+    pub fn send_packet(&mut self, data: &[u8]) {
+        loop {
+            self.put_byte(b'$');
+            let mut sum = 0u8;
+            for &c in data {
+                if matches!(c, b'#' | b'$' | b'}' | b'*') {
+                    self.put_byte(b'}');
+                    self.put_byte(c ^ 0x20);
+                    sum = sum.wrapping_add(b'}').wrapping_add(c ^ 0x20);
+                } else {
+                    self.put_byte(c);
+                    sum = sum.wrapping_add(c);
+                }
+            }
+            self.put_byte(b'#');
+            self.put_byte(HEX[(sum >> 4) as usize]);
+            self.put_byte(HEX[(sum & 0xf) as usize]);
+            loop {
+                match self.get_byte() {
+                    b'+' => return,
+                    b'-' => break,
+                    _ => {}
+                }
+            }
+        }
+    }
+}
+
+fn unescape(data: Vec<u8>) -> Vec<u8> {
+    let mut res = Vec::with_capacity(data.len());
+    let mut iter = data.into_iter();
+    while let Some(c) = iter.next() {
+        if c == b'}' {
+            res.extend(iter.next().map(|c| c ^ 0x20));
+        } else {
+            res.push(c);
+        }
+    }
+    res
+}
+
+fn hex_value(c: u8) -> Option<u8> {
+    match c {
+        b'0'..=b'9' => Some(c - b'0'),
+        b'a'..=b'f' => Some(c - b'a' + 10),
+        b'A'..=b'F' => Some(c - b'A' + 10),
+        _ => None,
+    }
+}
+
+/// 将十六进制字符串解码为字节序列
+pub fn decode_hex(hex: &[u8]) -> Option<Vec<u8>> {
+    if hex.len() % 2 != 0 {
+        return None;
+    }
+    hex.chunks(2)
+        .map(|pair| Some(hex_value(pair[0])? << 4 | hex_value(pair[1])?))
+        .collect()
+}
+
+/// 将字节序列编码为十六进制字符串，追加到 `out` 中
+pub fn encode_hex(out: &mut Vec<u8>, bytes: &[u8]) {
+    for &byte in bytes {
+        out.push(HEX[(byte >> 4) as usize]);
+        out.push(HEX[(byte & 0xf) as usize]);
+    }
+}
+
+/// 解析一个十六进制数
+pub fn parse_hex(hex: &[u8]) -> Option<usize> {
+    if hex.is_empty() || hex.len() > 16 {
+        return None;
+    }
+    hex.iter()
+        .try_fold(0usize, |acc, &c| Some(acc << 4 | hex_value(c)? as usize))
+}
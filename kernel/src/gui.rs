@@ -1,5 +1,7 @@
 //! GUI 相关的系统调用
 use crate::task::current_task;
+use alloc::vec::Vec;
+use constants::AlienResult;
 use page_table::addr::{align_up_4k, PhysAddr, VirtAddr};
 
 use devices::{GPU_DEVICE, KEYBOARD_INPUT_DEVICE, MOUSE_INPUT_DEVICE};
@@ -40,24 +42,18 @@ pub fn sys_framebuffer_flush() -> isize {
 /// 函数将返回成功获取到的事件个数。
 ///
 #[syscall_func(2002)]
-pub fn sys_event_get(event_buf: *mut u64, len: usize) -> isize {
+pub fn sys_event_get(event_buf: *mut u64, len: usize) -> AlienResult<isize> {
     let task = current_task().unwrap();
-    let user_buffer = task.transfer_buffer(event_buf, len);
-    let mut count = 0;
-    for buf in user_buffer {
-        let mut index = 0;
-        let len = buf.len();
-        while index < len {
-            let event = read_event();
-            if event == 0 {
-                break;
-            }
-            buf[index] = event;
-            index += 1;
-            count += 1;
+    let mut events = Vec::new();
+    while events.len() < len {
+        let event = read_event();
+        if event == 0 {
+            break;
         }
+        events.push(event);
     }
-    count
+    task.copy_to_user_buffer(&events, event_buf)?;
+    Ok(events.len() as isize)
 }
 
 fn read_event() -> u64 {
@@ -12,7 +12,7 @@ use spin::Lazy;
 use crate::fs::basic::sys_close;
 use crate::ipc::futex::{FutexWaitManager, FutexWaiter};
 use crate::task::schedule::schedule;
-use crate::task::{current_task, TaskState};
+use crate::task::{current_task, find_task};
 use constants::ipc::{FutexOp, RobustList};
 use constants::AlienResult;
 use constants::LinuxErrno;
@@ -52,12 +52,17 @@ pub fn sys_pipe(pipe: *mut u32, _flag: u32) -> AlienResult<isize> {
         return Err(LinuxErrno::EINVAL);
     }
     let process = current_task().unwrap();
-    let fd_pair = process.transfer_raw_ptr(pipe as *mut FdPair);
     let (read, write) = make_pipe_file()?;
     let read_fd = process.add_file(read).map_err(|_| LinuxErrno::EMFILE)?;
     let write_fd = process.add_file(write).map_err(|_| LinuxErrno::EMFILE)?;
-    fd_pair.fd[0] = read_fd as u32;
-    fd_pair.fd[1] = write_fd as u32;
+    let fd_pair = FdPair {
+        fd: [read_fd as u32, write_fd as u32],
+    };
+    if let Err(e) = process.copy_to_user(&fd_pair, pipe as *mut FdPair) {
+        let _ = process.remove_file(read_fd);
+        let _ = process.remove_file(write_fd);
+        return Err(e);
+    }
     Ok(0)
 }
 
@@ -135,16 +140,21 @@ pub fn futex(
     val3: u32,
 ) -> isize {
     *FCOUNT.lock() += 1;
-    let futex_op = FutexOp::try_from(futex_op).unwrap();
+    let futex_op = match FutexOp::try_from(futex_op) {
+        Ok(futex_op) => futex_op,
+        Err(_) => return LinuxErrno::EINVAL as isize,
+    };
     let task = current_task().unwrap();
-    let task_inner = task.access_inner();
     warn!(
         "futex: {:?} {:?} {:?} {:?} {:?} {:?}",
         uaddr, futex_op, val, val2, uaddr2, val3
     );
     match futex_op {
         FutexOp::FutexWaitPrivate | FutexOp::FutexWait => {
-            let uaddr_ref = task_inner.transfer_raw_ptr_mut(uaddr as *mut i32);
+            let uaddr_ref = match task.transfer_raw_ptr(uaddr as *mut i32) {
+                Ok(uaddr_ref) => uaddr_ref,
+                Err(e) => return e as isize,
+            };
             let uaddr_atomic = AtomicI32::from_mut(uaddr_ref);
 
             if uaddr_atomic.load(Ordering::SeqCst) != val as i32 {
@@ -153,20 +163,22 @@ pub fn futex(
             }
             // we checkout the timeout
             let wait_time = if val2 != 0 {
-                let time_spec = task_inner.transfer_raw_ptr(val2 as *const TimeSpec);
+                let time_spec = match task.copy_from_user(val2 as *const TimeSpec) {
+                    Ok(time_spec) => time_spec,
+                    Err(e) => return e as isize,
+                };
                 Some(time_spec.to_clock() + TimeSpec::now().to_clock())
             } else {
                 // wait forever
                 None
             };
             // add to wait queue
-            drop(task_inner);
             warn!("Futex wait time: {:?}", wait_time);
             let timeout_flag = Arc::new(Mutex::new(false));
             let waiter = FutexWaiter::new(task.clone(), wait_time, timeout_flag.clone());
             FUTEX_WAITER.lock().add_waiter(uaddr, waiter);
             // switch to other task
-            task.update_state(TaskState::Waiting);
+            task.wait_interruptible();
             warn!("Because of futex, we switch to other task");
             schedule();
             // checkout the timeout flag
@@ -176,8 +188,11 @@ pub fn futex(
             }
         }
         FutexOp::FutexCmpRequeuePiPrivate => {
-            let uaddr_ref = task_inner.transfer_raw_ptr(uaddr as *const u32);
-            if *uaddr_ref != val3 {
+            let uaddr_val = match task.copy_from_user(uaddr as *const u32) {
+                Ok(uaddr_val) => uaddr_val,
+                Err(e) => return e as isize,
+            };
+            if uaddr_val != val3 {
                 error!("FutexRequeuePrivate: uaddr_ref != val");
                 return LinuxErrno::EAGAIN as isize;
             }
@@ -214,8 +229,8 @@ pub fn futex(
             return res.unwrap() as isize;
         }
         _ => {
-            panic!("futex: unimplemented futex_op: {:?}", futex_op);
-            // return LinuxErrno::EINVAL as isize;
+            warn!("futex: unimplemented futex_op: {:?}", futex_op);
+            return LinuxErrno::ENOSYS as isize;
         }
     }
     0
@@ -239,19 +254,20 @@ pub fn set_robust_list(head: usize, len: usize) -> isize {
 ///
 /// `pid` 指明了要获取相关信息的进程号；`head_ptr` 指明了获取信息后保存的位置；`len_ptr` 指明了获取列表长度信息后保存的位置。
 ///
-/// 当 `pid` 的值为 0 时，将会导致函数 panic；当函数正确执行时，返回 0。
+/// `pid` 为 0 时表示调用者自身，找不到对应的进程时返回 `ESRCH`；当函数正确执行时，返回 0。
 #[syscall_func(100)]
-pub fn get_robust_list(pid: usize, head_ptr: usize, len_ptr: usize) -> isize {
-    assert_eq!(pid, 0);
+pub fn get_robust_list(pid: usize, head_ptr: usize, len_ptr: usize) -> AlienResult<isize> {
     let task = current_task().unwrap();
-    let task_inner = task.access_inner();
-    let head = task_inner.robust.head;
+    let target = if pid == 0 {
+        task.clone()
+    } else {
+        find_task(pid).ok_or(LinuxErrno::ESRCH)?
+    };
+    let head = target.access_inner().robust.head;
     let len = RobustList::HEAD_SIZE;
-    let head_ref = task_inner.transfer_raw_ptr_mut(head_ptr as *mut usize);
-    let len_ref = task_inner.transfer_raw_ptr_mut(len_ptr as *mut usize);
-    *head_ref = head;
-    *len_ref = len;
-    0
+    task.copy_to_user(&head, head_ptr as *mut usize)?;
+    task.copy_to_user(&len, len_ptr as *mut usize)?;
+    Ok(0)
 }
 
 /// 唤醒所有当前正在等待 futex 但因为超时或者信号而需要被唤醒的进程
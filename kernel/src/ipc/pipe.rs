@@ -384,3 +384,58 @@ impl Drop for PipeFile {
         }
     }
 }
+
+#[cfg(feature = "test")]
+mod ktests {
+    use super::*;
+    use ktest::{kassert, kassert_eq, kernel_test, TestResult};
+
+    #[kernel_test]
+    fn test_pipe_ring_buffer_wrap() -> TestResult {
+        let inode = PipeInode::new();
+        let mut data = inode.data.lock();
+        // 环形缓冲区保留一个字节用于区分空和满
+        kassert_eq!(data.available_write(), PIPE_BUF - 1);
+        let chunk = [0x5a; 1000];
+        let mut out = [0; 1000];
+        data.head = PIPE_BUF - 10;
+        data.tail = PIPE_BUF - 10;
+        kassert_eq!(data.write(&chunk), 1000);
+        kassert!(data.tail < data.head);
+        kassert_eq!(data.available_read(), 1000);
+        kassert_eq!(data.read(&mut out), 1000);
+        kassert!(data.is_empty());
+        kassert_eq!(out, chunk);
+        Ok(())
+    }
+
+    #[kernel_test]
+    fn test_pipe_full() -> TestResult {
+        let inode = PipeInode::new();
+        let mut data = inode.data.lock();
+        let chunk = [1; 4096];
+        let mut written = 0;
+        while !data.is_full() {
+            written += data.write(&chunk);
+        }
+        kassert_eq!(written, PIPE_BUF - 1);
+        kassert_eq!(data.available_write(), 0);
+        kassert_eq!(data.write(&chunk), 0);
+        Ok(())
+    }
+
+    #[kernel_test]
+    fn test_pipe_read_after_writer_closed() -> TestResult {
+        let (reader, sender) = make_pipe_file()?;
+        kassert_eq!(sender.write(b"hello")?, 5);
+        let events = reader.poll(PollEvents::IN)?;
+        kassert!(events.contains(PollEvents::IN));
+        drop(sender);
+        let mut buf = [0; 16];
+        kassert_eq!(reader.read(&mut buf)?, 5);
+        kassert_eq!(&buf[..5], b"hello");
+        // 写端关闭后缓冲区为空时读到文件末尾，不会挂起
+        kassert_eq!(reader.read(&mut buf)?, 0);
+        Ok(())
+    }
+}
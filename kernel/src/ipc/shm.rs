@@ -184,7 +184,7 @@ pub fn shmat(shmid: usize, shmaddr: usize, shmflg: u32) -> AlienResult<isize> {
     assert_eq!(shmaddr, 0);
     // we must find a place to map
     let task = current_task().unwrap();
-    let free_map = task.access_inner().mmap.alloc(shm.len());
+    let free_map = task.access_inner().mmap.lock().alloc(shm.len());
     // map to va
     error!("shm map range:{:#x?}", free_map);
     shm.access_inner().state = ShmMemoryState::Used;
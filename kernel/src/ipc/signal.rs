@@ -4,6 +4,7 @@
 //! 有关 Alien 中信号的具体处理流程可见 [`signal_handler`]。
 use alloc::collections::BTreeMap;
 use alloc::sync::Arc;
+use alloc::vec;
 use alloc::vec::Vec;
 use core::mem::size_of;
 
@@ -11,11 +12,15 @@ use constants::signal::{
     SigAction, SigActionDefault, SigActionFlags, SigInfo, SigProcMaskHow, SignalNumber,
     SignalReceivers, SignalUserContext, SimpleBitSet,
 };
-use constants::LinuxErrno;
+use constants::{AlienResult, LinuxErrno};
 use ksync::Mutex;
 use syscall_table::syscall_func;
 
-use crate::task::{current_task, do_exit, do_suspend};
+use crate::task::{
+    coredump_wait, current_task, do_coredump, do_exit_by_signal, do_suspend, is_core_signal,
+    notify_parent, process_group, ptrace_check_interrupt, ptrace_signal_stop, wake_child_waiters,
+    wake_stopped_tracees, JobEvent, Task,
+};
 use timer::{read_timer, TimeSpec};
 
 /// 记录每个线程的信号量，从 tid 获取信号相关信息
@@ -43,6 +48,12 @@ pub fn send_signal(tid: usize, signum: usize) {
         // 获取目标线程(可以是自己)的 signals 数组
         warn!("send signal {:?} to {}", SignalNumber::from(signum), tid);
         signals.lock().try_add_bit(signum);
+        // 唤醒在 wait 中等待的任务，使其重新检查子进程的状态或者因为信号而返回
+        wake_child_waiters(tid);
+        // 处于跟踪停止的任务只会被 SIGKILL 唤醒
+        if signum == SignalNumber::SIGKILL as usize {
+            wake_stopped_tracees(tid);
+        }
     }
 }
 
@@ -58,7 +69,7 @@ pub fn send_signal(tid: usize, signum: usize) {
 ///
 /// 函数执行成功后返回 0；若输入的 `sig` 是 `SIGSTOP`, `SIGKILL`, `ERR`中的一个时，将导致函数返回 `EINVAL`。
 #[syscall_func(134)]
-pub fn sigaction(sig: usize, action: usize, old_action: usize) -> isize {
+pub fn sigaction(sig: usize, action: usize, old_action: usize) -> AlienResult<isize> {
     let action = action as *const SigAction;
     let old_action = old_action as *mut SigAction;
     // check whether sig is valid
@@ -67,24 +78,26 @@ pub fn sigaction(sig: usize, action: usize, old_action: usize) -> isize {
         || signum == SignalNumber::SIGKILL
         || signum == SignalNumber::ERR
     {
-        return LinuxErrno::EINVAL as isize;
+        return Err(LinuxErrno::EINVAL);
     }
     let task = current_task().unwrap();
-    let mut task_inner = task.access_inner();
-    let signal_handler = task_inner.signal_handlers.clone();
-    let mut signal_handler = signal_handler.lock();
+    let signal_handler = task.access_inner().signal_handlers.clone();
+    // 先读入新的处理方式，避免写入旧的处理方式失败时已经修改了信号的处理动作
+    let new_action = if !action.is_null() {
+        Some(task.copy_from_user(action)?)
+    } else {
+        None
+    };
     if !old_action.is_null() {
         let mut tmp = SigAction::empty();
-        signal_handler.get_action(sig, &mut tmp);
-        task_inner.copy_to_user(&tmp, old_action);
+        signal_handler.lock().get_action(sig, &mut tmp);
+        task.copy_to_user(&tmp, old_action)?;
     }
-    if !action.is_null() {
-        let mut tmp_action = SigAction::empty();
-        task_inner.copy_from_user(action, &mut tmp_action);
-        warn!("sig {:?} action is {:?}", signum, tmp_action);
-        signal_handler.set_action(sig, &tmp_action);
+    if let Some(new_action) = new_action {
+        warn!("sig {:?} action is {:?}", signum, new_action);
+        signal_handler.lock().set_action(sig, &new_action);
     }
-    0
+    Ok(0)
 }
 
 /// 一个系统调用，用于使得一个进程在一段时间限制内等待一个信号，并保存信号的相关信息。
@@ -100,7 +113,7 @@ pub fn sigaction(sig: usize, action: usize, old_action: usize) -> isize {
 ///
 /// Reference: [sigtimedwait](https://linux.die.net/man/2/sigtimedwait)
 #[syscall_func(137)]
-pub fn sigtimewait(set: usize, info: usize, time: usize) -> isize {
+pub fn sigtimewait(set: usize, info: usize, time: usize) -> AlienResult<isize> {
     warn!(
         "sigtimewait: set: {:x}, info: {:x}, time: {:x}",
         set, info, time
@@ -110,11 +123,9 @@ pub fn sigtimewait(set: usize, info: usize, time: usize) -> isize {
     let mut target_time = 0;
 
     let task = current_task().unwrap().clone();
-    let mut time_spec = TimeSpec::new(0, 0);
-    task.access_inner()
-        .copy_from_user(time as *const TimeSpec, &mut time_spec);
+    let time_spec = task.copy_from_user(time as *const TimeSpec)?;
     loop {
-        let mut task_inner = task.access_inner();
+        let task_inner = task.access_inner();
         let mut signal_receivers = task_inner.signal_receivers.lock();
         for i in 1..64 {
             if set & (1 << i) != 0 {
@@ -124,16 +135,17 @@ pub fn sigtimewait(set: usize, info: usize, time: usize) -> isize {
                         tmp_info.si_signo = i as i32;
                         tmp_info.si_code = 0;
                         drop(signal_receivers);
-                        task_inner.copy_to_user(&tmp_info, info as *mut SigInfo);
+                        drop(task_inner);
+                        task.copy_to_user(&tmp_info, info as *mut SigInfo)?;
                     }
-                    return i as isize;
+                    return Ok(i as isize);
                 }
             }
         }
 
         // wait time
         if time_spec.tv_sec == 0 && time_spec.tv_nsec == 0 {
-            return -1;
+            return Ok(-1);
         }
         drop(signal_receivers);
         drop(task_inner);
@@ -156,10 +168,10 @@ pub fn sigtimewait(set: usize, info: usize, time: usize) -> isize {
         let receiver = task_inner.signal_receivers.lock();
         if receiver.have_signal() {
             let sig = receiver.have_signal_with_number().unwrap();
-            return sig as isize;
+            return Ok(sig as isize);
         }
     }
-    LinuxErrno::EAGAIN.into()
+    Err(LinuxErrno::EAGAIN)
 }
 
 /// 一个系统调用，用于获取和设置信号的屏蔽位。通过 `sigprocmask`，进程可以方便的屏蔽某些信号。
@@ -174,36 +186,45 @@ pub fn sigtimewait(set: usize, info: usize, time: usize) -> isize {
 ///
 /// Reference: [sigprocmask](https://www.man7.org/linux/man-pages/man2/sigprocmask.2.html)
 #[syscall_func(135)]
-pub fn sigprocmask(how: usize, set: usize, oldset: usize, _sig_set_size: usize) -> isize {
+pub fn sigprocmask(
+    how: usize,
+    set: usize,
+    oldset: usize,
+    _sig_set_size: usize,
+) -> AlienResult<isize> {
     let task = current_task().unwrap();
-    let task_inner = task.access_inner();
-    let mut signal_receivers = task_inner.signal_receivers.lock();
+    let new_set = if set != 0 {
+        Some(task.copy_from_user(set as *const usize)?)
+    } else {
+        None
+    };
+    let signal_receivers = task.access_inner().signal_receivers.clone();
     if oldset != 0 {
-        let set_mut = task_inner.transfer_raw_ptr_mut(oldset as *mut usize);
-        *set_mut = signal_receivers.mask.bits();
+        let old_mask = signal_receivers.lock().mask.bits();
+        task.copy_to_user(&old_mask, oldset as *mut usize)?;
     }
     let how = SigProcMaskHow::from(how);
     warn!("sigprocmask: how: {:?}, set: {:x}", how, set);
-    if set != 0 {
-        let set = task_inner.transfer_raw_ptr(set as *const usize);
+    let mut signal_receivers = signal_receivers.lock();
+    if let Some(set) = new_set {
         match how {
             SigProcMaskHow::SigBlock => {
-                signal_receivers.mask += SimpleBitSet::from(*set);
+                signal_receivers.mask += SimpleBitSet::from(set);
             }
             SigProcMaskHow::SigUnblock => {
-                signal_receivers.mask -= SimpleBitSet::from(*set);
+                signal_receivers.mask -= SimpleBitSet::from(set);
             }
             SigProcMaskHow::SigSetMask => {
-                signal_receivers.mask = SimpleBitSet::from(*set);
+                signal_receivers.mask = SimpleBitSet::from(set);
             }
             SigProcMaskHow::Unknown => {
-                return LinuxErrno::EINVAL as isize;
+                return Err(LinuxErrno::EINVAL);
             }
         }
     }
     let mask: Vec<SignalNumber> = signal_receivers.mask.into();
     trace!("after sigprocmask: {:?}", mask);
-    0
+    Ok(0)
 }
 
 /// 一个系统调用函数，向 `pid` 指定的进程发送信号。
@@ -213,9 +234,10 @@ pub fn sigprocmask(how: usize, set: usize, oldset: usize, _sig_set_size: usize)
 /// 1. pid > 0，则发送给指定进程
 /// 2. pid = 0，则发送给所有同组进程
 /// 3. pid = -1，则发送给除了初始进程(pid=1)外的所有当前进程有权限的进程
-/// 4. pid < -2，则发送给组内 pid 为参数相反数的进程
+/// 4. pid < -1，则发送给进程组号为参数相反数的所有进程
 ///
-/// 目前 2/3/4 未实现。对于 1，仿照 zCore 的设置，认为**当前进程自己或其直接子进程** 是"有权限"或者"同组"的进程。
+/// 目前 3 未实现。对于 1，仿照 zCore 的设置，认为**当前进程自己或其直接子进程** 是"有权限"或者"同组"的进程。
+/// 进程组中没有进程时返回 `ESRCH`。
 ///  
 /// 目前如果函数成功执行后会返回0；否则返回错误类型。
 ///
@@ -223,18 +245,33 @@ pub fn sigprocmask(how: usize, set: usize, oldset: usize, _sig_set_size: usize)
 #[syscall_func(129)]
 pub fn kill(pid: usize, sig: usize) -> isize {
     warn!("kill pid {}, signal id {:?}", pid, SignalNumber::from(sig));
-    if pid > 0 {
-        //println!("kill pid {}, signal id {}", pid, signal_id);
-        if sig > 0 {
-            send_signal(pid, sig);
+    let pgid = current_task().unwrap().access_inner().pgid;
+    match kill_targets(pid as isize, pgid) {
+        Ok(targets) => {
+            if sig > 0 {
+                targets.into_iter().for_each(|pid| send_signal(pid, sig));
+            }
+            0
         }
-        0
-    } else if pid == 0 {
-        LinuxErrno::ESRCH as isize
-    } else {
+        Err(e) => e as isize,
+    }
+}
+
+/// 找到 `kill` 的 `pid` 参数对应的接收信号的进程，`pgid` 为调用者所在的进程组
+fn kill_targets(pid: isize, pgid: usize) -> AlienResult<Vec<usize>> {
+    if pid > 0 {
+        return Ok(vec![pid as usize]);
+    }
+    if pid == -1 {
         // 如果 signal_id == 0，则仅为了检查是否存在对应进程，此时应该返回参数错误。是的，用户库是会刻意触发这个错误的
-        LinuxErrno::EINVAL as isize
+        return Err(LinuxErrno::EINVAL);
     }
+    let pgid = if pid == 0 { pgid } else { pid.unsigned_abs() };
+    let group = process_group(pgid);
+    if group.is_empty() {
+        return Err(LinuxErrno::ESRCH);
+    }
+    Ok(group)
 }
 
 /// 一个系统调用函数，向 `tid` 指定的线程发送信号。在`Alien`中`tid`是task的唯一标识，故 `tid` 只会指向一个线程。
@@ -284,14 +321,25 @@ pub fn signal_return() -> isize {
 ///
 /// 待用户态下的信号处理函数执行完毕后进程将重新陷入内核态，调用 [`signal_return`] 重新装载回原 trap 上下文。
 /// 至此，一个信号被处理完毕。
+///
+/// 被跟踪的进程在处理信号前先进入跟踪停止，实际处理的信号由跟踪者决定。
 pub fn signal_handler() {
     let task = current_task().unwrap();
+    coredump_wait(task);
+    ptrace_check_interrupt(task);
+    let signum = task
+        .access_inner()
+        .signal_receivers
+        .clone()
+        .lock()
+        .get_one_signal();
+    let signum = signum.and_then(|signum| ptrace_signal_stop(task, signum));
     let mut task_inner = task.access_inner();
     let receiver = task_inner.signal_receivers.clone();
-    let mut receiver = receiver.lock();
+    let receiver = receiver.lock();
     let handler = task_inner.signal_handlers.clone();
     let handler = handler.lock();
-    if let Some(signum) = receiver.get_one_signal() {
+    if let Some(signum) = signum {
         let sig = SignalNumber::from(signum);
         error!("task {:?} receive signal {:?}", task.tid, sig);
         match sig {
@@ -301,7 +349,8 @@ pub fn signal_handler() {
                 drop(handler);
                 drop(receiver);
                 warn!("task {:?} exit by signal {:?}", task.tid, sig);
-                do_exit(-1);
+                let dumped = do_coredump(task, signum);
+                do_exit_by_signal(signum, dumped);
             }
             _ => {
                 if let Some(action) = handler.get_action_ref(signum) {
@@ -338,25 +387,39 @@ pub fn signal_handler() {
                     if action.flags.contains(SigActionFlags::SA_SIGINFO) {
                         task_inner.signal_set_siginfo = true;
                         // 如果带 SIGINFO，则需要在用户栈上放额外的信息
-                        sp = (sp - size_of::<SigInfo>()) & !0xf;
-                        info!("add siginfo at {:x}", sp);
+                        let info_sp = (sp - size_of::<SigInfo>()) & !0xf;
+                        let ucontext_sp = (info_sp - size_of::<SignalUserContext>()) & !0xf;
                         let mut info = SigInfo::default();
                         info.si_signo = signum as i32;
-                        unsafe {
-                            let phy_sp = task_inner.transfer_raw(sp);
-                            *(phy_sp as *mut SigInfo) = info;
+                        // 信号发生时的 pc、通用寄存器与浮点寄存器
+                        let mut ucontext =
+                            SignalUserContext::init(receiver.mask.bits() as u64, old_pc);
+                        let mut old_trap_frame = task_inner.trap_cx_before_signal.unwrap();
+                        ucontext.mcontext.gregs[1..].copy_from_slice(&old_trap_frame.regs()[1..32]);
+                        let (f, fcsr) = task_inner.fp_context.registers();
+                        ucontext.mcontext.fpregs.f = f;
+                        ucontext.mcontext.fpregs.fcsr = fcsr as u32;
+                        info!(
+                            "add siginfo at {:x}, ucontext at {:x}",
+                            info_sp, ucontext_sp
+                        );
+                        let frame = task_inner
+                            .write_user(&info, info_sp)
+                            .and_then(|_| task_inner.write_user(&ucontext, ucontext_sp));
+                        if frame.is_err() {
+                            // 用户栈不可写，无法放置信号栈帧，与 SIGSEGV 一样直接退出
+                            warn!("task {:?} bad signal frame at {:#x}", task.tid, sp);
+                            drop(task_inner);
+                            drop(handler);
+                            drop(receiver);
+                            do_exit_by_signal(SignalNumber::SIGSEGV as usize, false);
+                            return;
                         }
                         // a1 = &siginfo
-                        trap_contex.regs()[11] = sp;
-                        sp = (sp - size_of::<SignalUserContext>()) & !0xf;
-                        info!("add ucontext at {:x}", sp);
-                        unsafe {
-                            let phy_sp = task_inner.transfer_raw(sp);
-                            *(phy_sp as *mut SignalUserContext) =
-                                SignalUserContext::init(receiver.mask.bits() as u64, old_pc);
-                        }
+                        trap_contex.regs()[11] = info_sp;
                         // a2 = &ucontext
-                        trap_contex.regs()[12] = sp;
+                        trap_contex.regs()[12] = ucontext_sp;
+                        sp = ucontext_sp;
                     }
                     // set sp
                     trap_contex.regs()[2] = sp;
@@ -370,13 +433,31 @@ pub fn signal_handler() {
                 } else {
                     // find the default handler
                     // 否则，查找默认处理方式
+                    if matches!(
+                        sig,
+                        SignalNumber::SIGSTOP
+                            | SignalNumber::SIGTSTP
+                            | SignalNumber::SIGTTIN
+                            | SignalNumber::SIGTTOU
+                    ) {
+                        drop(task_inner);
+                        drop(handler);
+                        drop(receiver);
+                        stop_task(task, signum);
+                        return;
+                    }
+                    if matches!(sig, SignalNumber::SIGCONT) {
+                        // 未暂停的任务收到 SIGCONT 时忽略该信号
+                        return;
+                    }
                     match SigActionDefault::of_signal(sig) {
                         SigActionDefault::Terminate => {
                             // 这里不需要 drop(task)，因为当前函数没有用到 task_inner，在 task.save_trap... 内部用过后已经 drop 了
                             drop(task_inner);
                             drop(handler);
                             drop(receiver);
-                            do_exit(-1);
+                            let dumped = is_core_signal(sig) && do_coredump(task, signum);
+                            do_exit_by_signal(signum, dumped);
                         }
                         SigActionDefault::Ignore => {
                             // 忽略信号时，要将已保存的上下文删除
@@ -389,6 +470,32 @@ pub fn signal_handler() {
     }
 }
 
+/// 以默认方式处理作业控制信号：暂停当前任务并通知父进程，直到收到 SIGCONT 后恢复运行，或者收到 SIGKILL 后退出。
+fn stop_task(task: &Arc<Task>, signum: usize) {
+    warn!(
+        "task {:?} stopped by signal {:?}",
+        task.tid,
+        SignalNumber::from(signum)
+    );
+    task.access_inner().job_event = Some(JobEvent::Stopped(signum));
+    notify_parent(task);
+    loop {
+        do_suspend();
+        let receiver = task.access_inner().signal_receivers.clone();
+        let mut receiver = receiver.lock();
+        if receiver.check_signal(SignalNumber::SIGKILL as usize) {
+            drop(receiver);
+            do_exit_by_signal(SignalNumber::SIGKILL as usize, false);
+            return;
+        }
+        if receiver.check_signal(SignalNumber::SIGCONT as usize) {
+            break;
+        }
+    }
+    task.access_inner().job_event = Some(JobEvent::Continued);
+    notify_parent(task);
+}
+
 /// 一个系统调用函数，用于阻塞当前进程，等待其他进程传入信号打断阻塞。当进程接收到某种信号时，终止阻塞，函数返回 `EINTR`。
 #[syscall_func(133)]
 pub fn sigsuspend() -> isize {
@@ -403,3 +510,28 @@ pub fn sigsuspend() -> isize {
         }
     }
 }
+
+#[cfg(feature = "test")]
+mod ktests {
+    use super::*;
+    use crate::task::INIT_PROCESS;
+    use ktest::{kassert_eq, kernel_test, TestResult};
+
+    /// 测试时临时使用的进程组号
+    const TEST_PGID: usize = 0x7fff_0000;
+
+    #[kernel_test]
+    fn test_kill_process_group() -> TestResult {
+        let init = INIT_PROCESS.clone();
+        let old = core::mem::replace(&mut init.access_inner().pgid, TEST_PGID);
+        let own = kill_targets(0, TEST_PGID);
+        let group = kill_targets(-(TEST_PGID as isize), 0);
+        let missing = kill_targets(-(TEST_PGID as isize + 1), TEST_PGID);
+        init.access_inner().pgid = old;
+        kassert_eq!(own, Ok(vec![init.pid]));
+        kassert_eq!(group, Ok(vec![init.pid]));
+        kassert_eq!(missing, Err(LinuxErrno::ESRCH));
+        kassert_eq!(kill_targets(-1, TEST_PGID), Err(LinuxErrno::EINVAL));
+        Ok(())
+    }
+}
@@ -0,0 +1,163 @@
+//! 锁依赖检查的输出与统计
+//!
+//! `ksync` 打开 `lockdep` 时会检查 [`ksync::Mutex`] 的加锁顺序，这里注册报告的输出方式，
+//! 调用栈只在当前任务的内核栈或者当前核的启动栈中回溯，通过内核符号表解析。
+//! `/proc/lock_stat` 按等待时间从长到短列出各类锁的竞争情况，写入 `0` 清空统计。
+use crate::task::current_task;
+use alloc::format;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use arch::hart_id;
+use core::cmp::min;
+use core::fmt::Arguments;
+use core::ops::Range;
+use ksync::lockdep::{for_each_class, register_reporter, reset_stats, LockStat, Reporter};
+use platform::config::CLOCK_FREQ;
+use vfscore::error::VfsError;
+use vfscore::file::VfsFile;
+use vfscore::inode::{InodeAttr, VfsInode};
+use vfscore::superblock::VfsSuperBlock;
+use vfscore::utils::{VfsFileStat, VfsNodePerm, VfsNodeType};
+use vfscore::VfsResult;
+
+static REPORTER: Reporter = Reporter {
+    print: print_line,
+    print_stack: unwinder::print_stack,
+    stack_range,
+};
+
+fn print_line(args: Arguments) {
+    println!("{}", args);
+}
+
+/// 任务在内核中运行时使用自己的内核栈，调度器与启动过程使用启动栈
+fn stack_range(fp: usize) -> Range<usize> {
+    let boot = platform::boot_stack(hart_id());
+    match current_task() {
+        Some(task) if task.kernel_stack.range().contains(&fp) => task.kernel_stack.range(),
+        _ if boot.contains(&fp) => boot,
+        _ => 0..0,
+    }
+}
+
+/// 注册报告的输出方式，尽早调用以便启动过程中的错误也能被打印
+pub fn init_lockdep() {
+    register_reporter(&REPORTER);
+}
+
+fn ticks_to_us(ticks: usize) -> usize {
+    ticks * 1000_000 / CLOCK_FREQ
+}
+
+fn lock_stat() -> String {
+    let mut stats = Vec::<LockStat>::new();
+    for_each_class(|stat| stats.push(*stat));
+    stats.sort_by(|a, b| b.wait_time.cmp(&a.wait_time));
+    let mut content = format!(
+        "{:<60} {:>12} {:>12} {:>14} {:>12}\n",
+        "class", "acquisitions", "contentions", "wait-total(us)", "wait-max(us)"
+    );
+    for stat in stats {
+        content += &format!(
+            "{:<60} {:>12} {:>12} {:>14} {:>12}\n",
+            stat.name,
+            stat.acquisitions,
+            stat.contentions,
+            ticks_to_us(stat.wait_time),
+            ticks_to_us(stat.max_wait)
+        );
+    }
+    content
+}
+
+/// `/proc/lock_stat`
+struct LockStatFile;
+
+impl VfsFile for LockStatFile {
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> VfsResult<usize> {
+        let content = lock_stat();
+        let content = content.as_bytes();
+        if offset as usize >= content.len() {
+            return Ok(0);
+        }
+        let len = min(buf.len(), content.len() - offset as usize);
+        buf[..len].copy_from_slice(&content[offset as usize..offset as usize + len]);
+        Ok(len)
+    }
+    fn write_at(&self, _offset: u64, buf: &[u8]) -> VfsResult<usize> {
+        match core::str::from_utf8(buf).map(str::trim) {
+            Ok("0") => reset_stats(),
+            _ => return Err(VfsError::Invalid),
+        }
+        Ok(buf.len())
+    }
+}
+
+impl VfsInode for LockStatFile {
+    fn get_super_block(&self) -> VfsResult<Arc<dyn VfsSuperBlock>> {
+        Err(VfsError::NoSys)
+    }
+    fn node_perm(&self) -> VfsNodePerm {
+        VfsNodePerm::empty()
+    }
+    fn set_attr(&self, _attr: InodeAttr) -> VfsResult<()> {
+        Ok(())
+    }
+
+    fn get_attr(&self) -> VfsResult<VfsFileStat> {
+        Ok(VfsFileStat {
+            st_size: lock_stat().len() as u64,
+            ..Default::default()
+        })
+    }
+
+    fn inode_type(&self) -> VfsNodeType {
+        VfsNodeType::File
+    }
+}
+
+/// 在 `/proc` 中创建 `lock_stat` 文件，需要在文件系统初始化之后调用
+pub fn init_lock_stat_file() {
+    let root = vfs::proc::procfs_root().expect("procfs is not initialized");
+    root.add_file_manually("lock_stat", Arc::new(LockStatFile), "rw-r--r--".into())
+        .unwrap();
+}
+
+#[cfg(feature = "test")]
+mod ktests {
+    use super::*;
+    use core::any::type_name;
+    use ksync::lockdep::violations;
+    use ksync::Mutex;
+    use ktest::{kassert, kassert_eq, kernel_test, TestResult};
+
+    struct First;
+    struct Second;
+
+    fn acquisitions<T>() -> Option<usize> {
+        let mut count = None;
+        for_each_class(|stat| {
+            if stat.name == type_name::<T>() {
+                count = Some(stat.acquisitions);
+            }
+        });
+        count
+    }
+
+    #[kernel_test]
+    fn test_lock_stat() -> TestResult {
+        let (first, second) = (Mutex::new(First), Mutex::new(Second));
+        let before = violations();
+        for _ in 0..3 {
+            let _first = first.lock();
+            let _second = second.lock();
+        }
+        kassert!(second.try_lock().is_some());
+        kassert_eq!(violations(), before);
+        kassert!(acquisitions::<First>() >= Some(3));
+        kassert!(acquisitions::<Second>() >= Some(4));
+        kassert!(lock_stat().contains(type_name::<Second>()));
+        Ok(())
+    }
+}
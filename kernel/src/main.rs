@@ -13,18 +13,24 @@ extern crate alloc;
 extern crate unwinder;
 use alloc::boxed::Box;
 pub use syscall_table::*;
+mod crash;
 mod fs;
+mod gdbstub;
 mod gui;
 mod ipc;
+#[cfg(feature = "lockdep")]
+mod lockdep;
 mod mm;
 mod net;
 mod system;
 mod task;
 mod time;
+mod trace;
 mod trap;
 
 use crate::task::DriverTaskImpl;
 use core::hint::spin_loop;
+use core::panic::PanicInfo;
 use core::sync::atomic::{AtomicBool, Ordering};
 use platform::platform_machine_info;
 
@@ -38,18 +44,32 @@ fn main(hart_id: usize) {
         .is_ok()
     {
         println!("Boot hart {}", hart_id);
+        #[cfg(feature = "lockdep")]
+        lockdep::init_lockdep();
         let machine_info = platform_machine_info();
         println!("{:#?}", machine_info);
+        crash::reserve_dump_region(&machine_info.memory);
         mem::init_memory_system(machine_info.memory.end, true);
+        crash::init_crash_dump();
         interrupt::init_plic(machine_info.plic.start);
         shim::register_task_func(Box::new(DriverTaskImpl));
         devices::init_device();
         vfs::init_filesystem().expect("init filesystem failed");
+        trace::init_trace();
+        crash::init_crash_dump_file();
+        #[cfg(feature = "lockdep")]
+        lockdep::init_lock_stat_file();
+        task::init_core_pattern();
         trap::init_trap_subsystem();
+        gdbstub::init_gdbstub();
+        unwinder::register_panic_hook(panic_hook);
         arch::allow_access_user_memory();
         task::init_task();
+        task::init_watchdog();
         // register all syscall
         syscall_table::init_init_array!();
+        #[cfg(feature = "test")]
+        run_kernel_tests();
         STARTED.store(false, Ordering::Relaxed);
     } else {
         while STARTED.load(Ordering::Relaxed) {
@@ -64,3 +84,30 @@ fn main(hart_id: usize) {
     println!("Begin run task...");
     task::schedule::run_task();
 }
+
+/// 内核 panic 时先保存崩溃转储，再进入调试器，运行单元测试时还会报告当前测试失败。
+///
+/// 保存了转储时最后热重启，使下一次启动可以取出转储，否则回到 panic 处理中关机
+fn panic_hook(info: &PanicInfo) {
+    let saved = crash::save_crash_dump(info);
+    gdbstub::panic_hook(info);
+    #[cfg(feature = "test")]
+    ktest::panic_hook(info);
+    if saved {
+        println!("crashdump: warm reboot");
+        platform::system_reboot();
+    }
+}
+
+/// 命令行中带有 `ktest` 时运行内核单元测试，测试结束后直接关机
+#[cfg(feature = "test")]
+fn run_kernel_tests() {
+    let cmdline = platform::cmdline::kernel_cmdline();
+    if let Some(format) = cmdline.ktest {
+        let format = ktest::OutputFormat::from_name(format).unwrap_or_else(|| {
+            println!("Unknown ktest output format {}, use tap", format);
+            ktest::OutputFormat::Tap
+        });
+        ktest::run_tests(format, cmdline.ktest_filter);
+    }
+}
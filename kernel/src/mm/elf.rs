@@ -20,6 +20,8 @@ pub enum ELFError {
     NoEntrySegment,
     RelocationError,
     DynsymNotFind,
+    /// 动态链接程序的解释器不存在
+    InterpNotFound,
 }
 
 impl Debug for ELFInfo {
@@ -15,6 +15,11 @@ use page_table::pte::MappingFlags;
 use page_table::table::Sv39PageTable;
 use xmas_elf::program::{SegmentData, Type};
 
+/// 软浮点 musl 动态链接器的路径
+const SOFT_FLOAT_INTERP: &str = "/lib/ld-musl-riscv64-sf.so.1";
+/// 硬浮点 musl 动态链接器的路径
+const HARD_FLOAT_INTERP: &str = "/lib/ld-musl-riscv64.so.1";
+
 extern "C" {
     fn strampoline();
 }
@@ -199,19 +204,29 @@ pub fn build_elf_address_space(
             SegmentData::Undefined(data) => data,
             _ => return Err(ELFError::NoEntrySegment),
         };
-        let path = core::str::from_utf8(data).unwrap();
-        assert!(path.starts_with("/lib/ld-musl-riscv64-sf.so.1"));
-        let mut new_args = vec!["/libc.so\0".to_string()];
+        let path = core::str::from_utf8(data)
+            .map_err(|_| ELFError::NotSupported)?
+            .trim_end_matches('\0');
+        // 软浮点的解释器使用根目录下的 libc.so，硬浮点的解释器从其自身的路径加载
+        let (interp, arg0) = match path {
+            SOFT_FLOAT_INTERP => ("libc.so", "/libc.so\0"),
+            HARD_FLOAT_INTERP => (HARD_FLOAT_INTERP, "/lib/ld-musl-riscv64.so.1\0"),
+            _ => {
+                error!("[map_elf] Unsupported interpreter: {}", path);
+                return Err(ELFError::NotSupported);
+            }
+        };
+        let mut new_args = vec![arg0.to_string()];
         new_args.extend(args.clone());
         *args = new_args;
         // load interpreter
         let mut data = vec![];
         warn!("load interpreter: {}, new_args:{:?}", path, args);
-        if fs::read_all("libc.so", &mut data) {
-            return build_elf_address_space(&data, args, "libc.so");
+        if fs::read_all(interp, &mut data) {
+            return build_elf_address_space(&data, args, interp);
         } else {
-            error!("[map_elf] Found interpreter path: {}", path);
-            panic!("load interpreter failed");
+            error!("[map_elf] Interpreter not found: {}", path);
+            return Err(ELFError::InterpNotFound);
         }
     }
 
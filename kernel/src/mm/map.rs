@@ -1,162 +1,37 @@
 use alloc::sync::Arc;
-use alloc::vec::Vec;
-use core::ops::Range;
 
-use bitflags::bitflags;
-use page_table::addr::{align_up_4k, VirtAddr};
+use page_table::addr::VirtAddr;
 use page_table::pte::MappingFlags;
 
 use crate::task::current_task;
-use config::{FRAME_SIZE, PROCESS_HEAP_MAX};
 use constants::io::MapFlags;
+pub use constants::io::ProtFlags;
 use constants::AlienResult;
 use constants::LinuxErrno;
 use syscall_table::syscall_func;
 use vfs::kfile::File;
 
-bitflags! {
-    pub struct ProtFlags: u32 {
-        const PROT_NONE = 0x0;
-        const PROT_READ = 0x1;
-        const PROT_WRITE = 0x2;
-        const PROT_EXEC = 0x4;
+/// 将 `mmap`、`mprotect` 的保护位转换为用户态页表项的权限
+pub fn mapping_flags(prot: ProtFlags) -> MappingFlags {
+    let mut perm = MappingFlags::empty();
+    if prot.contains(ProtFlags::PROT_READ) {
+        perm |= MappingFlags::R;
     }
-}
-
-impl Into<MappingFlags> for ProtFlags {
-    fn into(self) -> MappingFlags {
-        let mut perm = MappingFlags::empty();
-        if self.contains(ProtFlags::PROT_READ) {
-            perm |= MappingFlags::R;
-        }
-        if self.contains(ProtFlags::PROT_WRITE) {
-            perm |= MappingFlags::W;
-        }
-        if self.contains(ProtFlags::PROT_EXEC) {
-            perm |= MappingFlags::X;
-        }
-        perm |= MappingFlags::U;
-        perm
+    if prot.contains(ProtFlags::PROT_WRITE) {
+        perm |= MappingFlags::W;
     }
-}
-
-#[derive(Debug, Clone)]
-/// The Process should manage the mmap info
-pub struct MMapInfo {
-    /// The start address of the mmap, it is a constant
-    map_start: usize,
-    /// The regions of the mmap
-    regions: Vec<MMapRegion>,
-}
-
-#[derive(Debug, Clone)]
-pub struct MMapRegion {
-    /// The start address of the mapping
-    pub start: usize,
-    /// The length of the mapping
-    pub len: usize,
-    pub map_len: usize,
-    /// The protection flags of the mapping
-    pub prot: ProtFlags,
-    /// The flags of the mapping
-    pub flags: MapFlags,
-    /// The file descriptor to map
-    pub fd: Option<Arc<dyn File>>,
-    /// The offset in the file to start from
-    pub offset: usize,
-}
-
-impl MMapInfo {
-    pub fn new() -> Self {
-        Self {
-            map_start: PROCESS_HEAP_MAX,
-            regions: Vec::new(),
-        }
-    }
-
-    pub fn alloc(&mut self, len: usize) -> Range<usize> {
-        let addr = self.map_start;
-        self.map_start += len;
-        // align to Frame size
-        self.map_start = (self.map_start + FRAME_SIZE - 1) & !(FRAME_SIZE - 1);
-        addr..self.map_start
-    }
-
-    pub fn add_region(&mut self, region: MMapRegion) {
-        self.regions.push(region);
-    }
-
-    pub fn get_region(&self, addr: usize) -> Option<&MMapRegion> {
-        for region in self.regions.iter() {
-            if region.start <= addr && addr < region.start + region.len {
-                return Some(region);
-            }
-        }
-        None
-    }
-
-    pub fn get_region_mut(&mut self, addr: usize) -> Option<&mut MMapRegion> {
-        for region in self.regions.iter_mut() {
-            if region.start <= addr && addr < region.start + region.len {
-                return Some(region);
-            }
-        }
-        None
-    }
-
-    pub fn remove_region(&mut self, addr: usize) {
-        let mut index = 0;
-        for region in self.regions.iter() {
-            if region.start <= addr && addr < region.start + region.len {
-                break;
-            }
-            index += 1;
-        }
-        self.regions.remove(index);
+    if prot.contains(ProtFlags::PROT_EXEC) {
+        perm |= MappingFlags::X;
     }
+    perm |= MappingFlags::U;
+    perm
 }
 
-impl MMapRegion {
-    pub fn new(
-        start: usize,
-        len: usize,
-        map_len: usize,
-        prot: ProtFlags,
-        flags: MapFlags,
-        fd: Option<Arc<dyn File>>,
-        offset: usize,
-    ) -> Self {
-        Self {
-            start,
-            len,
-            map_len,
-            prot,
-            flags,
-            fd,
-            offset,
-        }
-    }
-    // [a-b]
-    // [a-c] [c-b]
-    pub fn split(&self, addr: usize) -> (Self, Self) {
-        let mut region1 = self.clone();
-        let mut region2 = self.clone();
-        region1.len = addr - self.start;
-        region1.map_len = align_up_4k(region1.len);
-        region2.start = addr;
-        region2.len = self.start + self.len - addr;
-        region2.map_len = align_up_4k(region2.len);
-        region2.offset += region1.len;
-        (region1, region2)
-    }
-
-    pub fn set_prot(&mut self, prot: ProtFlags) {
-        self.prot = prot;
-    }
-    pub fn set_flags(&mut self, flags: MapFlags) {
-        self.flags = flags;
-    }
-}
+/// 进程的内存映射信息，映射的文件为 `Arc<dyn File>`。
+///
+/// 分配、查找、重叠与拆分的测试随实现一起放在 `mem::mmap` 中，由 `make host-test` 运行。
+pub type MMapInfo = mem::mmap::MMapInfo<Arc<dyn File>>;
+pub type MMapRegion = mem::mmap::MMapRegion<Arc<dyn File>>;
 
 /// 一个函数调用，用于消除内存映射。
 /// 注意：传入的`start`必须是某段内存映射的首地址，`len`必须是该段内存映射的长度，否则将导致函数返回`EINVAL`。函数正常执行将返回0。
@@ -9,7 +9,6 @@ use crate::net::addr::socket_addr_resolution;
 use crate::task::{current_task, do_suspend};
 use alloc::sync::Arc;
 use alloc::vec;
-use alloc::vec::Vec;
 use constants::io::OpenFlags;
 use constants::net::*;
 use constants::{AlienResult, LinuxErrno};
@@ -102,23 +101,55 @@ pub fn listening(socketfd: usize, backlog: usize) -> AlienResult<isize> {
 /// 执行成功则返回新的套接字的文件描述符，否则返回错误信息.
 #[syscall_func(202)]
 pub fn accept(socketfd: usize, socket_addr: usize, addr_len: usize) -> AlienResult<isize> {
+    do_accept(socketfd, socket_addr, addr_len, 0)
+}
+
+/// 一个系统调用，与 [`accept`] 相同，`flags` 可以包含 `SOCK_NONBLOCK` 与 `SOCK_CLOEXEC`，用于设置新套接字的选项。
+///
+/// 执行成功则返回新的套接字的文件描述符，`flags` 中含有其它位时返回 EINVAL。
+///
+/// Reference: [accept4](https://man7.org/linux/man-pages/man2/accept4.2.html)
+#[syscall_func(242)]
+pub fn accept4(
+    socketfd: usize,
+    socket_addr: usize,
+    addr_len: usize,
+    flags: usize,
+) -> AlienResult<isize> {
+    let valid = SocketType::SOCK_NONBLOCK as usize | SocketType::SOCK_CLOEXEC as usize;
+    if flags & !valid != 0 {
+        return Err(LinuxErrno::EINVAL);
+    }
+    do_accept(socketfd, socket_addr, addr_len, flags)
+}
+
+fn do_accept(
+    socketfd: usize,
+    socket_addr: usize,
+    addr_len: usize,
+    flags: usize,
+) -> AlienResult<isize> {
     let socket_fd = common_socket_syscall(socketfd)?;
     let socket = socket_fd.get_socketdata()?;
     match socket.accept() {
         Ok(file) => {
             let task = current_task().unwrap();
+            if flags & SocketType::SOCK_NONBLOCK as usize != 0 {
+                file.set_open_flag(file.get_open_flag() | OpenFlags::O_NONBLOCK);
+                file.get_socketdata()?.set_socket_nonblock(true);
+            }
+            if flags & SocketType::SOCK_CLOEXEC as usize != 0 {
+                file.set_close_on_exec();
+            }
             // get peer addr
             if socket_addr != 0 {
                 let socket = file.get_socketdata()?;
                 let peer_addr = socket.peer_addr().unwrap();
                 info!("accept peer addr: {:?}", peer_addr);
                 let raw_ip_addr = RawIpV4Addr::from(peer_addr);
-                let addr_len_ref = task
-                    .access_inner()
-                    .transfer_raw_ptr_mut(addr_len as *mut u32);
-                *addr_len_ref = core::mem::size_of::<RawIpV4Addr>() as u32;
-                task.access_inner()
-                    .copy_to_user(&raw_ip_addr, socket_addr as *mut RawIpV4Addr);
+                let len = core::mem::size_of::<RawIpV4Addr>() as u32;
+                task.copy_to_user(&len, addr_len as *mut u32)?;
+                task.copy_to_user(&raw_ip_addr, socket_addr as *mut RawIpV4Addr)?;
             }
             let fd = task.add_file(file).map_err(|_| LinuxErrno::EMFILE)?;
             Ok(fd as isize)
@@ -183,10 +214,9 @@ pub fn getsockname(socketfd: usize, socket_addr: usize, len: usize) -> AlienResu
     info!("getsockname: {:?}", local_addr);
     let raw_ip_addr = RawIpV4Addr::from(local_addr);
     let task = current_task().unwrap();
-    task.access_inner()
-        .copy_to_user(&raw_ip_addr, socket_addr as *mut RawIpV4Addr);
-    let len_ref = task.access_inner().transfer_raw_ptr_mut(len as *mut u32);
-    *len_ref = core::mem::size_of::<RawIpV4Addr>() as u32;
+    task.copy_to_user(&raw_ip_addr, socket_addr as *mut RawIpV4Addr)?;
+    let addr_len = core::mem::size_of::<RawIpV4Addr>() as u32;
+    task.copy_to_user(&addr_len, len as *mut u32)?;
     Ok(0)
 }
 
@@ -205,10 +235,9 @@ pub fn get_peer_name(socketfd: usize, sockaddr: usize, len: usize) -> AlienResul
     info!("get_peer_name: {:?}", socket_addr);
     let raw_ip_addr = RawIpV4Addr::from(socket_addr);
     let task = current_task().unwrap();
-    task.access_inner()
-        .copy_to_user(&raw_ip_addr, sockaddr as *mut RawIpV4Addr);
-    let len_ref = task.access_inner().transfer_raw_ptr_mut(len as *mut u32);
-    *len_ref = core::mem::size_of::<RawIpV4Addr>() as u32;
+    task.copy_to_user(&raw_ip_addr, sockaddr as *mut RawIpV4Addr)?;
+    let addr_len = core::mem::size_of::<RawIpV4Addr>() as u32;
+    task.copy_to_user(&addr_len, len as *mut u32)?;
     Ok(0)
 }
 
@@ -234,14 +263,9 @@ pub fn sendto(
     assert_eq!(flags, 0);
     let socket_fd = common_socket_syscall(socketfd)?;
     let task = current_task().unwrap();
-    let message = task.transfer_buffer(message, length);
-    // to vec<u8>
-    // todo!(don't need)
-    let message = message
-        .iter()
-        .map(|buf| buf.to_vec())
-        .flatten()
-        .collect::<Vec<u8>>();
+    let mut buf = vec![0u8; length];
+    task.copy_from_user_buffer(message, &mut buf)?;
+    let message = buf;
     let socket = socket_fd.get_socketdata()?;
     match socket.socket_type() {
         SocketType::SOCK_STREAM | SocketType::SOCK_SEQPACKET => {
@@ -298,16 +322,12 @@ pub fn recvfrom(
     let mut tmp_buffer = vec![0u8; length];
     let recv_info = socket.recvfrom(tmp_buffer.as_mut_slice(), flags)?;
     let task = current_task().unwrap();
-    task.access_inner()
-        .copy_to_user_buffer(tmp_buffer.as_ptr(), buffer, recv_info.0);
+    task.copy_to_user_buffer(&tmp_buffer[..recv_info.0], buffer)?;
     if src_addr != 0 {
         let raw_ip_addr = RawIpV4Addr::from(recv_info.1);
-        task.access_inner()
-            .copy_to_user(&raw_ip_addr, src_addr as *mut RawIpV4Addr);
-        let addr_len_ref = task
-            .access_inner()
-            .transfer_raw_ptr_mut(addr_len as *mut u32);
-        *addr_len_ref = core::mem::size_of::<RawIpV4Addr>() as u32;
+        task.copy_to_user(&raw_ip_addr, src_addr as *mut RawIpV4Addr)?;
+        let len = core::mem::size_of::<RawIpV4Addr>() as u32;
+        task.copy_to_user(&len, addr_len as *mut u32)?;
     }
     Ok(recv_info.0 as isize)
 }
@@ -375,26 +395,26 @@ pub fn getsockopt(
                 SocketOption::SO_RCVBUF => {
                     let opt_value_ref = current_task()
                         .unwrap()
-                        .transfer_raw_ptr(opt_value as *mut u32);
+                        .transfer_raw_ptr(opt_value as *mut u32)?;
                     *opt_value_ref = netcore::common::SOCKET_RECV_BUFFER_SIZE as u32;
                 }
                 SocketOption::SO_SNDBUF => {
                     let opt_value_ref = current_task()
                         .unwrap()
-                        .transfer_raw_ptr(opt_value as *mut u32);
+                        .transfer_raw_ptr(opt_value as *mut u32)?;
                     *opt_value_ref = netcore::common::SOCKET_SEND_BUFFER_SIZE as u32;
                 }
                 SocketOption::SO_ERROR => {
                     let opt_value_ref = current_task()
                         .unwrap()
-                        .transfer_raw_ptr(opt_value as *mut u32);
+                        .transfer_raw_ptr(opt_value as *mut u32)?;
                     *opt_value_ref = 0;
                 }
                 _ => {}
             }
             let opt_len_ref = current_task()
                 .unwrap()
-                .transfer_raw_ptr(opt_len as *mut u32);
+                .transfer_raw_ptr(opt_len as *mut u32)?;
             *opt_len_ref = core::mem::size_of::<u32>() as u32;
         }
         SocketLevel::Tcp => {
@@ -404,13 +424,13 @@ pub fn getsockopt(
                 TcpSocketOption::TCP_MAXSEG => {
                     let opt_value_ref = current_task()
                         .unwrap()
-                        .transfer_raw_ptr(opt_value as *mut u32);
+                        .transfer_raw_ptr(opt_value as *mut u32)?;
                     *opt_value_ref = netcore::common::MAX_SEGMENT_SIZE as u32;
                 }
                 TcpSocketOption::TCP_NODELAY => {
                     let opt_value_ref = current_task()
                         .unwrap()
-                        .transfer_raw_ptr(opt_value as *mut u32);
+                        .transfer_raw_ptr(opt_value as *mut u32)?;
                     *opt_value_ref = 0;
                 }
                 _ => {}
@@ -5,7 +5,7 @@ use core::cmp::min;
 use syscall_table::syscall_func;
 use timer::{get_time_ms, TimeFromFreq};
 
-use crate::task::current_task;
+use crate::task::{current_task, find_task};
 
 /// 记录系统信息的结构，包括操作系统名、在网络中的用户名、操作系统release和version版本、硬件类型、域名等信息。
 #[repr(C)]
@@ -54,11 +54,10 @@ fn system_info() -> Utsname {
 ///
 /// 函数成功执行后返回0。
 #[syscall_func(160)]
-pub fn uname(utsname: *const u8) -> isize {
+pub fn uname(utsname: *const u8) -> AlienResult<isize> {
     let task = current_task().unwrap();
-    task.access_inner()
-        .copy_to_user(&system_info(), utsname as *mut Utsname);
-    0
+    task.copy_to_user(&system_info(), utsname as *mut Utsname)?;
+    Ok(0)
 }
 
 const LOG_BUF_LEN: usize = 4096;
@@ -89,15 +88,12 @@ pub fn syslog(log_type: u32, buf: usize, len: usize) -> isize {
             let min_len = min(len, LOG_BUF_LEN);
             let task = current_task().unwrap();
             // the buf may be not valid, so we need to check it -- > sbrk heap
-            let mut buf = task.transfer_buffer(buf as *mut u8, min_len);
             let log = LOG.as_bytes();
-            let mut offset = 0;
-            buf.iter_mut().for_each(|buf| {
-                let copy_len = min(log.len() - offset, buf.len());
-                buf[..copy_len].copy_from_slice(&log[offset..offset + copy_len]);
-                offset += copy_len;
-            });
-            offset as isize
+            let copy_len = min(log.len(), min_len);
+            match task.copy_to_user_buffer(&log[..copy_len], buf as *mut u8) {
+                Ok(_) => copy_len as isize,
+                Err(e) => e as isize,
+            }
         }
         SyslogAction::Unknown => LinuxErrno::EINVAL as isize,
         _ => 0,
@@ -113,7 +109,7 @@ extern "C" {
 ///
 /// 目前功能还有待完善。正确执行后返回0。
 #[syscall_func(179)]
-pub fn sys_info(dst_info: usize) -> isize {
+pub fn sys_info(dst_info: usize) -> AlienResult<isize> {
     const LINUX_SYSINFO_LOADS_SCALE: usize = 65536;
     let task = current_task().unwrap();
     // calculate the task number
@@ -138,9 +134,8 @@ pub fn sys_info(dst_info: usize) -> isize {
         freehigh: 0,
         mem_unit: 1,
     };
-    task.access_inner()
-        .copy_to_user(&info, dst_info as *mut Sysinfo);
-    0
+    task.copy_to_user(&info, dst_info as *mut Sysinfo)?;
+    Ok(0)
 }
 
 /// (待实现)一个系统调用，设置进程调度的参数。目前直接返回0。
@@ -161,27 +156,32 @@ pub fn sched_setaffinity() -> isize {
     0
 }
 
-/// (待完善)一个系统调用，获取某进程对CPU的亲和力(位掩码)。当前进程的cpu亲和力将保存到`mask`所指向的位置。函数执行成功后返回8。
+/// (待完善)一个系统调用，获取某进程对CPU的亲和力(位掩码)。进程 `pid` 的cpu亲和力将保存到`mask`所指向的位置，
+/// `pid` 为 0 时表示当前进程，找不到对应的进程时返回 `ESRCH`。函数执行成功后返回8。
 #[syscall_func(123)]
-pub fn sched_getaffinity(pid: usize, size: usize, mask: usize) -> isize {
+pub fn sched_getaffinity(pid: usize, size: usize, mask: usize) -> AlienResult<isize> {
     warn!(
         "sched_getaffinity: pid: {}, size: {}, mask: {}",
         pid, size, mask
     );
-    assert_eq!(pid, 0);
     let task = current_task().unwrap();
-    let res = task.access_inner().cpu_affinity;
-    let mask = task.access_inner().transfer_raw_ptr_mut(mask as *mut usize);
-    *mask = res;
-    8
+    let target = if pid == 0 {
+        task.clone()
+    } else {
+        find_task(pid).ok_or(LinuxErrno::ESRCH)?
+    };
+    let res = target.access_inner().cpu_affinity;
+    task.copy_to_user(&res, mask as *mut usize)?;
+    Ok(8)
 }
 
-/// (待实现)一个系统调用，用于获取当前CPU的调度策略。目前直接返回0。
+/// (待实现)一个系统调用，用于获取进程 `pid` 的调度策略。进程存在时目前直接返回0(SCHED_OTHER)，否则返回 `ESRCH`。
 #[syscall_func(120)]
-pub fn sched_getscheduler(pid: usize) -> isize {
-    assert_eq!(pid, 0);
-    // let task = current_task().unwrap();
-    0
+pub fn sched_getscheduler(pid: usize) -> AlienResult<isize> {
+    if pid != 0 && find_task(pid).is_none() {
+        return Err(LinuxErrno::ESRCH);
+    }
+    Ok(0)
 }
 
 /// (待实现)一个系统调用，用于设置当前CPU的调度策略。目前直接返回0。
@@ -209,8 +209,7 @@ pub fn getrusage(who: isize, usage: usize) -> AlienResult<isize> {
     let mut task_usage = Rusage::new();
     task_usage.ru_utime = TimeVal::from_freq(static_info.tms_utime);
     task_usage.ru_stime = TimeVal::from_freq(static_info.tms_stime);
-    task.access_inner()
-        .copy_to_user(&task_usage, usage as *mut Rusage);
+    task.copy_to_user(&task_usage, usage as *mut Rusage)?;
     Ok(0)
 }
 
@@ -29,6 +29,11 @@ impl Context {
             s: [0; 12],
         }
     }
+
+    /// 返回切换时保存的返回地址与帧指针(s0)，用于回溯让出 CPU 的任务的内核栈
+    pub fn frame(&self) -> (usize, usize) {
+        (self.ra, self.s[0])
+    }
 }
 
 global_asm!(include_str!("switch.asm"));
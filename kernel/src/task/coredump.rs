@@ -0,0 +1,619 @@
+//! 用户进程的 core dump
+//!
+//! 进程因 SIGSEGV、SIGBUS、SIGILL、SIGABRT 等信号终止时，如果 RLIMIT_CORE 的软上限不为 0，
+//! 就按照 `/proc/sys/kernel/core_pattern` 生成 ELF core 文件，可以通过 `gdb <program> <core>` 分析。
+//! core 文件中包含线程组中所有线程的寄存器(来自各自的 trap 帧)和浮点寄存器、进程信息，
+//! 以及程序映像、用户栈、堆和 mmap 映射的内存。尚未分配物理页的部分以 0 填充，超出 RLIMIT_CORE 的部分被丢弃。
+//!
+//! 生成 core 文件前先让线程组中的其它线程停下：正在运行的线程回到用户态前在 [`coredump_wait`] 中等待，
+//! 使它们的寄存器与进程的内存在转储期间保持不变；转储完成后向这些线程发送 SIGKILL，整个线程组随之退出。
+//!
+//! core_pattern 中可以使用以下占位符：`%p` 进程号，`%i` 线程号，`%e` 程序名，`%s` 信号，`%%` 字符 `%`。
+//! 不以 `/` 开头的路径相对于进程的当前工作目录。
+use crate::fs::create_file;
+use crate::ipc::send_signal;
+use crate::mm::map::mapping_flags;
+use crate::task::{do_suspend, Task, TaskState, INIT_PROCESS};
+use alloc::collections::BTreeSet;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+use config::FRAME_SIZE;
+use constants::signal::SignalNumber;
+use constants::PrLimitRes;
+use core::cmp::min;
+use core::fmt::Write;
+use core::ops::Range;
+use ksync::Mutex;
+use page_table::addr::{align_up_4k, VirtAddr};
+use page_table::pte::MappingFlags;
+use spin::Lazy;
+use vfs::kfile::File;
+use vfscore::error::VfsError;
+use vfscore::file::VfsFile;
+use vfscore::inode::{InodeAttr, VfsInode};
+use vfscore::superblock::VfsSuperBlock;
+use vfscore::utils::{VfsFileStat, VfsNodePerm, VfsNodeType};
+use vfscore::VfsResult;
+
+/// 默认在当前工作目录下生成名为 `core` 的文件
+const DEFAULT_CORE_PATTERN: &str = "core";
+/// core_pattern 的最大长度
+const CORE_PATTERN_MAX: usize = 128;
+
+const ELF_HEADER_SIZE: usize = 64;
+const PROGRAM_HEADER_SIZE: usize = 56;
+const ET_CORE: u16 = 4;
+const EM_RISCV: u16 = 243;
+/// RVC 与双精度浮点 ABI
+const EF_RISCV: u32 = 0x5;
+const PT_LOAD: u32 = 1;
+const PT_NOTE: u32 = 4;
+const PF_X: u32 = 1;
+const PF_W: u32 = 2;
+const PF_R: u32 = 4;
+
+const NT_PRSTATUS: u32 = 1;
+const NT_PRFPREG: u32 = 2;
+const NT_PRPSINFO: u32 = 3;
+/// riscv64 中 `struct elf_prstatus` 的大小，以及其中 `pr_reg` 与 `pr_fpvalid` 的偏移
+const PRSTATUS_SIZE: usize = 376;
+const PRSTATUS_REG_OFFSET: usize = 112;
+const PRSTATUS_FPVALID_OFFSET: usize = 368;
+/// riscv64 中 `struct elf_prpsinfo` 的大小
+const PRPSINFO_SIZE: usize = 136;
+/// 32 个浮点寄存器与 fcsr
+const FPREGSET_SIZE: usize = 264;
+
+static CORE_PATTERN: Lazy<Mutex<String>> =
+    Lazy::new(|| Mutex::new(String::from(DEFAULT_CORE_PATTERN)));
+
+/// 正在生成 core 文件的线程组的进程号
+static DUMPING: Mutex<BTreeSet<usize>> = Mutex::new(BTreeSet::new());
+/// 在 [`coredump_wait`] 中等待转储完成的线程的线程号
+static PARKED: Mutex<BTreeSet<usize>> = Mutex::new(BTreeSet::new());
+
+/// 一段写入 core 文件的内存
+struct Vma {
+    range: Range<usize>,
+    flags: u32,
+}
+
+/// 一个线程的寄存器
+struct ThreadState {
+    tid: usize,
+    /// pc 与 x1-x31
+    regs: [usize; 32],
+    fp: ([u64; 32], usize),
+}
+
+/// 终止进程的信号 `sig` 是否需要生成 core 文件
+pub fn is_core_signal(sig: SignalNumber) -> bool {
+    matches!(
+        sig,
+        SignalNumber::SIGQUIT
+            | SignalNumber::SIGILL
+            | SignalNumber::SIGTRAP
+            | SignalNumber::SIGABRT
+            | SignalNumber::SIGBUS
+            | SignalNumber::SIGFPE
+            | SignalNumber::SIGSEGV
+            | SignalNumber::SIGXCPU
+            | SignalNumber::SIGXFSZ
+            | SignalNumber::SIGSYS
+    )
+}
+
+/// 为因信号 `signum` 终止的任务 `task` 生成 core 文件，返回是否生成了 core 文件。
+///
+/// RLIMIT_CORE 为 0 或者生成失败时什么也不做。生成期间线程组中的其它线程被停下，生成完成后它们会收到 SIGKILL。
+pub fn do_coredump(task: &Arc<Task>, signum: usize) -> bool {
+    let limit = task.access_inner().rlimit_cur(PrLimitRes::RlimitCore);
+    if limit == 0 {
+        return false;
+    }
+    let pattern = CORE_PATTERN.lock().clone();
+    // 不支持将 core 文件通过管道交给用户程序处理
+    if pattern.is_empty() || pattern.starts_with('|') {
+        return false;
+    }
+    if !DUMPING.lock().insert(task.pid) {
+        // 同一线程组中的其它线程正在生成 core 文件
+        coredump_wait(task);
+        return false;
+    }
+    let others = group_threads(task);
+    stop_threads(&others);
+    let dumped = write_core_file(task, signum, &pattern, limit);
+    DUMPING.lock().remove(&task.pid);
+    for other in others {
+        send_signal(other.get_tid() as usize, SignalNumber::SIGKILL as usize);
+    }
+    dumped
+}
+
+/// 线程组中有其它线程正在生成 core 文件时，当前线程 `task` 在回到用户态前停下，直到生成完成
+pub fn coredump_wait(task: &Arc<Task>) {
+    if !DUMPING.lock().contains(&task.pid) {
+        return;
+    }
+    let tid = task.get_tid() as usize;
+    PARKED.lock().insert(tid);
+    while DUMPING.lock().contains(&task.pid) {
+        do_suspend();
+    }
+    PARKED.lock().remove(&tid);
+}
+
+/// 等待 `threads` 都离开用户态：已经在 [`coredump_wait`] 中等待，或者没有在 CPU 上运行。
+///
+/// 没有运行的线程的 trap 帧保存着其在用户态的寄存器，再次运行时会在回到用户态前停下。
+fn stop_threads(threads: &[Arc<Task>]) {
+    loop {
+        let running = threads.iter().any(|thread| {
+            thread.state() == TaskState::Running
+                && !PARKED.lock().contains(&(thread.get_tid() as usize))
+        });
+        if !running {
+            break;
+        }
+        do_suspend();
+    }
+}
+
+fn write_core_file(task: &Arc<Task>, signum: usize, pattern: &str, limit: u64) -> bool {
+    let path = expand_pattern(pattern, task, signum);
+    let file = match create_file(&path) {
+        Ok(file) => file,
+        Err(e) => {
+            warn!(
+                "task {} failed to create core file {}: {:?}",
+                task.tid.0, path, e
+            );
+            return false;
+        }
+    };
+    let mut writer = CoreWriter {
+        file,
+        offset: 0,
+        limit,
+    };
+    match write_core(task, signum, &mut writer) {
+        Ok(()) => {
+            warn!("task {} dumped core to {}", task.tid.0, path);
+            true
+        }
+        Err(_) => {
+            warn!("task {} failed to write core file {}", task.tid.0, path);
+            false
+        }
+    }
+}
+
+/// 根据 core_pattern 生成 core 文件的路径
+fn expand_pattern(pattern: &str, task: &Arc<Task>, signum: usize) -> String {
+    let mut path = String::new();
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            path.push(c);
+            continue;
+        }
+        let _ = match chars.next() {
+            Some('p') => write!(path, "{}", task.get_pid()),
+            Some('i') => write!(path, "{}", task.get_tid()),
+            Some('e') => write!(path, "{}", comm(task)),
+            Some('s') => write!(path, "{}", signum),
+            Some('%') => write!(path, "%"),
+            // 未知的占位符被忽略
+            _ => Ok(()),
+        };
+    }
+    path
+}
+
+/// 程序名，即可执行文件路径的最后一部分，最多 15 个字符
+fn comm(task: &Arc<Task>) -> String {
+    let name = task.get_name();
+    let name = name.trim_end_matches('\0');
+    name.rsplit('/')
+        .next()
+        .unwrap_or(name)
+        .chars()
+        .take(15)
+        .collect()
+}
+
+/// 顺序写入 core 文件，超出 RLIMIT_CORE 的部分被丢弃
+struct CoreWriter {
+    file: Arc<dyn File>,
+    offset: u64,
+    limit: u64,
+}
+
+impl CoreWriter {
+    fn write(&mut self, data: &[u8]) -> Result<(), ()> {
+        let len = min(data.len() as u64, self.limit.saturating_sub(self.offset)) as usize;
+        let mut written = 0;
+        while written < len {
+            match self
+                .file
+                .write_at(self.offset + written as u64, &data[written..len])
+            {
+                Ok(0) | Err(_) => return Err(()),
+                Ok(n) => written += n,
+            }
+        }
+        self.offset += data.len() as u64;
+        Ok(())
+    }
+}
+
+fn write_core(task: &Arc<Task>, signum: usize, writer: &mut CoreWriter) -> Result<(), ()> {
+    let threads = collect_threads(task);
+    let vmas = collect_vmas(task);
+    let notes = build_notes(task, signum, &threads);
+
+    let phnum = 1 + vmas.len();
+    let mut offset = ELF_HEADER_SIZE + phnum * PROGRAM_HEADER_SIZE;
+    let mut header = Vec::with_capacity(offset);
+    header.extend_from_slice(b"\x7fELF");
+    // 64 位、小端、版本 1
+    header.extend_from_slice(&[2, 1, 1]);
+    header.resize(16, 0);
+    header.extend_from_slice(&ET_CORE.to_le_bytes());
+    header.extend_from_slice(&EM_RISCV.to_le_bytes());
+    header.extend_from_slice(&1u32.to_le_bytes());
+    header.extend_from_slice(&0u64.to_le_bytes());
+    header.extend_from_slice(&(ELF_HEADER_SIZE as u64).to_le_bytes());
+    header.extend_from_slice(&0u64.to_le_bytes());
+    header.extend_from_slice(&EF_RISCV.to_le_bytes());
+    header.extend_from_slice(&(ELF_HEADER_SIZE as u16).to_le_bytes());
+    header.extend_from_slice(&(PROGRAM_HEADER_SIZE as u16).to_le_bytes());
+    header.extend_from_slice(&(phnum as u16).to_le_bytes());
+    header.extend_from_slice(&[0; 6]);
+
+    push_phdr(&mut header, PT_NOTE, 0, 0, notes.len(), offset, 4);
+    offset += notes.len();
+    // 内存按页对齐，便于调试器直接映射
+    offset = align_up_4k(offset);
+    let data_start = offset;
+    for vma in vmas.iter() {
+        let size = vma.range.len();
+        push_phdr(
+            &mut header,
+            PT_LOAD,
+            vma.flags,
+            vma.range.start,
+            size,
+            offset,
+            FRAME_SIZE,
+        );
+        offset += size;
+    }
+    writer.write(&header)?;
+    writer.write(&notes)?;
+    writer.write(&vec![0u8; data_start - header.len() - notes.len()])?;
+
+    let address_space = task.access_inner().address_space.clone();
+    let mut page = vec![0u8; FRAME_SIZE];
+    for vma in vmas.iter() {
+        for addr in vma.range.clone().step_by(FRAME_SIZE) {
+            if writer.offset >= writer.limit {
+                return Ok(());
+            }
+            page.fill(0);
+            let mapped = address_space.lock().query(VirtAddr::from(addr));
+            if let Ok((phy, flags, _)) = mapped {
+                // 尚未分配物理页的部分以 0 填充
+                if flags.contains(MappingFlags::V) {
+                    let src = unsafe {
+                        core::slice::from_raw_parts(phy.as_usize() as *const u8, FRAME_SIZE)
+                    };
+                    page.copy_from_slice(src);
+                }
+            }
+            writer.write(&page)?;
+        }
+    }
+    Ok(())
+}
+
+fn push_phdr(
+    out: &mut Vec<u8>,
+    kind: u32,
+    flags: u32,
+    vaddr: usize,
+    size: usize,
+    offset: usize,
+    align: usize,
+) {
+    out.extend_from_slice(&kind.to_le_bytes());
+    out.extend_from_slice(&flags.to_le_bytes());
+    out.extend_from_slice(&(offset as u64).to_le_bytes());
+    out.extend_from_slice(&(vaddr as u64).to_le_bytes());
+    out.extend_from_slice(&0u64.to_le_bytes());
+    out.extend_from_slice(&(size as u64).to_le_bytes());
+    out.extend_from_slice(&(size as u64).to_le_bytes());
+    out.extend_from_slice(&(align as u64).to_le_bytes());
+}
+
+/// 收集线程组中仍在运行的线程的寄存器，当前线程排在最前面
+fn collect_threads(task: &Arc<Task>) -> Vec<ThreadState> {
+    let mut threads = vec![thread_state(task)];
+    threads.extend(group_threads(task).iter().map(thread_state));
+    threads
+}
+
+/// 与 `task` 属于同一线程组且没有退出的其它线程
+fn group_threads(task: &Arc<Task>) -> Vec<Arc<Task>> {
+    let mut threads = Vec::new();
+    let mut stack = vec![INIT_PROCESS.clone()];
+    while let Some(other) = stack.pop() {
+        if other.pid == task.pid
+            && other.get_tid() != task.get_tid()
+            && !matches!(other.state(), TaskState::Zombie | TaskState::Terminated)
+        {
+            threads.push(other.clone());
+        }
+        stack.extend(other.children());
+    }
+    threads
+}
+
+fn thread_state(task: &Arc<Task>) -> ThreadState {
+    let frame = task.trap_frame();
+    let mut regs = [0usize; 32];
+    regs.copy_from_slice(frame.regs());
+    regs[0] = frame.sepc();
+    ThreadState {
+        tid: task.get_tid() as usize,
+        regs,
+        fp: task.access_inner().fp_context.registers(),
+    }
+}
+
+/// 收集需要写入 core 文件的内存：程序映像、用户栈、堆以及 mmap 映射的区域，各区域互不重叠
+fn collect_vmas(task: &Arc<Task>) -> Vec<Vma> {
+    let inner = task.access_inner();
+    let mmap = inner.mmap.lock();
+    let mut recorded = vec![Vma {
+        range: inner.stack.clone(),
+        flags: PF_R | PF_W,
+    }];
+    let heap = mmap.heap();
+    if heap.end > heap.start {
+        recorded.push(Vma {
+            range: heap.start..heap.end,
+            flags: PF_R | PF_W,
+        });
+    }
+    for region in mmap.regions() {
+        recorded.push(Vma {
+            range: region.start..region.start + region.map_len,
+            flags: page_flags(mapping_flags(region.prot)),
+        });
+    }
+    drop(mmap);
+    // 程序映像没有单独记录，根据页表找出用户栈之下其余的用户页
+    let address_space = inner.address_space.lock();
+    let pages = (0..inner.stack.start)
+        .step_by(FRAME_SIZE)
+        .filter_map(|addr| {
+            address_space
+                .query(VirtAddr::from(addr))
+                .ok()
+                .map(|(_, flags, _)| flags)
+                .filter(|flags| flags.contains(MappingFlags::U))
+                .map(|flags| (addr, page_flags(flags)))
+        });
+    let mut vmas = image_vmas(pages, &recorded);
+    drop(address_space);
+    vmas.extend(recorded);
+    vmas
+}
+
+/// 将逐页扫描得到的用户页 `(地址, 权限)` 合并为连续且权限相同的区域，已经包含在 `recorded` 中的页被跳过
+fn image_vmas(pages: impl Iterator<Item = (usize, u32)>, recorded: &[Vma]) -> Vec<Vma> {
+    let mut vmas: Vec<Vma> = Vec::new();
+    for (addr, flags) in pages {
+        if recorded.iter().any(|vma| vma.range.contains(&addr)) {
+            continue;
+        }
+        match vmas.last_mut() {
+            Some(Vma { range, flags: last }) if range.end == addr && *last == flags => {
+                range.end += FRAME_SIZE;
+            }
+            _ => vmas.push(Vma {
+                range: addr..addr + FRAME_SIZE,
+                flags,
+            }),
+        }
+    }
+    vmas
+}
+
+fn page_flags(flags: MappingFlags) -> u32 {
+    let mut res = 0;
+    if flags.contains(MappingFlags::R) {
+        res |= PF_R;
+    }
+    if flags.contains(MappingFlags::W) {
+        res |= PF_W;
+    }
+    if flags.contains(MappingFlags::X) {
+        res |= PF_X;
+    }
+    res
+}
+
+/// 向 `out` 中追加一个 ELF note
+fn push_note(out: &mut Vec<u8>, kind: u32, desc: &[u8]) {
+    const NAME: &[u8] = b"CORE\0";
+    out.extend_from_slice(&(NAME.len() as u32).to_le_bytes());
+    out.extend_from_slice(&(desc.len() as u32).to_le_bytes());
+    out.extend_from_slice(&kind.to_le_bytes());
+    out.extend_from_slice(NAME);
+    out.resize((out.len() + 3) & !3, 0);
+    out.extend_from_slice(desc);
+    out.resize((out.len() + 3) & !3, 0);
+}
+
+fn build_notes(task: &Arc<Task>, signum: usize, threads: &[ThreadState]) -> Vec<u8> {
+    let (ppid, pgid) = {
+        let inner = task.access_inner();
+        let ppid = inner
+            .parent
+            .as_ref()
+            .and_then(|parent| parent.upgrade())
+            .map_or(0, |parent| parent.get_pid());
+        (ppid as u32, inner.pgid as u32)
+    };
+    let pid = task.get_pid() as u32;
+    let mut notes = Vec::new();
+
+    let mut psinfo = [0u8; PRPSINFO_SIZE];
+    // pr_sname
+    psinfo[1] = b'R';
+    psinfo[24..28].copy_from_slice(&pid.to_le_bytes());
+    psinfo[28..32].copy_from_slice(&ppid.to_le_bytes());
+    psinfo[32..36].copy_from_slice(&pgid.to_le_bytes());
+    let comm = comm(task);
+    psinfo[40..40 + comm.len()].copy_from_slice(comm.as_bytes());
+    let name = task.get_name();
+    let args = &name.as_bytes()[..min(name.len(), 79)];
+    psinfo[56..56 + args.len()].copy_from_slice(args);
+    push_note(&mut notes, NT_PRPSINFO, &psinfo);
+
+    // 每个线程依次为 NT_PRSTATUS 与 NT_PRFPREG，GDB 将第一个线程作为当前线程
+    for (index, thread) in threads.iter().enumerate() {
+        let mut status = [0u8; PRSTATUS_SIZE];
+        if index == 0 {
+            // pr_info.si_signo 与 pr_cursig
+            status[0..4].copy_from_slice(&(signum as u32).to_le_bytes());
+            status[12..14].copy_from_slice(&(signum as u16).to_le_bytes());
+        }
+        status[32..36].copy_from_slice(&(thread.tid as u32).to_le_bytes());
+        status[36..40].copy_from_slice(&ppid.to_le_bytes());
+        status[40..44].copy_from_slice(&pgid.to_le_bytes());
+        for (i, reg) in thread.regs.iter().enumerate() {
+            let offset = PRSTATUS_REG_OFFSET + i * 8;
+            status[offset..offset + 8].copy_from_slice(&(*reg as u64).to_le_bytes());
+        }
+        status[PRSTATUS_FPVALID_OFFSET..PRSTATUS_FPVALID_OFFSET + 4]
+            .copy_from_slice(&1u32.to_le_bytes());
+        push_note(&mut notes, NT_PRSTATUS, &status);
+
+        let mut fpregs = [0u8; FPREGSET_SIZE];
+        let (f, fcsr) = thread.fp;
+        for (i, reg) in f.iter().enumerate() {
+            fpregs[i * 8..i * 8 + 8].copy_from_slice(&reg.to_le_bytes());
+        }
+        fpregs[256..260].copy_from_slice(&(fcsr as u32).to_le_bytes());
+        push_note(&mut notes, NT_PRFPREG, &fpregs);
+    }
+    notes
+}
+
+/// `/proc/sys/kernel/core_pattern`
+struct CorePatternFile;
+
+impl VfsFile for CorePatternFile {
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> VfsResult<usize> {
+        let mut content = CORE_PATTERN.lock().clone();
+        content.push('\n');
+        let content = content.as_bytes();
+        if offset as usize >= content.len() {
+            return Ok(0);
+        }
+        let len = min(buf.len(), content.len() - offset as usize);
+        buf[..len].copy_from_slice(&content[offset as usize..offset as usize + len]);
+        Ok(len)
+    }
+    fn write_at(&self, offset: u64, buf: &[u8]) -> VfsResult<usize> {
+        let value = core::str::from_utf8(buf).map_err(|_| VfsError::Invalid)?;
+        let mut pattern = CORE_PATTERN.lock();
+        let mut new = if offset == 0 {
+            String::new()
+        } else {
+            pattern.clone()
+        };
+        new.push_str(value.trim_end_matches('\n'));
+        if new.len() > CORE_PATTERN_MAX {
+            return Err(VfsError::Invalid);
+        }
+        *pattern = new;
+        Ok(buf.len())
+    }
+}
+
+impl VfsInode for CorePatternFile {
+    fn get_super_block(&self) -> VfsResult<Arc<dyn VfsSuperBlock>> {
+        Err(VfsError::NoSys)
+    }
+    fn node_perm(&self) -> VfsNodePerm {
+        VfsNodePerm::empty()
+    }
+    fn set_attr(&self, _attr: InodeAttr) -> VfsResult<()> {
+        Ok(())
+    }
+
+    fn get_attr(&self) -> VfsResult<VfsFileStat> {
+        Ok(VfsFileStat {
+            st_size: CORE_PATTERN.lock().len() as u64 + 1,
+            ..Default::default()
+        })
+    }
+
+    fn inode_type(&self) -> VfsNodeType {
+        VfsNodeType::File
+    }
+}
+
+/// 在 `/proc/sys/kernel` 中创建 `core_pattern` 文件
+pub fn init_core_pattern() {
+    let dir = vfs::proc::procfs_dir("sys/kernel").expect("procfs is not initialized");
+    dir.add_file_manually(
+        "core_pattern",
+        Arc::new(CorePatternFile),
+        "rw-r--r--".into(),
+    )
+    .unwrap();
+}
+
+#[cfg(feature = "test")]
+mod ktests {
+    use super::*;
+    use ktest::{kassert_eq, kernel_test, TestResult};
+
+    #[kernel_test]
+    fn test_image_vmas_skip_recorded() -> TestResult {
+        let recorded = [Vma {
+            range: 0x3000..0x5000,
+            flags: PF_R | PF_W,
+        }];
+        let pages = [
+            (0x1000, PF_R | PF_X),
+            (0x2000, PF_R | PF_X),
+            (0x3000, PF_R | PF_W),
+            (0x4000, PF_R | PF_W),
+            (0x5000, PF_R | PF_W),
+            (0x6000, PF_R),
+        ];
+        let vmas = image_vmas(pages.into_iter(), &recorded);
+        let segments = vmas
+            .iter()
+            .map(|vma| (vma.range.clone(), vma.flags))
+            .collect::<Vec<_>>();
+        kassert_eq!(
+            segments,
+            vec![
+                (0x1000..0x3000, PF_R | PF_X),
+                (0x5000..0x6000, PF_R | PF_W),
+                (0x6000..0x7000, PF_R),
+            ]
+        );
+        Ok(())
+    }
+}
@@ -1,6 +1,7 @@
 //! Alien 中有关进程的系统调用 和 多核的相关支持。
 use alloc::string::{String, ToString};
 use alloc::sync::Arc;
+use alloc::vec;
 use alloc::vec::Vec;
 use core::cell::UnsafeCell;
 use log::{error, info, warn};
@@ -9,7 +10,7 @@ use spin::Lazy;
 
 use constants::ipc::FutexOp;
 use constants::signal::SignalNumber;
-use constants::task::{CloneFlags, WaitOptions};
+use constants::task::CloneFlags;
 use constants::{AlienError, AlienResult};
 use constants::{PrLimit, PrLimitRes};
 use ksync::Mutex;
@@ -18,9 +19,10 @@ use syscall_table::syscall_func;
 use crate::fs;
 use crate::ipc::{futex, global_logoff_signals};
 use crate::task::context::Context;
+use crate::task::ptrace::{ptrace_clone, ptrace_event, ptrace_exec, ptrace_exit};
 use crate::task::schedule::schedule;
-use crate::task::task::{Task, TaskState};
-use crate::task::INIT_PROCESS;
+use crate::task::task::{task_count, Task, TaskState};
+use crate::task::{find_task, INIT_PROCESS};
 use crate::trap::{check_task_timer_expired, TrapFrame};
 use arch::hart_id;
 use config::CPU_NUM;
@@ -33,6 +35,8 @@ pub struct CPU {
     pub task: Option<Arc<Task>>,
     /// 当前线程的上下文
     pub context: Context,
+    /// 该 CPU 的浮点寄存器中保存着哪个线程的浮点上下文
+    pub fp_owner: Option<usize>,
 }
 
 impl CPU {
@@ -41,6 +45,7 @@ impl CPU {
         Self {
             task: None,
             context: Context::empty(),
+            fp_owner: None,
         }
     }
 
@@ -114,12 +119,25 @@ pub fn current_trap_frame() -> &'static mut TrapFrame {
 /// 当调用该函数的进程为`pid==0`的init进程时，将直接调用`system_shutdown`使得内核终止。
 #[syscall_func(93)]
 pub fn do_exit(exit_code: i32) -> isize {
+    exit_with_status((exit_code & 0xff) << 8)
+}
+
+/// 当前任务因信号 `signum` 终止，`core_dumped` 表示是否生成了 core 文件。
+///
+/// wait 获取的状态值的低 7 位为信号，生成了 core 文件时带有 WCOREDUMP 标志(0x80)。
+pub fn do_exit_by_signal(signum: usize, core_dumped: bool) -> isize {
+    let core = if core_dumped { 0x80 } else { 0 };
+    exit_with_status((signum as i32 & 0x7f) | core)
+}
+
+/// 以 wait 状态值 `exit_code` 退出当前任务
+fn exit_with_status(exit_code: i32) -> isize {
     let task = current_task().unwrap();
-    let exit_code = (exit_code & 0xff) << 8;
     if task.get_pid() == 1 {
         println!("Init process exit with code {}", exit_code);
         system_shutdown();
     }
+    ptrace_exit(task, exit_code);
     {
         let init = INIT_PROCESS.clone();
         task.take_children().into_iter().for_each(|child| {
@@ -134,8 +152,7 @@ pub fn do_exit(exit_code: i32) -> isize {
     let addr = task.access_inner().clear_child_tid;
     if addr != 0 {
         // 确认这个地址在用户地址空间中。如果没有也不需要报错，因为线程马上就退出了
-        let addr = task.transfer_raw_ptr(addr as *mut i32);
-        *addr = 0;
+        let _ = task.copy_to_user(&0i32, addr as *mut i32);
     }
 
     // 回收一些物理页，不然等到wait系统调用真正进行回收时，可能会出现OOM
@@ -147,8 +164,7 @@ pub fn do_exit(exit_code: i32) -> isize {
     info!("pre recycle done");
     let clear_child_tid = task.futex_wake();
     if clear_child_tid != 0 {
-        let phy_addr = task.transfer_raw_ptr(clear_child_tid as *mut usize);
-        *phy_addr = 0;
+        let _ = task.copy_to_user(&0usize, clear_child_tid as *mut usize);
         error!("exit wake futex on {:#x}", clear_child_tid);
         futex(clear_child_tid, FutexOp::FutexWake as u32, 1, 0, 0, 0);
     } else {
@@ -177,22 +193,65 @@ pub fn do_suspend() -> isize {
     0
 }
 
-/// (待实现)设置进程组的id。目前直接返回0。
+/// 一个系统调用，用于设置进程号为 `pid` 的进程的进程组号为 `pgid`。
+///
+/// `pid` 为 0 时表示调用者自身，`pgid` 为 0 时表示使用 `pid` 作为进程组号。目前只能设置调用者自身或者调用者的子进程。
+///
+/// Reference: [setpgid](https://man7.org/linux/man-pages/man2/setpgid.2.html)
 #[syscall_func(154)]
-pub fn set_pgid() -> isize {
-    0
+pub fn set_pgid(pid: usize, pgid: isize) -> AlienResult<isize> {
+    if pgid < 0 {
+        return Err(AlienError::EINVAL);
+    }
+    let task = find_self_or_child(pid)?;
+    let pgid = if pgid == 0 { task.pid } else { pgid as usize };
+    task.access_inner().pgid = pgid;
+    Ok(0)
 }
 
-/// (待实现)获取进程组的id。目前直接返回0。
+/// 一个系统调用，用于获取进程号为 `pid` 的进程的进程组号，`pid` 为 0 时表示调用者自身。
+///
+/// Reference: [getpgid](https://man7.org/linux/man-pages/man2/getpgid.2.html)
 #[syscall_func(155)]
-pub fn get_pgid() -> isize {
-    0
+pub fn get_pgid(pid: usize) -> AlienResult<isize> {
+    let task = find_self_or_child(pid)?;
+    let pgid = task.access_inner().pgid;
+    Ok(pgid as isize)
 }
 
-/// 创建一个新的session，并使得使用系统调用的当前task成为新session的leader，同时也是新进程组的leader(待实现)
+/// 创建一个新的session，并使得使用系统调用的当前task成为新session的leader，同时也是新进程组的leader。
+///
+/// 目前没有记录 session，仅将当前进程的进程组号设置为其进程号，返回新的进程组号。
 #[syscall_func(157)]
 pub fn set_sid() -> isize {
-    0
+    let task = current_task().unwrap();
+    task.access_inner().pgid = task.pid;
+    task.pid as isize
+}
+
+/// 查找进程号为 `pid` 的调用者自身或调用者的子进程，`pid` 为 0 时表示调用者自身
+fn find_self_or_child(pid: usize) -> AlienResult<Arc<Task>> {
+    let task = current_task().unwrap();
+    if pid == 0 || pid == task.pid {
+        return Ok(task.clone());
+    }
+    task.children()
+        .into_iter()
+        .find(|child| child.pid == pid)
+        .ok_or(AlienError::ESRCH)
+}
+
+/// 获取进程组号为 `pgid` 的所有进程的进程号
+pub fn process_group(pgid: usize) -> Vec<usize> {
+    let mut pids = Vec::new();
+    let mut stack = vec![INIT_PROCESS.clone()];
+    while let Some(task) = stack.pop() {
+        if task.access_inner().pgid == pgid && !pids.contains(&task.pid) {
+            pids.push(task.pid);
+        }
+        stack.extend(task.children());
+    }
+    pids
 }
 
 /// 获取当前正在运行task的pid号。在Alien中pid作为线程组的标识符，位于同一线程组中的线程的pid相同。
@@ -220,10 +279,10 @@ pub fn getuid() -> isize {
     0
 }
 
-/// (待实现)获取有效用户 id，即相当于哪个用户的权限。在实现多用户权限前默认为最高权限。目前直接返回0。
+/// 获取有效用户 id，即相当于哪个用户的权限。默认为 0，执行 set-user-ID 程序后变为程序文件的所有者。
 #[syscall_func(175)]
 pub fn geteuid() -> isize {
-    0
+    current_task().unwrap().access_inner().euid as isize
 }
 
 /// (待实现)获取用户组 id。在实现多用户权限前默认为最高权限。目前直接返回0。
@@ -232,10 +291,10 @@ pub fn getgid() -> isize {
     0
 }
 
-/// (待实现)获取有效用户组 id，即相当于哪个用户组的权限。在实现多用户组权限前默认为最高权限。目前直接返回0。
+/// 获取有效用户组 id，即相当于哪个用户组的权限。默认为 0，执行 set-group-ID 程序后变为程序文件所属的组。
 #[syscall_func(177)]
 pub fn getegid() -> isize {
-    0
+    current_task().unwrap().access_inner().egid as isize
 }
 
 /// 获取当前正在运行task的tid号。在Alien中tid作为task的唯一标识符。
@@ -269,6 +328,12 @@ pub fn clone(flag: usize, stack: usize, ptid: usize, tls: usize, ctid: usize) ->
     let sig = SignalNumber::from(sig);
     let mut task = current_task().unwrap();
 
+    // 线程数量达到 RLIMIT_NPROC 时不能再创建新的任务
+    let nproc_limit = task.access_inner().rlimit_cur(PrLimitRes::RlimitNproc);
+    if task_count() as u64 >= nproc_limit {
+        return AlienError::EAGAIN as isize;
+    }
+
     let child_num = task.access_inner().children.len();
     if child_num >= 10 {
         do_suspend();
@@ -283,7 +348,12 @@ pub fn clone(flag: usize, stack: usize, ptid: usize, tls: usize, ctid: usize) ->
     let trap_frame = new_task.trap_frame();
     trap_frame.update_res(0);
     let tid = new_task.get_tid();
+    // 被跟踪的任务创建的新任务需要在开始运行前被自动跟踪
+    let event = ptrace_clone(task, &new_task, clone_flag, sig);
     GLOBAL_TASK_MANAGER.add_task(Arc::new(FifoTask::new(new_task)));
+    if let Some(event) = event {
+        ptrace_event(task, event, tid as usize);
+    }
     // do_suspend();
     tid
 }
@@ -298,9 +368,9 @@ pub fn clone(flag: usize, stack: usize, ptid: usize, tls: usize, ctid: usize) ->
 #[syscall_func(221)]
 pub fn do_exec(path: *const u8, args_ptr: usize, env: usize) -> AlienResult<isize> {
     let task = current_task().unwrap();
-    let mut path_str = task.transfer_str(path);
+    let mut path_str = task.transfer_str(path)?;
     // get the args and push them into the new process stack
-    let (mut args, envs) = parse_user_arg_env(args_ptr, env);
+    let (mut args, envs) = parse_user_arg_env(args_ptr, env)?;
     warn!("exec path: {}", path_str);
     warn!("exec args: {:?} ,env: {:?}", args, envs);
     if path_str.ends_with(".sh") {
@@ -316,11 +386,11 @@ pub fn do_exec(path: *const u8, args_ptr: usize, env: usize) -> AlienResult<isiz
     if path_str.contains("libc-bench") {
         path_str = "libc-bench2".to_string();
     }
+    fs::check_executable(&path_str)?;
+    let setid = fs::exec_credentials(&path_str)?;
     if fs::read_all(&path_str, &mut data) {
-        let res = task.exec(&path_str, data.as_slice(), args, envs);
-        if res.is_err() {
-            return Err(AlienError::ENOEXEC);
-        }
+        task.exec(&path_str, data.as_slice(), args, envs, setid)?;
+        ptrace_exec(task);
         Ok(0)
     } else {
         info!("exec {} failed", path_str);
@@ -328,79 +398,22 @@ pub fn do_exec(path: *const u8, args_ptr: usize, env: usize) -> AlienResult<isiz
     }
 }
 
-/// 一个系统调用，用于父进程等待某子进程退出。
-///
-/// `pid`用于指明等待的子进程pid号。`pid == -1`表示父进程等待任意子进程返回。
-/// 当`exit_code`非空时，将会把退出的子程序的退出值赋给`exit_code`所指向的位置。
-/// `options`主要用于控制`wait4`的执行逻辑，例如当`wait_options`包含`WNOHANG`时，即使未发现子程序返回，函数也将直接返回0。
-///
-/// 一般`wait4`会使得父进程阻塞，直到子进程退出，返回退出的子进程pid。但当`wait_options`包含`WNOHANG`时，即使未发现子程序返回，函数也将直接返回0。
-/// 当父进程的所有子进程中不包含进程号为pid的子进程，将返回-1。
-///
-/// Reference:[wait](https://man7.org/linux/man-pages/man2/wait.2.html)
-#[syscall_func(260)]
-pub fn wait4(pid: isize, exit_code: *mut i32, options: u32, _rusage: *const u8) -> isize {
-    loop {
-        let task = current_task().unwrap();
-        if task
-            .children()
-            .iter()
-            .find(|child| child.get_pid() == pid || pid == -1)
-            .is_none()
-        {
-            return -1;
-        }
-        let res = task.check_child(pid);
-        if let Some(index) = res {
-            let child = task.remove_child(index);
-            assert_eq!(
-                Arc::strong_count(&child),
-                1,
-                "Father is [{}-{}], wait task is [{}-{}]",
-                task.get_pid(),
-                task.get_tid(),
-                child.get_pid(),
-                child.get_tid()
-            );
-            if !exit_code.is_null() {
-                let exit_code_ref = task.transfer_raw_ptr(exit_code);
-                *exit_code_ref = child.exit_code();
-            }
-            return child.get_tid();
-        } else {
-            let wait_options = WaitOptions::from_bits(options).unwrap();
-            if wait_options.contains(WaitOptions::WNOHANG) {
-                return 0;
-            } else {
-                do_suspend();
-            }
-        }
-    }
-}
-
-/// 一个系统调用，用于改变堆区的大小(目前仅可以增加堆区大小)
+/// 一个系统调用，用于改变堆区的大小
 ///
-/// `addr`用于指明扩充堆区后，堆区的末尾位置。
-/// 当`addr`所标识的位置在当前堆起始位置的前方，或者堆当前已使用的末尾位置的前方时，将会导致增加堆区大小失败。
+/// `addr`用于指明改变堆区大小后，堆区的末尾位置(program break)。`addr`为0时仅返回当前的末尾位置。
+/// `addr`大于当前末尾位置时扩充堆区，扩充后的堆区长度受到`RLIMIT_DATA`的限制，且不能与内存映射区重叠；
+/// `addr`小于当前末尾位置时缩减堆区，并释放不再使用的物理页。
 ///
-/// 成功增加堆区大小时，函数返回堆当前已使用的末尾位置；否则返回-1。
+/// 函数返回堆当前的末尾位置，修改失败时返回修改前的末尾位置。
 #[syscall_func(214)]
 pub fn do_brk(addr: usize) -> isize {
     let process = current_task().unwrap();
     let mut inner = process.access_inner();
     let heap_info = inner.heap_info();
-    if addr == 0 {
+    if addr == 0 || addr < heap_info.start {
         return heap_info.current as isize;
     }
-    if addr < heap_info.start || addr < heap_info.current {
-        // panic!("heap can't be shrinked");
-        return -1;
-    }
-    let res = inner.extend_heap(addr);
-    if res.is_err() {
-        return -1;
-    }
-    res.unwrap() as isize
+    inner.set_brk(addr).unwrap_or(heap_info.current) as isize
 }
 
 /// 一个系统调用，用于修改进程clear_child_tid的值，同时返回进程的tid。
@@ -411,87 +424,103 @@ pub fn set_tid_address(tidptr: usize) -> isize {
     task.get_tid()
 }
 
-/// 一个系统调用，用于修改进程的资源限制。
+/// 一个系统调用，用于获取和修改进程的资源限制。
 ///
-/// 进程对其拥有的资源，包括用户栈大小、可以打开的文件描述符数、用户地址空间大小等都有所上限。
+/// 进程对其拥有的资源，包括 CPU 时间、文件长度、用户栈大小、可以打开的文件描述符数、用户地址空间大小等都有所上限。
 ///
 /// `prlimit64`则可以根据资源的种类对不同的资源进行大小的限制。针对每一具体限制都包括软上限和硬上限，具体可见[`PrLimit`]。
-/// `pid`用于指明需要修改资源限制的进程的pid号。
-/// `resource`用于指明需要修改的资源类型，可选的值包括`RLIMIT_STACK`、`RLIMIT_NOFILE`、`RLIMIT_AS`等，详情可见[`PrLimitRes`]。
+/// `pid`用于指明需要修改资源限制的进程的pid号，为0时表示当前进程。
+/// `resource`用于指明需要修改的资源类型，可选的值包括`RLIMIT_CPU`、`RLIMIT_STACK`、`RLIMIT_NOFILE`、`RLIMIT_AS`等，详情可见[`PrLimitRes`]。
 /// `new_limit`用于指明新限制的指针，如果为空指针则不进行新限制的赋值。
 /// `old_limit`用于指明存放旧限制的指针，如果为空则不进行旧限制的保存。
 ///
-/// 正确执行后会返回0；如果输入的pid为0或者为当前正在运行的进程号，则会直接终止。
+/// 正确执行后会返回0；`resource`不合法或者新限制的软上限大于硬上限时返回`EINVAL`；找不到进程时返回`ESRCH`。
 #[syscall_func(261)]
-pub fn prlimit64(pid: usize, resource: usize, new_limit: *const u8, old_limit: *mut u8) -> isize {
-    assert!(pid == 0 || pid == current_task().unwrap().get_pid() as usize);
+pub fn prlimit64(
+    pid: usize,
+    resource: usize,
+    new_limit: *const u8,
+    old_limit: *mut u8,
+) -> AlienResult<isize> {
+    let resource = PrLimitRes::try_from(resource).map_err(|_| AlienError::EINVAL)?;
     let task = current_task().unwrap();
-    let mut inner = task.access_inner();
-    if let Ok(resource) = PrLimitRes::try_from(resource) {
-        if !old_limit.is_null() {
-            let limit = inner.get_prlimit(resource);
-            warn!("get rlimit nofile to {:?}", limit);
-            inner.copy_to_user(&limit, old_limit as *mut PrLimit);
-        }
-        match resource {
-            PrLimitRes::RlimitStack => {}
-            PrLimitRes::RlimitNofile => {
-                if !new_limit.is_null() {
-                    let mut limit = PrLimit::new(0, 0);
-                    inner.copy_from_user(new_limit as *const PrLimit, &mut limit);
-                    warn!("set rlimit nofile to {:?}", limit);
-                    inner.set_prlimit(resource, limit);
-                }
-            }
-            PrLimitRes::RlimitAs => {}
-        }
+    // Alien 中所有进程属于同一用户，因此可以获取或修改任意进程的资源限制
+    let target = if pid == 0 {
+        task.clone()
+    } else {
+        find_task(pid).ok_or(AlienError::ESRCH)?
+    };
+    let new_limit = if new_limit.is_null() {
+        None
+    } else {
+        Some(task.copy_from_user(new_limit as *const PrLimit)?)
+    };
+    if !old_limit.is_null() {
+        let limit = target.access_inner().get_prlimit(resource);
+        task.copy_to_user(&limit, old_limit as *mut PrLimit)?;
     }
-    0
+    if let Some(limit) = new_limit {
+        warn!("set rlimit {:?} of {} to {:?}", resource, target.pid, limit);
+        target.access_inner().set_prlimit(resource, limit)?;
+    }
+    Ok(0)
+}
+
+/// 一个系统调用，用于获取当前进程的资源限制，等价于`pid`为0且`new_limit`为空的[`prlimit64`]。
+#[syscall_func(163)]
+pub fn getrlimit(resource: usize, limit: *mut u8) -> AlienResult<isize> {
+    prlimit64(0, resource, core::ptr::null(), limit)
+}
+
+/// 一个系统调用，用于设置当前进程的资源限制，等价于`pid`为0且`old_limit`为空的[`prlimit64`]。
+#[syscall_func(164)]
+pub fn setrlimit(resource: usize, limit: *const u8) -> AlienResult<isize> {
+    prlimit64(0, resource, limit, core::ptr::null_mut())
 }
 
 /// 用于exec可执行文件时，分别在args_ptr和env_ptr所指向的地址处取出参数和环境变量
-fn parse_user_arg_env(args_ptr: usize, env_ptr: usize) -> (Vec<String>, Vec<String>) {
+fn parse_user_arg_env(args_ptr: usize, env_ptr: usize) -> AlienResult<(Vec<String>, Vec<String>)> {
     let task = current_task().unwrap();
     let mut args = Vec::new();
 
     if args_ptr != 0 {
-        let mut start = args_ptr as *mut usize;
+        let mut start = args_ptr as *const usize;
         loop {
-            let arg = task.transfer_raw_ptr(start);
-            if *arg == 0 {
+            let arg = task.copy_from_user(start)?;
+            if arg == 0 {
                 break;
             }
-            args.push(*arg);
+            args.push(arg);
             start = unsafe { start.add(1) };
         }
     }
     let args = args
         .into_iter()
         .map(|arg| {
-            let mut arg = task.transfer_str(arg as *const u8);
+            let mut arg = task.transfer_str(arg as *const u8)?;
             arg.push('\0');
-            arg
+            Ok(arg)
         })
-        .collect::<Vec<String>>();
+        .collect::<AlienResult<Vec<String>>>()?;
     let mut envs = Vec::new();
     if env_ptr != 0 {
-        let mut start = env_ptr as *mut usize;
+        let mut start = env_ptr as *const usize;
         loop {
-            let env = task.transfer_raw_ptr(start);
-            if *env == 0 {
+            let env = task.copy_from_user(start)?;
+            if env == 0 {
                 break;
             }
-            envs.push(*env);
+            envs.push(env);
             start = unsafe { start.add(1) };
         }
     }
     let envs = envs
         .into_iter()
         .map(|env| {
-            let mut env = task.transfer_str(env as *const u8);
+            let mut env = task.transfer_str(env as *const u8)?;
             env.push('\0');
-            env
+            Ok(env)
         })
-        .collect::<Vec<String>>();
-    (args, envs)
+        .collect::<AlienResult<Vec<String>>>()?;
+    Ok((args, envs))
 }
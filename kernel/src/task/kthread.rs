@@ -1,10 +1,11 @@
 use crate::fs::stdio::{STDIN, STDOUT};
 use crate::mm::map::MMapInfo;
 use crate::task::context::Context;
-use crate::task::heap::HeapInfo;
+use crate::task::rlimit::ResourceLimits;
 use crate::task::stack::Stack;
 use crate::task::task::{TaskInner, TaskTimer, TidHandle};
 use crate::task::{FsContext, StatisticalData, Task, TaskState, GLOBAL_TASK_MANAGER};
+use crate::trap::FpContext;
 use alloc::collections::BTreeMap;
 use alloc::string::ToString;
 use alloc::sync::Arc;
@@ -18,6 +19,7 @@ use gmanager::MinimalManager;
 use ksync::Mutex;
 use mem::kernel_space;
 use smpscheduler::FifoTask;
+use timer::get_time_ms;
 use vfs::kfile::File;
 
 type FdManager = MinimalManager<Arc<dyn File>>;
@@ -54,13 +56,16 @@ pub fn ktread_create(func: fn(), name: &str) -> AlienResult<()> {
             statistical_data: StatisticalData::new(),
             timer: TaskTimer::default(),
             exit_code: 0,
-            heap: Arc::new(Mutex::new(HeapInfo::new(0, 0))),
-            mmap: MMapInfo::new(),
+            mmap: Arc::new(Mutex::new(MMapInfo::new())),
+            rlimits: Arc::new(Mutex::new(ResourceLimits::new())),
             signal_handlers: Arc::new(Mutex::new(SignalHandlers::new())),
             signal_receivers: Arc::new(Mutex::new(SignalReceivers::new())),
             set_child_tid: 0,
             clear_child_tid: 0,
             trap_cx_before_signal: None,
+            fp_cx_before_signal: None,
+            fp_context: FpContext::new(),
+            ptrace: None,
             signal_set_siginfo: false,
             robust: RobustList::default(),
             shm: BTreeMap::new(),
@@ -73,6 +78,12 @@ pub fn ktread_create(func: fn(), name: &str) -> AlienResult<()> {
             // user mode stack info
             stack: 0..0,
             need_wait: 0,
+            pgid: 0,
+            job_event: None,
+            state_time: get_time_ms(),
+            interruptible: false,
+            euid: 0,
+            egid: 0,
         }),
         send_sigchld_when_exit: false,
     };
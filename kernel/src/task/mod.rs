@@ -1,39 +1,99 @@
 //! Alien 中有关进程管理的相关数据结构
 //!
 //! [`context`] 子模块定义了 Alien 中线程上下文的相关结构.
+//! [`coredump`] 子模块实现了用户进程因信号终止时生成 core 文件。
 //! [`cpu`] 子模块中指明了 Alien 中有关进程的系统调用 和 多核的相关支持。
 //! [`heap`] 子模块定义了 Alien 记录进程堆空间的相关信息的结构。
+//! [`ptrace`] 子模块实现了 Alien 中的进程跟踪。
+//! [`rlimit`] 子模块定义了 Alien 记录进程资源限制的相关结构。
 //! [`schedule`] 子模块指明了 Alien 中有关 CPU 调度的相关机制
 //! [`stack`] 子模块定义了 Alien 中有关内核栈的相关结构。
 //! [`task`] 子模块定义了 Alien 中有关进程控制块的定义。
+//! [`wait`] 子模块实现了 Alien 中等待子进程状态变化的相关系统调用。
+//! [`watchdog`] 子模块实现了长时间等待的任务与 soft lockup 的检测。
 use crate::fs::read_all;
 pub use crate::task::task::FsContext;
 use alloc::sync::Arc;
+use alloc::vec;
 use alloc::vec::Vec;
+use constants::AlienResult;
+pub use coredump::{coredump_wait, do_coredump, init_core_pattern, is_core_signal};
 pub use cpu::*;
+pub use ptrace::*;
 use shim::{KTask, KTaskShim};
 use smpscheduler::FifoTask;
 use spin::Lazy;
-pub use task::{StatisticalData, Task, TaskState};
+pub use task::{JobEvent, StatisticalData, Task, TaskState};
 use timer::get_time_ms;
+pub use wait::*;
+pub use watchdog::{check_soft_lockup, init_watchdog, touch_softlockup_watchdog};
 
 mod context;
+mod coredump;
 mod cpu;
-mod heap;
 mod kthread;
+mod ptrace;
+mod rlimit;
 pub mod schedule;
 mod stack;
 mod task;
+mod wait;
+mod watchdog;
+
+/// 未在内核命令行中指定 init 程序时，依次尝试的 init 程序
+const DEFAULT_INIT: [&str; 5] = [
+    "/tests/init",
+    "/sbin/init",
+    "/etc/init",
+    "/bin/init",
+    "/bin/sh",
+];
 
 /// 初始进程（0号进程）
+///
+/// 根文件系统为 initramfs 时优先执行 `rdinit=` 指定的程序，否则优先执行 `init=` 指定的程序，
+/// 都不存在时依次尝试 [`DEFAULT_INIT`] 中的程序。不是 ELF 文件的候选(例如 shell 脚本)会被跳过。
 pub static INIT_PROCESS: Lazy<Arc<Task>> = Lazy::new(|| {
-    let mut data = Vec::new();
-    read_all("/tests/init", &mut data);
-    assert!(data.len() > 0);
-    let task = Task::from_elf("/tests/init", data.as_slice()).unwrap();
+    let cmdline = platform::cmdline::kernel_cmdline();
+    let rdinit = if vfs::initramfs_is_root() {
+        cmdline.rdinit
+    } else {
+        None
+    };
+    let (init, task) = rdinit
+        .into_iter()
+        .chain(cmdline.init)
+        .chain(DEFAULT_INIT)
+        .find_map(|path| {
+            let mut data = Vec::new();
+            if !read_all(path, &mut data) {
+                return None;
+            }
+            match Task::from_elf(path, data.as_slice()) {
+                Some(task) => Some((path, task)),
+                None => {
+                    println!("{} is not a valid ELF file, skip it", path);
+                    None
+                }
+            }
+        })
+        .expect("No init program found");
+    println!("Run {} as init process", init);
     Arc::new(task)
 });
 
+/// 在系统中查找 tid 为 `tid` 的任务
+pub fn find_task(tid: usize) -> Option<Arc<Task>> {
+    let mut stack = vec![INIT_PROCESS.clone()];
+    while let Some(task) = stack.pop() {
+        if task.get_tid() as usize == tid {
+            return Some(task);
+        }
+        stack.extend(task.children());
+    }
+    None
+}
+
 /// 将初始进程加入进程池中进行调度
 pub fn init_task() {
     kthread::ktread_create(kthread_init, "kthread_test").unwrap();
@@ -57,7 +117,7 @@ fn kthread_init() {
 
 impl KTask for Task {
     fn to_wait(&self) {
-        self.update_state(TaskState::Waiting)
+        self.wait_interruptible()
     }
 
     fn to_wakeup(&self) {
@@ -83,13 +143,27 @@ impl KTaskShim for DriverTaskImpl {
         do_suspend();
     }
 
-    fn transfer_ptr_raw(&self, ptr: usize) -> usize {
+    fn transfer_ptr_raw(&self, ptr: usize, write: bool) -> AlienResult<usize> {
         let task = current_task().unwrap();
-        task.transfer_raw(ptr)
+        if write {
+            task.transfer_raw_ptr(ptr as *mut u8)
+                .map(|phy| phy as *mut u8 as usize)
+        } else {
+            task.transfer_raw(ptr)
+        }
     }
-    fn transfer_buf_raw(&self, src: usize, size: usize) -> Vec<&mut [u8]> {
+    fn transfer_buf_raw(
+        &self,
+        src: usize,
+        size: usize,
+        write: bool,
+    ) -> AlienResult<Vec<&mut [u8]>> {
         let task = current_task().unwrap();
-        task.transfer_buffer(src as *const u8, size)
+        if write {
+            task.transfer_buffer_mut(src as *mut u8, size)
+        } else {
+            task.transfer_buffer(src as *const u8, size)
+        }
     }
 }
 
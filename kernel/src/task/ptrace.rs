@@ -0,0 +1,809 @@
+//! Alien 中的进程跟踪(ptrace)，供 gdb、strace 等调试工具使用。
+//!
+//! 被跟踪者在收到信号、进出系统调用(PTRACE_SYSCALL)、单步执行结束或者发生 fork/exec/exit 等事件时进入跟踪停止，
+//! 并向跟踪者发送 SIGCHLD。跟踪者通过 [`wait4`](crate::task::wait4) 获取跟踪停止，在被跟踪者停止期间读写其内存与寄存器，
+//! 最后通过 PTRACE_CONT 等请求让其恢复运行。
+//!
+//! RISC-V 没有硬件单步执行，PTRACE_SINGLESTEP 通过在下一条指令的所有可能位置写入临时断点(`c.ebreak`)实现。
+//! 与被跟踪者共享地址空间的其它线程执行到临时断点时同样会收到 SIGTRAP。
+use alloc::collections::BTreeMap;
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+use arch::C_EBREAK;
+use bitflags::bitflags;
+use ksync::Mutex;
+use smpscheduler::FifoTask;
+
+use constants::io::IoVec;
+use constants::signal::{SigInfo, SignalNumber};
+use constants::task::CloneFlags;
+use constants::{AlienResult, LinuxErrno};
+use syscall_table::syscall_func;
+
+use crate::ipc::send_signal;
+use crate::task::schedule::schedule;
+use crate::task::{
+    current_task, do_exit_by_signal, find_task, Task, TaskState, GLOBAL_TASK_MANAGER,
+};
+use crate::trap::TrapFrame;
+
+/// ptrace 的请求类型
+const PTRACE_TRACEME: usize = 0;
+const PTRACE_PEEKTEXT: usize = 1;
+const PTRACE_PEEKDATA: usize = 2;
+const PTRACE_PEEKUSER: usize = 3;
+const PTRACE_POKETEXT: usize = 4;
+const PTRACE_POKEDATA: usize = 5;
+const PTRACE_POKEUSER: usize = 6;
+const PTRACE_CONT: usize = 7;
+const PTRACE_KILL: usize = 8;
+const PTRACE_SINGLESTEP: usize = 9;
+const PTRACE_ATTACH: usize = 16;
+const PTRACE_DETACH: usize = 17;
+const PTRACE_SYSCALL: usize = 24;
+const PTRACE_SETOPTIONS: usize = 0x4200;
+const PTRACE_GETEVENTMSG: usize = 0x4201;
+const PTRACE_GETSIGINFO: usize = 0x4202;
+const PTRACE_GETREGSET: usize = 0x4204;
+const PTRACE_SETREGSET: usize = 0x4205;
+const PTRACE_SEIZE: usize = 0x4206;
+const PTRACE_INTERRUPT: usize = 0x4207;
+
+/// 跟踪停止对应的事件，位于 wait 状态值的 16~23 位
+pub const PTRACE_EVENT_FORK: usize = 1;
+pub const PTRACE_EVENT_VFORK: usize = 2;
+pub const PTRACE_EVENT_CLONE: usize = 3;
+pub const PTRACE_EVENT_EXEC: usize = 4;
+pub const PTRACE_EVENT_EXIT: usize = 6;
+pub const PTRACE_EVENT_STOP: usize = 128;
+
+/// PTRACE_GETREGSET 与 PTRACE_SETREGSET 支持的寄存器组
+const NT_PRSTATUS: usize = 1;
+const NT_PRFPREG: usize = 2;
+
+/// 最大的信号编号
+const SIGRTMAX: usize = 64;
+
+bitflags! {
+    /// PTRACE_SETOPTIONS 设置的选项
+    pub struct PtraceOptions: usize {
+        /// 系统调用停止时报告的信号为 SIGTRAP | 0x80
+        const TRACESYSGOOD = 0x1;
+        /// fork 时停止，并自动跟踪新的子进程
+        const TRACEFORK = 0x2;
+        /// vfork 时停止，并自动跟踪新的子进程
+        const TRACEVFORK = 0x4;
+        /// clone 时停止，并自动跟踪新的子进程
+        const TRACECLONE = 0x8;
+        /// exec 成功后停止
+        const TRACEEXEC = 0x10;
+        /// vfork 的子进程释放父进程时停止，Alien 中不会产生该事件
+        const TRACEVFORKDONE = 0x20;
+        /// 退出前停止
+        const TRACEEXIT = 0x40;
+        /// 跟踪者退出时杀死被跟踪者
+        const EXITKILL = 0x10_0000;
+    }
+}
+
+/// 被跟踪者恢复运行的方式
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum ResumeMode {
+    /// 直到下一次收到信号或发生事件时停止
+    Cont,
+    /// 同时在进出系统调用时停止
+    Syscall,
+    /// 执行一条指令后停止
+    SingleStep,
+}
+
+/// 跟踪停止的原因
+#[derive(Debug, Copy, Clone)]
+enum PtraceStop {
+    /// 收到信号
+    Signal(usize),
+    /// 进入或退出系统调用
+    Syscall,
+    /// 发生事件
+    Event(usize),
+}
+
+/// 被跟踪者的跟踪状态
+#[derive(Debug, Clone)]
+pub struct PtraceState {
+    /// 跟踪者的进程号
+    tracer: usize,
+    options: PtraceOptions,
+    /// 是否通过 PTRACE_SEIZE 建立的跟踪关系
+    seized: bool,
+    /// 当前所处的跟踪停止
+    stop: Option<PtraceStop>,
+    /// 当前的跟踪停止是否已经被跟踪者通过 wait 获取
+    reported: bool,
+    /// 跟踪者让被跟踪者恢复运行时注入的信号，0 表示不注入信号
+    resume: Option<usize>,
+    mode: ResumeMode,
+    /// 跟踪者通过 PTRACE_INTERRUPT 请求被跟踪者停止
+    interrupt: bool,
+    /// PTRACE_GETEVENTMSG 返回的值
+    event_msg: usize,
+    /// 单步执行时写入的临时断点及其原来的指令
+    step_breaks: Vec<(usize, u16)>,
+}
+
+impl PtraceState {
+    fn new(tracer: usize, options: PtraceOptions, seized: bool) -> Self {
+        Self {
+            tracer,
+            options,
+            seized,
+            stop: None,
+            reported: false,
+            resume: None,
+            mode: ResumeMode::Cont,
+            interrupt: false,
+            event_msg: 0,
+            step_breaks: Vec::new(),
+        }
+    }
+
+    /// 当前跟踪停止的 wait 状态值与停止信号
+    fn stop_status(&self) -> Option<(i32, i32)> {
+        let trap = SignalNumber::SIGTRAP as i32;
+        let (status, sig) = match self.stop? {
+            PtraceStop::Signal(sig) => (sig as i32, sig as i32),
+            PtraceStop::Syscall if self.options.contains(PtraceOptions::TRACESYSGOOD) => {
+                (trap | 0x80, trap | 0x80)
+            }
+            PtraceStop::Syscall => (trap, trap),
+            PtraceStop::Event(event) => (trap | (event as i32) << 8, trap),
+        };
+        Some(((status << 8) | 0x7f, sig))
+    }
+
+    /// 当前跟踪停止对应的信号信息
+    fn siginfo(&self) -> Option<SigInfo> {
+        let (status, sig) = self.stop_status()?;
+        let mut info = SigInfo::default();
+        info.si_signo = sig & 0x7f;
+        // 系统调用停止与事件停止的 si_code 为停止时的状态值
+        if !matches!(self.stop, Some(PtraceStop::Signal(_))) {
+            info.si_code = status >> 8;
+        }
+        Some(info)
+    }
+}
+
+/// 正在被跟踪的任务，以 tid 为键
+static TRACEES: Mutex<BTreeMap<usize, Arc<Task>>> = Mutex::new(BTreeMap::new());
+
+/// 处于跟踪停止、等待跟踪者让其恢复运行的任务
+static STOPPED_TRACEES: Mutex<Vec<Arc<Task>>> = Mutex::new(Vec::new());
+
+/// 将任务移出 [`STOPPED_TRACEES`]，返回任务是否仍在其中(即还没有被唤醒)
+fn remove_stopped(task: &Arc<Task>) -> bool {
+    let mut stopped = STOPPED_TRACEES.lock();
+    let len = stopped.len();
+    stopped.retain(|tracee| !Arc::ptr_eq(tracee, task));
+    stopped.len() != len
+}
+
+/// 唤醒处于跟踪停止的任务 `tracee`，使其重新检查是否可以恢复运行
+fn wake_tracee(tracee: &Arc<Task>) {
+    if remove_stopped(tracee) {
+        GLOBAL_TASK_MANAGER.add_task(Arc::new(FifoTask::new(tracee.clone())));
+    }
+}
+
+/// 唤醒 tid 或 pid 为 `id` 的处于跟踪停止的任务。在发送 SIGKILL 时调用，使被跟踪者可以退出
+pub fn wake_stopped_tracees(id: usize) {
+    let woken = STOPPED_TRACEES
+        .lock()
+        .iter()
+        .filter(|task| task.get_tid() as usize == id || task.pid == id)
+        .cloned()
+        .collect::<Vec<_>>();
+    woken.iter().for_each(wake_tracee);
+}
+
+/// 返回进程号为 `tracer` 的进程正在跟踪的任务
+pub fn tracees_of(tracer: usize) -> Vec<Arc<Task>> {
+    TRACEES
+        .lock()
+        .values()
+        .filter(|task| {
+            task.access_inner()
+                .ptrace
+                .as_ref()
+                .map_or(false, |state| state.tracer == tracer)
+        })
+        .cloned()
+        .collect()
+}
+
+/// 获取被跟踪者尚未报告给跟踪者的跟踪停止，返回 wait 状态值与停止信号。
+///
+/// `consume` 为真时将跟踪停止标记为已报告。
+pub fn take_ptrace_stop(tracee: &Task, consume: bool) -> Option<(i32, i32)> {
+    let mut inner = tracee.access_inner();
+    let state = inner.ptrace.as_mut()?;
+    if state.reported {
+        return None;
+    }
+    let status = state.stop_status()?;
+    if consume {
+        state.reported = true;
+    }
+    Some(status)
+}
+
+/// 使当前任务 `task` 进入跟踪停止并通知跟踪者，直到跟踪者让其恢复运行或者解除跟踪。
+///
+/// 返回跟踪者注入的信号，0 表示没有注入信号。停止期间收到 SIGKILL 时解除跟踪并退出。
+fn ptrace_stop(task: &Arc<Task>, stop: PtraceStop) -> usize {
+    let tracer = {
+        let mut inner = task.access_inner();
+        let state = match inner.ptrace.as_mut() {
+            Some(state) => state,
+            None => return 0,
+        };
+        state.stop = Some(stop);
+        state.reported = false;
+        state.resume = None;
+        state.tracer
+    };
+    warn!("task {} enter ptrace stop {:?}", task.get_tid(), stop);
+    send_signal(tracer, SignalNumber::SIGCHLD as usize);
+    let resumed = loop {
+        // 先加入停止队列并进入等待状态，再检查是否可以恢复运行，
+        // 这样在检查之后、让出 CPU 之前到来的 PTRACE_CONT 也会把任务重新加入就绪队列
+        {
+            let mut stopped = STOPPED_TRACEES.lock();
+            if !stopped.iter().any(|tracee| Arc::ptr_eq(tracee, task)) {
+                stopped.push(task.clone());
+            }
+        }
+        task.wait_interruptible();
+        let receiver = task.access_inner().signal_receivers.clone();
+        if receiver.lock().check_signal(SignalNumber::SIGKILL as usize) {
+            break None;
+        }
+        let mut inner = task.access_inner();
+        match inner.ptrace.as_mut() {
+            // 跟踪者已经解除了跟踪
+            None => break Some(0),
+            Some(state) => {
+                if let Some(sig) = state.resume.take() {
+                    state.stop = None;
+                    break Some(sig);
+                }
+            }
+        }
+        drop(inner);
+        schedule();
+    };
+    // 已经被唤醒的任务在就绪队列中还有一项，需要让出一次 CPU 将其消耗掉
+    if remove_stopped(task) {
+        task.update_state(TaskState::Running);
+    } else {
+        schedule();
+    }
+    match resumed {
+        Some(sig) => sig,
+        None => {
+            detach(task);
+            do_exit_by_signal(SignalNumber::SIGKILL as usize, false);
+            0
+        }
+    }
+}
+
+/// 被跟踪的任务在处理信号 `signum` 前进入跟踪停止，由跟踪者决定实际处理的信号。
+///
+/// 返回需要处理的信号，`None` 表示跟踪者抑制了该信号。未被跟踪的任务直接返回 `signum`。
+pub fn ptrace_signal_stop(task: &Arc<Task>, signum: usize) -> Option<usize> {
+    if task.access_inner().ptrace.is_none() || signum == SignalNumber::SIGKILL as usize {
+        return Some(signum);
+    }
+    match ptrace_stop(task, PtraceStop::Signal(signum)) {
+        0 => None,
+        sig => Some(sig),
+    }
+}
+
+/// 处理跟踪者通过 PTRACE_INTERRUPT 发出的停止请求
+pub fn ptrace_check_interrupt(task: &Arc<Task>) {
+    let interrupt = task
+        .access_inner()
+        .ptrace
+        .as_mut()
+        .map_or(false, |state| core::mem::take(&mut state.interrupt));
+    if interrupt {
+        ptrace_stop(task, PtraceStop::Event(PTRACE_EVENT_STOP));
+    }
+}
+
+/// 当前任务以 PTRACE_SYSCALL 方式被跟踪时，在进入或退出系统调用时进入跟踪停止
+pub fn ptrace_syscall_stop() {
+    let task = current_task().unwrap();
+    let traced = task
+        .access_inner()
+        .ptrace
+        .as_ref()
+        .map_or(false, |state| state.mode == ResumeMode::Syscall);
+    if traced {
+        ptrace_stop(task, PtraceStop::Syscall);
+    }
+}
+
+/// 被跟踪的任务发生事件 `event` 时，如果跟踪者设置了对应的选项，则进入跟踪停止
+pub fn ptrace_event(task: &Arc<Task>, event: usize, msg: usize) {
+    let option = match event {
+        PTRACE_EVENT_FORK => PtraceOptions::TRACEFORK,
+        PTRACE_EVENT_VFORK => PtraceOptions::TRACEVFORK,
+        PTRACE_EVENT_CLONE => PtraceOptions::TRACECLONE,
+        PTRACE_EVENT_EXEC => PtraceOptions::TRACEEXEC,
+        PTRACE_EVENT_EXIT => PtraceOptions::TRACEEXIT,
+        _ => return,
+    };
+    {
+        let mut inner = task.access_inner();
+        match inner.ptrace.as_mut() {
+            Some(state) if state.options.contains(option) => state.event_msg = msg,
+            _ => return,
+        }
+    }
+    // 事件停止时跟踪者注入的信号被忽略
+    ptrace_stop(task, PtraceStop::Event(event));
+}
+
+/// 被跟踪的任务 `task` 创建了新任务 `child` 时，如果跟踪者设置了对应的选项，则自动跟踪新任务。
+///
+/// 需要在新任务开始运行前调用，返回需要报告的事件。
+pub fn ptrace_clone(
+    task: &Arc<Task>,
+    child: &Arc<Task>,
+    flag: CloneFlags,
+    sig: SignalNumber,
+) -> Option<usize> {
+    let state = task.access_inner().ptrace.clone()?;
+    let (event, option) = if flag.contains(CloneFlags::CLONE_VFORK) {
+        (PTRACE_EVENT_VFORK, PtraceOptions::TRACEVFORK)
+    } else if sig == SignalNumber::SIGCHLD {
+        (PTRACE_EVENT_FORK, PtraceOptions::TRACEFORK)
+    } else {
+        (PTRACE_EVENT_CLONE, PtraceOptions::TRACECLONE)
+    };
+    if !state.options.contains(option) {
+        return None;
+    }
+    let mut child_state = PtraceState::new(state.tracer, state.options, state.seized);
+    if state.seized {
+        child_state.interrupt = true;
+    } else {
+        send_signal(child.get_tid() as usize, SignalNumber::SIGSTOP as usize);
+    }
+    child.access_inner().ptrace = Some(child_state);
+    TRACEES
+        .lock()
+        .insert(child.get_tid() as usize, child.clone());
+    Some(event)
+}
+
+/// 被跟踪的任务 exec 成功后，报告 PTRACE_EVENT_EXEC；没有设置该选项且不是通过 PTRACE_SEIZE 跟踪时发送 SIGTRAP
+pub fn ptrace_exec(task: &Arc<Task>) {
+    let legacy = {
+        let mut inner = task.access_inner();
+        let state = match inner.ptrace.as_mut() {
+            Some(state) => state,
+            None => return,
+        };
+        // 临时断点所在的地址空间已经被替换
+        state.step_breaks.clear();
+        !state.seized && !state.options.contains(PtraceOptions::TRACEEXEC)
+    };
+    if legacy {
+        send_signal(task.get_tid() as usize, SignalNumber::SIGTRAP as usize);
+    } else {
+        ptrace_event(task, PTRACE_EVENT_EXEC, task.pid);
+    }
+}
+
+/// 任务退出时处理跟踪关系。
+///
+/// 被跟踪者在跟踪者设置了 PTRACE_O_TRACEEXIT 时先进入跟踪停止，然后解除跟踪；
+/// 跟踪者退出时解除其对其它任务的跟踪，设置了 PTRACE_O_EXITKILL 的被跟踪者会被杀死。
+pub fn ptrace_exit(task: &Arc<Task>, exit_code: i32) {
+    if task.access_inner().ptrace.is_some() {
+        ptrace_event(task, PTRACE_EVENT_EXIT, exit_code as usize);
+        detach(task);
+    }
+    if task.get_tid() as usize != task.pid {
+        return;
+    }
+    for tracee in tracees_of(task.pid) {
+        let kill = tracee
+            .access_inner()
+            .ptrace
+            .as_ref()
+            .map_or(false, |state| {
+                state.options.contains(PtraceOptions::EXITKILL)
+            });
+        detach(&tracee);
+        if kill {
+            send_signal(tracee.get_tid() as usize, SignalNumber::SIGKILL as usize);
+        }
+    }
+}
+
+/// 用户态执行了断点指令。如果这是单步执行写入的临时断点，则移除所有临时断点
+pub fn ptrace_breakpoint(task: &Arc<Task>, pc: usize) {
+    let hit = task.access_inner().ptrace.as_ref().map_or(false, |state| {
+        state.step_breaks.iter().any(|&(addr, _)| addr == pc)
+    });
+    if hit {
+        remove_step_breakpoints(task);
+    }
+}
+
+/// 解除对 `tracee` 的跟踪
+fn detach(tracee: &Arc<Task>) {
+    remove_step_breakpoints(tracee);
+    tracee.access_inner().ptrace = None;
+    TRACEES.lock().remove(&(tracee.get_tid() as usize));
+    wake_tracee(tracee);
+}
+
+/// 获取当前任务正在跟踪的 tid 为 `tid` 的任务。`stopped` 为真时要求被跟踪者处于跟踪停止，否则返回 ESRCH
+fn get_tracee(tid: usize, stopped: bool) -> AlienResult<Arc<Task>> {
+    let tracer = current_task().unwrap().pid;
+    let tracee = TRACEES.lock().get(&tid).cloned().ok_or(LinuxErrno::ESRCH)?;
+    let valid = tracee
+        .access_inner()
+        .ptrace
+        .as_ref()
+        .map_or(false, |state| {
+            state.tracer == tracer && (!stopped || state.stop.is_some())
+        });
+    if valid {
+        Ok(tracee)
+    } else {
+        Err(LinuxErrno::ESRCH)
+    }
+}
+
+/// 进程号为 `pid` 的进程是否是 `task` 的祖先
+fn is_ancestor(pid: usize, task: &Arc<Task>) -> bool {
+    let mut parent = task
+        .access_inner()
+        .parent
+        .as_ref()
+        .and_then(|parent| parent.upgrade());
+    while let Some(task) = parent {
+        if task.pid == pid {
+            return true;
+        }
+        parent = task
+            .access_inner()
+            .parent
+            .as_ref()
+            .and_then(|parent| parent.upgrade());
+    }
+    false
+}
+
+/// 当前任务开始跟踪 tid 为 `tid` 的任务。
+///
+/// Alien 没有用户的概念，所有进程都属于同一个用户，因此按照 Linux Yama 的 `ptrace_scope = 1` 的规则，
+/// 只允许跟踪自己的后代进程，否则返回 EPERM；任务已经被跟踪时同样返回 EPERM。
+fn attach(tid: usize, options: PtraceOptions, seized: bool) -> AlienResult<()> {
+    let tracer = current_task().unwrap();
+    let tracee = find_task(tid).ok_or(LinuxErrno::ESRCH)?;
+    if tracee.pid == tracer.pid || tracee.pid == 1 || !is_ancestor(tracer.pid, &tracee) {
+        return Err(LinuxErrno::EPERM);
+    }
+    if matches!(tracee.state(), TaskState::Zombie | TaskState::Terminated) {
+        return Err(LinuxErrno::ESRCH);
+    }
+    {
+        let mut inner = tracee.access_inner();
+        if inner.ptrace.is_some() {
+            return Err(LinuxErrno::EPERM);
+        }
+        inner.ptrace = Some(PtraceState::new(tracer.pid, options, seized));
+    }
+    TRACEES.lock().insert(tid, tracee.clone());
+    if !seized {
+        send_signal(tid, SignalNumber::SIGSTOP as usize);
+    }
+    Ok(())
+}
+
+/// 读取 `user_regs_struct` 中下标为 `index` 的寄存器，下标 0 为 pc，其余为对应的通用寄存器
+fn read_reg(frame: &mut TrapFrame, index: usize) -> usize {
+    if index == 0 {
+        frame.sepc()
+    } else {
+        frame.regs()[index]
+    }
+}
+
+/// 修改 `user_regs_struct` 中下标为 `index` 的寄存器
+fn write_reg(frame: &mut TrapFrame, index: usize, value: usize) {
+    if index == 0 {
+        frame.set_sepc(value);
+    } else {
+        frame.regs()[index] = value;
+    }
+}
+
+/// PTRACE_GETREGSET 以 NT_PRFPREG 读取的浮点寄存器组
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+struct UserFpRegs {
+    f: [u64; 32],
+    fcsr: u32,
+}
+
+/// 计算被跟踪者从 `pc` 处执行一条指令后可能到达的位置
+fn next_pcs(tracee: &Task, pc: usize) -> AlienResult<Vec<usize>> {
+    let mut regs = [0usize; 32];
+    regs.copy_from_slice(tracee.trap_frame().regs());
+    regs[0] = 0;
+    let low = tracee.copy_from_user(pc as *const u16)?;
+    let insn = if arch::insn_len(low) == 2 {
+        low as u32
+    } else {
+        tracee.copy_from_user(pc as *const u32)?
+    };
+    let (next, target) = arch::next_pcs(pc, insn, &regs);
+    let mut targets = vec![next];
+    targets.extend(target);
+    Ok(targets)
+}
+
+/// 在被跟踪者下一条指令所有可能到达的位置写入临时断点
+fn insert_step_breakpoints(tracee: &Arc<Task>) -> AlienResult<()> {
+    let pc = tracee.trap_frame().sepc();
+    let mut targets = next_pcs(tracee, pc)?;
+    targets.dedup();
+    let mut breaks = Vec::new();
+    for addr in targets {
+        let origin = tracee.copy_from_user(addr as *const u16)?;
+        tracee.poke_user(addr, &C_EBREAK.to_ne_bytes())?;
+        breaks.push((addr, origin));
+    }
+    if let Some(state) = tracee.access_inner().ptrace.as_mut() {
+        state.step_breaks = breaks;
+    }
+    Ok(())
+}
+
+/// 恢复被临时断点覆盖的指令
+fn remove_step_breakpoints(tracee: &Arc<Task>) {
+    let breaks = match tracee.access_inner().ptrace.as_mut() {
+        Some(state) => core::mem::take(&mut state.step_breaks),
+        None => return,
+    };
+    // 写入顺序与插入顺序相反，保证同一地址最终恢复为原来的指令
+    for (addr, origin) in breaks.into_iter().rev() {
+        let _ = tracee.poke_user(addr, &origin.to_ne_bytes());
+    }
+}
+
+/// 让处于跟踪停止的被跟踪者以 `mode` 方式恢复运行，并注入信号 `sig`
+fn resume(tracee: &Arc<Task>, mode: ResumeMode, sig: usize) -> AlienResult<()> {
+    if sig > SIGRTMAX {
+        return Err(LinuxErrno::EIO);
+    }
+    remove_step_breakpoints(tracee);
+    if mode == ResumeMode::SingleStep {
+        insert_step_breakpoints(tracee)?;
+    }
+    {
+        let mut inner = tracee.access_inner();
+        let state = inner.ptrace.as_mut().ok_or(LinuxErrno::ESRCH)?;
+        state.mode = mode;
+        state.resume = Some(sig);
+    }
+    wake_tracee(tracee);
+    Ok(())
+}
+
+/// 读取被跟踪者的寄存器组 `kind` 到 `iov` 指向的缓冲区中，并将实际读取的长度写回 `iov`
+fn get_regset(tracee: &Arc<Task>, kind: usize, iov: *mut IoVec) -> AlienResult<()> {
+    let task = current_task().unwrap();
+    let mut iovec = task.copy_from_user(iov)?;
+    let frame = tracee.trap_frame();
+    let len = match kind {
+        NT_PRSTATUS => {
+            let mut regs = [0usize; 32];
+            regs.iter_mut()
+                .enumerate()
+                .for_each(|(i, reg)| *reg = read_reg(frame, i));
+            let len = core::cmp::min(iovec.len, core::mem::size_of_val(&regs));
+            let bytes = unsafe { core::slice::from_raw_parts(regs.as_ptr() as *const u8, len) };
+            task.copy_to_user_buffer(bytes, iovec.base as *mut u8)?;
+            len
+        }
+        NT_PRFPREG => {
+            let (f, fcsr) = tracee.access_inner().fp_context.registers();
+            let regs = UserFpRegs {
+                f,
+                fcsr: fcsr as u32,
+            };
+            let len = core::cmp::min(iovec.len, core::mem::size_of::<UserFpRegs>());
+            let bytes = unsafe {
+                core::slice::from_raw_parts(&regs as *const UserFpRegs as *const u8, len)
+            };
+            task.copy_to_user_buffer(bytes, iovec.base as *mut u8)?;
+            len
+        }
+        _ => return Err(LinuxErrno::EINVAL),
+    };
+    iovec.len = len;
+    task.copy_to_user(&iovec, iov)
+}
+
+/// 用 `iov` 指向的缓冲区修改被跟踪者的寄存器组 `kind`
+fn set_regset(tracee: &Arc<Task>, kind: usize, iov: *const IoVec) -> AlienResult<()> {
+    let task = current_task().unwrap();
+    let iovec = task.copy_from_user(iov)?;
+    match kind {
+        NT_PRSTATUS => {
+            let mut regs = [0usize; 32];
+            let frame = tracee.trap_frame();
+            regs.iter_mut()
+                .enumerate()
+                .for_each(|(i, reg)| *reg = read_reg(frame, i));
+            let len = core::cmp::min(iovec.len, core::mem::size_of_val(&regs));
+            let bytes =
+                unsafe { core::slice::from_raw_parts_mut(regs.as_mut_ptr() as *mut u8, len) };
+            task.copy_from_user_buffer(iovec.base as *const u8, bytes)?;
+            regs.iter()
+                .enumerate()
+                .for_each(|(i, reg)| write_reg(frame, i, *reg));
+        }
+        NT_PRFPREG => {
+            if iovec.len < core::mem::size_of::<UserFpRegs>() {
+                return Err(LinuxErrno::EINVAL);
+            }
+            let regs = task.copy_from_user(iovec.base as *const UserFpRegs)?;
+            tracee
+                .access_inner()
+                .fp_context
+                .set_registers(regs.f, regs.fcsr as usize);
+        }
+        _ => return Err(LinuxErrno::EINVAL),
+    }
+    Ok(())
+}
+
+/// 一个系统调用，用于跟踪其它任务，观察和控制其执行，读写其内存与寄存器。
+///
+/// `request` 指明请求的类型，`pid` 指明被跟踪者的 tid，`addr` 与 `data` 的含义与请求的类型有关：
+/// + `PTRACE_TRACEME`: 当前任务被其父进程跟踪；
+/// + `PTRACE_ATTACH` / `PTRACE_SEIZE`: 开始跟踪 `pid`，前者会向被跟踪者发送 SIGSTOP，后者的 `data` 为跟踪选项；
+/// + `PTRACE_PEEKTEXT` / `PTRACE_PEEKDATA` / `PTRACE_PEEKUSER`: 读取 `addr` 处的内存或偏移为 `addr` 的寄存器，结果写入 `data` 指向的位置；
+/// + `PTRACE_POKETEXT` / `PTRACE_POKEDATA` / `PTRACE_POKEUSER`: 将 `data` 写入 `addr` 处的内存或偏移为 `addr` 的寄存器；
+/// + `PTRACE_GETREGSET` / `PTRACE_SETREGSET`: 读写 `addr` 指定的寄存器组(`NT_PRSTATUS` 或 `NT_PRFPREG`)，`data` 指向 `iovec`；
+/// + `PTRACE_CONT` / `PTRACE_SYSCALL` / `PTRACE_SINGLESTEP`: 让被跟踪者恢复运行，`data` 为注入的信号；
+/// + `PTRACE_SETOPTIONS` / `PTRACE_GETEVENTMSG` / `PTRACE_GETSIGINFO`: 设置跟踪选项，获取事件的附加信息或停止信号的信息；
+/// + `PTRACE_KILL` / `PTRACE_INTERRUPT` / `PTRACE_DETACH`: 杀死被跟踪者，使其停止，或解除跟踪。
+///
+/// 除 `PTRACE_TRACEME`、`PTRACE_ATTACH`、`PTRACE_SEIZE`、`PTRACE_KILL`、`PTRACE_INTERRUPT` 外，
+/// 被跟踪者必须处于跟踪停止，否则返回 `ESRCH`。
+///
+/// Reference: [ptrace](https://man7.org/linux/man-pages/man2/ptrace.2.html)
+#[syscall_func(117)]
+pub fn ptrace(request: usize, pid: usize, addr: usize, data: usize) -> AlienResult<isize> {
+    let task = current_task().unwrap();
+    match request {
+        PTRACE_TRACEME => {
+            let tracer = task
+                .access_inner()
+                .parent
+                .as_ref()
+                .and_then(|parent| parent.upgrade())
+                .ok_or(LinuxErrno::EPERM)?;
+            let mut inner = task.access_inner();
+            if inner.ptrace.is_some() {
+                return Err(LinuxErrno::EPERM);
+            }
+            inner.ptrace = Some(PtraceState::new(tracer.pid, PtraceOptions::empty(), false));
+            drop(inner);
+            TRACEES.lock().insert(task.get_tid() as usize, task.clone());
+        }
+        PTRACE_ATTACH => attach(pid, PtraceOptions::empty(), false)?,
+        PTRACE_SEIZE => {
+            let options = PtraceOptions::from_bits(data).ok_or(LinuxErrno::EINVAL)?;
+            attach(pid, options, true)?;
+        }
+        PTRACE_PEEKTEXT | PTRACE_PEEKDATA => {
+            let tracee = get_tracee(pid, true)?;
+            let value = tracee.copy_from_user(addr as *const usize)?;
+            task.copy_to_user(&value, data as *mut usize)?;
+        }
+        PTRACE_PEEKUSER => {
+            let tracee = get_tracee(pid, true)?;
+            if addr % 8 != 0 || addr / 8 >= 32 {
+                return Err(LinuxErrno::EIO);
+            }
+            let value = read_reg(tracee.trap_frame(), addr / 8);
+            task.copy_to_user(&value, data as *mut usize)?;
+        }
+        PTRACE_POKETEXT | PTRACE_POKEDATA => {
+            let tracee = get_tracee(pid, true)?;
+            tracee.poke_user(addr, &data.to_ne_bytes())?;
+        }
+        PTRACE_POKEUSER => {
+            let tracee = get_tracee(pid, true)?;
+            if addr % 8 != 0 || addr / 8 >= 32 {
+                return Err(LinuxErrno::EIO);
+            }
+            write_reg(tracee.trap_frame(), addr / 8, data);
+        }
+        PTRACE_GETREGSET => {
+            let tracee = get_tracee(pid, true)?;
+            get_regset(&tracee, addr, data as *mut IoVec)?;
+        }
+        PTRACE_SETREGSET => {
+            let tracee = get_tracee(pid, true)?;
+            set_regset(&tracee, addr, data as *const IoVec)?;
+        }
+        PTRACE_CONT => resume(&get_tracee(pid, true)?, ResumeMode::Cont, data)?,
+        PTRACE_SYSCALL => resume(&get_tracee(pid, true)?, ResumeMode::Syscall, data)?,
+        PTRACE_SINGLESTEP => resume(&get_tracee(pid, true)?, ResumeMode::SingleStep, data)?,
+        PTRACE_SETOPTIONS => {
+            let tracee = get_tracee(pid, true)?;
+            let options = PtraceOptions::from_bits(data).ok_or(LinuxErrno::EINVAL)?;
+            if let Some(state) = tracee.access_inner().ptrace.as_mut() {
+                state.options = options;
+            }
+        }
+        PTRACE_GETEVENTMSG => {
+            let tracee = get_tracee(pid, true)?;
+            let msg = tracee
+                .access_inner()
+                .ptrace
+                .as_ref()
+                .map_or(0, |state| state.event_msg);
+            task.copy_to_user(&msg, data as *mut usize)?;
+        }
+        PTRACE_GETSIGINFO => {
+            let tracee = get_tracee(pid, true)?;
+            let info = tracee
+                .access_inner()
+                .ptrace
+                .as_ref()
+                .and_then(|state| state.siginfo())
+                .ok_or(LinuxErrno::ESRCH)?;
+            task.copy_to_user(&info, data as *mut SigInfo)?;
+        }
+        PTRACE_KILL => {
+            let tracee = get_tracee(pid, false)?;
+            send_signal(tracee.get_tid() as usize, SignalNumber::SIGKILL as usize);
+        }
+        PTRACE_INTERRUPT => {
+            let tracee = get_tracee(pid, false)?;
+            let mut inner = tracee.access_inner();
+            match inner.ptrace.as_mut() {
+                Some(state) if state.seized => state.interrupt = true,
+                _ => return Err(LinuxErrno::EIO),
+            }
+        }
+        PTRACE_DETACH => {
+            let tracee = get_tracee(pid, true)?;
+            if data > SIGRTMAX {
+                return Err(LinuxErrno::EIO);
+            }
+            detach(&tracee);
+            if data != 0 {
+                send_signal(pid, data);
+            }
+        }
+        _ => return Err(LinuxErrno::EIO),
+    }
+    Ok(0)
+}
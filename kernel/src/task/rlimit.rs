@@ -0,0 +1,90 @@
+//! 记录进程的资源限制
+use config::{MAX_FD_NUM, MAX_THREAD_NUM, USER_STACK_SIZE};
+use constants::signal::SignalNumber;
+use constants::{AlienResult, LinuxErrno, PrLimit, PrLimitRes, RLIM_INFINITY, RLIM_NLIMITS};
+
+/// 一种资源的软上限和硬上限
+#[derive(Debug, Copy, Clone)]
+struct RLimit {
+    cur: u64,
+    max: u64,
+}
+
+impl RLimit {
+    const fn new(cur: u64, max: u64) -> Self {
+        Self { cur, max }
+    }
+
+    const fn infinity() -> Self {
+        Self::new(RLIM_INFINITY, RLIM_INFINITY)
+    }
+}
+
+/// 记录进程的资源限制，同一线程组中的线程共享同一份资源限制，fork 和 exec 时资源限制被继承
+#[derive(Debug, Clone)]
+pub struct ResourceLimits {
+    /// 以 [`PrLimitRes`] 为下标的各资源的限制
+    limits: [RLimit; RLIM_NLIMITS],
+    /// 最近一次因超过 RLIMIT_CPU 软上限而发送 SIGXCPU 时，进程已经使用的 CPU 时间(秒)
+    xcpu_sent: Option<u64>,
+}
+
+impl ResourceLimits {
+    /// 新建一个具有默认资源限制的 ResourceLimits
+    pub fn new() -> Self {
+        let mut limits = [RLimit::infinity(); RLIM_NLIMITS];
+        limits[PrLimitRes::RlimitStack as usize] =
+            RLimit::new(USER_STACK_SIZE as u64, USER_STACK_SIZE as u64);
+        limits[PrLimitRes::RlimitCore as usize] = RLimit::new(0, RLIM_INFINITY);
+        limits[PrLimitRes::RlimitNproc as usize] =
+            RLimit::new(MAX_THREAD_NUM as u64, MAX_THREAD_NUM as u64);
+        limits[PrLimitRes::RlimitNofile as usize] =
+            RLimit::new(MAX_FD_NUM as u64, MAX_FD_NUM as u64);
+        limits[PrLimitRes::RlimitMemlock as usize] = RLimit::new(0x80_0000, 0x80_0000);
+        limits[PrLimitRes::RlimitMsgqueue as usize] = RLimit::new(819200, 819200);
+        limits[PrLimitRes::RlimitNice as usize] = RLimit::new(0, 0);
+        limits[PrLimitRes::RlimitRtprio as usize] = RLimit::new(0, 0);
+        Self {
+            limits,
+            xcpu_sent: None,
+        }
+    }
+
+    /// 获取资源 `resource` 的限制
+    pub fn get(&self, resource: PrLimitRes) -> PrLimit {
+        let limit = self.limits[resource as usize];
+        PrLimit::new(limit.cur, limit.max)
+    }
+
+    /// 获取资源 `resource` 的软上限
+    pub fn cur(&self, resource: PrLimitRes) -> u64 {
+        self.limits[resource as usize].cur
+    }
+
+    /// 设置资源 `resource` 的限制，软上限大于硬上限时返回 EINVAL
+    pub fn set(&mut self, resource: PrLimitRes, value: PrLimit) -> AlienResult<()> {
+        if value.rlim_cur > value.rlim_max {
+            return Err(LinuxErrno::EINVAL);
+        }
+        self.limits[resource as usize] = RLimit::new(value.rlim_cur, value.rlim_max);
+        if resource == PrLimitRes::RlimitCpu {
+            self.xcpu_sent = None;
+        }
+        Ok(())
+    }
+
+    /// 检查已经使用 `secs` 秒 CPU 时间的进程是否超过了 RLIMIT_CPU，返回需要向进程发送的信号。
+    ///
+    /// 超过硬上限时发送 SIGKILL；超过软上限后每多使用一秒 CPU 时间发送一次 SIGXCPU。
+    pub fn check_cpu(&mut self, secs: u64) -> Option<SignalNumber> {
+        let limit = self.limits[PrLimitRes::RlimitCpu as usize];
+        if secs >= limit.max {
+            return Some(SignalNumber::SIGKILL);
+        }
+        if secs >= limit.cur && self.xcpu_sent.map_or(true, |sent| secs > sent) {
+            self.xcpu_sent = Some(secs);
+            return Some(SignalNumber::SIGXCPU);
+        }
+        None
+    }
+}
@@ -9,7 +9,7 @@ use crate::ipc::send_signal;
 use crate::task::context::switch;
 use crate::task::cpu::current_cpu;
 use crate::task::task::TaskState;
-use crate::task::GLOBAL_TASK_MANAGER;
+use crate::task::{touch_softlockup_watchdog, wake_child_waiters, GLOBAL_TASK_MANAGER};
 use crate::trap::check_timer_interrupt_pending;
 
 /// 在 CPU 启动并初始化完毕后初次进入用户态时，或者在一个任务将要让渡 CPU 时 将会执行该函数。
@@ -31,18 +31,21 @@ pub fn run_task() -> ! {
                     // drop(task);
                 }
                 TaskState::Zombie => {
-                    // 退出时向父进程发送信号，其中选项可被 sys_clone 控制
-                    if task.send_sigchld_when_exit || task.pid == task.tid.0 {
-                        let parent = task
-                            .access_inner()
-                            .parent
-                            .as_ref()
-                            .unwrap()
-                            .upgrade()
-                            .unwrap();
-                        send_signal(parent.pid, SignalNumber::SIGCHLD as usize);
-                    }
+                    let parent = task
+                        .access_inner()
+                        .parent
+                        .as_ref()
+                        .and_then(|parent| parent.upgrade());
+                    let send_sigchld = task.send_sigchld_when_exit || task.pid == task.tid.0;
+                    // 先将任务置为 Terminated，被唤醒的父进程才能在 wait 中回收该任务
                     task.terminate();
+                    if let Some(parent) = parent {
+                        // 退出时向父进程发送信号，其中选项可被 sys_clone 控制
+                        if send_sigchld {
+                            send_signal(parent.pid, SignalNumber::SIGCHLD as usize);
+                        }
+                        wake_child_waiters(parent.pid);
+                    }
                 }
                 _ => {
                     GLOBAL_TASK_MANAGER.add_task(Arc::new(FifoTask::new(task)));
@@ -57,6 +60,7 @@ pub fn run_task() -> ! {
             task.inner().update_state(TaskState::Running);
             // get the process context
             let context = task.inner().get_context_raw_ptr();
+            touch_softlockup_watchdog();
             cpu.task = Some(task.inner().clone());
             // switch to the process context
             let cpu_context = cpu.get_context_mut_raw_ptr();
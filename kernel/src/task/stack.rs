@@ -1,6 +1,7 @@
 //! 进程内核栈空间
 
 use config::FRAME_SIZE;
+use core::ops::Range;
 use mem::{alloc_frames, free_frames};
 
 /// 记录进程内核栈空间
@@ -26,6 +27,11 @@ impl Stack {
         self.start_ptr + self.pages * FRAME_SIZE
     }
 
+    /// 获取内核栈所在的地址范围
+    pub fn range(&self) -> Range<usize> {
+        self.start_ptr..self.top()
+    }
+
     /// 回收内核栈空间。
     pub fn release(&self) {
         free_frames(self.start_ptr as _, self.pages)
@@ -5,14 +5,16 @@
 //! tid 是标识不同任务的唯一标识。
 use crate::fs::stdio::{STDIN, STDOUT};
 use crate::ipc::{global_register_signals, ShmInfo};
+use crate::mm::elf::ELFError;
 use crate::mm::loader::{
     build_cow_address_space, build_elf_address_space, build_thread_address_space, UserStack,
 };
-use crate::mm::map::{MMapInfo, MMapRegion, ProtFlags};
+use crate::mm::map::{mapping_flags, MMapInfo, MMapRegion, ProtFlags};
 use crate::task::context::Context;
-use crate::task::heap::HeapInfo;
+use crate::task::ptrace::PtraceState;
+use crate::task::rlimit::ResourceLimits;
 use crate::task::stack::Stack;
-use crate::trap::{trap_common_read_file, trap_return, user_trap_vector, TrapFrame};
+use crate::trap::{trap_common_read_file, trap_return, user_trap_vector, FpContext, TrapFrame};
 use alloc::collections::BTreeMap;
 use alloc::string::{String, ToString};
 use alloc::sync::{Arc, Weak};
@@ -31,14 +33,16 @@ use constants::{AlienError, AlienResult};
 use constants::{LinuxErrno, PrLimit, PrLimitRes};
 use core::fmt::{Debug, Formatter};
 use core::ops::Range;
+use core::sync::atomic::{AtomicUsize, Ordering};
 use gmanager::MinimalManager;
 use ksync::{Mutex, MutexGuard};
-use mem::{kernel_satp, VmmPageAllocator, FRAME_REF_MANAGER};
+use mem::mmap::{BrkChange, HeapInfo};
+use mem::{kernel_satp, kernel_space, VmmPageAllocator, FRAME_REF_MANAGER};
 use page_table::addr::{align_down_4k, align_up_4k, VirtAddr};
 use page_table::pte::MappingFlags;
 use page_table::table::Sv39PageTable;
 use spin::Lazy;
-use timer::{read_timer, ITimerVal, TimeNow, ToClock};
+use timer::{get_time_ms, read_timer, ITimerVal, TimeFromFreq, TimeNow, ToClock};
 use vfs::kfile::File;
 use vfscore::dentry::VfsDentry;
 
@@ -49,6 +53,14 @@ type FdManager = MinimalManager<Arc<dyn File>>;
 pub static TID_MANAGER: Lazy<Mutex<MinimalManager<u8>>> =
     Lazy::new(|| Mutex::new(MinimalManager::new(MAX_THREAD_NUM)));
 
+/// 系统中已分配 tid 的线程数量，用于检查 RLIMIT_NPROC
+static TASK_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+/// 返回系统中尚未被回收的线程数量
+pub fn task_count() -> usize {
+    TASK_COUNT.load(Ordering::Relaxed)
+}
+
 /// 用于存储线程的tid
 #[derive(Debug)]
 pub struct TidHandle(pub usize);
@@ -60,6 +72,7 @@ impl TidHandle {
         if tid.is_err() {
             return None;
         }
+        TASK_COUNT.fetch_add(1, Ordering::Relaxed);
         Some(Self(tid.unwrap()))
     }
 }
@@ -67,6 +80,7 @@ impl TidHandle {
 impl Drop for TidHandle {
     fn drop(&mut self) {
         TID_MANAGER.lock().remove(self.0).unwrap();
+        TASK_COUNT.fetch_sub(1, Ordering::Relaxed);
     }
 }
 
@@ -114,10 +128,10 @@ pub struct TaskInner {
     pub timer: TaskTimer,
     /// 返回值
     pub exit_code: i32,
-    /// 堆空间
-    pub heap: Arc<Mutex<HeapInfo>>,
-    /// 地址空间中的映射信息
-    pub mmap: MMapInfo,
+    /// 地址空间中的映射信息，堆作为其中的一个区域记录。与地址空间一样由 CLONE_VM 创建的任务共享
+    pub mmap: Arc<Mutex<MMapInfo>>,
+    /// 资源限制，同一线程组中的线程共享
+    pub rlimits: Arc<Mutex<ResourceLimits>>,
     /// 信号量对应的一组处理函数。
     /// 因为发送信号是通过 pid/tid 查找的，因此放在 inner 中一起调用时更容易导致死锁
     pub signal_handlers: Arc<Mutex<SignalHandlers>>,
@@ -130,6 +144,12 @@ pub struct TaskInner {
     pub clear_child_tid: usize,
     /// 处理信号时，保存的之前的用户线程的上下文信息
     pub trap_cx_before_signal: Option<TrapFrame>,
+    /// 处理信号时，保存的之前的用户线程的浮点上下文
+    pub fp_cx_before_signal: Option<FpContext>,
+    /// 用户态的浮点寄存器上下文
+    pub fp_context: FpContext,
+    /// 被跟踪时的跟踪状态
+    pub ptrace: Option<PtraceState>,
     /// 保存信息时，处理函数是否设置了 SIGINFO 选项
     /// 如果设置了，说明信号触发前的上下文信息通过 ucontext 传递给了用户，
     /// 此时用户可能修改其中的 pc 信息(如musl-libc 的 pthread_cancel 函数)。
@@ -147,6 +167,27 @@ pub struct TaskInner {
     pub stack: Range<usize>,
     /// 是否需要等待
     pub need_wait: u8,
+    /// 进程组号
+    pub pgid: usize,
+    /// 因作业控制信号发生的、尚未被父进程通过 wait 系列系统调用获取的状态变化
+    pub job_event: Option<JobEvent>,
+    /// 进入当前状态的时间(ms)，用于检测长时间处于等待状态的任务
+    pub state_time: usize,
+    /// 处于等待状态时，等待是否可以被信号打断。可以被打断的等待没有时间限制，不会被 watchdog 报告
+    pub interruptible: bool,
+    /// 有效用户 id，执行 set-user-ID 程序时改变
+    pub euid: u32,
+    /// 有效用户组 id，执行 set-group-ID 程序时改变
+    pub egid: u32,
+}
+
+/// 进程因作业控制信号发生的状态变化
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum JobEvent {
+    /// 进程被信号暂停，记录暂停进程的信号
+    Stopped(usize),
+    /// 暂停的进程被 SIGCONT 恢复运行
+    Continued,
 }
 
 #[derive(Debug, Copy, Clone)]
@@ -322,6 +363,11 @@ impl Task {
         self.inner.lock()
     }
 
+    /// 获取 TaskInner，锁被占用时不等待而是返回 None，用于 panic 处理等不能等待锁的场景
+    pub fn try_access_inner(&self) -> Option<MutexGuard<TaskInner>> {
+        self.inner.try_lock()
+    }
+
     /// 获取进程页表的root ppn
     pub fn token(&self) -> usize {
         let inner = self.inner.lock();
@@ -342,7 +388,22 @@ impl Task {
     /// 将进程的状态修改为 state
     pub fn update_state(&self, state: TaskState) {
         let mut inner = self.inner.lock();
+        if inner.state != state {
+            inner.state_time = get_time_ms();
+        }
         inner.state = state;
+        inner.interruptible = false;
+    }
+
+    /// 进入可以被信号打断的等待状态，如等待用户输入、futex 或者子进程退出
+    pub fn wait_interruptible(&self) {
+        self.update_state(TaskState::Waiting);
+        self.inner.lock().interruptible = true;
+    }
+
+    /// 是否为内核线程，内核线程使用内核的地址空间
+    pub fn is_kthread(&self) -> bool {
+        Arc::ptr_eq(&self.inner.lock().address_space, &kernel_space())
     }
 
     /// 返回进程的状态
@@ -375,21 +436,6 @@ impl Task {
         inner.children.clone()
     }
 
-    /// 查找进程号为pid的子进程是否已经退出。当pid为-1时，任意子进程退出都将返回子进程的pid号。
-    pub fn check_child(&self, pid: isize) -> Option<usize> {
-        let res = self
-            .inner
-            .lock()
-            .children
-            .iter()
-            .enumerate()
-            .find(|(_, child)| {
-                child.state() == TaskState::Terminated && (child.get_pid() == pid || pid == -1)
-            })
-            .map(|(index, _)| index);
-        res
-    }
-
     /// 取走当前进程的子进程控制块列表的所有权
     pub fn take_children(&self) -> Vec<Arc<Task>> {
         let children = self.children();
@@ -475,73 +521,184 @@ impl Task {
         Ok(file)
     }
 
-    /// 获取一个虚拟地址 `ptr` 的实际物理地址
-    pub fn transfer_raw(&self, ptr: usize) -> usize {
-        self.access_inner().transfer_raw(ptr)
+    /// 检查用户地址 `addr` 所在的页能否以 `write` 指定的方式访问，必要时处理该页上的延迟映射与写时复制，
+    /// 返回 `addr` 对应的物理地址。
+    ///
+    /// 只有位于进程的内存映射区域、堆或栈中，且带有用户访问权限的页才能被访问，否则返回 EFAULT。
+    /// 与缺页异常处理一致，从文件中读入页内容时不持有 `TaskInner` 的锁。
+    fn user_addr_to_phys(&self, addr: usize, write: bool) -> AlienResult<usize> {
+        let pending = self.access_inner().prepare_user_page(addr, write)?;
+        if let Some((file, buf, offset)) = pending {
+            trap_common_read_file(file, buf, offset);
+        }
+        self.access_inner().checked_user_phys(addr, write)
+    }
+
+    /// 对用户空间中 `[start, start + len)` 范围内的每一页调用 `f(物理地址, 长度)`
+    fn for_each_user_page(
+        &self,
+        start: usize,
+        len: usize,
+        write: bool,
+        mut f: impl FnMut(usize, usize),
+    ) -> AlienResult<()> {
+        let end = start.checked_add(len).ok_or(LinuxErrno::EFAULT)?;
+        let mut addr = start;
+        while addr < end {
+            let bound = core::cmp::min(align_down_4k(addr) + FRAME_SIZE, end);
+            let phy = self.user_addr_to_phys(addr, write)?;
+            f(phy, bound - addr);
+            addr = bound;
+        }
+        Ok(())
     }
 
-    /// 获取一个虚拟地址 `ptr` 对应的 T 类型数据 的 可变引用
-    pub fn transfer_raw_ptr<T>(&self, ptr: *mut T) -> &'static mut T {
-        self.access_inner().transfer_raw_ptr_mut(ptr)
+    /// 获取用户空间中一个虚拟地址 `ptr` 的实际物理地址，`ptr` 不可读时返回 EFAULT
+    pub fn transfer_raw(&self, ptr: usize) -> AlienResult<usize> {
+        self.user_addr_to_phys(ptr, false)
     }
 
-    /// 通过用户地址空间中一个字符串的首指针 `ptr`，获取一个字符串。
-    pub fn transfer_str(&self, ptr: *const u8) -> String {
-        // we need check the ptr and len before transfer indeed
-        let mut start = ptr as usize;
-        let end = start + 128; //todo! string len is unknown
-        let address_space = self.access_inner().address_space.clone();
+    /// 获取用户空间中一个 T 类型数据的可变引用，用于需要原地读写用户数据的场景(如 futex)。
+    ///
+    /// 数据必须位于同一页内且可写，否则返回 EFAULT。只读取或只写入数据时应使用
+    /// [`Task::copy_from_user`] 或 [`Task::copy_to_user`]。
+    pub fn transfer_raw_ptr<T>(&self, ptr: *mut T) -> AlienResult<&'static mut T> {
+        let addr = ptr as usize;
+        let size = core::mem::size_of::<T>();
+        if size != 0 && align_down_4k(addr) != align_down_4k(addr + size - 1) {
+            return Err(LinuxErrno::EFAULT);
+        }
+        let phy = self.user_addr_to_phys(addr, true)?;
+        Ok(unsafe { &mut *(phy as *mut T) })
+    }
+
+    /// 从用户空间的 `src` 处读取一个 T 类型的数据，`T` 应为任意字节序列都是合法值的类型
+    pub fn copy_from_user<T: Copy>(&self, src: *const T) -> AlienResult<T> {
+        let mut value = core::mem::MaybeUninit::<T>::uninit();
+        let dst = value.as_mut_ptr() as *mut u8;
+        let mut copied = 0;
+        self.for_each_user_page(
+            src as usize,
+            core::mem::size_of::<T>(),
+            false,
+            |phy, len| {
+                unsafe { core::ptr::copy_nonoverlapping(phy as *const u8, dst.add(copied), len) };
+                copied += len;
+            },
+        )?;
+        Ok(unsafe { value.assume_init() })
+    }
+
+    /// 将一个 T 类型的数据 `src` 写入用户空间的 `dst` 处
+    pub fn copy_to_user<T>(&self, src: &T, dst: *mut T) -> AlienResult<()> {
+        let src = src as *const T as *const u8;
+        let mut copied = 0;
+        self.for_each_user_page(dst as usize, core::mem::size_of::<T>(), true, |phy, len| {
+            unsafe { core::ptr::copy_nonoverlapping(src.add(copied), phy as *mut u8, len) };
+            copied += len;
+        })
+    }
+
+    /// 从用户空间的 `src` 处读取 `dst.len()` 个 T 类型的数据到 `dst` 中
+    pub fn copy_from_user_buffer<T: Copy>(&self, src: *const T, dst: &mut [T]) -> AlienResult<()> {
+        let size = core::mem::size_of_val(dst);
+        let dst = dst.as_mut_ptr() as *mut u8;
+        let mut copied = 0;
+        self.for_each_user_page(src as usize, size, false, |phy, len| {
+            unsafe { core::ptr::copy_nonoverlapping(phy as *const u8, dst.add(copied), len) };
+            copied += len;
+        })
+    }
+
+    /// 将 `src` 中的数据写入用户空间的 `dst` 处
+    pub fn copy_to_user_buffer<T>(&self, src: &[T], dst: *mut T) -> AlienResult<()> {
+        let size = core::mem::size_of_val(src);
+        let src = src.as_ptr() as *const u8;
+        let mut copied = 0;
+        self.for_each_user_page(dst as usize, size, true, |phy, len| {
+            unsafe { core::ptr::copy_nonoverlapping(src.add(copied), phy as *mut u8, len) };
+            copied += len;
+        })
+    }
+
+    /// 为调试器将 `data` 写入用户空间的 `addr` 处，只读的页(如代码段)也可以写入
+    pub fn poke_user(&self, addr: usize, data: &[u8]) -> AlienResult<()> {
+        // 先处理延迟映射，并确认地址可读
+        self.for_each_user_page(addr, data.len(), false, |_, _| {})?;
+        let end = addr + data.len();
+        let mut inner = self.access_inner();
+        let mut start = addr;
         while start < end {
-            let (_phy, flag, _) = address_space
-                .lock()
-                .query(VirtAddr::from(start))
-                .expect(format!("transfer_buffer: {:x} failed", start).as_str());
-            if !flag.contains(MappingFlags::V) {
-                error!("transfer_str flag: {:?}, addr:{:#x}", flag, start);
-                let res = self
-                    .access_inner()
-                    .invalid_page_solver(align_down_4k(start))
-                    .unwrap();
-                if res.is_some() {
-                    let (file, buf, offset) = res.unwrap();
-                    if file.is_some() {
-                        trap_common_read_file(file.unwrap(), buf, offset);
-                    }
-                }
-            }
-            start += FRAME_SIZE;
+            let bound = core::cmp::min(align_down_4k(start) + FRAME_SIZE, end);
+            let phy = inner.private_user_phys(start)?;
+            let src = &data[start - addr..bound - addr];
+            unsafe { core::ptr::copy_nonoverlapping(src.as_ptr(), phy as *mut u8, src.len()) };
+            start = bound;
         }
-        self.access_inner().transfer_str(ptr)
+        Ok(())
     }
 
-    /// 通过用户地址空间中一个缓冲区的指针 `ptr` 和 缓冲区的长度 `len`，得到一组对用户地址空间中缓冲区的可变引用，每一组引用的长度为 4K
-    pub fn transfer_buffer<T: Debug>(&self, ptr: *const T, len: usize) -> Vec<&'static mut [T]> {
-        // we need check the ptr and len before transfer indeed
-        let mut start = ptr as usize;
-        let end = start + len;
-        let address_space = self.access_inner().address_space.clone();
-        start = align_down_4k(start);
-        while start < end {
-            let (_phy, flag, _) = address_space
-                .lock()
-                .query(VirtAddr::from(start))
-                .expect(format!("transfer_buffer: {:x} failed", start).as_str());
-            if !flag.contains(MappingFlags::V) {
-                error!("transfer_buffer flag: {:?}, addr:{:#x}", flag, start);
-                let res = self
-                    .access_inner()
-                    .invalid_page_solver(align_down_4k(start))
-                    .unwrap();
-                if res.is_some() {
-                    let (file, buf, offset) = res.unwrap();
-                    if file.is_some() {
-                        trap_common_read_file(file.unwrap(), buf, offset);
-                    }
+    /// 从用户空间的 `src` 处读取一个以 '\0' 结尾的字符串，字符串(包括结尾的 '\0')最多 `max_len` 字节。
+    ///
+    /// 地址不可读时返回 EFAULT，在 `max_len` 字节内没有找到结尾的 '\0' 时返回 ENAMETOOLONG。
+    pub fn strncpy_from_user(&self, src: *const u8, max_len: usize) -> AlienResult<String> {
+        let mut res = Vec::new();
+        let mut addr = src as usize;
+        loop {
+            let phy = self.user_addr_to_phys(addr, false)?;
+            let page_len = align_down_4k(addr) + FRAME_SIZE - addr;
+            let page = unsafe { core::slice::from_raw_parts(phy as *const u8, page_len) };
+            match page.iter().position(|&c| c == 0) {
+                Some(end) if res.len() + end < max_len => {
+                    res.extend_from_slice(&page[..end]);
+                    break;
                 }
+                Some(_) => return Err(LinuxErrno::ENAMETOOLONG),
+                None if res.len() + page_len >= max_len => return Err(LinuxErrno::ENAMETOOLONG),
+                None => res.extend_from_slice(page),
             }
-            start += FRAME_SIZE;
+            addr += page_len;
         }
-        self.access_inner().transfer_buffer(ptr, len)
+        Ok(String::from_utf8_lossy(&res).into_owned())
+    }
+
+    /// 从用户空间中读取一个路径或名称字符串，长度上限为 [`PATH_MAX`]
+    pub fn transfer_str(&self, ptr: *const u8) -> AlienResult<String> {
+        self.strncpy_from_user(ptr, PATH_MAX)
+    }
+
+    /// 通过用户地址空间中一个缓冲区的指针 `ptr` 和 缓冲区的长度 `len`，得到一组对用户地址空间中缓冲区的引用，
+    /// 每一组引用都不会跨越页边界。缓冲区不可读时返回 EFAULT。
+    ///
+    /// 内核需要向缓冲区中写入数据时应使用 [`Task::transfer_buffer_mut`]。
+    pub fn transfer_buffer(
+        &self,
+        ptr: *const u8,
+        len: usize,
+    ) -> AlienResult<Vec<&'static mut [u8]>> {
+        self.user_buffer(ptr as usize, len, false)
+    }
+
+    /// 与 [`Task::transfer_buffer`] 相同，但要求缓冲区可写，并会提前处理缓冲区中的写时复制页
+    pub fn transfer_buffer_mut(
+        &self,
+        ptr: *mut u8,
+        len: usize,
+    ) -> AlienResult<Vec<&'static mut [u8]>> {
+        self.user_buffer(ptr as usize, len, true)
+    }
+
+    fn user_buffer(
+        &self,
+        ptr: usize,
+        len: usize,
+        write: bool,
+    ) -> AlienResult<Vec<&'static mut [u8]>> {
+        let mut v = Vec::new();
+        self.for_each_user_page(ptr, len, write, |phy, len| unsafe {
+            v.push(core::slice::from_raw_parts_mut(phy as *mut u8, len));
+        })?;
+        Ok(v)
     }
 }
 
@@ -558,26 +715,34 @@ impl TaskInner {
 
     /// 获取当前进程对于资源的限制
     pub fn get_prlimit(&self, resource: PrLimitRes) -> PrLimit {
-        match resource {
-            PrLimitRes::RlimitStack => PrLimit::new(USER_STACK_SIZE as u64, USER_STACK_SIZE as u64),
-            PrLimitRes::RlimitNofile => {
-                let max_fd = self.fd_table.lock().max();
-                PrLimit::new(max_fd as u64, max_fd as u64)
-            }
-            PrLimitRes::RlimitAs => PrLimit::new(u64::MAX, u64::MAX),
-        }
+        self.rlimits.lock().get(resource)
     }
 
-    /// 设置当前进程对于资源的限制
-    pub fn set_prlimit(&mut self, resource: PrLimitRes, value: PrLimit) {
-        match resource {
-            PrLimitRes::RlimitStack => {}
-            PrLimitRes::RlimitNofile => {
-                let new_max_fd = value.rlim_cur;
-                self.fd_table.lock().set_max(new_max_fd as usize);
-            }
-            PrLimitRes::RlimitAs => {}
+    /// 设置当前进程对于资源的限制，软上限大于硬上限时返回 EINVAL
+    pub fn set_prlimit(&mut self, resource: PrLimitRes, value: PrLimit) -> AlienResult<()> {
+        self.rlimits.lock().set(resource, value)?;
+        if resource == PrLimitRes::RlimitNofile {
+            let new_max_fd = value.rlim_cur.min(MAX_FD_NUM as u64);
+            self.fd_table.lock().set_max(new_max_fd as usize);
         }
+        Ok(())
+    }
+
+    /// 获取当前进程对于资源 `resource` 的软上限
+    pub fn rlimit_cur(&self, resource: PrLimitRes) -> u64 {
+        self.rlimits.lock().cur(resource)
+    }
+
+    /// 返回进程地址空间中堆、用户栈与内存映射区的总长度，用于检查 RLIMIT_AS
+    pub fn address_space_size(&self) -> usize {
+        self.mmap.lock().total_len() + self.stack.len()
+    }
+
+    /// 检查进程使用的 CPU 时间是否超过了 RLIMIT_CPU，返回需要向进程发送的信号
+    pub fn check_cpu_limit(&mut self) -> Option<SignalNumber> {
+        let data = &self.statistical_data;
+        let secs = TimeVal::from_freq(data.tms_utime + data.tms_stime).tv_sec as u64;
+        self.rlimits.lock().check_cpu(secs)
     }
 
     /// 返回 trap 上下文的一个可变指针
@@ -614,279 +779,187 @@ impl TaskInner {
             return false;
         }
         self.trap_cx_before_signal = Some(*trap_frame);
+        self.fp_cx_before_signal = Some(self.fp_context);
         self.signal_set_siginfo = false;
         true
     }
 
     /// 待用户态信号处理函数执行完毕后，需要重新加载原 trap 上下文。
+    ///
+    /// 信号处理函数设置了 SA_SIGINFO 时，从用户栈上的 ucontext 中恢复可能被修改的 pc 与浮点寄存器。
     pub fn load_trap_frame(&mut self) -> isize {
         if let Some(old_trap_frame) = self.trap_cx_before_signal.take() {
             let trap_frame = self.trap_frame();
             // 这里假定是 sigreturn 触发的，即用户的信号处理函数 return 了(cancel_handler)
             // 也就是说信号触发时的 sp 就是现在的 sp
             let sp = trap_frame.regs()[2];
-            // 获取可能被修改的 pc
-            let phy_sp = self.transfer_raw(sp);
-
-            let pc = unsafe { (*(phy_sp as *const SignalUserContext)).get_pc() };
+            let ucontext = if self.signal_set_siginfo {
+                self.read_user::<SignalUserContext>(sp).ok()
+            } else {
+                None
+            };
             *trap_frame = old_trap_frame;
-            if self.signal_set_siginfo {
-                // 更新用户修改的 pc
-                trap_frame.set_sepc(pc);
-                warn!("sig return sp = {:x} pc = {:x}", sp, pc);
+            let fp_context = self.fp_cx_before_signal.take();
+            match ucontext {
+                Some(ucontext) => {
+                    // 更新用户修改的 pc 与浮点寄存器
+                    let pc = ucontext.get_pc();
+                    let fpregs = ucontext.mcontext.fpregs;
+                    trap_frame.set_sepc(pc);
+                    self.fp_context
+                        .set_registers(fpregs.f, fpregs.fcsr as usize);
+                    warn!("sig return sp = {:x} pc = {:x}", sp, pc);
+                }
+                // 用户栈已经无法访问时，无法获取用户修改的值，直接回到信号发生前的状态
+                None => {
+                    if let Some(fp_context) = fp_context {
+                        self.fp_context = fp_context;
+                    }
+                }
             }
+            // 浮点寄存器可能已被信号处理函数修改，下一次使用时重新装载
+            self.fp_context.invalidate();
             trap_frame.regs()[10] as isize // old arg0
         } else {
             -1
         }
     }
 
-    /// 获取一个虚拟地址 `ptr` 的实际物理地址
-    pub fn transfer_raw(&mut self, ptr: usize) -> usize {
-        let (phy, flag, _) = self
-            .address_space
-            .lock()
-            .query(VirtAddr::from(ptr))
-            .unwrap();
-        if !flag.contains(MappingFlags::V) {
-            error!("[transfer_raw] invalid page {:?}, ptr:{:#x}", flag, ptr);
-            self.invalid_page_solver(ptr).unwrap();
-            let (phy, flag, _) = self
-                .address_space
-                .lock()
-                .query(VirtAddr::from(ptr))
-                .unwrap();
-            assert!(flag.contains(MappingFlags::V));
-            return phy.as_usize();
+    /// 处理用户地址 `addr` 所在页上的延迟映射与写时复制，使其可以以 `write` 指定的方式访问。
+    ///
+    /// 如果该页映射了文件，返回需要从文件中读入的内容，由调用者在释放锁之后读入。
+    /// 地址不属于进程的任何映射区域时返回 EFAULT。
+    fn prepare_user_page(
+        &mut self,
+        addr: usize,
+        write: bool,
+    ) -> AlienResult<Option<(Arc<dyn File>, &'static mut [u8], u64)>> {
+        if addr == 0 || addr >= USER_SPACE_END {
+            return Err(LinuxErrno::EFAULT);
         }
-        phy.as_usize()
-    }
-
-    /// 获取 虚拟地址空间中的以 `ptr` 为起始地址，以 '\0' 结尾的字符串
-    pub fn transfer_str(&self, ptr: *const u8) -> String {
-        let mut res = String::new();
-        let physical = self
+        let (_, flags, _) = self
             .address_space
             .lock()
-            .query(VirtAddr::from(ptr as usize));
-        if physical.is_err() {
-            return res;
-        }
-        let (physical, _, _) = physical.unwrap();
-        let mut physical = physical.as_usize();
-        loop {
-            let c = unsafe { &*(physical as *const u8) };
-            if *c == 0 {
-                break;
+            .query(VirtAddr::from(addr))
+            .map_err(|_| LinuxErrno::EFAULT)?;
+        if !flags.contains(MappingFlags::V) {
+            let res = self
+                .invalid_page_solver(align_down_4k(addr))
+                .map_err(|_| LinuxErrno::EFAULT)?;
+            if let Some((Some(file), buf, offset)) = res {
+                return Ok(Some((file, buf, offset)));
             }
-            res.push(*c as char);
-            physical += 1;
+        } else if write && flags.contains(MappingFlags::RSD) {
+            self.do_store_page_fault(addr)
+                .map_err(|_| LinuxErrno::EFAULT)?;
         }
-        res
+        Ok(None)
     }
 
-    /// 从物理地址的 `src` 处取一个长度为 `len` 类型为 T 的缓冲区 赋到 用户虚拟地址空间下的 `dst` 处
-    pub fn copy_to_user_buffer<T: 'static + Copy>(
-        &mut self,
-        src: *const T,
-        dst: *mut T,
-        len: usize,
-    ) {
-        let size = core::mem::size_of::<T>() * len;
-        if VirtAddr::from(dst as usize).align_down_4k()
-            == VirtAddr::from(dst as usize + size - 1).align_down_4k()
-        {
-            // the src and dst are in same page
-            let dst = self.transfer_raw(dst as usize);
-            unsafe {
-                core::ptr::copy_nonoverlapping(src as *const u8, dst as *mut u8, size);
-            }
+    /// 检查用户地址 `addr` 当前的映射是否允许以 `write` 指定的方式访问，返回其对应的物理地址
+    fn checked_user_phys(&self, addr: usize, write: bool) -> AlienResult<usize> {
+        let (phy, flags, _) = self
+            .address_space
+            .lock()
+            .query(VirtAddr::from(addr))
+            .map_err(|_| LinuxErrno::EFAULT)?;
+        let need = if write {
+            MappingFlags::V | MappingFlags::U | MappingFlags::W
         } else {
-            let bufs = self.transfer_buffer(dst as *const u8, size);
-            let src = unsafe { core::slice::from_raw_parts(src as *const u8, size) };
-            let mut start = 0;
-            let src_len = src.len();
-            for buffer in bufs {
-                let len = if start + buffer.len() > src_len {
-                    src_len - start
-                } else {
-                    buffer.len()
-                };
-                unsafe {
-                    core::ptr::copy_nonoverlapping(
-                        src.as_ptr().add(start),
-                        buffer.as_mut_ptr(),
-                        len,
-                    );
-                }
-                start += len;
-            }
+            MappingFlags::V | MappingFlags::U | MappingFlags::R
+        };
+        if !flags.contains(need) {
+            return Err(LinuxErrno::EFAULT);
         }
+        Ok(phy.as_usize())
     }
 
-    /// 从用户虚拟地址空间的 `src` 处取一个长度为 `len` 类型为 T 的缓冲区 赋到 物理地址下的 `dst` 处
-    pub fn copy_from_user_buffer<T: 'static + Copy>(
-        &mut self,
-        src: *const T,
-        dst: *mut T,
-        len: usize,
-    ) {
-        let size = core::mem::size_of::<T>() * len;
-        if VirtAddr::from(src as usize).align_down_4k()
-            == VirtAddr::from(src as usize + size - 1).align_down_4k()
-        {
-            // the src and dst are in same page
-            let src = self.transfer_raw(src as usize);
+    /// 获取用户地址 `addr` 对应的物理地址，供调试器写入。
+    ///
+    /// 由引用计数管理的只读页可能与其它进程共享物理页，写入前先将其复制到新的物理页中；
+    /// 其它只读页为进程私有，直接写入；写时复制页按写时复制处理。
+    fn private_user_phys(&mut self, addr: usize) -> AlienResult<usize> {
+        let page = align_down_4k(addr);
+        let (phy, flags, page_size) = self
+            .address_space
+            .lock()
+            .query(VirtAddr::from(page))
+            .map_err(|_| LinuxErrno::EFAULT)?;
+        let shared = FRAME_REF_MANAGER
+            .lock()
+            .is_tracked(phy.as_usize() >> FRAME_BITS);
+        if flags.contains(MappingFlags::RSD) {
+            self.do_store_page_fault(addr)
+                .map_err(|_| LinuxErrno::EFAULT)?;
+        } else if !flags.contains(MappingFlags::W) && shared {
+            let new_phy = self
+                .address_space
+                .lock()
+                .modify_pte_flags(VirtAddr::from(page), flags, true)
+                .map_err(|_| LinuxErrno::EFAULT)?
+                .ok_or(LinuxErrno::EFAULT)?;
             unsafe {
-                core::ptr::copy_nonoverlapping(src as *const u8, dst as *mut u8, size);
+                core::ptr::copy(
+                    phy.as_usize() as *const u8,
+                    new_phy.as_usize() as *mut u8,
+                    usize::from(page_size),
+                );
             }
-        } else {
-            let mut bufs = self.transfer_buffer(src as *const u8, size);
-            let dst = unsafe { core::slice::from_raw_parts_mut(dst as *mut u8, size) };
-            let mut start = 0;
-            let dst_len = dst.len();
-            for buffer in bufs.iter_mut() {
-                let len = if start + buffer.len() > dst_len {
-                    dst_len - start
-                } else {
-                    buffer.len()
-                };
-                unsafe {
-                    core::ptr::copy_nonoverlapping(
-                        buffer.as_ptr(),
-                        dst.as_mut_ptr().add(start),
-                        len,
-                    );
-                }
-                start += len;
+            let mut frame_ref_manager = FRAME_REF_MANAGER.lock();
+            for i in 0..usize::from(page_size) / FRAME_SIZE {
+                let t_phy = phy + i * FRAME_SIZE;
+                frame_ref_manager.dec_ref(t_phy.as_usize() >> FRAME_BITS);
             }
         }
+        let (phy, _, _) = self
+            .address_space
+            .lock()
+            .query(VirtAddr::from(addr))
+            .map_err(|_| LinuxErrno::EFAULT)?;
+        Ok(phy.as_usize())
     }
 
-    /// 从物理空间下的 `src` 处取一个 T 类型的数据 赋给 虚拟地址空间下的 `dst` 处
-    pub fn copy_to_user<T: 'static + Copy>(&mut self, src: *const T, dst: *mut T) {
-        // self.copy_to_user_buffer(src, dst, 1);
-        let size = core::mem::size_of::<T>();
-        if VirtAddr::from(dst as usize).align_down_4k()
-            == VirtAddr::from(dst as usize + size - 1).align_down_4k()
-        {
-            // the src and dst are in same page
-            let dst = self.transfer_raw(dst as usize);
-            unsafe {
-                core::ptr::copy_nonoverlapping(src as *const u8, dst as *mut u8, size);
-            }
-        } else {
-            let bufs = self.transfer_buffer(dst as *const u8, size);
-            let src = unsafe { core::slice::from_raw_parts(src as *const u8, size) };
-            let mut start = 0;
-            let src_len = src.len();
-            for buffer in bufs {
-                let len = if start + buffer.len() > src_len {
-                    src_len - start
-                } else {
-                    buffer.len()
-                };
-                unsafe {
-                    core::ptr::copy_nonoverlapping(
-                        src.as_ptr().add(start),
-                        buffer.as_mut_ptr(),
-                        len,
-                    );
-                }
-                start += len;
-            }
+    /// 获取用户空间中一个虚拟地址 `ptr` 的实际物理地址。
+    ///
+    /// 用于信号处理等已经持有 `TaskInner` 的场景，映射了文件的页会在持有锁的情况下读入。
+    pub fn transfer_raw(&mut self, ptr: usize) -> AlienResult<usize> {
+        if let Some((file, buf, offset)) = self.prepare_user_page(ptr, true)? {
+            trap_common_read_file(file, buf, offset);
         }
+        self.checked_user_phys(ptr, true)
     }
 
-    /// 从用户虚拟地址空间的 `src` 处取一个 T 类型的数据 赋给 物理地址下的 `dst` 处
-    pub fn copy_from_user<T: 'static + Copy>(&mut self, src: *const T, dst: *mut T) {
-        // self.copy_from_user_buffer(src, dst, 1);
-        let size = core::mem::size_of::<T>();
-        if VirtAddr::from(src as usize).align_down_4k()
-            == VirtAddr::from(src as usize + size - 1).align_down_4k()
-        {
-            // the src and dst are in same page
-            let src = self.transfer_raw(src as usize);
+    /// 在已经持有 `TaskInner` 的情况下将 `src` 写入用户空间的 `dst` 处，数据可以跨页
+    pub fn write_user<T>(&mut self, src: &T, dst: usize) -> AlienResult<()> {
+        let src = src as *const T as *const u8;
+        let end = dst + core::mem::size_of::<T>();
+        let mut addr = dst;
+        while addr < end {
+            let bound = core::cmp::min(align_down_4k(addr) + FRAME_SIZE, end);
+            let phy = self.transfer_raw(addr)?;
             unsafe {
-                core::ptr::copy_nonoverlapping(src as *const u8, dst as *mut u8, size);
-            }
-        } else {
-            let mut bufs = self.transfer_buffer(src as *const u8, size);
-            let dst = unsafe { core::slice::from_raw_parts_mut(dst as *mut u8, size) };
-            let mut start = 0;
-            let dst_len = dst.len();
-            for buffer in bufs.iter_mut() {
-                let len = if start + buffer.len() > dst_len {
-                    dst_len - start
-                } else {
-                    buffer.len()
-                };
-                unsafe {
-                    core::ptr::copy_nonoverlapping(
-                        buffer.as_ptr(),
-                        dst.as_mut_ptr().add(start),
-                        len,
-                    );
-                }
-                start += len;
-            }
+                core::ptr::copy_nonoverlapping(src.add(addr - dst), phy as *mut u8, bound - addr)
+            };
+            addr = bound;
         }
+        Ok(())
     }
 
-    /// 将在进程的虚拟空间中的一段缓冲区的首地址 `ptr` 和 长度 `len` 转换为 实地址下的一组页
-    pub fn transfer_buffer<T: Debug>(
-        &mut self,
-        ptr: *const T,
-        len: usize,
-    ) -> Vec<&'static mut [T]> {
-        let mut start = ptr as usize;
-        let end = start + len;
-        let mut v = Vec::new();
-        while start < end {
-            let (start_phy, flag, _) = self
-                .address_space
-                .lock()
-                .query(VirtAddr::from(start))
-                .expect(format!("transfer_buffer: {:x} failed", start).as_str());
-            if !flag.contains(MappingFlags::V) {
-                panic!("transfer_buffer: {:x} not mapped", start);
-            }
-            // start_phy向上取整到FRAME_SIZE
-            let bound = (start & !(FRAME_SIZE - 1)) + FRAME_SIZE;
-            let len = if bound > end {
-                end - start
-            } else {
-                bound - start
-            };
+    /// 在已经持有 `TaskInner` 的情况下从用户空间的 `src` 处读取一个 T 类型的数据，数据可以跨页
+    pub fn read_user<T: Copy>(&mut self, src: usize) -> AlienResult<T> {
+        let mut value = core::mem::MaybeUninit::<T>::uninit();
+        let dst = value.as_mut_ptr() as *mut u8;
+        let end = src + core::mem::size_of::<T>();
+        let mut addr = src;
+        while addr < end {
+            let bound = core::cmp::min(align_down_4k(addr) + FRAME_SIZE, end);
+            let phy = self.transfer_raw(addr)?;
             unsafe {
-                let buf = core::slice::from_raw_parts_mut(start_phy.as_usize() as *mut T, len);
-                v.push(buf);
-            }
-            start = bound;
+                core::ptr::copy_nonoverlapping(phy as *const u8, dst.add(addr - src), bound - addr)
+            };
+            addr = bound;
         }
-        v
-    }
-
-    /// 将一个在进程的虚拟空间中的虚地址 转换为一个实地址的可变引用
-    pub fn transfer_raw_ptr_mut<T>(&self, ptr: *mut T) -> &'static mut T {
-        let (physical, flag, _) = self
-            .address_space
-            .lock()
-            .query(VirtAddr::from(ptr as usize))
-            .expect(format!("{} transfer_raw_ptr: {:#x} failed", self.name, ptr as usize).as_str());
-        assert!(flag.contains(MappingFlags::V));
-        unsafe { &mut *(physical.as_usize() as *mut T) }
-    }
-
-    /// 将一个在进程的虚拟空间中的虚地址 转换为一个实地址的不可变引用
-    pub fn transfer_raw_ptr<T>(&self, ptr: *const T) -> &'static T {
-        let (physical, flag, _) = self
-            .address_space
-            .lock()
-            .query(VirtAddr::from(ptr as usize))
-            .expect(format!("{} transfer_raw_ptr: {:#x} failed", self.name, ptr as usize).as_str());
-        assert!(flag.contains(MappingFlags::V));
-        unsafe { &*(physical.as_usize() as *const T) }
+        Ok(unsafe { value.assume_init() })
     }
 
     /// 当进程回到用户态时，需要更新进程在内核态下的运行时间
@@ -960,41 +1033,45 @@ impl TaskInner {
 
     /// 返回堆信息
     pub fn heap_info(&self) -> HeapInfo {
-        self.heap.lock().clone()
-    }
-
-    #[allow(unused)]
-    /// (待实现)缩减堆空间
-    pub fn shrink_heap(_addr: usize) -> Result<usize, AlienError> {
-        todo!()
+        self.mmap.lock().heap().clone()
     }
 
-    /// 拓展堆空间
-    pub fn extend_heap(&mut self, addr: usize) -> Result<usize, AlienError> {
-        let mut heap = self.heap.lock();
-        heap.current = addr;
-        if addr < heap.end {
-            return Ok(heap.current);
+    /// 将堆使用到的位置(program break)设置为 `addr`，映射新增的页或解除 `addr` 所在页之后的页的映射。
+    ///
+    /// 堆的长度超过 RLIMIT_DATA、地址空间的长度超过 RLIMIT_AS，或者堆与内存映射区重叠时返回 ENOMEM。
+    /// 只有页表修改成功后才会更新堆的记录，失败时堆保持不变。
+    pub fn set_brk(&mut self, addr: usize) -> Result<usize, AlienError> {
+        let data_limit = self.rlimit_cur(PrLimitRes::RlimitData);
+        let as_limit = self.rlimit_cur(PrLimitRes::RlimitAs);
+        let mut mmap = self.mmap.lock();
+        let change = mmap
+            .brk_change(addr, data_limit, as_limit, self.stack.len())
+            .map_err(|_| AlienError::ENOMEM)?;
+        match change {
+            BrkChange::Keep => {}
+            BrkChange::Shrink(range) => {
+                trace!("shrink heap: {:#x?}", range);
+                self.address_space
+                    .lock()
+                    .unmap_region(VirtAddr::from(range.start), range.len())
+                    .map_err(|_| AlienError::ENOMEM)?;
+            }
+            BrkChange::Extend(range) => {
+                trace!("extend heap: {:#x?}", range);
+                self.address_space
+                    .lock()
+                    .map_region_no_target(
+                        VirtAddr::from(range.start),
+                        range.len(),
+                        "RWUAD".into(), // no V flag
+                        false,
+                        true,
+                    )
+                    .map_err(|_| AlienError::ENOMEM)?;
+            }
         }
-        let addition = addr - heap.end;
-        // increase heap size
-        let end = heap.end;
-        // align addition to PAGE_SIZE
-        let addition = (addition + FRAME_SIZE - 1) & !(FRAME_SIZE - 1);
-        trace!("extend heap: {:#x} -- {:#x}", end, addition);
-        self.address_space
-            .lock()
-            .map_region_no_target(
-                VirtAddr::from(end),
-                addition,
-                "RWUAD".into(), // no V flag
-                false,
-                true,
-            )
-            .unwrap();
-        let new_end = end + addition;
-        heap.end = new_end;
-        Ok(heap.current)
+        mmap.set_brk(addr);
+        Ok(addr)
     }
 
     /// 在虚拟空间中创建内存映射。
@@ -1020,9 +1097,17 @@ impl TaskInner {
             return Err(LinuxErrno::EINVAL);
         }
 
-        // if the map in heap, now we ignore it
-        if self.heap.lock().contains(start) && self.heap.lock().contains(start + len) {
-            return Ok(start);
+        let mut mmap = mmap.lock();
+        // 新的映射区使地址空间的长度超过 RLIMIT_AS，MAP_FIXED 替换掉的已有映射不重复计算
+        let replaced = if flags.contains(MapFlags::MAP_FIXED) {
+            let fixed_start = align_down_4k(start);
+            mmap.overlap_len(fixed_start, fixed_start + align_up_4k(len))
+        } else {
+            0
+        };
+        let new_size = mmap.total_len() + self.stack.len() + align_up_4k(len) - replaced;
+        if new_size as u64 > self.rlimit_cur(PrLimitRes::RlimitAs) {
+            return Err(LinuxErrno::ENOMEM);
         }
 
         // not map to file
@@ -1043,11 +1128,11 @@ impl TaskInner {
         let mut start = align_down_4k(start);
         let v_range = if prot.contains(ProtFlags::PROT_EXEC) {
             let len = align_up_4k(len);
-            if start > self.heap.lock().start {
+            if mmap.heap().overlaps(start, start + len) {
                 // the mmap region is in heap
-                return Err(LinuxErrno::EINVAL);
+                return Err(LinuxErrno::ENOMEM);
             }
-            if let Some(_region) = self.mmap.get_region(start) {
+            if let Some(_region) = mmap.get_region(start) {
                 return Err(LinuxErrno::EINVAL);
             }
             if start == 0 {
@@ -1056,18 +1141,18 @@ impl TaskInner {
             start..start + len
         } else if flags.contains(MapFlags::MAP_FIXED) {
             let len = align_up_4k(len);
-            if start > self.heap.lock().start {
+            if mmap.heap().overlaps(start, start + len) {
                 error!("mmap fixed address conflict with heap");
-                return Err(LinuxErrno::EINVAL);
+                return Err(LinuxErrno::ENOMEM);
             }
             // check if the region is already mapped
-            if let Some(region) = self.mmap.get_region(start) {
+            if let Some(region) = mmap.get_region(start).cloned() {
                 // split the region
                 let (left, mut right) = region.split(start);
                 // delete the old region
-                self.mmap.remove_region(region.start);
+                mmap.remove_region(region.start);
                 // add the left region
-                self.mmap.add_region(left);
+                mmap.add_region(left);
                 if start + len < right.start + right.map_len {
                     // slice the right region
                     trace!(
@@ -1077,13 +1162,13 @@ impl TaskInner {
                     );
                     let (mut left, right) = right.split(start + len);
                     // add the right region
-                    self.mmap.add_region(right);
+                    mmap.add_region(right);
                     // update prot and flags
                     left.set_prot(prot);
                     left.set_flags(flags);
                     left.offset = offset;
                     left.fd = fd;
-                    self.mmap.add_region(left);
+                    mmap.add_region(left);
                 } else {
                     trace!(
                         "directly add the right region:{:#x?}, len:{:#x}",
@@ -1095,13 +1180,13 @@ impl TaskInner {
                     right.set_flags(flags);
                     right.offset = offset;
                     right.fd = fd;
-                    self.mmap.add_region(right);
+                    mmap.add_region(right);
                 }
                 return Ok(start);
             }
             start..start + len
         } else {
-            let v_range = self.mmap.alloc(len);
+            let v_range = mmap.alloc(len);
             v_range
         };
 
@@ -1115,9 +1200,9 @@ impl TaskInner {
             offset,
         );
         // warn!("add mmap region:{:#x?}",region);
-        self.mmap.add_region(region);
+        mmap.add_region(region);
         let start = v_range.start;
-        let mut map_flags: MappingFlags = prot.into(); // no V  flag
+        let mut map_flags = mapping_flags(prot); // no V  flag
         map_flags |= "AD".into();
         self.address_space
             .lock()
@@ -1135,7 +1220,8 @@ impl TaskInner {
     /// 用于在进程的虚拟内存空间中消除一段内存映射。传入的`start`需要是某段内存映射的首地址，`len`需要是该段内存映射的长度。
     pub fn unmap(&mut self, start: usize, len: usize) -> Result<(), isize> {
         // check whether the start is in mmap
-        let x = self.mmap.get_region(start);
+        let mut mmap = self.mmap.lock();
+        let x = mmap.get_region(start);
         if x.is_none() {
             return Err(LinuxErrno::EINVAL.into());
         }
@@ -1148,14 +1234,15 @@ impl TaskInner {
             .lock()
             .unmap_region(VirtAddr::from(start), region.map_len)
             .unwrap();
-        self.mmap.remove_region(start);
+        mmap.remove_region(start);
         Ok(())
     }
 
     /// 设置内存映射的保护位，函数会检查传入的`start`和`len`所指示的内存映射区是否已经处于被映射状态，如果是，则将对应内存映射区的保护位与`prot`做或运算。
     pub fn map_protect(&mut self, start: usize, len: usize, prot: ProtFlags) -> AlienResult<()> {
         // check whether the start is in mmap
-        let x = self.mmap.get_region_mut(start);
+        let mut mmap = self.mmap.lock();
+        let x = mmap.get_region_mut(start);
         if x.is_none() {
             let res = self.address_space.lock().query(VirtAddr::from(start));
             return if res.is_err() {
@@ -1197,10 +1284,11 @@ impl TaskInner {
         }
         assert!(!flags.contains(MappingFlags::RSD));
 
-        let region = self.mmap.get_region(addr).ok_or(AlienError::EINVAL)?;
+        let mmap = self.mmap.lock();
+        let region = mmap.get_region(addr).ok_or(AlienError::EINVAL)?;
         // now we need make sure the start is equal to the start of the region, and the len is equal to the len of the region
         // update page table
-        let mut map_flags: MappingFlags = region.prot.into();
+        let mut map_flags = mapping_flags(region.prot);
         map_flags |= "V".into();
 
         let mut address_space = self.address_space.lock();
@@ -1225,8 +1313,9 @@ impl TaskInner {
         addr: usize,
     ) -> AlienResult<Option<(Option<Arc<dyn File>>, &'static mut [u8], u64)>> {
         trace!("invalid page fault at {:#x}", addr);
-        let is_mmap = self.mmap.get_region(addr);
-        let is_heap = self.heap.lock().contains(addr);
+        let mmap = self.mmap.lock();
+        let is_mmap = mmap.get_region(addr);
+        let is_heap = mmap.heap().contains(addr);
 
         let is_stack = self.stack.contains(&addr);
 
@@ -1245,7 +1334,7 @@ impl TaskInner {
             let region = is_mmap.unwrap();
             // assert_eq!(addr % FRAME_SIZE, 0);
             // update page table
-            let mut map_flags: MappingFlags = region.prot.into();
+            let mut map_flags = mapping_flags(region.prot);
             map_flags |= "VAD".into();
             warn!(
                 "invalid page fault at {:#x}, flag is :{:?}",
@@ -1268,6 +1357,12 @@ impl TaskInner {
             return Ok(Some((file.clone(), buf, read_offset as u64)));
         } else {
             warn!("invalid page fault in stack, addr: {:#x}", addr);
+            // 用户栈的长度超过 RLIMIT_STACK
+            let stack_limit = self.rlimit_cur(PrLimitRes::RlimitStack);
+            if (self.stack.end - addr) as u64 > stack_limit {
+                warn!("stack overflow at {:#x}, limit: {:#x}", addr, stack_limit);
+                return Err(AlienError::EFAULT);
+            }
             let map_flags = "RWUVAD".into();
             self.address_space
                 .lock()
@@ -1429,16 +1524,16 @@ impl Task {
                 statistical_data: StatisticalData::new(),
                 timer: TaskTimer::default(),
                 exit_code: 0,
-                heap: Arc::new(Mutex::new(HeapInfo::new(
-                    elf_info.heap_bottom,
-                    elf_info.heap_bottom,
-                ))),
-                mmap: MMapInfo::new(),
+                mmap: Arc::new(Mutex::new(MMapInfo::with_heap(elf_info.heap_bottom))),
+                rlimits: Arc::new(Mutex::new(ResourceLimits::new())),
                 signal_handlers: Arc::new(Mutex::new(SignalHandlers::new())),
                 signal_receivers: Arc::new(Mutex::new(SignalReceivers::new())),
                 set_child_tid: 0,
                 clear_child_tid: 0,
                 trap_cx_before_signal: None,
+                fp_cx_before_signal: None,
+                fp_context: FpContext::new(),
+                ptrace: None,
                 signal_set_siginfo: false,
                 robust: RobustList::default(),
                 shm: BTreeMap::new(),
@@ -1450,10 +1545,16 @@ impl Task {
                 unmask: 0o022,
                 stack: stack_info,
                 need_wait: 0,
+                pgid: pid,
+                job_event: None,
+                state_time: get_time_ms(),
+                interruptible: false,
+                euid: 0,
+                egid: 0,
             }),
             send_sigchld_when_exit: false,
         };
-        let phy_button = process.transfer_raw(elf_info.stack_top - FRAME_SIZE);
+        let phy_button = process.transfer_raw(elf_info.stack_top - FRAME_SIZE).ok()?;
         let mut user_stack = UserStack::new(phy_button + FRAME_SIZE, elf_info.stack_top);
         user_stack.push(0).unwrap();
         let argc_ptr = user_stack.push(0).unwrap();
@@ -1552,10 +1653,16 @@ impl Task {
             (trap_frame, 0)
         };
 
-        let heap = if flag.contains(CloneFlags::CLONE_VM) {
-            inner.heap.clone()
+        let mmap = if flag.contains(CloneFlags::CLONE_VM) {
+            inner.mmap.clone()
+        } else {
+            Arc::new(Mutex::new(inner.mmap.lock().clone()))
+        };
+
+        let rlimits = if flag.contains(CloneFlags::CLONE_THREAD) {
+            inner.rlimits.clone()
         } else {
-            Arc::new(Mutex::new(inner.heap.lock().clone()))
+            Arc::new(Mutex::new(inner.rlimits.lock().clone()))
         };
 
         // 设置内核栈地址
@@ -1622,8 +1729,8 @@ impl Task {
                 statistical_data: StatisticalData::new(),
                 timer: TaskTimer::default(),
                 exit_code: 0,
-                heap,
-                mmap: inner.mmap.clone(),
+                mmap,
+                rlimits,
                 signal_handlers,
                 signal_receivers,
                 set_child_tid: if flag.contains(CloneFlags::CLONE_CHILD_SETTID) {
@@ -1637,6 +1744,13 @@ impl Task {
                     0
                 },
                 trap_cx_before_signal: None,
+                fp_cx_before_signal: None,
+                fp_context: {
+                    let mut fp_context = inner.fp_context;
+                    fp_context.invalidate();
+                    fp_context
+                },
+                ptrace: None,
                 signal_set_siginfo: false,
                 robust: RobustList::default(),
                 shm: inner.shm.clone(),
@@ -1648,6 +1762,12 @@ impl Task {
                 unmask: 0o022,
                 stack: inner.stack.clone(),
                 need_wait: 0,
+                pgid: inner.pgid,
+                job_event: None,
+                state_time: get_time_ms(),
+                interruptible: false,
+                euid: inner.euid,
+                egid: inner.egid,
             }),
             send_sigchld_when_exit: sig == SignalNumber::SIGCHLD,
         };
@@ -1665,34 +1785,38 @@ impl Task {
     /// `elf_data`用于传入从对应文件处读入的文件数据，用于构造elf_info。
     /// `args`用于指明启动可执行文件时要传入的参数。
     /// `env`用于指明相关环境变量。
+    /// `setid`为执行 set-user-ID/set-group-ID 程序后的有效用户 id 和有效用户组 id，为 `None` 时保持不变。
     ///
-    /// 成功执行则返回OK(())；否则返回错误码(isize)。
+    /// 成功执行则返回OK(())；动态链接程序的解释器不存在时返回 ENOENT，其它无法加载的情况返回 ENOEXEC。
     pub fn exec(
         &self,
         name: &str,
         elf_data: &[u8],
         args: Vec<String>,
         env: Vec<String>,
-    ) -> Result<(), isize> {
+        setid: (Option<u32>, Option<u32>),
+    ) -> AlienResult<()> {
         let mut args = args;
-        let elf_info = build_elf_address_space(elf_data, &mut args, name);
-        if elf_info.is_err() {
-            return Err(-1);
-        }
-        let elf_info = elf_info.unwrap();
+        let elf_info =
+            build_elf_address_space(elf_data, &mut args, name).map_err(|err| match err {
+                ELFError::InterpNotFound => LinuxErrno::ENOENT,
+                _ => LinuxErrno::ENOEXEC,
+            })?;
         let mut inner = self.inner.lock();
         assert_eq!(inner.thread_number, 0);
+        if let Some(euid) = setid.0 {
+            inner.euid = euid;
+        }
+        if let Some(egid) = setid.1 {
+            inner.egid = egid;
+        }
+        let (euid, egid) = (inner.euid as usize, inner.egid as usize);
         let name = elf_info.name;
         let address_space = elf_info.address_space;
         // reset the address space
         inner.address_space = Arc::new(Mutex::new(address_space));
-        // reset the heap
-        inner.heap = Arc::new(Mutex::new(HeapInfo::new(
-            elf_info.heap_bottom,
-            elf_info.heap_bottom,
-        )));
-        // reset the mmap
-        inner.mmap = MMapInfo::new();
+        // reset the mmap and the heap
+        inner.mmap = Arc::new(Mutex::new(MMapInfo::with_heap(elf_info.heap_bottom)));
         // set the name of the process
         inner.name = name.to_string();
         // reset time record
@@ -1704,6 +1828,7 @@ impl Task {
         inner.signal_handlers.lock().clear();
         inner.signal_receivers.lock().clear();
         inner.timer.clear();
+        inner.fp_context = FpContext::new();
         inner.stack = elf_info.stack_top - USER_STACK_SIZE..elf_info.stack_top;
         let env = if env.is_empty() {
             let envp = vec![
@@ -1720,7 +1845,9 @@ impl Task {
             env
         };
         // we need make sure the args and env size is less than 4KB
-        let phy_button = inner.transfer_raw(elf_info.stack_top - FRAME_SIZE);
+        let phy_button = inner
+            .transfer_raw(elf_info.stack_top - FRAME_SIZE)
+            .map_err(|e| e as isize)?;
         let mut user_stack = UserStack::new(phy_button + FRAME_SIZE, elf_info.stack_top);
         // push env to the top of stack of the process
         // we have push '\0' into the env string,so we don't need to push it again
@@ -1765,13 +1892,13 @@ impl Task {
         user_stack.push(AT_PHDR).unwrap();
         user_stack.push(0).unwrap();
         user_stack.push(AT_GID).unwrap();
-        user_stack.push(0).unwrap();
+        user_stack.push(egid).unwrap();
         user_stack.push(AT_EGID).unwrap();
         user_stack.push(0).unwrap();
         user_stack.push(AT_UID).unwrap();
-        user_stack.push(0).unwrap();
+        user_stack.push(euid).unwrap();
         user_stack.push(AT_EUID).unwrap();
-        user_stack.push(0).unwrap();
+        user_stack.push((euid != 0 || egid != 0) as usize).unwrap();
         user_stack.push(AT_SECURE).unwrap();
         user_stack.push(random_ptr).unwrap();
         user_stack.push(AT_RANDOM).unwrap();
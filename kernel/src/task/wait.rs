@@ -0,0 +1,539 @@
+//! Alien 中等待子进程状态变化的相关系统调用。
+//!
+//! 调用 [`wait4`] 或 [`waitid`] 的进程在没有可以获取的子进程状态变化时，会被加入等待队列 [`CHILD_WAITERS`] 并让出 CPU。
+//! 子进程退出、暂停或恢复运行时会向父进程发送 SIGCHLD，[`send_signal`](crate::ipc::send_signal) 在发送信号的同时
+//! 唤醒等待队列中属于目标进程的任务，被唤醒的任务重新检查子进程的状态，或者因为收到其它信号而返回 EINTR。
+//!
+//! 被跟踪者的跟踪停止只报告给跟踪者，跟踪者无论是否是被跟踪者的父进程、是否设置了 WUNTRACED 都可以获取。
+use alloc::sync::{Arc, Weak};
+use alloc::vec::Vec;
+use bitflags::bitflags;
+use core::fmt::{Debug, Formatter};
+use ksync::Mutex;
+use smpscheduler::FifoTask;
+use spin::Lazy;
+
+use constants::io::{OpenFlags, PollEvents, SeekFrom};
+use constants::signal::SignalNumber;
+use constants::sys::{Rusage, TimeVal};
+use constants::{AlienResult, LinuxErrno};
+use syscall_table::syscall_func;
+use timer::TimeFromFreq;
+use vfs::kfile::File;
+use vfscore::dentry::VfsDentry;
+use vfscore::inode::VfsInode;
+use vfscore::utils::VfsFileStat;
+
+use crate::task::ptrace::{take_ptrace_stop, tracees_of};
+use crate::task::schedule::schedule;
+use crate::task::task::{JobEvent, Task, TaskState};
+use crate::task::{current_task, GLOBAL_TASK_MANAGER};
+
+bitflags! {
+    /// `wait4` 与 `waitid` 的选项
+    pub struct WaitFlags: u32 {
+        /// 没有可以获取的子进程状态变化时立即返回
+        const WNOHANG = 0x1;
+        /// 获取被暂停的子进程，在 `waitid` 中名为 WSTOPPED
+        const WUNTRACED = 0x2;
+        /// 获取已经退出的子进程，`wait4` 总是包含该选项
+        const WEXITED = 0x4;
+        /// 获取被 SIGCONT 恢复运行的子进程
+        const WCONTINUED = 0x8;
+        /// 只获取子进程的状态，不回收子进程，也不清除子进程的状态变化
+        const WNOWAIT = 0x0100_0000;
+        /// 不等待同一线程组中其它线程的子进程，Alien 中每个线程只能等待自己的子进程，因此该选项总是成立
+        const __WNOTHREAD = 0x2000_0000;
+        /// 等待所有子进程，无论其退出时是否发送 SIGCHLD
+        const __WALL = 0x4000_0000;
+        /// 只等待退出时不发送 SIGCHLD 的 "clone" 子进程
+        const __WCLONE = 0x8000_0000;
+    }
+}
+
+/// `waitid` 中 `idtype` 的取值
+const P_ALL: usize = 0;
+const P_PID: usize = 1;
+const P_PGID: usize = 2;
+const P_PIDFD: usize = 3;
+
+/// `waitid` 写入 `siginfo_t` 的 `si_code`
+const CLD_EXITED: i32 = 1;
+const CLD_KILLED: i32 = 2;
+const CLD_DUMPED: i32 = 3;
+const CLD_TRAPPED: i32 = 4;
+const CLD_STOPPED: i32 = 5;
+const CLD_CONTINUED: i32 = 6;
+
+/// 等待子进程状态变化的任务
+static CHILD_WAITERS: Lazy<Mutex<Vec<Arc<Task>>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+/// 唤醒等待队列中 tid 或 pid 为 `id` 的任务。在向任务发送信号时调用。
+pub fn wake_child_waiters(id: usize) {
+    let woken = {
+        let mut waiters = CHILD_WAITERS.lock();
+        let mut woken = Vec::new();
+        waiters.retain(|task| {
+            if task.get_tid() as usize == id || task.pid == id {
+                woken.push(task.clone());
+                false
+            } else {
+                true
+            }
+        });
+        woken
+    };
+    woken.into_iter().for_each(|task| {
+        GLOBAL_TASK_MANAGER.add_task(Arc::new(FifoTask::new(task)));
+    });
+}
+
+fn add_child_waiter(task: &Arc<Task>) {
+    let mut waiters = CHILD_WAITERS.lock();
+    if !waiters.iter().any(|waiter| Arc::ptr_eq(waiter, task)) {
+        waiters.push(task.clone());
+    }
+}
+
+/// 将任务移出等待队列，返回任务是否仍在队列中(即还没有被唤醒)
+fn remove_child_waiter(task: &Arc<Task>) -> bool {
+    let mut waiters = CHILD_WAITERS.lock();
+    let len = waiters.len();
+    waiters.retain(|waiter| !Arc::ptr_eq(waiter, task));
+    waiters.len() != len
+}
+
+/// 子进程状态发生变化(暂停或恢复运行)时，向父进程发送 SIGCHLD，同时唤醒在 wait 中等待的父进程
+pub fn notify_parent(task: &Arc<Task>) {
+    let parent = task
+        .access_inner()
+        .parent
+        .as_ref()
+        .and_then(|parent| parent.upgrade());
+    if let Some(parent) = parent {
+        crate::ipc::send_signal(parent.pid, SignalNumber::SIGCHLD as usize);
+    }
+}
+
+/// wait 系列系统调用等待的子进程
+#[derive(Debug, Copy, Clone)]
+enum WaitTarget {
+    /// 任意子进程
+    Any,
+    /// 进程号为 pid 的子进程
+    Pid(usize),
+    /// 进程组号为 pgid 的子进程
+    Pgid(usize),
+}
+
+impl WaitTarget {
+    fn matches(&self, child: &Task) -> bool {
+        match *self {
+            WaitTarget::Any => true,
+            WaitTarget::Pid(pid) => child.pid == pid,
+            WaitTarget::Pgid(pgid) => child.access_inner().pgid == pgid,
+        }
+    }
+}
+
+/// 获取到的一个子进程状态变化
+struct WaitResult {
+    /// 子进程的进程号
+    pid: usize,
+    /// `wait4` 返回的状态值
+    status: i32,
+    /// `waitid` 返回的 `si_code`
+    code: i32,
+    /// `waitid` 返回的 `si_status`
+    si_status: i32,
+    /// 子进程(及其已被回收的子进程)在用户态下运行的时间，单位为 cpu 时钟周期
+    utime: usize,
+    /// 子进程(及其已被回收的子进程)在内核态下运行的时间，单位为 cpu 时钟周期
+    stime: usize,
+}
+
+impl WaitResult {
+    fn new(child: &Task, status: i32, code: i32, si_status: i32) -> Self {
+        let inner = child.access_inner();
+        let data = &inner.statistical_data;
+        Self {
+            pid: child.pid,
+            status,
+            code,
+            si_status,
+            utime: data.tms_utime + data.tms_cutime,
+            stime: data.tms_stime + data.tms_cstime,
+        }
+    }
+
+    fn rusage(&self) -> Rusage {
+        let mut usage = Rusage::new();
+        usage.ru_utime = TimeVal::from_freq(self.utime);
+        usage.ru_stime = TimeVal::from_freq(self.stime);
+        usage
+    }
+}
+
+/// 在 `task` 的子进程中查找一个符合 `target` 和 `flags` 的状态变化。
+///
+/// 没有符合 `target` 的子进程时返回 ECHILD，有符合的子进程但其状态没有变化时返回 `Ok(None)`。
+fn find_child_event(
+    task: &Arc<Task>,
+    target: WaitTarget,
+    flags: WaitFlags,
+) -> AlienResult<Option<WaitResult>> {
+    let mut found = false;
+    for tracee in tracees_of(task.pid) {
+        // 被跟踪的线程也可以通过 tid 等待
+        let matched = match target {
+            WaitTarget::Pid(pid) => tracee.get_tid() as usize == pid,
+            _ => target.matches(&tracee),
+        };
+        if !matched {
+            continue;
+        }
+        found = true;
+        let consume = !flags.contains(WaitFlags::WNOWAIT);
+        if let Some((status, sig)) = take_ptrace_stop(&tracee, consume) {
+            let mut result = WaitResult::new(&tracee, status, CLD_TRAPPED, sig);
+            result.pid = tracee.get_tid() as usize;
+            return Ok(Some(result));
+        }
+    }
+    for child in task.children() {
+        // 同一线程组中的线程不是可以等待的子进程
+        if child.pid == task.pid || !target.matches(&child) {
+            continue;
+        }
+        let is_clone = !child.send_sigchld_when_exit;
+        if !flags.contains(WaitFlags::__WALL) && is_clone != flags.contains(WaitFlags::__WCLONE) {
+            continue;
+        }
+        found = true;
+        if flags.contains(WaitFlags::WEXITED) && child.state() == TaskState::Terminated {
+            let status = child.exit_code();
+            // 状态值的低 7 位为终止进程的信号，第 7 位表示生成了 core 文件
+            let result = match (status & 0x7f, status & 0x80) {
+                (0, _) => WaitResult::new(&child, status, CLD_EXITED, (status >> 8) & 0xff),
+                (sig, 0) => WaitResult::new(&child, status, CLD_KILLED, sig),
+                (sig, _) => WaitResult::new(&child, status, CLD_DUMPED, sig),
+            };
+            if !flags.contains(WaitFlags::WNOWAIT) {
+                task.remove_child_by_tid(child.get_tid());
+                let mut inner = task.access_inner();
+                inner.statistical_data.tms_cutime += result.utime;
+                inner.statistical_data.tms_cstime += result.stime;
+            }
+            return Ok(Some(result));
+        }
+        let event = child.access_inner().job_event;
+        let result = match event {
+            Some(JobEvent::Stopped(sig)) if flags.contains(WaitFlags::WUNTRACED) => {
+                WaitResult::new(&child, ((sig as i32) << 8) | 0x7f, CLD_STOPPED, sig as i32)
+            }
+            Some(JobEvent::Continued) if flags.contains(WaitFlags::WCONTINUED) => {
+                WaitResult::new(&child, 0xffff, CLD_CONTINUED, SignalNumber::SIGCONT as i32)
+            }
+            _ => continue,
+        };
+        if !flags.contains(WaitFlags::WNOWAIT) {
+            child.access_inner().job_event = None;
+        }
+        return Ok(Some(result));
+    }
+    if found {
+        Ok(None)
+    } else {
+        Err(LinuxErrno::ECHILD)
+    }
+}
+
+/// 当前任务是否收到了需要打断 wait 的信号，即除 SIGCHLD 外是否还有未被屏蔽的待处理信号。
+/// 子进程状态变化产生的 SIGCHLD 不会打断等待。
+fn interrupted_by_signal(task: &Arc<Task>) -> bool {
+    let receivers = task.access_inner().signal_receivers.clone();
+    let mut receivers = receivers.lock();
+    // 暂时取下待处理的 SIGCHLD，检查其余的待处理信号
+    let sigchld = receivers.check_signal(SignalNumber::SIGCHLD as usize);
+    let interrupted = receivers.have_signal();
+    if sigchld {
+        receivers.try_add_bit(SignalNumber::SIGCHLD as usize);
+    }
+    interrupted
+}
+
+/// 结束等待。任务仍在等待队列中时将其移出并恢复运行状态；
+/// 已经被唤醒时任务已经加入了就绪队列，此时让出一次 CPU，由就绪队列中的这一项恢复运行。
+fn finish_wait(task: &Arc<Task>) {
+    if remove_child_waiter(task) {
+        task.update_state(TaskState::Running);
+    } else {
+        schedule();
+    }
+}
+
+/// 等待一个符合 `target` 和 `flags` 的子进程状态变化。
+///
+/// 设置了 WNOHANG 且没有可以获取的状态变化时返回 `Ok(None)`；没有符合条件的子进程时返回 ECHILD；
+/// 等待过程中收到信号时返回 EINTR。
+fn do_wait(target: WaitTarget, flags: WaitFlags) -> AlienResult<Option<WaitResult>> {
+    let task = current_task().unwrap().clone();
+    if flags.contains(WaitFlags::WNOHANG) {
+        return find_child_event(&task, target, flags);
+    }
+    loop {
+        // 先加入等待队列并进入等待状态，再检查子进程的状态，
+        // 这样在检查之后、让出 CPU 之前发生的状态变化也会把任务重新加入就绪队列
+        add_child_waiter(&task);
+        task.wait_interruptible();
+        let res = match find_child_event(&task, target, flags) {
+            Ok(None) if interrupted_by_signal(&task) => Err(LinuxErrno::EINTR),
+            Ok(None) => {
+                schedule();
+                continue;
+            }
+            res => res,
+        };
+        finish_wait(&task);
+        return res;
+    }
+}
+
+/// 一个系统调用，用于父进程等待某子进程的状态变化。
+///
+/// `pid`用于指明等待的子进程：
+/// + `pid > 0`: 等待进程号为 `pid` 的子进程；
+/// + `pid == -1`: 等待任意子进程；
+/// + `pid == 0`: 等待与调用者属于同一进程组的子进程；
+/// + `pid < -1`: 等待进程组号为 `-pid` 的子进程。
+///
+/// 当`exit_code`非空时，将会把子进程的状态值写入`exit_code`所指向的位置；当`rusage`非空时，将会把子进程的资源使用量写入
+/// `rusage`所指向的[`Rusage`]结构中。`options`可以包含 `WNOHANG`、`WUNTRACED`、`WCONTINUED`、`__WALL`、`__WCLONE` 等选项，
+/// 具体可见 [`WaitFlags`]。
+///
+/// 成功时返回状态发生变化的子进程的pid；当设置了`WNOHANG`且没有子进程的状态发生变化时返回0；
+/// 当没有符合条件的子进程时返回`ECHILD`；等待过程中收到信号时返回`EINTR`。
+///
+/// Reference:[wait](https://man7.org/linux/man-pages/man2/wait.2.html)
+#[syscall_func(260)]
+pub fn wait4(pid: isize, exit_code: *mut i32, options: u32, rusage: *mut u8) -> AlienResult<isize> {
+    let flags = WaitFlags::from_bits(options).ok_or(LinuxErrno::EINVAL)?;
+    if flags.intersects(WaitFlags::WEXITED | WaitFlags::WNOWAIT) {
+        return Err(LinuxErrno::EINVAL);
+    }
+    let target = match pid {
+        -1 => WaitTarget::Any,
+        0 => WaitTarget::Pgid(current_task().unwrap().access_inner().pgid),
+        pid if pid < 0 => WaitTarget::Pgid(-pid as usize),
+        pid => WaitTarget::Pid(pid as usize),
+    };
+    let result = match do_wait(target, flags | WaitFlags::WEXITED)? {
+        Some(result) => result,
+        None => return Ok(0),
+    };
+    let task = current_task().unwrap();
+    if !exit_code.is_null() {
+        task.copy_to_user(&result.status, exit_code)?;
+    }
+    if !rusage.is_null() {
+        task.copy_to_user(&result.rusage(), rusage as *mut Rusage)?;
+    }
+    Ok(result.pid as isize)
+}
+
+/// `waitid` 写入用户空间的 `siginfo_t` 结构，只包含 SIGCHLD 相关的字段
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+struct WaitIdInfo {
+    si_signo: i32,
+    si_errno: i32,
+    si_code: i32,
+    _pad0: i32,
+    si_pid: i32,
+    si_uid: u32,
+    si_status: i32,
+    _pad1: i32,
+    si_utime: i64,
+    si_stime: i64,
+    _pad2: [u8; 80],
+}
+
+impl Default for WaitIdInfo {
+    fn default() -> Self {
+        Self {
+            si_signo: 0,
+            si_errno: 0,
+            si_code: 0,
+            _pad0: 0,
+            si_pid: 0,
+            si_uid: 0,
+            si_status: 0,
+            _pad1: 0,
+            si_utime: 0,
+            si_stime: 0,
+            _pad2: [0; 80],
+        }
+    }
+}
+
+/// 将 cpu 时钟周期数转换为 `siginfo_t` 中使用的时钟滴答数(每秒 100 次)
+fn freq_to_ticks(freq: usize) -> i64 {
+    let time = TimeVal::from_freq(freq);
+    (time.tv_sec * 100 + time.tv_usec / 10000) as i64
+}
+
+/// 一个系统调用，用于父进程等待某子进程的状态变化，功能与 [`wait4`] 相似，但可以更精确地指定等待的子进程和状态变化。
+///
+/// 参数：
+/// + `idtype` 与 `id`: 指明等待的子进程，`P_ALL` 表示任意子进程，`P_PID` 表示进程号为 `id` 的子进程，
+/// `P_PGID` 表示进程组号为 `id` 的子进程(`id` 为 0 时表示调用者所在的进程组)，`P_PIDFD` 表示文件描述符 `id` 所指向的进程；
+/// + `info`: 用于保存子进程状态变化的 `siginfo_t` 结构，为空时不保存；
+/// + `options`: 必须包含 `WEXITED`、`WSTOPPED`、`WCONTINUED` 中的至少一个，还可以包含 `WNOHANG`、`WNOWAIT` 等选项；
+/// + `rusage`: 非空时将子进程的资源使用量写入其指向的 [`Rusage`] 结构中。
+///
+/// 成功时返回0，设置了 `WNOHANG` 且没有子进程的状态发生变化时 `info` 中的 `si_pid` 为 0。
+///
+/// Reference: [waitid](https://man7.org/linux/man-pages/man2/waitid.2.html)
+#[syscall_func(95)]
+pub fn waitid(
+    idtype: usize,
+    id: usize,
+    info: *mut u8,
+    options: u32,
+    rusage: *mut u8,
+) -> AlienResult<isize> {
+    let flags = WaitFlags::from_bits(options).ok_or(LinuxErrno::EINVAL)?;
+    if !flags.intersects(WaitFlags::WEXITED | WaitFlags::WUNTRACED | WaitFlags::WCONTINUED) {
+        return Err(LinuxErrno::EINVAL);
+    }
+    let task = current_task().unwrap();
+    let target = match idtype {
+        P_ALL => WaitTarget::Any,
+        P_PID if id > 0 => WaitTarget::Pid(id),
+        P_PGID if id == 0 => WaitTarget::Pgid(task.access_inner().pgid),
+        P_PGID => WaitTarget::Pgid(id),
+        P_PIDFD => {
+            let file = task.get_file(id).ok_or(LinuxErrno::EBADF)?;
+            let pidfd = file
+                .downcast_arc::<PidFdFile>()
+                .map_err(|_| LinuxErrno::EINVAL)?;
+            WaitTarget::Pid(pidfd.pid)
+        }
+        _ => return Err(LinuxErrno::EINVAL),
+    };
+    let result = do_wait(target, flags)?;
+    let mut siginfo = WaitIdInfo::default();
+    if let Some(result) = &result {
+        siginfo.si_signo = SignalNumber::SIGCHLD as i32;
+        siginfo.si_code = result.code;
+        siginfo.si_pid = result.pid as i32;
+        siginfo.si_status = result.si_status;
+        siginfo.si_utime = freq_to_ticks(result.utime);
+        siginfo.si_stime = freq_to_ticks(result.stime);
+    }
+    if !info.is_null() {
+        task.copy_to_user(&siginfo, info as *mut WaitIdInfo)?;
+    }
+    if !rusage.is_null() {
+        let usage = result
+            .map(|result| result.rusage())
+            .unwrap_or(Rusage::new());
+        task.copy_to_user(&usage, rusage as *mut Rusage)?;
+    }
+    Ok(0)
+}
+
+/// 指向一个进程的文件描述符(pidfd)，可以用于 [`waitid`] 的 `P_PIDFD`，进程退出后变为可读
+pub struct PidFdFile {
+    /// 指向的进程的进程号
+    pid: usize,
+    /// 指向的进程
+    task: Weak<Task>,
+    open_flag: Mutex<OpenFlags>,
+}
+
+impl Debug for PidFdFile {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("PidFdFile").field("pid", &self.pid).finish()
+    }
+}
+
+impl File for PidFdFile {
+    fn read(&self, _buf: &mut [u8]) -> AlienResult<usize> {
+        Err(LinuxErrno::EINVAL)
+    }
+    fn write(&self, _buf: &[u8]) -> AlienResult<usize> {
+        Err(LinuxErrno::EINVAL)
+    }
+    fn seek(&self, _pos: SeekFrom) -> AlienResult<u64> {
+        Err(LinuxErrno::ESPIPE)
+    }
+    fn get_attr(&self) -> AlienResult<VfsFileStat> {
+        Err(LinuxErrno::ENOSYS)
+    }
+    fn set_open_flag(&self, flag: OpenFlags) {
+        *self.open_flag.lock() = flag;
+    }
+    fn get_open_flag(&self) -> OpenFlags {
+        *self.open_flag.lock()
+    }
+    fn dentry(&self) -> Arc<dyn VfsDentry> {
+        panic!("dentry in pidfd file is not supported")
+    }
+    fn inode(&self) -> Arc<dyn VfsInode> {
+        panic!("inode in pidfd file is not supported")
+    }
+    fn is_readable(&self) -> bool {
+        true
+    }
+    fn is_writable(&self) -> bool {
+        false
+    }
+    fn is_append(&self) -> bool {
+        false
+    }
+    fn poll(&self, event: PollEvents) -> AlienResult<PollEvents> {
+        let exited = match self.task.upgrade() {
+            Some(task) => matches!(task.state(), TaskState::Zombie | TaskState::Terminated),
+            None => true,
+        };
+        if exited && event.contains(PollEvents::IN) {
+            Ok(PollEvents::IN)
+        } else {
+            Ok(PollEvents::empty())
+        }
+    }
+}
+
+/// 一个系统调用，用于创建一个指向进程号为 `pid` 的进程的文件描述符。
+///
+/// 目前只能指向调用者自身或者调用者的子进程，`flags` 只支持 `O_NONBLOCK`。
+///
+/// 成功时返回新的文件描述符；进程不存在时返回 `ESRCH`。
+///
+/// Reference: [pidfd_open](https://man7.org/linux/man-pages/man2/pidfd_open.2.html)
+#[syscall_func(434)]
+pub fn pidfd_open(pid: usize, flags: usize) -> AlienResult<isize> {
+    let flags = OpenFlags::from_bits(flags).ok_or(LinuxErrno::EINVAL)?;
+    if !(flags - OpenFlags::O_NONBLOCK).is_empty() {
+        return Err(LinuxErrno::EINVAL);
+    }
+    let task = current_task().unwrap();
+    let target = if pid == task.pid {
+        Some(task.clone())
+    } else {
+        task.children()
+            .into_iter()
+            .find(|child| child.pid == pid && child.get_tid() as usize == pid)
+    };
+    let target = target.ok_or(LinuxErrno::ESRCH)?;
+    let file = PidFdFile {
+        pid,
+        task: Arc::downgrade(&target),
+        open_flag: Mutex::new(flags),
+    };
+    let fd = task
+        .add_file(Arc::new(file))
+        .map_err(|_| LinuxErrno::EMFILE)?;
+    Ok(fd as isize)
+}
@@ -0,0 +1,325 @@
+//! 检测长时间没有进展的任务
+//!
+//! + hung task: 内核线程 `khungtaskd` 每秒遍历一次进程树，任务处于不可被信号打断的 [`TaskState::Waiting`]
+//!   的时间超过 `hung_task_timeout_secs` 秒时，打印其在让出 CPU 时的内核栈回溯。等待用户输入、futex、
+//!   子进程或者跟踪者的任务通过 [`Task::wait_interruptible`] 进入等待，可以无限期地等待下去，不会被报告。
+//! + soft lockup: 时钟中断发生在内核态时，检查当前 CPU 是否已经超过 `2 * watchdog_thresh` 秒没有进行任务切换，
+//!   如果是则打印当前的栈回溯。在用户态运行的任务会在时钟中断时让出 CPU，所以只有在内核态中卡死才会被报告。
+//!
+//! 每个卡死的任务只报告一次，报告之后的处理方式由 `hung_task_action` 决定：`warn` 只打印信息，`kill` 向卡死的进程
+//! 发送 SIGKILL(init 进程与内核线程除外，只打印信息)，`panic` 使内核 panic。这些设置位于 `/proc/sys/kernel` 中，`hung_task_timeout_secs` 或
+//! `watchdog_thresh` 为 0 时关闭对应的检测。
+use crate::ipc::send_signal;
+use crate::task::kthread::ktread_create;
+use crate::task::{current_cpu, do_suspend, Task, TaskState, INIT_PROCESS};
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec;
+use arch::hart_id;
+use config::CPU_NUM;
+use constants::signal::SignalNumber;
+use core::cmp::min;
+use core::sync::atomic::{AtomicBool, AtomicU8, AtomicUsize, Ordering};
+use ksync::Mutex;
+use spin::Lazy;
+use timer::get_time_ms;
+use vfscore::error::VfsError;
+use vfscore::file::VfsFile;
+use vfscore::inode::{InodeAttr, VfsInode};
+use vfscore::superblock::VfsSuperBlock;
+use vfscore::utils::{VfsFileStat, VfsNodePerm, VfsNodeType};
+use vfscore::VfsResult;
+
+/// 与 Linux 相同，默认等待超过 120 秒的任务被认为卡死
+static HUNG_TASK_TIMEOUT_SECS: AtomicUsize = AtomicUsize::new(120);
+/// `watchdog_thresh`，默认为 10 秒。与 Linux 相同，soft lockup 的阈值是它的两倍，
+/// 即默认超过 20 秒没有任务切换时认为发生 soft lockup
+static WATCHDOG_THRESH: AtomicUsize = AtomicUsize::new(10);
+static ACTION: AtomicU8 = AtomicU8::new(WatchdogAction::Warn as u8);
+
+/// khungtaskd 检查的间隔
+const CHECK_INTERVAL_MS: usize = 1000;
+
+#[allow(clippy::declare_interior_mutable_const)]
+const ZERO: AtomicUsize = AtomicUsize::new(0);
+#[allow(clippy::declare_interior_mutable_const)]
+const FALSE: AtomicBool = AtomicBool::new(false);
+/// 每个 CPU 上一次切换任务的时间(ms)
+static TOUCH_TIME: [AtomicUsize; CPU_NUM] = [ZERO; CPU_NUM];
+/// 每个 CPU 在上一次切换任务之后是否已经报告过 soft lockup
+static SOFTLOCKUP_REPORTED: [AtomicBool; CPU_NUM] = [FALSE; CPU_NUM];
+
+/// 已经报告过的卡死任务，tid -> 进入等待状态的时间
+static HUNG_REPORTED: Lazy<Mutex<BTreeMap<usize, usize>>> =
+    Lazy::new(|| Mutex::new(BTreeMap::new()));
+
+/// 发现卡死的任务之后的处理方式
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[repr(u8)]
+enum WatchdogAction {
+    Warn = 0,
+    Kill = 1,
+    Panic = 2,
+}
+
+impl WatchdogAction {
+    fn current() -> Self {
+        match ACTION.load(Ordering::Relaxed) {
+            1 => WatchdogAction::Kill,
+            2 => WatchdogAction::Panic,
+            _ => WatchdogAction::Warn,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            WatchdogAction::Warn => "warn",
+            WatchdogAction::Kill => "kill",
+            WatchdogAction::Panic => "panic",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "warn" => Some(WatchdogAction::Warn),
+            "kill" => Some(WatchdogAction::Kill),
+            "panic" => Some(WatchdogAction::Panic),
+            _ => None,
+        }
+    }
+
+    /// 对卡死的任务执行处理
+    fn apply(&self, task: &Arc<Task>, reason: &str) {
+        match self {
+            WatchdogAction::Warn => {}
+            // 杀死 init 进程或内核线程会使整个系统无法工作
+            WatchdogAction::Kill if task.pid == INIT_PROCESS.pid || task.is_kthread() => {
+                println!(
+                    "watchdog: not killing init process or kernel thread {}",
+                    task.pid
+                );
+            }
+            WatchdogAction::Kill => {
+                println!("watchdog: killing process {}", task.pid);
+                send_signal(task.pid, SignalNumber::SIGKILL as usize);
+            }
+            WatchdogAction::Panic => panic!("watchdog: {}", reason),
+        }
+    }
+}
+
+/// 当前 CPU 切换了任务，在调度时调用
+pub fn touch_softlockup_watchdog() {
+    let hart = hart_id();
+    TOUCH_TIME[hart].store(get_time_ms(), Ordering::Relaxed);
+    SOFTLOCKUP_REPORTED[hart].store(false, Ordering::Relaxed);
+}
+
+/// 检查当前 CPU 是否发生 soft lockup，在内核态的时钟中断中调用
+pub fn check_soft_lockup() {
+    let thresh = WATCHDOG_THRESH.load(Ordering::Relaxed) * 2 * 1000;
+    let hart = hart_id();
+    let touch = TOUCH_TIME[hart].load(Ordering::Relaxed);
+    let now = get_time_ms();
+    if thresh == 0 || touch == 0 || now.saturating_sub(touch) < thresh {
+        return;
+    }
+    let task = match current_cpu().task.clone() {
+        Some(task) => task,
+        None => return,
+    };
+    if SOFTLOCKUP_REPORTED[hart].swap(true, Ordering::Relaxed) {
+        return;
+    }
+    let reason = format!(
+        "soft lockup - CPU#{} stuck for {}s! [{}:{}]",
+        hart,
+        (now - touch) / 1000,
+        task.get_name(),
+        task.get_tid()
+    );
+    println!("watchdog: BUG: {}", reason);
+    unwinder::back_trace();
+    WatchdogAction::current().apply(&task, &reason);
+}
+
+/// 检查所有处于不可打断的等待状态的任务，报告等待时间超过 `hung_task_timeout_secs` 秒的任务
+fn check_hung_tasks() {
+    let timeout = HUNG_TASK_TIMEOUT_SECS.load(Ordering::Relaxed) * 1000;
+    if timeout == 0 {
+        return;
+    }
+    let now = get_time_ms();
+    let mut hung = BTreeMap::new();
+    let mut stack = vec![INIT_PROCESS.clone()];
+    while let Some(task) = stack.pop() {
+        stack.extend(task.children());
+        let (state, interruptible, since) = {
+            let inner = task.access_inner();
+            (inner.state, inner.interruptible, inner.state_time)
+        };
+        if state != TaskState::Waiting || interruptible || now.saturating_sub(since) < timeout {
+            continue;
+        }
+        let tid = task.get_tid() as usize;
+        if hung.insert(tid, since).is_some() {
+            continue;
+        }
+        let reported = HUNG_REPORTED.lock().get(&tid) == Some(&since);
+        if !reported {
+            report_hung_task(&task, (now - since) / 1000);
+        }
+    }
+    // 只保留仍然卡死的任务，任务恢复运行后再次卡死时需要重新报告
+    *HUNG_REPORTED.lock() = hung;
+}
+
+fn report_hung_task(task: &Arc<Task>, secs: usize) {
+    let reason = format!(
+        "task {}:{} blocked for more than {} seconds",
+        task.get_name(),
+        task.get_tid(),
+        secs
+    );
+    println!("watchdog: INFO: {}", reason);
+    let (ra, fp) = unsafe { (*task.get_context_raw_ptr()).frame() };
+    unwinder::back_trace_from(ra, fp, task.kernel_stack.range());
+    WatchdogAction::current().apply(task, &reason);
+}
+
+fn khungtaskd() {
+    let mut last = get_time_ms();
+    loop {
+        let now = get_time_ms();
+        if now - last >= CHECK_INTERVAL_MS {
+            last = now;
+            check_hung_tasks();
+        }
+        do_suspend();
+    }
+}
+
+/// `/proc/sys/kernel` 中的设置
+enum WatchdogControl {
+    HungTaskTimeout,
+    WatchdogThresh,
+    Action,
+}
+
+impl WatchdogControl {
+    fn content(&self) -> String {
+        match self {
+            WatchdogControl::HungTaskTimeout => {
+                format!("{}\n", HUNG_TASK_TIMEOUT_SECS.load(Ordering::Relaxed))
+            }
+            WatchdogControl::WatchdogThresh => {
+                format!("{}\n", WATCHDOG_THRESH.load(Ordering::Relaxed))
+            }
+            WatchdogControl::Action => format!("{}\n", WatchdogAction::current().as_str()),
+        }
+    }
+
+    fn update(&self, buf: &[u8]) -> VfsResult<()> {
+        let value = core::str::from_utf8(buf)
+            .map_err(|_| VfsError::Invalid)?
+            .trim();
+        match self {
+            WatchdogControl::HungTaskTimeout | WatchdogControl::WatchdogThresh => {
+                let value = value.parse::<usize>().map_err(|_| VfsError::Invalid)?;
+                let target = match self {
+                    WatchdogControl::HungTaskTimeout => &HUNG_TASK_TIMEOUT_SECS,
+                    _ => &WATCHDOG_THRESH,
+                };
+                target.store(value, Ordering::Relaxed);
+            }
+            WatchdogControl::Action => {
+                let action = WatchdogAction::parse(value).ok_or(VfsError::Invalid)?;
+                ACTION.store(action as u8, Ordering::Relaxed);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl VfsFile for WatchdogControl {
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> VfsResult<usize> {
+        let content = self.content();
+        let content = content.as_bytes();
+        if offset as usize >= content.len() {
+            return Ok(0);
+        }
+        let len = min(buf.len(), content.len() - offset as usize);
+        buf[..len].copy_from_slice(&content[offset as usize..offset as usize + len]);
+        Ok(len)
+    }
+    fn write_at(&self, _offset: u64, buf: &[u8]) -> VfsResult<usize> {
+        self.update(buf)?;
+        Ok(buf.len())
+    }
+}
+
+impl VfsInode for WatchdogControl {
+    fn get_super_block(&self) -> VfsResult<Arc<dyn VfsSuperBlock>> {
+        Err(VfsError::NoSys)
+    }
+    fn node_perm(&self) -> VfsNodePerm {
+        VfsNodePerm::empty()
+    }
+    fn set_attr(&self, _attr: InodeAttr) -> VfsResult<()> {
+        Ok(())
+    }
+
+    fn get_attr(&self) -> VfsResult<VfsFileStat> {
+        Ok(VfsFileStat {
+            st_size: self.content().len() as u64,
+            ..Default::default()
+        })
+    }
+
+    fn inode_type(&self) -> VfsNodeType {
+        VfsNodeType::File
+    }
+}
+
+/// 在 `/proc/sys/kernel` 中创建 watchdog 的设置文件，并启动 khungtaskd
+pub fn init_watchdog() {
+    let dir = vfs::proc::procfs_dir("sys/kernel").expect("procfs is not initialized");
+    let files = [
+        ("hung_task_timeout_secs", WatchdogControl::HungTaskTimeout),
+        ("watchdog_thresh", WatchdogControl::WatchdogThresh),
+        ("hung_task_action", WatchdogControl::Action),
+    ];
+    for (name, control) in files {
+        dir.add_file_manually(name, Arc::new(control), "rw-r--r--".into())
+            .unwrap();
+    }
+    ktread_create(khungtaskd, "khungtaskd").unwrap();
+    println!("watchdog init success");
+}
+
+#[cfg(feature = "test")]
+mod ktests {
+    use super::*;
+    use ktest::{kassert, kassert_eq, kernel_test, TestResult};
+
+    #[kernel_test]
+    fn test_watchdog_control() -> TestResult {
+        let timeout = WatchdogControl::HungTaskTimeout;
+        let old = timeout.content();
+        kassert!(timeout.update(b"30\n").is_ok());
+        kassert_eq!(timeout.content(), "30\n");
+        kassert!(timeout.update(b"-1").is_err());
+        kassert!(timeout.update(old.as_bytes()).is_ok());
+
+        let action = WatchdogControl::Action;
+        kassert!(action.update(b"kill\n").is_ok());
+        kassert_eq!(WatchdogAction::current(), WatchdogAction::Kill);
+        kassert!(action.update(b"reboot").is_err());
+        kassert_eq!(WatchdogAction::current(), WatchdogAction::Kill);
+        kassert!(action.update(b"warn").is_ok());
+        kassert_eq!(action.content(), "warn\n");
+        Ok(())
+    }
+}
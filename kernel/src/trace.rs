@@ -0,0 +1,461 @@
+//! 系统调用跟踪
+//!
+//! 与 Linux ftrace 的 syscall 事件类似，跟踪记录保存在一个固定大小的环形缓冲区中，
+//! 通过 `/sys/kernel/tracing` 下的控制文件在运行时配置和读取：
+//!
+//! - `tracing_on`: 写入 `1` 开启跟踪，写入 `0` 关闭跟踪
+//! - `set_event`: 需要跟踪的系统调用，可以是名称或调用号，以空白或 `,` 分隔，为空时跟踪所有系统调用
+//! - `set_event_pid`: 需要跟踪的进程或线程号，为空时跟踪所有进程
+//! - `trace`: 读取缓冲区中的记录，写入任意内容会清空缓冲区
+//! - `trace_pipe`: 读取并移出缓冲区中的记录，缓冲区为空时读取返回 0 而不会阻塞
+//!
+//! `trace` 的每次读取都会重新生成缓冲区的内容，分多次读取期间有新的记录写入时，读到的内容可能错位或重复；
+//! 需要在跟踪开启时持续读取记录应使用 `trace_pipe`，每条记录只会被读取一次。
+//!
+//! 对控制文件的写入从偏移 0 开始时替换原有的配置，否则追加到原有的配置中。
+use crate::task::current_task;
+use alloc::collections::{BTreeSet, VecDeque};
+use alloc::format;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use constants::AT_FDCWD;
+use core::cmp::min;
+use core::fmt::Write;
+use core::sync::atomic::{AtomicBool, Ordering};
+use ksync::Mutex;
+use platform::config::CLOCK_FREQ;
+use spin::Lazy;
+use timer::read_timer;
+use vfscore::error::VfsError;
+use vfscore::file::VfsFile;
+use vfscore::inode::{InodeAttr, VfsInode};
+use vfscore::superblock::VfsSuperBlock;
+use vfscore::utils::{VfsFileStat, VfsNodePerm, VfsNodeType};
+use vfscore::VfsResult;
+
+/// 环形缓冲区最多保存的记录数，缓冲区满时丢弃最早的记录
+const TRACE_BUFFER_SIZE: usize = 4096;
+/// 记录中字符串参数的最大长度
+const TRACE_STR_MAX: usize = 64;
+/// 查找系统调用名称时遍历的最大调用号
+const SYSCALL_NR_MAX: usize = 512;
+
+const SYSCALL_EXIT: usize = 93;
+const SYSCALL_EXIT_GROUP: usize = 94;
+
+/// 跟踪是否开启，在系统调用的快速路径上检查
+static TRACING_ON: AtomicBool = AtomicBool::new(false);
+
+static TRACE_FILTER: Mutex<TraceFilter> = Mutex::new(TraceFilter::new());
+
+static TRACE_BUFFER: Lazy<Mutex<TraceBuffer>> = Lazy::new(|| Mutex::new(TraceBuffer::new()));
+
+/// 已经从缓冲区中移出、但还没有被 `trace_pipe` 的读取者读走的内容
+static TRACE_PIPE_PENDING: Mutex<String> = Mutex::new(String::new());
+
+/// 运行时设置的过滤条件，集合为空时不过滤
+struct TraceFilter {
+    syscalls: BTreeSet<usize>,
+    pids: BTreeSet<usize>,
+}
+
+impl TraceFilter {
+    const fn new() -> Self {
+        Self {
+            syscalls: BTreeSet::new(),
+            pids: BTreeSet::new(),
+        }
+    }
+
+    fn matches(&self, syscall: usize, pid: usize, tid: usize) -> bool {
+        (self.syscalls.is_empty() || self.syscalls.contains(&syscall))
+            && (self.pids.is_empty() || self.pids.contains(&pid) || self.pids.contains(&tid))
+    }
+}
+
+/// 一条系统调用记录
+struct TraceRecord {
+    pid: usize,
+    tid: usize,
+    hart: usize,
+    name: String,
+    /// 进入系统调用时的时钟
+    start: usize,
+    syscall: usize,
+    /// 解析后的参数
+    args: String,
+    /// 系统调用的返回值，不返回的系统调用(exit, exit_group)为 None
+    ret: Option<isize>,
+    /// 系统调用执行的时钟数
+    duration: usize,
+}
+
+impl TraceRecord {
+    fn render(&self, out: &mut String) {
+        let ns = (self.start % CLOCK_FREQ) * 1000_000_000 / CLOCK_FREQ;
+        let _ = write!(
+            out,
+            "{:>16}-{:<5} [{:<5}] [{:03}] {:>6}.{:06}: {}({})",
+            self.name,
+            self.pid,
+            self.tid,
+            self.hart,
+            self.start / CLOCK_FREQ,
+            ns / 1000,
+            constants::syscall_name(self.syscall),
+            self.args
+        );
+        match self.ret {
+            Some(ret) => {
+                let us = self.duration * 1000_000 / CLOCK_FREQ;
+                let _ = writeln!(out, " = {} <{}.{:06}>", ret, us / 1000_000, us % 1000_000);
+            }
+            None => {
+                let _ = writeln!(out, " = ?");
+            }
+        }
+    }
+}
+
+/// 保存跟踪记录的环形缓冲区
+struct TraceBuffer {
+    records: VecDeque<TraceRecord>,
+    /// 写入缓冲区的记录总数，包括已经被丢弃的记录
+    written: usize,
+}
+
+impl TraceBuffer {
+    fn new() -> Self {
+        Self {
+            records: VecDeque::with_capacity(TRACE_BUFFER_SIZE),
+            written: 0,
+        }
+    }
+
+    fn push(&mut self, record: TraceRecord) {
+        if self.records.len() == TRACE_BUFFER_SIZE {
+            self.records.pop_front();
+        }
+        self.records.push_back(record);
+        self.written += 1;
+    }
+
+    fn clear(&mut self) {
+        self.records.clear();
+        self.written = 0;
+    }
+
+    /// 移出最早的记录并追加到 `out` 中，缓冲区为空时返回 false
+    fn consume(&mut self, out: &mut String) -> bool {
+        match self.records.pop_front() {
+            Some(record) => {
+                record.render(out);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn render(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "# tracer: syscall");
+        let _ = writeln!(
+            out,
+            "# entries-in-buffer/entries-written: {}/{}",
+            self.records.len(),
+            self.written
+        );
+        let _ = writeln!(out, "#");
+        let _ = writeln!(
+            out,
+            "#           TASK-PID    TID    CPU  TIMESTAMP  SYSCALL"
+        );
+        self.records
+            .iter()
+            .for_each(|record| record.render(&mut out));
+        out
+    }
+}
+
+/// 已经进入但还没有返回的系统调用，由 [`syscall_enter`] 返回并交给 [`syscall_exit`]
+pub struct PendingSyscall {
+    record: TraceRecord,
+}
+
+/// 系统调用参数的解析方式
+#[derive(Copy, Clone)]
+enum ArgKind {
+    Hex,
+    Int,
+    Oct,
+    Fd,
+    DirFd,
+    Str,
+}
+
+/// 常用系统调用的参数解析方式，其余系统调用的 6 个参数都以十六进制输出
+fn arg_kinds(syscall: usize) -> &'static [ArgKind] {
+    use ArgKind::*;
+    match syscall {
+        17 => &[Hex, Int],                     // getcwd
+        23 => &[Fd],                           // dup
+        24 => &[Fd, Fd, Hex],                  // dup3
+        29 => &[Fd, Hex, Hex],                 // ioctl
+        34 => &[DirFd, Str, Oct],              // mkdirat
+        35 => &[DirFd, Str, Hex],              // unlinkat
+        48 => &[DirFd, Str, Oct, Hex],         // faccessat
+        49 => &[Str],                          // chdir
+        56 => &[DirFd, Str, Hex, Oct],         // openat
+        57 => &[Fd],                           // close
+        62 => &[Fd, Int, Int],                 // lseek
+        63 | 64 => &[Fd, Hex, Int],            // read, write
+        79 => &[DirFd, Str, Hex, Hex],         // fstatat
+        80 => &[Fd, Hex],                      // fstat
+        93 | 94 => &[Int],                     // exit, exit_group
+        129 => &[Int, Int],                    // kill
+        214 => &[Hex],                         // brk
+        215 => &[Hex, Int],                    // munmap
+        220 => &[Hex, Hex, Hex, Hex, Hex],     // clone
+        221 => &[Str, Hex, Hex],               // execve
+        222 => &[Hex, Int, Hex, Hex, Fd, Hex], // mmap
+        260 => &[Int, Hex, Hex, Hex],          // wait4
+        _ => &[Hex, Hex, Hex, Hex, Hex, Hex],
+    }
+}
+
+/// 按照 `kind` 解析一个参数，字符串参数需要在系统调用执行前从用户空间读取
+fn decode_arg(out: &mut String, kind: ArgKind, arg: usize) {
+    let _ = match kind {
+        ArgKind::Hex => write!(out, "{:#x}", arg),
+        ArgKind::Int | ArgKind::Fd => write!(out, "{}", arg as isize),
+        ArgKind::Oct => write!(out, "{:#o}", arg),
+        ArgKind::DirFd if arg as isize == AT_FDCWD => write!(out, "AT_FDCWD"),
+        ArgKind::DirFd => write!(out, "{}", arg as isize),
+        ArgKind::Str => {
+            let task = current_task().unwrap();
+            match task.transfer_str(arg as *const u8) {
+                Ok(s) if s.len() > TRACE_STR_MAX => {
+                    let end = (0..=TRACE_STR_MAX)
+                        .rev()
+                        .find(|&i| s.is_char_boundary(i))
+                        .unwrap_or(0);
+                    write!(out, "{:?}...", &s[..end])
+                }
+                Ok(s) => write!(out, "{:?}", s),
+                Err(_) => write!(out, "{:#x}", arg),
+            }
+        }
+    };
+}
+
+/// 在系统调用执行前调用，`parameters` 为系统调用号和 6 个参数。
+///
+/// 跟踪未开启或者不满足过滤条件时返回 None。不会返回的系统调用在进入时就写入缓冲区。
+pub fn syscall_enter(parameters: &[usize; 7]) -> Option<PendingSyscall> {
+    if !TRACING_ON.load(Ordering::Relaxed) {
+        return None;
+    }
+    let syscall = parameters[0];
+    let task = current_task().unwrap();
+    let pid = task.get_pid() as usize;
+    let tid = task.get_tid() as usize;
+    if !TRACE_FILTER.lock().matches(syscall, pid, tid) {
+        return None;
+    }
+    let mut args = String::new();
+    for (i, kind) in arg_kinds(syscall).iter().enumerate() {
+        if i != 0 {
+            args.push_str(", ");
+        }
+        decode_arg(&mut args, *kind, parameters[i + 1]);
+    }
+    let record = TraceRecord {
+        pid,
+        tid,
+        hart: arch::hart_id(),
+        name: task.get_name(),
+        start: read_timer(),
+        syscall,
+        args,
+        ret: None,
+        duration: 0,
+    };
+    if syscall == SYSCALL_EXIT || syscall == SYSCALL_EXIT_GROUP {
+        TRACE_BUFFER.lock().push(record);
+        return None;
+    }
+    Some(PendingSyscall { record })
+}
+
+/// 在系统调用返回后调用，记录返回值和执行时间
+pub fn syscall_exit(pending: PendingSyscall, ret: isize) {
+    let mut record = pending.record;
+    record.duration = read_timer() - record.start;
+    record.ret = Some(ret);
+    TRACE_BUFFER.lock().push(record);
+}
+
+/// 将系统调用名称或调用号解析为调用号
+fn parse_syscall(token: &str) -> Option<usize> {
+    if let Ok(id) = token.parse::<usize>() {
+        return Some(id);
+    }
+    (0..SYSCALL_NR_MAX).find(|&id| constants::syscall_name(id) == token)
+}
+
+fn parse_list<F>(buf: &[u8], parse: F) -> VfsResult<Vec<usize>>
+where
+    F: Fn(&str) -> Option<usize>,
+{
+    let content = core::str::from_utf8(buf).map_err(|_| VfsError::Invalid)?;
+    content
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|token| !token.is_empty())
+        .map(|token| parse(token).ok_or(VfsError::Invalid))
+        .collect()
+}
+
+/// `/sys/kernel/tracing` 下的控制文件
+#[derive(Copy, Clone)]
+enum TraceControl {
+    TracingOn,
+    SetEvent,
+    SetEventPid,
+    Trace,
+    TracePipe,
+}
+
+impl TraceControl {
+    fn content(&self) -> String {
+        match self {
+            TraceControl::TracingOn => {
+                format!("{}\n", TRACING_ON.load(Ordering::Relaxed) as u8)
+            }
+            TraceControl::SetEvent => TRACE_FILTER
+                .lock()
+                .syscalls
+                .iter()
+                .map(|id| format!("{}\n", constants::syscall_name(*id)))
+                .collect(),
+            TraceControl::SetEventPid => TRACE_FILTER
+                .lock()
+                .pids
+                .iter()
+                .map(|pid| format!("{}\n", pid))
+                .collect(),
+            TraceControl::Trace => TRACE_BUFFER.lock().render(),
+            TraceControl::TracePipe => String::new(),
+        }
+    }
+
+    /// 从 `trace_pipe` 中读取记录，读取的记录会从缓冲区中移出
+    fn read_pipe(buf: &mut [u8]) -> usize {
+        let mut pending = TRACE_PIPE_PENDING.lock();
+        {
+            let mut buffer = TRACE_BUFFER.lock();
+            while pending.len() < buf.len() && buffer.consume(&mut pending) {}
+        }
+        let mut len = min(buf.len(), pending.len());
+        while !pending.is_char_boundary(len) {
+            len -= 1;
+        }
+        buf[..len].copy_from_slice(&pending.as_bytes()[..len]);
+        pending.drain(..len);
+        len
+    }
+
+    fn update(&self, offset: u64, buf: &[u8]) -> VfsResult<()> {
+        match self {
+            TraceControl::TracingOn => {
+                let on = match parse_list(buf, |token| token.parse::<usize>().ok())?.as_slice() {
+                    [0] => false,
+                    [1] => true,
+                    _ => return Err(VfsError::Invalid),
+                };
+                TRACING_ON.store(on, Ordering::Relaxed);
+            }
+            TraceControl::SetEvent => {
+                let syscalls = parse_list(buf, parse_syscall)?;
+                let mut filter = TRACE_FILTER.lock();
+                if offset == 0 {
+                    filter.syscalls.clear();
+                }
+                filter.syscalls.extend(syscalls);
+            }
+            TraceControl::SetEventPid => {
+                let pids = parse_list(buf, |token| token.parse::<usize>().ok())?;
+                let mut filter = TRACE_FILTER.lock();
+                if offset == 0 {
+                    filter.pids.clear();
+                }
+                filter.pids.extend(pids);
+            }
+            TraceControl::Trace | TraceControl::TracePipe => {
+                TRACE_BUFFER.lock().clear();
+                TRACE_PIPE_PENDING.lock().clear();
+            }
+        }
+        Ok(())
+    }
+}
+
+impl VfsFile for TraceControl {
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> VfsResult<usize> {
+        if let TraceControl::TracePipe = self {
+            return Ok(Self::read_pipe(buf));
+        }
+        let content = self.content();
+        let content = content.as_bytes();
+        if offset as usize >= content.len() {
+            return Ok(0);
+        }
+        let len = min(buf.len(), content.len() - offset as usize);
+        buf[..len].copy_from_slice(&content[offset as usize..offset as usize + len]);
+        Ok(len)
+    }
+    fn write_at(&self, offset: u64, buf: &[u8]) -> VfsResult<usize> {
+        self.update(offset, buf)?;
+        Ok(buf.len())
+    }
+}
+
+impl VfsInode for TraceControl {
+    fn get_super_block(&self) -> VfsResult<Arc<dyn VfsSuperBlock>> {
+        Err(VfsError::NoSys)
+    }
+    fn node_perm(&self) -> VfsNodePerm {
+        VfsNodePerm::empty()
+    }
+    fn set_attr(&self, _attr: InodeAttr) -> VfsResult<()> {
+        Ok(())
+    }
+
+    fn get_attr(&self) -> VfsResult<VfsFileStat> {
+        Ok(VfsFileStat {
+            st_size: self.content().len() as u64,
+            ..Default::default()
+        })
+    }
+
+    fn inode_type(&self) -> VfsNodeType {
+        VfsNodeType::File
+    }
+}
+
+/// 在 `/sys/kernel/tracing` 中创建跟踪的控制文件
+pub fn init_trace() {
+    let dir = vfs::sys::sysfs_dir("kernel/tracing").expect("sysfs is not initialized");
+    let files = [
+        ("tracing_on", TraceControl::TracingOn),
+        ("set_event", TraceControl::SetEvent),
+        ("set_event_pid", TraceControl::SetEventPid),
+        ("trace", TraceControl::Trace),
+        ("trace_pipe", TraceControl::TracePipe),
+    ];
+    for (name, control) in files {
+        dir.add_file_manually(name, Arc::new(control), "rw-r--r--".into())
+            .unwrap();
+    }
+    println!("syscall tracing init success");
+}
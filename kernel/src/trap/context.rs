@@ -1,6 +1,7 @@
-//！ Trap 上下文 (Trap帧) 的定义和相关操作
-use arch::ExtSstatus;
-use riscv::register::sstatus::SPP;
+//！ Trap 上下文 (Trap帧) 和浮点寄存器上下文的定义和相关操作
+use arch::{hart_id, ExtSstatus};
+use core::arch::asm;
+use riscv::register::sstatus::{self, FS, SPP};
 
 #[repr(C)]
 #[derive(Debug, Copy, Clone)]
@@ -19,7 +20,6 @@ pub struct TrapFrame {
     hart_id: usize,
     /// 给出 Trap 发生之前 CPU 处在哪个特权级等信息
     sstatus: ExtSstatus,
-    fg: [usize; 2],
 }
 
 impl TrapFrame {
@@ -28,6 +28,16 @@ impl TrapFrame {
         self.sstatus
     }
 
+    /// 获取回到用户态后浮点单元的状态
+    pub fn fp_status(&self) -> FS {
+        self.sstatus.fs()
+    }
+
+    /// 设置回到用户态后浮点单元的状态
+    pub fn set_fp_status(&mut self, fs: FS) {
+        self.sstatus.set_fs(fs);
+    }
+
     /// 用于在收到外部中断时，需要将程序计数器 pc + 4 (下一条指令位置加4个字节)
     pub fn update_sepc(&mut self) {
         self.sepc += 4;
@@ -65,6 +75,8 @@ impl TrapFrame {
         // assert!(sstatus.0.get_bit(5)); //spie == 1
         sstatus.set_spp(SPP::User);
         sstatus.set_sie(false);
+        // 任务第一次使用浮点指令时才装载其浮点寄存器
+        sstatus.set_fs(FS::Off);
         let mut res = Self {
             x: [0; 32],
             sepc: entry,
@@ -73,7 +85,6 @@ impl TrapFrame {
             trap_handler,
             hart_id: 0,
             sstatus,
-            fg: [0; 2],
         };
         res.x[2] = sp;
         res
@@ -100,3 +111,331 @@ impl TrapFrame {
         &mut self.x
     }
 }
+
+/// 用户态浮点寄存器 f0 ~ f31 与 fcsr 的上下文
+///
+/// 浮点上下文采用延迟保存与恢复的方式：任务从用户态陷入内核时，只有 sstatus.FS 为 Dirty 时才保存浮点寄存器；
+/// 回到用户态时，如果当前 CPU 上的浮点寄存器不属于该任务，则将 sstatus.FS 设置为 Off，
+/// 任务下一次执行浮点指令时会产生非法指令异常，此时再恢复其浮点寄存器。
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct FpContext {
+    /// 浮点寄存器组
+    f: [u64; 32],
+    /// 浮点控制与状态寄存器
+    fcsr: usize,
+    /// 浮点寄存器中保存着该上下文的 CPU
+    cpu: Option<usize>,
+}
+
+impl FpContext {
+    /// 创建一个全为 0 的浮点上下文
+    pub const fn new() -> Self {
+        Self {
+            f: [0; 32],
+            fcsr: 0,
+            cpu: None,
+        }
+    }
+
+    /// 返回当前 CPU 上的浮点寄存器是否保存着该上下文
+    pub fn loaded_on(&self, cpu: usize) -> bool {
+        self.cpu == Some(cpu)
+    }
+
+    /// 使各个 CPU 上的浮点寄存器都不再被认为保存着该上下文，下一次使用时将重新装载
+    pub fn invalidate(&mut self) {
+        self.cpu = None;
+    }
+
+    /// 获取浮点寄存器组与 fcsr
+    pub fn registers(&self) -> ([u64; 32], usize) {
+        (self.f, self.fcsr)
+    }
+
+    /// 修改浮点寄存器组与 fcsr，修改后的值在任务下一次使用浮点单元时装载
+    pub fn set_registers(&mut self, f: [u64; 32], fcsr: usize) {
+        self.f = f;
+        self.fcsr = fcsr;
+        self.cpu = None;
+    }
+
+    /// 将当前 CPU 上的浮点寄存器保存到上下文中
+    pub fn save(&mut self) {
+        let ptr = self.f.as_mut_ptr();
+        let fcsr: usize;
+        unsafe {
+            asm!(
+                "fsd f0, 0*8({0})",
+                "fsd f1, 1*8({0})",
+                "fsd f2, 2*8({0})",
+                "fsd f3, 3*8({0})",
+                "fsd f4, 4*8({0})",
+                "fsd f5, 5*8({0})",
+                "fsd f6, 6*8({0})",
+                "fsd f7, 7*8({0})",
+                "fsd f8, 8*8({0})",
+                "fsd f9, 9*8({0})",
+                "fsd f10, 10*8({0})",
+                "fsd f11, 11*8({0})",
+                "fsd f12, 12*8({0})",
+                "fsd f13, 13*8({0})",
+                "fsd f14, 14*8({0})",
+                "fsd f15, 15*8({0})",
+                "fsd f16, 16*8({0})",
+                "fsd f17, 17*8({0})",
+                "fsd f18, 18*8({0})",
+                "fsd f19, 19*8({0})",
+                "fsd f20, 20*8({0})",
+                "fsd f21, 21*8({0})",
+                "fsd f22, 22*8({0})",
+                "fsd f23, 23*8({0})",
+                "fsd f24, 24*8({0})",
+                "fsd f25, 25*8({0})",
+                "fsd f26, 26*8({0})",
+                "fsd f27, 27*8({0})",
+                "fsd f28, 28*8({0})",
+                "fsd f29, 29*8({0})",
+                "fsd f30, 30*8({0})",
+                "fsd f31, 31*8({0})",
+                "frcsr {1}",
+                in(reg) ptr,
+                out(reg) fcsr,
+            );
+            sstatus::set_fs(FS::Clean);
+        }
+        self.fcsr = fcsr;
+        self.cpu = Some(hart_id());
+    }
+
+    /// 将上下文装载到当前 CPU 上的浮点寄存器中
+    pub fn restore(&mut self) {
+        let ptr = self.f.as_ptr();
+        unsafe {
+            asm!(
+                "fld f0, 0*8({0})",
+                "fld f1, 1*8({0})",
+                "fld f2, 2*8({0})",
+                "fld f3, 3*8({0})",
+                "fld f4, 4*8({0})",
+                "fld f5, 5*8({0})",
+                "fld f6, 6*8({0})",
+                "fld f7, 7*8({0})",
+                "fld f8, 8*8({0})",
+                "fld f9, 9*8({0})",
+                "fld f10, 10*8({0})",
+                "fld f11, 11*8({0})",
+                "fld f12, 12*8({0})",
+                "fld f13, 13*8({0})",
+                "fld f14, 14*8({0})",
+                "fld f15, 15*8({0})",
+                "fld f16, 16*8({0})",
+                "fld f17, 17*8({0})",
+                "fld f18, 18*8({0})",
+                "fld f19, 19*8({0})",
+                "fld f20, 20*8({0})",
+                "fld f21, 21*8({0})",
+                "fld f22, 22*8({0})",
+                "fld f23, 23*8({0})",
+                "fld f24, 24*8({0})",
+                "fld f25, 25*8({0})",
+                "fld f26, 26*8({0})",
+                "fld f27, 27*8({0})",
+                "fld f28, 28*8({0})",
+                "fld f29, 29*8({0})",
+                "fld f30, 30*8({0})",
+                "fld f31, 31*8({0})",
+                "fscsr {1}",
+                in(reg) ptr,
+                in(reg) self.fcsr,
+            );
+            // 装载后的寄存器与上下文一致
+            sstatus::set_fs(FS::Clean);
+        }
+        self.cpu = Some(hart_id());
+    }
+}
+
+/// 一个核的通用寄存器和 pc，供调试器和崩溃转储使用
+#[derive(Debug, Copy, Clone, Default)]
+pub struct Registers {
+    pub x: [usize; 32],
+    pub pc: usize,
+}
+
+/// `kernel_v` 在内核栈上保存的寄存器，其中 x0 与 sp 没有保存
+#[repr(C)]
+pub struct KernelFrame {
+    x: [usize; 32],
+    #[allow(dead_code)]
+    sstatus: usize,
+    sepc: usize,
+}
+
+impl KernelFrame {
+    /// 通过 `kernel_trap_vector` 的参数获取内核态 trap 保存的寄存器
+    pub fn from_sp(sp: usize) -> &'static mut Self {
+        unsafe { &mut *(sp as *mut Self) }
+    }
+}
+
+/// 被中断或者 panic 打断的上下文
+pub enum StopFrame<'a> {
+    /// 内核态 trap
+    Kernel(&'a mut KernelFrame),
+    /// 用户态 trap
+    User(&'a mut TrapFrame),
+    /// panic 时捕获的寄存器，修改不会生效
+    Panic(Registers),
+}
+
+impl StopFrame<'_> {
+    /// 获取被打断时的寄存器
+    pub fn registers(&mut self) -> Registers {
+        let mut regs = Registers::default();
+        match self {
+            StopFrame::Kernel(frame) => {
+                regs.x = frame.x;
+                regs.x[2] =
+                    *frame as *const KernelFrame as usize + core::mem::size_of::<KernelFrame>();
+                regs.pc = frame.sepc;
+            }
+            StopFrame::User(frame) => {
+                regs.x.copy_from_slice(frame.regs());
+                regs.pc = frame.sepc();
+            }
+            StopFrame::Panic(panic_regs) => regs = *panic_regs,
+        }
+        regs.x[0] = 0;
+        regs
+    }
+
+    /// 修改返回时恢复的寄存器
+    pub fn set_registers(&mut self, regs: &Registers) {
+        match self {
+            StopFrame::Kernel(frame) => {
+                // 内核栈指针由 kernel_v 恢复，不能修改
+                let sp = frame.x[2];
+                frame.x = regs.x;
+                frame.x[2] = sp;
+                frame.sepc = regs.pc;
+            }
+            StopFrame::User(frame) => {
+                frame.regs()[1..].copy_from_slice(&regs.x[1..]);
+                frame.set_sepc(regs.pc);
+            }
+            StopFrame::Panic(_) => {}
+        }
+    }
+}
+
+/// 捕获当前核的通用寄存器，pc 为捕获时的位置
+#[inline(always)]
+pub fn capture_registers() -> Registers {
+    let mut regs = Registers::default();
+    let ptr = regs.x.as_mut_ptr();
+    unsafe {
+        asm!(
+            "sd x1, 8({0})",
+            "sd x2, 16({0})",
+            "sd x3, 24({0})",
+            "sd x4, 32({0})",
+            "sd x5, 40({0})",
+            "sd x6, 48({0})",
+            "sd x7, 56({0})",
+            "sd x8, 64({0})",
+            "sd x9, 72({0})",
+            "sd x10, 80({0})",
+            "sd x11, 88({0})",
+            "sd x12, 96({0})",
+            "sd x13, 104({0})",
+            "sd x14, 112({0})",
+            "sd x15, 120({0})",
+            "sd x16, 128({0})",
+            "sd x17, 136({0})",
+            "sd x18, 144({0})",
+            "sd x19, 152({0})",
+            "sd x20, 160({0})",
+            "sd x21, 168({0})",
+            "sd x22, 176({0})",
+            "sd x23, 184({0})",
+            "sd x24, 192({0})",
+            "sd x25, 200({0})",
+            "sd x26, 208({0})",
+            "sd x27, 216({0})",
+            "sd x28, 224({0})",
+            "sd x29, 232({0})",
+            "sd x30, 240({0})",
+            "sd x31, 248({0})",
+            "auipc {1}, 0",
+            in(reg) ptr,
+            out(reg) regs.pc,
+        );
+    }
+    regs
+}
+
+#[cfg(feature = "test")]
+mod ktests {
+    use super::*;
+    use ktest::{kassert, kassert_eq, kernel_test, TestResult};
+
+    /// 在当前 CPU 的浮点寄存器上执行 `f`，执行完毕后恢复原来的浮点寄存器与 sstatus.FS
+    fn with_fpu(f: impl FnOnce() -> TestResult) -> TestResult {
+        let fs = ExtSstatus::read().fs();
+        unsafe { sstatus::set_fs(FS::Initial) };
+        let mut origin = FpContext::new();
+        origin.save();
+        let res = f();
+        origin.restore();
+        unsafe { sstatus::set_fs(fs) };
+        res
+    }
+
+    #[kernel_test]
+    fn test_fp_context_save_restore() -> TestResult {
+        with_fpu(|| {
+            let mut f = [0u64; 32];
+            for (i, reg) in f.iter_mut().enumerate() {
+                *reg = 0x4000_0000_0000_0000 | i as u64;
+            }
+            let mut ctx = FpContext::new();
+            ctx.set_registers(f, 0x21);
+            ctx.restore();
+            kassert!(ctx.loaded_on(hart_id()));
+            kassert_eq!(ExtSstatus::read().fs(), FS::Clean);
+            let mut saved = FpContext::new();
+            saved.save();
+            kassert_eq!(saved.registers(), (f, 0x21));
+            kassert!(saved.loaded_on(hart_id()));
+            Ok(())
+        })
+    }
+
+    #[kernel_test]
+    fn test_fp_context_invalidate() -> TestResult {
+        with_fpu(|| {
+            let mut ctx = FpContext::new();
+            kassert!(!ctx.loaded_on(hart_id()));
+            ctx.restore();
+            kassert!(ctx.loaded_on(hart_id()));
+            ctx.invalidate();
+            kassert!(!ctx.loaded_on(hart_id()));
+            ctx.restore();
+            ctx.set_registers([1; 32], 0);
+            kassert!(!ctx.loaded_on(hart_id()));
+            Ok(())
+        })
+    }
+
+    #[kernel_test]
+    fn test_ext_sstatus_fs() -> TestResult {
+        let mut sstatus = ExtSstatus(!0);
+        for fs in [FS::Off, FS::Initial, FS::Clean, FS::Dirty] {
+            sstatus.set_fs(fs);
+            kassert_eq!(sstatus.fs(), fs);
+            kassert_eq!(sstatus.0 | (0b11 << 13), !0);
+        }
+        Ok(())
+    }
+}
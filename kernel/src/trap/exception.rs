@@ -3,7 +3,8 @@
 //! 目前包括系统调用异常处理 [`syscall_exception_handler`]、页错误异常处理 [`page_exception_handler`] (包括
 //! 指令页错误异常处理 [`instruction_page_fault_exception_handler`]、 加载页错误异常处理[`load_page_fault_exception_handler`]、
 //! 储存页错误异常处理 [`store_page_fault_exception_handler`]) 和 文件读入异常处理 [`trap_common_read_file`]。
-use crate::task::{current_task, current_trap_frame};
+use crate::task::{current_task, current_trap_frame, ptrace_syscall_stop};
+use crate::trace;
 use alloc::sync::Arc;
 use arch::interrupt_enable;
 use constants::{AlienError, AlienResult};
@@ -17,32 +18,11 @@ pub fn syscall_exception_handler() {
     // jump to next instruction anyway
     let mut cx = current_trap_frame();
     cx.update_sepc();
+    // 被跟踪时在系统调用入口停止，跟踪者可能会修改系统调用的参数
+    ptrace_syscall_stop();
     // get system call return value
     let parameters = cx.parameters();
-    let syscall_name = constants::syscall_name(parameters[0]);
-
-    let task = current_task().unwrap();
-    let p_name = task.get_name();
-    let tid = task.get_tid();
-    let pid = task.get_pid();
-    if !p_name.contains("shell") && !p_name.contains("init") && !p_name.contains("ls") {
-        // ignore shell and init
-        info!(
-            "[pid:{}, tid: {}][p_name: {}] syscall: [{}] {}({:#x}, {:#x}, {:#x}, {:#x}, {:#x}, {:#x})",
-            pid,
-            tid,
-            p_name,
-            parameters[0],
-            syscall_name,
-            parameters[1],
-            parameters[2],
-            parameters[3],
-            parameters[4],
-            parameters[5],
-            parameters[6]
-        );
-    }
-
+    let pending = trace::syscall_enter(&parameters);
     let result = invoke_call_id!(
         parameters[0],
         parameters[1],
@@ -52,21 +32,13 @@ pub fn syscall_exception_handler() {
         parameters[5],
         parameters[6]
     );
-    let result = Some(result);
+    if let Some(pending) = pending {
+        trace::syscall_exit(pending, result);
+    }
     // cx is changed during sys_exec, so we have to call it again
     cx = current_trap_frame();
-
-    if !p_name.contains("shell") && !p_name.contains("init") && !p_name.contains("ls") {
-        info!(
-            "[pid:{}, tid: {}] syscall: [{}] result: {:?}, tp: {:#x}",
-            pid,
-            tid,
-            syscall_name,
-            result,
-            cx.regs()[4]
-        );
-    }
-    cx.update_res(result.unwrap() as usize);
+    cx.update_res(result as usize);
+    ptrace_syscall_stop();
 }
 
 /// 页异常处理，会根据不同的异常类型，分发至指令页错误异常处理 [`instruction_page_fault_exception_handler`]、
@@ -1,22 +1,28 @@
 use bit_field::BitField;
 use core::arch::{asm, global_asm};
-use riscv::register::sstatus::SPP;
+use core::sync::atomic::{AtomicUsize, Ordering};
+use riscv::register::sstatus::{FS, SPP};
 use riscv::register::{sepc, sscratch, sstatus, stval, stvec};
 
 use constants::signal::SignalNumber;
 use constants::signal::SIGNAL_RETURN_TRAP;
 use constants::time::TimerType;
-pub use context::TrapFrame;
+pub use context::{capture_registers, FpContext, KernelFrame, Registers, StopFrame, TrapFrame};
 pub use exception::trap_common_read_file;
 
+use crate::crash;
+use crate::gdbstub;
 use crate::ipc::{send_signal, signal_handler, signal_return, solve_futex_wait};
-use crate::task::{current_task, current_trap_frame, current_user_token, do_exit, do_suspend};
+use crate::task::{
+    check_soft_lockup, current_cpu, current_task, current_trap_frame, current_user_token, do_exit,
+    do_suspend, ptrace_breakpoint,
+};
 use crate::time::{check_timer_queue, set_next_trigger, set_next_trigger_in_kernel};
 use ::interrupt::external_interrupt_handler;
 use ::interrupt::record::write_irq_info;
 use arch::{
-    external_interrupt_enable, interrupt_disable, interrupt_enable, is_interrupt_enable,
-    timer_interrupt_enable,
+    external_interrupt_enable, hart_id, interrupt_disable, interrupt_enable, is_interrupt_enable,
+    software_interrupt_enable, timer_interrupt_enable,
 };
 use config::TRAMPOLINE;
 use constants::AlienError;
@@ -46,6 +52,7 @@ pub fn trap_return() -> ! {
     interrupt_disable();
     set_user_trap_entry();
     let trap_frame = current_trap_frame();
+    prepare_user_fp(trap_frame);
     let sstatues = trap_frame.get_status();
     let enable = sstatues.0.get_bit(5);
     let sie = sstatues.0.get_bit(1);
@@ -83,17 +90,31 @@ pub fn set_kernel_trap_entry() {
     }
 }
 
+/// 已经初始化中断处理、可以响应核间中断的核
+static ONLINE_HARTS: AtomicUsize = AtomicUsize::new(0);
+
 /// 开启中断/异常
 pub fn init_trap_subsystem() {
     println!("++++ setup interrupt ++++");
     set_kernel_trap_entry();
     external_interrupt_enable();
     timer_interrupt_enable();
+    software_interrupt_enable();
+    ONLINE_HARTS.fetch_or(1 << hart_id(), Ordering::AcqRel);
     interrupt_enable();
     let enable = is_interrupt_enable();
     println!("++++ setup interrupt done, enable:{:?} ++++", enable);
 }
 
+/// 向其它已经初始化中断处理的核发送核间中断，返回接收中断的核的数量
+pub fn send_ipi_to_others() -> usize {
+    let mask = ONLINE_HARTS.load(Ordering::Acquire) & !(1 << hart_id());
+    if mask != 0 {
+        platform::send_ipi(mask);
+    }
+    mask.count_ones() as usize
+}
+
 pub trait TrapHandler {
     fn do_user_handle(&self);
     fn do_kernel_handle(&self, sp: usize);
@@ -111,8 +132,7 @@ impl TrapHandler for Trap {
             }
             Trap::Exception(Exception::StoreFault)
             | Trap::Exception(Exception::LoadFault)
-            | Trap::Exception(Exception::InstructionFault)
-            | Trap::Exception(Exception::IllegalInstruction) => {
+            | Trap::Exception(Exception::InstructionFault) => {
                 error!(
                     "[User] {:?} in application,stval:{:#x?} sepc:{:#x?}",
                     self, stval, sepc
@@ -120,6 +140,29 @@ impl TrapHandler for Trap {
                 let task = current_task().unwrap();
                 send_signal(task.get_tid() as usize, SignalNumber::SIGSEGV as usize)
             }
+            Trap::Exception(Exception::IllegalInstruction) => {
+                // 浮点单元关闭时执行浮点指令，装载浮点上下文后重新执行该指令
+                if load_user_fp() {
+                    return;
+                }
+                error!(
+                    "[User] {:?} in application,stval:{:#x?} sepc:{:#x?}",
+                    self, stval, sepc
+                );
+                let task = current_task().unwrap();
+                send_signal(task.get_tid() as usize, SignalNumber::SIGILL as usize)
+            }
+            Trap::Exception(Exception::Breakpoint) => {
+                let task = current_task().unwrap();
+                warn!(
+                    "[User][tid:{}] breakpoint at sepc:{:#x?}",
+                    task.get_tid(),
+                    sepc
+                );
+                // 单步执行结束时移除临时断点，pc 仍然指向断点所在的位置
+                ptrace_breakpoint(task, sepc);
+                send_signal(task.get_tid() as usize, SignalNumber::SIGTRAP as usize)
+            }
             Trap::Exception(Exception::StorePageFault)
             | Trap::Exception(Exception::LoadPageFault) => {
                 let task = current_task().unwrap();
@@ -171,8 +214,15 @@ impl TrapHandler for Trap {
             }
             Trap::Interrupt(Interrupt::SupervisorTimer) => {
                 trace!("[User] timer interrupt");
+                gdbstub::poll(StopFrame::User(current_trap_frame()));
                 interrupt::timer_interrupt_handler();
             }
+            Trap::Interrupt(Interrupt::SupervisorSoft) => {
+                arch::clear_software_interrupt();
+                let mut frame = StopFrame::User(current_trap_frame());
+                crash::handle_ipi(&mut frame);
+                gdbstub::handle_ipi(frame);
+            }
             Trap::Interrupt(Interrupt::SupervisorExternal) => {
                 trace!("external interrupt");
                 external_interrupt_handler();
@@ -193,9 +243,11 @@ impl TrapHandler for Trap {
         match self {
             Trap::Interrupt(Interrupt::SupervisorTimer) => {
                 trace!("[kernel] timer interrupt");
+                gdbstub::poll(StopFrame::Kernel(KernelFrame::from_sp(sp)));
                 write_irq_info(1);
                 check_timer_queue();
                 solve_futex_wait();
+                check_soft_lockup();
                 // set_next_trigger();
                 set_next_trigger_in_kernel();
             }
@@ -209,6 +261,15 @@ impl TrapHandler for Trap {
                     debug!("physical address: {:#x?}", phy);
                 }
             }
+            Trap::Exception(Exception::Breakpoint) => {
+                // 没有配置调试端口时，内核中的断点与其它异常一样无法处理
+                if !gdbstub::kernel_breakpoint(KernelFrame::from_sp(sp)) {
+                    panic!(
+                        "unhandled trap: {:?}, stval: {:#x?}, sepc: {:#x}, sp: {:#x}",
+                        self, stval, sepc, sp
+                    )
+                }
+            }
             Trap::Exception(_) => {
                 panic!(
                     "unhandled trap: {:?}, stval: {:#x?}, sepc: {:#x}, sp: {:#x}",
@@ -218,6 +279,12 @@ impl TrapHandler for Trap {
             Trap::Interrupt(Interrupt::SupervisorExternal) => {
                 external_interrupt_handler();
             }
+            Trap::Interrupt(Interrupt::SupervisorSoft) => {
+                arch::clear_software_interrupt();
+                let mut frame = StopFrame::Kernel(KernelFrame::from_sp(sp));
+                crash::handle_ipi(&mut frame);
+                gdbstub::handle_ipi(frame);
+            }
             _ => {
                 panic!(
                     "unhandled trap: {:?}, stval: {:?}, sepc: {:x}",
@@ -251,6 +318,7 @@ pub fn user_trap_vector() {
     if spp == SPP::Supervisor {
         panic!("user_trap_vector: spp == SPP::Supervisor");
     }
+    save_user_fp();
     {
         let task = current_task().expect("user_trap_vector: current_task is none");
         // update process statistics
@@ -271,11 +339,65 @@ pub fn user_trap_vector() {
     trap_return();
 }
 
+/// 用户态修改过浮点寄存器时，将其保存到任务的浮点上下文中。之后内核可以使用浮点单元
+fn save_user_fp() {
+    let task = current_task().expect("save_user_fp: current_task is none");
+    let trap_frame = task.trap_frame();
+    if trap_frame.fp_status() == FS::Dirty {
+        task.access_inner().fp_context.save();
+        current_cpu().fp_owner = Some(task.get_tid() as usize);
+        trap_frame.set_fp_status(FS::Clean);
+    }
+    unsafe {
+        sstatus::set_fs(FS::Clean);
+    }
+}
+
+/// 用户态在浮点单元关闭时产生非法指令异常，装载任务的浮点上下文并打开浮点单元。
+///
+/// 浮点单元本来就是打开的，说明这是真正的非法指令，返回 false
+fn load_user_fp() -> bool {
+    let task = current_task().unwrap();
+    let trap_frame = task.trap_frame();
+    if trap_frame.fp_status() != FS::Off {
+        return false;
+    }
+    task.access_inner().fp_context.restore();
+    current_cpu().fp_owner = Some(task.get_tid() as usize);
+    trap_frame.set_fp_status(FS::Clean);
+    true
+}
+
+/// 回到用户态前，只有当前 CPU 的浮点寄存器中保存着任务自己的浮点上下文时才为其打开浮点单元，
+/// 否则关闭浮点单元，等任务使用浮点指令时再装载
+fn prepare_user_fp(trap_frame: &mut TrapFrame) {
+    let cpu = current_cpu();
+    if sstatus::read().fs() == FS::Dirty {
+        // 内核或其它任务修改了浮点寄存器
+        cpu.fp_owner = None;
+        unsafe {
+            sstatus::set_fs(FS::Clean);
+        }
+    }
+    let task = current_task().unwrap();
+    let loaded = cpu.fp_owner == Some(task.get_tid() as usize)
+        && task.access_inner().fp_context.loaded_on(hart_id());
+    let fs = if loaded { FS::Clean } else { FS::Off };
+    trap_frame.set_fp_status(fs);
+}
+
 /// 用于检查进程的计时器是否超时。如果超时则会重置计时器，并按照计时器类型向进程发送信号。
+///
+/// 同时检查进程使用的 CPU 时间是否超过了 RLIMIT_CPU，超过软上限时发送 SIGXCPU，超过硬上限时发送 SIGKILL。
 pub fn check_task_timer_expired() {
     let task = current_task().unwrap();
     let timer_expired = task.access_inner().check_timer_expired();
     let tid = task.get_tid() as usize;
+    let cpu_limit_signal = task.access_inner().check_cpu_limit();
+    if let Some(sig) = cpu_limit_signal {
+        warn!("task {} exceeds cpu limit, send {:?}", tid, sig);
+        send_signal(tid, sig as usize);
+    }
     if timer_expired.is_some() {
         error!("timer expired: {:?}", timer_expired);
         let timer_type = timer_expired.unwrap();
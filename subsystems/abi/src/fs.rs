@@ -0,0 +1,173 @@
+use core::fmt::{Display, Formatter};
+
+use bitflags::bitflags;
+
+bitflags! {
+    /// `open`、`openat` 等系统调用使用的标志位
+    pub struct OpenFlags: usize {
+        const O_RDONLY = 0;
+        const O_WRONLY = 0o1;
+        const O_RDWR = 0o2;
+        const O_CREAT = 0o100;
+        const O_EXCL = 0o200;
+        const O_NOCTTY = 0o400;
+        const O_TRUNC = 0o1000;
+        const O_APPEND = 0o2000;
+        const O_NONBLOCK = 0o4000;
+        const O_DSYNC = 0o10000;
+        const O_ASYNC = 0o20000;
+        const O_DIRECT = 0o40000;
+        const O_LARGEFILE = 0o100000;
+        const O_DIRECTORY = 0o200000;
+        const O_NOFOLLOW = 0o400000;
+        const O_NOATIME = 0o1000000;
+        const O_CLOEXEC = 0o2000000;
+        const O_SYNC = 0o4010000;
+        const O_PATH = 0o10000000;
+        const O_TMPFILE = 0o20200000;
+    }
+}
+
+/// `fstat`、`fstatat` 返回的文件信息，对应 `asm-generic/stat.h` 中的 `struct stat`
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Stat {
+    pub st_dev: u64,
+    pub st_ino: u64,
+    pub st_mode: u32,
+    pub st_nlink: u32,
+    pub st_uid: u32,
+    pub st_gid: u32,
+    pub st_rdev: u64,
+    __pad: u64,
+    pub st_size: u64,
+    pub st_blksize: u32,
+    __pad2: u32,
+    pub st_blocks: u64,
+    pub st_atime_sec: u64,
+    pub st_atime_nsec: u64,
+    pub st_mtime_sec: u64,
+    pub st_mtime_nsec: u64,
+    pub st_ctime_sec: u64,
+    pub st_ctime_nsec: u64,
+    __unused: [u32; 2],
+}
+
+assert_layout!(
+    Stat,
+    size = 128,
+    align = 8,
+    st_dev = 0,
+    st_ino = 8,
+    st_mode = 16,
+    st_nlink = 20,
+    st_uid = 24,
+    st_gid = 28,
+    st_rdev = 32,
+    st_size = 48,
+    st_blksize = 56,
+    st_blocks = 64,
+    st_atime_sec = 72,
+    st_atime_nsec = 80,
+    st_mtime_sec = 88,
+    st_mtime_nsec = 96,
+    st_ctime_sec = 104,
+    st_ctime_nsec = 112,
+);
+
+/// `getdents64` 返回的目录项，对应 `struct linux_dirent64`
+///
+/// 结构后紧跟以 `\0` 结尾的文件名，整个目录项的长度由 `reclen` 给出。
+#[repr(C)]
+#[derive(Debug)]
+pub struct Dirent64 {
+    /// ino is an inode number
+    pub ino: u64,
+    /// off is an offset to next linux_dirent
+    pub off: i64,
+    /// reclen is the length of this linux_dirent
+    pub reclen: u16,
+    /// type is the file type
+    pub type_: DirentType,
+    /// name is the filename (null-terminated)
+    pub name: [u8; 0],
+}
+
+assert_layout!(
+    Dirent64,
+    size = 24,
+    align = 8,
+    ino = 0,
+    off = 8,
+    reclen = 16,
+    type_ = 18,
+    name = 19,
+);
+
+impl Dirent64 {
+    /// 创建文件名为 `name` 的目录项，`reclen` 包含文件名及结尾的 `\0`，并对齐到 8 字节
+    ///
+    /// 返回的结构中不包含文件名，需要由调用者将文件名写到 `name` 处。
+    pub fn new(name: &str, ino: u64, off: i64, type_: DirentType) -> Self {
+        let len = core::mem::offset_of!(Dirent64, name) + name.len() + 1;
+        Self {
+            ino,
+            off,
+            reclen: ((len + 7) & !7) as u16,
+            type_,
+            name: [],
+        }
+    }
+
+    /// 目录项的长度
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        self.reclen as usize
+    }
+
+    /// 读取目录项后的文件名，只能用于从 `getdents64` 的缓冲区中取得的目录项
+    pub fn get_name(&self) -> &str {
+        unsafe {
+            let name = core::ffi::CStr::from_ptr(self.name.as_ptr() as *const core::ffi::c_char);
+            name.to_str().unwrap()
+        }
+    }
+}
+
+bitflags! {
+    /// 目录项中的文件类型
+    pub struct DirentType: u8 {
+        const DT_UNKNOWN = 0;
+        const DT_FIFO = 1;
+        const DT_CHR = 2;
+        const DT_DIR = 4;
+        const DT_BLK = 6;
+        const DT_REG = 8;
+        const DT_LNK = 10;
+        const DT_SOCK = 12;
+        const DT_WHT = 14;
+    }
+}
+
+impl DirentType {
+    pub fn from_u8(value: u8) -> Self {
+        Self::from_bits_truncate(value)
+    }
+}
+
+impl Display for DirentType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        let name = match *self {
+            DirentType::DT_FIFO => "fifo",
+            DirentType::DT_CHR => "char",
+            DirentType::DT_DIR => "dir",
+            DirentType::DT_BLK => "block",
+            DirentType::DT_REG => "regular",
+            DirentType::DT_LNK => "link",
+            DirentType::DT_SOCK => "sock",
+            DirentType::DT_WHT => "whiteout",
+            _ => "unknown",
+        };
+        f.write_str(name)
+    }
+}
@@ -0,0 +1,107 @@
+//! 内核与用户程序共享的 ABI 定义
+//!
+//! 这里的结构和标志位会直接在内核与用户态之间按内存传递，因此内核(通过 `constants` 重新导出)与 `Mstd`
+//! 都只使用本 crate 中的定义。所有结构都按照 Linux riscv64 ABI 布局，并在编译期检查大小、对齐和各字段的偏移，
+//! 修改结构时如果与 Linux 不一致会直接导致构建失败。
+#![no_std]
+#![feature(ip_in_core)]
+#![feature(offset_of)]
+// O_RDONLY、PROT_NONE 等取值为 0 的标志位是 ABI 的一部分
+#![allow(clippy::bad_bit_mask)]
+
+pub use fs::*;
+pub use mm::*;
+pub use net::*;
+pub use signal::*;
+pub use task::*;
+pub use time::*;
+
+/// 在编译期检查结构的大小、对齐以及字段的偏移
+macro_rules! assert_layout {
+    ($ty:ty, size = $size:expr, align = $align:expr $(, $field:ident = $offset:expr)* $(,)?) => {
+        const _: () = {
+            assert!(core::mem::size_of::<$ty>() == $size);
+            assert!(core::mem::align_of::<$ty>() == $align);
+            $(assert!(core::mem::offset_of!($ty, $field) == $offset);)*
+        };
+    };
+}
+
+mod fs;
+mod mm;
+mod net;
+mod signal;
+mod task;
+mod time;
+
+#[cfg(test)]
+mod tests {
+    use core::net::{Ipv4Addr, SocketAddrV4};
+
+    use super::*;
+
+    #[test]
+    fn test_open_flags() {
+        // include/uapi/asm-generic/fcntl.h
+        assert_eq!(OpenFlags::O_CREAT.bits(), 0o100);
+        assert_eq!(OpenFlags::O_EXCL.bits(), 0o200);
+        assert_eq!(OpenFlags::O_TRUNC.bits(), 0o1000);
+        assert_eq!(OpenFlags::O_APPEND.bits(), 0o2000);
+        assert_eq!(OpenFlags::O_NONBLOCK.bits(), 0o4000);
+        assert_eq!(OpenFlags::O_DIRECTORY.bits(), 0o200000);
+        assert_eq!(OpenFlags::O_NOFOLLOW.bits(), 0o400000);
+        assert_eq!(OpenFlags::O_CLOEXEC.bits(), 0o2000000);
+    }
+
+    #[test]
+    fn test_mm_flags() {
+        // include/uapi/asm-generic/mman-common.h
+        assert_eq!(ProtFlags::all().bits(), 0x7);
+        assert_eq!(MapFlags::MAP_FIXED.bits(), 0x10);
+        assert_eq!(MapFlags::MAP_ANONYMOUS.bits(), 0x20);
+        assert_eq!(MapFlags::MAP_POPULATE.bits(), 0x8000);
+        assert_eq!(MapFlags::MAP_FIXED_NOREPLACE.bits(), 0x100000);
+    }
+
+    #[test]
+    fn test_clone_flags() {
+        // include/uapi/linux/sched.h
+        assert_eq!(CloneFlags::CLONE_VM.bits(), 0x100);
+        assert_eq!(CloneFlags::CLONE_THREAD.bits(), 0x10000);
+        assert_eq!(CloneFlags::CLONE_SETTLS.bits(), 0x80000);
+        assert_eq!(CloneFlags::CLONE_CHILD_SETTID.bits(), 0x1000000);
+        assert_eq!(CloneFlags::CLONE_IO.bits(), 0x80000000);
+    }
+
+    #[test]
+    fn test_dirent64() {
+        let dirent = Dirent64::new("a", 1, 2, DirentType::DT_REG);
+        // 19 字节的头部加上 "a\0"，对齐到 8 字节
+        assert_eq!(dirent.len(), 24);
+        let dirent = Dirent64::new("hello", 1, 2, DirentType::DT_DIR);
+        assert_eq!(dirent.len(), 32);
+        assert_eq!(DirentType::from_u8(10), DirentType::DT_LNK);
+    }
+
+    #[test]
+    fn test_sockaddr() {
+        let addr = SocketAddrV4::new(Ipv4Addr::new(127, 0, 0, 1), 8080);
+        let raw = Sockaddr::from(addr);
+        assert_eq!(raw.sa_family, AF_INET);
+        // 端口与地址都以网络字节序保存
+        assert_eq!(raw.sa_port.to_ne_bytes(), 8080u16.to_be_bytes());
+        assert_eq!(raw.sa_addr.to_ne_bytes(), [127, 0, 0, 1]);
+        assert_eq!(SocketAddrV4::from(raw), addr);
+    }
+
+    #[test]
+    fn test_signal_user_context() {
+        let ucontext = SignalUserContext::init(0x1000, 0x8000);
+        assert_eq!(ucontext.get_pc(), 0x8000);
+        assert_eq!(ucontext.sigmask, 0x1000);
+        // arch/riscv/include/uapi/asm/sigcontext.h: sc_regs 之后紧跟 sc_fpregs
+        let base = &ucontext as *const _ as usize;
+        let fpregs = &ucontext.mcontext.fpregs as *const _ as usize;
+        assert_eq!(fpregs - base, 176 + 32 * 8);
+    }
+}
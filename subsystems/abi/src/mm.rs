@@ -0,0 +1,33 @@
+use bitflags::bitflags;
+
+bitflags! {
+    /// `mmap`、`mprotect` 使用的内存保护位
+    pub struct ProtFlags: u32 {
+        const PROT_NONE = 0x0;
+        const PROT_READ = 0x1;
+        const PROT_WRITE = 0x2;
+        const PROT_EXEC = 0x4;
+    }
+}
+
+bitflags! {
+    /// `mmap` 使用的映射标志
+    pub struct MapFlags: u32 {
+        const MAP_SHARED = 0x01;
+        const MAP_PRIVATE = 0x02;
+        const MAP_SHARED_VALIDATE = 0x03;
+        const MAP_FIXED = 0x10;
+        const MAP_ANONYMOUS = 0x20;
+        const MAP_GROWSDOWN = 0x100;
+        const MAP_DENYWRITE = 0x800;
+        const MAP_EXECUTABLE = 0x1000;
+        const MAP_LOCKED = 0x2000;
+        const MAP_NORESERVE = 0x4000;
+        const MAP_POPULATE = 0x8000;
+        const MAP_NONBLOCK = 0x10000;
+        const MAP_STACK = 0x20000;
+        const MAP_HUGETLB = 0x40000;
+        const MAP_SYNC = 0x80000;
+        const MAP_FIXED_NOREPLACE = 0x100000;
+    }
+}
@@ -0,0 +1,77 @@
+use core::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
+
+/// 本地通信
+pub const AF_UNIX: u16 = 1;
+/// IPv4 网络协议
+pub const AF_INET: u16 = 2;
+
+/// IPv4 套接字地址，对应 `struct sockaddr_in`
+///
+/// `sa_port` 与 `sa_addr` 都以网络字节序保存，与 [`SocketAddrV4`] 之间的转换会处理字节序。
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Sockaddr {
+    /// 地址协议族
+    pub sa_family: u16,
+    /// 端口
+    pub sa_port: u16,
+    /// IPv4 地址
+    pub sa_addr: u32,
+    pub zero: [u8; 8],
+}
+
+assert_layout!(
+    Sockaddr,
+    size = 16,
+    align = 4,
+    sa_family = 0,
+    sa_port = 2,
+    sa_addr = 4,
+    zero = 8,
+);
+
+impl Sockaddr {
+    pub fn new(sa_family: u16, sa_addr: u32, sa_port: u16) -> Self {
+        Self {
+            sa_family,
+            sa_port,
+            sa_addr,
+            zero: [0; 8],
+        }
+    }
+}
+
+impl From<SocketAddrV4> for Sockaddr {
+    fn from(addr: SocketAddrV4) -> Self {
+        Sockaddr::new(
+            AF_INET,
+            u32::from_ne_bytes(addr.ip().octets()),
+            addr.port().to_be(),
+        )
+    }
+}
+
+impl From<SocketAddr> for Sockaddr {
+    /// 目前只支持 IPv4，传入 IPv6 地址时 panic
+    fn from(addr: SocketAddr) -> Self {
+        match addr {
+            SocketAddr::V4(addr) => addr.into(),
+            SocketAddr::V6(_) => panic!("ipv6 is not supported"),
+        }
+    }
+}
+
+impl From<Sockaddr> for SocketAddrV4 {
+    fn from(addr: Sockaddr) -> Self {
+        SocketAddrV4::new(
+            Ipv4Addr::from(addr.sa_addr.to_ne_bytes()),
+            u16::from_be(addr.sa_port),
+        )
+    }
+}
+
+impl From<Sockaddr> for SocketAddr {
+    fn from(addr: Sockaddr) -> Self {
+        SocketAddr::V4(addr.into())
+    }
+}
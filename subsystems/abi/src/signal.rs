@@ -0,0 +1,96 @@
+/// 信号栈，对应 `stack_t`
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SignalStack {
+    pub ss_sp: usize,
+    pub ss_flags: i32,
+    pub ss_size: usize,
+}
+
+assert_layout!(
+    SignalStack,
+    size = 24,
+    align = 8,
+    ss_sp = 0,
+    ss_flags = 8,
+    ss_size = 16
+);
+
+/// 信号处理函数看到的浮点寄存器，对应 `union __riscv_fp_state`。
+///
+/// 联合体的大小由 Q 扩展决定，Alien 只支持 D 扩展，因此只使用开头的 f0 ~ f31 与 fcsr。
+#[repr(C, align(16))]
+#[derive(Debug, Clone, Copy)]
+pub struct UserFpState {
+    pub f: [u64; 32],
+    pub fcsr: u32,
+    reserved: [u32; 67],
+}
+
+assert_layout!(UserFpState, size = 528, align = 16, f = 0, fcsr = 256);
+
+impl Default for UserFpState {
+    fn default() -> Self {
+        Self {
+            f: [0; 32],
+            fcsr: 0,
+            reserved: [0; 67],
+        }
+    }
+}
+
+/// 信号发生时的寄存器，对应 `mcontext_t`
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MContext {
+    /// pc 与 x1 ~ x31
+    pub gregs: [usize; 32],
+    /// 浮点寄存器
+    pub fpregs: UserFpState,
+}
+
+assert_layout!(MContext, size = 784, align = 16, gregs = 0, fpregs = 256);
+
+/// 设置了 SA_SIGINFO 的信号处理函数的第三个参数，对应 `ucontext_t`。
+///
+/// 信号处理函数返回时(`rt_sigreturn`)，内核从这里恢复 pc 与浮点寄存器，因此处理函数可以修改它们。
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct SignalUserContext {
+    pub flags: usize,
+    pub link: usize,
+    pub stack: SignalStack,
+    pub sigmask: u64,
+    unused: [u8; 120],
+    pub mcontext: MContext,
+}
+
+assert_layout!(
+    SignalUserContext,
+    size = 960,
+    align = 16,
+    stack = 16,
+    sigmask = 40,
+    mcontext = 176
+);
+
+impl SignalUserContext {
+    /// 创建一个信号屏蔽字为 `mask`、返回地址为 `pc` 的上下文
+    pub fn init(mask: u64, pc: usize) -> Self {
+        let mut mcontext = MContext::default();
+        mcontext.gregs[0] = pc;
+        Self {
+            flags: 0,
+            link: 0,
+            stack: SignalStack::default(),
+            sigmask: mask,
+            unused: [0; 120],
+            mcontext,
+        }
+    }
+
+    /// 信号处理函数返回后继续执行的地址
+    pub fn get_pc(&self) -> usize {
+        self.mcontext.gregs[0]
+    }
+}
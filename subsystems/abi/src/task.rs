@@ -0,0 +1,143 @@
+use bitflags::bitflags;
+
+use crate::TimeVal;
+
+bitflags! {
+    /// `clone` 使用的标志位，低 8 位为子进程退出时发送给父进程的信号
+    pub struct CloneFlags: u32 {
+        const CLONE_VM = 0x00000100;
+        const CLONE_FS = 0x00000200;
+        const CLONE_FILES = 0x00000400;
+        const CLONE_SIGHAND = 0x00000800;
+        const CLONE_PIDFD = 0x00001000;
+        const CLONE_PTRACE = 0x00002000;
+        const CLONE_VFORK = 0x00004000;
+        const CLONE_PARENT = 0x00008000;
+        const CLONE_THREAD = 0x00010000;
+        const CLONE_NEWNS = 0x00020000;
+        const CLONE_SYSVSEM = 0x00040000;
+        const CLONE_SETTLS = 0x00080000;
+        const CLONE_PARENT_SETTID = 0x00100000;
+        const CLONE_CHILD_CLEARTID = 0x00200000;
+        const CLONE_DETACHED = 0x00400000;
+        const CLONE_UNTRACED = 0x00800000;
+        const CLONE_CHILD_SETTID = 0x01000000;
+        const CLONE_NEWCGROUP = 0x02000000;
+        const CLONE_NEWUTS = 0x04000000;
+        const CLONE_NEWIPC = 0x08000000;
+        const CLONE_NEWUSER = 0x10000000;
+        const CLONE_NEWPID = 0x20000000;
+        const CLONE_NEWNET = 0x40000000;
+        const CLONE_IO = 0x80000000;
+    }
+}
+
+/// `getrusage`、`wait4` 返回的资源使用量
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Rusage {
+    /// 用户态运行时间
+    pub ru_utime: TimeVal,
+    /// 内核态运行时间
+    pub ru_stime: TimeVal,
+    pub ru_maxrss: isize,
+    pub ru_ixrss: isize,
+    pub ru_idrss: isize,
+    pub ru_isrss: isize,
+    pub ru_minflt: isize,
+    pub ru_majflt: isize,
+    pub ru_nswap: isize,
+    pub ru_inblock: isize,
+    pub ru_oublock: isize,
+    pub ru_msgsnd: isize,
+    pub ru_msgrcv: isize,
+    pub ru_nsignals: isize,
+    pub ru_nvcsw: isize,
+    pub ru_nivcsw: isize,
+}
+
+assert_layout!(
+    Rusage,
+    size = 144,
+    align = 8,
+    ru_utime = 0,
+    ru_stime = 16,
+    ru_maxrss = 32,
+    ru_nivcsw = 136,
+);
+
+impl Rusage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// `getrlimit`、`setrlimit`、`prlimit64` 中资源限制的种类，取值与 Linux 中的 `RLIMIT_*` 一致
+#[repr(usize)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum PrLimitRes {
+    /// 进程可以使用的 CPU 时间(秒)
+    RlimitCpu = 0,
+    /// 进程可以创建的文件的最大长度
+    RlimitFsize = 1,
+    /// 进程数据段(堆)的最大长度
+    RlimitData = 2,
+    /// 进程用户栈的最大长度
+    RlimitStack = 3,
+    /// core dump 文件的最大长度
+    RlimitCore = 4,
+    /// 进程驻留内存的最大长度
+    RlimitRss = 5,
+    /// 可以同时存在的最大进程(线程)数
+    RlimitNproc = 6,
+    /// 进程可以打开的文件描述符数量的上限加一
+    RlimitNofile = 7,
+    /// 可以锁定在内存中的最大字节数
+    RlimitMemlock = 8,
+    /// 进程虚拟地址空间的最大长度
+    RlimitAs = 9,
+    /// 进程可以持有的文件锁的数量
+    RlimitLocks = 10,
+    /// 可以排队等待的信号的数量
+    RlimitSigpending = 11,
+    /// POSIX 消息队列可以使用的最大字节数
+    RlimitMsgqueue = 12,
+    /// nice 值的上限，实际上限为 20 - rlim_cur
+    RlimitNice = 13,
+    /// 实时调度优先级的上限
+    RlimitRtprio = 14,
+    /// 实时调度的进程在不阻塞的情况下可以使用的 CPU 时间(微秒)
+    RlimitRttime = 15,
+}
+
+/// 资源种类的数量
+pub const RLIM_NLIMITS: usize = 16;
+/// 表示不限制资源使用量
+pub const RLIM_INFINITY: u64 = u64::MAX;
+
+/// 取值不合法时返回原值
+impl TryFrom<usize> for PrLimitRes {
+    type Error = usize;
+    fn try_from(value: usize) -> Result<Self, Self::Error> {
+        let res = match value {
+            0 => PrLimitRes::RlimitCpu,
+            1 => PrLimitRes::RlimitFsize,
+            2 => PrLimitRes::RlimitData,
+            3 => PrLimitRes::RlimitStack,
+            4 => PrLimitRes::RlimitCore,
+            5 => PrLimitRes::RlimitRss,
+            6 => PrLimitRes::RlimitNproc,
+            7 => PrLimitRes::RlimitNofile,
+            8 => PrLimitRes::RlimitMemlock,
+            9 => PrLimitRes::RlimitAs,
+            10 => PrLimitRes::RlimitLocks,
+            11 => PrLimitRes::RlimitSigpending,
+            12 => PrLimitRes::RlimitMsgqueue,
+            13 => PrLimitRes::RlimitNice,
+            14 => PrLimitRes::RlimitRtprio,
+            15 => PrLimitRes::RlimitRttime,
+            _ => return Err(value),
+        };
+        Ok(res)
+    }
+}
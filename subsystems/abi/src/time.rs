@@ -0,0 +1,47 @@
+/// 秒与微秒表示的时间，对应 `struct timeval`
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TimeVal {
+    /// seconds
+    pub tv_sec: usize,
+    /// microseconds
+    pub tv_usec: usize,
+}
+
+assert_layout!(TimeVal, size = 16, align = 8, tv_sec = 0, tv_usec = 8);
+
+impl TimeVal {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// `times` 返回的进程运行时间，对应 `struct tms`
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Times {
+    /// the ticks of user mode
+    pub tms_utime: usize,
+    /// the ticks of kernel mode
+    pub tms_stime: usize,
+    /// the ticks of user mode of child process
+    pub tms_cutime: usize,
+    /// the ticks of kernel mode of child process
+    pub tms_cstime: usize,
+}
+
+assert_layout!(
+    Times,
+    size = 32,
+    align = 8,
+    tms_utime = 0,
+    tms_stime = 8,
+    tms_cutime = 16,
+    tms_cstime = 24,
+);
+
+impl Times {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
@@ -0,0 +1,85 @@
+//! 宿主机后端，用于在 Linux 上通过 `cargo test` 测试依赖本 crate 的子系统
+//!
+//! 每个线程被视为一个核，中断开关只记录在线程本地，页表与缓存相关的操作为空操作。
+use std::cell::Cell;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::OnceLock;
+use std::time::Instant;
+
+static NEXT_HART: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    static HART_ID: usize = NEXT_HART.fetch_add(1, Ordering::Relaxed);
+    static INTERRUPT_ENABLE: Cell<bool> = const { Cell::new(true) };
+}
+
+/// 获取当前的 hart id，每个线程第一次调用时分配一个新的 id
+pub fn hart_id() -> usize {
+    HART_ID.with(|id| *id)
+}
+
+/// 检查全局中断是否开启
+pub fn is_interrupt_enable() -> bool {
+    INTERRUPT_ENABLE.with(|enable| enable.get())
+}
+
+/// 关闭全局中断
+pub fn interrupt_disable() {
+    INTERRUPT_ENABLE.with(|enable| enable.set(false))
+}
+
+/// 开启全局中断
+pub fn interrupt_enable() {
+    INTERRUPT_ENABLE.with(|enable| enable.set(true))
+}
+
+pub fn external_interrupt_enable() {}
+
+pub fn software_interrupt_enable() {}
+
+pub fn clear_software_interrupt() {}
+
+pub fn external_interrupt_disable() {}
+
+pub fn timer_interrupt_enable() {}
+
+/// 读取时钟，单位为纳秒，对应宿主机平台的 `CLOCK_FREQ`
+pub fn read_timer() -> usize {
+    static START: OnceLock<Instant> = OnceLock::new();
+    START.get_or_init(Instant::now).elapsed().as_nanos() as usize
+}
+
+/// 宿主机上不回溯调用栈，返回 0
+pub fn frame_pointer() -> usize {
+    0
+}
+
+pub fn activate_paging_mode(_root_ppn: usize) {}
+
+pub fn flush_tlb() {}
+
+pub fn flush_icache() {}
+
+pub fn allow_access_user_memory() {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hart_id_per_thread() {
+        let id = hart_id();
+        assert_eq!(hart_id(), id);
+        let other = std::thread::spawn(hart_id).join().unwrap();
+        assert_ne!(other, id);
+    }
+
+    #[test]
+    fn test_interrupt_flag_is_thread_local() {
+        interrupt_disable();
+        assert!(!is_interrupt_enable());
+        assert!(std::thread::spawn(is_interrupt_enable).join().unwrap());
+        interrupt_enable();
+        assert!(is_interrupt_enable());
+    }
+}
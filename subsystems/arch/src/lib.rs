@@ -1,5 +1,13 @@
-#![no_std]
+#![cfg_attr(not(feature = "host"), no_std)]
 
+#[cfg(not(feature = "host"))]
 mod riscv;
 
+#[cfg(not(feature = "host"))]
 pub use riscv::*;
+
+#[cfg(feature = "host")]
+mod host;
+
+#[cfg(feature = "host")]
+pub use host::*;
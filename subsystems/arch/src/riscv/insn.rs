@@ -0,0 +1,101 @@
+//! 指令解码，RISC-V 没有硬件单步执行，调试器需要据此在下一条指令的位置写入临时断点
+
+/// `ebreak` 指令
+pub const EBREAK: u32 = 0x0010_0073;
+/// `c.ebreak` 指令，调试器使用它作为软件断点
+pub const C_EBREAK: u16 = 0x9002;
+
+/// 根据指令的低 16 位获取指令的长度
+pub fn insn_len(low: u16) -> usize {
+    if low & 0b11 == 0b11 {
+        4
+    } else {
+        2
+    }
+}
+
+/// 计算位于 `pc` 的指令 `insn` 执行后可能到达的位置，`regs` 为执行前的通用寄存器(`regs[0]` 必须为 0)。
+///
+/// 对于压缩指令，`insn` 只有低 16 位有效。返回值的第一项是顺序执行或者跳转到达的位置，
+/// 第二项是条件分支跳转时到达的位置。
+pub fn next_pcs(pc: usize, insn: u32, regs: &[usize; 32]) -> (usize, Option<usize>) {
+    let reg = |index: usize| regs[index];
+    if insn_len(insn as u16) == 2 {
+        // 压缩指令
+        let insn = insn & 0xffff;
+        let funct3 = insn >> 13;
+        let bit = |n: u32| ((insn >> n) & 1) as isize;
+        let next = pc + 2;
+        return match (insn & 0b11, funct3) {
+            // c.j
+            (0b01, 0b101) => {
+                let imm = (bit(12) << 11)
+                    | (bit(11) << 4)
+                    | (bit(10) << 9)
+                    | (bit(9) << 8)
+                    | (bit(8) << 10)
+                    | (bit(7) << 6)
+                    | (bit(6) << 7)
+                    | (bit(5) << 3)
+                    | (bit(4) << 2)
+                    | (bit(3) << 1)
+                    | (bit(2) << 5);
+                let imm = (imm << 52) >> 52;
+                (pc.wrapping_add_signed(imm), None)
+            }
+            // c.beqz / c.bnez
+            (0b01, 0b110) | (0b01, 0b111) => {
+                let imm = (bit(12) << 8)
+                    | (bit(11) << 4)
+                    | (bit(10) << 3)
+                    | (bit(6) << 7)
+                    | (bit(5) << 6)
+                    | (bit(4) << 2)
+                    | (bit(3) << 1)
+                    | (bit(2) << 5);
+                let imm = (imm << 55) >> 55;
+                (next, Some(pc.wrapping_add_signed(imm)))
+            }
+            // c.jr / c.jalr
+            (0b10, 0b100) => {
+                let rs1 = ((insn >> 7) & 0x1f) as usize;
+                let rs2 = (insn >> 2) & 0x1f;
+                if rs1 != 0 && rs2 == 0 {
+                    (reg(rs1) & !1, None)
+                } else {
+                    (next, None)
+                }
+            }
+            _ => (next, None),
+        };
+    }
+    let next = pc + 4;
+    let bits = |hi: u32, lo: u32| ((insn >> lo) & ((1 << (hi - lo + 1)) - 1)) as isize;
+    match insn & 0x7f {
+        // jal
+        0x6f => {
+            let imm = (bits(31, 31) << 20)
+                | (bits(19, 12) << 12)
+                | (bits(20, 20) << 11)
+                | (bits(30, 21) << 1);
+            let imm = (imm << 43) >> 43;
+            (pc.wrapping_add_signed(imm), None)
+        }
+        // jalr
+        0x67 => {
+            let rs1 = bits(19, 15) as usize;
+            let imm = (insn as i32 >> 20) as isize;
+            (reg(rs1).wrapping_add_signed(imm) & !1, None)
+        }
+        // beq, bne, blt, bge, bltu, bgeu
+        0x63 => {
+            let imm = (bits(31, 31) << 12)
+                | (bits(7, 7) << 11)
+                | (bits(30, 25) << 5)
+                | (bits(11, 8) << 1);
+            let imm = (imm << 51) >> 51;
+            (next, Some(pc.wrapping_add_signed(imm)))
+        }
+        _ => (next, None),
+    }
+}
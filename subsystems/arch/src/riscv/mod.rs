@@ -1,5 +1,7 @@
+mod insn;
 mod regs;
 
+pub use insn::*;
 pub use regs::*;
 
 use core::arch::asm;
@@ -50,6 +52,13 @@ pub fn software_interrupt_enable() {
     }
 }
 
+/// 清除当前核上等待处理的软件中断
+pub fn clear_software_interrupt() {
+    unsafe {
+        asm!("csrc sip, {}", in(reg) 1 << 1);
+    }
+}
+
 /// 关闭外部中断
 pub fn external_interrupt_disable() {
     unsafe {
@@ -69,6 +78,16 @@ pub fn read_timer() -> usize {
     riscv::register::time::read()
 }
 
+/// 读取调用者的帧指针 `s0`，需要内联到调用者中才能得到调用者的栈帧
+#[inline(always)]
+pub fn frame_pointer() -> usize {
+    let fp: usize;
+    unsafe {
+        asm!("mv {}, s0", out(reg) fp);
+    }
+    fp
+}
+
 /// 激活页表模式
 pub fn activate_paging_mode(root_ppn: usize) {
     unsafe {
@@ -78,6 +97,20 @@ pub fn activate_paging_mode(root_ppn: usize) {
     }
 }
 
+/// 刷新当前核的 TLB
+pub fn flush_tlb() {
+    unsafe {
+        sfence_vma_all();
+    }
+}
+
+/// 同步当前核的指令缓存，修改代码后需要调用
+pub fn flush_icache() {
+    unsafe {
+        asm!("fence.i");
+    }
+}
+
 /// Permit Supervisor User Memory access
 pub fn allow_access_user_memory() {
     unsafe {
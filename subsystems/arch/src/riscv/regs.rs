@@ -1,5 +1,5 @@
 use core::arch::asm;
-use riscv::register::sstatus::SPP;
+use riscv::register::sstatus::{FS, SPP};
 
 #[derive(Debug, Default, Copy, Clone)]
 pub struct ExtSstatus(pub usize);
@@ -36,4 +36,16 @@ impl ExtSstatus {
     pub fn set_sie(&mut self, value: bool) {
         self.0 = self.0 & !(1 << 1) | ((value as usize) << 1);
     }
+    /// 浮点单元的状态(sstatus.FS)
+    pub fn fs(&self) -> FS {
+        match (self.0 >> 13) & 0b11 {
+            0 => FS::Off,
+            1 => FS::Initial,
+            2 => FS::Clean,
+            _ => FS::Dirty,
+        }
+    }
+    pub fn set_fs(&mut self, fs: FS) {
+        self.0 = self.0 & !(0b11 << 13) | ((fs as usize) << 13);
+    }
 }
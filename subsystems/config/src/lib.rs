@@ -29,6 +29,8 @@ pub const PROCESS_HEAP_MAX: usize = u32::MAX as usize + 1;
 pub const TRAMPOLINE: usize = usize::MAX - 2 * FRAME_SIZE + 1;
 /// trap context的虚拟地址
 pub const TRAP_CONTEXT_BASE: usize = TRAMPOLINE - FRAME_SIZE;
+/// 用户地址空间的上界(Sv39 下低 256GB)，内核访问用户内存时不会越过此地址
+pub const USER_SPACE_END: usize = 1 << 38;
 
 /// app内核栈大小
 pub const USER_KERNEL_STACK_SIZE: usize = 0x1000 * 2;
@@ -42,6 +44,8 @@ pub const PIPE_BUF: usize = 65536;
 pub const MAX_THREAD_NUM: usize = 65536;
 /// 描述符数量大小限制
 pub const MAX_FD_NUM: usize = 4096;
+/// 路径的最大长度(包括结尾的 '\0')
+pub const PATH_MAX: usize = 4096;
 
 /// 最大的输入事件数量
 pub const MAX_INPUT_EVENT_NUM: usize = 1024;
@@ -36,3 +36,32 @@ impl From<u64> for DeviceId {
 }
 
 pub const AT_FDCWD: isize = -100isize;
+
+/// 资源限制的种类由 `abi` 定义，`pconst` 中的同名定义只包含 STACK、NOFILE 和 AS 三种资源，不再使用
+pub use abi::{PrLimitRes, RLIM_INFINITY, RLIM_NLIMITS};
+
+/// 与用户态共享的结构与标志位统一由 `abi` 定义，覆盖 `pconst` 中的同名定义，保证内核与 `Mstd` 使用同一份布局。
+pub mod io {
+    pub use abi::{Dirent64, DirentType, MapFlags, OpenFlags, ProtFlags, Stat as FileStat};
+    pub use pconst::io::*;
+}
+
+pub mod signal {
+    pub use abi::{MContext, SignalStack, SignalUserContext, UserFpState};
+    pub use pconst::signal::*;
+}
+
+pub mod task {
+    pub use abi::CloneFlags;
+    pub use pconst::task::*;
+}
+
+pub mod sys {
+    pub use abi::{Rusage, TimeVal, Times};
+    pub use pconst::sys::*;
+}
+
+pub mod net {
+    pub use abi::Sockaddr;
+    pub use pconst::net::*;
+}
@@ -1,5 +1,15 @@
 #![no_std]
 
+#[cfg(feature = "host")]
+extern crate alloc;
+#[cfg(test)]
+extern crate std;
+
+#[cfg(feature = "host")]
+mod mem_block;
+#[cfg(feature = "host")]
+pub use mem_block::{MemBlockDevice, MEM_BLOCK_SIZE};
+
 use constants::io::RtcTime;
 use constants::AlienResult;
 use core::any::Any;
@@ -0,0 +1,124 @@
+//! 以内存作为存储的块设备，用于在宿主机上测试文件系统等依赖块设备的模块
+use alloc::vec;
+use alloc::vec::Vec;
+
+use constants::{AlienResult, LinuxErrno};
+
+use crate::LowBlockDevice;
+
+/// 块大小
+pub const MEM_BLOCK_SIZE: usize = 512;
+
+pub struct MemBlockDevice {
+    data: Vec<u8>,
+}
+
+impl MemBlockDevice {
+    /// 创建一个有 `blocks` 个块且内容全为 0 的设备
+    pub fn new(blocks: usize) -> Self {
+        Self {
+            data: vec![0; blocks * MEM_BLOCK_SIZE],
+        }
+    }
+
+    /// 使用磁盘镜像创建设备，镜像的大小不是块大小的整数倍时在末尾补 0
+    pub fn from_image(mut data: Vec<u8>) -> Self {
+        let len = data.len().div_ceil(MEM_BLOCK_SIZE) * MEM_BLOCK_SIZE;
+        data.resize(len, 0);
+        Self { data }
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// `buf` 可以包含多个连续的块，长度必须是块大小的整数倍
+    fn range(&self, block_id: usize, len: usize) -> AlienResult<core::ops::Range<usize>> {
+        if len % MEM_BLOCK_SIZE != 0 {
+            return Err(LinuxErrno::EINVAL);
+        }
+        let start = block_id
+            .checked_mul(MEM_BLOCK_SIZE)
+            .ok_or(LinuxErrno::EIO)?;
+        let end = start.checked_add(len).ok_or(LinuxErrno::EIO)?;
+        if end > self.data.len() {
+            return Err(LinuxErrno::EIO);
+        }
+        Ok(start..end)
+    }
+}
+
+impl LowBlockDevice for MemBlockDevice {
+    fn read_block(&mut self, block_id: usize, buf: &mut [u8]) -> AlienResult<()> {
+        let range = self.range(block_id, buf.len())?;
+        buf.copy_from_slice(&self.data[range]);
+        Ok(())
+    }
+
+    fn write_block(&mut self, block_id: usize, buf: &[u8]) -> AlienResult<()> {
+        let range = self.range(block_id, buf.len())?;
+        self.data[range].copy_from_slice(buf);
+        Ok(())
+    }
+
+    fn capacity(&self) -> usize {
+        self.data.len() / MEM_BLOCK_SIZE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quickcheck::quickcheck;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_read_write_block() {
+        let mut device = MemBlockDevice::new(8);
+        assert_eq!(device.capacity(), 8);
+        let block = [0x5a; MEM_BLOCK_SIZE];
+        device.write_block(3, &block).unwrap();
+        let mut buf = [0; MEM_BLOCK_SIZE * 2];
+        device.read_block(2, &mut buf).unwrap();
+        assert!(buf[..MEM_BLOCK_SIZE].iter().all(|&b| b == 0));
+        assert_eq!(&buf[MEM_BLOCK_SIZE..], &block);
+    }
+
+    #[test]
+    fn test_out_of_range() {
+        let mut device = MemBlockDevice::new(2);
+        let mut buf = [0; MEM_BLOCK_SIZE];
+        assert_eq!(device.read_block(2, &mut buf), Err(LinuxErrno::EIO));
+        assert_eq!(device.write_block(usize::MAX, &buf), Err(LinuxErrno::EIO));
+        assert_eq!(
+            device.read_block(0, &mut buf[..100]),
+            Err(LinuxErrno::EINVAL)
+        );
+    }
+
+    #[test]
+    fn test_from_image() {
+        let device = MemBlockDevice::from_image(vec![1; MEM_BLOCK_SIZE + 1]);
+        assert_eq!(device.capacity(), 2);
+        assert_eq!(device.as_bytes()[MEM_BLOCK_SIZE + 1], 0);
+    }
+
+    quickcheck! {
+        /// 任意顺序写入后，每个块读出的都是最后一次写入的内容
+        fn prop_read_after_write(writes: Vec<(u8, u8)>) -> bool {
+            let mut device = MemBlockDevice::new(16);
+            let mut model = BTreeMap::new();
+            for (block_id, value) in writes {
+                let block_id = block_id as usize % 16;
+                device.write_block(block_id, &[value; MEM_BLOCK_SIZE]).unwrap();
+                model.insert(block_id, value);
+            }
+            (0..16).all(|block_id| {
+                let mut buf = [0xff; MEM_BLOCK_SIZE];
+                device.read_block(block_id, &mut buf).unwrap();
+                let expect = model.get(&block_id).copied().unwrap_or(0);
+                buf.iter().all(|&b| b == expect)
+            })
+        }
+    }
+}
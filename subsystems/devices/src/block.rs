@@ -1,7 +1,9 @@
 use alloc::sync::Arc;
+use alloc::vec::Vec;
 use constants::DeviceId;
 use device_interface::BlockDevice;
-use spin::Once;
+use ksync::Mutex;
+use spin::Lazy;
 use vfscore::error::VfsError;
 use vfscore::file::VfsFile;
 use vfscore::inode::{InodeAttr, VfsInode};
@@ -9,11 +11,16 @@ use vfscore::utils::{VfsFileStat, VfsNodeType, VfsPollEvents};
 use vfscore::VfsResult;
 
 use drivers::block_device::GenericBlockDevice;
-pub static BLOCK_DEVICE: Once<Arc<GenericBlockDevice>> = Once::new();
+pub static BLOCK_DEVICES: Lazy<Mutex<Vec<Arc<GenericBlockDevice>>>> =
+    Lazy::new(|| Mutex::new(Vec::new()));
 
 pub fn init_block_device(block_device: Arc<GenericBlockDevice>) {
-    // BLOCK_DEVICE.lock().push(block_device);
-    BLOCK_DEVICE.call_once(|| block_device);
+    BLOCK_DEVICES.lock().push(block_device);
+}
+
+/// 获取系统中所有的块设备，顺序与探测到的顺序一致
+pub fn block_devices() -> Vec<Arc<GenericBlockDevice>> {
+    BLOCK_DEVICES.lock().clone()
 }
 
 pub struct BLKDevice {
@@ -15,21 +15,22 @@ use alloc::sync::Arc;
 
 use crate::prob::Probe;
 use alloc::vec::Vec;
-pub use block::{BLKDevice, BLOCK_DEVICE};
+pub use block::{block_devices, BLKDevice, BLOCK_DEVICES};
 use config::MAX_INPUT_EVENT_NUM;
 use core::ptr::NonNull;
 use device_interface::{DeviceBase, GpuDevice, LowBlockDevice};
 use drivers::block_device::GenericBlockDevice;
 use drivers::rtc::GoldFishRtc;
-use drivers::uart::{Uart, Uart16550, Uart8250};
+use drivers::uart::{LowUartDriver, Uart, Uart16550, Uart8250};
 use fdt::Fdt;
 pub use gpu::{GPUDevice, GPU_DEVICE};
 pub use input::{INPUTDevice, KEYBOARD_INPUT_DEVICE, MOUSE_INPUT_DEVICE};
 use interrupt::register_device_to_plic;
+use ksync::Mutex;
 use log::info;
 use platform::println;
 pub use rtc::{RTCDevice, RTC_DEVICE};
-pub use uart::{UARTDevice, UART_DEVICE};
+pub use uart::{DebugUart, UARTDevice, DEBUG_UART, UART_DEVICE};
 use virtio_drivers::transport::mmio::{MmioTransport, VirtIOHeader};
 use virtio_drivers::transport::{DeviceType, Transport};
 
@@ -61,6 +62,14 @@ pub fn init_device() {
         }
     }
 
+    if let Some(index) = platform::cmdline::kernel_cmdline().kgdb {
+        match dtb.probe_uarts().into_iter().nth(index) {
+            Some(uart) if index != 0 => init_debug_uart(uart),
+            Some(_) => println!("The console uart can't be used as the debug port"),
+            None => println!("There is no uart {} for kgdb", index),
+        }
+    }
+
     #[cfg(not(all(feature = "vf2", feature = "hifive")))]
     {
         match dtb.probe_virtio() {
@@ -117,28 +126,48 @@ fn init_rtc(rtc: prob::DeviceInfo) {
 fn init_uart(uart: prob::DeviceInfo) {
     let (base_addr, irq) = (uart.base_addr, uart.irq);
     println!("Init uart, base_addr:{:#x},irq:{}", base_addr, irq);
-    match uart.compatible.as_str() {
-        "ns16550a" => {
-            // qemu
-            let uart = Uart16550::new(base_addr);
-            let uart = Arc::new(Uart::new(Box::new(uart)));
+    match low_uart_driver(&uart) {
+        Some(low_uart) => {
+            let uart = Arc::new(Uart::new(low_uart));
             uart::init_uart(uart.clone());
             register_device_to_plic(irq, uart);
         }
-        "snps,dw-apb-uart" => {
-            // vf2
-            let uart = Uart8250::new(base_addr);
-            let uart = Arc::new(Uart::new(Box::new(uart)));
-            uart::init_uart(uart.clone());
-            register_device_to_plic(irq, uart);
-        }
-        name => {
-            panic!("Don't support uart: {}", name);
+        None => {
+            panic!("Don't support uart: {}", uart.compatible);
         }
     }
     println!("Init uart success");
 }
 
+/// 初始化内核调试端口使用的串口，该串口不注册中断
+fn init_debug_uart(uart: prob::DeviceInfo) {
+    println!(
+        "Init debug uart {}, base_addr:{:#x}",
+        uart.name, uart.base_addr
+    );
+    match low_uart_driver(&uart) {
+        Some(low_uart) => {
+            uart::DEBUG_UART.call_once(|| DebugUart {
+                base_addr: uart.base_addr,
+                uart: Mutex::new(low_uart),
+            });
+        }
+        None => {
+            println!("Don't support uart: {}", uart.compatible);
+        }
+    }
+}
+
+fn low_uart_driver(uart: &prob::DeviceInfo) -> Option<Box<dyn LowUartDriver>> {
+    match uart.compatible.as_str() {
+        // qemu
+        "ns16550a" => Some(Box::new(Uart16550::new(uart.base_addr))),
+        // vf2
+        "snps,dw-apb-uart" => Some(Box::new(Uart8250::new(uart.base_addr))),
+        _ => None,
+    }
+}
+
 // keyboard
 const VIRTIO5: usize = 0x10005000;
 // mouse
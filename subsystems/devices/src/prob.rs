@@ -27,6 +27,8 @@ impl DeviceInfo {
 pub trait Probe {
     /// Get the base address and irq number of the uart device from the device tree.
     fn probe_uart(&self) -> Option<DeviceInfo>;
+    /// Get all uart devices from the device tree, in the order they appear.
+    fn probe_uarts(&self) -> Vec<DeviceInfo>;
     /// Get the base address and irq number of the rtc device from the device tree.
     fn probe_rtc(&self) -> Option<DeviceInfo>;
     /// Get the base address and irq number of the virtio devices from the device tree.
@@ -46,6 +48,29 @@ impl Probe for Fdt<'_> {
         }
     }
 
+    fn probe_uarts(&self) -> Vec<DeviceInfo> {
+        let mut uarts = Vec::new();
+        for node in self.all_nodes() {
+            if !node.name.starts_with("uart") && !node.name.starts_with("serial") {
+                continue;
+            }
+            let info = (|| {
+                let reg = node.reg()?.next()?;
+                let irq = node.property("interrupts")?.value;
+                let irq = u32::from_be_bytes(irq[0..4].try_into().ok()?);
+                let compatible = node.compatible().map(Compatible::first)?;
+                Some(DeviceInfo::new(
+                    node.name.to_string(),
+                    reg.starting_address as usize,
+                    irq as usize,
+                    compatible.to_string(),
+                ))
+            })();
+            uarts.extend(info);
+        }
+        uarts
+    }
+
     fn probe_rtc(&self) -> Option<DeviceInfo> {
         self.probe_common("rtc")
     }
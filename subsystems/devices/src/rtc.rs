@@ -54,7 +54,8 @@ impl VfsFile for RTCDevice {
         match cmd {
             TeletypeCommand::RTC_RD_TIME => {
                 let time = self.device.read_time();
-                shim::copy_data_to_task(&time, arg as *mut RtcTime);
+                shim::copy_data_to_task(&time, arg as *mut RtcTime)
+                    .map_err(|_| VfsError::Invalid)?;
             }
             _ => return Err(VfsError::Invalid),
         }
@@ -1,7 +1,9 @@
+use alloc::boxed::Box;
 use alloc::sync::Arc;
 use constants::io::{LocalModes, TeletypeCommand, Termios, WinSize};
 use constants::DeviceId;
 use device_interface::UartDevice;
+use drivers::uart::LowUartDriver;
 use ksync::Mutex;
 use spin::Once;
 use vfscore::error::VfsError;
@@ -17,6 +19,15 @@ pub fn init_uart(uart: Arc<dyn UartDevice>) {
     UART_DEVICE.call_once(|| uart);
 }
 
+/// 用作内核调试端口的串口，只能以轮询方式访问
+pub struct DebugUart {
+    /// 串口寄存器的起始地址，使用前需要映射到内核地址空间中
+    pub base_addr: usize,
+    pub uart: Mutex<Box<dyn LowUartDriver>>,
+}
+
+pub static DEBUG_UART: Once<DebugUart> = Once::new();
+
 #[derive(Debug, Default)]
 pub struct IoData {
     foreground_pgid: u32,
@@ -96,29 +107,33 @@ impl VfsFile for UARTDevice {
         let cmd = TeletypeCommand::try_from(cmd).unwrap();
         return match cmd {
             TeletypeCommand::TCGETS | TeletypeCommand::TCGETA => {
-                shim::copy_data_to_task(&io.termios, arg as *mut Termios);
+                shim::copy_data_to_task(&io.termios, arg as *mut Termios)
+                    .map_err(|_| VfsError::Invalid)?;
                 Ok(0)
             }
             TeletypeCommand::TCSETS | TeletypeCommand::TCSETSW | TeletypeCommand::TCSETSF => {
-                shim::copy_data_from_task(arg as *const Termios, &mut io.termios);
+                shim::copy_data_from_task(arg as *const Termios, &mut io.termios)
+                    .map_err(|_| VfsError::Invalid)?;
                 Ok(0)
             }
             TeletypeCommand::TIOCGPGRP => {
-                let word = shim::transfer_ptr_mut(arg as *mut u32);
-                *word = io.foreground_pgid;
+                shim::copy_data_to_task(&io.foreground_pgid, arg as *mut u32)
+                    .map_err(|_| VfsError::Invalid)?;
                 Ok(0)
             }
             TeletypeCommand::TIOCSPGRP => {
-                let word = shim::transfer_ptr(arg as *const u32);
-                io.foreground_pgid = *word;
+                shim::copy_data_from_task(arg as *const u32, &mut io.foreground_pgid)
+                    .map_err(|_| VfsError::Invalid)?;
                 Ok(0)
             }
             TeletypeCommand::TIOCGWINSZ => {
-                shim::copy_data_to_task(&io.winsize, arg as *mut WinSize);
+                shim::copy_data_to_task(&io.winsize, arg as *mut WinSize)
+                    .map_err(|_| VfsError::Invalid)?;
                 Ok(0)
             }
             TeletypeCommand::TIOCSWINSZ => {
-                shim::copy_data_from_task(arg as *const WinSize, &mut io.winsize);
+                shim::copy_data_from_task(arg as *const WinSize, &mut io.winsize)
+                    .map_err(|_| VfsError::Invalid)?;
                 Ok(0)
             }
             _ => {
@@ -94,9 +94,9 @@ impl<T: Clone> MinimalManager<T> {
         if index >= self.max {
             return Err(ManagerError::IndexOver);
         }
-        let val = self.data.get(index).unwrap();
-        if val.is_none() {
-            return Err(ManagerError::NotExist);
+        match self.data.get(index) {
+            Some(Some(_)) => {}
+            _ => return Err(ManagerError::NotExist),
         }
         self.data[index] = None;
         if self.usable > index {
@@ -168,7 +168,9 @@ impl From<usize> for ManagerError {
 
 #[cfg(test)]
 mod tests {
-    use crate::MinimalManager;
+    use crate::{ManagerError, MinimalManager};
+    use quickcheck::quickcheck;
+    use std::collections::BTreeMap;
     #[test]
     pub fn test_gmanager() {
         let mut manager = MinimalManager::<usize>::new(10);
@@ -184,4 +186,37 @@ mod tests {
         let index = manager.insert(10).unwrap();
         assert_eq!(index, 1);
     }
+
+    quickcheck! {
+        /// 插入总是返回最小的空闲索引，删除与查询的结果与 `BTreeMap` 一致
+        fn prop_matches_model(ops: Vec<(bool, u8)>) -> bool {
+            const MAX: usize = 16;
+            let mut manager = MinimalManager::<u8>::new(MAX);
+            let mut model = BTreeMap::new();
+            for (insert, value) in ops {
+                if insert {
+                    let expect = (0..MAX).find(|index| !model.contains_key(index));
+                    match (manager.insert(value), expect) {
+                        (Ok(index), Some(expect)) if index == expect => {
+                            model.insert(index, value);
+                        }
+                        (Err(ManagerError::NoSpace), None) => {}
+                        _ => return false,
+                    }
+                } else {
+                    let index = value as usize % (MAX + 4);
+                    let ok = match manager.remove(index) {
+                        Ok(()) => model.remove(&index).is_some(),
+                        Err(ManagerError::IndexOver) => index >= MAX,
+                        Err(ManagerError::NotExist) => !model.contains_key(&index),
+                        Err(ManagerError::NoSpace) => false,
+                    };
+                    if !ok {
+                        return false;
+                    }
+                }
+            }
+            (0..MAX).all(|index| manager.get(index).ok().flatten() == model.get(&index).copied())
+        }
+    }
 }
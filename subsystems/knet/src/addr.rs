@@ -11,9 +11,8 @@
 //! 向下层的具体套接字中传递相应地址时，传递的也是 [`SocketAddrExt`] 结构。
 //!
 use alloc::string::String;
-use constants::net::Domain;
 use core::fmt::Debug;
-use core::net::{IpAddr, SocketAddr};
+use core::net::SocketAddr;
 
 /// 用于存储套接字通信地址的结构，分为本地路径地址和网络套接字地址。
 #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Debug)]
@@ -25,19 +24,8 @@ pub enum SocketAddrExt {
 /// 用于存储一个Ipv4套接字相关信息的结构。对应 `linux` 中 `socket.h` 的 `sockaddr_in` 结构。
 ///
 /// 在 socket 相关系统调用中，一般都先分析出套接字采用的地址协议族，如果是 `IPV4` 则会将传入的套接字相关信息解析成 `RawIpV4Addr`。
-/// 且 `Alien` 目前默认使用网络套接字时，即采用 `IPV4` 协议。
-#[repr(C)]
-#[derive(Debug, Clone, Copy, Default)]
-pub struct RawIpV4Addr {
-    /// 地址协议族
-    pub family: u16,
-    /// Ipv4 的端口
-    pub port: u16,
-    /// Ipv4 的地址
-    pub addr: u32,
-    /// 零位，用于后续扩展
-    pub zero: [u8; 8],
-}
+/// 且 `Alien` 目前默认使用网络套接字时，即采用 `IPV4` 协议。该结构与用户态共用 `abi` 中的定义。
+pub use constants::net::Sockaddr as RawIpV4Addr;
 
 impl SocketAddrExt {
     /// 获取网络套接字地址。当本结构中存储的是本地路径地址时，将导致 panic。
@@ -60,25 +48,3 @@ impl SocketAddrExt {
         }
     }
 }
-
-impl From<SocketAddr> for RawIpV4Addr {
-    /// 用一个 [`SocketAddr`] 结构 初始化 `RawIpV4Addr  `
-    fn from(addr: SocketAddr) -> Self {
-        let ip = addr.ip();
-        let port = addr.port();
-        let ip = match ip {
-            IpAddr::V4(ip) => ip,
-            IpAddr::V6(_) => {
-                panic!("ipv6 is not supported")
-            }
-        };
-        let ip = ip.octets();
-        let ip = u32::from_le_bytes(ip);
-        Self {
-            family: Domain::AF_INET as u16,
-            port: port.to_be(),
-            addr: ip,
-            zero: [0u8; 8],
-        }
-    }
-}
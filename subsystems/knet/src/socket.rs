@@ -465,3 +465,59 @@ impl SocketData {
         }
     }
 }
+
+#[cfg(feature = "ktest")]
+mod ktests {
+    use super::*;
+    use core::net::{Ipv4Addr, SocketAddrV4};
+    use ktest::{kassert_eq, kernel_test, TestResult};
+    use netcore::common::NetError;
+
+    fn loopback(port: u16) -> SocketAddrExt {
+        SocketAddrExt::SocketAddr(SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::LOCALHOST, port)))
+    }
+
+    #[kernel_test]
+    fn test_neterror_to_errno() -> TestResult {
+        kassert_eq!(neterror2alien(NetError::WouldBlock), LinuxErrno::EAGAIN);
+        kassert_eq!(neterror2alien(NetError::AddrInUse), LinuxErrno::EADDRINUSE);
+        kassert_eq!(neterror2alien(NetError::NotConnected), LinuxErrno::ENOTCONN);
+        Ok(())
+    }
+
+    #[kernel_test]
+    fn test_unsupported_socket_type() -> TestResult {
+        let res = SocketData::new(Domain::AF_INET, SocketType::SOCK_SEQPACKET, 0);
+        kassert_eq!(res.err(), Some(LinuxErrno::EPROTONOSUPPORT));
+        Ok(())
+    }
+
+    #[kernel_test]
+    fn test_udp_loopback() -> TestResult {
+        let receiver = SocketData::new(Domain::AF_INET, SocketType::SOCK_DGRAM, 0)?;
+        let sender = SocketData::new(Domain::AF_INET, SocketType::SOCK_DGRAM, 0)?;
+        let receiver = receiver.get_socketdata()?;
+        let sender = sender.get_socketdata()?;
+        receiver.set_socket_nonblock(true);
+        receiver.bind(loopback(45001))?;
+        sender.bind(loopback(45002))?;
+        kassert_eq!(
+            receiver.local_addr(),
+            Some(loopback(45001).get_socketaddr())
+        );
+        kassert_eq!(sender.send_to(b"ping", 0, Some(loopback(45001)))?, 4);
+        let mut buf = [0; 16];
+        // 非阻塞套接字在数据经过回环设备之前返回 EAGAIN
+        let mut res = Err(LinuxErrno::EAGAIN);
+        for _ in 0..100 {
+            res = receiver.recvfrom(&mut buf, 0);
+            if res != Err(LinuxErrno::EAGAIN) {
+                break;
+            }
+        }
+        let (len, from) = res?;
+        kassert_eq!(&buf[..len], b"ping");
+        kassert_eq!(from, loopback(45002).get_socketaddr());
+        Ok(())
+    }
+}
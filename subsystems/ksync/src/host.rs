@@ -0,0 +1,106 @@
+//! 宿主机后端使用的锁，基于 `std::sync::Mutex` 实现，接口与内核中的 `TicketMutex` 保持一致
+//!
+//! 持有锁的线程 panic 后锁仍然可以继续使用，与内核中的锁一样不存在“中毒”的状态。
+#[cfg(feature = "lockdep")]
+use kernel_sync::LockAction;
+use std::sync::{PoisonError, TryLockError};
+
+pub type MutexGuard<'a, T> = std::sync::MutexGuard<'a, T>;
+
+#[derive(Debug, Default)]
+pub struct Mutex<T: ?Sized>(std::sync::Mutex<T>);
+
+impl<T> Mutex<T> {
+    pub const fn new(data: T) -> Self {
+        Self(std::sync::Mutex::new(data))
+    }
+
+    pub fn into_inner(self) -> T {
+        self.0.into_inner().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<T: ?Sized> Mutex<T> {
+    pub fn lock(&self) -> MutexGuard<'_, T> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub fn try_lock(&self) -> Option<MutexGuard<'_, T>> {
+        match self.0.try_lock() {
+            Ok(guard) => Some(guard),
+            Err(TryLockError::Poisoned(err)) => Some(err.into_inner()),
+            Err(TryLockError::WouldBlock) => None,
+        }
+    }
+
+    pub fn is_locked(&self) -> bool {
+        self.try_lock().is_none()
+    }
+
+    pub fn get_mut(&mut self) -> &mut T {
+        self.0.get_mut().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// 宿主机上没有中断，lockdep 加锁时不需要关闭中断
+#[cfg(feature = "lockdep")]
+pub struct KernelLockAction;
+
+#[cfg(feature = "lockdep")]
+impl LockAction for KernelLockAction {
+    fn before_lock() {}
+    fn after_lock() {}
+}
+
+#[cfg(feature = "lockdep")]
+pub(crate) fn push_off() {}
+
+#[cfg(feature = "lockdep")]
+pub(crate) fn pop_off() {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_mutex_across_threads() {
+        let counter = Arc::new(Mutex::new(0usize));
+        let handles = (0..8)
+            .map(|_| {
+                let counter = counter.clone();
+                std::thread::spawn(move || {
+                    for _ in 0..1000 {
+                        *counter.lock() += 1;
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        handles.into_iter().for_each(|h| h.join().unwrap());
+        assert_eq!(*counter.lock(), 8000);
+    }
+
+    #[test]
+    fn test_try_lock() {
+        let mutex = Mutex::new(1);
+        let guard = mutex.lock();
+        assert!(mutex.is_locked());
+        assert!(mutex.try_lock().is_none());
+        drop(guard);
+        assert_eq!(*mutex.try_lock().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_lock_after_panic() {
+        let mutex = Arc::new(Mutex::new(1));
+        let cloned = mutex.clone();
+        let res = std::thread::spawn(move || {
+            let _guard = cloned.lock();
+            panic!("panic with lock held");
+        })
+        .join();
+        assert!(res.is_err());
+        *mutex.lock() += 1;
+        assert_eq!(*mutex.lock(), 2);
+    }
+}
@@ -1,16 +1,44 @@
-#![no_std]
+#![cfg_attr(not(feature = "host"), no_std)]
 
+#[cfg(not(feature = "host"))]
 use arch::{hart_id, interrupt_disable, interrupt_enable, is_interrupt_enable};
+#[cfg(not(feature = "host"))]
 use config::CPU_NUM;
+#[cfg(not(feature = "host"))]
 use core::cell::{RefCell, RefMut};
-use kernel_sync::{ticket::TicketMutexGuard, LockAction};
+#[cfg(all(not(feature = "host"), not(feature = "lockdep")))]
+use kernel_sync::ticket::TicketMutexGuard;
+#[cfg(not(feature = "host"))]
+use kernel_sync::LockAction;
 
+#[cfg(feature = "host")]
+mod host;
+// 与 host 一起打开时只用于测试依赖图，Mutex 仍然是 std 的锁
+#[cfg(feature = "lockdep")]
+pub mod lockdep;
+
+#[cfg(not(feature = "host"))]
 pub type SpinMutex<T> = kernel_sync::spin::SpinMutex<T, KernelLockAction>;
+#[cfg(not(feature = "host"))]
 pub type TicketMutex<T> = kernel_sync::ticket::TicketMutex<T, KernelLockAction>;
 pub type RwLock<T> = kernel_sync::RwLock<T>;
+#[cfg(all(not(feature = "host"), not(feature = "lockdep")))]
 pub type Mutex<T> = TicketMutex<T>;
+#[cfg(all(not(feature = "host"), not(feature = "lockdep")))]
 pub type MutexGuard<'a, T> = TicketMutexGuard<'a, T, KernelLockAction>;
+#[cfg(all(not(feature = "host"), feature = "lockdep"))]
+pub use lockdep::{Mutex, MutexGuard};
+
+#[cfg(all(feature = "host", feature = "lockdep"))]
+use host::{pop_off, push_off, KernelLockAction};
+#[cfg(feature = "host")]
+pub use host::{Mutex, MutexGuard};
+#[cfg(feature = "host")]
+pub type SpinMutex<T> = Mutex<T>;
+#[cfg(feature = "host")]
+pub type TicketMutex<T> = Mutex<T>;
 
+#[cfg(not(feature = "host"))]
 #[derive(Debug, Default, Clone, Copy)]
 #[repr(align(64))]
 pub struct Cpu {
@@ -18,6 +46,7 @@ pub struct Cpu {
     pub interrupt_enable: bool, // Were interrupts enabled before push_off()?
 }
 
+#[cfg(not(feature = "host"))]
 impl Cpu {
     const fn new() -> Self {
         Self {
@@ -27,22 +56,28 @@ impl Cpu {
     }
 }
 
+#[cfg(not(feature = "host"))]
 pub struct SafeRefCell<T>(RefCell<T>);
 
 /// # Safety: Only the corresponding cpu will access it.
+#[cfg(not(feature = "host"))]
 unsafe impl<Cpu> Sync for SafeRefCell<Cpu> {}
 
+#[cfg(not(feature = "host"))]
 impl<T> SafeRefCell<T> {
     const fn new(t: T) -> Self {
         Self(RefCell::new(t))
     }
 }
 
+#[cfg(not(feature = "host"))]
 #[allow(clippy::declare_interior_mutable_const)]
 const DEFAULT_CPU: SafeRefCell<Cpu> = SafeRefCell::new(Cpu::new());
 
+#[cfg(not(feature = "host"))]
 static CPUS: [SafeRefCell<Cpu>; CPU_NUM] = [DEFAULT_CPU; CPU_NUM];
 
+#[cfg(not(feature = "host"))]
 pub fn mycpu() -> RefMut<'static, Cpu> {
     CPUS[hart_id()].0.borrow_mut()
 }
@@ -50,6 +85,7 @@ pub fn mycpu() -> RefMut<'static, Cpu> {
 // push_off/pop_off are like intr_off()/intr_on() except that they are matched:
 // it takes two pop_off()s to undo two push_off()s.  Also, if interrupts
 // are initially off, then push_off, pop_off leaves them off.
+#[cfg(not(feature = "host"))]
 pub(crate) fn push_off() {
     let old = is_interrupt_enable();
     interrupt_disable();
@@ -60,6 +96,7 @@ pub(crate) fn push_off() {
     cpu.noff += 1;
 }
 
+#[cfg(not(feature = "host"))]
 pub(crate) fn pop_off() {
     let mut cpu = mycpu();
     if is_interrupt_enable() || cpu.noff < 1 {
@@ -74,7 +111,9 @@ pub(crate) fn pop_off() {
     }
 }
 
+#[cfg(not(feature = "host"))]
 pub struct KernelLockAction;
+#[cfg(not(feature = "host"))]
 impl LockAction for KernelLockAction {
     fn before_lock() {
         push_off();
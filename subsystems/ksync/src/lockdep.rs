@@ -0,0 +1,708 @@
+//! 锁依赖检查，打开 `lockdep` feature 时用于替换 [`Mutex`](crate::Mutex)
+//!
+//! 与 Linux 的 lockdep 类似，以锁保护的数据类型作为锁的类别，记录每个核上持有的锁以及类别之间的获取顺序：
+//! + 持有 A 的同时获取 B 时记录 A -> B，如果之前已经存在 B -> ... -> A，则报告可能发生的循环等待
+//! + 获取当前核已经持有的同一个锁时报告递归加锁，ticket lock 不可重入，这种情况一定会死锁
+//! + 记录每个类别的加锁次数、发生竞争的次数与等待时间，通过 [`for_each_class`] 读取
+//!
+//! 报告会打印当前加锁的调用栈，以及已经持有的锁或者相反顺序第一次出现时的调用栈，输出由内核通过
+//! [`register_reporter`] 注册。与 Linux 相同，报告一次之后不再检查，避免后续的输出淹没第一次报告，
+//! 统计信息仍然会继续记录。同一类别的不同锁之间不记录顺序，`try_lock` 不会等待，也不记录顺序。
+use crate::{pop_off, push_off, KernelLockAction};
+use arch::{frame_pointer, hart_id, read_timer};
+use config::CPU_NUM;
+use core::any::type_name;
+use core::cell::UnsafeCell;
+use core::fmt::{Arguments, Debug, Formatter};
+use core::ops::{Deref, DerefMut, Range};
+use core::sync::atomic::{AtomicBool, AtomicPtr, AtomicU16, AtomicU64, AtomicUsize, Ordering};
+use kernel_sync::spin::SpinMutex;
+use kernel_sync::ticket::{TicketMutex, TicketMutexGuard};
+
+/// 最多记录的锁类别数
+const MAX_CLASSES: usize = 256;
+/// 每个核最多同时持有的锁的数量，超过时不再记录之后获取的锁
+const MAX_HELD: usize = 32;
+/// 最多记录调用栈的依赖数，超过之后的依赖只记录顺序
+const MAX_TRACES: usize = 256;
+/// 每个调用栈记录的栈帧数
+const STACK_DEPTH: usize = 16;
+/// 报告中最多打印调用栈的依赖数
+const MAX_CHAIN: usize = 4;
+const WORDS: usize = MAX_CLASSES / 64;
+/// 类别表已满时使用的类别，这样的锁不参与检查
+const UNTRACKED: u16 = u16::MAX;
+
+/// 是否仍在检查，第一次报告之后关闭
+static CHECKING: AtomicBool = AtomicBool::new(true);
+static VIOLATIONS: AtomicUsize = AtomicUsize::new(0);
+static OVERFLOW_WARNED: AtomicBool = AtomicBool::new(false);
+static REPORTER: AtomicPtr<Reporter> = AtomicPtr::new(core::ptr::null_mut());
+
+/// 输出报告的方式，`ksync` 不依赖控制台、符号表与任务，因此由内核注册
+pub struct Reporter {
+    /// 输出一行信息
+    pub print: fn(Arguments),
+    /// 输出调用栈，参数为各栈帧的返回地址
+    pub print_stack: fn(&[usize]),
+    /// 返回帧指针 `fp` 所在的内核栈的范围，`fp` 不在任何内核栈中时返回空的范围
+    pub stack_range: fn(fp: usize) -> Range<usize>,
+}
+
+/// 注册报告的输出方式，注册之前发生的错误只会被计数，也不会记录调用栈
+pub fn register_reporter(reporter: &'static Reporter) {
+    REPORTER.store(reporter as *const _ as *mut _, Ordering::Release);
+}
+
+/// 已经报告的错误数
+pub fn violations() -> usize {
+    VIOLATIONS.load(Ordering::Relaxed)
+}
+
+fn reporter() -> Option<&'static Reporter> {
+    unsafe { REPORTER.load(Ordering::Acquire).as_ref() }
+}
+
+macro_rules! report {
+    ($reporter:expr, $($arg:tt)*) => {
+        ($reporter.print)(format_args!($($arg)*))
+    };
+}
+
+/// 一个锁类别的统计信息，时间的单位为时钟周期
+#[derive(Debug, Clone, Copy)]
+pub struct LockStat {
+    pub name: &'static str,
+    pub acquisitions: usize,
+    pub contentions: usize,
+    pub wait_time: usize,
+    pub max_wait: usize,
+}
+
+struct ClassStat {
+    acquisitions: AtomicUsize,
+    contentions: AtomicUsize,
+    wait_time: AtomicUsize,
+    max_wait: AtomicUsize,
+}
+
+impl ClassStat {
+    const fn new() -> Self {
+        Self {
+            acquisitions: AtomicUsize::new(0),
+            contentions: AtomicUsize::new(0),
+            wait_time: AtomicUsize::new(0),
+            max_wait: AtomicUsize::new(0),
+        }
+    }
+}
+
+#[allow(clippy::declare_interior_mutable_const)]
+const DEFAULT_STAT: ClassStat = ClassStat::new();
+static STATS: [ClassStat; MAX_CLASSES] = [DEFAULT_STAT; MAX_CLASSES];
+
+/// 按注册顺序遍历所有锁类别的统计信息
+pub fn for_each_class(mut f: impl FnMut(&LockStat)) {
+    let count = GRAPH.lock().count;
+    for (class, stat) in STATS[..count].iter().enumerate() {
+        f(&LockStat {
+            name: class_name(class as u16),
+            acquisitions: stat.acquisitions.load(Ordering::Relaxed),
+            contentions: stat.contentions.load(Ordering::Relaxed),
+            wait_time: stat.wait_time.load(Ordering::Relaxed),
+            max_wait: stat.max_wait.load(Ordering::Relaxed),
+        });
+    }
+}
+
+/// 清空统计信息，类别与获取顺序保持不变
+pub fn reset_stats() {
+    for stat in STATS.iter() {
+        stat.acquisitions.store(0, Ordering::Relaxed);
+        stat.contentions.store(0, Ordering::Relaxed);
+        stat.wait_time.store(0, Ordering::Relaxed);
+        stat.max_wait.store(0, Ordering::Relaxed);
+    }
+}
+
+/// `ORDER[a]` 的第 b 位表示已经出现过持有 a 时获取 b，只在持有 [`GRAPH`] 时写入
+#[allow(clippy::declare_interior_mutable_const)]
+const ZERO: AtomicU64 = AtomicU64::new(0);
+#[allow(clippy::declare_interior_mutable_const)]
+const EMPTY_ROW: [AtomicU64; WORDS] = [ZERO; WORDS];
+static ORDER: [[AtomicU64; WORDS]; MAX_CLASSES] = [EMPTY_ROW; MAX_CLASSES];
+
+fn has_order(from: u16, to: u16) -> bool {
+    let (from, to) = (from as usize, to as usize);
+    ORDER[from][to / 64].load(Ordering::Relaxed) & (1 << (to % 64)) != 0
+}
+
+/// 某个依赖第一次出现时获取后一个锁的调用栈
+#[derive(Clone, Copy)]
+struct Trace {
+    from: u16,
+    to: u16,
+    stack: [usize; STACK_DEPTH],
+}
+
+/// 类别表与依赖的调用栈，使用不经过检查的自旋锁保护
+struct Graph {
+    names: [&'static str; MAX_CLASSES],
+    count: usize,
+    traces: [Trace; MAX_TRACES],
+    trace_count: usize,
+    /// 搜索依赖路径时使用，记录每个类别在路径上的前一个类别
+    parent: [u16; MAX_CLASSES],
+    queue: [u16; MAX_CLASSES],
+}
+
+static GRAPH: SpinMutex<Graph, KernelLockAction> = SpinMutex::new(Graph::new());
+
+impl Graph {
+    const fn new() -> Self {
+        Self {
+            names: [""; MAX_CLASSES],
+            count: 0,
+            traces: [Trace {
+                from: 0,
+                to: 0,
+                stack: [0; STACK_DEPTH],
+            }; MAX_TRACES],
+            trace_count: 0,
+            parent: [UNTRACKED; MAX_CLASSES],
+            queue: [0; MAX_CLASSES],
+        }
+    }
+
+    fn class_of(&mut self, name: &'static str) -> u16 {
+        if let Some(class) = self.names[..self.count].iter().position(|n| *n == name) {
+            return class as u16;
+        }
+        if self.count == MAX_CLASSES {
+            return UNTRACKED;
+        }
+        self.names[self.count] = name;
+        self.count += 1;
+        (self.count - 1) as u16
+    }
+
+    /// 按依赖广度优先搜索 `from` 到 `to` 的路径，找到时 `parent` 中记录了路径
+    fn search(&mut self, from: u16, to: u16) -> bool {
+        self.parent[..self.count].fill(UNTRACKED);
+        self.parent[from as usize] = from;
+        self.queue[0] = from;
+        let (mut head, mut tail) = (0, 1);
+        while head < tail {
+            let class = self.queue[head];
+            head += 1;
+            if class == to {
+                return true;
+            }
+            for next in 0..self.count as u16 {
+                if self.parent[next as usize] == UNTRACKED && has_order(class, next) {
+                    self.parent[next as usize] = class;
+                    self.queue[tail] = next;
+                    tail += 1;
+                }
+            }
+        }
+        false
+    }
+
+    /// 记录 `from -> to`，如果已经存在 `to -> ... -> from` 则不记录并返回 false
+    fn add_order(&mut self, from: u16, to: u16, stack: &[usize; STACK_DEPTH]) -> bool {
+        if has_order(from, to) {
+            return true;
+        }
+        if self.search(to, from) {
+            return false;
+        }
+        ORDER[from as usize][to as usize / 64].fetch_or(1 << (to % 64), Ordering::Relaxed);
+        if self.trace_count < MAX_TRACES {
+            self.traces[self.trace_count] = Trace {
+                from,
+                to,
+                stack: *stack,
+            };
+            self.trace_count += 1;
+        }
+        true
+    }
+
+    fn trace(&self, from: u16, to: u16) -> Option<&Trace> {
+        self.traces[..self.trace_count]
+            .iter()
+            .find(|t| t.from == from && t.to == to)
+    }
+}
+
+fn class_name(class: u16) -> &'static str {
+    if class == UNTRACKED {
+        return "<untracked>";
+    }
+    GRAPH.lock().names[class as usize]
+}
+
+/// 当前核持有的一个锁
+#[derive(Clone, Copy)]
+struct HeldLock {
+    class: u16,
+    addr: usize,
+    stack: [usize; STACK_DEPTH],
+}
+
+struct HeldLocks {
+    locks: [HeldLock; MAX_HELD],
+    depth: usize,
+    /// 正在处理加锁或者输出报告，此时获取的锁(例如控制台的锁)不做检查
+    busy: bool,
+}
+
+impl HeldLocks {
+    const fn new() -> Self {
+        Self {
+            locks: [HeldLock {
+                class: 0,
+                addr: 0,
+                stack: [0; STACK_DEPTH],
+            }; MAX_HELD],
+            depth: 0,
+            busy: false,
+        }
+    }
+
+    fn held(&self) -> &[HeldLock] {
+        &self.locks[..self.depth]
+    }
+}
+
+struct PerCpuHeld(UnsafeCell<HeldLocks>);
+
+/// # Safety: 每个核只访问自己的记录，并且访问时关闭中断
+unsafe impl Sync for PerCpuHeld {}
+
+#[allow(clippy::declare_interior_mutable_const)]
+const DEFAULT_HELD: PerCpuHeld = PerCpuHeld(UnsafeCell::new(HeldLocks::new()));
+static HELD: [PerCpuHeld; CPU_NUM] = [DEFAULT_HELD; CPU_NUM];
+
+/// 在关闭中断时访问当前核持有的锁
+fn with_held<R>(f: impl FnOnce(&mut HeldLocks) -> R) -> R {
+    push_off();
+    let res = f(unsafe { &mut *HELD[hart_id()].0.get() });
+    pop_off();
+    res
+}
+
+/// 沿帧指针记录调用栈，帧指针离开当前的内核栈或者没有向栈底增长时停止
+#[inline(always)]
+fn capture_stack() -> [usize; STACK_DEPTH] {
+    let fp = frame_pointer();
+    match reporter() {
+        Some(reporter) => walk_stack(fp, (reporter.stack_range)(fp)),
+        None => [0; STACK_DEPTH],
+    }
+}
+
+/// 从帧指针 `fp` 开始，记录位于 `range` 中的栈帧的返回地址
+fn walk_stack(mut fp: usize, range: Range<usize>) -> [usize; STACK_DEPTH] {
+    let mut stack = [0; STACK_DEPTH];
+    for slot in stack.iter_mut() {
+        if fp % 8 != 0 || fp < range.start + 16 || fp > range.end {
+            break;
+        }
+        let (ra, prev) = unsafe { (*((fp - 8) as *const usize), *((fp - 16) as *const usize)) };
+        if ra == 0 {
+            break;
+        }
+        *slot = ra;
+        if prev <= fp {
+            break;
+        }
+        fp = prev;
+    }
+    stack
+}
+
+fn print_stack(reporter: &Reporter, stack: &[usize]) {
+    let len = stack.iter().position(|&ra| ra == 0).unwrap_or(stack.len());
+    (reporter.print_stack)(&stack[..len]);
+}
+
+/// 在获取锁之前记录，`check` 为 false 时只记录持有的锁，返回这次加锁是否被记录
+fn acquire(class: u16, addr: usize, check: bool) -> bool {
+    if class == UNTRACKED {
+        return false;
+    }
+    with_held(|held| {
+        if held.busy {
+            return false;
+        }
+        held.busy = true;
+        let stack = capture_stack();
+        if check && CHECKING.load(Ordering::Relaxed) {
+            check_acquire(held, class, addr, &stack);
+        }
+        let recorded = held.depth < MAX_HELD;
+        if recorded {
+            held.locks[held.depth] = HeldLock { class, addr, stack };
+            held.depth += 1;
+        } else if !OVERFLOW_WARNED.swap(true, Ordering::Relaxed) {
+            if let Some(reporter) = reporter() {
+                report!(
+                    reporter,
+                    "lockdep: more than {} locks held, stop tracking",
+                    MAX_HELD
+                );
+            }
+        }
+        held.busy = false;
+        recorded
+    })
+}
+
+fn check_acquire(held: &HeldLocks, class: u16, addr: usize, stack: &[usize; STACK_DEPTH]) {
+    if let Some(prev) = held.held().iter().find(|l| l.addr == addr) {
+        if CHECKING.swap(false, Ordering::Relaxed) {
+            report_recursive(held, prev, addr, stack);
+        }
+        return;
+    }
+    for prev in held.held() {
+        if prev.class == class || has_order(prev.class, class) {
+            continue;
+        }
+        let ok = GRAPH.lock().add_order(prev.class, class, stack);
+        if !ok && CHECKING.swap(false, Ordering::Relaxed) {
+            report_inversion(held, prev, class, addr, stack);
+            return;
+        }
+    }
+}
+
+/// 释放锁之前移除记录，任务持有锁时切换到其它核的情况下找不到记录，直接忽略
+fn release(addr: usize) {
+    with_held(|held| {
+        if let Some(index) = held.held().iter().rposition(|l| l.addr == addr) {
+            held.locks.copy_within(index + 1..held.depth, index);
+            held.depth -= 1;
+        }
+    })
+}
+
+fn report_header(reporter: &Reporter, title: &str) {
+    report!(reporter, "");
+    report!(
+        reporter,
+        "======================================================"
+    );
+    report!(reporter, "WARNING: {}", title);
+    report!(
+        reporter,
+        "------------------------------------------------------"
+    );
+}
+
+fn report_held(reporter: &Reporter, held: &HeldLocks) {
+    report!(reporter, "hart {} holds {} locks:", hart_id(), held.depth);
+    for (i, lock) in held.held().iter().enumerate() {
+        report!(
+            reporter,
+            "  #{}: {} at {:#x}",
+            i,
+            class_name(lock.class),
+            lock.addr
+        );
+    }
+}
+
+#[cold]
+fn report_recursive(held: &HeldLocks, prev: &HeldLock, addr: usize, stack: &[usize; STACK_DEPTH]) {
+    VIOLATIONS.fetch_add(1, Ordering::Relaxed);
+    let Some(reporter) = reporter() else {
+        return;
+    };
+    report_header(reporter, "possible recursive locking detected");
+    let name = class_name(prev.class);
+    report!(
+        reporter,
+        "hart {} is trying to acquire lock {} at {:#x}:",
+        hart_id(),
+        name,
+        addr
+    );
+    print_stack(reporter, stack);
+    report!(reporter, "but it already holds the same lock, acquired at:");
+    print_stack(reporter, &prev.stack);
+    report_held(reporter, held);
+}
+
+#[cold]
+fn report_inversion(
+    held: &HeldLocks,
+    prev: &HeldLock,
+    class: u16,
+    addr: usize,
+    stack: &[usize; STACK_DEPTH],
+) {
+    VIOLATIONS.fetch_add(1, Ordering::Relaxed);
+    let Some(reporter) = reporter() else {
+        return;
+    };
+    // 打印时不能持有 GRAPH，先复制依赖路径 class -> ... -> prev.class
+    let mut chain = [(0u16, 0u16, None::<[usize; STACK_DEPTH]>); MAX_CHAIN];
+    let mut len = 0;
+    let mut total = 0;
+    {
+        let mut graph = GRAPH.lock();
+        if graph.search(class, prev.class) {
+            let mut to = prev.class;
+            while to != class {
+                let from = graph.parent[to as usize];
+                total += 1;
+                if len < MAX_CHAIN {
+                    chain[len] = (from, to, graph.trace(from, to).map(|t| t.stack));
+                    len += 1;
+                }
+                to = from;
+            }
+        }
+    }
+    report_header(reporter, "possible circular locking dependency detected");
+    report!(
+        reporter,
+        "hart {} is trying to acquire lock {} at {:#x}:",
+        hart_id(),
+        class_name(class),
+        addr
+    );
+    print_stack(reporter, stack);
+    report!(
+        reporter,
+        "but it already holds lock {} at {:#x}, acquired at:",
+        class_name(prev.class),
+        prev.addr
+    );
+    print_stack(reporter, &prev.stack);
+    report!(reporter, "which the new lock already depends on:");
+    // 路径是从后向前复制的
+    for (from, to, stack) in chain[..len].iter().rev() {
+        report!(
+            reporter,
+            "-> {} then {}, first acquired at:",
+            class_name(*from),
+            class_name(*to)
+        );
+        match stack {
+            Some(stack) => print_stack(reporter, stack),
+            None => report!(reporter, "   <no stack recorded>"),
+        }
+    }
+    if total > len {
+        report!(reporter, "... {} more dependencies", total - len);
+    }
+    report_held(reporter, held);
+}
+
+/// 记录加锁顺序与竞争情况的 ticket lock，接口与 [`TicketMutex`] 保持一致
+pub struct Mutex<T: ?Sized> {
+    /// 0 表示尚未注册，否则为类别加 1，或者为 [`UNTRACKED`]
+    class: AtomicU16,
+    inner: TicketMutex<T, KernelLockAction>,
+}
+
+pub struct MutexGuard<'a, T: ?Sized> {
+    guard: TicketMutexGuard<'a, T, KernelLockAction>,
+    addr: usize,
+    recorded: bool,
+}
+
+impl<T> Mutex<T> {
+    pub const fn new(data: T) -> Self {
+        Self {
+            class: AtomicU16::new(0),
+            inner: TicketMutex::new(data),
+        }
+    }
+
+    pub fn into_inner(self) -> T {
+        self.inner.into_inner()
+    }
+}
+
+impl<T: ?Sized> Mutex<T> {
+    /// 锁的类别为保护的数据类型，第一次加锁时注册
+    fn class(&self) -> u16 {
+        match self.class.load(Ordering::Acquire) {
+            0 => {
+                let class = GRAPH.lock().class_of(type_name::<T>());
+                let value = if class == UNTRACKED { class } else { class + 1 };
+                self.class.store(value, Ordering::Release);
+                class
+            }
+            UNTRACKED => UNTRACKED,
+            value => value - 1,
+        }
+    }
+
+    fn addr(&self) -> usize {
+        self as *const Self as *const () as usize
+    }
+
+    pub fn lock(&self) -> MutexGuard<'_, T> {
+        let class = self.class();
+        let addr = self.addr();
+        let recorded = acquire(class, addr, true);
+        let guard = match self.inner.try_lock() {
+            Some(guard) => guard,
+            None => {
+                let start = read_timer();
+                let guard = self.inner.lock();
+                if class != UNTRACKED {
+                    let wait = read_timer().wrapping_sub(start);
+                    let stat = &STATS[class as usize];
+                    stat.contentions.fetch_add(1, Ordering::Relaxed);
+                    stat.wait_time.fetch_add(wait, Ordering::Relaxed);
+                    stat.max_wait.fetch_max(wait, Ordering::Relaxed);
+                }
+                guard
+            }
+        };
+        if class != UNTRACKED {
+            STATS[class as usize]
+                .acquisitions
+                .fetch_add(1, Ordering::Relaxed);
+        }
+        MutexGuard {
+            guard,
+            addr,
+            recorded,
+        }
+    }
+
+    pub fn try_lock(&self) -> Option<MutexGuard<'_, T>> {
+        let guard = self.inner.try_lock()?;
+        let class = self.class();
+        if class != UNTRACKED {
+            STATS[class as usize]
+                .acquisitions
+                .fetch_add(1, Ordering::Relaxed);
+        }
+        let addr = self.addr();
+        Some(MutexGuard {
+            guard,
+            addr,
+            recorded: acquire(class, addr, false),
+        })
+    }
+
+    pub fn is_locked(&self) -> bool {
+        self.inner.is_locked()
+    }
+
+    pub fn get_mut(&mut self) -> &mut T {
+        self.inner.get_mut()
+    }
+}
+
+impl<T: Default> Default for Mutex<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T: ?Sized + Debug> Debug for Mutex<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self.inner.try_lock() {
+            Some(guard) => f.debug_struct("Mutex").field("data", &&*guard).finish(),
+            None => f.write_str("Mutex { <locked> }"),
+        }
+    }
+}
+
+impl<T: ?Sized> Deref for MutexGuard<'_, T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        &self.guard
+    }
+}
+
+impl<T: ?Sized> DerefMut for MutexGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.guard
+    }
+}
+
+impl<T: ?Sized> Drop for MutexGuard<'_, T> {
+    fn drop(&mut self) {
+        if self.recorded {
+            release(self.addr);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STACK: [usize; STACK_DEPTH] = [0; STACK_DEPTH];
+
+    #[test]
+    fn test_order_inversion() {
+        let mut graph = Graph::new();
+        let (a, b, c) = (
+            graph.class_of("A"),
+            graph.class_of("B"),
+            graph.class_of("C"),
+        );
+        assert_eq!(graph.class_of("B"), b);
+        assert!(graph.add_order(a, b, &STACK));
+        assert!(graph.add_order(b, c, &STACK));
+        // 已经记录的顺序可以重复出现
+        assert!(graph.add_order(a, b, &STACK));
+        // B -> A 与 A -> B 相反，C -> A 与 A -> B -> C 构成环
+        assert!(!graph.add_order(b, a, &STACK));
+        assert!(!graph.add_order(c, a, &STACK));
+        assert!(!has_order(b, a) && !has_order(c, a));
+        // 报告中打印的依赖路径
+        assert!(graph.search(a, c));
+        assert_eq!(graph.parent[c as usize], b);
+        assert_eq!(graph.parent[b as usize], a);
+        assert!(graph.trace(a, b).is_some());
+        assert!(graph.trace(b, a).is_none());
+    }
+
+    #[test]
+    fn test_walk_stack_in_range() {
+        // 两个栈帧，fp - 8 处为返回地址，fp - 16 处为上一帧的帧指针
+        let mut frames = vec![0usize; 8];
+        let base = frames.as_ptr() as usize;
+        let range = base..base + 64;
+        frames[0] = base + 48;
+        frames[1] = 0x111;
+        // 上一帧位于栈之外时停止
+        frames[4] = base + 0x1000;
+        frames[5] = 0x222;
+        let stack = walk_stack(base + 16, range);
+        assert_eq!(stack[..3], [0x111, 0x222, 0]);
+        assert_eq!(walk_stack(base + 16, 0..0), STACK);
+    }
+
+    #[test]
+    fn test_recursive_acquire() {
+        let mut held = HeldLocks::new();
+        held.locks[0] = HeldLock {
+            class: 0,
+            addr: 0x1000,
+            stack: STACK,
+        };
+        held.depth = 1;
+        let before = violations();
+        // 同一类别的另一个锁不是递归加锁，也不记录顺序
+        check_acquire(&held, 0, 0x2000, &STACK);
+        assert_eq!(violations(), before);
+        check_acquire(&held, 0, 0x1000, &STACK);
+        assert_eq!(violations(), before + 1);
+        // 报告之后不再检查
+        assert!(!CHECKING.load(Ordering::Relaxed));
+    }
+}
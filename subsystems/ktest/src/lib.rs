@@ -0,0 +1,239 @@
+//! 内核单元测试框架
+//!
+//! 使用 [`kernel_test`] 标注的函数会被放到 `.kernel_test` 段中。内核启动完成后，如果命令行中带有 `ktest`，
+//! 就依次运行这些测试，以 TAP 或 JSON 格式在控制台输出结果，然后关机，有测试失败时以系统故障为原因关机，
+//! QEMU 会因此以非 0 状态退出。
+//!
+//! 内核不支持栈展开，所以测试通过返回 [`TestResult`] 报告失败，断言使用 [`kassert!`]、[`kassert_eq!`]
+//! 和 [`kassert_ne!`]。测试中发生 panic 时，[`panic_hook`] 会把当前测试记为失败并中止剩余的测试。
+#![no_std]
+#![feature(panic_info_message)]
+
+extern crate alloc;
+
+mod report;
+
+use alloc::string::{String, ToString};
+use core::fmt::{Arguments, Debug};
+use core::panic::{Location, PanicInfo};
+use core::sync::atomic::{AtomicPtr, AtomicUsize, Ordering};
+
+pub use ktest_macros::kernel_test;
+use platform::{system_shutdown_with_reason, ShutdownReason};
+use report::Reporter;
+use spin::Once;
+
+/// 测试描述符，由 [`kernel_test`] 生成
+pub struct KernelTest {
+    /// 带模块路径的测试名称
+    pub name: &'static str,
+    pub file: &'static str,
+    pub line: u32,
+    pub func: fn() -> TestResult,
+}
+
+/// 测试失败的原因与位置
+pub struct TestFailure {
+    pub message: String,
+    pub file: &'static str,
+    pub line: u32,
+}
+
+impl TestFailure {
+    pub fn new(message: Arguments, file: &'static str, line: u32) -> Self {
+        Self {
+            message: alloc::fmt::format(message),
+            file,
+            line,
+        }
+    }
+}
+
+/// 测试中可以直接用 `?` 传递错误，失败位置为 `?` 所在的位置
+impl<E: Debug> From<E> for TestFailure {
+    #[track_caller]
+    fn from(err: E) -> Self {
+        let location = Location::caller();
+        Self {
+            message: alloc::format!("unexpected error: {:?}", err),
+            file: location.file(),
+            line: location.line(),
+        }
+    }
+}
+
+pub type TestResult = Result<(), TestFailure>;
+
+/// 测试结果的输出格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Test Anything Protocol 13
+    Tap,
+    /// 每行一个 JSON 对象
+    Json,
+}
+
+impl OutputFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "tap" | "TAP" => Some(Self::Tap),
+            "json" | "JSON" => Some(Self::Json),
+            _ => None,
+        }
+    }
+}
+
+/// 断言条件成立，否则令当前测试失败
+#[macro_export]
+macro_rules! kassert {
+    ($cond:expr $(,)?) => {
+        $crate::kassert!($cond, "assertion failed: {}", stringify!($cond))
+    };
+    ($cond:expr, $($arg:tt)+) => {
+        if !$cond {
+            return Err($crate::TestFailure::new(format_args!($($arg)+), file!(), line!()));
+        }
+    };
+}
+
+/// 断言两个值相等，否则令当前测试失败
+#[macro_export]
+macro_rules! kassert_eq {
+    ($left:expr, $right:expr $(,)?) => {
+        match (&$left, &$right) {
+            (left, right) => {
+                $crate::kassert!(
+                    *left == *right,
+                    "assertion `left == right` failed\n  left: {:?}\n right: {:?}",
+                    left,
+                    right
+                )
+            }
+        }
+    };
+}
+
+/// 断言两个值不相等，否则令当前测试失败
+#[macro_export]
+macro_rules! kassert_ne {
+    ($left:expr, $right:expr $(,)?) => {
+        match (&$left, &$right) {
+            (left, right) => {
+                $crate::kassert!(
+                    *left != *right,
+                    "assertion `left != right` failed\n  left: {:?}\n right: {:?}",
+                    left,
+                    right
+                )
+            }
+        }
+    };
+}
+
+extern "C" {
+    fn skernel_test();
+    fn ekernel_test();
+}
+
+/// 链接到内核中的所有测试
+fn kernel_tests() -> &'static [KernelTest] {
+    let start = skernel_test as usize;
+    let end = ekernel_test as usize;
+    let len = (end - start) / core::mem::size_of::<KernelTest>();
+    unsafe { core::slice::from_raw_parts(start as *const KernelTest, len) }
+}
+
+/// 正在运行的测试的序号，从 1 开始，为 0 时没有测试在运行
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+/// 正在运行的测试的描述符，panic 时用于报告失败的测试
+static CURRENT_TEST: AtomicPtr<KernelTest> = AtomicPtr::new(core::ptr::null_mut());
+static TOTAL: AtomicUsize = AtomicUsize::new(0);
+static PASSED: AtomicUsize = AtomicUsize::new(0);
+static FAILED: AtomicUsize = AtomicUsize::new(0);
+static FORMAT: Once<OutputFormat> = Once::new();
+
+fn reporter() -> Reporter {
+    Reporter::new(*FORMAT.get().unwrap_or(&OutputFormat::Tap))
+}
+
+/// 运行名称中包含 `filter` 的测试并输出结果，全部通过时正常关机，否则以系统故障为原因关机
+pub fn run_tests(format: OutputFormat, filter: Option<&str>) -> ! {
+    let reporter = Reporter::new(*FORMAT.call_once(|| format));
+    let tests = kernel_tests()
+        .iter()
+        .filter(|test| filter.map_or(true, |filter| test.name.contains(filter)));
+    let total = tests.clone().count();
+    TOTAL.store(total, Ordering::Relaxed);
+    reporter.plan(total);
+    for (index, test) in tests.enumerate() {
+        let id = index + 1;
+        CURRENT_TEST.store(test as *const _ as *mut _, Ordering::SeqCst);
+        CURRENT.store(id, Ordering::SeqCst);
+        let start = arch::read_timer();
+        let result = (test.func)();
+        let elapsed = elapsed_us(start);
+        CURRENT.store(0, Ordering::SeqCst);
+        match result {
+            Ok(()) => {
+                PASSED.fetch_add(1, Ordering::Relaxed);
+                reporter.passed(id, test, elapsed);
+            }
+            Err(failure) => {
+                FAILED.fetch_add(1, Ordering::Relaxed);
+                reporter.failed(id, test, elapsed, &failure);
+            }
+        }
+    }
+    finish(false)
+}
+
+/// 内核 panic 时调用，如果正在运行测试，则把当前测试记为失败并中止剩余的测试
+///
+/// 没有测试在运行时直接返回。
+pub fn panic_hook(info: &PanicInfo) {
+    let id = CURRENT.swap(0, Ordering::SeqCst);
+    if id == 0 {
+        return;
+    }
+    let test = unsafe { &*CURRENT_TEST.load(Ordering::SeqCst) };
+    let message = match info.message() {
+        Some(message) => alloc::fmt::format(*message),
+        None => "unknown panic".to_string(),
+    };
+    let message = match info.location() {
+        Some(location) => alloc::format!(
+            "panicked at {}:{}: {}",
+            location.file(),
+            location.line(),
+            message
+        ),
+        None => alloc::format!("panicked: {}", message),
+    };
+    // panic 的位置已经包含在信息中，失败位置记为测试本身
+    let failure = TestFailure {
+        message,
+        file: test.file,
+        line: test.line,
+    };
+    FAILED.fetch_add(1, Ordering::Relaxed);
+    reporter().failed(id, test, 0, &failure);
+    finish(true)
+}
+
+fn finish(aborted: bool) -> ! {
+    let total = TOTAL.load(Ordering::Relaxed);
+    let passed = PASSED.load(Ordering::Relaxed);
+    let failed = FAILED.load(Ordering::Relaxed);
+    reporter().summary(total, passed, failed, aborted);
+    let reason = if failed == 0 && !aborted {
+        ShutdownReason::None
+    } else {
+        ShutdownReason::SystemFailure
+    };
+    system_shutdown_with_reason(reason)
+}
+
+fn elapsed_us(start: usize) -> usize {
+    let ticks = arch::read_timer() - start;
+    ticks / (platform::config::CLOCK_FREQ / 1_000_000)
+}
@@ -0,0 +1,109 @@
+//! 测试结果的输出
+//!
+//! TAP 格式的失败信息放在 YAML 块中；JSON 格式每行输出一个对象，`event` 字段为 `plan`、`test` 或 `summary`。
+use core::fmt::{Display, Formatter, Write};
+
+use platform::println;
+
+use crate::{KernelTest, OutputFormat, TestFailure};
+
+pub struct Reporter {
+    format: OutputFormat,
+}
+
+impl Reporter {
+    pub fn new(format: OutputFormat) -> Self {
+        Self { format }
+    }
+
+    pub fn plan(&self, total: usize) {
+        match self.format {
+            OutputFormat::Tap => {
+                println!("TAP version 13");
+                println!("1..{}", total);
+            }
+            OutputFormat::Json => {
+                println!(r#"{{"event":"plan","count":{}}}"#, total)
+            }
+        }
+    }
+
+    pub fn passed(&self, id: usize, test: &KernelTest, elapsed_us: usize) {
+        match self.format {
+            OutputFormat::Tap => {
+                println!("ok {} - {} # {}us", id, test.name, elapsed_us)
+            }
+            OutputFormat::Json => println!(
+                r#"{{"event":"test","id":{},"name":{},"result":"ok","elapsed_us":{}}}"#,
+                id,
+                JsonStr(test.name),
+                elapsed_us
+            ),
+        }
+    }
+
+    pub fn failed(&self, id: usize, test: &KernelTest, elapsed_us: usize, failure: &TestFailure) {
+        match self.format {
+            OutputFormat::Tap => {
+                println!("not ok {} - {}", id, test.name);
+                println!("  ---");
+                println!("  message: {:?}", failure.message.as_str());
+                println!("  at: {}:{}", failure.file, failure.line);
+                println!("  test: {}:{}", test.file, test.line);
+                println!("  ...");
+            }
+            OutputFormat::Json => println!(
+                r#"{{"event":"test","id":{},"name":{},"result":"failed","elapsed_us":{},"message":{},"file":{},"line":{}}}"#,
+                id,
+                JsonStr(test.name),
+                elapsed_us,
+                JsonStr(&failure.message),
+                JsonStr(failure.file),
+                failure.line
+            ),
+        }
+    }
+
+    /// 输出统计信息，`aborted` 表示因 panic 而没有运行剩余的测试
+    pub fn summary(&self, total: usize, passed: usize, failed: usize, aborted: bool) {
+        match self.format {
+            OutputFormat::Tap => {
+                if aborted {
+                    println!("Bail out! kernel panicked");
+                }
+                println!(
+                    "# total {} passed {} failed {} skipped {}",
+                    total,
+                    passed,
+                    failed,
+                    total - passed - failed
+                );
+            }
+            OutputFormat::Json => println!(
+                r#"{{"event":"summary","total":{},"passed":{},"failed":{},"aborted":{}}}"#,
+                total, passed, failed, aborted
+            ),
+        }
+    }
+}
+
+/// 按 JSON 字符串的格式输出，包括两端的引号
+struct JsonStr<'a>(&'a str);
+
+impl Display for JsonStr<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.write_char('"')?;
+        for c in self.0.chars() {
+            match c {
+                '"' => f.write_str("\\\"")?,
+                '\\' => f.write_str("\\\\")?,
+                '\n' => f.write_str("\\n")?,
+                '\r' => f.write_str("\\r")?,
+                '\t' => f.write_str("\\t")?,
+                c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+                c => f.write_char(c)?,
+            }
+        }
+        f.write_char('"')
+    }
+}
@@ -0,0 +1,75 @@
+//! `#[kernel_test]` 属性宏
+//!
+//! 为被标注的函数生成一个放在 `.kernel_test` 段中的 `ktest::KernelTest` 描述符，
+//! 内核运行测试时从链接脚本给出的段边界中取得所有测试。
+use proc_macro::TokenStream;
+use quote::{format_ident, quote};
+use syn::{parse_macro_input, ItemFn, ReturnType};
+
+/// 把函数注册为内核单元测试
+///
+/// 测试函数不能有参数，返回值可以是 `()` 或 `ktest::TestResult`。
+#[proc_macro_attribute]
+pub fn kernel_test(attr: TokenStream, item: TokenStream) -> TokenStream {
+    if !attr.is_empty() {
+        return syn::Error::new(
+            proc_macro2::Span::call_site(),
+            "#[kernel_test] does not take arguments",
+        )
+        .to_compile_error()
+        .into();
+    }
+    let func = parse_macro_input!(item as ItemFn);
+    if let Err(err) = check_signature(&func) {
+        return err.to_compile_error().into();
+    }
+    let ident = &func.sig.ident;
+    let name = ident.to_string();
+    let desc = format_ident!("__KERNEL_TEST_{}", name.to_uppercase());
+    let call = match func.sig.output {
+        ReturnType::Default => quote! {{ #ident(); Ok(()) }},
+        ReturnType::Type(..) => quote! { #ident() },
+    };
+    quote! {
+        #func
+
+        #[used]
+        #[doc(hidden)]
+        #[link_section = ".kernel_test"]
+        static #desc: ::ktest::KernelTest = ::ktest::KernelTest {
+            name: concat!(module_path!(), "::", #name),
+            file: file!(),
+            line: line!(),
+            func: {
+                fn wrapper() -> ::ktest::TestResult {
+                    #call
+                }
+                wrapper
+            },
+        };
+    }
+    .into()
+}
+
+fn check_signature(func: &ItemFn) -> syn::Result<()> {
+    let sig = &func.sig;
+    if !sig.inputs.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &sig.inputs,
+            "kernel tests cannot take arguments",
+        ));
+    }
+    if let Some(asyncness) = &sig.asyncness {
+        return Err(syn::Error::new_spanned(
+            asyncness,
+            "kernel tests cannot be async",
+        ));
+    }
+    if !sig.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &sig.generics,
+            "kernel tests cannot be generic",
+        ));
+    }
+    Ok(())
+}
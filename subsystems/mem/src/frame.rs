@@ -2,19 +2,28 @@ use crate::manager::FRAME_REF_MANAGER;
 use alloc::format;
 use config::{FRAME_BITS, FRAME_SIZE};
 use core::mem::forget;
-use core::ops::{Deref, DerefMut};
+use core::ops::{Deref, DerefMut, Range};
 use ksync::Mutex;
 use log::trace;
 use page_table::addr::{PhysAddr, VirtAddr};
 use page_table::table::PagingIf;
 use pager::{PageAllocator, PageAllocatorExt};
 use platform::println;
+use spin::Once;
 
 #[cfg(feature = "pager_bitmap")]
 pub static FRAME_ALLOCATOR: Mutex<pager::Bitmap<0>> = Mutex::new(pager::Bitmap::new());
 #[cfg(feature = "pager_buddy")]
 pub static FRAME_ALLOCATOR: Mutex<pager::Zone<12>> = Mutex::new(pager::Zone::new());
 
+/// 不交给页帧分配器管理的物理内存
+static RESERVED_FRAMES: Once<Range<usize>> = Once::new();
+
+/// 保留物理内存 `range`，使其不会被页帧分配器分配，需要在内存管理初始化之前调用
+pub fn reserve_frames(range: Range<usize>) {
+    RESERVED_FRAMES.call_once(|| range);
+}
+
 pub fn init_frame_allocator(start: usize, end: usize) {
     let page_start = start / FRAME_SIZE;
     let page_end = end / FRAME_SIZE;
@@ -27,6 +36,38 @@ pub fn init_frame_allocator(start: usize, end: usize) {
         .lock()
         .init(start..end)
         .expect("init frame allocator failed");
+    if let Some(reserved) = RESERVED_FRAMES.get() {
+        take_frames(reserved);
+    }
+}
+
+/// 从页帧分配器中取出 `range` 中的页，之后不再归还。需要在开启分页之前调用
+///
+/// 分配器无法分配指定的页，因此逐页分配直到区域中的页都被取出，期间分配到的区域之外的页
+/// 通过保存在页开头的页号串成链表，最后全部归还。
+fn take_frames(range: &Range<usize>) {
+    let pages = (range.start >> FRAME_BITS)..(range.end >> FRAME_BITS);
+    let mut allocator = FRAME_ALLOCATOR.lock();
+    let mut taken = 0;
+    // 页号 0 不在物理内存中，用作链表的结尾
+    let mut others = 0;
+    while taken < pages.len() {
+        let page = match allocator.alloc_pages(1, FRAME_SIZE) {
+            Ok(page) => page,
+            Err(_) => break,
+        };
+        if pages.contains(&page) {
+            taken += 1;
+        } else {
+            unsafe { *((page << FRAME_BITS) as *mut usize) = others };
+            others = page;
+        }
+    }
+    while others != 0 {
+        let next = unsafe { *((others << FRAME_BITS) as *const usize) };
+        allocator.free_pages(others, 1).expect("free frame failed");
+        others = next;
+    }
 }
 
 #[no_mangle]
@@ -136,3 +177,34 @@ impl PagingIf for VmmPageAllocator {
         Some(PhysAddr::from(start_addr))
     }
 }
+
+#[cfg(feature = "ktest")]
+mod ktests {
+    use super::*;
+    use ktest::{kassert, kassert_eq, kernel_test, TestResult};
+
+    #[kernel_test]
+    fn test_frame_tracker_refcount() -> TestResult {
+        let mut frames = alloc_frame_trackers(2);
+        let page = frames.start() >> FRAME_BITS;
+        kassert_eq!(frames.start() % FRAME_SIZE, 0);
+        kassert_eq!(frames.len(), 2 * FRAME_SIZE);
+        kassert_eq!(FRAME_REF_MANAGER.lock().get_ref(page + 1), 1);
+        frames.fill(0xa5);
+        kassert!(frames.iter().all(|&b| b == 0xa5));
+        // 共享的页在最后一个引用释放时才归还给分配器
+        FRAME_REF_MANAGER.lock().add_ref(page);
+        drop(frames);
+        kassert_eq!(FRAME_REF_MANAGER.lock().get_ref(page), 1);
+        kassert_eq!(FRAME_REF_MANAGER.lock().dec_ref(page), Some(0));
+        Ok(())
+    }
+
+    #[kernel_test]
+    fn test_kernel_space_identity_mapped() -> TestResult {
+        let frames = alloc_frame_trackers(1);
+        let addr = frames.start();
+        kassert_eq!(crate::query_kernel_space(addr), Some(addr));
+        Ok(())
+    }
+}
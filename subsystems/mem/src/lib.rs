@@ -1,33 +1,50 @@
-#![no_std]
+#![cfg_attr(not(feature = "host"), no_std)]
 
 extern crate alloc;
 #[macro_use]
 extern crate platform;
+#[cfg(not(feature = "host"))]
 use arch::activate_paging_mode;
+#[cfg(not(feature = "host"))]
 use config::FRAME_BITS;
+#[cfg(not(feature = "host"))]
 use heap::HeapAllocator;
+#[cfg(not(feature = "host"))]
 use platform::config::HEAP_SIZE;
 pub mod data;
 mod frame;
+#[cfg(not(feature = "host"))]
 mod heap;
 mod manager;
+pub mod mmap;
+#[cfg(not(feature = "host"))]
 mod vmm;
 
-pub use frame::{alloc_frame_trackers, alloc_frames, free_frames, FrameTracker, VmmPageAllocator};
+pub use frame::{
+    alloc_frame_trackers, alloc_frames, free_frames, reserve_frames, FrameTracker, VmmPageAllocator,
+};
 
-pub use vmm::{kernel_pgd, kernel_satp, kernel_space, map_region_to_kernel, query_kernel_space};
+#[cfg(not(feature = "host"))]
+pub use vmm::{
+    kernel_pgd, kernel_satp, kernel_space, map_region_to_kernel, patch_kernel_text,
+    query_kernel_space,
+};
 
 pub use manager::FRAME_REF_MANAGER;
+/// 内核堆分配器，宿主机上使用标准库的分配器
+#[cfg(not(feature = "host"))]
 #[global_allocator]
 static HEAP_ALLOCATOR: HeapAllocator = HeapAllocator::new();
 
-#[cfg(any(feature = "talloc", feature = "buddy"))]
+#[cfg(all(not(feature = "host"), any(feature = "talloc", feature = "buddy")))]
 static mut KERNEL_HEAP: [u8; HEAP_SIZE] = [0; HEAP_SIZE];
 
+#[cfg(not(feature = "host"))]
 extern "C" {
     fn ekernel();
 }
 
+#[cfg(not(feature = "host"))]
 pub fn init_memory_system(memory_end: usize, is_first_cpu: bool) {
     if is_first_cpu {
         frame::init_frame_allocator(ekernel as usize, memory_end);
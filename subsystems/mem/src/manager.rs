@@ -42,6 +42,10 @@ impl FrameRefManager {
             panic!("dec page {:#x?} ref error", id);
         }
     }
+    /// 物理页 `id` 是否由引用计数管理(即可能被多个地址空间共享)
+    pub fn is_tracked(&self, id: usize) -> bool {
+        self.record.contains_key(&id)
+    }
     pub fn get_ref(&self, id: usize) -> usize {
         if let Some(count) = self.record.get(&id) {
             *count
@@ -0,0 +1,503 @@
+//! 进程的内存映射信息
+//!
+//! 映射的文件类型作为泛型参数，内核中为 `Arc<dyn File>`，这样这部分逻辑可以脱离文件系统单独测试。
+//! 进程的堆也作为一个区域记录在这里，brk 与 mmap 的重叠检查、RLIMIT_AS 统计都基于同一份记录。
+use alloc::vec::Vec;
+use core::cmp::{max, min};
+use core::ops::Range;
+
+use abi::{MapFlags, ProtFlags};
+use config::{FRAME_SIZE, PROCESS_HEAP_MAX};
+use page_table::addr::align_up_4k;
+
+#[derive(Debug, Clone)]
+/// The Process should manage the mmap info
+pub struct MMapInfo<F: Clone> {
+    /// The start address of the mmap, it is a constant
+    map_start: usize,
+    /// The regions of the mmap
+    regions: Vec<MMapRegion<F>>,
+    /// The heap region, adjusted by brk
+    heap: HeapInfo,
+}
+
+/// 记录进程的堆空间的相关信息
+#[derive(Debug, Clone, Default)]
+pub struct HeapInfo {
+    /// 堆使用到的位置
+    pub current: usize,
+    /// 堆空间的起始位置
+    pub start: usize,
+    /// 堆空间的末尾位置，`[start, end)` 是已经映射的页
+    pub end: usize,
+}
+
+impl HeapInfo {
+    /// 新建一个 HeapInfo
+    pub fn new(start: usize, end: usize) -> Self {
+        HeapInfo {
+            current: start,
+            start,
+            end,
+        }
+    }
+
+    /// 返回堆的大小
+    pub fn size(&self) -> usize {
+        self.end - self.start
+    }
+
+    /// 返回堆是否包括某地址
+    pub fn contains(&self, addr: usize) -> bool {
+        addr >= self.start && addr < self.end
+    }
+
+    /// 返回堆空间是否与 `[start, end)` 重叠
+    pub fn overlaps(&self, start: usize, end: usize) -> bool {
+        start < self.end && self.start < end
+    }
+}
+
+/// 将 brk 设置到新位置时需要对页表做的修改
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BrkChange {
+    /// 新位置与原来在同一页内，不需要修改映射
+    Keep,
+    /// 解除 `[start, end)` 的映射
+    Shrink(Range<usize>),
+    /// 映射 `[start, end)`
+    Extend(Range<usize>),
+}
+
+/// brk 失败的原因，对用户态都表现为 ENOMEM
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BrkError {
+    /// 新位置低于堆的起始位置
+    BelowStart,
+    /// 堆的长度超过 RLIMIT_DATA
+    DataLimit,
+    /// 地址空间的长度超过 RLIMIT_AS
+    AsLimit,
+    /// 堆与内存映射区重叠或超出堆的地址范围
+    Overlap,
+}
+
+#[derive(Debug, Clone)]
+pub struct MMapRegion<F: Clone> {
+    /// The start address of the mapping
+    pub start: usize,
+    /// The length of the mapping
+    pub len: usize,
+    pub map_len: usize,
+    /// The protection flags of the mapping
+    pub prot: ProtFlags,
+    /// The flags of the mapping
+    pub flags: MapFlags,
+    /// The file descriptor to map
+    pub fd: Option<F>,
+    /// The offset in the file to start from
+    pub offset: usize,
+}
+
+impl<F: Clone> MMapInfo<F> {
+    pub fn new() -> Self {
+        Self {
+            map_start: PROCESS_HEAP_MAX,
+            regions: Vec::new(),
+            heap: HeapInfo::default(),
+        }
+    }
+
+    /// 新建一个堆从 `heap_start` 开始的 MMapInfo，堆初始为空
+    pub fn with_heap(heap_start: usize) -> Self {
+        let mut info = Self::new();
+        info.heap = HeapInfo::new(heap_start, heap_start);
+        info
+    }
+
+    /// The heap region
+    pub fn heap(&self) -> &HeapInfo {
+        &self.heap
+    }
+
+    /// 计算将 brk 设置为 `addr` 时需要修改的映射，不修改记录。
+    ///
+    /// `data_limit`、`as_limit` 分别为 RLIMIT_DATA、RLIMIT_AS 的软上限，`other_len` 为地址空间中
+    /// 堆与内存映射区之外部分(用户栈)的长度。映射成功后需要调用 [`MMapInfo::set_brk`] 更新记录。
+    pub fn brk_change(
+        &self,
+        addr: usize,
+        data_limit: u64,
+        as_limit: u64,
+        other_len: usize,
+    ) -> Result<BrkChange, BrkError> {
+        let heap = &self.heap;
+        if addr < heap.start {
+            return Err(BrkError::BelowStart);
+        }
+        if (addr - heap.start) as u64 > data_limit {
+            return Err(BrkError::DataLimit);
+        }
+        let new_end = align_up_4k(addr);
+        if new_end < heap.end {
+            return Ok(BrkChange::Shrink(new_end..heap.end));
+        }
+        if new_end == heap.end {
+            return Ok(BrkChange::Keep);
+        }
+        let addition = new_end - heap.end;
+        if (self.total_len() + addition + other_len) as u64 > as_limit {
+            return Err(BrkError::AsLimit);
+        }
+        if new_end > PROCESS_HEAP_MAX || self.overlaps(heap.end, new_end) {
+            return Err(BrkError::Overlap);
+        }
+        Ok(BrkChange::Extend(heap.end..new_end))
+    }
+
+    /// 在 [`MMapInfo::brk_change`] 返回的修改完成后，将 brk 设置为 `addr`
+    pub fn set_brk(&mut self, addr: usize) {
+        self.heap.current = addr;
+        self.heap.end = align_up_4k(addr);
+    }
+
+    pub fn alloc(&mut self, len: usize) -> Range<usize> {
+        let addr = self.map_start;
+        self.map_start += len;
+        // align to Frame size
+        self.map_start = (self.map_start + FRAME_SIZE - 1) & !(FRAME_SIZE - 1);
+        addr..self.map_start
+    }
+
+    pub fn add_region(&mut self, region: MMapRegion<F>) {
+        self.regions.push(region);
+    }
+
+    pub fn get_region(&self, addr: usize) -> Option<&MMapRegion<F>> {
+        self.regions
+            .iter()
+            .find(|region| region.start <= addr && addr < region.start + region.len)
+    }
+
+    pub fn get_region_mut(&mut self, addr: usize) -> Option<&mut MMapRegion<F>> {
+        self.regions
+            .iter_mut()
+            .find(|region| region.start <= addr && addr < region.start + region.len)
+    }
+
+    /// Whether any mapping overlaps with `[start, end)`, the heap is not included
+    pub fn overlaps(&self, start: usize, end: usize) -> bool {
+        self.regions
+            .iter()
+            .any(|region| region.start < end && start < region.start + region.map_len)
+    }
+
+    /// The length of the mappings inside `[start, end)`, counted by `map_len`
+    pub fn overlap_len(&self, start: usize, end: usize) -> usize {
+        self.regions
+            .iter()
+            .map(|region| {
+                let region_end = region.start + region.map_len;
+                min(end, region_end).saturating_sub(max(start, region.start))
+            })
+            .sum()
+    }
+
+    /// All the mappings
+    pub fn regions(&self) -> &[MMapRegion<F>] {
+        &self.regions
+    }
+
+    /// The total length of all the mappings and the heap
+    pub fn total_len(&self) -> usize {
+        self.heap.size()
+            + self
+                .regions
+                .iter()
+                .map(|region| region.map_len)
+                .sum::<usize>()
+    }
+
+    pub fn remove_region(&mut self, addr: usize) {
+        let mut index = 0;
+        for region in self.regions.iter() {
+            if region.start <= addr && addr < region.start + region.len {
+                break;
+            }
+            index += 1;
+        }
+        self.regions.remove(index);
+    }
+}
+
+impl<F: Clone> Default for MMapInfo<F> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<F: Clone> MMapRegion<F> {
+    pub fn new(
+        start: usize,
+        len: usize,
+        map_len: usize,
+        prot: ProtFlags,
+        flags: MapFlags,
+        fd: Option<F>,
+        offset: usize,
+    ) -> Self {
+        Self {
+            start,
+            len,
+            map_len,
+            prot,
+            flags,
+            fd,
+            offset,
+        }
+    }
+    // [a-b]
+    // [a-c] [c-b]
+    pub fn split(&self, addr: usize) -> (Self, Self) {
+        let mut region1 = self.clone();
+        let mut region2 = self.clone();
+        region1.len = addr - self.start;
+        region1.map_len = align_up_4k(region1.len);
+        region2.start = addr;
+        region2.len = self.start + self.len - addr;
+        region2.map_len = align_up_4k(region2.len);
+        region2.offset += region1.len;
+        (region1, region2)
+    }
+
+    pub fn set_prot(&mut self, prot: ProtFlags) {
+        self.prot = prot;
+    }
+    pub fn set_flags(&mut self, flags: MapFlags) {
+        self.flags = flags;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quickcheck::quickcheck;
+
+    fn anon_region(start: usize, len: usize) -> MMapRegion<()> {
+        MMapRegion::new(
+            start,
+            len,
+            align_up_4k(len),
+            ProtFlags::PROT_READ | ProtFlags::PROT_WRITE,
+            MapFlags::MAP_PRIVATE | MapFlags::MAP_ANONYMOUS,
+            None,
+            0,
+        )
+    }
+
+    #[test]
+    fn test_mmap_alloc_aligned() {
+        let mut info = MMapInfo::<()>::new();
+        let first = info.alloc(100);
+        assert_eq!(first, PROCESS_HEAP_MAX..PROCESS_HEAP_MAX + FRAME_SIZE);
+        let second = info.alloc(FRAME_SIZE + 1);
+        assert_eq!(second.start, first.end);
+        assert_eq!(second.len(), 2 * FRAME_SIZE);
+    }
+
+    #[test]
+    fn test_mmap_region_lookup() {
+        let mut info = MMapInfo::new();
+        info.add_region(anon_region(0x1000, 0x1800));
+        info.add_region(anon_region(0x4000, 0x1000));
+        assert_eq!(info.get_region(0x1000).map(|r| r.start), Some(0x1000));
+        assert_eq!(info.get_region(0x27ff).map(|r| r.start), Some(0x1000));
+        // 超出 len 但仍在 map_len 内的地址不属于映射
+        assert!(info.get_region(0x2800).is_none());
+        assert!(info.get_region(0x5000).is_none());
+        assert_eq!(info.total_len(), 0x3000);
+        info.remove_region(0x1000);
+        assert_eq!(info.regions().len(), 1);
+        assert_eq!(info.regions()[0].start, 0x4000);
+    }
+
+    #[test]
+    fn test_mmap_overlaps() {
+        let mut info = MMapInfo::new();
+        info.add_region(anon_region(0x2000, 0x1800));
+        // 重叠检查按照页对齐后的 map_len 计算
+        assert!(info.overlaps(0x3800, 0x4000));
+        assert!(info.overlaps(0x1000, 0x2001));
+        assert!(!info.overlaps(0x1000, 0x2000));
+        assert!(!info.overlaps(0x4000, 0x5000));
+    }
+
+    #[test]
+    fn test_mmap_overlap_len() {
+        let mut info = MMapInfo::new();
+        info.add_region(anon_region(0x2000, 0x1800));
+        info.add_region(anon_region(0x6000, 0x1000));
+        assert_eq!(info.overlap_len(0x3000, 0x7000), 0x2000);
+        assert_eq!(info.overlap_len(0x0, 0x10000), info.total_len());
+        assert_eq!(info.overlap_len(0x4000, 0x6000), 0);
+    }
+
+    #[test]
+    fn test_mmap_region_split() {
+        let mut region = anon_region(0x1000, 0x3000);
+        region.offset = 0x100;
+        let (left, right) = region.split(0x2000);
+        assert_eq!(
+            (left.start, left.len, left.map_len),
+            (0x1000, 0x1000, 0x1000)
+        );
+        assert_eq!(
+            (right.start, right.len, right.map_len),
+            (0x2000, 0x2000, 0x2000)
+        );
+        assert_eq!(left.offset, 0x100);
+        assert_eq!(right.offset, 0x1100);
+    }
+
+    const HEAP_START: usize = 0x10000;
+
+    #[test]
+    fn test_brk_extend_and_shrink() {
+        let mut info = MMapInfo::<()>::with_heap(HEAP_START);
+        let brk = HEAP_START + 2 * FRAME_SIZE + 1;
+        let change = info.brk_change(brk, u64::MAX, u64::MAX, 0);
+        assert_eq!(
+            change,
+            Ok(BrkChange::Extend(HEAP_START..HEAP_START + 3 * FRAME_SIZE))
+        );
+        info.set_brk(brk);
+        assert_eq!(info.heap().current, brk);
+        assert_eq!(info.total_len(), 3 * FRAME_SIZE);
+        // 同一页内移动 brk 不需要修改映射
+        assert_eq!(
+            info.brk_change(brk + 1, u64::MAX, u64::MAX, 0),
+            Ok(BrkChange::Keep)
+        );
+        let change = info.brk_change(HEAP_START + 10, u64::MAX, u64::MAX, 0);
+        assert_eq!(
+            change,
+            Ok(BrkChange::Shrink(
+                HEAP_START + FRAME_SIZE..HEAP_START + 3 * FRAME_SIZE
+            ))
+        );
+        info.set_brk(HEAP_START + 10);
+        assert_eq!(info.heap().end, HEAP_START + FRAME_SIZE);
+        assert!(info.heap().contains(HEAP_START));
+        assert!(!info.heap().contains(HEAP_START + FRAME_SIZE));
+        assert_eq!(
+            info.brk_change(HEAP_START - 1, u64::MAX, u64::MAX, 0),
+            Err(BrkError::BelowStart)
+        );
+    }
+
+    #[test]
+    fn test_brk_into_mmap_region() {
+        let mut info = MMapInfo::with_heap(HEAP_START);
+        info.add_region(anon_region(HEAP_START + 2 * FRAME_SIZE, FRAME_SIZE));
+        assert_eq!(
+            info.brk_change(HEAP_START + 2 * FRAME_SIZE, u64::MAX, u64::MAX, 0),
+            Ok(BrkChange::Extend(HEAP_START..HEAP_START + 2 * FRAME_SIZE))
+        );
+        assert_eq!(
+            info.brk_change(HEAP_START + 2 * FRAME_SIZE + 1, u64::MAX, u64::MAX, 0),
+            Err(BrkError::Overlap)
+        );
+        assert_eq!(
+            info.brk_change(PROCESS_HEAP_MAX + 1, u64::MAX, u64::MAX, 0),
+            Err(BrkError::Overlap)
+        );
+    }
+
+    #[test]
+    fn test_brk_limits() {
+        let mut info = MMapInfo::with_heap(HEAP_START);
+        info.add_region(anon_region(PROCESS_HEAP_MAX, 2 * FRAME_SIZE));
+        let data_limit = 4 * FRAME_SIZE as u64;
+        assert!(info
+            .brk_change(HEAP_START + 4 * FRAME_SIZE, data_limit, u64::MAX, 0)
+            .is_ok());
+        assert_eq!(
+            info.brk_change(HEAP_START + 4 * FRAME_SIZE + 1, data_limit, u64::MAX, 0),
+            Err(BrkError::DataLimit)
+        );
+        // 地址空间的长度包括映射区、堆和用户栈
+        let as_limit = 5 * FRAME_SIZE as u64;
+        assert!(info
+            .brk_change(HEAP_START + 2 * FRAME_SIZE, u64::MAX, as_limit, FRAME_SIZE)
+            .is_ok());
+        assert_eq!(
+            info.brk_change(
+                HEAP_START + 2 * FRAME_SIZE + 1,
+                u64::MAX,
+                as_limit,
+                FRAME_SIZE
+            ),
+            Err(BrkError::AsLimit)
+        );
+        // 缩减堆不受限制影响
+        info.set_brk(HEAP_START + 2 * FRAME_SIZE);
+        assert!(matches!(
+            info.brk_change(HEAP_START, 0, 0, FRAME_SIZE),
+            Ok(BrkChange::Shrink(_))
+        ));
+    }
+
+    quickcheck! {
+        /// 分配的区间按页对齐、互不重叠且首尾相接
+        fn prop_alloc_aligned_and_disjoint(lens: Vec<u16>) -> bool {
+            let mut info = MMapInfo::<()>::new();
+            let mut next = PROCESS_HEAP_MAX;
+            lens.into_iter().all(|len| {
+                let len = len as usize + 1;
+                let range = info.alloc(len);
+                let ok = range.start == next
+                    && range.end % FRAME_SIZE == 0
+                    && range.len() >= len
+                    && range.len() < len + FRAME_SIZE;
+                next = range.end;
+                ok
+            })
+        }
+
+        /// 拆分后两部分首尾相接，长度与文件偏移保持一致
+        fn prop_split_preserves_range(start: u32, len: u16, at: u16, offset: u32) -> bool {
+            let start = start as usize & !(FRAME_SIZE - 1);
+            let len = len as usize + 2;
+            let addr = start + 1 + at as usize % (len - 1);
+            let mut region = anon_region(start, len);
+            region.offset = offset as usize;
+            let (left, right) = region.split(addr);
+            left.start == start
+                && left.start + left.len == right.start
+                && left.len + right.len == len
+                && right.offset == region.offset + left.len
+                && left.map_len == align_up_4k(left.len)
+                && right.map_len == align_up_4k(right.len)
+        }
+
+        /// 按地址查找到的区域一定包含该地址
+        fn prop_get_region_contains(regions: Vec<(u8, u16)>, addr: u32) -> bool {
+            let mut info = MMapInfo::new();
+            let mut start = 0;
+            for (gap, len) in regions {
+                start += gap as usize * FRAME_SIZE;
+                let len = len as usize + 1;
+                info.add_region(anon_region(start, len));
+                start += align_up_4k(len);
+            }
+            let addr = addr as usize;
+            match info.get_region(addr) {
+                Some(region) => region.start <= addr && addr < region.start + region.len,
+                None => info
+                    .regions()
+                    .iter()
+                    .all(|region| addr < region.start || addr >= region.start + region.len),
+            }
+        }
+    }
+}
@@ -163,6 +163,33 @@ pub fn query_kernel_space(addr: usize) -> Option<usize> {
         .map(|(x, _, _)| x.as_usize())
 }
 
+/// 修改内核代码段中 `addr` 处的指令，供内核调试器写入软件断点。地址没有映射时返回 false
+///
+/// 代码段以只读方式映射，写入期间临时为所在的页加上写权限。调用者需要保证其它核没有在执行被修改的代码，
+/// 并在这些核恢复执行前同步它们的指令缓存。
+pub fn patch_kernel_text(addr: usize, data: &[u8]) -> bool {
+    let mut kernel_space = KERNEL_SPACE.lock();
+    for (i, byte) in data.iter().enumerate() {
+        let vaddr = VirtAddr::from((addr + i) & !(FRAME_SIZE - 1));
+        let flags = match kernel_space.query(vaddr) {
+            Ok((_, flags, _)) => flags,
+            Err(_) => return false,
+        };
+        if kernel_space
+            .modify_pte_flags(vaddr, flags | MappingFlags::W, false)
+            .is_err()
+        {
+            return false;
+        }
+        arch::flush_tlb();
+        unsafe { ((addr + i) as *mut u8).write_volatile(*byte) };
+        kernel_space.modify_pte_flags(vaddr, flags, false).unwrap();
+        arch::flush_tlb();
+    }
+    arch::flush_icache();
+    true
+}
+
 pub fn kernel_space() -> Arc<Mutex<Sv39PageTable<VmmPageAllocator>>> {
     KERNEL_SPACE.clone()
 }
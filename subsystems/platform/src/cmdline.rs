@@ -0,0 +1,174 @@
+//! 内核命令行解析
+//!
+//! 命令行来自设备树的 `/chosen/bootargs`，目前支持的参数有：
+//! + `root=`: 根文件系统所在的块设备，如 `root=/dev/sda`；
+//! + `rootfstype=`: 根文件系统的类型，未指定时根据超级块自动识别；
+//! + `init=`: 切换到真正的根文件系统后执行的第一个程序；
+//! + `rdinit=`: 使用 initramfs 作为根文件系统时执行的第一个程序；
+//! + `ro`/`rw`: 以只读/读写方式挂载根文件系统；
+//! + `loglevel=`: 内核日志等级，可以是 0-7 的数字或 error/warn/info/debug/trace；
+//! + `kgdb=`: 用作 GDB 调试端口的串口在设备树中的序号，0 号串口为控制台，如 `kgdb=1`；
+//! + `kgdbwait`: 启动过程中停下来等待 GDB 连接；
+//! + `crashdump=`: 保留用于保存崩溃转储的内存区域，格式为 `<size>[@<addr>]`，大小可以带 K/M/G 后缀，
+//!   未指定地址时使用内存末尾的区域，如 `crashdump=4M`；
+//! + `ktest`/`ktest=`: 启动后运行内核单元测试(需要开启 `test` 特性)，结果以 `tap`(默认) 或 `json` 格式输出，
+//!   运行结束后直接关机；
+//! + `ktest.filter=`: 只运行名称中包含该字符串的测试。
+use crate::PlatformInfo;
+use log::LevelFilter;
+use spin::Once;
+
+#[derive(Debug, Default)]
+pub struct KernelCmdline {
+    pub root: Option<&'static str>,
+    pub rootfstype: Option<&'static str>,
+    pub init: Option<&'static str>,
+    pub rdinit: Option<&'static str>,
+    pub read_only: bool,
+    pub loglevel: Option<LevelFilter>,
+    pub kgdb: Option<usize>,
+    pub kgdb_wait: bool,
+    /// 崩溃转储区域的大小和起始地址
+    pub crashdump: Option<(usize, Option<usize>)>,
+    /// 内核单元测试结果的输出格式，为 `None` 时不运行测试
+    pub ktest: Option<&'static str>,
+    pub ktest_filter: Option<&'static str>,
+}
+
+static KERNEL_CMDLINE: Once<KernelCmdline> = Once::new();
+
+/// 解析内核命令行。此时堆分配器还未初始化，因此解析过程不能分配内存
+pub(crate) fn init_cmdline(info: &'static PlatformInfo) {
+    let bootargs = info
+        .bootargs
+        .as_ref()
+        .and_then(|args| core::str::from_utf8(&args[..info.bootargs_len]).ok())
+        .unwrap_or("");
+    KERNEL_CMDLINE.call_once(|| parse_cmdline(bootargs));
+}
+
+/// 获取内核命令行
+pub fn kernel_cmdline() -> &'static KernelCmdline {
+    KERNEL_CMDLINE.get().unwrap()
+}
+
+fn parse_cmdline(bootargs: &'static str) -> KernelCmdline {
+    let mut cmdline = KernelCmdline::default();
+    for arg in bootargs.split_whitespace() {
+        let (key, value) = match arg.split_once('=') {
+            Some((key, value)) => (key, Some(value)),
+            None => (arg, None),
+        };
+        match (key, value) {
+            ("root", Some(value)) => cmdline.root = Some(value),
+            ("rootfstype", Some(value)) => cmdline.rootfstype = Some(value),
+            ("init", Some(value)) => cmdline.init = Some(value),
+            ("rdinit", Some(value)) => cmdline.rdinit = Some(value),
+            ("ro", None) => cmdline.read_only = true,
+            ("rw", None) => cmdline.read_only = false,
+            ("loglevel", Some(value)) => cmdline.loglevel = parse_loglevel(value),
+            ("kgdb", Some(value)) => cmdline.kgdb = value.parse().ok(),
+            ("kgdbwait", None) => cmdline.kgdb_wait = true,
+            ("crashdump", Some(value)) => cmdline.crashdump = parse_crashdump(value),
+            ("ktest", None) => cmdline.ktest = Some("tap"),
+            ("ktest", Some(value)) => cmdline.ktest = Some(value),
+            ("ktest.filter", Some(value)) => cmdline.ktest_filter = Some(value),
+            _ => {}
+        }
+    }
+    cmdline
+}
+
+fn parse_loglevel(value: &str) -> Option<LevelFilter> {
+    let level = match value {
+        "0" | "1" | "2" | "3" | "error" | "ERROR" => LevelFilter::Error,
+        "4" | "warn" | "WARN" => LevelFilter::Warn,
+        "5" | "6" | "info" | "INFO" => LevelFilter::Info,
+        "7" | "debug" | "DEBUG" => LevelFilter::Debug,
+        "trace" | "TRACE" => LevelFilter::Trace,
+        "off" | "OFF" => LevelFilter::Off,
+        _ => return None,
+    };
+    Some(level)
+}
+
+/// 解析 `<size>[@<addr>]`，地址可以是十进制数或者以 0x 开头的十六进制数
+fn parse_crashdump(value: &str) -> Option<(usize, Option<usize>)> {
+    let (size, addr) = match value.split_once('@') {
+        Some((size, addr)) => (size, Some(addr)),
+        None => (value, None),
+    };
+    let size = parse_size(size).filter(|size| *size > 0)?;
+    let addr = match addr {
+        Some(addr) => Some(parse_size(addr)?),
+        None => None,
+    };
+    Some((size, addr))
+}
+
+fn parse_size(value: &str) -> Option<usize> {
+    let (value, shift) = match value.as_bytes().last()? {
+        b'K' | b'k' => (&value[..value.len() - 1], 10),
+        b'M' | b'm' => (&value[..value.len() - 1], 20),
+        b'G' | b'g' => (&value[..value.len() - 1], 30),
+        _ => (value, 0),
+    };
+    let value = match value.strip_prefix("0x") {
+        Some(hex) => usize::from_str_radix(hex, 16).ok()?,
+        None => value.parse().ok()?,
+    };
+    value.checked_mul(1 << shift)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cmdline() {
+        let cmdline =
+            parse_cmdline("root=/dev/sda rootfstype=ext4 init=/sbin/init ro loglevel=debug kgdb=1");
+        assert_eq!(cmdline.root, Some("/dev/sda"));
+        assert_eq!(cmdline.rootfstype, Some("ext4"));
+        assert_eq!(cmdline.init, Some("/sbin/init"));
+        assert!(cmdline.read_only);
+        assert_eq!(cmdline.loglevel, Some(LevelFilter::Debug));
+        assert_eq!(cmdline.kgdb, Some(1));
+        assert!(!cmdline.kgdb_wait);
+        let cmdline = parse_cmdline("ro rw unknown=1 kgdbwait");
+        assert!(!cmdline.read_only);
+        assert!(cmdline.kgdb_wait);
+        assert_eq!(cmdline.root, None);
+    }
+
+    #[test]
+    fn test_parse_ktest() {
+        assert_eq!(parse_cmdline("ktest").ktest, Some("tap"));
+        let cmdline = parse_cmdline("ktest=json ktest.filter=vfs::");
+        assert_eq!(cmdline.ktest, Some("json"));
+        assert_eq!(cmdline.ktest_filter, Some("vfs::"));
+        assert_eq!(parse_cmdline("").ktest, None);
+    }
+
+    #[test]
+    fn test_parse_loglevel() {
+        assert_eq!(parse_loglevel("3"), Some(LevelFilter::Error));
+        assert_eq!(parse_loglevel("WARN"), Some(LevelFilter::Warn));
+        assert_eq!(parse_loglevel("trace"), Some(LevelFilter::Trace));
+        assert_eq!(parse_loglevel("verbose"), None);
+    }
+
+    #[test]
+    fn test_parse_crashdump() {
+        assert_eq!(parse_crashdump("4M"), Some((4 << 20, None)));
+        assert_eq!(
+            parse_crashdump("16k@0x90000000"),
+            Some((16 << 10, Some(0x9000_0000)))
+        );
+        assert_eq!(parse_crashdump("0"), None);
+        assert_eq!(parse_crashdump("4M@"), None);
+        assert_eq!(parse_size("1G"), Some(1 << 30));
+        assert_eq!(parse_size("0x10"), Some(16));
+        assert_eq!(parse_size(&format!("{}G", usize::MAX)), None);
+    }
+}
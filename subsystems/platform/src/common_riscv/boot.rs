@@ -1,9 +1,16 @@
 use config::{CPU_NUM, STACK_SIZE, STACK_SIZE_BITS};
 use core::arch::asm;
+use core::ops::Range;
 
 #[link_section = ".bss.stack"]
 static mut STACK: [u8; STACK_SIZE * CPU_NUM] = [0; STACK_SIZE * CPU_NUM];
 
+/// 核 `hart_id` 启动时使用的栈，之后调度器也运行在这个栈上
+pub fn boot_stack(hart_id: usize) -> Range<usize> {
+    let start = unsafe { STACK.as_ptr() as usize } + hart_id * STACK_SIZE;
+    start..start + STACK_SIZE
+}
+
 /// 内核入口
 ///
 /// 用于初始化内核的栈空间，并关闭中断
@@ -1,3 +1,5 @@
 pub mod basic;
+#[cfg(not(feature = "host"))]
 pub mod boot;
+#[cfg(not(feature = "host"))]
 pub mod sbi;
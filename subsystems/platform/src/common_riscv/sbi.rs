@@ -71,7 +71,8 @@ pub const EXTENSION_TIMER: usize = 0x54494D45;
 // pub const EXTENSION_RFENCE: usize = 0x52464E43;
 /// SBI HSM 扩展
 pub const EXTENSION_HSM: usize = 0x48534D;
-// pub const EXTENSION_SRST: usize = 0x53525354;
+/// SBI 系统复位扩展
+pub const EXTENSION_SRST: usize = 0x53525354;
 
 /// SBI HSM扩展的启动cpu功能
 const FUNCTION_HSM_HART_START: usize = 0x0;
@@ -79,6 +80,13 @@ const FUNCTION_HSM_HART_START: usize = 0x0;
 // const FUNCTION_HSM_HART_GET_STATUS: usize = 0x2;
 const FUNCTION_HSM_HART_SUSPEND: usize = 0x3;
 
+/// SBI SRST扩展的复位功能
+const FUNCTION_SRST_SYSTEM_RESET: usize = 0x0;
+/// 复位类型：关机
+const SRST_TYPE_SHUTDOWN: usize = 0x0;
+/// 复位类型：热重启，内存中的内容保持不变
+const SRST_TYPE_WARM_REBOOT: usize = 0x2;
+
 /// 第三种类型的SBI调用
 ///
 /// 可以传递更多参数
@@ -117,6 +125,33 @@ pub fn hart_start(hart_id: usize, start_addr: usize, opaque: usize) -> SbiRet {
     )
 }
 
+/// 通过 SRST 扩展关机，`reason` 为复位原因，0 表示正常关机，1 表示系统故障
+///
+/// QEMU virt 上的 OpenSBI 会把系统故障转换为 QEMU 的非 0 退出码。
+/// 固件不支持 SRST 扩展时退回到旧的关机调用。
+pub fn system_reset(reason: usize) -> ! {
+    sbi_call_3(
+        EXTENSION_SRST,
+        FUNCTION_SRST_SYSTEM_RESET,
+        SRST_TYPE_SHUTDOWN,
+        reason,
+        0,
+    );
+    system_shutdown()
+}
+
+/// 通过 SRST 扩展热重启，重启后内存中的内容保持不变。固件不支持时关机
+pub fn system_warm_reboot() -> ! {
+    sbi_call_3(
+        EXTENSION_SRST,
+        FUNCTION_SRST_SYSTEM_RESET,
+        SRST_TYPE_WARM_REBOOT,
+        0,
+        0,
+    );
+    system_shutdown()
+}
+
 /// wrap sbi SBI_SEND_IPI call
 #[allow(unused)]
 pub fn send_ipi(ptr: usize) {
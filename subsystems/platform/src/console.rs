@@ -19,11 +19,52 @@ macro_rules! println {
         concat!($fmt, "\n"), $($arg)*));
 }
 
+/// 内核日志环形缓冲区的大小
+pub const LOG_RING_SIZE: usize = 64 * 1024;
+
+/// 保存最近的控制台输出，供崩溃转储使用
+struct LogRing {
+    buf: [u8; LOG_RING_SIZE],
+    /// 累计写入的字节数
+    written: usize,
+}
+
+impl LogRing {
+    const fn new() -> Self {
+        Self {
+            buf: [0; LOG_RING_SIZE],
+            written: 0,
+        }
+    }
+
+    fn push(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.buf[self.written % LOG_RING_SIZE] = byte;
+            self.written += 1;
+        }
+    }
+}
+
+static LOG_RING: Mutex<LogRing> = Mutex::new(LogRing::new());
+
+/// 将日志环形缓冲区中最近的输出复制到 `out` 中，返回复制的字节数。
+/// 缓冲区中的内容比 `out` 长时只复制最新的部分
+pub fn read_log_ring(out: &mut [u8]) -> usize {
+    let ring = LOG_RING.lock();
+    let len = ring.written.min(LOG_RING_SIZE).min(out.len());
+    let start = ring.written - len;
+    for (index, byte) in out[..len].iter_mut().enumerate() {
+        *byte = ring.buf[(start + index) % LOG_RING_SIZE];
+    }
+    len
+}
+
 pub struct Stdout;
 
 /// 对`Stdout`实现输出的Trait
 impl Write for Stdout {
     fn write_str(&mut self, s: &str) -> Result {
+        LOG_RING.lock().push(s.as_bytes());
         s.as_bytes().iter().for_each(|x| {
             crate::console_putchar(*x);
         });
@@ -0,0 +1,7 @@
+/// 宿主机后端的时钟以纳秒计数
+pub const CLOCK_FREQ: usize = 1_000_000_000;
+pub const BLOCK_CACHE_FRAMES: usize = 1024 * 4 * 4;
+pub const HEAP_SIZE: usize = 0x26_00000;
+
+/// 宿主机上没有需要映射的设备地址空间
+pub const MMIO: &[(usize, usize)] = &[];
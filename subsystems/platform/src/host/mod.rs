@@ -0,0 +1,52 @@
+//! 宿主机平台，用于在 Linux 上通过 `cargo test` 测试各个子系统
+//!
+//! 控制台输出写到标准输出，关机时直接退出进程，关机原因作为进程的退出码。
+pub mod config;
+
+use crate::{PlatformInfo, ShutdownReason};
+use std::io::Write;
+
+/// 宿主机的机器信息，命令行来自环境变量 `ALIEN_CMDLINE`
+pub fn basic_machine_info() -> PlatformInfo {
+    let mut info = PlatformInfo {
+        model: [0; 32],
+        smp: ::config::CPU_NUM,
+        memory: 0..0,
+        plic: 0..0,
+        clint: 0..0,
+        initrd: None,
+        bootargs: None,
+        bootargs_len: 0,
+    };
+    info.model[..4].copy_from_slice(b"host");
+    if let Ok(cmdline) = std::env::var("ALIEN_CMDLINE") {
+        let mut bootargs = [0; 255];
+        let len = cmdline.len().min(bootargs.len());
+        bootargs[..len].copy_from_slice(&cmdline.as_bytes()[..len]);
+        info.bootargs = Some(bootargs);
+        info.bootargs_len = len;
+    }
+    info
+}
+
+pub fn set_timer(_time: usize) {}
+
+pub fn system_shutdown() -> ! {
+    system_shutdown_with_reason(ShutdownReason::None)
+}
+
+/// 宿主机上无法热重启，直接退出
+pub fn system_reboot() -> ! {
+    system_shutdown()
+}
+
+pub fn system_shutdown_with_reason(reason: ShutdownReason) -> ! {
+    std::io::stdout().flush().unwrap();
+    std::process::exit(reason as i32)
+}
+
+pub fn send_ipi(_hart_mask: usize) {}
+
+pub fn console_putchar(ch: u8) {
+    std::io::stdout().write_all(&[ch]).unwrap();
+}
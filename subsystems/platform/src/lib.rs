@@ -1,14 +1,18 @@
-#![no_std]
+#![cfg_attr(not(feature = "host"), no_std)]
 #![feature(naked_functions)]
 #![feature(asm_const)]
 extern crate alloc;
 
+pub mod cmdline;
 #[macro_use]
 pub mod console;
 mod common_riscv;
 #[cfg(feature = "hifive")]
 mod hifive_riscv;
+#[cfg(feature = "host")]
+mod host;
 
+#[cfg(not(feature = "host"))]
 use ::config::CPU_NUM;
 pub use common_riscv::basic::MachineInfo as PlatformInfo;
 use spin::Once;
@@ -32,11 +36,20 @@ pub use starfive2_riscv::{config, set_timer, system_shutdown};
 #[cfg(feature = "hifive")]
 use hifive_riscv::{basic_machine_info, console_putchar};
 
+#[cfg(not(feature = "host"))]
 use crate::common_riscv::sbi::hart_start;
 use crate::console::PrePrint;
 #[cfg(feature = "hifive")]
 pub use hifive_riscv::{config, set_timer, system_shutdown};
 
+#[cfg(feature = "host")]
+use host::{basic_machine_info, console_putchar};
+#[cfg(feature = "host")]
+pub use host::{
+    config, send_ipi, set_timer, system_reboot, system_shutdown, system_shutdown_with_reason,
+};
+
+#[cfg(not(feature = "host"))]
 #[no_mangle]
 pub fn platform_init(hart_id: usize, _dtb: usize) {
     println!("{}", ::config::FLAG);
@@ -47,7 +60,8 @@ pub fn platform_init(hart_id: usize, _dtb: usize) {
     #[cfg(feature = "qemu_riscv")]
     qemu_riscv::init_dtb(Some(_dtb));
     let machine_info = basic_machine_info();
-    MACHINE_INFO.call_once(|| machine_info);
+    let machine_info = MACHINE_INFO.call_once(|| machine_info);
+    cmdline::init_cmdline(machine_info);
     logging::init_logger();
     preprint::init_print(&PrePrint);
     #[cfg(feature = "smp")]
@@ -55,10 +69,25 @@ pub fn platform_init(hart_id: usize, _dtb: usize) {
     unsafe { main(hart_id) }
 }
 
+/// 初始化宿主机平台，在测试开始前调用，重复调用时只有第一次生效
+///
+/// 内核命令行从环境变量 `ALIEN_CMDLINE` 中读取。
+#[cfg(feature = "host")]
+pub fn platform_init() {
+    static INIT: Once<()> = Once::new();
+    INIT.call_once(|| {
+        let machine_info = MACHINE_INFO.call_once(basic_machine_info);
+        cmdline::init_cmdline(machine_info);
+        logging::init_logger();
+        preprint::init_print(&PrePrint);
+    });
+}
+
 /// 唤醒其它核
 ///
 /// 对于qemu来说，只需要工具所有的核都是一样的，因此从严号核开始唤醒。
 /// 对于visionfive2/unmatched 来说，0号核只有M态，因此不进行唤醒
+#[cfg(not(feature = "host"))]
 fn init_other_hart(hart_id: usize) {
     let start_hart = if cfg!(any(feature = "vf2", feature = "hifive")) {
         1
@@ -73,6 +102,7 @@ fn init_other_hart(hart_id: usize) {
     }
 }
 
+#[cfg(not(feature = "host"))]
 extern "C" {
     fn main(hart_id: usize);
     fn _start_secondary();
@@ -85,6 +115,38 @@ pub fn platform_dtb_ptr() -> usize {
     return *starfive2_riscv::DTB.get().unwrap();
     #[cfg(feature = "qemu_riscv")]
     return *qemu_riscv::DTB.get().unwrap();
+    #[cfg(feature = "host")]
+    return 0;
+}
+
+/// 关机原因，对应 SBI SRST 扩展中的复位原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShutdownReason {
+    /// 正常关机
+    None = 0,
+    /// 因系统故障关机，在 QEMU 中表现为非 0 的退出码
+    SystemFailure = 1,
+}
+
+/// 关机并向固件报告关机原因
+#[cfg(not(feature = "host"))]
+pub fn system_shutdown_with_reason(reason: ShutdownReason) -> ! {
+    common_riscv::sbi::system_reset(reason as usize)
+}
+
+/// 热重启，重启后内存中的内容保持不变。固件不支持时关机
+#[cfg(not(feature = "host"))]
+pub fn system_reboot() -> ! {
+    common_riscv::sbi::system_warm_reboot()
+}
+
+#[cfg(not(feature = "host"))]
+pub use common_riscv::boot::boot_stack;
+
+/// 向 `hart_mask` 中的核发送核间中断
+#[cfg(not(feature = "host"))]
+pub fn send_ipi(hart_mask: usize) {
+    common_riscv::sbi::send_ipi(&hart_mask as *const usize as usize);
 }
 
 static MACHINE_INFO: Once<PlatformInfo> = Once::new();
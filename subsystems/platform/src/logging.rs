@@ -30,12 +30,16 @@ impl Log for SimpleLogger {
 pub fn init_logger() {
     println!("Init logger {:?}", option_env!("LOG"));
     log::set_logger(&SimpleLogger).unwrap();
-    log::set_max_level(match option_env!("LOG") {
-        Some("ERROR") => LevelFilter::Error,
-        Some("WARN") => LevelFilter::Warn,
-        Some("INFO") => LevelFilter::Info,
-        Some("DEBUG") => LevelFilter::Debug,
-        Some("TRACE") => LevelFilter::Trace,
-        _ => LevelFilter::Off,
-    });
+    // the loglevel in kernel command line takes precedence over the LOG env at build time
+    let level = crate::cmdline::kernel_cmdline()
+        .loglevel
+        .unwrap_or(match option_env!("LOG") {
+            Some("ERROR") => LevelFilter::Error,
+            Some("WARN") => LevelFilter::Warn,
+            Some("INFO") => LevelFilter::Info,
+            Some("DEBUG") => LevelFilter::Debug,
+            Some("TRACE") => LevelFilter::Trace,
+            _ => LevelFilter::Off,
+        });
+    log::set_max_level(level);
 }
@@ -0,0 +1,166 @@
+//! 宿主机后端，每个线程对应一个任务
+//!
+//! 任务等待时挂起所在的线程，被唤醒时恢复线程。用户地址空间与宿主机地址空间相同，
+//! 用户指针直接当作可访问的地址使用。
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicBool, Ordering};
+use std::thread::Thread;
+use std::time::Duration;
+
+use constants::{AlienResult, LinuxErrno};
+
+use crate::{KTask, KTaskShim, KTASK_SHIM};
+
+/// 线程挂起的最长时间，超时后由调用者重新检查等待条件
+const PARK_TIMEOUT: Duration = Duration::from_millis(10);
+
+pub struct HostTask {
+    thread: Thread,
+    waiting: AtomicBool,
+    signal: AtomicBool,
+}
+
+impl HostTask {
+    fn new() -> Self {
+        Self {
+            thread: std::thread::current(),
+            waiting: AtomicBool::new(false),
+            signal: AtomicBool::new(false),
+        }
+    }
+
+    /// 设置任务是否有待处理的信号，用于测试等待被信号打断的情况
+    pub fn set_signal(&self, signal: bool) {
+        self.signal.store(signal, Ordering::SeqCst);
+        if signal {
+            self.thread.unpark();
+        }
+    }
+
+    pub fn is_waiting(&self) -> bool {
+        self.waiting.load(Ordering::SeqCst)
+    }
+}
+
+impl KTask for HostTask {
+    fn to_wait(&self) {
+        self.waiting.store(true, Ordering::SeqCst);
+    }
+
+    fn to_wakeup(&self) {
+        self.waiting.store(false, Ordering::SeqCst);
+        self.thread.unpark();
+    }
+
+    fn have_signal(&self) -> bool {
+        self.signal.load(Ordering::SeqCst)
+    }
+}
+
+std::thread_local! {
+    static CURRENT: Arc<HostTask> = Arc::new(HostTask::new());
+}
+
+/// 当前线程对应的任务
+pub fn current_host_task() -> Arc<HostTask> {
+    CURRENT.with(|task| task.clone())
+}
+
+struct HostTaskShim;
+
+impl KTaskShim for HostTaskShim {
+    fn get_task(&self) -> Arc<dyn KTask> {
+        current_host_task()
+    }
+
+    /// 没有运行队列，放回任务即唤醒任务所在的线程
+    fn put_task(&self, task: Arc<dyn KTask>) {
+        task.to_wakeup();
+    }
+
+    fn suspend(&self) {
+        let task = current_host_task();
+        if task.is_waiting() && !task.have_signal() {
+            std::thread::park_timeout(PARK_TIMEOUT);
+        } else {
+            std::thread::yield_now();
+        }
+    }
+
+    fn transfer_ptr_raw(&self, ptr: usize, _write: bool) -> AlienResult<usize> {
+        if ptr == 0 {
+            return Err(LinuxErrno::EFAULT);
+        }
+        Ok(ptr)
+    }
+
+    fn transfer_buf_raw(
+        &self,
+        src: usize,
+        size: usize,
+        _write: bool,
+    ) -> AlienResult<Vec<&mut [u8]>> {
+        if src == 0 {
+            return Err(LinuxErrno::EFAULT);
+        }
+        let buf = unsafe { core::slice::from_raw_parts_mut(src as *mut u8, size) };
+        Ok(vec![buf])
+    }
+}
+
+/// 注册宿主机后端，测试开始前调用，重复调用时只有第一次生效
+pub fn init_host_shim() {
+    KTASK_SHIM.call_once(|| Box::new(HostTaskShim));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{copy_data_from_task, copy_data_to_task, current_task, put_task, suspend};
+
+    #[test]
+    fn test_wait_and_wakeup() {
+        init_host_shim();
+        let (tx, rx) = std::sync::mpsc::channel();
+        let handle = std::thread::spawn(move || {
+            let task = current_task();
+            task.to_wait();
+            tx.send(task).unwrap();
+            while current_host_task().is_waiting() {
+                suspend();
+            }
+        });
+        let task = rx.recv().unwrap();
+        put_task(task);
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_signal() {
+        init_host_shim();
+        let task = current_host_task();
+        assert!(!current_task().have_signal());
+        task.set_signal(true);
+        assert!(current_task().have_signal());
+        task.set_signal(false);
+    }
+
+    #[test]
+    fn test_copy_data() {
+        init_host_shim();
+        let src = [1u32, 2, 3, 4];
+        let mut dst = [0u32; 4];
+        copy_data_to_task(&src, &mut dst).unwrap();
+        assert_eq!(dst, src);
+        let mut back = [0u32; 4];
+        copy_data_from_task(&dst, &mut back).unwrap();
+        assert_eq!(back, src);
+        assert_eq!(
+            copy_data_to_task(&src, core::ptr::null_mut()),
+            Err(LinuxErrno::EFAULT)
+        );
+    }
+}
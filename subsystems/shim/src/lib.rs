@@ -1,9 +1,15 @@
-#![no_std]
+#![cfg_attr(not(feature = "host"), no_std)]
 extern crate alloc;
 
+#[cfg(feature = "host")]
+mod host;
+#[cfg(feature = "host")]
+pub use host::{current_host_task, init_host_shim, HostTask};
+
 use alloc::boxed::Box;
 use alloc::sync::Arc;
 use alloc::vec::Vec;
+use constants::AlienResult;
 use downcast_rs::{impl_downcast, DowncastSync};
 use spin::Once;
 
@@ -19,14 +25,17 @@ pub trait KTaskShim: Send + Sync {
     fn get_task(&self) -> Arc<dyn KTask>;
     fn put_task(&self, task: Arc<dyn KTask>);
     fn suspend(&self);
-    fn transfer_ptr_raw(&self, ptr: usize) -> usize;
-    fn transfer_buf_raw(&self, src: usize, size: usize) -> Vec<&mut [u8]>;
+    /// 获取当前任务地址空间中 `ptr` 对应的物理地址，`write` 表示是否需要写入，地址不可访问时返回 EFAULT
+    fn transfer_ptr_raw(&self, ptr: usize, write: bool) -> AlienResult<usize>;
+    /// 获取当前任务地址空间中一段缓冲区对应的一组物理页，地址不可访问时返回 EFAULT
+    fn transfer_buf_raw(&self, src: usize, size: usize, write: bool)
+        -> AlienResult<Vec<&mut [u8]>>;
 }
 
 impl dyn KTaskShim {
-    fn copy_data_to_task<T: 'static + Copy>(&self, src: *const T, dst: *mut T) {
+    fn copy_data_to_task<T: 'static + Copy>(&self, src: *const T, dst: *mut T) -> AlienResult<()> {
         let size = core::mem::size_of::<T>();
-        let bufs = self.transfer_buf_raw(dst as usize, size);
+        let bufs = self.transfer_buf_raw(dst as usize, size, true)?;
         let src = unsafe { core::slice::from_raw_parts(src as *const u8, size) };
         let mut start = 0;
         for buffer in bufs {
@@ -40,10 +49,15 @@ impl dyn KTaskShim {
             }
             start += len;
         }
+        Ok(())
     }
-    fn copy_data_from_task<T: 'static + Copy>(&self, src: *const T, dst: *mut T) {
+    fn copy_data_from_task<T: 'static + Copy>(
+        &self,
+        src: *const T,
+        dst: *mut T,
+    ) -> AlienResult<()> {
         let size = core::mem::size_of::<T>();
-        let bufs = self.transfer_buf_raw(src as usize, size);
+        let bufs = self.transfer_buf_raw(src as usize, size, false)?;
         let dst = unsafe { core::slice::from_raw_parts_mut(dst as *mut u8, size) };
         let mut start = 0;
         for buffer in bufs {
@@ -57,16 +71,17 @@ impl dyn KTaskShim {
             }
             start += len;
         }
+        Ok(())
     }
-    fn transfer_ptr_mut<T>(&self, ptr: *mut T) -> &'static mut T {
+    fn transfer_ptr_mut<T>(&self, ptr: *mut T) -> AlienResult<&'static mut T> {
         let ptr = ptr as usize;
-        let ptr = self.transfer_ptr_raw(ptr);
-        unsafe { &mut *(ptr as *mut T) }
+        let ptr = self.transfer_ptr_raw(ptr, true)?;
+        Ok(unsafe { &mut *(ptr as *mut T) })
     }
-    fn transfer_ptr<T>(&self, ptr: *const T) -> &'static T {
+    fn transfer_ptr<T>(&self, ptr: *const T) -> AlienResult<&'static T> {
         let ptr = ptr as usize;
-        let ptr = self.transfer_ptr_raw(ptr);
-        unsafe { &*(ptr as *const T) }
+        let ptr = self.transfer_ptr_raw(ptr, false)?;
+        Ok(unsafe { &*(ptr as *const T) })
     }
 }
 
@@ -102,28 +117,28 @@ pub fn suspend() {
         .suspend();
 }
 #[cfg(feature = "lib")]
-pub fn copy_data_to_task<T: 'static + Copy>(src: *const T, dst: *mut T) {
+pub fn copy_data_to_task<T: 'static + Copy>(src: *const T, dst: *mut T) -> AlienResult<()> {
     KTASK_SHIM
         .get()
         .expect("ktask_shim not initialized")
-        .copy_data_to_task(src, dst);
+        .copy_data_to_task(src, dst)
 }
 #[cfg(feature = "lib")]
-pub fn copy_data_from_task<T: 'static + Copy>(src: *const T, dst: *mut T) {
+pub fn copy_data_from_task<T: 'static + Copy>(src: *const T, dst: *mut T) -> AlienResult<()> {
     KTASK_SHIM
         .get()
         .expect("ktask_shim not initialized")
-        .copy_data_from_task(src, dst);
+        .copy_data_from_task(src, dst)
 }
 #[cfg(feature = "lib")]
-pub fn transfer_ptr_mut<T>(ptr: *mut T) -> &'static mut T {
+pub fn transfer_ptr_mut<T>(ptr: *mut T) -> AlienResult<&'static mut T> {
     KTASK_SHIM
         .get()
         .expect("ktask_shim not initialized")
         .transfer_ptr_mut(ptr)
 }
 #[cfg(feature = "lib")]
-pub fn transfer_ptr<T>(ptr: *const T) -> &'static T {
+pub fn transfer_ptr<T>(ptr: *const T) -> AlienResult<&'static T> {
     KTASK_SHIM
         .get()
         .expect("ktask_shim not initialized")
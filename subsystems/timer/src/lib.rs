@@ -1,34 +1,12 @@
 #![no_std]
 
 use constants::sys::TimeVal;
+/// 程序运行时间，与用户态共用 `abi` 中的定义
+pub use constants::sys::Times;
 use platform::config::CLOCK_FREQ;
 use vfscore::utils::VfsTimeSpec;
 /// 每秒包含的毫秒数
 const MSEC_PER_SEC: usize = 1000;
-/// 程序运行时间
-#[repr(C)]
-#[derive(Debug, Copy, Clone)]
-pub struct Times {
-    /// the ticks of user mode
-    pub tms_utime: usize,
-    /// the ticks of kernel mode
-    pub tms_stime: usize,
-    /// the ticks of user mode of child process
-    pub tms_cutime: usize,
-    /// the ticks of kernel mode of child process
-    pub tms_cstime: usize,
-}
-
-impl Times {
-    pub fn new() -> Self {
-        Self {
-            tms_utime: 0,
-            tms_stime: 0,
-            tms_cutime: 0,
-            tms_cstime: 0,
-        }
-    }
-}
 
 /// 实现 `TimeNow` 特征的时钟结构，能够通过调用 `now` 方法得出 表示当前的 cpu 时间的一个本类型时钟
 pub trait TimeNow {
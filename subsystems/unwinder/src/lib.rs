@@ -2,3 +2,7 @@
 #![feature(panic_info_message)]
 mod panic;
 mod symbol;
+mod trace;
+
+pub use panic::{back_trace, register_panic_hook};
+pub use trace::{back_trace_from, print_stack};
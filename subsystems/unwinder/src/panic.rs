@@ -4,6 +4,7 @@ use crate::symbol::find_symbol_with_addr;
 use core::panic::PanicInfo;
 use core::sync::atomic::AtomicBool;
 use platform::{println, system_shutdown};
+use spin::Once;
 #[cfg(all(not(feature = "debug-eh-frame"), not(feature = "debug-frame-point")))]
 use tracer::CompilerTracer;
 #[cfg(feature = "debug-eh-frame")]
@@ -15,9 +16,17 @@ use tracer::{Tracer, TracerProvider};
 /// 递归标志
 static RECURSION: AtomicBool = AtomicBool::new(false);
 
+/// panic 时调用的钩子，如进入内核调试器
+static PANIC_HOOK: Once<fn(&PanicInfo)> = Once::new();
+
+/// 注册 panic 钩子，钩子在打印堆栈回溯之后、关机之前被调用
+pub fn register_panic_hook(hook: fn(&PanicInfo)) {
+    PANIC_HOOK.call_once(|| hook);
+}
+
 /// 错误处理
 ///
-/// 发生 panic 是进行结果处理.目前我们会读取符号表信息，进行堆栈回溯
+/// 发生 panic 是进行结果处理.目前我们会读取符号表信息，进行堆栈回溯，然后调用注册的 panic 钩子
 #[panic_handler]
 fn panic_handler(info: &PanicInfo) -> ! {
     if let Some(p) = info.location() {
@@ -32,6 +41,9 @@ fn panic_handler(info: &PanicInfo) -> ! {
     }
     if !RECURSION.swap(true, core::sync::atomic::Ordering::SeqCst) {
         back_trace();
+        if let Some(hook) = PANIC_HOOK.get() {
+            hook(info);
+        }
     }
     println!("!TEST FINISH!");
     system_shutdown();
@@ -75,8 +87,8 @@ impl DwarfProvider for DwarfProviderImpl {
     }
 }
 
-/// 打印堆栈回溯信息
-fn back_trace() {
+/// 打印当前调用栈的堆栈回溯信息
+pub fn back_trace() {
     println!("---START BACKTRACE---");
     #[cfg(all(not(feature = "debug-eh-frame"), not(feature = "debug-frame-point")))]
     let tracer = CompilerTracer::new(TracerProviderImpl);
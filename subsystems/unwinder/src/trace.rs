@@ -0,0 +1,47 @@
+//! 回溯已经让出 CPU 的任务的内核栈，以及打印其它模块记录下来的调用栈
+
+use crate::symbol::find_symbol_with_addr;
+use core::ops::Range;
+use platform::println;
+
+/// 最多回溯的栈帧数，避免栈被破坏时陷入循环
+const MAX_DEPTH: usize = 64;
+
+/// 从任务切换时保存的返回地址 `ra` 与帧指针 `fp` 开始，打印位于 `stack` 中的调用栈
+///
+/// 按照 RISC-V 的帧指针约定，`fp - 8` 处保存返回地址，`fp - 16` 处保存上一帧的帧指针。
+/// 帧指针离开 `stack` 或者没有向栈底增长时停止回溯，因此内核没有保留帧指针时只会打印 `ra` 所在的函数。
+pub fn back_trace_from(ra: usize, fp: usize, stack: Range<usize>) {
+    println!("---START BACKTRACE---");
+    print_frame(ra);
+    let mut fp = fp;
+    for _ in 0..MAX_DEPTH {
+        if fp % 8 != 0 || fp < stack.start + 16 || fp > stack.end {
+            break;
+        }
+        let (ra, prev) = unsafe { (*((fp - 8) as *const usize), *((fp - 16) as *const usize)) };
+        if ra == 0 {
+            break;
+        }
+        print_frame(ra);
+        if prev <= fp {
+            break;
+        }
+        fp = prev;
+    }
+    println!("---END   BACKTRACE---");
+}
+
+/// 打印已经记录下来的调用栈，`frames` 为各栈帧的返回地址
+pub fn print_stack(frames: &[usize]) {
+    println!("---START BACKTRACE---");
+    frames.iter().for_each(|&ra| print_frame(ra));
+    println!("---END   BACKTRACE---");
+}
+
+fn print_frame(addr: usize) {
+    match find_symbol_with_addr(addr) {
+        Some((func, name)) => println!("[{:#x}] (+{:0>4x}) {}", func, addr - func, name),
+        None => println!("[{:#x}] <unknown>", addr),
+    }
+}
@@ -0,0 +1,631 @@
+//! 回环设备(/dev/loopN)
+//!
+//! 回环设备将一个普通文件包装为块设备，从而可以像挂载磁盘一样挂载文件系统镜像。
+//! 设备与文件的绑定关系通过 `LOOP_SET_FD`/`LOOP_CLR_FD`/`LOOP_SET_STATUS64` 等 ioctl 修改。
+//!
+//! 绑定后，后备文件由 [`LoopBackend`] 适配为 [`LowBlockDevice`]，设备的读写都按扇区经过它完成，
+//! 与磁盘驱动一样只提供整块的读写，挂载在 /dev/loopN 上的文件系统因此和挂载在磁盘上时走同一条块设备路径。
+use crate::kfile::{File, KernelFile};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use constants::{AlienResult, DeviceId, LinuxErrno};
+use core::cmp::min;
+use core::sync::atomic::{AtomicUsize, Ordering};
+use device_interface::LowBlockDevice;
+use ksync::Mutex;
+use spin::Lazy;
+use vfscore::error::VfsError;
+use vfscore::file::VfsFile;
+use vfscore::inode::{InodeAttr, VfsInode};
+use vfscore::superblock::VfsSuperBlock;
+use vfscore::utils::{VfsFileStat, VfsNodePerm, VfsNodeType};
+use vfscore::VfsResult;
+
+pub const LOOP_SET_FD: u32 = 0x4C00;
+pub const LOOP_CLR_FD: u32 = 0x4C01;
+pub const LOOP_SET_STATUS64: u32 = 0x4C04;
+pub const LOOP_GET_STATUS64: u32 = 0x4C05;
+pub const LOOP_CONFIGURE: u32 = 0x4C0A;
+
+/// 设备只读
+pub const LO_FLAGS_READ_ONLY: u32 = 1;
+/// 最后一次关闭时自动解除绑定
+pub const LO_FLAGS_AUTOCLEAR: u32 = 4;
+/// 扫描分区表
+pub const LO_FLAGS_PARTSCAN: u32 = 8;
+
+/// 系统启动时创建的回环设备数量
+pub const MAX_LOOP: usize = 8;
+
+const LO_NAME_SIZE: usize = 64;
+const LO_KEY_SIZE: usize = 32;
+const SECTOR_SIZE: usize = 512;
+
+/// `LOOP_GET_STATUS64`/`LOOP_SET_STATUS64` 使用的 `struct loop_info64`
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct LoopInfo64 {
+    pub lo_device: u64,
+    pub lo_inode: u64,
+    pub lo_rdevice: u64,
+    pub lo_offset: u64,
+    pub lo_sizelimit: u64,
+    pub lo_number: u32,
+    pub lo_encrypt_type: u32,
+    pub lo_encrypt_key_size: u32,
+    pub lo_flags: u32,
+    pub lo_file_name: [u8; LO_NAME_SIZE],
+    pub lo_crypt_name: [u8; LO_NAME_SIZE],
+    pub lo_encrypt_key: [u8; LO_KEY_SIZE],
+    pub lo_init: [u64; 2],
+}
+
+impl Default for LoopInfo64 {
+    fn default() -> Self {
+        Self {
+            lo_device: 0,
+            lo_inode: 0,
+            lo_rdevice: 0,
+            lo_offset: 0,
+            lo_sizelimit: 0,
+            lo_number: 0,
+            lo_encrypt_type: 0,
+            lo_encrypt_key_size: 0,
+            lo_flags: 0,
+            lo_file_name: [0; LO_NAME_SIZE],
+            lo_crypt_name: [0; LO_NAME_SIZE],
+            lo_encrypt_key: [0; LO_KEY_SIZE],
+            lo_init: [0; 2],
+        }
+    }
+}
+
+/// `LOOP_CONFIGURE` 使用的 `struct loop_config`
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Default)]
+pub struct LoopConfig {
+    pub fd: u32,
+    pub block_size: u32,
+    pub info: LoopInfo64,
+    pub reserved: [u64; 8],
+}
+
+/// 将后备文件中 `[offset, offset + blocks * SECTOR_SIZE)` 范围内的数据按扇区划分为块的块设备
+struct LoopBackend {
+    file: Arc<KernelFile>,
+    offset: u64,
+    blocks: usize,
+}
+
+impl LoopBackend {
+    /// 检查 `[block_id, block_id + len / SECTOR_SIZE)` 是否在设备范围内，返回对应的文件偏移
+    ///
+    /// `len` 可以包含多个连续的块，但必须是扇区大小的整数倍。
+    fn position(&self, block_id: usize, len: usize) -> AlienResult<u64> {
+        if len % SECTOR_SIZE != 0 {
+            return Err(LinuxErrno::EINVAL);
+        }
+        let end = block_id
+            .checked_add(len / SECTOR_SIZE)
+            .ok_or(LinuxErrno::EIO)?;
+        if end > self.blocks {
+            return Err(LinuxErrno::EIO);
+        }
+        Ok(self.offset + (block_id * SECTOR_SIZE) as u64)
+    }
+}
+
+/// 后备文件的读写错误都以 `EIO` 返回给设备的使用者
+impl LowBlockDevice for LoopBackend {
+    fn read_block(&mut self, block_id: usize, buf: &mut [u8]) -> AlienResult<()> {
+        let pos = self.position(block_id, buf.len())?;
+        let mut count = 0;
+        while count < buf.len() {
+            let read = self
+                .file
+                .read_at(pos + count as u64, &mut buf[count..])
+                .map_err(|_| LinuxErrno::EIO)?;
+            if read == 0 {
+                break;
+            }
+            count += read;
+        }
+        // 后备文件被截断时，超出文件末尾的部分读出 0
+        buf[count..].fill(0);
+        Ok(())
+    }
+    fn write_block(&mut self, block_id: usize, buf: &[u8]) -> AlienResult<()> {
+        let pos = self.position(block_id, buf.len())?;
+        let mut count = 0;
+        while count < buf.len() {
+            let write = self
+                .file
+                .write_at(pos + count as u64, &buf[count..])
+                .map_err(|_| LinuxErrno::EIO)?;
+            if write == 0 {
+                return Err(LinuxErrno::EIO);
+            }
+            count += write;
+        }
+        Ok(())
+    }
+    fn capacity(&self) -> usize {
+        self.blocks
+    }
+    fn flush(&mut self) {
+        let _ = self.file.flush();
+    }
+}
+
+struct LoopBinding {
+    backend: LoopBackend,
+    info: LoopInfo64,
+}
+
+impl LoopBinding {
+    /// 设备的大小，即文件中 `[lo_offset, lo_offset + size)` 范围内的数据
+    fn size(&self) -> u64 {
+        (self.backend.capacity() * SECTOR_SIZE) as u64
+    }
+}
+
+pub struct LoopDevice {
+    device_id: DeviceId,
+    number: u32,
+    binding: Mutex<Option<LoopBinding>>,
+    /// 打开设备的文件数量，用于实现 `LO_FLAGS_AUTOCLEAR`
+    openers: AtomicUsize,
+}
+
+impl LoopDevice {
+    pub fn new(device_id: DeviceId, number: u32) -> Self {
+        Self {
+            device_id,
+            number,
+            binding: Mutex::new(None),
+            openers: AtomicUsize::new(0),
+        }
+    }
+    pub fn device_id(&self) -> DeviceId {
+        self.device_id
+    }
+    pub fn number(&self) -> u32 {
+        self.number
+    }
+    pub fn is_bound(&self) -> bool {
+        self.binding.lock().is_some()
+    }
+
+    /// 将设备绑定到文件 `file` 上(LOOP_SET_FD)
+    pub fn set_fd(&self, file: Arc<dyn File>) -> AlienResult<()> {
+        self.configure(file, &LoopInfo64::default())
+    }
+
+    /// 将设备绑定到文件 `file` 上，同时设置偏移、大小限制等参数(LOOP_CONFIGURE)
+    ///
+    /// 与 Linux 一致，只能绑定可读的普通文件或块设备，且不能绑定到设备自身。
+    pub fn configure(&self, file: Arc<dyn File>, info: &LoopInfo64) -> AlienResult<()> {
+        let file = file
+            .downcast_arc::<KernelFile>()
+            .map_err(|_| LinuxErrno::EINVAL)?;
+        if !file.is_readable() {
+            return Err(LinuxErrno::EBADF);
+        }
+        let inode = file.inode();
+        match inode.inode_type() {
+            VfsNodeType::File => {}
+            VfsNodeType::BlockDevice if inode.get_attr()?.st_rdev != self.device_id.id() => {}
+            _ => return Err(LinuxErrno::EINVAL),
+        }
+        let mut binding = self.binding.lock();
+        if binding.is_some() {
+            return Err(LinuxErrno::EBUSY);
+        }
+        let stat = file.get_attr()?;
+        let mut new_info = *info;
+        new_info.lo_device = stat.st_dev;
+        new_info.lo_inode = stat.st_ino;
+        new_info.lo_rdevice = stat.st_rdev;
+        new_info.lo_number = self.number;
+        if !file.is_writable() {
+            new_info.lo_flags |= LO_FLAGS_READ_ONLY;
+        }
+        if new_info.lo_file_name[0] == 0 {
+            let path = file.dentry().path();
+            let len = min(path.len(), LO_NAME_SIZE - 1);
+            new_info.lo_file_name[..len].copy_from_slice(&path.as_bytes()[..len]);
+        }
+        *binding = Some(Self::bind(file, new_info)?);
+        Ok(())
+    }
+
+    /// 解除设备与文件的绑定(LOOP_CLR_FD)，解除前会将数据写回文件
+    ///
+    /// 设备被挂载时返回 `EBUSY`。
+    pub fn clear_fd(&self) -> AlienResult<()> {
+        if crate::mount::device_mounted(self.device_id.id()) {
+            return Err(LinuxErrno::EBUSY);
+        }
+        let binding = self.binding.lock().take().ok_or(LinuxErrno::ENXIO)?;
+        binding.backend.file.flush()
+    }
+
+    /// 获取设备的状态(LOOP_GET_STATUS64)
+    pub fn status(&self) -> AlienResult<LoopInfo64> {
+        self.binding
+            .lock()
+            .as_ref()
+            .map(|binding| binding.info)
+            .ok_or(LinuxErrno::ENXIO)
+    }
+
+    /// 修改设备的偏移、大小限制和标志(LOOP_SET_STATUS64)
+    ///
+    /// 与 Linux 一致，只读标志不能通过该命令修改。
+    pub fn set_status(&self, info: &LoopInfo64) -> AlienResult<()> {
+        let mut binding = self.binding.lock();
+        let old = binding.as_ref().ok_or(LinuxErrno::ENXIO)?;
+        old.backend.file.flush()?;
+        let mut new_info = old.info;
+        new_info.lo_offset = info.lo_offset;
+        new_info.lo_sizelimit = info.lo_sizelimit;
+        let settable = LO_FLAGS_AUTOCLEAR | LO_FLAGS_PARTSCAN;
+        new_info.lo_flags = (old.info.lo_flags & !settable) | (info.lo_flags & settable);
+        new_info.lo_file_name = info.lo_file_name;
+        new_info.lo_file_name[LO_NAME_SIZE - 1] = 0;
+        let file = old.backend.file.clone();
+        *binding = Some(Self::bind(file, new_info)?);
+        Ok(())
+    }
+
+    /// 设备被打开时调用
+    pub fn open(&self) {
+        self.openers.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// 打开设备的文件被关闭时调用，最后一个文件关闭后尝试自动解除绑定
+    pub fn release(&self) {
+        if self.openers.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.autoclear();
+        }
+    }
+
+    /// 设置了 `LO_FLAGS_AUTOCLEAR` 的设备在没有被打开也没有被挂载时自动解除绑定，
+    /// 在关闭设备文件和卸载文件系统后调用
+    pub fn autoclear(&self) {
+        let autoclear = self
+            .binding
+            .lock()
+            .as_ref()
+            .map(|binding| binding.info.lo_flags & LO_FLAGS_AUTOCLEAR != 0)
+            .unwrap_or(false);
+        if autoclear && self.openers.load(Ordering::SeqCst) == 0 {
+            let _ = self.clear_fd();
+        }
+    }
+
+    fn bind(file: Arc<KernelFile>, info: LoopInfo64) -> AlienResult<LoopBinding> {
+        let file_size = file.get_attr()?.st_size;
+        if info.lo_offset > file_size {
+            return Err(LinuxErrno::EINVAL);
+        }
+        let mut size = file_size - info.lo_offset;
+        if info.lo_sizelimit != 0 {
+            size = min(size, info.lo_sizelimit);
+        }
+        // 与块设备一致，大小按扇区向下取整
+        let backend = LoopBackend {
+            file,
+            offset: info.lo_offset,
+            blocks: (size / SECTOR_SIZE as u64) as usize,
+        };
+        Ok(LoopBinding { backend, info })
+    }
+}
+
+/// 按字节的读写被拆分为对 [`LoopBackend`] 的整块读写，不足一个扇区的部分先读出整个扇区再修改
+impl VfsFile for LoopDevice {
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> VfsResult<usize> {
+        let mut binding = self.binding.lock();
+        let binding = binding.as_mut().ok_or(VfsError::IoError)?;
+        let size = binding.size();
+        if offset >= size {
+            return Ok(0);
+        }
+        let len = min(buf.len() as u64, size - offset) as usize;
+        let backend = &mut binding.backend;
+        let mut sector = [0u8; SECTOR_SIZE];
+        let mut count = 0;
+        while count < len {
+            let pos = offset + count as u64;
+            let block = (pos / SECTOR_SIZE as u64) as usize;
+            let start = (pos % SECTOR_SIZE as u64) as usize;
+            let n = if start == 0 && len - count >= SECTOR_SIZE {
+                // 对齐的整扇区直接读入 buf
+                let n = (len - count) / SECTOR_SIZE * SECTOR_SIZE;
+                backend
+                    .read_block(block, &mut buf[count..count + n])
+                    .map_err(|_| VfsError::IoError)?;
+                n
+            } else {
+                let n = min(SECTOR_SIZE - start, len - count);
+                backend
+                    .read_block(block, &mut sector)
+                    .map_err(|_| VfsError::IoError)?;
+                buf[count..count + n].copy_from_slice(&sector[start..start + n]);
+                n
+            };
+            count += n;
+        }
+        Ok(len)
+    }
+    fn write_at(&self, offset: u64, buf: &[u8]) -> VfsResult<usize> {
+        let mut binding = self.binding.lock();
+        let binding = binding.as_mut().ok_or(VfsError::IoError)?;
+        if binding.info.lo_flags & LO_FLAGS_READ_ONLY != 0 {
+            return Err(VfsError::PermissionDenied);
+        }
+        let size = binding.size();
+        if offset >= size {
+            return Err(VfsError::IoError);
+        }
+        let len = min(buf.len() as u64, size - offset) as usize;
+        let backend = &mut binding.backend;
+        let mut sector = [0u8; SECTOR_SIZE];
+        let mut count = 0;
+        while count < len {
+            let pos = offset + count as u64;
+            let block = (pos / SECTOR_SIZE as u64) as usize;
+            let start = (pos % SECTOR_SIZE as u64) as usize;
+            let n = if start == 0 && len - count >= SECTOR_SIZE {
+                let n = (len - count) / SECTOR_SIZE * SECTOR_SIZE;
+                backend
+                    .write_block(block, &buf[count..count + n])
+                    .map_err(|_| VfsError::IoError)?;
+                n
+            } else {
+                let n = min(SECTOR_SIZE - start, len - count);
+                backend
+                    .read_block(block, &mut sector)
+                    .map_err(|_| VfsError::IoError)?;
+                sector[start..start + n].copy_from_slice(&buf[count..count + n]);
+                backend
+                    .write_block(block, &sector)
+                    .map_err(|_| VfsError::IoError)?;
+                n
+            };
+            count += n;
+        }
+        Ok(len)
+    }
+    fn flush(&self) -> VfsResult<()> {
+        match self.binding.lock().as_ref() {
+            Some(binding) => binding.backend.file.flush().map_err(|_| VfsError::IoError),
+            None => Ok(()),
+        }
+    }
+    fn fsync(&self) -> VfsResult<()> {
+        self.flush()
+    }
+}
+
+impl VfsInode for LoopDevice {
+    fn get_super_block(&self) -> VfsResult<Arc<dyn VfsSuperBlock>> {
+        Err(VfsError::NoSys)
+    }
+    fn node_perm(&self) -> VfsNodePerm {
+        VfsNodePerm::empty()
+    }
+    fn set_attr(&self, _attr: InodeAttr) -> VfsResult<()> {
+        Ok(())
+    }
+    fn get_attr(&self) -> VfsResult<VfsFileStat> {
+        let size = self
+            .binding
+            .lock()
+            .as_ref()
+            .map(|binding| binding.size())
+            .unwrap_or(0);
+        Ok(VfsFileStat {
+            st_rdev: self.device_id.id(),
+            st_size: size,
+            st_blksize: 512,
+            ..Default::default()
+        })
+    }
+    fn inode_type(&self) -> VfsNodeType {
+        VfsNodeType::BlockDevice
+    }
+}
+
+static LOOP_DEVICES: Lazy<Mutex<Vec<Arc<LoopDevice>>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+/// 在 devfs 中创建 loop0 ~ loop{MAX_LOOP-1}
+pub fn init_loop_devices(root: &Arc<dyn VfsInode>) {
+    for number in 0..MAX_LOOP {
+        let device = Arc::new(LoopDevice::new(
+            super::alloc_device_id(VfsNodeType::BlockDevice),
+            number as u32,
+        ));
+        root.create(
+            &alloc::format!("loop{}", number),
+            VfsNodeType::BlockDevice,
+            "rw-rw----".into(),
+            Some(device.device_id().id()),
+        )
+        .unwrap();
+        super::register_device(device.clone());
+        LOOP_DEVICES.lock().push(device);
+    }
+}
+
+/// 如果 `inode` 是回环设备，返回对应的设备
+pub fn loop_device_of(inode: &Arc<dyn VfsInode>) -> Option<Arc<LoopDevice>> {
+    if inode.inode_type() != VfsNodeType::BlockDevice {
+        return None;
+    }
+    loop_device(inode.get_attr().ok()?.st_rdev)
+}
+
+/// 根据设备号查找回环设备
+pub fn loop_device(rdev: u64) -> Option<Arc<LoopDevice>> {
+    let device_id = DeviceId::from(rdev);
+    LOOP_DEVICES
+        .lock()
+        .iter()
+        .find(|device| device.device_id() == device_id)
+        .cloned()
+}
+
+#[cfg(feature = "ktest")]
+mod ktests {
+    use super::*;
+    use crate::mount::{record_mount, remove_mount, MountEntry};
+    use alloc::string::String;
+    use constants::io::{MountFlags, OpenFlags};
+    use ktest::{kassert, kassert_eq, kernel_test, TestResult};
+    use vfscore::path::VfsPath;
+    use vfscore::utils::VfsInodeMode;
+
+    /// 在 /tmp 下创建一个内容为 `data` 的文件并打开
+    fn backing_file(name: &str, data: &[u8]) -> AlienResult<Arc<dyn File>> {
+        let root = crate::system_root_fs();
+        let path = VfsPath::new(root.clone(), root).join(name)?;
+        let dentry = path.open(Some(VfsInodeMode::from_bits_truncate(0o100644)))?;
+        dentry.inode()?.write_at(0, data)?;
+        Ok(Arc::new(KernelFile::new(dentry, OpenFlags::O_RDWR)))
+    }
+
+    fn test_device() -> LoopDevice {
+        LoopDevice::new(super::super::alloc_device_id(VfsNodeType::BlockDevice), 0)
+    }
+
+    #[kernel_test]
+    fn test_loop_read_write() -> TestResult {
+        let mut data = [0u8; 2 * SECTOR_SIZE];
+        data[SECTOR_SIZE..].fill(0xaa);
+        let file = backing_file("tmp/ktest_loop_rw", &data)?;
+        let device = test_device();
+        device.set_fd(file.clone())?;
+        kassert_eq!(device.get_attr()?.st_size, data.len() as u64);
+        kassert_eq!(device.set_fd(file.clone()), Err(LinuxErrno::EBUSY));
+        let mut info = device.status()?;
+        info.lo_offset = SECTOR_SIZE as u64;
+        device.set_status(&info)?;
+        let mut buf = [0; 4];
+        kassert_eq!(device.read_at(0, &mut buf)?, 4);
+        kassert_eq!(buf, [0xaa; 4]);
+        device.write_at(0, b"loop")?;
+        let mut buf = [0; 4];
+        file.read_at(SECTOR_SIZE as u64, &mut buf)?;
+        kassert_eq!(&buf, b"loop");
+        device.clear_fd()?;
+        kassert!(!device.is_bound());
+        kassert_eq!(device.clear_fd(), Err(LinuxErrno::ENXIO));
+        Ok(())
+    }
+
+    #[kernel_test]
+    fn test_loop_invalid_backing_file() -> TestResult {
+        let root = crate::system_root_fs();
+        let dir = VfsPath::new(root.clone(), root).join("tmp")?.open(None)?;
+        let device = test_device();
+        let dir_file: Arc<dyn File> = Arc::new(KernelFile::new(dir, OpenFlags::O_RDONLY));
+        kassert_eq!(device.set_fd(dir_file), Err(LinuxErrno::EINVAL));
+        let file = backing_file("tmp/ktest_loop_wronly", &[0; SECTOR_SIZE])?;
+        let write_only = Arc::new(KernelFile::new(file.dentry(), OpenFlags::O_WRONLY));
+        kassert_eq!(device.set_fd(write_only), Err(LinuxErrno::EBADF));
+        kassert!(!device.is_bound());
+        Ok(())
+    }
+
+    /// 构造一个 64KiB 的 FAT12 镜像，根目录下只有一个内容为 `hello` 的 HELLO.TXT
+    #[cfg(feature = "fat")]
+    fn fat12_image() -> Vec<u8> {
+        let mut image = alloc::vec![0u8; 128 * SECTOR_SIZE];
+        let boot = &mut image[..SECTOR_SIZE];
+        boot[..3].copy_from_slice(&[0xeb, 0x3c, 0x90]);
+        boot[3..11].copy_from_slice(b"MSWIN4.1");
+        boot[11..13].copy_from_slice(&(SECTOR_SIZE as u16).to_le_bytes());
+        // 每簇 1 个扇区，1 个保留扇区，2 个 FAT，根目录 16 项，共 128 个扇区，每个 FAT 1 个扇区
+        boot[13] = 1;
+        boot[14..16].copy_from_slice(&1u16.to_le_bytes());
+        boot[16] = 2;
+        boot[17..19].copy_from_slice(&16u16.to_le_bytes());
+        boot[19..21].copy_from_slice(&128u16.to_le_bytes());
+        boot[21] = 0xf8;
+        boot[22..24].copy_from_slice(&1u16.to_le_bytes());
+        boot[24..26].copy_from_slice(&32u16.to_le_bytes());
+        boot[26..28].copy_from_slice(&2u16.to_le_bytes());
+        boot[36] = 0x80;
+        boot[38] = 0x29;
+        boot[39..43].copy_from_slice(&0x1234_5678u32.to_le_bytes());
+        boot[43..54].copy_from_slice(b"NO NAME    ");
+        boot[54..62].copy_from_slice(b"FAT12   ");
+        boot[510..512].copy_from_slice(&[0x55, 0xaa]);
+        // 簇 0、1 保留，簇 2 是文件唯一的簇
+        let fat = [0xf8, 0xff, 0xff, 0xff, 0x0f];
+        image[SECTOR_SIZE..SECTOR_SIZE + fat.len()].copy_from_slice(&fat);
+        image[2 * SECTOR_SIZE..2 * SECTOR_SIZE + fat.len()].copy_from_slice(&fat);
+        let entry = &mut image[3 * SECTOR_SIZE..3 * SECTOR_SIZE + 32];
+        entry[..11].copy_from_slice(b"HELLO   TXT");
+        entry[11] = 0x20;
+        entry[26..28].copy_from_slice(&2u16.to_le_bytes());
+        entry[28..32].copy_from_slice(&5u32.to_le_bytes());
+        image[4 * SECTOR_SIZE..4 * SECTOR_SIZE + 5].copy_from_slice(b"hello");
+        image
+    }
+
+    #[cfg(feature = "fat")]
+    #[kernel_test]
+    fn test_loop_mount_fat_image() -> TestResult {
+        let file = backing_file("tmp/ktest_loop_fat.img", &fat12_image())?;
+        let device = LOOP_DEVICES.lock().last().cloned().unwrap();
+        device.set_fd(file)?;
+        let root = crate::system_root_fs();
+        let dev = VfsPath::new(root.clone(), root)
+            .join(&alloc::format!("dev/loop{}", device.number()))?
+            .open(None)?
+            .inode()?;
+        kassert_eq!(crate::probe::probe_fs_type(&dev), Some("vfat"));
+        let fs = crate::system_support_fs("vfat").unwrap();
+        let fs_root = fs.i_mount(0, "/ktest_loop_fat", Some(dev), &[])?;
+        let hello = VfsPath::new(fs_root.clone(), fs_root)
+            .join("HELLO.TXT")?
+            .open(None)?;
+        let mut buf = [0u8; 8];
+        let len = hello.inode()?.read_at(0, &mut buf)?;
+        kassert_eq!(&buf[..len], b"hello");
+        device.clear_fd()?;
+        Ok(())
+    }
+
+    #[kernel_test]
+    fn test_loop_busy_and_autoclear() -> TestResult {
+        let file = backing_file("tmp/ktest_loop_autoclear", &[0; SECTOR_SIZE])?;
+        let device = test_device();
+        device.set_fd(file.clone())?;
+        let target = "/ktest_loop_mount";
+        record_mount(MountEntry {
+            source: String::from("/dev/loop"),
+            target: String::from(target),
+            fs_type: String::from("none"),
+            flags: MountFlags::empty(),
+            options: String::new(),
+            root: file.dentry(),
+            rdev: Some(device.device_id().id()),
+        });
+        kassert_eq!(device.clear_fd(), Err(LinuxErrno::EBUSY));
+        let mut info = device.status()?;
+        info.lo_flags |= LO_FLAGS_AUTOCLEAR;
+        device.set_status(&info)?;
+        device.open();
+        remove_mount(target);
+        device.autoclear();
+        // 设备仍被打开，不会解除绑定
+        kassert!(device.is_bound());
+        device.release();
+        kassert!(!device.is_bound());
+        Ok(())
+    }
+}
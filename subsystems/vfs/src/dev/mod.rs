@@ -1,13 +1,17 @@
 use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::String;
 use alloc::sync::Arc;
+use alloc::vec::Vec;
 use constants::DeviceId;
 use devfs::DevKernelProvider;
 use devices::{
-    BLKDevice, GPUDevice, INPUTDevice, RTCDevice, UARTDevice, BLOCK_DEVICE, GPU_DEVICE,
+    block_devices, BLKDevice, GPUDevice, INPUTDevice, RTCDevice, UARTDevice, GPU_DEVICE,
     KEYBOARD_INPUT_DEVICE, MOUSE_INPUT_DEVICE, RTC_DEVICE, UART_DEVICE,
 };
 use ksync::Mutex;
 use log::info;
+pub use loopdev::*;
 use null::NullDevice;
 use random::RandomDevice;
 use spin::Lazy;
@@ -16,6 +20,7 @@ use vfscore::fstype::VfsFsType;
 use vfscore::inode::VfsInode;
 use vfscore::utils::{VfsNodeType, VfsTimeSpec};
 
+mod loopdev;
 mod null;
 mod random;
 
@@ -93,6 +98,7 @@ impl DevKernelProvider for DevFsProviderImpl {
 /// |-- random
 /// |-- urandom
 /// |-- tty
+/// |-- loop0 ~ loop7
 /// |-- shm (a ramfs will be mounted here)
 /// |-- misc
 ///    |-- rtc
@@ -151,28 +157,54 @@ pub fn init_devfs(devfs: Arc<dyn VfsFsType>) -> Arc<dyn VfsDentry> {
         .create("misc", VfsNodeType::Dir, "rwxrwxrwx".into(), None)
         .unwrap();
 
+    init_loop_devices(&root_inode);
     scan_system_devices(root_inode);
     // todo!(tty,shm,misc)
     println!("devfs init success");
     root
 }
 
+/// 系统中块设备的数量
+pub fn block_device_count() -> usize {
+    block_devices().len()
+}
+
+/// 第 `index` 个块设备在 devfs 中的名称，与 Linux 相同依次为 sda, ..., sdz, sdaa, sdab, ...
+pub fn block_device_name(index: usize) -> String {
+    let mut suffix = Vec::new();
+    let mut n = index + 1;
+    while n > 0 {
+        n -= 1;
+        suffix.push((b'a' + (n % 26) as u8) as char);
+        n /= 26;
+    }
+    format!("sd{}", suffix.iter().rev().collect::<String>())
+}
+
 fn scan_system_devices(root: Arc<dyn VfsInode>) {
-    BLOCK_DEVICE.get().map(|blk| {
-        let block_device = Arc::new(BLKDevice::new(
-            alloc_device_id(VfsNodeType::BlockDevice),
-            blk.clone(),
-        ));
-        root.create(
-            "sda",
-            VfsNodeType::BlockDevice,
-            "rw-rw----".into(),
-            Some(block_device.device_id().id()),
-        )
-        .unwrap();
-        info!("block device id: {}", block_device.device_id().id());
-        register_device(block_device);
-    });
+    block_devices()
+        .into_iter()
+        .enumerate()
+        .for_each(|(index, blk)| {
+            let block_device = Arc::new(BLKDevice::new(
+                alloc_device_id(VfsNodeType::BlockDevice),
+                blk,
+            ));
+            let name = block_device_name(index);
+            root.create(
+                &name,
+                VfsNodeType::BlockDevice,
+                "rw-rw----".into(),
+                Some(block_device.device_id().id()),
+            )
+            .unwrap();
+            info!(
+                "block device {} id: {}",
+                name,
+                block_device.device_id().id()
+            );
+            register_device(block_device);
+        });
     GPU_DEVICE.get().map(|gpu| {
         let gpu_device = Arc::new(GPUDevice::new(
             alloc_device_id(VfsNodeType::CharDevice),
@@ -251,3 +283,20 @@ fn scan_system_devices(root: Arc<dyn VfsInode>) {
         register_device(uart_device);
     });
 }
+
+#[cfg(feature = "ktest")]
+mod ktests {
+    use super::*;
+    use ktest::{kassert_eq, kernel_test, TestResult};
+
+    #[kernel_test]
+    fn test_block_device_name() -> TestResult {
+        kassert_eq!(block_device_name(0), "sda");
+        kassert_eq!(block_device_name(25), "sdz");
+        kassert_eq!(block_device_name(26), "sdaa");
+        kassert_eq!(block_device_name(27), "sdab");
+        kassert_eq!(block_device_name(701), "sdzz");
+        kassert_eq!(block_device_name(702), "sdaaa");
+        Ok(())
+    }
+}
@@ -1,51 +1,354 @@
+//! initramfs 解压
+//!
+//! 支持 newc 格式(magic 为 `070701` 或 `070702`)的 cpio 归档，归档可以是未压缩的，也可以是 gzip 压缩的，
+//! 多个归档可以首尾相连。归档中的普通文件、目录、符号链接、硬链接、设备文件、管道以及
+//! 它们的权限、属主和修改时间都会被还原到根文件系统中。
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
 use alloc::sync::Arc;
 use alloc::vec;
-use constants::AlienResult;
+use constants::{AlienResult, DeviceId, LinuxErrno};
 use core2::io::Read;
-use cpio_reader::Mode;
 use vfscore::dentry::VfsDentry;
+use vfscore::inode::InodeAttr;
 use vfscore::path::VfsPath;
-use vfscore::utils::{VfsInodeMode, VfsNodeType};
-
-pub fn populate_initrd(root: Arc<dyn VfsDentry>) -> AlienResult<()> {
-    root.inode()?
-        .create("bin", VfsNodeType::Dir, "rwxr-xr-x".into(), None)?;
-    root.inode()?
-        .create("sbin", VfsNodeType::Dir, "rwxr-xr-x".into(), None)?;
-    parse_initrd_data(root)?;
-    println!("Initrd populate success");
-    Ok(())
-}
-fn parse_initrd_data(root: Arc<dyn VfsDentry>) -> AlienResult<()> {
+use vfscore::utils::{VfsInodeMode, VfsNodeType, VfsTimeSpec};
+
+const NEWC_MAGIC: &[u8] = b"070701";
+const NEWC_CRC_MAGIC: &[u8] = b"070702";
+const NEWC_HEADER_SIZE: usize = 110;
+const TRAILER: &str = "TRAILER!!!";
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+const S_IFMT: u32 = 0o170000;
+const S_IFSOCK: u32 = 0o140000;
+const S_IFLNK: u32 = 0o120000;
+const S_IFREG: u32 = 0o100000;
+const S_IFBLK: u32 = 0o060000;
+const S_IFDIR: u32 = 0o040000;
+const S_IFCHR: u32 = 0o020000;
+const S_IFIFO: u32 = 0o010000;
+
+/// 将 initramfs 解压到 `root` 中，返回是否存在 initramfs
+pub fn populate_initrd(root: Arc<dyn VfsDentry>) -> AlienResult<bool> {
     let mut guard = mem::data::INITRD_DATA.lock();
-    if guard.is_some() {
+    if let Some(data) = guard.as_ref() {
+        let data = unsafe { core::slice::from_raw_parts(data.data_ptr as *const u8, data.size) };
         let path = VfsPath::new(root.clone(), root.clone());
-        let data = guard.as_ref().unwrap();
-        let st = data.data_ptr;
-        let size = data.size;
-        let data = unsafe { core::slice::from_raw_parts(st as *const u8, size) };
-        let mut decoder = libflate::gzip::Decoder::new(data).unwrap();
-        let mut buf = vec![];
-        let _r = decoder.read_to_end(&mut buf).unwrap();
-        for entry in cpio_reader::iter_files(&buf) {
-            let mode = entry.mode();
-            let name = entry.name();
-            if name.starts_with("bin/") | name.starts_with("sbin/") {
-                let inode_mode = VfsInodeMode::from_bits_truncate(mode.bits());
-                if mode.contains(Mode::SYMBOLIK_LINK) {
-                    // create symlink
-                    let data = entry.file();
-                    let target = core::str::from_utf8(data).unwrap();
-                    path.join(name)?.symlink(target)?;
-                } else if mode.contains(Mode::REGULAR_FILE) {
-                    // create file
-                    let f = path.join(name)?.open(Some(inode_mode))?;
-                    f.inode()?.write_at(0, entry.file())?;
-                }
-            }
-        }
+        let mut hardlinks = BTreeMap::new();
+        let res = unpack_archives(&path, data, &mut hardlinks);
         // release the page frame
         guard.take();
+        res?;
+        println!("Initrd populate success");
+        return Ok(true);
+    }
+    Ok(false)
+}
+
+/// 一个 cpio 归档项
+struct CpioEntry<'a> {
+    ino: u32,
+    mode: u32,
+    uid: u32,
+    gid: u32,
+    nlink: u32,
+    mtime: u32,
+    dev: u64,
+    rdev: u64,
+    name: &'a str,
+    data: &'a [u8],
+}
+
+/// 解压 `data` 中所有首尾相连的归档，遇到 gzip 压缩的数据时逐个解压 gzip 成员，
+/// 成员之后可以是另一个成员，也可以是未压缩的归档
+fn unpack_archives(
+    path: &VfsPath,
+    mut data: &[u8],
+    hardlinks: &mut BTreeMap<(u64, u32), String>,
+) -> AlienResult<()> {
+    loop {
+        // 归档之间可能有用于对齐的 0 填充
+        let start = data.iter().position(|&b| b != 0).unwrap_or(data.len());
+        data = &data[start..];
+        if data.is_empty() {
+            return Ok(());
+        }
+        if data.starts_with(&GZIP_MAGIC) {
+            let mut buf = vec![];
+            // 解码器读完一个成员后停在成员的末尾，data 随之指向剩余的数据
+            libflate::gzip::Decoder::new(&mut data)
+                .and_then(|mut decoder| decoder.read_to_end(&mut buf))
+                .map_err(|_| LinuxErrno::EINVAL)?;
+            unpack_archives(path, &buf, hardlinks)?;
+            continue;
+        }
+        let consumed = unpack_archive(path, data, hardlinks)?;
+        data = &data[consumed..];
+    }
+}
+
+/// 解压一个以 TRAILER!!! 结尾的归档，返回归档占用的字节数
+fn unpack_archive(
+    path: &VfsPath,
+    data: &[u8],
+    hardlinks: &mut BTreeMap<(u64, u32), String>,
+) -> AlienResult<usize> {
+    let mut offset = 0;
+    loop {
+        let (entry, next) = parse_entry(data, offset)?;
+        offset = next;
+        if entry.name == TRAILER {
+            return Ok(offset);
+        }
+        create_entry(path, &entry, hardlinks)?;
+    }
+}
+
+fn parse_entry(data: &[u8], offset: usize) -> AlienResult<(CpioEntry, usize)> {
+    let header = data
+        .get(offset..offset + NEWC_HEADER_SIZE)
+        .ok_or(LinuxErrno::EINVAL)?;
+    if &header[..6] != NEWC_MAGIC && &header[..6] != NEWC_CRC_MAGIC {
+        return Err(LinuxErrno::EINVAL);
+    }
+    let field = |index: usize| parse_hex(&header[6 + index * 8..6 + (index + 1) * 8]);
+    let ino = field(0)?;
+    let mode = field(1)?;
+    let uid = field(2)?;
+    let gid = field(3)?;
+    let nlink = field(4)?;
+    let mtime = field(5)?;
+    let file_size = field(6)? as usize;
+    let dev = DeviceId::new(field(7)?, field(8)?).id();
+    let rdev = DeviceId::new(field(9)?, field(10)?).id();
+    let name_size = field(11)? as usize;
+
+    let name_start = offset + NEWC_HEADER_SIZE;
+    // name_size 包括结尾的 '\0'
+    let name = data
+        .get(name_start..name_start + name_size.saturating_sub(1))
+        .ok_or(LinuxErrno::EINVAL)?;
+    let name = core::str::from_utf8(name).map_err(|_| LinuxErrno::EINVAL)?;
+    let data_start = align4(name_start + name_size);
+    let file = data
+        .get(data_start..data_start + file_size)
+        .ok_or(LinuxErrno::EINVAL)?;
+    let entry = CpioEntry {
+        ino,
+        mode,
+        uid,
+        gid,
+        nlink,
+        mtime,
+        dev,
+        rdev,
+        name,
+        data: file,
+    };
+    Ok((entry, align4(data_start + file_size)))
+}
+
+fn create_entry(
+    path: &VfsPath,
+    entry: &CpioEntry,
+    hardlinks: &mut BTreeMap<(u64, u32), String>,
+) -> AlienResult<()> {
+    let name = entry.name.trim_start_matches("./").trim_start_matches('/');
+    if name.is_empty() || name == "." {
+        return Ok(());
+    }
+    create_parent_dirs(path, name)?;
+    let file_type = entry.mode & S_IFMT;
+    let inode_mode = VfsInodeMode::from_bits_truncate(entry.mode);
+    // 硬链接：newc 格式中同一 inode 的所有项里只有最后一项携带数据
+    if file_type == S_IFREG && entry.nlink > 1 {
+        let key = (entry.dev, entry.ino);
+        if let Some(target) = hardlinks.get(&key) {
+            let target = path.join(target)?.open(None)?;
+            path.join(name)?.link(target.clone())?;
+            if !entry.data.is_empty() {
+                target.inode()?.write_at(0, entry.data)?;
+            }
+            return Ok(());
+        }
+        hardlinks.insert(key, name.to_string());
+    }
+    let dentry = match file_type {
+        S_IFLNK => {
+            let target = core::str::from_utf8(entry.data).map_err(|_| LinuxErrno::EINVAL)?;
+            path.join(name)?.symlink(target)?;
+            // 不修改符号链接本身的属性
+            return Ok(());
+        }
+        S_IFREG => {
+            let dentry = path.join(name)?.open(Some(inode_mode))?;
+            if !entry.data.is_empty() {
+                dentry.inode()?.write_at(0, entry.data)?;
+            }
+            dentry
+        }
+        S_IFDIR => path.join(name)?.open(Some(inode_mode))?,
+        S_IFCHR | S_IFBLK | S_IFIFO | S_IFSOCK => {
+            let (parent, file_name) = match name.rsplit_once('/') {
+                Some((parent, file_name)) => (path.join(parent)?.open(None)?, file_name),
+                None => (path.join(".")?.open(None)?, name),
+            };
+            let node_type = match file_type {
+                S_IFCHR => VfsNodeType::CharDevice,
+                S_IFBLK => VfsNodeType::BlockDevice,
+                S_IFIFO => VfsNodeType::Fifo,
+                _ => VfsNodeType::Socket,
+            };
+            let rdev = match node_type {
+                VfsNodeType::CharDevice | VfsNodeType::BlockDevice => Some(entry.rdev),
+                _ => None,
+            };
+            let perm = perm_string(entry.mode);
+            parent
+                .inode()?
+                .create(file_name, node_type, perm.as_str().into(), rdev)?;
+            path.join(name)?.open(None)?
+        }
+        _ => return Err(LinuxErrno::EINVAL),
+    };
+    let inode = dentry.inode()?;
+    let stat = inode.get_attr()?;
+    let mtime = VfsTimeSpec::new(entry.mtime as u64, 0);
+    inode.set_attr(InodeAttr {
+        mode: entry.mode,
+        uid: entry.uid,
+        gid: entry.gid,
+        size: stat.st_size,
+        atime: mtime,
+        mtime,
+        ctime: mtime,
+    })?;
+    Ok(())
+}
+
+/// 创建 `name` 的所有上级目录，归档中不一定包含这些目录项
+fn create_parent_dirs(path: &VfsPath, name: &str) -> AlienResult<()> {
+    let mode = VfsInodeMode::from_bits_truncate(S_IFDIR | 0o755);
+    let mut end = 0;
+    while let Some(pos) = name[end..].find('/') {
+        end += pos;
+        path.join(&name[..end])?.open(Some(mode))?;
+        end += 1;
     }
     Ok(())
 }
+
+/// 将权限位转换为 "rwxr-xr-x" 形式的字符串
+fn perm_string(mode: u32) -> String {
+    let mut perm = String::new();
+    for shift in [6, 3, 0] {
+        let bits = (mode >> shift) & 0o7;
+        perm.push(if bits & 0o4 != 0 { 'r' } else { '-' });
+        perm.push(if bits & 0o2 != 0 { 'w' } else { '-' });
+        perm.push(if bits & 0o1 != 0 { 'x' } else { '-' });
+    }
+    perm
+}
+
+fn parse_hex(field: &[u8]) -> AlienResult<u32> {
+    let field = core::str::from_utf8(field).map_err(|_| LinuxErrno::EINVAL)?;
+    u32::from_str_radix(field, 16).map_err(|_| LinuxErrno::EINVAL)
+}
+
+#[inline]
+fn align4(value: usize) -> usize {
+    (value + 3) & !3
+}
+
+#[cfg(feature = "ktest")]
+mod ktests {
+    use super::*;
+    use alloc::format;
+    use alloc::vec::Vec;
+    use core2::io::Write;
+    use ktest::{kassert, kassert_eq, kernel_test, TestResult};
+
+    /// 按 newc 格式生成一个归档项
+    fn newc_entry(name: &str, mode: u32, data: &[u8]) -> Vec<u8> {
+        let mut entry = Vec::new();
+        let fields = [1, mode, 0, 0, 1, 0x5f5e_1000, data.len() as u32, 0, 0, 0, 0];
+        entry.extend_from_slice(NEWC_MAGIC);
+        for field in fields {
+            entry.extend_from_slice(format!("{:08x}", field).as_bytes());
+        }
+        entry.extend_from_slice(format!("{:08x}{:08x}", name.len() + 1, 0).as_bytes());
+        entry.extend_from_slice(name.as_bytes());
+        entry.push(0);
+        entry.resize(align4(entry.len()), 0);
+        entry.extend_from_slice(data);
+        entry.resize(align4(entry.len()), 0);
+        entry
+    }
+
+    fn newc_archive(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut archive = Vec::new();
+        for (name, data) in files {
+            archive.extend(newc_entry(name, S_IFREG | 0o644, data));
+        }
+        archive.extend(newc_entry(TRAILER, 0, &[]));
+        archive
+    }
+
+    fn gzip(data: &[u8]) -> Vec<u8> {
+        let mut encoder = libflate::gzip::Encoder::new(Vec::new()).unwrap();
+        encoder.write_all(data).unwrap();
+        encoder.finish().into_result().unwrap()
+    }
+
+    #[kernel_test]
+    fn test_parse_newc_entry() -> TestResult {
+        let archive = newc_archive(&[("bin/hello", b"hello")]);
+        let (entry, next) = parse_entry(&archive, 0)?;
+        kassert_eq!(entry.name, "bin/hello");
+        kassert_eq!(entry.mode, S_IFREG | 0o644);
+        kassert_eq!(entry.mtime, 0x5f5e_1000);
+        kassert_eq!(entry.data, b"hello");
+        kassert_eq!(next % 4, 0);
+        let (trailer, end) = parse_entry(&archive, next)?;
+        kassert_eq!(trailer.name, TRAILER);
+        kassert_eq!(end, archive.len());
+        Ok(())
+    }
+
+    #[kernel_test]
+    fn test_parse_invalid_newc() -> TestResult {
+        let archive = newc_archive(&[("hello", b"hello")]);
+        let mut bad_magic = archive.clone();
+        bad_magic[..6].copy_from_slice(b"070707");
+        kassert!(parse_entry(&bad_magic, 0).is_err());
+        kassert!(parse_entry(&archive[..NEWC_HEADER_SIZE - 1], 0).is_err());
+        // 文件数据被截断
+        kassert!(parse_entry(&archive[..NEWC_HEADER_SIZE + 8], 0).is_err());
+        Ok(())
+    }
+
+    #[kernel_test]
+    fn test_unpack_concatenated_archives() -> TestResult {
+        let root = crate::system_root_fs();
+        let path = VfsPath::new(root.clone(), root).join("tmp/ktest_initrd")?;
+        let mut data = gzip(&newc_archive(&[("a", b"first")]));
+        data.extend(gzip(&newc_archive(&[("dir/b", b"second")])));
+        // 归档之间的 0 填充以及最后一个未压缩的归档
+        data.extend_from_slice(&[0; 4]);
+        data.extend(newc_archive(&[("c", b"third")]));
+        let dir_mode = VfsInodeMode::from_bits_truncate(S_IFDIR | 0o755);
+        path.open(Some(dir_mode))?;
+        unpack_archives(&path, &data, &mut BTreeMap::new())?;
+        for (name, content) in [
+            ("a", b"first" as &[u8]),
+            ("dir/b", b"second"),
+            ("c", b"third"),
+        ] {
+            let mut buf = [0; 16];
+            let len = path.join(name)?.open(None)?.inode()?.read_at(0, &mut buf)?;
+            kassert_eq!(&buf[..len], content);
+        }
+        Ok(())
+    }
+}
@@ -1,3 +1,4 @@
+use crate::dev::loop_device_of;
 use crate::system_root_fs;
 use alloc::sync::Arc;
 use constants::io::{Dirent64, DirentType, OpenFlags, PollEvents, SeekFrom};
@@ -35,6 +36,9 @@ impl KernelFile {
         } else {
             0
         };
+        if let Some(device) = dentry.inode().ok().and_then(|inode| loop_device_of(&inode)) {
+            device.open();
+        }
         Self {
             pos: Mutex::new(pos),
             open_flag: Mutex::new(open_flag),
@@ -269,5 +273,13 @@ impl Drop for KernelFile {
     fn drop(&mut self) {
         let _ = self.flush();
         let _ = self.fsync();
+        if let Some(device) = self
+            .dentry
+            .inode()
+            .ok()
+            .and_then(|inode| loop_device_of(&inode))
+        {
+            device.release();
+        }
     }
 }
@@ -6,25 +6,32 @@ extern crate alloc;
 extern crate platform;
 use crate::dev::DevFsProviderImpl;
 use alloc::collections::BTreeMap;
+use alloc::format;
 use alloc::string::{String, ToString};
 use alloc::sync::Arc;
-use constants::AlienResult;
+use constants::io::MountFlags;
+use constants::{AlienResult, LinuxErrno};
 use core::ops::Index;
+use core::sync::atomic::{AtomicBool, Ordering};
 use dynfs::DynFsKernelProvider;
 use ksync::Mutex;
+use mount::MountEntry;
+use platform::cmdline::KernelCmdline;
 use spin::{Lazy, Once};
 use vfscore::dentry::VfsDentry;
 use vfscore::fstype::VfsFsType;
-#[cfg(feature = "ext")]
 use vfscore::inode::VfsInode;
 use vfscore::path::VfsPath;
-use vfscore::utils::VfsTimeSpec;
+use vfscore::utils::{VfsInodeMode, VfsNodeType, VfsTimeSpec};
 pub mod dev;
 #[cfg(feature = "ext")]
 mod extffi;
+#[cfg(feature = "initrd")]
 mod initrd;
 pub mod kfile;
+pub mod mount;
 pub mod pipefs;
+pub mod probe;
 pub mod proc;
 pub mod ram;
 pub mod sys;
@@ -33,6 +40,8 @@ pub static FS: Lazy<Mutex<BTreeMap<String, Arc<dyn VfsFsType>>>> =
     Lazy::new(|| Mutex::new(BTreeMap::new()));
 
 static SYSTEM_ROOT_FS: Once<Arc<dyn VfsDentry>> = Once::new();
+/// 根文件系统是否为解压了 initramfs 的 ramfs
+static INITRAMFS_ROOT: AtomicBool = AtomicBool::new(false);
 
 type SysFs = dynfs::DynFs<CommonFsProviderImpl, Mutex<()>>;
 type ProcFs = dynfs::DynFs<CommonFsProviderImpl, Mutex<()>>;
@@ -42,10 +51,10 @@ type TmpFs = ramfs::RamFs<CommonFsProviderImpl, Mutex<()>>;
 type PipeFs = dynfs::DynFs<CommonFsProviderImpl, Mutex<()>>;
 
 #[cfg(feature = "fat")]
-type DiskFs = fat_vfs::FatFs<CommonFsProviderImpl, Mutex<()>>;
+type FatFs = fat_vfs::FatFs<CommonFsProviderImpl, Mutex<()>>;
 
 #[cfg(feature = "ext")]
-type DiskFs = lwext4_vfs::ExtFs<CommonFsProviderImpl, Mutex<()>>;
+type ExtFs = lwext4_vfs::ExtFs<CommonFsProviderImpl, Mutex<()>>;
 
 #[derive(Clone)]
 pub struct CommonFsProviderImpl;
@@ -95,21 +104,29 @@ fn register_all_fs() {
     FS.lock().insert("pipefs".to_string(), pipefs);
 
     #[cfg(feature = "fat")]
-    let diskfs = Arc::new(DiskFs::new(CommonFsProviderImpl));
+    {
+        let fatfs = Arc::new(FatFs::new(CommonFsProviderImpl));
+        FS.lock().insert("vfat".to_string(), fatfs);
+    }
     #[cfg(feature = "ext")]
-    let diskfs = Arc::new(DiskFs::new(
-        lwext4_vfs::ExtFsType::Ext4,
-        CommonFsProviderImpl,
-    ));
-
-    FS.lock().insert("diskfs".to_string(), diskfs);
+    {
+        let extfs = Arc::new(ExtFs::new(
+            lwext4_vfs::ExtFsType::Ext4,
+            CommonFsProviderImpl,
+        ));
+        FS.lock().insert("ext4".to_string(), extfs);
+    }
 
     println!("register fs success");
 }
 
 /// Init the filesystem
+///
+/// 根文件系统由内核命令行决定：未指定 `root=` 时，使用解压了 initramfs 的 ramfs 作为根文件系统；
+/// 否则挂载 `root=` 指定的块设备作为根文件系统，并将 initramfs 挂载在 `/initrd` 下(如果该目录存在)。
 pub fn init_filesystem() -> AlienResult<()> {
     register_all_fs();
+    let cmdline = platform::cmdline::kernel_cmdline();
     let ramfs_root = ram::init_ramfs(FS.lock().index("ramfs").clone());
     let procfs = FS.lock().index("procfs").clone();
     let procfs_root = proc::init_procfs(procfs);
@@ -123,37 +140,167 @@ pub fn init_filesystem() -> AlienResult<()> {
 
     pipefs::init_pipefs(FS.lock().index("pipefs").clone());
 
-    let path = VfsPath::new(ramfs_root.clone(), ramfs_root.clone());
-    path.join("proc")?.mount(procfs_root, 0)?;
-    path.join("sys")?.mount(sysfs_root, 0)?;
-    path.join("dev")?.mount(devfs_root, 0)?;
+    #[cfg(feature = "initrd")]
+    let initrd_loaded = initrd::populate_initrd(ramfs_root.clone()).unwrap_or_else(|e| {
+        println!("Initrd populate failed: {:?}", e);
+        false
+    });
+    #[cfg(not(feature = "initrd"))]
+    let initrd_loaded = false;
+
+    let (root, root_dev) = match cmdline.root {
+        Some(root_dev) => {
+            let dev_name = root_dev.trim_start_matches("/dev/");
+            let root = mount_real_root(&devfs_root, dev_name, cmdline)?;
+            (root, Some(dev_name))
+        }
+        None => {
+            INITRAMFS_ROOT.store(initrd_loaded, Ordering::Relaxed);
+            record_init_mount("rootfs", "/", "ramfs", ramfs_root.clone(), None);
+            (ramfs_root.clone(), None)
+        }
+    };
+
+    let path = VfsPath::new(root.clone(), root.clone());
+    if root_dev.is_some() {
+        for dir in ["proc", "sys", "dev", "tmp"] {
+            ensure_dir(&path, dir)?;
+        }
+        if path.join("initrd")?.open(None).is_ok() {
+            path.join("initrd")?.mount(ramfs_root.clone(), 0)?;
+            record_init_mount("rootfs", "/initrd", "ramfs", ramfs_root.clone(), None);
+        }
+    }
+    path.join("proc")?.mount(procfs_root.clone(), 0)?;
+    record_init_mount("proc", "/proc", "procfs", procfs_root, None);
+    path.join("sys")?.mount(sysfs_root.clone(), 0)?;
+    record_init_mount("sysfs", "/sys", "sysfs", sysfs_root, None);
+    path.join("dev")?.mount(devfs_root.clone(), 0)?;
+    record_init_mount("devfs", "/dev", "devfs", devfs_root, None);
     path.join("tmp")?.mount(tmpfs_root.clone(), 0)?;
+    record_init_mount("tmpfs", "/tmp", "tmpfs", tmpfs_root.clone(), None);
 
     let shm_ramfs = FS
         .lock()
         .index("ramfs")
         .clone()
         .i_mount(0, "/dev/shm", None, &[])?;
-    path.join("dev/shm")?.mount(shm_ramfs, 0)?;
-
-    let diskfs = FS.lock().index("diskfs").clone();
-    let blk_inode = path
-        .join("/dev/sda")?
-        .open(None)
-        .expect("open /dev/sda failed")
-        .inode()?;
-    let diskfs_root = diskfs.i_mount(0, "/tests", Some(blk_inode), &[])?;
-    path.join("tests")?.mount(diskfs_root, 0)?;
-    vfscore::path::print_fs_tree(&mut VfsOutPut, ramfs_root.clone(), "".to_string(), false)
-        .unwrap();
-
-    initrd::populate_initrd(ramfs_root.clone())?;
-
-    SYSTEM_ROOT_FS.call_once(|| ramfs_root);
+    path.join("dev/shm")?.mount(shm_ramfs.clone(), 0)?;
+    record_init_mount("shm", "/dev/shm", "ramfs", shm_ramfs, None);
+
+    mount_block_devices(&path, root_dev)?;
+    vfscore::path::print_fs_tree(&mut VfsOutPut, root.clone(), "".to_string(), false).unwrap();
+
+    SYSTEM_ROOT_FS.call_once(|| root);
     println!("Init filesystem success");
     Ok(())
 }
 
+/// 挂载 `root=` 指定的块设备作为根文件系统
+fn mount_real_root(
+    devfs_root: &Arc<dyn VfsDentry>,
+    dev_name: &str,
+    cmdline: &KernelCmdline,
+) -> AlienResult<Arc<dyn VfsDentry>> {
+    let dev_path = VfsPath::new(devfs_root.clone(), devfs_root.clone());
+    let blk_inode = dev_path.join(dev_name)?.open(None)?.inode()?;
+    if blk_inode.inode_type() != VfsNodeType::BlockDevice {
+        return Err(LinuxErrno::ENOTBLK);
+    }
+    let fs_name = match cmdline.rootfstype {
+        Some(fs_name) => fs_name,
+        None => probe::probe_fs_type(&blk_inode).ok_or(LinuxErrno::EINVAL)?,
+    };
+    let fs = system_support_fs(fs_name).ok_or(LinuxErrno::ENODEV)?;
+    let flags = if cmdline.read_only {
+        MountFlags::MS_RDONLY
+    } else {
+        MountFlags::empty()
+    };
+    let rdev = blk_inode.get_attr()?.st_rdev;
+    let root = fs.i_mount(flags.bits(), "/", Some(blk_inode), &[])?;
+    let source = format!("/dev/{}", dev_name);
+    record_init_mount(&source, "/", fs_name, root.clone(), Some(rdev));
+    if cmdline.read_only {
+        mount::update_mount("/", flags, "");
+    }
+    println!(
+        "Mount root {}({}) {}",
+        source,
+        fs_name,
+        if cmdline.read_only { "ro" } else { "rw" }
+    );
+    Ok(root)
+}
+
+/// 确保目录 `dir` 存在，不存在时创建它
+fn ensure_dir(path: &VfsPath, dir: &str) -> AlienResult<()> {
+    let mode = VfsInodeMode::from_bits_truncate(0o040755);
+    path.join(dir)?.open(Some(mode))?;
+    Ok(())
+}
+
+/// 挂载系统中除根设备外所有能识别出文件系统的块设备。
+///
+/// 根文件系统为 ramfs 时，第一个块设备挂载在 `/tests` 下，其余的块设备挂载在 `/mnt/<设备名>` 下。
+fn mount_block_devices(path: &VfsPath, root_dev: Option<&str>) -> AlienResult<()> {
+    let mut use_tests = root_dev.is_none();
+    for index in 0..dev::block_device_count() {
+        let name = dev::block_device_name(index);
+        if root_dev == Some(name.as_str()) {
+            continue;
+        }
+        let dev_path = format!("/dev/{}", name);
+        let blk_inode = path.join(&dev_path)?.open(None)?.inode()?;
+        let fs_name = match probe::probe_fs_type(&blk_inode) {
+            Some(fs_name) => fs_name,
+            None => {
+                println!("Unknown filesystem on {}, skip it", dev_path);
+                continue;
+            }
+        };
+        let fs = match system_support_fs(fs_name) {
+            Some(fs) => fs,
+            None => {
+                println!("Filesystem {} on {} is not supported", fs_name, dev_path);
+                continue;
+            }
+        };
+        let target = if use_tests {
+            use_tests = false;
+            "/tests".to_string()
+        } else {
+            format!("/mnt/{}", name)
+        };
+        ensure_dir(path, "mnt")?;
+        ensure_dir(path, &target)?;
+        let rdev = blk_inode.get_attr()?.st_rdev;
+        let fs_root = fs.i_mount(0, &target, Some(blk_inode), &[])?;
+        path.join(&target)?.mount(fs_root.clone(), 0)?;
+        record_init_mount(&dev_path, &target, fs_name, fs_root, Some(rdev));
+        println!("Mount {}({}) at {}", dev_path, fs_name, target);
+    }
+    Ok(())
+}
+
+fn record_init_mount(
+    source: &str,
+    target: &str,
+    fs_type: &str,
+    root: Arc<dyn VfsDentry>,
+    rdev: Option<u64>,
+) {
+    mount::record_mount(MountEntry {
+        source: source.to_string(),
+        target: target.to_string(),
+        fs_type: fs_type.to_string(),
+        flags: MountFlags::empty(),
+        options: String::new(),
+        root,
+        rdev,
+    });
+}
+
 struct VfsOutPut;
 impl core::fmt::Write for VfsOutPut {
     fn write_str(&mut self, s: &str) -> core::fmt::Result {
@@ -168,9 +315,24 @@ pub fn system_root_fs() -> Arc<dyn VfsDentry> {
     SYSTEM_ROOT_FS.get().unwrap().clone()
 }
 
+/// 根文件系统是否为 initramfs，只有此时才应该执行 `rdinit=` 指定的程序
+pub fn initramfs_is_root() -> bool {
+    INITRAMFS_ROOT.load(Ordering::Relaxed)
+}
+
+/// 将 mount 时常用的文件系统别名转换为注册时使用的名称
+fn canonical_fs_name(fs_name: &str) -> &str {
+    match fs_name {
+        "fat" | "fat32" | "msdos" => "vfat",
+        "ext2" | "ext3" => "ext4",
+        name => name,
+    }
+}
+
 /// Get the filesystem by name
 #[inline]
 pub fn system_support_fs(fs_name: &str) -> Option<Arc<dyn VfsFsType>> {
+    let fs_name = canonical_fs_name(fs_name);
     FS.lock().iter().find_map(|(name, fs)| {
         if name == fs_name {
             Some(fs.clone())
@@ -179,3 +341,37 @@ pub fn system_support_fs(fs_name: &str) -> Option<Arc<dyn VfsFsType>> {
         }
     })
 }
+
+#[cfg(feature = "ktest")]
+mod ktests {
+    use super::*;
+    use ktest::{kassert, kassert_eq, kernel_test, TestResult};
+
+    #[kernel_test]
+    fn test_tmpfs_read_write() -> TestResult {
+        let root = system_root_fs();
+        let path = VfsPath::new(root.clone(), root);
+        let mode = VfsInodeMode::from_bits_truncate(0o100644);
+        let file = path.join("tmp/ktest_rw")?.open(Some(mode))?;
+        let inode = file.inode()?;
+        kassert_eq!(inode.inode_type(), VfsNodeType::File);
+        kassert_eq!(inode.write_at(0, b"hello world")?, 11);
+        kassert_eq!(inode.write_at(6, b"alien")?, 5);
+        let mut buf = [0; 32];
+        let len = inode.read_at(0, &mut buf)?;
+        kassert_eq!(&buf[..len], b"hello alien");
+        kassert_eq!(inode.get_attr()?.st_size, 11);
+        path.join("tmp/ktest_rw")?.unlink()?;
+        kassert!(path.join("tmp/ktest_rw")?.open(None).is_err());
+        Ok(())
+    }
+
+    #[kernel_test]
+    fn test_support_fs_alias() -> TestResult {
+        kassert!(system_support_fs("tmpfs").is_some());
+        kassert!(system_support_fs("nosuchfs").is_none());
+        kassert_eq!(canonical_fs_name("fat32"), "vfat");
+        kassert_eq!(canonical_fs_name("ext3"), "ext4");
+        Ok(())
+    }
+}
@@ -0,0 +1,236 @@
+//! 挂载表，记录系统中所有的挂载点信息。
+//!
+//! `mount`/`umount` 系统调用在修改文件系统树的同时会同步更新这里的记录，
+//! `/proc/mounts` 的内容也由此生成。
+use alloc::string::{String, ToString};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use constants::io::MountFlags;
+use ksync::Mutex;
+use spin::Lazy;
+use vfscore::dentry::VfsDentry;
+
+/// 一个挂载点的记录
+#[derive(Clone)]
+pub struct MountEntry {
+    /// 挂载源，通常为设备路径，对于虚拟文件系统则为文件系统名称
+    pub source: String,
+    /// 挂载点的绝对路径
+    pub target: String,
+    /// 文件系统名称
+    pub fs_type: String,
+    /// 挂载标志
+    pub flags: MountFlags,
+    /// 挂载时传入的选项字符串
+    pub options: String,
+    /// 挂载的文件系统的根目录项
+    pub root: Arc<dyn VfsDentry>,
+    /// 文件系统所在块设备的设备号，虚拟文件系统为 `None`
+    pub rdev: Option<u64>,
+}
+
+static MOUNT_TABLE: Lazy<Mutex<Vec<MountEntry>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+/// 记录一个新的挂载点
+pub fn record_mount(entry: MountEntry) {
+    MOUNT_TABLE.lock().push(entry);
+}
+
+/// 移除挂载在 `target` 上的最后一个挂载点，并返回其记录
+pub fn remove_mount(target: &str) -> Option<MountEntry> {
+    let mut table = MOUNT_TABLE.lock();
+    let index = table.iter().rposition(|entry| entry.target == target)?;
+    Some(table.remove(index))
+}
+
+/// 查找挂载在 `target` 上的挂载点
+pub fn find_mount(target: &str) -> Option<MountEntry> {
+    MOUNT_TABLE
+        .lock()
+        .iter()
+        .rev()
+        .find(|entry| entry.target == target)
+        .cloned()
+}
+
+/// 判断设备号为 `rdev` 的块设备上是否有被挂载的文件系统
+pub fn device_mounted(rdev: u64) -> bool {
+    MOUNT_TABLE
+        .lock()
+        .iter()
+        .any(|entry| entry.rdev == Some(rdev))
+}
+
+/// 修改挂载在 `target` 上的挂载点的标志和选项(用于 MS_REMOUNT)
+pub fn update_mount(target: &str, flags: MountFlags, options: &str) -> bool {
+    let mut table = MOUNT_TABLE.lock();
+    match table.iter_mut().rev().find(|entry| entry.target == target) {
+        Some(entry) => {
+            entry.flags = flags;
+            entry.options = options.to_string();
+            true
+        }
+        None => false,
+    }
+}
+
+/// 修改挂载点的位置(用于 MS_MOVE)，同时更新位于其下方的挂载点
+pub fn move_mount(old_target: &str, new_target: &str) {
+    let mut table = MOUNT_TABLE.lock();
+    table.iter_mut().for_each(|entry| {
+        if entry.target == old_target {
+            entry.target = new_target.to_string();
+        } else if let Some(rest) = entry.target.strip_prefix(old_target) {
+            if rest.starts_with('/') || old_target == "/" {
+                entry.target = join_path(new_target, rest);
+            }
+        }
+    });
+}
+
+/// 找到 `path` 所在的挂载点，即挂载路径为 `path` 最长前缀的挂载点
+pub fn mount_of(path: &str) -> Option<MountEntry> {
+    MOUNT_TABLE
+        .lock()
+        .iter()
+        .filter(|entry| is_path_prefix(&entry.target, path))
+        .max_by_key(|entry| entry.target.len())
+        .cloned()
+}
+
+/// 判断 `path` 所在的挂载点是否包含 `flag`
+pub fn path_has_flag(path: &str, flag: MountFlags) -> bool {
+    mount_of(path)
+        .map(|entry| entry.flags.contains(flag))
+        .unwrap_or(false)
+}
+
+/// 按照 `/proc/mounts` 的格式生成挂载信息
+pub fn serialize() -> String {
+    let mut res = String::new();
+    for entry in MOUNT_TABLE.lock().iter() {
+        res.push_str(&entry.source);
+        res.push(' ');
+        res.push_str(&entry.target);
+        res.push(' ');
+        res.push_str(&entry.fs_type);
+        res.push(' ');
+        res.push_str(&flags_to_options(entry.flags, &entry.options));
+        res.push_str(" 0 0\n");
+    }
+    res
+}
+
+fn flags_to_options(flags: MountFlags, options: &str) -> String {
+    let mut res = String::new();
+    if flags.contains(MountFlags::MS_RDONLY) {
+        res.push_str("ro");
+    } else {
+        res.push_str("rw");
+    }
+    if flags.contains(MountFlags::MS_NOSUID) {
+        res.push_str(",nosuid");
+    }
+    if flags.contains(MountFlags::MS_NODEV) {
+        res.push_str(",nodev");
+    }
+    if flags.contains(MountFlags::MS_NOEXEC) {
+        res.push_str(",noexec");
+    }
+    options
+        .split(',')
+        .filter(|opt| !opt.is_empty() && *opt != "ro" && *opt != "rw")
+        .for_each(|opt| {
+            res.push(',');
+            res.push_str(opt);
+        });
+    res
+}
+
+fn is_path_prefix(prefix: &str, path: &str) -> bool {
+    if prefix == "/" {
+        return path.starts_with('/');
+    }
+    match path.strip_prefix(prefix) {
+        Some(rest) => rest.is_empty() || rest.starts_with('/'),
+        None => false,
+    }
+}
+
+fn join_path(base: &str, rest: &str) -> String {
+    let mut res = base.trim_end_matches('/').to_string();
+    if !rest.starts_with('/') {
+        res.push('/');
+    }
+    res.push_str(rest);
+    res
+}
+
+/// 将路径规范化为不包含 `.`、`..` 和重复 `/` 的绝对路径
+pub fn normalize_path(path: &str) -> String {
+    let mut parts: Vec<&str> = Vec::new();
+    for part in path.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop();
+            }
+            _ => parts.push(part),
+        }
+    }
+    let mut res = String::new();
+    for part in parts {
+        res.push('/');
+        res.push_str(part);
+    }
+    if res.is_empty() {
+        res.push('/');
+    }
+    res
+}
+
+#[cfg(feature = "ktest")]
+mod ktests {
+    use super::*;
+    use ktest::{kassert, kassert_eq, kernel_test, TestResult};
+
+    #[kernel_test]
+    fn test_normalize_path() -> TestResult {
+        kassert_eq!(normalize_path("/"), "/");
+        kassert_eq!(normalize_path(""), "/");
+        kassert_eq!(normalize_path("//a/./b/"), "/a/b");
+        kassert_eq!(normalize_path("/a/../../b"), "/b");
+        Ok(())
+    }
+
+    #[kernel_test]
+    fn test_path_prefix() -> TestResult {
+        kassert!(is_path_prefix("/", "/tmp"));
+        kassert!(is_path_prefix("/tmp", "/tmp"));
+        kassert!(is_path_prefix("/tmp", "/tmp/a"));
+        kassert!(!is_path_prefix("/tmp", "/tmpfile"));
+        kassert_eq!(join_path("/mnt/", "/a"), "/mnt/a");
+        kassert_eq!(join_path("/mnt", "a"), "/mnt/a");
+        Ok(())
+    }
+
+    #[kernel_test]
+    fn test_mount_options() -> TestResult {
+        let flags = MountFlags::MS_RDONLY | MountFlags::MS_NOEXEC;
+        kassert_eq!(flags_to_options(flags, "rw,size=1M"), "ro,noexec,size=1M");
+        kassert_eq!(flags_to_options(MountFlags::empty(), ""), "rw");
+        let flags = MountFlags::MS_NOSUID | MountFlags::MS_NOEXEC;
+        kassert_eq!(flags_to_options(flags, ""), "rw,nosuid,noexec");
+        Ok(())
+    }
+
+    #[kernel_test]
+    fn test_mount_of_boot_mounts() -> TestResult {
+        let entry = mount_of("/tmp/a/b");
+        kassert_eq!(entry.map(|e| e.fs_type), Some("tmpfs".to_string()));
+        let entry = mount_of("/dev/shm");
+        kassert_eq!(entry.map(|e| e.target), Some("/dev/shm".to_string()));
+        kassert!(serialize().contains("proc /proc procfs rw 0 0\n"));
+        Ok(())
+    }
+}
@@ -0,0 +1,49 @@
+//! 通过读取块设备上的超级块，自动识别设备上的文件系统类型。
+use alloc::sync::Arc;
+use vfscore::inode::VfsInode;
+
+/// ext2/3/4 超级块位于设备偏移 1024 处，魔数位于超级块内偏移 56 处
+const EXT_SUPER_BLOCK_OFFSET: u64 = 1024;
+const EXT_MAGIC_OFFSET: usize = 56;
+const EXT_MAGIC: u16 = 0xEF53;
+
+/// FAT 引导扇区以 0x55AA 结尾，FAT12/16 与 FAT32 的类型字符串位置不同
+const FAT_BOOT_SIGNATURE: [u8; 2] = [0x55, 0xAA];
+const FAT16_TYPE_OFFSET: usize = 54;
+const FAT32_TYPE_OFFSET: usize = 82;
+
+/// 探测块设备 `dev` 上的文件系统，返回对应的已注册文件系统名称
+pub fn probe_fs_type(dev: &Arc<dyn VfsInode>) -> Option<&'static str> {
+    if is_ext(dev) {
+        return Some("ext4");
+    }
+    if is_fat(dev) {
+        return Some("vfat");
+    }
+    None
+}
+
+fn is_ext(dev: &Arc<dyn VfsInode>) -> bool {
+    let mut buf = [0u8; 64];
+    match dev.read_at(EXT_SUPER_BLOCK_OFFSET, &mut buf) {
+        Ok(len) if len == buf.len() => {
+            let magic = u16::from_le_bytes([buf[EXT_MAGIC_OFFSET], buf[EXT_MAGIC_OFFSET + 1]]);
+            magic == EXT_MAGIC
+        }
+        _ => false,
+    }
+}
+
+fn is_fat(dev: &Arc<dyn VfsInode>) -> bool {
+    let mut buf = [0u8; 512];
+    match dev.read_at(0, &mut buf) {
+        Ok(len) if len == buf.len() => {
+            if buf[510..512] != FAT_BOOT_SIGNATURE {
+                return false;
+            }
+            buf[FAT32_TYPE_OFFSET..FAT32_TYPE_OFFSET + 5] == *b"FAT32"
+                || buf[FAT16_TYPE_OFFSET..FAT16_TYPE_OFFSET + 3] == *b"FAT"
+        }
+        _ => false,
+    }
+}
@@ -12,12 +12,16 @@ use interrupt::InterruptRecord;
 use ksync::Mutex;
 use mem::MemInfo;
 use mounts::MountInfo;
+use spin::Once;
 use vfscore::dentry::VfsDentry;
 use vfscore::error::VfsError;
 use vfscore::fstype::VfsFsType;
 use vfscore::path::VfsPath;
 pub type ProcFsDirInodeImpl = DynFsDirInode<CommonFsProviderImpl, Mutex<()>>;
 
+/// procfs 的根目录，内核的其它模块可以在其中添加自己的文件
+pub static PROC_FS_ROOT: Once<Arc<dyn VfsDentry>> = Once::new();
+
 ///
 /// ```bash
 /// |
@@ -25,6 +29,8 @@ pub type ProcFsDirInodeImpl = DynFsDirInode<CommonFsProviderImpl, Mutex<()>>;
 /// |-- interrupts
 /// |-- mounts
 /// |-- filesystems
+/// |-- sys
+///     |-- kernel
 /// ```
 // todo!(use ramfs instead of dynfs)
 pub fn init_procfs(procfs: Arc<dyn VfsFsType>) -> Arc<dyn VfsDentry> {
@@ -51,6 +57,20 @@ pub fn init_procfs(procfs: Arc<dyn VfsFsType>) -> Arc<dyn VfsDentry> {
     root_inode
         .add_dir_manually("self", "r-xr-xr-x".into())
         .unwrap();
+    root_inode
+        .add_dir_manually("sys", "r-xr-xr-x".into())
+        .unwrap();
+    let sys_inode = VfsPath::new(root_dt.clone(), root_dt.clone())
+        .join("sys")
+        .and_then(|path| path.open(None))
+        .and_then(|dt| dt.inode())
+        .unwrap()
+        .downcast_arc::<ProcFsDirInodeImpl>()
+        .map_err(|_| VfsError::Invalid)
+        .unwrap();
+    sys_inode
+        .add_dir_manually("kernel", "r-xr-xr-x".into())
+        .unwrap();
 
     let path = VfsPath::new(root_dt.clone(), root_dt.clone());
     let ramfs = FS.lock().index("ramfs").clone();
@@ -62,7 +82,30 @@ pub fn init_procfs(procfs: Arc<dyn VfsFsType>) -> Arc<dyn VfsDentry> {
         .symlink("/bin/busybox")
         .unwrap();
 
+    PROC_FS_ROOT.call_once(|| root_dt.clone());
     println!("procfs init success");
 
     root_dt
 }
+
+/// 获取 procfs 的根目录，用于向其中添加文件
+pub fn procfs_root() -> Option<Arc<ProcFsDirInodeImpl>> {
+    PROC_FS_ROOT
+        .get()?
+        .inode()
+        .ok()?
+        .downcast_arc::<ProcFsDirInodeImpl>()
+        .ok()
+}
+
+/// 获取 procfs 中 `path` 处的目录，用于向其中添加文件
+pub fn procfs_dir(path: &str) -> Option<Arc<ProcFsDirInodeImpl>> {
+    let root = PROC_FS_ROOT.get()?;
+    VfsPath::new(root.clone(), root.clone())
+        .join(path)
+        .and_then(|path| path.open(None))
+        .and_then(|dt| dt.inode())
+        .ok()?
+        .downcast_arc::<ProcFsDirInodeImpl>()
+        .ok()
+}
@@ -7,18 +7,17 @@ use vfscore::superblock::VfsSuperBlock;
 use vfscore::utils::{VfsFileStat, VfsNodePerm, VfsNodeType};
 use vfscore::VfsResult;
 
-// todo!(dynamic mount info)
-const MOUNT_INFO: &str = r"
- rootfs / rootfs rw 0 0
- devfs /dev devfs rw 0 0
- fat32 / fat rw 0 0
-";
 pub struct MountInfo;
 
 impl VfsFile for MountInfo {
     fn read_at(&self, offset: u64, buf: &mut [u8]) -> VfsResult<usize> {
-        let min_len = min(buf.len(), MOUNT_INFO.as_bytes().len() - offset as usize);
-        buf[..min_len].copy_from_slice(&MOUNT_INFO.as_bytes()[..min_len]);
+        let info = crate::mount::serialize();
+        let info = info.as_bytes();
+        if offset as usize >= info.len() {
+            return Ok(0);
+        }
+        let min_len = min(buf.len(), info.len() - offset as usize);
+        buf[..min_len].copy_from_slice(&info[offset as usize..offset as usize + min_len]);
         Ok(min_len)
     }
 }
@@ -36,7 +35,7 @@ impl VfsInode for MountInfo {
 
     fn get_attr(&self) -> VfsResult<VfsFileStat> {
         Ok(VfsFileStat {
-            st_size: MOUNT_INFO.as_bytes().len() as u64,
+            st_size: crate::mount::serialize().as_bytes().len() as u64,
             ..Default::default()
         })
     }
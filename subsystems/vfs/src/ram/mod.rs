@@ -21,6 +21,8 @@ use vfscore::utils::VfsNodeType;
 /// |-- sys  (sysfs)
 /// |-- bin  (fat32)
 /// |-- tmp   (ramfs)
+/// |-- tests (first block device)
+/// |-- mnt   (other block devices)
 /// ```
 pub fn init_ramfs(ramfs: Arc<dyn VfsFsType>) -> Arc<dyn VfsDentry> {
     let root_dt = ramfs.i_mount(0, "/", None, &[]).unwrap();
@@ -71,6 +73,9 @@ pub fn init_ramfs(ramfs: Arc<dyn VfsFsType>) -> Arc<dyn VfsDentry> {
     root_inode
         .create("tests", VfsNodeType::Dir, "rwxr-xr-x".into(), None)
         .unwrap();
+    root_inode
+        .create("mnt", VfsNodeType::Dir, "rwxr-xr-x".into(), None)
+        .unwrap();
 
     let _bashrc = root
         .create(".bashrc", VfsNodeType::File, "rwxrwxrwx".into(), None)
@@ -2,17 +2,57 @@ use crate::CommonFsProviderImpl;
 use alloc::sync::Arc;
 use dynfs::DynFsDirInode;
 use ksync::Mutex;
+use spin::Once;
 use vfscore::dentry::VfsDentry;
+use vfscore::error::VfsError;
 use vfscore::fstype::VfsFsType;
+use vfscore::path::VfsPath;
 
 pub type SysFsDirInodeImpl = DynFsDirInode<CommonFsProviderImpl, Mutex<()>>;
 
+/// sysfs 的根目录，内核的其它模块可以在其中添加自己的控制文件
+pub static SYS_FS_ROOT: Once<Arc<dyn VfsDentry>> = Once::new();
+
+///
+/// ```bash
+/// |
+/// |-- kernel
+///     |-- tracing
+/// ```
 pub fn init_sysfs(sysfs: Arc<dyn VfsFsType>) -> Arc<dyn VfsDentry> {
     let root_dt = sysfs.i_mount(0, "/sys", None, &[]).unwrap();
-    // let root_inode = root_dt.inode().unwrap();
-    // let root_inode = root_inode
-    //     .downcast_arc::<SysFsDirInodeImpl>()
-    //     .map_err(|_| VfsError::Invalid).unwrap();
+    let root_inode = root_dt.inode().unwrap();
+    let root_inode = root_inode
+        .downcast_arc::<SysFsDirInodeImpl>()
+        .map_err(|_| VfsError::Invalid)
+        .unwrap();
+    root_inode
+        .add_dir_manually("kernel", "r-xr-xr-x".into())
+        .unwrap();
+    let kernel_inode = VfsPath::new(root_dt.clone(), root_dt.clone())
+        .join("kernel")
+        .and_then(|path| path.open(None))
+        .and_then(|dt| dt.inode())
+        .unwrap()
+        .downcast_arc::<SysFsDirInodeImpl>()
+        .map_err(|_| VfsError::Invalid)
+        .unwrap();
+    kernel_inode
+        .add_dir_manually("tracing", "r-xr-xr-x".into())
+        .unwrap();
+    SYS_FS_ROOT.call_once(|| root_dt.clone());
     println!("sysfs init success");
     root_dt
 }
+
+/// 获取 sysfs 中 `path` 处的目录，用于向其中添加文件
+pub fn sysfs_dir(path: &str) -> Option<Arc<SysFsDirInodeImpl>> {
+    let root = SYS_FS_ROOT.get()?;
+    VfsPath::new(root.clone(), root.clone())
+        .join(path)
+        .and_then(|path| path.open(None))
+        .and_then(|dt| dt.inode())
+        .ok()?
+        .downcast_arc::<SysFsDirInodeImpl>()
+        .ok()
+}
@@ -0,0 +1,202 @@
+//! 比较两次运行的报告
+//!
+//! 测试点以 `(suite, case)` 标识。之前通过而现在失败、超时或者缺失的测试点为回归；
+//! 测试脚本的状态从 `passed` 变为其他状态同样为回归。
+use std::collections::BTreeMap;
+
+use crate::report::{Report, Suite};
+
+/// 耗时的增长超过该比例且超过 [`Thresholds::slowdown_min_ms`] 时认为变慢
+#[derive(Debug, Clone, Copy)]
+pub struct Thresholds {
+    pub slowdown_ratio: f64,
+    pub slowdown_min_ms: u64,
+}
+
+impl Default for Thresholds {
+    fn default() -> Self {
+        Self {
+            slowdown_ratio: 0.5,
+            slowdown_min_ms: 1000,
+        }
+    }
+}
+
+/// 一个测试点或者测试脚本的变化
+#[derive(Debug, PartialEq, Eq)]
+pub struct Change {
+    pub suite: String,
+    /// 为 `None` 时表示整个测试脚本
+    pub case: Option<String>,
+    pub old: String,
+    pub new: String,
+}
+
+#[derive(Debug, Default)]
+pub struct Diff {
+    pub regressions: Vec<Change>,
+    pub fixes: Vec<Change>,
+    /// 新增的测试点
+    pub added: Vec<Change>,
+    pub slowdowns: Vec<Change>,
+}
+
+impl Diff {
+    pub fn has_regression(&self) -> bool {
+        !self.regressions.is_empty()
+    }
+}
+
+const MISSING: &str = "missing";
+
+pub fn diff(old: &Report, new: &Report, thresholds: Thresholds) -> Diff {
+    let mut diff = Diff::default();
+    let new_suites = new
+        .suites
+        .iter()
+        .map(|s| (s.name.as_str(), s))
+        .collect::<BTreeMap<_, _>>();
+    for old_suite in &old.suites {
+        match new_suites.get(old_suite.name.as_str()) {
+            Some(new_suite) => diff_suite(&mut diff, old_suite, new_suite, thresholds),
+            None => diff.regressions.push(Change {
+                suite: old_suite.name.clone(),
+                case: None,
+                old: old_suite.status.clone(),
+                new: MISSING.to_string(),
+            }),
+        }
+    }
+    for new_suite in &new.suites {
+        if !old.suites.iter().any(|s| s.name == new_suite.name) {
+            diff.added.push(Change {
+                suite: new_suite.name.clone(),
+                case: None,
+                old: MISSING.to_string(),
+                new: new_suite.status.clone(),
+            });
+        }
+    }
+    diff
+}
+
+fn diff_suite(diff: &mut Diff, old: &Suite, new: &Suite, thresholds: Thresholds) {
+    let change = |case: Option<&str>, from: &str, to: &str| Change {
+        suite: old.name.clone(),
+        case: case.map(str::to_string),
+        old: from.to_string(),
+        new: to.to_string(),
+    };
+    if old.status != new.status {
+        let c = change(None, &old.status, &new.status);
+        if old.status == "passed" {
+            diff.regressions.push(c);
+        } else if new.status == "passed" {
+            diff.fixes.push(c);
+        }
+    }
+    let new_cases = new
+        .cases
+        .iter()
+        .map(|c| (c.name.as_str(), c))
+        .collect::<BTreeMap<_, _>>();
+    for old_case in &old.cases {
+        let name = Some(old_case.name.as_str());
+        let Some(new_case) = new_cases.get(old_case.name.as_str()) else {
+            if old_case.result == "pass" {
+                diff.regressions
+                    .push(change(name, &old_case.result, MISSING));
+            }
+            continue;
+        };
+        if old_case.result != new_case.result {
+            let c = change(name, &old_case.result, &new_case.result);
+            if old_case.result == "pass" {
+                diff.regressions.push(c);
+            } else if new_case.result == "pass" {
+                diff.fixes.push(c);
+            }
+        } else if is_slowdown(old_case.elapsed_ms, new_case.elapsed_ms, thresholds) {
+            diff.slowdowns.push(change(
+                name,
+                &format!("{}ms", old_case.elapsed_ms),
+                &format!("{}ms", new_case.elapsed_ms),
+            ));
+        }
+    }
+    for new_case in &new.cases {
+        if !old.cases.iter().any(|c| c.name == new_case.name) {
+            diff.added
+                .push(change(Some(&new_case.name), MISSING, &new_case.result));
+        }
+    }
+}
+
+fn is_slowdown(old_ms: u64, new_ms: u64, thresholds: Thresholds) -> bool {
+    new_ms >= old_ms + thresholds.slowdown_min_ms
+        && new_ms as f64 > old_ms as f64 * (1.0 + thresholds.slowdown_ratio)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(json: &str) -> Report {
+        Report::parse(&format!(
+            r#"{{"version": 1, "kernel": {{"release": "6.6", "version": "test"}}, "suites": [{}]}}"#,
+            json
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_case_regression_and_fix() {
+        let old = report(
+            r#"{"name": "busybox", "status": "failed", "exit_code": 0, "signal": null, "elapsed_ms": 10, "cases": [
+                {"name": "echo", "result": "pass", "elapsed_ms": 1},
+                {"name": "ls", "result": "fail", "elapsed_ms": 1},
+                {"name": "cat", "result": "pass", "elapsed_ms": 1}
+            ]}"#,
+        );
+        let new = report(
+            r#"{"name": "busybox", "status": "failed", "exit_code": 0, "signal": null, "elapsed_ms": 10, "cases": [
+                {"name": "echo", "result": "timeout", "elapsed_ms": 1},
+                {"name": "ls", "result": "pass", "elapsed_ms": 1},
+                {"name": "df", "result": "pass", "elapsed_ms": 1}
+            ]}"#,
+        );
+        let diff = diff(&old, &new, Thresholds::default());
+        let names = |changes: &[Change]| {
+            changes
+                .iter()
+                .map(|c| format!("{}:{}", c.case.as_deref().unwrap_or("-"), c.new))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names(&diff.regressions), ["echo:timeout", "cat:missing"]);
+        assert_eq!(names(&diff.fixes), ["ls:pass"]);
+        assert_eq!(names(&diff.added), ["df:pass"]);
+        assert!(diff.has_regression());
+    }
+
+    #[test]
+    fn test_suite_status() {
+        let old = report(
+            r#"{"name": "time-test", "status": "passed", "exit_code": 0, "signal": null, "elapsed_ms": 10, "cases": []},
+               {"name": "lmbench", "status": "passed", "exit_code": 0, "signal": null, "elapsed_ms": 10, "cases": []}"#,
+        );
+        let new = report(
+            r#"{"name": "time-test", "status": "timeout", "exit_code": null, "signal": 9, "elapsed_ms": 60000, "cases": []}"#,
+        );
+        let diff = diff(&old, &new, Thresholds::default());
+        assert_eq!(diff.regressions.len(), 2);
+        assert_eq!(diff.regressions[1].new, MISSING);
+    }
+
+    #[test]
+    fn test_slowdown() {
+        let thresholds = Thresholds::default();
+        assert!(!is_slowdown(100, 900, thresholds));
+        assert!(!is_slowdown(10_000, 11_000, thresholds));
+        assert!(is_slowdown(10_000, 16_000, thresholds));
+    }
+}
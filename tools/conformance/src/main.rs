@@ -0,0 +1,108 @@
+//! 比较 `final_test` 在两个内核版本上生成的测试报告
+//!
+//! 用法：`conformance <old.json> <new.json> [--slowdown <ratio>] [--slowdown-min-ms <ms>]`
+//!
+//! 输出回归、修复、新增以及明显变慢的测试点，存在回归时以 1 退出，便于在 CI 中使用。
+#[cfg(test)]
+extern crate alloc;
+
+mod diff;
+/// `final_test` 解析测试输出的代码，只为在宿主机上运行它的测试而引用
+#[cfg(test)]
+#[allow(dead_code)]
+#[path = "../../../user/apps/final_test/src/parser.rs"]
+mod parser;
+mod report;
+
+use std::env;
+use std::process::ExitCode;
+
+use diff::{Change, Thresholds};
+use report::Report;
+
+const USAGE: &str =
+    "usage: conformance <old.json> <new.json> [--slowdown <ratio>] [--slowdown-min-ms <ms>]";
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(true) => ExitCode::from(1),
+        Ok(false) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("conformance: {}", e);
+            ExitCode::from(2)
+        }
+    }
+}
+
+/// 返回是否存在回归
+fn run() -> Result<bool, String> {
+    let mut paths = Vec::new();
+    let mut thresholds = Thresholds::default();
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--slowdown" => thresholds.slowdown_ratio = parse_value(&arg, args.next())?,
+            "--slowdown-min-ms" => thresholds.slowdown_min_ms = parse_value(&arg, args.next())?,
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return Ok(false);
+            }
+            _ => paths.push(arg),
+        }
+    }
+    let [old, new] = paths.as_slice() else {
+        return Err(USAGE.to_string());
+    };
+    let (old, new) = (Report::load(old)?, Report::load(new)?);
+    println!(
+        "old: {} {}\nnew: {} {}",
+        old.kernel.release, old.kernel.version, new.kernel.release, new.kernel.version
+    );
+    print_totals("old", &old);
+    print_totals("new", &new);
+    let diff = diff::diff(&old, &new, thresholds);
+    print_changes("regressions", &diff.regressions);
+    print_changes("fixes", &diff.fixes);
+    print_changes("new tests", &diff.added);
+    print_changes("slowdowns", &diff.slowdowns);
+    if !diff.has_regression() {
+        println!("no regressions");
+    }
+    Ok(diff.has_regression())
+}
+
+fn parse_value<T: std::str::FromStr>(arg: &str, value: Option<String>) -> Result<T, String> {
+    value
+        .and_then(|v| v.parse().ok())
+        .ok_or_else(|| format!("invalid value for {}", arg))
+}
+
+fn print_totals(label: &str, report: &Report) {
+    let cases = report.suites.iter().flat_map(|s| &s.cases);
+    let count = |result: &str| cases.clone().filter(|c| c.result == result).count();
+    println!(
+        "{}: {} suites, {} cases, {} passed, {} failed, {} timeout",
+        label,
+        report.suites.len(),
+        cases.clone().count(),
+        count("pass"),
+        count("fail"),
+        count("timeout")
+    );
+}
+
+fn print_changes(title: &str, changes: &[Change]) {
+    if changes.is_empty() {
+        return;
+    }
+    println!("\n{} ({}):", title, changes.len());
+    for change in changes {
+        match &change.case {
+            Some(case) => println!(
+                "  {} / {}: {} -> {}",
+                change.suite, case, change.old, change.new
+            ),
+            None => println!("  {}: {} -> {}", change.suite, change.old, change.new),
+        }
+    }
+}
@@ -0,0 +1,49 @@
+//! `final_test` 写入磁盘镜像的 JSON 报告，这里只解析比较时用到的字段
+use std::fs;
+
+use serde::Deserialize;
+
+/// 支持的报告版本，与 `user/apps/final_test/src/report.rs` 中的 `REPORT_VERSION` 一致
+pub const REPORT_VERSION: u32 = 1;
+
+#[derive(Debug, Deserialize)]
+pub struct Report {
+    pub version: u32,
+    pub kernel: Kernel,
+    pub suites: Vec<Suite>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Kernel {
+    pub release: String,
+    pub version: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Suite {
+    pub name: String,
+    pub status: String,
+    pub cases: Vec<Case>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Case {
+    pub name: String,
+    pub result: String,
+    pub elapsed_ms: u64,
+}
+
+impl Report {
+    pub fn parse(json: &str) -> Result<Self, String> {
+        let report: Report = serde_json::from_str(json).map_err(|e| e.to_string())?;
+        if report.version != REPORT_VERSION {
+            return Err(format!("unsupported report version {}", report.version));
+        }
+        Ok(report)
+    }
+
+    pub fn load(path: &str) -> Result<Self, String> {
+        let json = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+        Self::parse(&json).map_err(|e| format!("{}: {}", path, e))
+    }
+}
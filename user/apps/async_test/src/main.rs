@@ -0,0 +1,95 @@
+#![no_std]
+#![no_main]
+#![feature(ip_in_core)]
+
+extern crate Mstd;
+
+use core::net::{Ipv4Addr, SocketAddrV4};
+use core::time::Duration;
+use Mstd::println;
+use Mstd::runtime::{block_on, sleep, spawn, timeout, Instant, TcpListener, TcpStream, UdpSocket};
+use Mstd::socket::{getsockopt, setsockopt, SockOpt, SockOptName};
+
+const PORT: u16 = 8898;
+
+#[no_mangle]
+fn main() {
+    println!("async_tests...");
+    block_on(async {
+        timer_test().await;
+        tcp_test().await;
+        udp_test().await;
+    });
+    println!("async_tests passed!");
+}
+
+async fn timer_test() {
+    println!("timer_test...");
+    let start = Instant::now();
+    let fast = spawn(async {
+        sleep(Duration::from_millis(50)).await;
+        Instant::now()
+    });
+    let slow = spawn(async {
+        sleep(Duration::from_millis(100)).await;
+        Instant::now()
+    });
+    let (fast, slow) = (fast.await, slow.await);
+    assert!(fast - start >= Duration::from_millis(50));
+    assert!(slow >= fast);
+    let res = timeout(Duration::from_millis(20), sleep(Duration::from_secs(10))).await;
+    assert!(res.is_err());
+    println!("timer_test passed, elapsed {:?}", start.elapsed());
+}
+
+async fn tcp_test() {
+    println!("tcp_test...");
+    let addr = SocketAddrV4::new(Ipv4Addr::LOCALHOST, PORT);
+    let listener = TcpListener::bind(addr).unwrap();
+    let server = spawn(async move {
+        let (stream, peer) = listener.accept().await.unwrap();
+        println!("tcp server accept {}", peer);
+        let mut buf = [0u8; 64];
+        loop {
+            let len = stream.read(&mut buf).await.unwrap();
+            if len == 0 {
+                break;
+            }
+            stream.write_all(&buf[..len]).await.unwrap();
+        }
+    });
+    let stream = TcpStream::connect(addr).await.unwrap();
+    assert_eq!(setsockopt(stream.as_raw_fd(), SockOpt::TcpNoDelay(true)), 0);
+    let recv_buffer = getsockopt(stream.as_raw_fd(), SockOptName::RecvBuffer).unwrap();
+    println!("tcp client {:?}", recv_buffer);
+    let msg = b"Hello, this is Alien's async client";
+    stream.write_all(msg).await.unwrap();
+    let mut buf = [0u8; 64];
+    let mut read = 0;
+    while read < msg.len() {
+        read += stream.read(&mut buf[read..]).await.unwrap();
+    }
+    assert_eq!(&buf[..read], msg);
+    drop(stream);
+    server.await;
+    println!("tcp_test passed");
+}
+
+async fn udp_test() {
+    println!("udp_test...");
+    let a = UdpSocket::bind(SocketAddrV4::new(Ipv4Addr::LOCALHOST, PORT + 1)).unwrap();
+    let b = UdpSocket::bind(SocketAddrV4::new(Ipv4Addr::LOCALHOST, PORT + 2)).unwrap();
+    let b_addr = b.local_addr().unwrap();
+    let receiver = spawn(async move {
+        let mut buf = [0u8; 16];
+        let (len, from) = b.recv_from(&mut buf).await.unwrap();
+        assert_eq!(&buf[..len], b"ping");
+        b.send_to(b"pong", from).await.unwrap();
+    });
+    a.send_to(b"ping", b_addr).await.unwrap();
+    let mut buf = [0u8; 16];
+    let (len, _) = a.recv_from(&mut buf).await.unwrap();
+    assert_eq!(&buf[..len], b"pong");
+    receiver.await;
+    println!("udp_test passed");
+}
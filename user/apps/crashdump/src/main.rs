@@ -0,0 +1,250 @@
+//! 读取 `/proc/crashdump` 中上一次内核崩溃时保存的转储，打印 panic 信息和任务列表，
+//! 并将其转换为 ELF core 文件，可以通过 `gdb kernel-qemu <core>` 离线分析。
+//!
+//! 用法：`crashdump [output]`，默认输出到 `/crash.core`
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use Mstd::fs::{close, open, read, write, OpenFlags};
+use Mstd::println;
+
+const DUMP_FILE: &str = "/proc/crashdump\0";
+const DEFAULT_OUTPUT: &str = "/crash.core";
+
+const DUMP_MAGIC: &[u8; 8] = b"ALIENDMP";
+const DUMP_VERSION: u32 = 1;
+const HEADER_SIZE: usize = 32;
+
+const RECORD_MESSAGE: u32 = 1;
+const RECORD_REGS: u32 = 2;
+const RECORD_LOG: u32 = 3;
+const RECORD_MEMORY: u32 = 4;
+const RECORD_TASKS: u32 = 5;
+
+const ELF_HEADER_SIZE: usize = 64;
+const PROGRAM_HEADER_SIZE: usize = 56;
+const ET_CORE: u16 = 4;
+const EM_RISCV: u16 = 243;
+const PT_LOAD: u32 = 1;
+const PT_NOTE: u32 = 4;
+const PF_W: u32 = 2;
+const PF_R: u32 = 4;
+const NT_PRSTATUS: u32 = 1;
+
+/// riscv64 中 `struct elf_prstatus` 的大小，以及其中 `pr_reg` 的偏移
+const PRSTATUS_SIZE: usize = 376;
+const PRSTATUS_REG_OFFSET: usize = 112;
+const SIGABRT: u16 = 6;
+
+/// 转储中的一条记录
+struct Record<'a> {
+    kind: u32,
+    data: &'a [u8],
+}
+
+#[no_mangle]
+fn main(_argc: usize, argv: Vec<String>) -> isize {
+    let output = argv.get(1).map(String::as_str).unwrap_or(DEFAULT_OUTPUT);
+    let dump = match read_file(DUMP_FILE) {
+        Some(dump) => dump,
+        None => {
+            println!("crashdump: no crash dump from the previous boot");
+            return -1;
+        }
+    };
+    let records = match parse_dump(&dump) {
+        Some(records) => records,
+        None => {
+            println!("crashdump: invalid crash dump");
+            return -1;
+        }
+    };
+    for record in records.iter() {
+        match record.kind {
+            RECORD_MESSAGE | RECORD_TASKS => println!("{}", String::from_utf8_lossy(record.data)),
+            _ => {}
+        }
+    }
+    let core = build_core(&records);
+    if !write_file(output, &core) {
+        println!("crashdump: failed to write {}", output);
+        return -1;
+    }
+    println!("crashdump: core file written to {}", output);
+    0
+}
+
+fn read_file(path: &str) -> Option<Vec<u8>> {
+    let fd = open(path, OpenFlags::O_RDONLY);
+    if fd < 0 {
+        return None;
+    }
+    let mut data = Vec::new();
+    let mut buf = [0u8; 4096];
+    loop {
+        let len = read(fd as usize, &mut buf);
+        if len <= 0 {
+            break;
+        }
+        data.extend_from_slice(&buf[..len as usize]);
+    }
+    close(fd as usize);
+    Some(data)
+}
+
+fn write_file(path: &str, data: &[u8]) -> bool {
+    let mut path = String::from(path);
+    path.push('\0');
+    let fd = open(
+        &path,
+        OpenFlags::O_WRONLY | OpenFlags::O_CREAT | OpenFlags::O_TRUNC,
+    );
+    if fd < 0 {
+        return false;
+    }
+    let mut written = 0;
+    while written < data.len() {
+        let len = write(fd as usize, &data[written..]);
+        if len <= 0 {
+            break;
+        }
+        written += len as usize;
+    }
+    close(fd as usize);
+    written == data.len()
+}
+
+fn u32_at(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+}
+
+fn u64_at(data: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+}
+
+/// 检查转储的头部并拆分出其中的记录
+fn parse_dump(dump: &[u8]) -> Option<Vec<Record>> {
+    if dump.len() < HEADER_SIZE || &dump[..8] != DUMP_MAGIC || u32_at(dump, 8) != DUMP_VERSION {
+        return None;
+    }
+    let size = (u64_at(dump, 16) as usize).min(dump.len());
+    let mut records = Vec::new();
+    let mut offset = HEADER_SIZE;
+    while offset + 8 <= size {
+        let kind = u32_at(dump, offset);
+        let len = u32_at(dump, offset + 4) as usize;
+        let start = offset + 8;
+        if start + len > size {
+            break;
+        }
+        records.push(Record {
+            kind,
+            data: &dump[start..start + len],
+        });
+        offset = start + ((len + 7) & !7);
+    }
+    Some(records)
+}
+
+/// 向 `out` 中追加一个 ELF note
+fn push_note(out: &mut Vec<u8>, name: &str, kind: u32, desc: &[u8]) {
+    let name_len = name.len() + 1;
+    out.extend_from_slice(&(name_len as u32).to_le_bytes());
+    out.extend_from_slice(&(desc.len() as u32).to_le_bytes());
+    out.extend_from_slice(&kind.to_le_bytes());
+    out.extend_from_slice(name.as_bytes());
+    out.push(0);
+    out.resize((out.len() + 3) & !3, 0);
+    out.extend_from_slice(desc);
+    out.resize((out.len() + 3) & !3, 0);
+}
+
+/// 寄存器记录转换为 `NT_PRSTATUS`，GDB 中的线程号为 hart id + 1，第一个线程是发生 panic 的核
+fn prstatus(regs: &[u8], first: bool) -> Option<Vec<u8>> {
+    if regs.len() < 34 * 8 {
+        return None;
+    }
+    let hart = u64_at(regs, 0);
+    let mut desc = alloc::vec![0u8; PRSTATUS_SIZE];
+    if first {
+        desc[12..14].copy_from_slice(&SIGABRT.to_le_bytes());
+    }
+    desc[32..36].copy_from_slice(&(hart as u32 + 1).to_le_bytes());
+    // pr_reg 依次为 pc 和 x1-x31，与转储中跳过 hart id 和 x0 后的顺序一致
+    let pr_reg = &mut desc[PRSTATUS_REG_OFFSET..PRSTATUS_REG_OFFSET + 32 * 8];
+    pr_reg[..8].copy_from_slice(&regs[8..16]);
+    pr_reg[8..].copy_from_slice(&regs[24..34 * 8]);
+    Some(desc)
+}
+
+fn build_core(records: &[Record]) -> Vec<u8> {
+    let mut notes = Vec::new();
+    let mut first = true;
+    for record in records.iter() {
+        match record.kind {
+            RECORD_REGS => {
+                if let Some(desc) = prstatus(record.data, first) {
+                    push_note(&mut notes, "CORE", NT_PRSTATUS, &desc);
+                    first = false;
+                }
+            }
+            // 其它文本记录保存在自定义的 note 中
+            RECORD_MESSAGE | RECORD_LOG | RECORD_TASKS => {
+                push_note(&mut notes, "ALIEN", record.kind, record.data)
+            }
+            _ => {}
+        }
+    }
+    let memory = records
+        .iter()
+        .filter(|record| record.kind == RECORD_MEMORY && record.data.len() >= 8)
+        .collect::<Vec<_>>();
+
+    let phnum = 1 + memory.len();
+    let mut offset = ELF_HEADER_SIZE + phnum * PROGRAM_HEADER_SIZE;
+    let mut core = Vec::new();
+    // ELF 头部
+    core.extend_from_slice(b"\x7fELF");
+    core.extend_from_slice(&[2, 1, 1, 0]);
+    core.resize(16, 0);
+    core.extend_from_slice(&ET_CORE.to_le_bytes());
+    core.extend_from_slice(&EM_RISCV.to_le_bytes());
+    core.extend_from_slice(&1u32.to_le_bytes());
+    core.extend_from_slice(&0u64.to_le_bytes());
+    core.extend_from_slice(&(ELF_HEADER_SIZE as u64).to_le_bytes());
+    core.extend_from_slice(&0u64.to_le_bytes());
+    // e_flags: RVC | 双精度浮点
+    core.extend_from_slice(&0x5u32.to_le_bytes());
+    core.extend_from_slice(&(ELF_HEADER_SIZE as u16).to_le_bytes());
+    core.extend_from_slice(&(PROGRAM_HEADER_SIZE as u16).to_le_bytes());
+    core.extend_from_slice(&(phnum as u16).to_le_bytes());
+    core.extend_from_slice(&[0; 6]);
+
+    let mut push_phdr = |kind: u32, flags: u32, vaddr: u64, size: usize, offset: usize| {
+        core.extend_from_slice(&kind.to_le_bytes());
+        core.extend_from_slice(&flags.to_le_bytes());
+        core.extend_from_slice(&(offset as u64).to_le_bytes());
+        core.extend_from_slice(&vaddr.to_le_bytes());
+        core.extend_from_slice(&vaddr.to_le_bytes());
+        core.extend_from_slice(&(size as u64).to_le_bytes());
+        core.extend_from_slice(&(size as u64).to_le_bytes());
+        core.extend_from_slice(&(if kind == PT_NOTE { 4u64 } else { 1 }).to_le_bytes());
+    };
+    push_phdr(PT_NOTE, 0, 0, notes.len(), offset);
+    offset += notes.len();
+    for record in memory.iter() {
+        let size = record.data.len() - 8;
+        push_phdr(PT_LOAD, PF_R | PF_W, u64_at(record.data, 0), size, offset);
+        offset += size;
+    }
+    core.extend_from_slice(&notes);
+    for record in memory.iter() {
+        core.extend_from_slice(&record.data[8..]);
+    }
+    core
+}
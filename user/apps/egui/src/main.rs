@@ -10,7 +10,7 @@ use simplegui::GPUDevice;
 use Mstd::gui::embedded_graphics::geometry::{Point, Size};
 use Mstd::io::{flush_frame_buffer, frame_buffer, VIRTGPU_XRES, VIRTGPU_YRES};
 use Mstd::println;
-use Mstd::sync::mutex::Mutex;
+use Mstd::sync::spin::Mutex;
 
 struct GpuDevice {
     frame_buffer: &'static mut [u8],
@@ -1,64 +1,400 @@
+//! 比赛测试用例的运行器
+//!
+//! 依次运行 `tests/testbin-second-stage` 中的测试脚本，解析每个测试点的结果和耗时，超时时杀死整个测试进程组，
+//! 最后将 JSON 格式的报告写入磁盘镜像，可以用 `tools/conformance` 比较两次运行的报告。
+//!
+//! 用法：`final_test [-o report] [suite...]`，默认运行所有测试，报告写入测试目录下的 `conformance.json`
 #![no_main]
 #![no_std]
 
-use Mstd::process::{exec, exit, fork, waitpid};
-use Mstd::thread::m_yield;
+extern crate alloc;
+
+mod parser;
+mod report;
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use Mstd::fs::{OpenFlags, PollEvents, PollFd, AT_FDCWD};
+use Mstd::linux::{
+    chdir, clock_gettime, close, dup3, execve, exit, faccessat, fsync, kill, openat, pipe2, ppoll,
+    read, setpgid, sync, uname, wait4, write,
+};
+use Mstd::process::fork;
+use Mstd::time::{TimeSpec, CLOCK_MONOTONIC};
 use Mstd::{println, system_shutdown};
 
+use parser::{Event, Format, Outcome, Parser};
+use report::{CaseResult, Status, SuiteResult};
+
+const DEFAULT_REPORT: &str = "conformance.json";
+const TEST_DIR: &str = "/tests";
+
+const WNOHANG: u32 = 1;
+const SIGKILL: usize = 9;
+/// 测试脚本无法执行时子进程的退出码
+const EXEC_FAILED: i32 = 127;
+/// 测试脚本退出后，等待其遗留的后台进程关闭输出的时间
+const DRAIN_MS: usize = 5_000;
+
+/// 一个测试脚本
+struct Suite {
+    name: &'static str,
+    command: &'static str,
+    format: Format,
+    /// 整个测试脚本的超时时间
+    timeout_s: usize,
+    /// 相邻两个测试点之间的超时时间，超过时认为测试卡死
+    case_timeout_s: usize,
+}
+
+const fn suite(
+    name: &'static str,
+    command: &'static str,
+    format: Format,
+    timeout_s: usize,
+    case_timeout_s: usize,
+) -> Suite {
+    Suite {
+        name,
+        command,
+        format,
+        timeout_s,
+        case_timeout_s,
+    }
+}
+
+#[rustfmt::skip]
+const SUITES: &[Suite] = &[
+    suite("time-test", "./time-test", Format::Exit, 60, 60),
+    suite("interrupts-test-1", "./interrupts-test-1", Format::Exit, 60, 60),
+    suite("interrupts-test-2", "./interrupts-test-2", Format::Exit, 60, 60),
+    suite("copy-file-range-test-1", "./copy-file-range-test-1", Format::Exit, 60, 60),
+    suite("copy-file-range-test-2", "./copy-file-range-test-2", Format::Exit, 60, 60),
+    suite("copy-file-range-test-3", "./copy-file-range-test-3", Format::Exit, 60, 60),
+    suite("copy-file-range-test-4", "./copy-file-range-test-4", Format::Exit, 60, 60),
+    suite("lua", "./lua_testcode.sh", Format::Testcase, 300, 60),
+    suite("busybox", "./busybox_testcode.sh", Format::Testcase, 600, 60),
+    suite("libc-static", "./run-static.sh", Format::Libc, 900, 60),
+    suite("libc-dynamic", "./run-dynamic.sh", Format::Libc, 900, 60),
+    suite("libc-bench", "./libc-bench", Format::Exit, 300, 300),
+    suite("cyclictest", "./cyclictest_testcode.sh", Format::Marker, 900, 300),
+    suite("netperf", "./netperf_testcode.sh", Format::Marker, 900, 180),
+    suite("iperf", "./iperf_testcode.sh", Format::Marker, 900, 180),
+    suite("lmbench", "./lmbench_testcode.sh", Format::Exit, 1800, 1800),
+    suite("iozone", "./iozone_testcode.sh", Format::Exit, 1800, 1800),
+    suite("unixbench", "./unixbench_testcode.sh", Format::Unixbench, 3600, 300),
+];
+
+const BASH_ENV: &[&str] = &[
+    "SHELL=/bash",
+    "PWD=/",
+    "LOGNAME=root",
+    "MOTD_SHOWN=pam",
+    "HOME=/root",
+    "LANG=C.UTF-8",
+    "TERM=vt220",
+    "USER=root",
+    "SHLVL=0",
+    "OLDPWD=/root",
+    "PS1=\x1b[1m\x1b[32mAlien\x1b[0m:\x1b[1m\x1b[34m\\w\x1b[0m\\$ ",
+    "_=/bin/bash",
+    "PATH=/:/bin:/sbin:/tests",
+    "LD_LIBRARY_PATH=/tests:/bin",
+];
+
 #[no_mangle]
-fn main() -> isize {
-    run_test();
+fn main(_argc: usize, argv: Vec<String>) -> isize {
+    let mut output = DEFAULT_REPORT.to_string();
+    let mut filters = Vec::new();
+    let mut args = argv.into_iter().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-o" => match args.next() {
+                Some(path) => output = path,
+                None => println!("final_test: -o requires a path"),
+            },
+            _ => filters.push(arg),
+        }
+    }
+    enter_test_dir();
+    let results = SUITES
+        .iter()
+        .filter(|suite| filters.is_empty() || filters.iter().any(|f| f == suite.name))
+        .map(run_suite)
+        .collect::<Vec<_>>();
+    println!("====== conformance summary ======");
+    for result in &results {
+        println!(
+            "{}: {} ({} passed, {} failed, {} timeout, {}ms)",
+            result.name,
+            result.status.as_str(),
+            result.count(Outcome::Pass),
+            result.count(Outcome::Fail),
+            result.count(Outcome::Timeout),
+            result.elapsed_ms
+        );
+    }
+    match write_report(&output, &results) {
+        Ok(()) => println!("report written to {}", output),
+        Err(e) => println!("final_test: failed to write {}: {:?}", output, e),
+    }
     println!("!TEST FINISH!");
     system_shutdown();
 }
-fn run_test() {
-    let commands = [
-        "./time-test\0",
-        "./interrupts-test-1\0",
-        "./interrupts-test-2\0",
-        "./copy-file-range-test-1\0",
-        "./copy-file-range-test-2\0",
-        "./copy-file-range-test-3\0",
-        "./copy-file-range-test-4\0",
-        "./lua_testcode.sh\0",
-        "./busybox_testcode.sh\0",
-        "./run-static.sh\0",
-        "./run-dynamic.sh\0",
-        "./libc-bench\0",
-        "./cyclictest_testcode.sh\0",
-        "./netperf_testcode.sh\0",
-        "./iperf_testcode.sh\0",
-        "./lmbench_testcode.sh\0",
-        "./iozone_testcode.sh\0",
-        "./unixbench_testcode.sh\0",
-    ];
-    commands.into_iter().for_each(|app| {
-        let args = [app.as_ptr()];
-        let pid = fork();
-        if pid == 0 {
-            exec(app, &args, BASH_ENV);
-            exit(0);
+
+/// 测试程序使用相对路径，在根目录下启动时(如作为 init 程序)切换到磁盘镜像的挂载点
+fn enter_test_dir() {
+    if faccessat(AT_FDCWD, "busybox_testcode.sh", 0, 0).is_err() {
+        let _ = chdir(TEST_DIR);
+    }
+}
+
+fn now_ms() -> usize {
+    let ts = clock_gettime(CLOCK_MONOTONIC).unwrap_or_default();
+    ts.tv_sec * 1000 + ts.tv_nsec / 1_000_000
+}
+
+/// 记录测试点的结果，测试点的耗时为距上一个事件的时间
+struct Tracker {
+    parser: Parser,
+    last_ms: usize,
+    cases: Vec<CaseResult>,
+    /// 还没有凑成一行的输出
+    pending: Vec<u8>,
+}
+
+impl Tracker {
+    fn new(format: Format, now: usize) -> Self {
+        Self {
+            parser: Parser::new(format),
+            last_ms: now,
+            cases: Vec::new(),
+            pending: Vec::new(),
+        }
+    }
+
+    fn feed(&mut self, data: &[u8]) {
+        self.pending.extend_from_slice(data);
+        while let Some(pos) = self.pending.iter().position(|&b| b == b'\n') {
+            let line = self.pending.drain(..=pos).collect::<Vec<_>>();
+            self.feed_line(&String::from_utf8_lossy(&line));
+        }
+    }
+
+    fn feed_line(&mut self, line: &str) {
+        match self.parser.feed(line) {
+            Some(Event::Begin) => self.last_ms = now_ms(),
+            Some(Event::Case {
+                name,
+                outcome,
+                metric,
+            }) => {
+                let now = now_ms();
+                self.cases.push(CaseResult {
+                    name,
+                    outcome,
+                    elapsed_ms: now - self.last_ms,
+                    metric,
+                });
+                self.last_ms = now;
+            }
+            None => {}
+        }
+    }
+
+    /// 输出结束，处理最后一行不完整的输出
+    fn finish(&mut self) {
+        if !self.pending.is_empty() {
+            let line = core::mem::take(&mut self.pending);
+            self.feed_line(&String::from_utf8_lossy(&line));
+        }
+    }
+
+    /// 测试被杀死，将正在运行的测试点记为超时
+    fn timeout(&mut self) {
+        self.finish();
+        if let Some(name) = self.parser.running() {
+            self.cases.push(CaseResult {
+                name: name.to_string(),
+                outcome: Outcome::Timeout,
+                elapsed_ms: now_ms() - self.last_ms,
+                metric: None,
+            });
+        }
+    }
+}
+
+fn run_suite(suite: &Suite) -> SuiteResult {
+    println!("====== final_test {} begin ======", suite.name);
+    let start = now_ms();
+    let mut result = SuiteResult {
+        name: suite.name,
+        command: suite.command,
+        status: Status::Error,
+        exit_code: None,
+        signal: None,
+        elapsed_ms: 0,
+        cases: Vec::new(),
+    };
+    let [rfd, wfd] = match pipe2(OpenFlags::O_CLOEXEC) {
+        Ok(fds) => fds,
+        Err(e) => {
+            println!("final_test: pipe2 failed: {:?}", e);
+            return result;
+        }
+    };
+    let pid = fork();
+    if pid == 0 {
+        // 子进程作为新进程组的组长，超时时可以通过进程组杀死测试脚本创建的所有进程
+        let _ = setpgid(0, 0);
+        let _ = dup3(wfd, 1, OpenFlags::empty());
+        let _ = dup3(wfd, 2, OpenFlags::empty());
+        let err = execve(suite.command, &[suite.command], BASH_ENV);
+        println!("final_test: failed to exec {}: {:?}", suite.command, err);
+        exit(EXEC_FAILED);
+    }
+    let _ = close(wfd);
+    if pid < 0 {
+        println!("final_test: fork failed");
+        let _ = close(rfd);
+        return result;
+    }
+    let pid = pid as usize;
+    // 与子进程中的 setpgid 竞争，保证 kill 之前进程组已经建立
+    let _ = setpgid(pid, pid);
+
+    let mut tracker = Tracker::new(suite.format, start);
+    let mut status = None;
+    let mut timed_out = false;
+    let mut eof = false;
+    let mut drain_deadline = usize::MAX;
+    let mut buf = [0u8; 512];
+    // 测试脚本可能关闭了输出而仍在运行，输出结束并且回收了测试脚本后才退出循环
+    while !(eof && status.is_some()) {
+        let now = now_ms();
+        if status.is_none()
+            && (now >= start + suite.timeout_s * 1000
+                || now >= tracker.last_ms + suite.case_timeout_s * 1000)
+        {
+            println!(
+                "final_test: {} timed out, killing process group {}",
+                suite.name, pid
+            );
+            timed_out = true;
+            let _ = kill(pid.wrapping_neg(), SIGKILL);
+            break;
+        }
+        if now >= drain_deadline {
+            break;
+        }
+        let mut fds = [PollFd::new(rfd, PollEvents::IN)];
+        let fds = if eof { &mut fds[..0] } else { &mut fds[..] };
+        let timeout = TimeSpec {
+            tv_sec: 1,
+            tv_nsec: 0,
+        };
+        if let Ok(n) = ppoll(fds, Some(&timeout)) {
+            if n > 0 && !fds[0].revents.is_empty() {
+                match read(rfd, &mut buf) {
+                    Ok(0) | Err(_) => eof = true,
+                    Ok(n) => {
+                        let _ = write(1, &buf[..n]);
+                        tracker.feed(&buf[..n]);
+                    }
+                }
+            }
+        }
+        if status.is_none() {
+            if let Ok((child, code)) = wait4(pid as isize, WNOHANG, None) {
+                if child == pid {
+                    status = Some(code);
+                    // 测试脚本已经退出，杀死其遗留在后台的进程
+                    let _ = kill(pid.wrapping_neg(), SIGKILL);
+                    drain_deadline = now_ms() + DRAIN_MS;
+                }
+            }
+        }
+    }
+    let _ = close(rfd);
+    if timed_out {
+        tracker.timeout();
+        status = wait4(pid as isize, 0, None).ok().map(|(_, code)| code);
+    } else {
+        tracker.finish();
+    }
+    reap_orphans();
+
+    if let Some(code) = status {
+        if code & 0x7f == 0 {
+            result.exit_code = Some((code >> 8) & 0xff);
+        } else {
+            result.signal = Some(code & 0x7f);
+        }
+    }
+    result.cases = tracker.cases;
+    result.elapsed_ms = now_ms() - start;
+    result.status = if timed_out {
+        Status::Timeout
+    } else if result.exit_code == Some(EXEC_FAILED) && result.cases.is_empty() {
+        Status::Error
+    } else if suite.format == Format::Exit {
+        if result.exit_code == Some(0) {
+            Status::Passed
         } else {
-            m_yield();
-            let mut exit_code: i32 = 0;
-            let _x = waitpid(pid as usize, &mut exit_code);
+            Status::Failed
         }
-    });
+    } else if !result.cases.is_empty() && result.count(Outcome::Pass) == result.cases.len() {
+        // 测试脚本的退出码是最后一条命令的退出码，只根据测试点判断
+        Status::Passed
+    } else {
+        Status::Failed
+    };
+    println!(
+        "====== final_test {} end: {} ======",
+        suite.name,
+        result.status.as_str()
+    );
+    result
+}
+
+/// 回收被过继给当前进程的孤儿进程
+fn reap_orphans() {
+    while let Ok((pid, _)) = wait4(-1, WNOHANG, None) {
+        if pid == 0 {
+            break;
+        }
+    }
+}
+
+/// `utsname` 中以 `\0` 结尾的字段
+fn c_field(field: &[u8]) -> &str {
+    let len = field.iter().position(|&b| b == 0).unwrap_or(field.len());
+    core::str::from_utf8(&field[..len]).unwrap_or("")
+}
+
+fn write_report(path: &str, results: &[SuiteResult]) -> Mstd::linux::Result<()> {
+    let name = uname()?;
+    let json = report::to_json(c_field(&name.release), c_field(&name.version), results);
+    let fd = openat(
+        AT_FDCWD,
+        path,
+        OpenFlags::O_WRONLY | OpenFlags::O_CREAT | OpenFlags::O_TRUNC,
+        0o644,
+    )?;
+    let mut data = json.as_bytes();
+    while !data.is_empty() {
+        match write(fd, data) {
+            Ok(0) => break,
+            Ok(n) => data = &data[n..],
+            Err(e) => {
+                let _ = close(fd);
+                return Err(e);
+            }
+        }
+    }
+    // 测试结束后直接关机，需要保证报告已经写回磁盘
+    fsync(fd)?;
+    close(fd)?;
+    sync()
 }
-const BASH_ENV: &[*const u8] = &[
-    "SHELL=/bash\0".as_ptr(),
-    "PWD=/\0".as_ptr(),
-    "LOGNAME=root\0".as_ptr(),
-    "MOTD_SHOWN=pam\0".as_ptr(),
-    "HOME=/root\0".as_ptr(),
-    "LANG=C.UTF-8\0".as_ptr(),
-    "TERM=vt220\0".as_ptr(),
-    "USER=root\0".as_ptr(),
-    "SHLVL=0\0".as_ptr(),
-    "OLDPWD=/root\0".as_ptr(),
-    "PS1=\x1b[1m\x1b[32mAlien\x1b[0m:\x1b[1m\x1b[34m\\w\x1b[0m\\$ \0".as_ptr(),
-    "_=/bin/bash\0".as_ptr(),
-    "PATH=/:/bin:/sbin:/tests\0".as_ptr(),
-    "LD_LIBRARY_PATH=/tests:/bin\0".as_ptr(),
-    core::ptr::null(),
-];
@@ -0,0 +1,293 @@
+//! 各个测试脚本输出格式的解析
+//!
+//! 测试脚本的输出按行交给 [`Parser::feed`]，每识别出一个测试点的结果就返回一个 [`Event`]。
+use alloc::string::{String, ToString};
+
+/// 测试点的结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Pass,
+    Fail,
+    Timeout,
+}
+
+impl Outcome {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Outcome::Pass => "pass",
+            Outcome::Fail => "fail",
+            Outcome::Timeout => "timeout",
+        }
+    }
+}
+
+/// 解析器从输出中识别出的事件
+#[derive(Debug, PartialEq)]
+pub enum Event {
+    /// 一个测试点开始运行，用于计算该测试点的耗时
+    Begin,
+    /// 一个测试点结束，`metric` 为性能测试输出的数值(带单位)
+    Case {
+        name: String,
+        outcome: Outcome,
+        metric: Option<String>,
+    },
+}
+
+/// 测试脚本的输出格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// 不解析输出，只根据退出码判断
+    Exit,
+    /// busybox/lua: `testcase <suite> <cmd> success|fail`
+    Testcase,
+    /// libc-test: `========== START <name> ==========`、`Pass!`、`========== END <name> ==========`
+    Libc,
+    /// netperf/iperf/cyclictest: `====== <tool> <name> begin ======`、`====== <tool> <name> end: success|fail ======`
+    Marker,
+    /// unixbench: `Unixbench <name> test(<unit>): <value>`
+    Unixbench,
+}
+
+/// 按行解析测试脚本输出的状态机
+pub struct Parser {
+    format: Format,
+    /// 正在运行的测试点
+    running: Option<String>,
+    /// libc-test 中当前测试点是否输出了 `Pass!`
+    passed: bool,
+}
+
+impl Parser {
+    pub fn new(format: Format) -> Self {
+        Self {
+            format,
+            running: None,
+            passed: false,
+        }
+    }
+
+    /// 正在运行而没有结束的测试点，测试超时被杀死时将其记为超时
+    pub fn running(&self) -> Option<&str> {
+        self.running.as_deref()
+    }
+
+    pub fn feed(&mut self, line: &str) -> Option<Event> {
+        let line = line.trim_end_matches('\r').trim();
+        match self.format {
+            Format::Exit => None,
+            Format::Testcase => parse_testcase(line),
+            Format::Libc => self.parse_libc(line),
+            Format::Marker => self.parse_marker(line),
+            Format::Unixbench => parse_unixbench(line),
+        }
+    }
+
+    fn parse_libc(&mut self, line: &str) -> Option<Event> {
+        if line == "Pass!" {
+            self.passed = true;
+            return None;
+        }
+        let body = line
+            .strip_prefix("==========")?
+            .strip_suffix("==========")?
+            .trim();
+        if let Some(name) = body.strip_prefix("START ") {
+            self.running = Some(name.trim().to_string());
+            self.passed = false;
+            Some(Event::Begin)
+        } else if let Some(name) = body.strip_prefix("END ") {
+            self.running = None;
+            let outcome = if self.passed {
+                Outcome::Pass
+            } else {
+                Outcome::Fail
+            };
+            self.passed = false;
+            Some(Event::Case {
+                name: name.trim().to_string(),
+                outcome,
+                metric: None,
+            })
+        } else {
+            None
+        }
+    }
+
+    fn parse_marker(&mut self, line: &str) -> Option<Event> {
+        let body = line.strip_prefix("======")?.strip_suffix("======")?.trim();
+        if let Some(name) = body.strip_suffix(" begin") {
+            self.running = Some(name.trim().to_string());
+            return Some(Event::Begin);
+        }
+        let (name, result) = body.rsplit_once(" end:")?;
+        self.running = None;
+        Some(Event::Case {
+            name: name.trim().to_string(),
+            outcome: outcome(result.trim())?,
+            metric: None,
+        })
+    }
+}
+
+fn outcome(result: &str) -> Option<Outcome> {
+    match result {
+        "success" => Some(Outcome::Pass),
+        "fail" => Some(Outcome::Fail),
+        _ => None,
+    }
+}
+
+fn parse_testcase(line: &str) -> Option<Event> {
+    let (name, result) = line.strip_prefix("testcase ")?.rsplit_once(' ')?;
+    Some(Event::Case {
+        name: name.trim().to_string(),
+        outcome: outcome(result)?,
+        metric: None,
+    })
+}
+
+fn parse_unixbench(line: &str) -> Option<Event> {
+    let (name, rest) = line.strip_prefix("Unixbench ")?.split_once(" test(")?;
+    let (unit, value) = rest.split_once("):")?;
+    let value = value.trim();
+    // 测试程序异常退出时没有输出数值
+    let (outcome, metric) = if value.parse::<f64>().is_ok() {
+        (Outcome::Pass, Some(alloc::format!("{} {}", value, unit)))
+    } else {
+        (Outcome::Fail, None)
+    };
+    Some(Event::Case {
+        name: name.trim().to_string(),
+        outcome,
+        metric,
+    })
+}
+
+/// 解析器不依赖 Alien，由 `tools/conformance` 引用后在宿主机上测试
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+
+    fn case(name: &str, outcome: Outcome, metric: Option<&str>) -> Event {
+        Event::Case {
+            name: name.to_string(),
+            outcome,
+            metric: metric.map(|m| m.to_string()),
+        }
+    }
+
+    fn feed_all(parser: &mut Parser, lines: &[&str]) -> Vec<Event> {
+        lines.iter().filter_map(|line| parser.feed(line)).collect()
+    }
+
+    #[test]
+    fn test_testcase() {
+        let mut parser = Parser::new(Format::Testcase);
+        let events = feed_all(
+            &mut parser,
+            &[
+                "testcase busybox echo \"#### independent command test\" success\r",
+                "#### independent command test",
+                "testcase lua sin30.lua fail",
+                "testcase busybox ls unknown",
+            ],
+        );
+        assert_eq!(
+            events,
+            [
+                case(
+                    "busybox echo \"#### independent command test\"",
+                    Outcome::Pass,
+                    None
+                ),
+                case("lua sin30.lua", Outcome::Fail, None),
+            ]
+        );
+        assert_eq!(parser.running(), None);
+    }
+
+    #[test]
+    fn test_libc() {
+        let mut parser = Parser::new(Format::Libc);
+        let events = feed_all(
+            &mut parser,
+            &[
+                "========== START entry-static.exe argv ==========",
+                "Pass!",
+                "========== END entry-static.exe argv ==========",
+                "========== START entry-static.exe fdopen ==========",
+                "src/functional/fdopen.c:22: fopen failed",
+                "========== END entry-static.exe fdopen ==========",
+                "========== START entry-static.exe pthread_cancel ==========",
+            ],
+        );
+        assert_eq!(
+            events,
+            [
+                Event::Begin,
+                case("entry-static.exe argv", Outcome::Pass, None),
+                Event::Begin,
+                case("entry-static.exe fdopen", Outcome::Fail, None),
+                Event::Begin,
+            ]
+        );
+        // 最后一个测试点没有结束，超时时按它记录
+        assert_eq!(parser.running(), Some("entry-static.exe pthread_cancel"));
+    }
+
+    #[test]
+    fn test_marker() {
+        let mut parser = Parser::new(Format::Marker);
+        let events = feed_all(
+            &mut parser,
+            &[
+                "====== netperf UDP_STREAM begin ======",
+                "212992    1024   10.00      123456      0     101.14",
+                "====== netperf UDP_STREAM end: success ======",
+                "====== iperf BASIC_TCP begin ======",
+                "====== iperf BASIC_TCP end: fail ======",
+                "====== cyclictest NO_STRESS_P1 end: unknown ======",
+            ],
+        );
+        assert_eq!(
+            events,
+            [
+                Event::Begin,
+                case("netperf UDP_STREAM", Outcome::Pass, None),
+                Event::Begin,
+                case("iperf BASIC_TCP", Outcome::Fail, None),
+            ]
+        );
+        assert_eq!(parser.running(), None);
+    }
+
+    #[test]
+    fn test_unixbench() {
+        let mut parser = Parser::new(Format::Unixbench);
+        let events = feed_all(
+            &mut parser,
+            &[
+                "Unixbench DHRY2 test(lps): 10235714",
+                "Unixbench SYSCALL test(lps): ",
+                "Unixbench FSBUFFER_256 test(KBps):",
+                "unrelated output",
+            ],
+        );
+        assert_eq!(
+            events,
+            [
+                case("DHRY2", Outcome::Pass, Some("10235714 lps")),
+                case("SYSCALL", Outcome::Fail, None),
+                case("FSBUFFER_256", Outcome::Fail, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_exit() {
+        let mut parser = Parser::new(Format::Exit);
+        assert_eq!(parser.feed("testcase busybox echo success"), None);
+    }
+}
@@ -0,0 +1,153 @@
+//! 测试结果与 JSON 报告
+//!
+//! 报告的格式见 `tools/conformance`，`version` 在格式不兼容地修改时递增。
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::{Display, Formatter, Write};
+
+use crate::parser::Outcome;
+
+pub const REPORT_VERSION: u32 = 1;
+
+/// 测试脚本的运行结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Passed,
+    Failed,
+    Timeout,
+    /// 无法运行测试脚本
+    Error,
+}
+
+impl Status {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Status::Passed => "passed",
+            Status::Failed => "failed",
+            Status::Timeout => "timeout",
+            Status::Error => "error",
+        }
+    }
+}
+
+pub struct CaseResult {
+    pub name: String,
+    pub outcome: Outcome,
+    pub elapsed_ms: usize,
+    pub metric: Option<String>,
+}
+
+pub struct SuiteResult {
+    pub name: &'static str,
+    pub command: &'static str,
+    pub status: Status,
+    pub exit_code: Option<i32>,
+    pub signal: Option<i32>,
+    pub elapsed_ms: usize,
+    pub cases: Vec<CaseResult>,
+}
+
+impl SuiteResult {
+    pub fn count(&self, outcome: Outcome) -> usize {
+        self.cases.iter().filter(|c| c.outcome == outcome).count()
+    }
+}
+
+/// 生成 JSON 格式的报告
+pub fn to_json(release: &str, version: &str, suites: &[SuiteResult]) -> String {
+    let mut out = String::new();
+    // 写入 String 不会失败
+    let _ = write_json(&mut out, release, version, suites);
+    out
+}
+
+fn write_json(
+    out: &mut String,
+    release: &str,
+    version: &str,
+    suites: &[SuiteResult],
+) -> core::fmt::Result {
+    write!(
+        out,
+        "{{\n  \"version\": {},\n  \"kernel\": {{\"release\": {}, \"version\": {}}},\n  \"suites\": [",
+        REPORT_VERSION,
+        JsonStr(release),
+        JsonStr(version)
+    )?;
+    for (i, suite) in suites.iter().enumerate() {
+        out.push_str(if i == 0 { "\n" } else { ",\n" });
+        write!(
+            out,
+            "    {{\"name\": {}, \"command\": {}, \"status\": \"{}\", \"exit_code\": {}, \"signal\": {}, \"elapsed_ms\": {}, \"cases\": [",
+            JsonStr(suite.name),
+            JsonStr(suite.command),
+            suite.status.as_str(),
+            JsonOpt(suite.exit_code),
+            JsonOpt(suite.signal),
+            suite.elapsed_ms
+        )?;
+        for (j, case) in suite.cases.iter().enumerate() {
+            out.push_str(if j == 0 { "\n" } else { ",\n" });
+            write!(
+                out,
+                "      {{\"name\": {}, \"result\": \"{}\", \"elapsed_ms\": {}",
+                JsonStr(&case.name),
+                case.outcome.as_str(),
+                case.elapsed_ms
+            )?;
+            if let Some(metric) = &case.metric {
+                write!(out, ", \"metric\": {}", JsonStr(metric))?;
+            }
+            out.push('}');
+        }
+        if !suite.cases.is_empty() {
+            out.push_str("\n    ");
+        }
+        out.push_str("]}");
+    }
+    let total = suites.iter().map(|s| s.cases.len()).sum::<usize>();
+    let passed = suites.iter().map(|s| s.count(Outcome::Pass)).sum::<usize>();
+    let failed = suites.iter().map(|s| s.count(Outcome::Fail)).sum::<usize>();
+    let timeout = suites
+        .iter()
+        .map(|s| s.count(Outcome::Timeout))
+        .sum::<usize>();
+    write!(
+        out,
+        "\n  ],\n  \"summary\": {{\"total\": {}, \"passed\": {}, \"failed\": {}, \"timeout\": {}}}\n}}\n",
+        total, passed, failed, timeout
+    )
+}
+
+/// 按 JSON 字符串的格式输出，包括两端的引号
+struct JsonStr<'a>(&'a str);
+
+impl Display for JsonStr<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.write_char('"')?;
+        for c in self.0.chars() {
+            match c {
+                '"' => f.write_str("\\\"")?,
+                '\\' => f.write_str("\\\\")?,
+                '\n' => f.write_str("\\n")?,
+                '\r' => f.write_str("\\r")?,
+                '\t' => f.write_str("\\t")?,
+                c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+                c => f.write_char(c)?,
+            }
+        }
+        f.write_char('"')
+    }
+}
+
+/// 没有值时输出 `null`
+struct JsonOpt(Option<i32>);
+
+impl Display for JsonOpt {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self.0 {
+            Some(v) => write!(f, "{}", v),
+            None => f.write_str("null"),
+        }
+    }
+}
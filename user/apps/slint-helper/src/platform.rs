@@ -3,7 +3,7 @@ use core::time::Duration;
 
 use slint::platform::software_renderer::MinimalSoftwareWindow;
 
-use Mstd::time::{TimeSpec, TimeVal};
+use Mstd::time::{TimeNow, TimeSpec, TimeVal};
 
 pub struct MyPlatform {
     window: Rc<MinimalSoftwareWindow>,
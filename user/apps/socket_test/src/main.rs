@@ -31,7 +31,7 @@ fn tcp_test() {
         // socket() -> bind() -> listen() -> accept() -> recv() -> send() -> close()
         let sockfd = socket(Domain::AF_INET, SocketType::SOCK_STREAM, 0);
         assert!(sockfd > 0);
-        let addr = Sockaddr::new(Domain::AF_INET, INTADDR, PORT);
+        let addr = Sockaddr::new(Domain::AF_INET as u16, INTADDR, PORT);
         assert!(
             bind(
                 sockfd as usize,
@@ -72,7 +72,7 @@ fn tcp_test() {
         sleep(1000);
         let sockfd = socket(Domain::AF_INET, SocketType::SOCK_DGRAM, 0);
         assert!(sockfd > 0);
-        let addr = Sockaddr::new(Domain::AF_INET, INTADDR, PORT);
+        let addr = Sockaddr::new(Domain::AF_INET as u16, INTADDR, PORT);
         let buf: &[u8] = b"Hello, this is Alien's client";
         assert!(
             connect(
@@ -103,7 +103,7 @@ fn udp_test() {
         // socket() -> bind() -> recvfrom() -> close()
         let sockfd = socket(Domain::AF_INET, SocketType::SOCK_DGRAM, 0);
         assert!(sockfd > 0);
-        let addr = Sockaddr::new(Domain::AF_INET, INTADDR, PORT);
+        let addr = Sockaddr::new(Domain::AF_INET as u16, INTADDR, PORT);
         assert!(
             bind(
                 sockfd as usize,
@@ -138,7 +138,7 @@ fn udp_test() {
         sleep(1000);
         let sockfd = socket(Domain::AF_INET, SocketType::SOCK_STREAM, 0);
         assert!(sockfd > 0);
-        let addr = Sockaddr::new(Domain::AF_INET, INTADDR, PORT);
+        let addr = Sockaddr::new(Domain::AF_INET as u16, INTADDR, PORT);
         let buf: &[u8] = b"Hello, this is Alien's client";
         sendto(
             sockfd as usize,
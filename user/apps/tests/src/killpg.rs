@@ -0,0 +1,42 @@
+use Mstd::linux::{getpgid, kill, sched_yield, setpgid, Errno};
+use Mstd::process::{fork, waitpid};
+
+const SIGKILL: usize = 9;
+
+/// 创建一个加入进程组 `pgid` 的子进程，`pgid` 为 0 时以子进程自己的进程号作为组号
+fn spawn_member(pgid: usize) -> usize {
+    let pid = fork();
+    assert!(pid >= 0);
+    if pid == 0 {
+        setpgid(0, pgid).unwrap();
+        loop {
+            sched_yield();
+        }
+    }
+    let pid = pid as usize;
+    // 父进程也设置一次，保证 kill 之前子进程已经在组内
+    setpgid(pid, if pgid == 0 { pid } else { pgid }).unwrap();
+    pid
+}
+
+/// `kill(0, sig)` 发送给同组进程，`kill(-pgid, sig)` 发送给进程组 `pgid` 中的所有进程
+pub fn killpg_test() -> isize {
+    assert_eq!(kill(0, 0), Ok(()));
+
+    let first = spawn_member(0);
+    let second = spawn_member(first);
+    assert_eq!(getpgid(second), Ok(first));
+
+    let group = (first as isize).wrapping_neg() as usize;
+    assert_eq!(kill(group, 0), Ok(()));
+    assert_eq!(kill(group, SIGKILL), Ok(()));
+    for pid in [first, second] {
+        let mut status = 0;
+        assert_eq!(waitpid(pid, &mut status), pid as isize);
+        assert_eq!(status & 0x7f, SIGKILL as i32);
+    }
+    // 组内的进程都已经退出
+    assert_eq!(kill(group, 0), Err(Errno::ESRCH.into()));
+    println!("killpg_test pass.");
+    0
+}
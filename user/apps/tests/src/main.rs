@@ -13,6 +13,7 @@ mod alloctest;
 mod attrtest;
 mod dirtest;
 mod forktest;
+mod killpg;
 mod linktest;
 mod mmmap;
 mod pipe;
@@ -31,6 +32,7 @@ fn main(_argc: usize, argv: Vec<String>) -> isize {
                 println!("alloc_test");
                 println!("attr_test[1-2]");
                 println!("fork_test");
+                println!("killpg_test");
                 println!("link_test");
                 println!("mmap_test");
                 println!("pipe_test[1-2]");
@@ -58,6 +60,9 @@ fn main(_argc: usize, argv: Vec<String>) -> isize {
             "fork_test" => {
                 forktest::fork_test();
             }
+            "killpg_test" => {
+                killpg::killpg_test();
+            }
             "link_test" => {
                 linktest::link_test();
             }
@@ -1,48 +1,55 @@
-use alloc::format;
-use alloc::vec::Vec;
+use alloc::sync::Arc;
+use core::cell::Cell;
 
-use Mstd::process::{getpid, CloneFlags};
-use Mstd::thread::{gettid, thread_create};
-use Mstd::time::sleep;
+use Mstd::process::getpid;
+use Mstd::sync::{Condvar, Mutex};
+use Mstd::thread::{gettid, spawn};
 
-const STACK_SIZE: usize = 2 * 1024;
+const THREAD_NUM: usize = 4;
+const ITERATIONS: usize = 1000;
 
-fn thread_fun() {
-    println!("This is child thread!");
-    print_id();
+thread_local! {
+    static LOCAL: Cell<usize> = Cell::new(0);
 }
 
 pub fn thread_test1() -> isize {
-    let ustack = [0u8; STACK_SIZE];
-    let sp: *const u8 = &ustack as *const [u8; STACK_SIZE] as *const u8;
-    // CloneFlags::CLONE_THREAD
-    let flags: u32 = 0x00010000;
-    let args = Vec::new();
-    unsafe {
-        let tid = thread_create(
-            thread_fun as *const u32,
-            (sp as usize + STACK_SIZE) as *const u32,
-            flags,
-            &args as &[*const u8],
-        );
-        assert!(tid != 0);
-    }
     print_id();
-    sleep(500);
-    // pthread_join(tid, NULL);
+    let counter = Arc::new(Mutex::new(0));
+    let handles = (0..THREAD_NUM)
+        .map(|i| {
+            let counter = counter.clone();
+            spawn(move || {
+                println!("This is child thread {}!", i);
+                print_id();
+                for _ in 0..ITERATIONS {
+                    *counter.lock() += 1;
+                    LOCAL.with(|local| local.set(local.get() + 1));
+                }
+                LOCAL.with(|local| local.get())
+            })
+        })
+        .collect::<alloc::vec::Vec<_>>();
+    for handle in handles {
+        assert_eq!(handle.join(), Some(ITERATIONS));
+    }
+    assert_eq!(*counter.lock(), THREAD_NUM * ITERATIONS);
+    // 主线程的线程局部变量不受其它线程影响
+    assert_eq!(LOCAL.with(|local| local.get()), 0);
+
+    let pair = Arc::new((Mutex::new(false), Condvar::new()));
+    let their_pair = pair.clone();
+    let handle = spawn(move || {
+        let (ready, cond) = &*their_pair;
+        *ready.lock() = true;
+        cond.notify_one();
+    });
+    let (ready, cond) = &*pair;
+    drop(cond.wait_while(ready.lock(), |ready| !*ready));
+    handle.join();
+    println!("thread test passed");
     0
 }
 
 fn print_id() {
     println!("pid: {}, tid: {}", getpid(), gettid());
 }
-
-// fn thread_fun1(){
-//     println!("thread 1 return \n");
-
-// }
-
-// fn thread_fun2() -> !{
-//     println!("thread2 exit");
-//     pthread_exit((void *)2);
-// }
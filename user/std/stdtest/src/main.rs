@@ -0,0 +1,189 @@
+//! 检查标准库在 Alien 上的基本功能：参数与环境变量、文件系统、标准输入输出、线程与同步、时间、进程以及网络。
+//!
+//! 用法：`stdtest [test...]`，不指定测试时运行所有测试
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::io::{self, BufRead, Read, Write};
+use std::net::{TcpListener, TcpStream, UdpSocket};
+use std::process::{self, Command};
+use std::sync::mpsc;
+use std::sync::{Arc, Condvar, Mutex, RwLock};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+const TESTS: &[(&str, fn() -> io::Result<()>)] = &[
+    ("env", env_test),
+    ("fs", fs_test),
+    ("io", io_test),
+    ("thread", thread_test),
+    ("time", time_test),
+    ("process", process_test),
+    ("net", net_test),
+];
+
+fn main() {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    let mut failed = 0;
+    for (name, test) in TESTS {
+        if !args.is_empty() && !args.iter().any(|arg| arg == name) {
+            continue;
+        }
+        match test() {
+            Ok(()) => println!("[stdtest] {} ok", name),
+            Err(e) => {
+                println!("[stdtest] {} failed: {}", name, e);
+                failed += 1;
+            }
+        }
+    }
+    process::exit(failed);
+}
+
+fn env_test() -> io::Result<()> {
+    println!("args: {:?}", env::args().collect::<Vec<_>>());
+    env::set_var("ALIEN_STDTEST", "1");
+    assert_eq!(env::var("ALIEN_STDTEST").as_deref(), Ok("1"));
+    env::remove_var("ALIEN_STDTEST");
+    assert!(env::var("ALIEN_STDTEST").is_err());
+    println!("cwd: {}", env::current_dir()?.display());
+    Ok(())
+}
+
+fn fs_test() -> io::Result<()> {
+    let dir = env::temp_dir().join("stdtest");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(dir.join("sub"))?;
+    let file = dir.join("sub/hello.txt");
+    fs::write(&file, "hello alien\n")?;
+    fs::OpenOptions::new()
+        .append(true)
+        .open(&file)?
+        .write_all(b"from std\n")?;
+    assert_eq!(fs::read_to_string(&file)?, "hello alien\nfrom std\n");
+    assert_eq!(fs::metadata(&file)?.len(), 21);
+    let renamed = dir.join("renamed.txt");
+    fs::rename(&file, &renamed)?;
+    let mut names = fs::read_dir(&dir)?
+        .map(|entry| entry.map(|entry| entry.file_name()))
+        .collect::<io::Result<Vec<_>>>()?;
+    names.sort();
+    assert_eq!(names, ["renamed.txt", "sub"]);
+    fs::remove_dir_all(&dir)?;
+    assert!(!dir.exists());
+    Ok(())
+}
+
+fn io_test() -> io::Result<()> {
+    let mut stdout = io::stdout().lock();
+    writeln!(
+        stdout,
+        "stdout is a terminal: {}",
+        io::IsTerminal::is_terminal(&io::stdout())
+    )?;
+    stdout.flush()?;
+    let mut reader = io::BufReader::new(&b"line 1\nline 2\n"[..]);
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    let mut rest = String::new();
+    reader.read_to_string(&mut rest)?;
+    assert_eq!((line.as_str(), rest.as_str()), ("line 1\n", "line 2\n"));
+    Ok(())
+}
+
+fn thread_test() -> io::Result<()> {
+    const THREADS: usize = 4;
+    let counter = Arc::new(Mutex::new(0));
+    let handles = (0..THREADS)
+        .map(|i| {
+            let counter = counter.clone();
+            thread::Builder::new()
+                .name(format!("worker-{}", i))
+                .spawn(move || {
+                    for _ in 0..1000 {
+                        *counter.lock().unwrap() += 1;
+                    }
+                    thread::current().name().unwrap().to_string()
+                })
+        })
+        .collect::<io::Result<Vec<_>>>()?;
+    for (i, handle) in handles.into_iter().enumerate() {
+        assert_eq!(handle.join().unwrap(), format!("worker-{}", i));
+    }
+    assert_eq!(*counter.lock().unwrap(), THREADS * 1000);
+
+    let pair = Arc::new((Mutex::new(false), Condvar::new()));
+    let their_pair = pair.clone();
+    thread::spawn(move || {
+        *their_pair.0.lock().unwrap() = true;
+        their_pair.1.notify_one();
+    });
+    drop(
+        pair.1
+            .wait_while(pair.0.lock().unwrap(), |ready| !*ready)
+            .unwrap(),
+    );
+
+    let map = Arc::new(RwLock::new(HashMap::new()));
+    let (tx, rx) = mpsc::channel();
+    for i in 0..THREADS {
+        let (map, tx) = (map.clone(), tx.clone());
+        thread::spawn(move || {
+            map.write().unwrap().insert(i, i * i);
+            tx.send(i).unwrap();
+        });
+    }
+    drop(tx);
+    assert_eq!(rx.iter().count(), THREADS);
+    assert_eq!(map.read().unwrap().values().sum::<usize>(), 14);
+
+    thread_local!(static LOCAL: std::cell::Cell<u32> = std::cell::Cell::new(1));
+    LOCAL.with(|local| local.set(2));
+    thread::spawn(|| assert_eq!(LOCAL.with(|local| local.get()), 1))
+        .join()
+        .unwrap();
+    Ok(())
+}
+
+fn time_test() -> io::Result<()> {
+    let start = Instant::now();
+    thread::sleep(Duration::from_millis(100));
+    let elapsed = start.elapsed();
+    assert!(elapsed >= Duration::from_millis(100), "{:?}", elapsed);
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+    println!("slept {:?}, unix time {:?}", elapsed, now);
+    Ok(())
+}
+
+fn process_test() -> io::Result<()> {
+    println!("pid: {}", process::id());
+    let exe = env::current_exe().unwrap_or_else(|_| "/stdtest".into());
+    let status = Command::new(exe).arg("env").status()?;
+    assert!(status.success(), "{}", status);
+    Ok(())
+}
+
+fn net_test() -> io::Result<()> {
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let addr = listener.local_addr()?;
+    let server = thread::spawn(move || -> io::Result<()> {
+        let (mut stream, _) = listener.accept()?;
+        let mut buf = [0; 5];
+        stream.read_exact(&mut buf)?;
+        stream.write_all(&buf)
+    });
+    let mut stream = TcpStream::connect(addr)?;
+    stream.write_all(b"alien")?;
+    let mut buf = [0; 5];
+    stream.read_exact(&mut buf)?;
+    assert_eq!(&buf, b"alien");
+    server.join().unwrap()?;
+
+    let a = UdpSocket::bind("127.0.0.1:0")?;
+    let b = UdpSocket::bind("127.0.0.1:0")?;
+    a.send_to(b"ping", b.local_addr()?)?;
+    let mut buf = [0; 4];
+    let (len, from) = b.recv_from(&mut buf)?;
+    assert_eq!((&buf[..len], from), (&b"ping"[..], a.local_addr()?));
+    Ok(())
+}
@@ -0,0 +1,268 @@
+//! 根据内核中 `#[syscall_func(N)]` 注册的系统调用生成系统调用号。
+//!
+//! 生成的 `syscall_id.rs` 中为内核注册的每一个系统调用定义 `SYSCALL_<NAME>` 常量，名称采用 Linux 中的名称，
+//! 同时为每一个系统调用设置 `cfg(syscall = "<name>")`，用于按内核是否支持来选择实现。
+//! `src/linux` 中需要为每一个系统调用提供封装，缺少封装时构建失败。
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+use std::{env, fs};
+
+const KERNEL_SRC: &str = "../../kernel/src";
+const WRAPPER_SRC: &str = "src/linux";
+
+/// Linux(riscv64) 中的系统调用号与名称，以及 Alien 自定义的系统调用
+const SYSCALL_NAMES: &[(usize, &str)] = &[
+    (5, "setxattr"),
+    (6, "lsetxattr"),
+    (7, "fsetxattr"),
+    (8, "getxattr"),
+    (9, "lgetxattr"),
+    (10, "fgetxattr"),
+    (11, "listxattr"),
+    (12, "llistxattr"),
+    (13, "flistxattr"),
+    (14, "removexattr"),
+    (15, "lremovexattr"),
+    (16, "fremovexattr"),
+    (17, "getcwd"),
+    (20, "epoll_create1"),
+    (21, "epoll_ctl"),
+    (22, "epoll_pwait"),
+    (23, "dup"),
+    (24, "dup3"),
+    (25, "fcntl"),
+    (29, "ioctl"),
+    (34, "mkdirat"),
+    (35, "unlinkat"),
+    (36, "symlinkat"),
+    (37, "linkat"),
+    (38, "renameat"),
+    (39, "umount2"),
+    (40, "mount"),
+    (43, "statfs"),
+    (44, "fstatfs"),
+    (45, "truncate"),
+    (46, "ftruncate"),
+    (48, "faccessat"),
+    (49, "chdir"),
+    (50, "fchdir"),
+    (52, "fchmod"),
+    (53, "fchmodat"),
+    (55, "fchown"),
+    (56, "openat"),
+    (57, "close"),
+    (59, "pipe2"),
+    (61, "getdents64"),
+    (62, "lseek"),
+    (63, "read"),
+    (64, "write"),
+    (65, "readv"),
+    (66, "writev"),
+    (67, "pread64"),
+    (68, "pwrite64"),
+    (71, "sendfile"),
+    (72, "pselect6"),
+    (73, "ppoll"),
+    (78, "readlinkat"),
+    (79, "fstatat"),
+    (80, "fstat"),
+    (81, "sync"),
+    (82, "fsync"),
+    (88, "utimensat"),
+    (93, "exit"),
+    (94, "exit_group"),
+    (95, "waitid"),
+    (96, "set_tid_address"),
+    (98, "futex"),
+    (99, "set_robust_list"),
+    (100, "get_robust_list"),
+    (101, "nanosleep"),
+    (102, "getitimer"),
+    (103, "setitimer"),
+    (113, "clock_gettime"),
+    (114, "clock_getres"),
+    (115, "clock_nanosleep"),
+    (116, "syslog"),
+    (117, "ptrace"),
+    (118, "sched_setparam"),
+    (119, "sched_setscheduler"),
+    (120, "sched_getscheduler"),
+    (121, "sched_getparam"),
+    (122, "sched_setaffinity"),
+    (123, "sched_getaffinity"),
+    (124, "sched_yield"),
+    (129, "kill"),
+    (130, "tkill"),
+    (133, "rt_sigsuspend"),
+    (134, "rt_sigaction"),
+    (135, "rt_sigprocmask"),
+    (137, "rt_sigtimedwait"),
+    (139, "rt_sigreturn"),
+    (153, "times"),
+    (154, "setpgid"),
+    (155, "getpgid"),
+    (157, "setsid"),
+    (160, "uname"),
+    (163, "getrlimit"),
+    (164, "setrlimit"),
+    (165, "getrusage"),
+    (166, "umask"),
+    (169, "gettimeofday"),
+    (172, "getpid"),
+    (173, "getppid"),
+    (174, "getuid"),
+    (175, "geteuid"),
+    (176, "getgid"),
+    (177, "getegid"),
+    (178, "gettid"),
+    (179, "sysinfo"),
+    (194, "shmget"),
+    (195, "shmctl"),
+    (196, "shmat"),
+    (197, "shmdt"),
+    (198, "socket"),
+    (199, "socketpair"),
+    (200, "bind"),
+    (201, "listen"),
+    (202, "accept"),
+    (203, "connect"),
+    (204, "getsockname"),
+    (205, "getpeername"),
+    (206, "sendto"),
+    (207, "recvfrom"),
+    (208, "setsockopt"),
+    (209, "getsockopt"),
+    (210, "shutdown"),
+    (214, "brk"),
+    (215, "munmap"),
+    (220, "clone"),
+    (221, "execve"),
+    (222, "mmap"),
+    (226, "mprotect"),
+    (227, "msync"),
+    (233, "madvise"),
+    (242, "accept4"),
+    (260, "wait4"),
+    (261, "prlimit64"),
+    (276, "renameat2"),
+    (283, "membarrier"),
+    (285, "copy_file_range"),
+    (434, "pidfd_open"),
+    (2000, "framebuffer"),
+    (2001, "framebuffer_flush"),
+    (2002, "event_get"),
+    (2003, "system_shutdown"),
+];
+
+fn main() {
+    let kernel_src = Path::new(KERNEL_SRC);
+    println!("cargo:rerun-if-changed={}", KERNEL_SRC);
+    println!("cargo:rerun-if-changed={}", WRAPPER_SRC);
+
+    let mut registered = BTreeMap::new();
+    for file in rust_files(kernel_src) {
+        let source = fs::read_to_string(&file).unwrap();
+        for (id, func) in syscall_funcs(&source) {
+            if let Some((_, old)) = registered.insert(id, (file.clone(), func)) {
+                panic!("syscall {} is registered twice (`{}`)", id, old);
+            }
+        }
+    }
+
+    let names = SYSCALL_NAMES.iter().copied().collect::<BTreeMap<_, _>>();
+    let mut generated = String::new();
+    for (id, (file, func)) in registered.iter() {
+        let name = names.get(id).unwrap_or_else(|| {
+            panic!(
+                "syscall {} (`{}` in {}) has no name, please add it to SYSCALL_NAMES in build.rs",
+                id,
+                func,
+                file.display()
+            )
+        });
+        let file = file.strip_prefix(kernel_src).unwrap();
+        writeln!(generated, "/// 内核中的 `{}` ({})", func, file.display()).unwrap();
+        writeln!(
+            generated,
+            "pub const SYSCALL_{}: usize = {};",
+            name.to_uppercase(),
+            id
+        )
+        .unwrap();
+        println!("cargo:rustc-cfg=syscall=\"{}\"", name);
+    }
+    writeln!(generated, "\n/// 内核注册的所有系统调用").unwrap();
+    writeln!(generated, "pub const SYSCALL_TABLE: &[(usize, &str)] = &[").unwrap();
+    for id in registered.keys() {
+        writeln!(generated, "    ({}, \"{}\"),", id, names[id]).unwrap();
+    }
+    writeln!(generated, "];").unwrap();
+
+    // 每一个系统调用都需要在 linux 模块中有对应的封装，即以它的调用号作为第一个参数发起调用
+    let wrappers = rust_files(Path::new(WRAPPER_SRC))
+        .into_iter()
+        .map(|file| fs::read_to_string(file).unwrap())
+        .collect::<String>();
+    let missing = registered
+        .keys()
+        .map(|id| format!("SYSCALL_{}", names[id].to_uppercase()))
+        .filter(|id| !passes_syscall_id(&wrappers, id))
+        .collect::<Vec<_>>();
+    if !missing.is_empty() {
+        panic!(
+            "syscalls without a wrapper in {}: {}",
+            WRAPPER_SRC,
+            missing.join(", ")
+        );
+    }
+
+    let out = PathBuf::from(env::var("OUT_DIR").unwrap()).join("syscall_id.rs");
+    fs::write(out, generated).unwrap();
+}
+
+fn rust_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            files.extend(rust_files(&path));
+        } else if path.extension().map_or(false, |ext| ext == "rs") {
+            files.push(path);
+        }
+    }
+    files.sort();
+    files
+}
+
+/// 找出 `#[syscall_func(N)]` 以及其后的函数名
+fn syscall_funcs(source: &str) -> Vec<(usize, String)> {
+    const ATTR: &str = "#[syscall_func(";
+    let mut funcs = Vec::new();
+    let mut rest = source;
+    while let Some(pos) = rest.find(ATTR) {
+        rest = &rest[pos + ATTR.len()..];
+        let end = rest.find(')').unwrap();
+        let id = rest[..end].trim().parse::<usize>().unwrap();
+        let func = rest.find("fn ").unwrap() + "fn ".len();
+        let name = rest[func..]
+            .chars()
+            .take_while(|c| c.is_alphanumeric() || *c == '_')
+            .collect::<String>();
+        funcs.push((id, name));
+    }
+    funcs
+}
+
+/// `source` 中是否有以 `ident` 为第一个参数的调用，如 `syscall(SYSCALL_READ, ...)`。
+///
+/// 只在注释、`use` 或者其它表达式中出现系统调用号不算作提供了封装。
+fn passes_syscall_id(source: &str, ident: &str) -> bool {
+    source.match_indices(ident).any(|(pos, _)| {
+        let next = source[pos + ident.len()..].chars().next();
+        let line = source[..pos].rsplit('\n').next().unwrap_or("");
+        !next.map_or(false, |c| c.is_alphanumeric() || c == '_')
+            && !line.contains("//")
+            && source[..pos].trim_end().ends_with('(')
+    })
+}
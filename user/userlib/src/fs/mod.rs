@@ -4,27 +4,15 @@ use core::fmt::{Debug, Formatter, Pointer};
 
 use bitflags::bitflags;
 
+pub use abi::{Dirent64, DirentType, OpenFlags, Stat};
 pub use attr::*;
+pub use poll::*;
 
 use crate::syscall::*;
 
 mod attr;
+mod poll;
 
-bitflags! {
-    pub struct OpenFlags:u32{
-        const O_RDONLY = 0x0;
-        const O_WRONLY = 0x1;
-        const O_RDWR = 0x2;
-        const O_CREAT = 0x40;
-        const O_EXCL = 0x200;
-        const O_NOCTTY = 0x400;
-        const O_TRUNC = 0x1000;
-        const O_APPEND = 0x2000;
-        const O_NONBLOCK = 0x4000;
-        const O_NOFOLLOW = 0x400000;
-        const O_DIRECTORY = 0x200000;
-    }
-}
 bitflags! {
     pub struct FileMode:u32{
         const FMODE_READ = 0x0;
@@ -34,30 +22,6 @@ bitflags! {
     }
 }
 
-#[derive(Debug, Clone, Default)]
-#[repr(C)]
-pub struct Stat {
-    pub st_dev: u64,
-    pub st_ino: u64,
-    pub st_mode: u32,
-    pub st_nlink: u32,
-    pub st_uid: u32,
-    pub st_gid: u32,
-    pub st_rdev: u64,
-    __pad: u64,
-    pub st_size: u64,
-    pub st_blksize: u32,
-    __pad2: u32,
-    pub st_blocks: u64,
-    pub st_atime_sec: u64,
-    pub st_atime_nsec: u64,
-    pub st_mtime_sec: u64,
-    pub st_mtime_nsec: u64,
-    pub st_ctime_sec: u64,
-    pub st_ctime_nsec: u64,
-    unused: u64,
-} //128
-
 #[derive(Default, Debug, Clone)]
 #[repr(C)]
 pub struct StatTime {
@@ -77,65 +41,6 @@ bitflags! {
     }
 }
 
-#[repr(C)]
-#[derive(Debug)]
-pub struct Dirent64 {
-    /// ino is an inode number
-    pub ino: u64,
-    /// off is an offset to next linux_dirent
-    pub off: i64,
-    /// reclen is the length of this linux_dirent
-    pub reclen: u16,
-    /// type is the file type
-    pub type_: DirentType,
-    /// name is the filename (null-terminated)
-    pub name: [u8; 0],
-}
-
-impl Dirent64 {
-    pub fn get_name(&self) -> &str {
-        unsafe {
-            let name = self.name.as_ptr();
-            let name = core::ffi::CStr::from_ptr(name as *const i8);
-            name.to_str().unwrap()
-        }
-    }
-    pub fn len(&self) -> usize {
-        self.reclen as usize
-    }
-}
-
-bitflags! {
-    pub struct DirentType:u8{
-        const DT_UNKNOWN = 0;
-        const DT_FIFO = 1;
-        const DT_CHR = 2;
-        const DT_DIR = 4;
-        const DT_BLK = 6;
-        const DT_REG = 8;
-        const DT_LNK = 10;
-        const DT_SOCK = 12;
-        const DT_WHT = 14;
-    }
-}
-
-impl ToString for DirentType {
-    fn to_string(&self) -> String {
-        match *self {
-            DirentType::DT_UNKNOWN => "unknown".to_string(),
-            DirentType::DT_FIFO => "fifo".to_string(),
-            DirentType::DT_CHR => "char".to_string(),
-            DirentType::DT_DIR => "dir".to_string(),
-            DirentType::DT_BLK => "block".to_string(),
-            DirentType::DT_REG => "regular".to_string(),
-            DirentType::DT_LNK => "link".to_string(),
-            DirentType::DT_SOCK => "sock".to_string(),
-            DirentType::DT_WHT => "whiteout".to_string(),
-            _ => "unknown".to_string(),
-        }
-    }
-}
-
 pub fn read(fd: usize, buf: &mut [u8]) -> isize {
     sys_read(fd, buf.as_mut_ptr(), buf.len())
 }
@@ -148,20 +53,37 @@ pub fn readdir(fd: usize, buf: &mut [u8]) -> isize {
     sys_read(fd, buf.as_mut_ptr(), buf.len())
 }
 
+/// 打印目录 `path` 中的所有目录项
 pub fn list(path: &str) -> isize {
+    let mut path = String::from(path);
     if !path.ends_with('\0') {
-        let mut p = String::from(path);
-        p.push('\0');
-        return sys_list(p.as_ptr());
+        path.push('\0');
+    }
+    let fd = open(&path, OpenFlags::O_RDONLY | OpenFlags::O_DIRECTORY);
+    if fd < 0 {
+        return fd;
+    }
+    let mut buf = [0u8; 512];
+    loop {
+        let size = getdents(fd as usize, &mut buf);
+        if size <= 0 {
+            close(fd as usize);
+            return size;
+        }
+        let mut offset = 0;
+        while offset < size as usize {
+            let dirent = unsafe { &*(buf.as_ptr().add(offset) as *const Dirent64) };
+            crate::println!("{} {}", dirent.type_, dirent.get_name());
+            offset += dirent.len();
+        }
     }
-    sys_list(path.as_ptr())
 }
 
 pub fn open(name: &str, flag: OpenFlags) -> isize {
     sys_openat(
         AT_FDCWD,
         name.as_ptr(),
-        flag.bits as usize,
+        flag.bits(),
         FileMode::FMODE_RDWR.bits() as usize,
     )
 }
@@ -171,7 +93,7 @@ pub fn openat(fd: isize, name: &str, flag: OpenFlags, file_mode: FileMode) -> is
     sys_openat(
         fd,
         name.as_ptr(),
-        flag.bits as usize,
+        flag.bits(),
         file_mode.bits() as usize,
     )
 }
@@ -180,6 +102,25 @@ pub fn close(fd: usize) -> isize {
     sys_close(fd)
 }
 
+/// fcntl 的命令
+pub const F_GETFL: usize = 3;
+pub const F_SETFL: usize = 4;
+
+pub fn fcntl(fd: usize, cmd: usize, arg: usize) -> isize {
+    sys_fcntl(fd, cmd, arg)
+}
+
+/// 设置或清除文件描述符的 `O_NONBLOCK` 标志
+pub fn set_nonblocking(fd: usize, nonblocking: bool) -> isize {
+    let flags = fcntl(fd, F_GETFL, 0);
+    if flags < 0 {
+        return flags;
+    }
+    let mut flags = OpenFlags::from_bits_truncate(flags as usize);
+    flags.set(OpenFlags::O_NONBLOCK, nonblocking);
+    fcntl(fd, F_SETFL, flags.bits())
+}
+
 pub fn get_cwd(buf: &mut [u8]) -> Result<&str, IoError> {
     let len = sys_get_cwd(buf.as_mut_ptr(), buf.len());
     if len == -1 {
@@ -201,7 +142,11 @@ pub fn chdir(path: &str) -> isize {
 }
 
 pub fn mkdir(path: &str) -> isize {
-    sys_mkdir(path.as_ptr())
+    let mut path = String::from(path);
+    if !path.ends_with('\0') {
+        path.push('\0');
+    }
+    sys_mkdirat(AT_FDCWD, path.as_ptr(), 0o755)
 }
 
 pub fn seek(fd: usize, offset: isize, whence: usize) -> isize {
@@ -272,7 +217,7 @@ pub fn renameat(old_fd: isize, old_path: &str, new_fd: isize, new_path: &str) ->
 }
 
 pub fn mkdirat(fd: isize, path: &str, flag: OpenFlags) -> isize {
-    sys_mkdirat(fd, path.as_ptr(), flag.bits as usize)
+    sys_mkdirat(fd, path.as_ptr(), flag.bits())
 }
 
 #[derive(Debug)]
@@ -0,0 +1,50 @@
+use bitflags::bitflags;
+
+use crate::syscall::sys_ppoll;
+use crate::time::TimeSpec;
+
+bitflags! {
+    pub struct PollEvents: i16 {
+        /// 有数据可读
+        const IN = 0x0001;
+        /// 有紧急数据可读
+        const PRI = 0x0002;
+        /// 可写
+        const OUT = 0x0004;
+        /// 发生错误
+        const ERR = 0x0008;
+        /// 对端挂断
+        const HUP = 0x0010;
+        /// 无效的文件描述符
+        const NVAL = 0x0020;
+    }
+}
+
+/// 与 `linux` 中 `struct pollfd` 对应
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct PollFd {
+    pub fd: i32,
+    pub events: PollEvents,
+    pub revents: PollEvents,
+}
+
+impl PollFd {
+    pub fn new(fd: usize, events: PollEvents) -> Self {
+        Self {
+            fd: fd as i32,
+            events,
+            revents: PollEvents::empty(),
+        }
+    }
+}
+
+/// 等待 `fds` 中的事件，`timeout` 为 `None` 时一直等待。
+///
+/// 返回就绪的文件描述符个数，超时返回 0
+pub fn ppoll(fds: &mut [PollFd], timeout: Option<&TimeSpec>) -> isize {
+    let timeout = timeout.map_or(core::ptr::null(), |timeout| {
+        timeout as *const TimeSpec as *const u8
+    });
+    sys_ppoll(fds.as_mut_ptr() as *mut u8, fds.len(), timeout, 0, 0)
+}
@@ -4,6 +4,7 @@
 #![allow(unused)]
 #![allow(non_snake_case)]
 #![feature(naked_functions)]
+#![feature(ip_in_core)]
 extern crate alloc;
 
 use alloc::string::String;
@@ -20,11 +21,13 @@ pub mod fs;
 mod heap;
 pub mod io;
 pub mod ipc;
+pub mod linux;
 mod macros;
 pub mod memory;
 mod panic;
 pub mod process;
 pub mod pthread;
+pub mod runtime;
 pub mod socket;
 mod sys;
 mod syscall;
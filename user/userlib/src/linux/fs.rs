@@ -0,0 +1,606 @@
+//! 文件系统相关的系统调用
+use alloc::vec::Vec;
+
+use crate::fs::{LinkFlags, OpenFlags, PollFd, Stat, StatFlags, StatFs};
+use crate::syscall::*;
+use crate::time::TimeSpec;
+
+use super::{c_str, opt_mut_ptr, opt_ptr, syscall, Result};
+
+/// 与 `linux` 中 `struct iovec` 对应
+#[repr(C)]
+struct IoVec {
+    base: usize,
+    len: usize,
+}
+
+/// 与 `linux` 中 `fd_set` 对应，最多记录 1024 个文件描述符
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct FdSet {
+    bits: [u64; 16],
+}
+
+impl FdSet {
+    /// 能够表示的文件描述符的上限，与 Linux 中的 `FD_SETSIZE` 相同
+    pub const SIZE: usize = 1024;
+
+    /// 加入文件描述符 `fd`，`fd` 不小于 [`FdSet::SIZE`] 时忽略
+    pub fn set(&mut self, fd: usize) {
+        if fd < Self::SIZE {
+            self.bits[fd / 64] |= 1 << (fd % 64);
+        }
+    }
+
+    /// 移除文件描述符 `fd`，`fd` 不小于 [`FdSet::SIZE`] 时忽略
+    pub fn clear(&mut self, fd: usize) {
+        if fd < Self::SIZE {
+            self.bits[fd / 64] &= !(1 << (fd % 64));
+        }
+    }
+
+    /// `fd` 是否在集合中，`fd` 不小于 [`FdSet::SIZE`] 时总是返回 false
+    pub fn is_set(&self, fd: usize) -> bool {
+        fd < Self::SIZE && self.bits[fd / 64] & (1 << (fd % 64)) != 0
+    }
+}
+
+pub fn setxattr(path: &str, name: &str, value: &[u8], flags: usize) -> Result<()> {
+    let (path, name) = (c_str(path)?, c_str(name)?);
+    syscall(
+        SYSCALL_SETXATTR,
+        [
+            path.as_ptr() as usize,
+            name.as_ptr() as usize,
+            value.as_ptr() as usize,
+            value.len(),
+            flags,
+        ],
+    )
+    .map(drop)
+}
+
+pub fn lsetxattr(path: &str, name: &str, value: &[u8], flags: usize) -> Result<()> {
+    let (path, name) = (c_str(path)?, c_str(name)?);
+    syscall(
+        SYSCALL_LSETXATTR,
+        [
+            path.as_ptr() as usize,
+            name.as_ptr() as usize,
+            value.as_ptr() as usize,
+            value.len(),
+            flags,
+        ],
+    )
+    .map(drop)
+}
+
+pub fn fsetxattr(fd: usize, name: &str, value: &[u8], flags: usize) -> Result<()> {
+    let name = c_str(name)?;
+    syscall(
+        SYSCALL_FSETXATTR,
+        [
+            fd,
+            name.as_ptr() as usize,
+            value.as_ptr() as usize,
+            value.len(),
+            flags,
+        ],
+    )
+    .map(drop)
+}
+
+/// 读取扩展属性，返回属性值的长度
+pub fn getxattr(path: &str, name: &str, value: &mut [u8]) -> Result<usize> {
+    let (path, name) = (c_str(path)?, c_str(name)?);
+    syscall(
+        SYSCALL_GETXATTR,
+        [
+            path.as_ptr() as usize,
+            name.as_ptr() as usize,
+            value.as_mut_ptr() as usize,
+            value.len(),
+        ],
+    )
+}
+
+pub fn lgetxattr(path: &str, name: &str, value: &mut [u8]) -> Result<usize> {
+    let (path, name) = (c_str(path)?, c_str(name)?);
+    syscall(
+        SYSCALL_LGETXATTR,
+        [
+            path.as_ptr() as usize,
+            name.as_ptr() as usize,
+            value.as_mut_ptr() as usize,
+            value.len(),
+        ],
+    )
+}
+
+pub fn fgetxattr(fd: usize, name: &str, value: &mut [u8]) -> Result<usize> {
+    let name = c_str(name)?;
+    syscall(
+        SYSCALL_FGETXATTR,
+        [
+            fd,
+            name.as_ptr() as usize,
+            value.as_mut_ptr() as usize,
+            value.len(),
+        ],
+    )
+}
+
+/// 列出扩展属性名，名称之间以 `\0` 分隔，返回使用的长度
+pub fn listxattr(path: &str, list: &mut [u8]) -> Result<usize> {
+    let path = c_str(path)?;
+    syscall(
+        SYSCALL_LISTXATTR,
+        [
+            path.as_ptr() as usize,
+            list.as_mut_ptr() as usize,
+            list.len(),
+        ],
+    )
+}
+
+pub fn llistxattr(path: &str, list: &mut [u8]) -> Result<usize> {
+    let path = c_str(path)?;
+    syscall(
+        SYSCALL_LLISTXATTR,
+        [
+            path.as_ptr() as usize,
+            list.as_mut_ptr() as usize,
+            list.len(),
+        ],
+    )
+}
+
+pub fn flistxattr(fd: usize, list: &mut [u8]) -> Result<usize> {
+    syscall(
+        SYSCALL_FLISTXATTR,
+        [fd, list.as_mut_ptr() as usize, list.len()],
+    )
+}
+
+pub fn removexattr(path: &str, name: &str) -> Result<()> {
+    let (path, name) = (c_str(path)?, c_str(name)?);
+    syscall(
+        SYSCALL_REMOVEXATTR,
+        [path.as_ptr() as usize, name.as_ptr() as usize],
+    )
+    .map(drop)
+}
+
+pub fn lremovexattr(path: &str, name: &str) -> Result<()> {
+    let (path, name) = (c_str(path)?, c_str(name)?);
+    syscall(
+        SYSCALL_LREMOVEXATTR,
+        [path.as_ptr() as usize, name.as_ptr() as usize],
+    )
+    .map(drop)
+}
+
+pub fn fremovexattr(fd: usize, name: &str) -> Result<()> {
+    let name = c_str(name)?;
+    syscall(SYSCALL_FREMOVEXATTR, [fd, name.as_ptr() as usize]).map(drop)
+}
+
+/// 将当前工作目录写入 `buf`，返回以 `\0` 结尾的路径
+pub fn getcwd(buf: &mut [u8]) -> Result<&str> {
+    syscall(SYSCALL_GETCWD, [buf.as_mut_ptr() as usize, buf.len()])?;
+    let len = buf.iter().position(|&c| c == 0).unwrap_or(buf.len());
+    core::str::from_utf8(&buf[..len]).map_err(|_| super::Errno::EINVAL.into())
+}
+
+pub fn dup(fd: usize) -> Result<usize> {
+    syscall(SYSCALL_DUP, [fd])
+}
+
+pub fn dup3(old_fd: usize, new_fd: usize, flags: OpenFlags) -> Result<usize> {
+    syscall(SYSCALL_DUP3, [old_fd, new_fd, flags.bits() as usize])
+}
+
+pub fn fcntl(fd: usize, cmd: usize, arg: usize) -> Result<usize> {
+    syscall(SYSCALL_FCNTL, [fd, cmd, arg])
+}
+
+/// # Safety
+///
+/// `arg` 需要是 `cmd` 要求的参数，通常是指向对应结构的指针
+pub unsafe fn ioctl(fd: usize, cmd: usize, arg: usize) -> Result<usize> {
+    syscall(SYSCALL_IOCTL, [fd, cmd, arg])
+}
+
+pub fn mkdirat(dirfd: isize, path: &str, mode: u32) -> Result<()> {
+    let path = c_str(path)?;
+    syscall(
+        SYSCALL_MKDIRAT,
+        [dirfd as usize, path.as_ptr() as usize, mode as usize],
+    )
+    .map(drop)
+}
+
+pub fn unlinkat(dirfd: isize, path: &str, flags: usize) -> Result<()> {
+    let path = c_str(path)?;
+    syscall(
+        SYSCALL_UNLINKAT,
+        [dirfd as usize, path.as_ptr() as usize, flags],
+    )
+    .map(drop)
+}
+
+pub fn symlinkat(target: &str, new_dirfd: isize, link_path: &str) -> Result<()> {
+    let (target, link_path) = (c_str(target)?, c_str(link_path)?);
+    syscall(
+        SYSCALL_SYMLINKAT,
+        [
+            target.as_ptr() as usize,
+            new_dirfd as usize,
+            link_path.as_ptr() as usize,
+        ],
+    )
+    .map(drop)
+}
+
+pub fn linkat(
+    old_dirfd: isize,
+    old_path: &str,
+    new_dirfd: isize,
+    new_path: &str,
+    flags: LinkFlags,
+) -> Result<()> {
+    let (old_path, new_path) = (c_str(old_path)?, c_str(new_path)?);
+    syscall(
+        SYSCALL_LINKAT,
+        [
+            old_dirfd as usize,
+            old_path.as_ptr() as usize,
+            new_dirfd as usize,
+            new_path.as_ptr() as usize,
+            flags.bits() as usize,
+        ],
+    )
+    .map(drop)
+}
+
+pub fn renameat(old_dirfd: isize, old_path: &str, new_dirfd: isize, new_path: &str) -> Result<()> {
+    let (old_path, new_path) = (c_str(old_path)?, c_str(new_path)?);
+    syscall(
+        SYSCALL_RENAMEAT,
+        [
+            old_dirfd as usize,
+            old_path.as_ptr() as usize,
+            new_dirfd as usize,
+            new_path.as_ptr() as usize,
+        ],
+    )
+    .map(drop)
+}
+
+pub fn renameat2(
+    old_dirfd: isize,
+    old_path: &str,
+    new_dirfd: isize,
+    new_path: &str,
+    flags: u32,
+) -> Result<()> {
+    let (old_path, new_path) = (c_str(old_path)?, c_str(new_path)?);
+    syscall(
+        SYSCALL_RENAMEAT2,
+        [
+            old_dirfd as usize,
+            old_path.as_ptr() as usize,
+            new_dirfd as usize,
+            new_path.as_ptr() as usize,
+            flags as usize,
+        ],
+    )
+    .map(drop)
+}
+
+/// 内核目前忽略 `flags`
+pub fn umount2(target: &str, flags: usize) -> Result<()> {
+    let target = c_str(target)?;
+    syscall(SYSCALL_UMOUNT2, [target.as_ptr() as usize, flags]).map(drop)
+}
+
+pub fn mount(
+    source: &str,
+    target: &str,
+    fs_type: &str,
+    flags: usize,
+    data: Option<&str>,
+) -> Result<()> {
+    let (source, target, fs_type) = (c_str(source)?, c_str(target)?, c_str(fs_type)?);
+    let data = data.map(c_str).transpose()?;
+    syscall(
+        SYSCALL_MOUNT,
+        [
+            source.as_ptr() as usize,
+            target.as_ptr() as usize,
+            fs_type.as_ptr() as usize,
+            flags,
+            data.as_ref().map_or(0, |data| data.as_ptr() as usize),
+        ],
+    )
+    .map(drop)
+}
+
+pub fn statfs(path: &str) -> Result<StatFs> {
+    let path = c_str(path)?;
+    let mut stat = StatFs::default();
+    syscall(
+        SYSCALL_STATFS,
+        [path.as_ptr() as usize, &mut stat as *mut StatFs as usize],
+    )?;
+    Ok(stat)
+}
+
+pub fn fstatfs(fd: usize) -> Result<StatFs> {
+    let mut stat = StatFs::default();
+    syscall(SYSCALL_FSTATFS, [fd, &mut stat as *mut StatFs as usize])?;
+    Ok(stat)
+}
+
+pub fn truncate(path: &str, len: usize) -> Result<()> {
+    let path = c_str(path)?;
+    syscall(SYSCALL_TRUNCATE, [path.as_ptr() as usize, len]).map(drop)
+}
+
+pub fn ftruncate(fd: usize, len: usize) -> Result<()> {
+    syscall(SYSCALL_FTRUNCATE, [fd, len]).map(drop)
+}
+
+/// 检查当前进程是否可以按照 `mode`(`R_OK`/`W_OK`/`X_OK`/`F_OK`) 访问文件
+pub fn faccessat(dirfd: isize, path: &str, mode: usize, flags: usize) -> Result<()> {
+    let path = c_str(path)?;
+    syscall(
+        SYSCALL_FACCESSAT,
+        [dirfd as usize, path.as_ptr() as usize, mode, flags],
+    )
+    .map(drop)
+}
+
+pub fn chdir(path: &str) -> Result<()> {
+    let path = c_str(path)?;
+    syscall(SYSCALL_CHDIR, [path.as_ptr() as usize]).map(drop)
+}
+
+pub fn fchdir(fd: usize) -> Result<()> {
+    syscall(SYSCALL_FCHDIR, [fd]).map(drop)
+}
+
+pub fn fchmod(fd: usize, mode: u32) -> Result<()> {
+    syscall(SYSCALL_FCHMOD, [fd, mode as usize]).map(drop)
+}
+
+pub fn fchmodat(dirfd: isize, path: &str, mode: u32, flags: usize) -> Result<()> {
+    let path = c_str(path)?;
+    syscall(
+        SYSCALL_FCHMODAT,
+        [dirfd as usize, path.as_ptr() as usize, mode as usize, flags],
+    )
+    .map(drop)
+}
+
+pub fn fchown(fd: usize, uid: u32, gid: u32) -> Result<()> {
+    syscall(SYSCALL_FCHOWN, [fd, uid as usize, gid as usize]).map(drop)
+}
+
+/// 打开文件，返回文件描述符
+pub fn openat(dirfd: isize, path: &str, flags: OpenFlags, mode: u32) -> Result<usize> {
+    let path = c_str(path)?;
+    syscall(
+        SYSCALL_OPENAT,
+        [
+            dirfd as usize,
+            path.as_ptr() as usize,
+            flags.bits() as usize,
+            mode as usize,
+        ],
+    )
+}
+
+pub fn close(fd: usize) -> Result<()> {
+    syscall(SYSCALL_CLOSE, [fd]).map(drop)
+}
+
+/// 创建管道，返回 `[读端, 写端]`
+pub fn pipe2(flags: OpenFlags) -> Result<[usize; 2]> {
+    let mut fds = [0u32; 2];
+    syscall(
+        SYSCALL_PIPE2,
+        [fds.as_mut_ptr() as usize, flags.bits() as usize],
+    )?;
+    Ok([fds[0] as usize, fds[1] as usize])
+}
+
+/// 读取目录项到 `buf` 中，返回读取的字节数，0 表示已经读完
+pub fn getdents64(fd: usize, buf: &mut [u8]) -> Result<usize> {
+    syscall(
+        SYSCALL_GETDENTS64,
+        [fd, buf.as_mut_ptr() as usize, buf.len()],
+    )
+}
+
+/// 返回新的偏移量
+pub fn lseek(fd: usize, offset: isize, whence: usize) -> Result<usize> {
+    syscall(SYSCALL_LSEEK, [fd, offset as usize, whence])
+}
+
+pub fn read(fd: usize, buf: &mut [u8]) -> Result<usize> {
+    syscall(SYSCALL_READ, [fd, buf.as_mut_ptr() as usize, buf.len()])
+}
+
+pub fn write(fd: usize, buf: &[u8]) -> Result<usize> {
+    syscall(SYSCALL_WRITE, [fd, buf.as_ptr() as usize, buf.len()])
+}
+
+pub fn readv(fd: usize, bufs: &mut [&mut [u8]]) -> Result<usize> {
+    let iov = bufs
+        .iter_mut()
+        .map(|buf| IoVec {
+            base: buf.as_mut_ptr() as usize,
+            len: buf.len(),
+        })
+        .collect::<Vec<_>>();
+    syscall(SYSCALL_READV, [fd, iov.as_ptr() as usize, iov.len()])
+}
+
+pub fn writev(fd: usize, bufs: &[&[u8]]) -> Result<usize> {
+    let iov = bufs
+        .iter()
+        .map(|buf| IoVec {
+            base: buf.as_ptr() as usize,
+            len: buf.len(),
+        })
+        .collect::<Vec<_>>();
+    syscall(SYSCALL_WRITEV, [fd, iov.as_ptr() as usize, iov.len()])
+}
+
+/// 从 `offset` 处读取，不改变文件的偏移量
+pub fn pread64(fd: usize, buf: &mut [u8], offset: u64) -> Result<usize> {
+    syscall(
+        SYSCALL_PREAD64,
+        [fd, buf.as_mut_ptr() as usize, buf.len(), offset as usize],
+    )
+}
+
+pub fn pwrite64(fd: usize, buf: &[u8], offset: u64) -> Result<usize> {
+    syscall(
+        SYSCALL_PWRITE64,
+        [fd, buf.as_ptr() as usize, buf.len(), offset as usize],
+    )
+}
+
+/// 将 `in_fd` 中的数据复制到 `out_fd`，`offset` 不为空时从该偏移量读取并更新它，而不改变 `in_fd` 的偏移量
+pub fn sendfile(
+    out_fd: usize,
+    in_fd: usize,
+    offset: Option<&mut i64>,
+    count: usize,
+) -> Result<usize> {
+    syscall(
+        SYSCALL_SENDFILE,
+        [out_fd, in_fd, opt_mut_ptr(offset), count],
+    )
+}
+
+/// 等待 `nfds` 以内的文件描述符就绪，返回就绪的文件描述符个数
+pub fn pselect6(
+    nfds: usize,
+    read_fds: Option<&mut FdSet>,
+    write_fds: Option<&mut FdSet>,
+    except_fds: Option<&mut FdSet>,
+    timeout: Option<&TimeSpec>,
+) -> Result<usize> {
+    syscall(
+        SYSCALL_PSELECT6,
+        [
+            nfds,
+            opt_mut_ptr(read_fds),
+            opt_mut_ptr(write_fds),
+            opt_mut_ptr(except_fds),
+            opt_ptr(timeout),
+            0,
+        ],
+    )
+}
+
+/// 见 [`crate::fs::ppoll`]
+pub fn ppoll(fds: &mut [PollFd], timeout: Option<&TimeSpec>) -> Result<usize> {
+    syscall(
+        SYSCALL_PPOLL,
+        [fds.as_mut_ptr() as usize, fds.len(), opt_ptr(timeout), 0],
+    )
+}
+
+/// 读取符号链接的内容，返回写入 `buf` 的长度
+pub fn readlinkat(dirfd: isize, path: &str, buf: &mut [u8]) -> Result<usize> {
+    let path = c_str(path)?;
+    syscall(
+        SYSCALL_READLINKAT,
+        [
+            dirfd as usize,
+            path.as_ptr() as usize,
+            buf.as_mut_ptr() as usize,
+            buf.len(),
+        ],
+    )
+}
+
+pub fn fstatat(dirfd: isize, path: &str, flags: StatFlags) -> Result<Stat> {
+    let path = c_str(path)?;
+    let mut stat = Stat::default();
+    syscall(
+        SYSCALL_FSTATAT,
+        [
+            dirfd as usize,
+            path.as_ptr() as usize,
+            &mut stat as *mut Stat as usize,
+            flags.bits() as usize,
+        ],
+    )?;
+    Ok(stat)
+}
+
+pub fn fstat(fd: usize) -> Result<Stat> {
+    let mut stat = Stat::default();
+    syscall(SYSCALL_FSTAT, [fd, &mut stat as *mut Stat as usize])?;
+    Ok(stat)
+}
+
+pub fn sync() -> Result<()> {
+    syscall(SYSCALL_SYNC, []).map(drop)
+}
+
+pub fn fsync(fd: usize) -> Result<()> {
+    syscall(SYSCALL_FSYNC, [fd]).map(drop)
+}
+
+/// 修改文件的访问时间和修改时间，`times` 为空时设置为当前时间
+pub fn utimensat(
+    dirfd: isize,
+    path: Option<&str>,
+    times: Option<&[TimeSpec; 2]>,
+    flags: usize,
+) -> Result<()> {
+    let path = path.map(c_str).transpose()?;
+    syscall(
+        SYSCALL_UTIMENSAT,
+        [
+            dirfd as usize,
+            path.as_ref().map_or(0, |path| path.as_ptr() as usize),
+            opt_ptr(times),
+            flags,
+        ],
+    )
+    .map(drop)
+}
+
+/// 设置文件创建掩码，返回原来的掩码
+pub fn umask(mask: u32) -> Result<u32> {
+    syscall(SYSCALL_UMASK, [mask as usize]).map(|old| old as u32)
+}
+
+/// 在两个文件之间复制数据，偏移量不为空时使用并更新它，而不改变文件的偏移量
+pub fn copy_file_range(
+    fd_in: usize,
+    off_in: Option<&mut i64>,
+    fd_out: usize,
+    off_out: Option<&mut i64>,
+    len: usize,
+    flags: usize,
+) -> Result<usize> {
+    syscall(
+        SYSCALL_COPY_FILE_RANGE,
+        [
+            fd_in,
+            opt_mut_ptr(off_in),
+            fd_out,
+            opt_mut_ptr(off_out),
+            len,
+            flags,
+        ],
+    )
+}
@@ -0,0 +1,22 @@
+//! Alien 中图形界面相关的系统调用
+use crate::syscall::*;
+
+use super::{syscall, Result};
+
+/// 将帧缓冲区映射到地址空间中，返回映射的起始地址
+pub fn framebuffer() -> Result<usize> {
+    syscall(SYSCALL_FRAMEBUFFER, [])
+}
+
+/// 将帧缓冲区中的内容刷新到屏幕上
+pub fn framebuffer_flush() -> Result<()> {
+    syscall(SYSCALL_FRAMEBUFFER_FLUSH, []).map(drop)
+}
+
+/// 读取输入事件，返回读取的事件个数
+pub fn event_get(events: &mut [u64]) -> Result<usize> {
+    syscall(
+        SYSCALL_EVENT_GET,
+        [events.as_mut_ptr() as usize, events.len()],
+    )
+}
@@ -0,0 +1,85 @@
+//! 进程间通信相关的系统调用
+use core::sync::atomic::AtomicU32;
+
+use constants::ipc::RobustList;
+
+use crate::syscall::*;
+use crate::time::TimeSpec;
+
+use super::{opt_ptr, syscall, Result};
+
+const FUTEX_WAIT: usize = 0;
+const FUTEX_WAKE: usize = 1;
+const FUTEX_PRIVATE_FLAG: usize = 128;
+
+/// 如果 `futex` 的值仍为 `expected`，则睡眠直到被唤醒或者超过 `timeout`(相对时间)
+///
+/// `private` 为 `true` 时只能被同一进程中的线程唤醒
+pub fn futex_wait(
+    futex: &AtomicU32,
+    expected: u32,
+    timeout: Option<&TimeSpec>,
+    private: bool,
+) -> Result<()> {
+    let op = FUTEX_WAIT | if private { FUTEX_PRIVATE_FLAG } else { 0 };
+    syscall(
+        SYSCALL_FUTEX,
+        [
+            futex.as_ptr() as usize,
+            op,
+            expected as usize,
+            opt_ptr(timeout),
+        ],
+    )
+    .map(drop)
+}
+
+/// 唤醒最多 `count` 个等待在 `futex` 上的线程，返回被唤醒的线程数
+pub fn futex_wake(futex: &AtomicU32, count: u32, private: bool) -> Result<usize> {
+    let op = FUTEX_WAKE | if private { FUTEX_PRIVATE_FLAG } else { 0 };
+    syscall(SYSCALL_FUTEX, [futex.as_ptr() as usize, op, count as usize])
+}
+
+/// # Safety
+///
+/// `head` 需要在线程退出前一直有效，线程退出时内核会访问其中记录的锁
+pub unsafe fn set_robust_list(head: *const RobustList) -> Result<()> {
+    syscall(
+        SYSCALL_SET_ROBUST_LIST,
+        [head as usize, core::mem::size_of::<RobustList>()],
+    )
+    .map(drop)
+}
+
+/// 返回线程 `pid` 注册的 robust list 的地址
+pub fn get_robust_list(pid: usize) -> Result<usize> {
+    let (mut head, mut len) = (0usize, 0usize);
+    syscall(
+        SYSCALL_GET_ROBUST_LIST,
+        [
+            pid,
+            &mut head as *mut usize as usize,
+            &mut len as *mut usize as usize,
+        ],
+    )?;
+    Ok(head)
+}
+
+/// 创建或者获取键值为 `key` 的共享内存，返回共享内存的标识符
+pub fn shmget(key: usize, size: usize, flags: u32) -> Result<usize> {
+    syscall(SYSCALL_SHMGET, [key, size, flags as usize])
+}
+
+/// 内核目前只支持 `IPC_RMID`
+pub fn shmctl(shmid: usize, cmd: usize) -> Result<()> {
+    syscall(SYSCALL_SHMCTL, [shmid, cmd, 0]).map(drop)
+}
+
+/// 将共享内存映射到地址空间中，返回映射的起始地址
+///
+/// # Safety
+///
+/// `addr` 不为 0 时会映射到指定的地址上
+pub unsafe fn shmat(shmid: usize, addr: usize, flags: u32) -> Result<usize> {
+    syscall(SYSCALL_SHMAT, [shmid, addr, flags as usize])
+}
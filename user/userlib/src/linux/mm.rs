@@ -0,0 +1,68 @@
+//! 地址空间相关的系统调用
+use crate::ipc::{MapFlags, ProtFlags};
+use crate::syscall::*;
+
+use super::{syscall, Result};
+
+/// 将堆顶设置为 `addr`，返回新的堆顶，`addr` 为 0 时返回当前堆顶
+///
+/// # Safety
+///
+/// 缩小堆会释放堆顶以上的内存，调用者需要保证这些内存不再被使用，Mstd 的分配器不使用 `brk`
+pub unsafe fn brk(addr: usize) -> Result<usize> {
+    syscall(SYSCALL_BRK, [addr])
+}
+
+/// 返回映射的起始地址
+///
+/// # Safety
+///
+/// 指定 `MAP_FIXED` 时会替换 `[addr, addr + len)` 中已有的映射
+pub unsafe fn mmap(
+    addr: usize,
+    len: usize,
+    prot: ProtFlags,
+    flags: MapFlags,
+    fd: usize,
+    offset: usize,
+) -> Result<usize> {
+    syscall(
+        SYSCALL_MMAP,
+        [
+            addr,
+            len,
+            prot.bits() as usize,
+            flags.bits() as usize,
+            fd,
+            offset,
+        ],
+    )
+}
+
+/// # Safety
+///
+/// 调用者需要保证 `[addr, addr + len)` 中的内存不再被使用
+pub unsafe fn munmap(addr: usize, len: usize) -> Result<()> {
+    syscall(SYSCALL_MUNMAP, [addr, len]).map(drop)
+}
+
+/// # Safety
+///
+/// 去掉读写权限后访问 `[addr, addr + len)` 会触发 `SIGSEGV`
+pub unsafe fn mprotect(addr: usize, len: usize, prot: ProtFlags) -> Result<()> {
+    syscall(SYSCALL_MPROTECT, [addr, len, prot.bits() as usize]).map(drop)
+}
+
+/// 将文件映射的修改写回文件
+pub fn msync(addr: usize, len: usize, flags: usize) -> Result<()> {
+    syscall(SYSCALL_MSYNC, [addr, len, flags]).map(drop)
+}
+
+pub fn madvise(addr: usize, len: usize, advice: usize) -> Result<()> {
+    syscall(SYSCALL_MADVISE, [addr, len, advice]).map(drop)
+}
+
+/// 内核目前只返回 0
+pub fn membarrier(cmd: usize, flags: usize) -> Result<usize> {
+    syscall(SYSCALL_MEMBARRIER, [cmd, flags])
+}
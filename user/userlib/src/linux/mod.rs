@@ -0,0 +1,111 @@
+//! 内核系统调用的类型安全封装
+//!
+//! 每一个内核中通过 `#[syscall_func]` 注册的系统调用都在这里有对应的函数，函数名与 Linux 中的系统调用名一致，
+//! 出错时返回 [`Error`]。系统调用号由 `build.rs` 从内核源码中生成，内核新增系统调用而这里没有封装时构建失败。
+//!
+//! 需要传入任意地址、会改变地址空间或者控制流的系统调用(如 [`mmap`]、[`clone`]、[`rt_sigreturn`])标记为 `unsafe`。
+use alloc::ffi::CString;
+use alloc::vec::Vec;
+
+use constants::signal::SigAction;
+use constants::sys::{Rusage, Sysinfo};
+pub use constants::LinuxErrno as Errno;
+use constants::PrLimit;
+pub use fs::*;
+pub use gui::*;
+pub use ipc::*;
+pub use mm::*;
+pub use net::*;
+pub use signal::*;
+pub use system::*;
+pub use task::*;
+pub use time::*;
+
+use crate::socket::Sockaddr;
+pub use crate::syscall::SYSCALL_TABLE;
+
+mod fs;
+mod gui;
+mod ipc;
+mod mm;
+mod net;
+mod signal;
+mod system;
+mod task;
+mod time;
+
+pub type Result<T> = core::result::Result<T, Error>;
+
+/// 系统调用失败时的错误
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /// [`Errno`] 中定义的错误码
+    Errno(Errno),
+    /// [`Errno`] 中没有定义的错误码，保存内核返回的原始值
+    Unknown(isize),
+}
+
+impl From<Errno> for Error {
+    fn from(errno: Errno) -> Self {
+        Self::Errno(errno)
+    }
+}
+
+/// 将系统调用的返回值转换为 [`Result`]
+fn check(ret: isize) -> Result<usize> {
+    if ret < 0 {
+        Err(Errno::try_from(ret).map_or(Error::Unknown(ret), Error::Errno))
+    } else {
+        Ok(ret as usize)
+    }
+}
+
+/// 执行系统调用号为 `id` 的系统调用，不足 6 个的参数补 0
+fn syscall<const N: usize>(id: usize, args: [usize; N]) -> Result<usize> {
+    let mut all = [0; 6];
+    all[..N].copy_from_slice(&args);
+    check(crate::syscall::syscall(id, all))
+}
+
+/// 将路径等字符串转换为以 `\0` 结尾的字符串，字符串中间含有 `\0` 时返回 `EINVAL`
+fn c_str(s: &str) -> Result<CString> {
+    CString::new(s).map_err(|_| Errno::EINVAL.into())
+}
+
+/// 将字符串数组转换为以空指针结尾的指针数组，`strings` 需要在使用返回值期间保持有效
+fn c_str_array(strings: &[CString]) -> Vec<usize> {
+    strings
+        .iter()
+        .map(|s| s.as_ptr() as usize)
+        .chain(Some(0))
+        .collect()
+}
+
+/// 全零的字节是合法值的结构，用于传给内核填充
+///
+/// # Safety
+///
+/// 只能为由整数、数组以及同样满足要求的结构组成的 `repr(C)` 结构实现，
+/// 不能包含引用、`NonNull`、枚举等不允许全零的字段
+unsafe trait Zeroable {}
+
+unsafe impl Zeroable for Sockaddr {}
+unsafe impl Zeroable for SigAction {}
+unsafe impl Zeroable for Utsname {}
+unsafe impl Zeroable for Sysinfo {}
+unsafe impl Zeroable for PrLimit {}
+unsafe impl Zeroable for Rusage {}
+
+/// 用全零初始化内核会填充的结构
+fn zeroed<T: Zeroable>() -> T {
+    // SAFETY: `Zeroable` 保证全零的字节是 `T` 的合法值
+    unsafe { core::mem::zeroed() }
+}
+
+fn opt_ptr<T>(value: Option<&T>) -> usize {
+    value.map_or(0, |value| value as *const T as usize)
+}
+
+fn opt_mut_ptr<T>(value: Option<&mut T>) -> usize {
+    value.map_or(0, |value| value as *mut T as usize)
+}
@@ -0,0 +1,180 @@
+//! 网络相关的系统调用，地址使用 [`Sockaddr`] 表示
+use core::mem::size_of;
+
+use crate::socket::{Domain, ShutdownFlag, Sockaddr};
+use crate::syscall::*;
+
+use super::{syscall, zeroed, Result};
+
+/// 创建套接字，`socket_type` 可以与 `SOCK_NONBLOCK`、`SOCK_CLOEXEC` 按位或
+pub fn socket(domain: Domain, socket_type: usize, protocol: usize) -> Result<usize> {
+    syscall(SYSCALL_SOCKET, [domain as usize, socket_type, protocol])
+}
+
+/// 创建一对相互连接的套接字
+pub fn socketpair(domain: Domain, socket_type: usize, protocol: usize) -> Result<[u32; 2]> {
+    let mut sv = [0u32; 2];
+    syscall(
+        SYSCALL_SOCKETPAIR,
+        [
+            domain as usize,
+            socket_type,
+            protocol,
+            sv.as_mut_ptr() as usize,
+        ],
+    )?;
+    Ok(sv)
+}
+
+pub fn bind(socket: usize, address: &Sockaddr) -> Result<()> {
+    syscall(
+        SYSCALL_BIND,
+        [
+            socket,
+            address as *const Sockaddr as usize,
+            size_of::<Sockaddr>(),
+        ],
+    )
+    .map(drop)
+}
+
+pub fn listen(socket: usize, backlog: usize) -> Result<()> {
+    syscall(SYSCALL_LISTEN, [socket, backlog]).map(drop)
+}
+
+/// 接受一个连接，返回新的套接字与对端地址
+pub fn accept(socket: usize) -> Result<(usize, Sockaddr)> {
+    let mut address = zeroed::<Sockaddr>();
+    let mut len = size_of::<Sockaddr>() as u32;
+    let fd = syscall(
+        SYSCALL_ACCEPT,
+        [
+            socket,
+            &mut address as *mut Sockaddr as usize,
+            &mut len as *mut u32 as usize,
+        ],
+    )?;
+    Ok((fd, address))
+}
+
+/// 接受一个连接，`flags` 可以包含 `SOCK_NONBLOCK`、`SOCK_CLOEXEC`，返回新的套接字与对端地址
+pub fn accept4(socket: usize, flags: usize) -> Result<(usize, Sockaddr)> {
+    let mut address = zeroed::<Sockaddr>();
+    let mut len = size_of::<Sockaddr>() as u32;
+    let fd = syscall(
+        SYSCALL_ACCEPT4,
+        [
+            socket,
+            &mut address as *mut Sockaddr as usize,
+            &mut len as *mut u32 as usize,
+            flags,
+        ],
+    )?;
+    Ok((fd, address))
+}
+
+pub fn connect(socket: usize, address: &Sockaddr) -> Result<()> {
+    syscall(
+        SYSCALL_CONNECT,
+        [
+            socket,
+            address as *const Sockaddr as usize,
+            size_of::<Sockaddr>(),
+        ],
+    )
+    .map(drop)
+}
+
+/// 获取套接字绑定的本地地址
+pub fn getsockname(socket: usize) -> Result<Sockaddr> {
+    sockaddr_of(SYSCALL_GETSOCKNAME, socket)
+}
+
+/// 获取已连接套接字的对端地址
+pub fn getpeername(socket: usize) -> Result<Sockaddr> {
+    sockaddr_of(SYSCALL_GETPEERNAME, socket)
+}
+
+fn sockaddr_of(id: usize, socket: usize) -> Result<Sockaddr> {
+    let mut address = zeroed::<Sockaddr>();
+    let mut len = size_of::<Sockaddr>() as u32;
+    syscall(
+        id,
+        [
+            socket,
+            &mut address as *mut Sockaddr as usize,
+            &mut len as *mut u32 as usize,
+        ],
+    )?;
+    Ok(address)
+}
+
+/// 发送数据，`address` 为 `None` 时发送给已连接的对端
+pub fn sendto(
+    socket: usize,
+    buf: &[u8],
+    flags: usize,
+    address: Option<&Sockaddr>,
+) -> Result<usize> {
+    let (address, len) = address.map_or((0, 0), |address| {
+        (address as *const Sockaddr as usize, size_of::<Sockaddr>())
+    });
+    syscall(
+        SYSCALL_SENDTO,
+        [
+            socket,
+            buf.as_ptr() as usize,
+            buf.len(),
+            flags,
+            address,
+            len,
+        ],
+    )
+}
+
+/// 接收数据，返回接收的字节数与发送方地址
+pub fn recvfrom(socket: usize, buf: &mut [u8], flags: usize) -> Result<(usize, Sockaddr)> {
+    let mut address = zeroed::<Sockaddr>();
+    let mut len = size_of::<Sockaddr>() as u32;
+    let n = syscall(
+        SYSCALL_RECVFROM,
+        [
+            socket,
+            buf.as_mut_ptr() as usize,
+            buf.len(),
+            flags,
+            &mut address as *mut Sockaddr as usize,
+            &mut len as *mut u32 as usize,
+        ],
+    )?;
+    Ok((n, address))
+}
+
+/// 设置套接字选项，选项的取值按原始字节传入，类型化的接口见 [`crate::socket::setsockopt`]
+pub fn setsockopt(socket: usize, level: usize, name: usize, value: &[u8]) -> Result<()> {
+    syscall(
+        SYSCALL_SETSOCKOPT,
+        [socket, level, name, value.as_ptr() as usize, value.len()],
+    )
+    .map(drop)
+}
+
+/// 获取套接字选项，返回写入 `value` 的字节数
+pub fn getsockopt(socket: usize, level: usize, name: usize, value: &mut [u8]) -> Result<usize> {
+    let mut len = value.len() as u32;
+    syscall(
+        SYSCALL_GETSOCKOPT,
+        [
+            socket,
+            level,
+            name,
+            value.as_mut_ptr() as usize,
+            &mut len as *mut u32 as usize,
+        ],
+    )?;
+    Ok(len as usize)
+}
+
+pub fn shutdown(socket: usize, how: ShutdownFlag) -> Result<()> {
+    syscall(SYSCALL_SHUTDOWN, [socket, how as usize]).map(drop)
+}
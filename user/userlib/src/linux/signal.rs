@@ -0,0 +1,92 @@
+//! 信号相关的系统调用
+use constants::signal::{SigAction, SigInfo};
+
+use crate::syscall::*;
+use crate::time::TimeSpec;
+
+use super::{opt_mut_ptr, opt_ptr, syscall, zeroed, Result};
+
+/// 信号集，第 `n - 1` 位表示信号 `n`
+pub type SigSet = usize;
+
+/// `rt_sigprocmask` 的 `how`
+pub const SIG_BLOCK: usize = 0;
+pub const SIG_UNBLOCK: usize = 1;
+pub const SIG_SETMASK: usize = 2;
+
+/// 向进程 `pid` 发送信号 `sig`
+pub fn kill(pid: usize, sig: usize) -> Result<()> {
+    syscall(SYSCALL_KILL, [pid, sig]).map(drop)
+}
+
+/// 向线程 `tid` 发送信号 `sig`
+pub fn tkill(tid: usize, sig: usize) -> Result<()> {
+    syscall(SYSCALL_TKILL, [tid, sig]).map(drop)
+}
+
+/// 阻塞直到收到信号，总是返回 `EINTR`
+pub fn rt_sigsuspend(mask: &SigSet) -> Result<()> {
+    syscall(
+        SYSCALL_RT_SIGSUSPEND,
+        [
+            mask as *const SigSet as usize,
+            core::mem::size_of::<SigSet>(),
+        ],
+    )
+    .map(drop)
+}
+
+/// 设置信号 `sig` 的处理方式为 `action`，返回原来的处理方式
+///
+/// # Safety
+///
+/// `action` 中的处理函数会在收到信号时在任意位置被调用
+pub unsafe fn rt_sigaction(sig: usize, action: Option<&SigAction>) -> Result<SigAction> {
+    let mut old = zeroed::<SigAction>();
+    syscall(
+        SYSCALL_RT_SIGACTION,
+        [sig, opt_ptr(action), &mut old as *mut SigAction as usize],
+    )?;
+    Ok(old)
+}
+
+/// 按照 `how` 修改信号屏蔽位，返回原来的屏蔽位
+pub fn rt_sigprocmask(how: usize, set: Option<&SigSet>) -> Result<SigSet> {
+    let mut old: SigSet = 0;
+    syscall(
+        SYSCALL_RT_SIGPROCMASK,
+        [
+            how,
+            opt_ptr(set),
+            &mut old as *mut SigSet as usize,
+            core::mem::size_of::<SigSet>(),
+        ],
+    )?;
+    Ok(old)
+}
+
+/// 等待 `set` 中的信号，返回收到的信号，超时返回 `EAGAIN`
+pub fn rt_sigtimedwait(
+    set: &SigSet,
+    info: Option<&mut SigInfo>,
+    timeout: Option<&TimeSpec>,
+) -> Result<usize> {
+    syscall(
+        SYSCALL_RT_SIGTIMEDWAIT,
+        [
+            set as *const SigSet as usize,
+            opt_mut_ptr(info),
+            opt_ptr(timeout),
+        ],
+    )
+}
+
+/// 从信号处理函数返回，恢复收到信号前的上下文
+///
+/// # Safety
+///
+/// 只能在内核调用的信号处理函数中使用，内核通常已经将返回地址设置为执行该系统调用的代码
+pub unsafe fn rt_sigreturn() -> ! {
+    let _ = syscall(SYSCALL_RT_SIGRETURN, []);
+    unreachable!("rt_sigreturn returned")
+}
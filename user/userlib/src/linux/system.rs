@@ -0,0 +1,43 @@
+//! 系统信息相关的系统调用
+use constants::sys::Sysinfo;
+
+use crate::syscall::*;
+
+use super::{syscall, zeroed, Result};
+
+/// 与 `linux` 中 `struct utsname` 对应，各字段为以 `\0` 结尾的字符串
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct Utsname {
+    pub sysname: [u8; 65],
+    pub nodename: [u8; 65],
+    pub release: [u8; 65],
+    pub version: [u8; 65],
+    pub machine: [u8; 65],
+    pub domainname: [u8; 65],
+}
+
+/// 读取或者清除内核日志，`action` 为 `SYSLOG_ACTION_*`，返回读取的长度
+pub fn syslog(action: u32, buf: &mut [u8]) -> Result<usize> {
+    syscall(
+        SYSCALL_SYSLOG,
+        [action as usize, buf.as_mut_ptr() as usize, buf.len()],
+    )
+}
+
+pub fn uname() -> Result<Utsname> {
+    let mut name = zeroed::<Utsname>();
+    syscall(SYSCALL_UNAME, [&mut name as *mut Utsname as usize])?;
+    Ok(name)
+}
+
+pub fn sysinfo() -> Result<Sysinfo> {
+    let mut info = zeroed::<Sysinfo>();
+    syscall(SYSCALL_SYSINFO, [&mut info as *mut Sysinfo as usize])?;
+    Ok(info)
+}
+
+/// 关闭系统，成功时不会返回
+pub fn system_shutdown() -> Result<()> {
+    syscall(SYSCALL_SYSTEM_SHUTDOWN, []).map(drop)
+}
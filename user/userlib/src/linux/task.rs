@@ -0,0 +1,291 @@
+//! 进程与线程相关的系统调用
+use alloc::vec::Vec;
+
+use constants::sys::Rusage;
+use constants::PrLimit;
+
+use crate::process::CloneFlags;
+use crate::syscall::*;
+use crate::time::Times;
+
+use super::{c_str, c_str_array, opt_mut_ptr, opt_ptr, syscall, zeroed, Error, Result};
+
+/// 结束当前线程
+pub fn exit(code: i32) -> ! {
+    let _ = syscall(SYSCALL_EXIT, [code as usize]);
+    unreachable!("exit returned")
+}
+
+/// 结束当前进程中的所有线程
+pub fn exit_group(code: i32) -> ! {
+    let _ = syscall(SYSCALL_EXIT_GROUP, [code as usize]);
+    unreachable!("exit_group returned")
+}
+
+/// 等待子进程状态改变，返回子进程号以及退出状态
+pub fn wait4(pid: isize, options: u32, rusage: Option<&mut Rusage>) -> Result<(usize, i32)> {
+    let mut status = 0i32;
+    let pid = syscall(
+        SYSCALL_WAIT4,
+        [
+            pid as usize,
+            &mut status as *mut i32 as usize,
+            options as usize,
+            opt_mut_ptr(rusage),
+        ],
+    )?;
+    Ok((pid, status))
+}
+
+/// `info` 需要指向 `siginfo_t` 大小的缓冲区
+pub fn waitid(
+    id_type: usize,
+    id: usize,
+    info: &mut [u8; 128],
+    options: u32,
+    rusage: Option<&mut Rusage>,
+) -> Result<()> {
+    syscall(
+        SYSCALL_WAITID,
+        [
+            id_type,
+            id,
+            info.as_mut_ptr() as usize,
+            options as usize,
+            opt_mut_ptr(rusage),
+        ],
+    )
+    .map(drop)
+}
+
+/// 线程退出时内核将 `tid` 清零并唤醒等待在该地址上的 futex，返回当前线程号
+///
+/// # Safety
+///
+/// `tid` 需要在线程退出前一直有效，内核清零时写入 8 字节
+pub unsafe fn set_tid_address(tid: *mut u32) -> Result<usize> {
+    syscall(SYSCALL_SET_TID_ADDRESS, [tid as usize])
+}
+
+/// # Safety
+///
+/// 子进程(线程)从系统调用返回后直接使用 `stack` 作为栈，共享地址空间时调用者需要保证子进程不会破坏父进程的栈，
+/// 通常应使用 [`crate::thread::spawn`]
+pub unsafe fn clone(
+    flags: CloneFlags,
+    stack: usize,
+    ptid: Option<&mut u32>,
+    tls: usize,
+    ctid: Option<&mut u32>,
+) -> Result<usize> {
+    syscall(
+        SYSCALL_CLONE,
+        [
+            flags.bits() as usize,
+            stack,
+            opt_mut_ptr(ptid),
+            tls,
+            opt_mut_ptr(ctid),
+        ],
+    )
+}
+
+/// 执行 `path` 处的程序，成功时不会返回，失败时返回错误码
+pub fn execve(path: &str, args: &[&str], envs: &[&str]) -> Error {
+    match try_execve(path, args, envs) {
+        Ok(_) => unreachable!("execve returned"),
+        Err(e) => e,
+    }
+}
+
+fn try_execve(path: &str, args: &[&str], envs: &[&str]) -> Result<usize> {
+    let path = c_str(path)?;
+    let args = args
+        .iter()
+        .map(|arg| c_str(arg))
+        .collect::<Result<Vec<_>>>()?;
+    let envs = envs
+        .iter()
+        .map(|env| c_str(env))
+        .collect::<Result<Vec<_>>>()?;
+    let (argv, envp) = (c_str_array(&args), c_str_array(&envs));
+    syscall(
+        SYSCALL_EXECVE,
+        [
+            path.as_ptr() as usize,
+            argv.as_ptr() as usize,
+            envp.as_ptr() as usize,
+        ],
+    )
+}
+
+/// 调度参数，与 `linux` 中 `struct sched_param` 对应
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SchedParam {
+    pub sched_priority: i32,
+}
+
+pub fn sched_setparam(pid: usize, param: &SchedParam) -> Result<()> {
+    syscall(
+        SYSCALL_SCHED_SETPARAM,
+        [pid, param as *const SchedParam as usize],
+    )
+    .map(drop)
+}
+
+pub fn sched_getparam(pid: usize) -> Result<SchedParam> {
+    let mut param = SchedParam::default();
+    syscall(
+        SYSCALL_SCHED_GETPARAM,
+        [pid, &mut param as *mut SchedParam as usize],
+    )?;
+    Ok(param)
+}
+
+pub fn sched_setscheduler(pid: usize, policy: usize, param: &SchedParam) -> Result<()> {
+    syscall(
+        SYSCALL_SCHED_SETSCHEDULER,
+        [pid, policy, param as *const SchedParam as usize],
+    )
+    .map(drop)
+}
+
+/// 返回调度策略
+pub fn sched_getscheduler(pid: usize) -> Result<usize> {
+    syscall(SYSCALL_SCHED_GETSCHEDULER, [pid])
+}
+
+/// `mask` 的第 i 位表示可以在第 i 个核上运行
+pub fn sched_setaffinity(pid: usize, mask: usize) -> Result<()> {
+    syscall(
+        SYSCALL_SCHED_SETAFFINITY,
+        [
+            pid,
+            core::mem::size_of::<usize>(),
+            &mask as *const usize as usize,
+        ],
+    )
+    .map(drop)
+}
+
+/// 内核目前只支持查询当前进程(`pid` 为 0)
+pub fn sched_getaffinity(pid: usize) -> Result<usize> {
+    let mut mask = 0usize;
+    syscall(
+        SYSCALL_SCHED_GETAFFINITY,
+        [
+            pid,
+            core::mem::size_of::<usize>(),
+            &mut mask as *mut usize as usize,
+        ],
+    )?;
+    Ok(mask)
+}
+
+pub fn sched_yield() {
+    let _ = syscall(SYSCALL_SCHED_YIELD, []);
+}
+
+/// 返回进程的运行时间以及系统启动以来的时钟数
+pub fn times() -> Result<(Times, usize)> {
+    let mut tms = Times::new();
+    let ticks = syscall(SYSCALL_TIMES, [&mut tms as *mut Times as usize])?;
+    Ok((tms, ticks))
+}
+
+pub fn setpgid(pid: usize, pgid: usize) -> Result<()> {
+    syscall(SYSCALL_SETPGID, [pid, pgid]).map(drop)
+}
+
+pub fn getpgid(pid: usize) -> Result<usize> {
+    syscall(SYSCALL_GETPGID, [pid])
+}
+
+/// 创建新的会话，返回会话号
+pub fn setsid() -> Result<usize> {
+    syscall(SYSCALL_SETSID, [])
+}
+
+/// `resource` 为 `RLIMIT_*`
+pub fn getrlimit(resource: usize) -> Result<PrLimit> {
+    let mut limit = zeroed::<PrLimit>();
+    syscall(
+        SYSCALL_GETRLIMIT,
+        [resource, &mut limit as *mut PrLimit as usize],
+    )?;
+    Ok(limit)
+}
+
+pub fn setrlimit(resource: usize, limit: &PrLimit) -> Result<()> {
+    syscall(
+        SYSCALL_SETRLIMIT,
+        [resource, limit as *const PrLimit as usize],
+    )
+    .map(drop)
+}
+
+/// 设置进程 `pid` 的资源限制为 `new_limit`，返回原来的限制
+pub fn prlimit64(pid: usize, resource: usize, new_limit: Option<&PrLimit>) -> Result<PrLimit> {
+    let mut old = zeroed::<PrLimit>();
+    syscall(
+        SYSCALL_PRLIMIT64,
+        [
+            pid,
+            resource,
+            opt_ptr(new_limit),
+            &mut old as *mut PrLimit as usize,
+        ],
+    )?;
+    Ok(old)
+}
+
+/// `who` 为 `RUSAGE_SELF`(0)、`RUSAGE_CHILDREN`(-1) 或 `RUSAGE_THREAD`(1)
+pub fn getrusage(who: isize) -> Result<Rusage> {
+    let mut usage = zeroed::<Rusage>();
+    syscall(
+        SYSCALL_GETRUSAGE,
+        [who as usize, &mut usage as *mut Rusage as usize],
+    )?;
+    Ok(usage)
+}
+
+pub fn getpid() -> usize {
+    syscall(SYSCALL_GETPID, []).unwrap_or(0)
+}
+
+pub fn getppid() -> usize {
+    syscall(SYSCALL_GETPPID, []).unwrap_or(0)
+}
+
+pub fn getuid() -> usize {
+    syscall(SYSCALL_GETUID, []).unwrap_or(0)
+}
+
+pub fn geteuid() -> usize {
+    syscall(SYSCALL_GETEUID, []).unwrap_or(0)
+}
+
+pub fn getgid() -> usize {
+    syscall(SYSCALL_GETGID, []).unwrap_or(0)
+}
+
+pub fn getegid() -> usize {
+    syscall(SYSCALL_GETEGID, []).unwrap_or(0)
+}
+
+pub fn gettid() -> usize {
+    syscall(SYSCALL_GETTID, []).unwrap_or(0)
+}
+
+/// 返回指向进程 `pid` 的文件描述符
+pub fn pidfd_open(pid: usize, flags: usize) -> Result<usize> {
+    syscall(SYSCALL_PIDFD_OPEN, [pid, flags])
+}
+
+/// # Safety
+///
+/// `PTRACE_POKE*`、`PTRACE_SETREGS` 等请求会修改被跟踪进程的内存和寄存器，`addr` 和 `data` 的含义由 `request` 决定
+pub unsafe fn ptrace(request: usize, pid: usize, addr: usize, data: usize) -> Result<usize> {
+    syscall(SYSCALL_PTRACE, [request, pid, addr, data])
+}
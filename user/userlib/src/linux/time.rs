@@ -0,0 +1,92 @@
+//! 时间相关的系统调用
+use crate::syscall::*;
+use crate::time::{TimeSpec, TimeVal};
+
+use super::{opt_mut_ptr, syscall, Result};
+
+/// 与 `linux` 中 `struct itimerval` 对应
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ITimerVal {
+    /// 定时器到期后重新设置的时间
+    pub it_interval: TimeVal,
+    /// 定时器剩余的时间
+    pub it_value: TimeVal,
+}
+
+/// 睡眠 `req` 时长，被信号打断时将剩余时间写入 `rem`
+pub fn nanosleep(req: &TimeSpec, rem: Option<&mut TimeSpec>) -> Result<()> {
+    syscall(
+        SYSCALL_NANOSLEEP,
+        [req as *const TimeSpec as usize, opt_mut_ptr(rem)],
+    )
+    .map(drop)
+}
+
+/// `which` 为 `ITIMER_REAL`(0)、`ITIMER_VIRTUAL`(1) 或 `ITIMER_PROF`(2)
+pub fn getitimer(which: usize) -> Result<ITimerVal> {
+    let mut value = ITimerVal::default();
+    syscall(
+        SYSCALL_GETITIMER,
+        [which, &mut value as *mut ITimerVal as usize],
+    )?;
+    Ok(value)
+}
+
+/// 设置定时器，返回原来的定时器
+pub fn setitimer(which: usize, value: &ITimerVal) -> Result<ITimerVal> {
+    let mut old = ITimerVal::default();
+    syscall(
+        SYSCALL_SETITIMER,
+        [
+            which,
+            value as *const ITimerVal as usize,
+            &mut old as *mut ITimerVal as usize,
+        ],
+    )?;
+    Ok(old)
+}
+
+pub fn clock_gettime(clock_id: usize) -> Result<TimeSpec> {
+    let mut ts = TimeSpec::default();
+    syscall(
+        SYSCALL_CLOCK_GETTIME,
+        [clock_id, &mut ts as *mut TimeSpec as usize],
+    )?;
+    Ok(ts)
+}
+
+/// 返回时钟的精度
+pub fn clock_getres(clock_id: usize) -> Result<TimeSpec> {
+    let mut res = TimeSpec::default();
+    syscall(
+        SYSCALL_CLOCK_GETRES,
+        [clock_id, &mut res as *mut TimeSpec as usize],
+    )?;
+    Ok(res)
+}
+
+/// 见 [`crate::time::clock_nanosleep`]
+pub fn clock_nanosleep(
+    clock_id: usize,
+    flags: usize,
+    req: &TimeSpec,
+    rem: Option<&mut TimeSpec>,
+) -> Result<()> {
+    syscall(
+        SYSCALL_CLOCK_NANOSLEEP,
+        [
+            clock_id,
+            flags,
+            req as *const TimeSpec as usize,
+            opt_mut_ptr(rem),
+        ],
+    )
+    .map(drop)
+}
+
+pub fn gettimeofday() -> Result<TimeVal> {
+    let mut tv = TimeVal::default();
+    syscall(SYSCALL_GETTIMEOFDAY, [&mut tv as *mut TimeVal as usize])?;
+    Ok(tv)
+}
@@ -0,0 +1,147 @@
+//! 单线程执行器
+use alloc::boxed::Box;
+use alloc::collections::VecDeque;
+use alloc::sync::Arc;
+use alloc::task::Wake;
+use core::future::Future;
+use core::pin::Pin;
+use core::sync::atomic::{AtomicBool, Ordering};
+use core::task::{Context, Poll, Waker};
+
+use spin::Lazy;
+
+use crate::sync::Mutex;
+use crate::thread::m_yield;
+
+use super::reactor;
+
+type BoxFuture = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+/// 等待执行的任务
+static RUN_QUEUE: Lazy<Mutex<VecDeque<Arc<Task>>>> = Lazy::new(|| Mutex::new(VecDeque::new()));
+
+struct Task {
+    future: Mutex<Option<BoxFuture>>,
+    /// 任务是否已经在 [`RUN_QUEUE`] 中
+    queued: AtomicBool,
+}
+
+impl Wake for Task {
+    fn wake(self: Arc<Self>) {
+        if !self.queued.swap(true, Ordering::AcqRel) {
+            RUN_QUEUE.lock().push_back(self);
+        }
+    }
+}
+
+impl Task {
+    fn run(self: Arc<Self>) {
+        self.queued.store(false, Ordering::Release);
+        let waker = Waker::from(self.clone());
+        let mut cx = Context::from_waker(&waker);
+        let mut future = self.future.lock();
+        if let Some(fut) = future.as_mut() {
+            if fut.as_mut().poll(&mut cx).is_ready() {
+                *future = None;
+            }
+        }
+    }
+}
+
+struct JoinState<T> {
+    output: Option<T>,
+    waker: Option<Waker>,
+}
+
+/// 等待 [`spawn`] 创建的任务结束并取回结果
+pub struct JoinHandle<T> {
+    state: Arc<Mutex<JoinState<T>>>,
+}
+
+impl<T> JoinHandle<T> {
+    pub fn is_finished(&self) -> bool {
+        self.state.lock().output.is_some()
+    }
+}
+
+impl<T> Future for JoinHandle<T> {
+    type Output = T;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.state.lock();
+        match state.output.take() {
+            Some(output) => Poll::Ready(output),
+            None => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// 创建一个任务，任务在 [`block_on`] 中执行
+pub fn spawn<F>(future: F) -> JoinHandle<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    let state = Arc::new(Mutex::new(JoinState {
+        output: None,
+        waker: None,
+    }));
+    let join = JoinHandle {
+        state: state.clone(),
+    };
+    let task = Arc::new(Task {
+        future: Mutex::new(Some(Box::pin(async move {
+            let output = future.await;
+            let waker = {
+                let mut state = state.lock();
+                state.output = Some(output);
+                state.waker.take()
+            };
+            waker.map(Waker::wake);
+        }))),
+        queued: AtomicBool::new(false),
+    });
+    task.wake();
+    join
+}
+
+/// 主任务的唤醒标志
+struct MainWaker(AtomicBool);
+
+impl Wake for MainWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.store(true, Ordering::Release);
+    }
+}
+
+/// 执行 `future` 直到完成，期间同时执行 [`spawn`] 创建的任务
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    let main = Arc::new(MainWaker(AtomicBool::new(true)));
+    let waker = Waker::from(main.clone());
+    let mut cx = Context::from_waker(&waker);
+    let mut future = core::pin::pin!(future);
+    loop {
+        if main.0.swap(false, Ordering::AcqRel) {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                return output;
+            }
+        }
+        // 只执行当前已经就绪的任务，避免主任务饿死
+        let ready = RUN_QUEUE.lock().len();
+        for _ in 0..ready {
+            let task = RUN_QUEUE.lock().pop_front();
+            match task {
+                Some(task) => task.run(),
+                None => break,
+            }
+        }
+        let idle = !main.0.load(Ordering::Acquire) && RUN_QUEUE.lock().is_empty();
+        if !reactor::wait(idle) {
+            // 没有可以等待的事件，可能在等待其它线程
+            m_yield();
+        }
+    }
+}
@@ -0,0 +1,31 @@
+//! 异步运行时
+//!
+//! 运行时由三部分组成：
+//! - [`reactor`]：记录等待 I/O 的文件描述符和定时器，通过 `ppoll` 等待事件并唤醒对应的任务，
+//!   定时器到期前没有文件描述符需要等待时通过 `clock_nanosleep` 睡眠；
+//! - [`executor`]：单线程的执行器，[`block_on`] 驱动主任务，[`spawn`] 创建的任务在其中轮流执行；
+//! - 基于非阻塞套接字的 [`TcpListener`]、[`TcpStream`]、[`UdpSocket`] 以及 [`sleep`]、[`timeout`] 等定时器。
+//!
+//! ```no_run
+//! use core::net::{Ipv4Addr, SocketAddrV4};
+//! use Mstd::runtime::{block_on, TcpListener};
+//!
+//! block_on(async {
+//!     let addr = SocketAddrV4::new(Ipv4Addr::LOCALHOST, 8080);
+//!     let listener = TcpListener::bind(addr).unwrap();
+//!     let (stream, _) = listener.accept().await.unwrap();
+//!     let mut buf = [0u8; 64];
+//!     let len = stream.read(&mut buf).await.unwrap();
+//!     stream.write_all(&buf[..len]).await.unwrap();
+//! });
+//! ```
+//!
+//! 运行时不是线程安全的唤醒源：其它线程唤醒任务时不会打断正在阻塞等待事件的执行器。
+pub use executor::{block_on, spawn, JoinHandle};
+pub use net::{TcpListener, TcpStream, UdpSocket};
+pub use time::{sleep, sleep_until, timeout, Elapsed, Instant, Sleep};
+
+mod executor;
+mod net;
+mod reactor;
+mod time;
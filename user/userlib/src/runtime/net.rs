@@ -0,0 +1,267 @@
+//! 基于非阻塞套接字的异步网络接口，目前只支持 IPv4
+use core::future::poll_fn;
+use core::mem::size_of;
+use core::net::{Ipv4Addr, SocketAddrV4};
+use core::task::Poll;
+
+use crate::fs::{close, read, set_nonblocking, write};
+use crate::socket::{
+    accept, bind, connect, getpeername, getsockname, getsockopt, listen, recvfrom, sendto,
+    shutdown, Domain, ShutdownFlag, SockOpt, SockOptName, Sockaddr, SocketType,
+};
+
+use crate::syscall::sys_socket;
+
+use super::reactor::{self, Interest};
+
+const EAGAIN: isize = -11;
+const EINPROGRESS: isize = -115;
+/// [`TcpListener::bind`] 使用的监听队列长度
+const BACKLOG: usize = 128;
+
+fn check(res: isize) -> Result<usize, isize> {
+    if res < 0 {
+        Err(res)
+    } else {
+        Ok(res as usize)
+    }
+}
+
+/// 已经设置为非阻塞的套接字，释放时关闭
+#[derive(Debug)]
+struct Socket {
+    fd: usize,
+}
+
+impl Socket {
+    fn new(socket_type: SocketType) -> Result<Self, isize> {
+        let socket_type = socket_type as usize | SocketType::SOCK_NONBLOCK as usize;
+        let fd = sys_socket(Domain::AF_INET as usize, socket_type, 0);
+        check(fd).map(|fd| Socket { fd })
+    }
+
+    fn bind(&self, addr: SocketAddrV4) -> Result<(), isize> {
+        let addr = Sockaddr::from(addr);
+        check(bind(self.fd, &addr, size_of::<Sockaddr>())).map(|_| ())
+    }
+
+    fn local_addr(&self) -> Result<SocketAddrV4, isize> {
+        let mut addr = Sockaddr::default();
+        let mut len = size_of::<Sockaddr>();
+        check(getsockname(self.fd, &mut addr, &mut len))?;
+        Ok(addr.into())
+    }
+
+    fn peer_addr(&self) -> Result<SocketAddrV4, isize> {
+        let mut addr = Sockaddr::default();
+        let mut len = size_of::<Sockaddr>();
+        check(getpeername(self.fd, &mut addr, &mut len))?;
+        Ok(addr.into())
+    }
+
+    /// 执行 `op`，返回 `EAGAIN` 时等待套接字就绪后重试
+    async fn io(&self, interest: Interest, mut op: impl FnMut() -> isize) -> Result<usize, isize> {
+        poll_fn(|cx| match op() {
+            EAGAIN => {
+                reactor::register(self.fd, interest, cx.waker());
+                Poll::Pending
+            }
+            res => Poll::Ready(check(res)),
+        })
+        .await
+    }
+
+    async fn connect(&self, addr: SocketAddrV4) -> Result<(), isize> {
+        let addr = Sockaddr::from(addr);
+        match connect(self.fd, &addr, size_of::<Sockaddr>()) {
+            EINPROGRESS => {}
+            res => return check(res).map(|_| ()),
+        }
+        // 连接完成后套接字变为可写
+        poll_fn(|cx| {
+            if self.peer_addr().is_ok() {
+                Poll::Ready(())
+            } else {
+                reactor::register(self.fd, Interest::Write, cx.waker());
+                Poll::Pending
+            }
+        })
+        .await;
+        match getsockopt(self.fd, SockOptName::Error)? {
+            SockOpt::Error(0) => Ok(()),
+            SockOpt::Error(errno) => Err(-(errno as isize)),
+            _ => unreachable!(),
+        }
+    }
+
+    async fn send_to(&self, buf: &[u8], addr: Option<SocketAddrV4>) -> Result<usize, isize> {
+        let addr = addr.map(Sockaddr::from);
+        self.io(Interest::Write, || {
+            // 裸指针只在闭包中创建，避免 future 因跨越 await 持有裸指针而不满足 Send
+            let (addr_ptr, addr_len) = match addr.as_ref() {
+                Some(addr) => (addr as *const Sockaddr, size_of::<Sockaddr>()),
+                None => (core::ptr::null(), 0),
+            };
+            sendto(self.fd, buf.as_ptr(), buf.len(), 0, addr_ptr, addr_len)
+        })
+        .await
+    }
+
+    async fn recv_from(&self, buf: &mut [u8]) -> Result<(usize, SocketAddrV4), isize> {
+        let mut addr = Sockaddr::default();
+        let mut len = size_of::<Sockaddr>();
+        let n = self
+            .io(Interest::Read, || {
+                recvfrom(self.fd, buf.as_mut_ptr(), buf.len(), 0, &mut addr, &mut len)
+            })
+            .await?;
+        Ok((n, addr.into()))
+    }
+}
+
+impl Drop for Socket {
+    fn drop(&mut self) {
+        reactor::deregister(self.fd);
+        close(self.fd);
+    }
+}
+
+/// 异步的 TCP 监听套接字
+#[derive(Debug)]
+pub struct TcpListener {
+    socket: Socket,
+}
+
+impl TcpListener {
+    pub fn bind(addr: SocketAddrV4) -> Result<Self, isize> {
+        let socket = Socket::new(SocketType::SOCK_STREAM)?;
+        socket.bind(addr)?;
+        check(listen(socket.fd, BACKLOG))?;
+        Ok(Self { socket })
+    }
+
+    /// 套接字的文件描述符，可以用于 [`setsockopt`](crate::socket::setsockopt) 等
+    pub fn as_raw_fd(&self) -> usize {
+        self.socket.fd
+    }
+
+    pub fn local_addr(&self) -> Result<SocketAddrV4, isize> {
+        self.socket.local_addr()
+    }
+
+    /// 等待新的连接
+    pub async fn accept(&self) -> Result<(TcpStream, SocketAddrV4), isize> {
+        let mut addr = Sockaddr::default();
+        let mut len = size_of::<Sockaddr>();
+        let fd = self
+            .socket
+            .io(Interest::Read, || {
+                accept(self.socket.fd, &mut addr, &mut len)
+            })
+            .await?;
+        // 新的套接字不会继承监听套接字的非阻塞状态
+        let socket = Socket { fd };
+        check(set_nonblocking(fd, true))?;
+        Ok((TcpStream { socket }, addr.into()))
+    }
+}
+
+/// 异步的 TCP 连接
+#[derive(Debug)]
+pub struct TcpStream {
+    socket: Socket,
+}
+
+impl TcpStream {
+    pub async fn connect(addr: SocketAddrV4) -> Result<Self, isize> {
+        let socket = Socket::new(SocketType::SOCK_STREAM)?;
+        socket.connect(addr).await?;
+        Ok(Self { socket })
+    }
+
+    /// 套接字的文件描述符
+    pub fn as_raw_fd(&self) -> usize {
+        self.socket.fd
+    }
+
+    pub fn local_addr(&self) -> Result<SocketAddrV4, isize> {
+        self.socket.local_addr()
+    }
+
+    pub fn peer_addr(&self) -> Result<SocketAddrV4, isize> {
+        self.socket.peer_addr()
+    }
+
+    /// 读取数据，返回 0 表示对端已经关闭连接
+    pub async fn read(&self, buf: &mut [u8]) -> Result<usize, isize> {
+        self.socket
+            .io(Interest::Read, || read(self.socket.fd, buf))
+            .await
+    }
+
+    pub async fn write(&self, buf: &[u8]) -> Result<usize, isize> {
+        self.socket
+            .io(Interest::Write, || write(self.socket.fd, buf))
+            .await
+    }
+
+    pub async fn write_all(&self, mut buf: &[u8]) -> Result<(), isize> {
+        while !buf.is_empty() {
+            let n = self.write(buf).await?;
+            buf = &buf[n..];
+        }
+        Ok(())
+    }
+
+    pub fn shutdown(&self, how: ShutdownFlag) -> Result<(), isize> {
+        check(shutdown(self.socket.fd, how)).map(|_| ())
+    }
+}
+
+/// 异步的 UDP 套接字
+#[derive(Debug)]
+pub struct UdpSocket {
+    socket: Socket,
+}
+
+impl UdpSocket {
+    pub fn bind(addr: SocketAddrV4) -> Result<Self, isize> {
+        let socket = Socket::new(SocketType::SOCK_DGRAM)?;
+        socket.bind(addr)?;
+        Ok(Self { socket })
+    }
+
+    /// 套接字的文件描述符
+    pub fn as_raw_fd(&self) -> usize {
+        self.socket.fd
+    }
+
+    pub fn local_addr(&self) -> Result<SocketAddrV4, isize> {
+        self.socket.local_addr()
+    }
+
+    pub fn peer_addr(&self) -> Result<SocketAddrV4, isize> {
+        self.socket.peer_addr()
+    }
+
+    /// 设置默认的对端地址，之后可以使用 [`UdpSocket::send`] 与 [`UdpSocket::recv`]
+    pub async fn connect(&self, addr: SocketAddrV4) -> Result<(), isize> {
+        self.socket.connect(addr).await
+    }
+
+    pub async fn send_to(&self, buf: &[u8], addr: SocketAddrV4) -> Result<usize, isize> {
+        self.socket.send_to(buf, Some(addr)).await
+    }
+
+    pub async fn recv_from(&self, buf: &mut [u8]) -> Result<(usize, SocketAddrV4), isize> {
+        self.socket.recv_from(buf).await
+    }
+
+    pub async fn send(&self, buf: &[u8]) -> Result<usize, isize> {
+        self.socket.send_to(buf, None).await
+    }
+
+    pub async fn recv(&self, buf: &mut [u8]) -> Result<usize, isize> {
+        self.socket.recv_from(buf).await.map(|(n, _)| n)
+    }
+}
@@ -0,0 +1,153 @@
+//! 等待文件描述符事件和定时器的反应器
+//!
+//! 每次等待时根据等待的任务构造 `pollfd` 数组并调用 `ppoll`。
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use core::task::Waker;
+use core::time::Duration;
+
+use spin::Lazy;
+
+use crate::fs::{ppoll, PollEvents, PollFd};
+use crate::sync::Mutex;
+use crate::time::{clock_nanosleep, TimeSpec, CLOCK_MONOTONIC, TIMER_ABSTIME};
+
+use super::time::Instant;
+
+static REACTOR: Lazy<Mutex<Reactor>> = Lazy::new(|| Mutex::new(Reactor::new()));
+
+/// 等待的方向
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub(crate) enum Interest {
+    Read,
+    Write,
+}
+
+#[derive(Default)]
+struct Source {
+    reader: Option<Waker>,
+    writer: Option<Waker>,
+}
+
+impl Source {
+    fn interest(&self) -> PollEvents {
+        let mut events = PollEvents::empty();
+        events.set(PollEvents::IN, self.reader.is_some());
+        events.set(PollEvents::OUT, self.writer.is_some());
+        events
+    }
+
+    /// 唤醒关心 `revents` 的任务
+    fn wake(&mut self, revents: PollEvents) {
+        let failed = PollEvents::ERR | PollEvents::HUP | PollEvents::NVAL;
+        if revents.intersects(PollEvents::IN | failed) {
+            self.reader.take().map(Waker::wake);
+        }
+        if revents.intersects(PollEvents::OUT | failed) {
+            self.writer.take().map(Waker::wake);
+        }
+    }
+}
+
+struct Reactor {
+    sources: BTreeMap<usize, Source>,
+    /// 按照到期时间排序的定时器，第二项用于区分同一时间到期的定时器
+    timers: BTreeMap<(Instant, u64), Waker>,
+    next_timer: u64,
+}
+
+impl Reactor {
+    fn new() -> Self {
+        Self {
+            sources: BTreeMap::new(),
+            timers: BTreeMap::new(),
+            next_timer: 0,
+        }
+    }
+}
+
+/// 在 `fd` 可读或可写时唤醒 `waker`
+pub(crate) fn register(fd: usize, interest: Interest, waker: &Waker) {
+    let mut reactor = REACTOR.lock();
+    let source = reactor.sources.entry(fd).or_default();
+    let slot = match interest {
+        Interest::Read => &mut source.reader,
+        Interest::Write => &mut source.writer,
+    };
+    match slot {
+        Some(old) if old.will_wake(waker) => {}
+        _ => *slot = Some(waker.clone()),
+    }
+}
+
+/// 文件描述符关闭前需要移除
+pub(crate) fn deregister(fd: usize) {
+    REACTOR.lock().sources.remove(&fd);
+}
+
+/// 添加在 `deadline` 唤醒 `waker` 的定时器，返回定时器的编号
+pub(crate) fn add_timer(deadline: Instant, waker: &Waker) -> u64 {
+    let mut reactor = REACTOR.lock();
+    let id = reactor.next_timer;
+    reactor.next_timer += 1;
+    reactor.timers.insert((deadline, id), waker.clone());
+    id
+}
+
+pub(crate) fn remove_timer(deadline: Instant, id: u64) {
+    REACTOR.lock().timers.remove(&(deadline, id));
+}
+
+/// 等待 I/O 事件或定时器到期并唤醒对应的任务。
+///
+/// `block` 为 `false` 时只检查一次已经就绪的事件。没有可以等待的事件时返回 `false`
+pub(crate) fn wait(block: bool) -> bool {
+    let (mut fds, deadline) = {
+        let reactor = REACTOR.lock();
+        let fds = reactor
+            .sources
+            .iter()
+            .filter(|(_, source)| !source.interest().is_empty())
+            .map(|(fd, source)| PollFd::new(*fd, source.interest()))
+            .collect::<Vec<_>>();
+        let deadline = reactor.timers.keys().next().map(|(deadline, _)| *deadline);
+        (fds, deadline)
+    };
+    if block && fds.is_empty() && deadline.is_none() {
+        return false;
+    }
+    // 阻塞等待时不能持有锁
+    let timeout = if block {
+        deadline.map(|deadline| deadline.saturating_duration_since(Instant::now()))
+    } else {
+        Some(Duration::ZERO)
+    };
+    let mut ready = Vec::new();
+    if fds.is_empty() {
+        if let (true, Some(deadline)) = (block, deadline) {
+            clock_nanosleep(CLOCK_MONOTONIC, TIMER_ABSTIME, &deadline.into());
+        }
+    } else {
+        let timeout = timeout.map(TimeSpec::from);
+        if ppoll(&mut fds, timeout.as_ref()) > 0 {
+            for fd in fds.iter().filter(|fd| !fd.revents.is_empty()) {
+                ready.push((fd.fd as usize, fd.revents));
+            }
+        }
+    }
+
+    let mut reactor = REACTOR.lock();
+    for (fd, events) in ready {
+        if let Some(source) = reactor.sources.get_mut(&fd) {
+            source.wake(events);
+        }
+    }
+    let now = Instant::now();
+    while let Some(entry) = reactor.timers.first_entry() {
+        if entry.key().0 > now {
+            break;
+        }
+        entry.remove().wake();
+    }
+    true
+}
@@ -0,0 +1,128 @@
+//! 定时器
+use core::fmt::{Display, Formatter};
+use core::future::Future;
+use core::ops::{Add, Sub};
+use core::pin::Pin;
+use core::task::{Context, Poll};
+use core::time::Duration;
+
+use crate::time::{clock_gettime, TimeSpec, CLOCK_MONOTONIC};
+
+use super::reactor;
+
+/// 单调时钟上的时间点
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+pub struct Instant(Duration);
+
+impl Instant {
+    pub fn now() -> Self {
+        let mut ts = TimeSpec::default();
+        clock_gettime(CLOCK_MONOTONIC, &mut ts);
+        Self(ts.into())
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        Self::now().saturating_duration_since(*self)
+    }
+
+    pub fn saturating_duration_since(&self, earlier: Instant) -> Duration {
+        self.0.saturating_sub(earlier.0)
+    }
+}
+
+impl Add<Duration> for Instant {
+    type Output = Instant;
+
+    fn add(self, rhs: Duration) -> Self::Output {
+        Instant(self.0 + rhs)
+    }
+}
+
+impl Sub<Instant> for Instant {
+    type Output = Duration;
+
+    fn sub(self, rhs: Instant) -> Self::Output {
+        self.saturating_duration_since(rhs)
+    }
+}
+
+impl From<Instant> for TimeSpec {
+    fn from(instant: Instant) -> Self {
+        instant.0.into()
+    }
+}
+
+/// [`sleep`] 与 [`sleep_until`] 返回的 future
+pub struct Sleep {
+    deadline: Instant,
+    /// 已经注册到反应器中的定时器编号
+    timer: Option<u64>,
+}
+
+impl Sleep {
+    pub fn deadline(&self) -> Instant {
+        self.deadline
+    }
+}
+
+impl Future for Sleep {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if let Some(timer) = self.timer.take() {
+            reactor::remove_timer(self.deadline, timer);
+        }
+        if Instant::now() >= self.deadline {
+            return Poll::Ready(());
+        }
+        self.timer = Some(reactor::add_timer(self.deadline, cx.waker()));
+        Poll::Pending
+    }
+}
+
+impl Drop for Sleep {
+    fn drop(&mut self) {
+        if let Some(timer) = self.timer.take() {
+            reactor::remove_timer(self.deadline, timer);
+        }
+    }
+}
+
+/// 等待 `duration` 时长
+pub fn sleep(duration: Duration) -> Sleep {
+    sleep_until(Instant::now() + duration)
+}
+
+/// 等待到 `deadline`
+pub fn sleep_until(deadline: Instant) -> Sleep {
+    Sleep {
+        deadline,
+        timer: None,
+    }
+}
+
+/// [`timeout`] 超时时返回的错误
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Elapsed;
+
+impl Display for Elapsed {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "deadline has elapsed")
+    }
+}
+
+/// 在 `duration` 时长内等待 `future` 完成
+pub async fn timeout<F: Future>(duration: Duration, future: F) -> Result<F::Output, Elapsed> {
+    let mut future = core::pin::pin!(future);
+    let mut sleep = sleep(duration);
+    core::future::poll_fn(|cx| {
+        if let Poll::Ready(output) = future.as_mut().poll(cx) {
+            return Poll::Ready(Ok(output));
+        }
+        match Pin::new(&mut sleep).poll(cx) {
+            Poll::Ready(()) => Poll::Ready(Err(Elapsed)),
+            Poll::Pending => Poll::Pending,
+        }
+    })
+    .await
+}
@@ -1,10 +1,15 @@
 use alloc::string::String;
+use core::mem::size_of;
+use core::time::Duration;
 
 use crate::syscall::{
     sys_accept, sys_bind, sys_connect, sys_getpeername, sys_getsockname, sys_getsockopt,
     sys_listen, sys_recvfrom, sys_sendto, sys_setsockopt, sys_shutdown, sys_socket,
     sys_socket_pair,
 };
+use crate::time::TimeVal;
+
+pub use abi::Sockaddr;
 
 pub fn socket(domain: Domain, socket_type: SocketType, protocol: usize) -> isize {
     sys_socket(domain as usize, socket_type as usize, protocol)
@@ -94,12 +99,100 @@ pub fn recv(socket: usize, buffer: *mut u8, length: usize, flags: usize) -> isiz
     )
 }
 
-pub fn setsockopt(socket: usize) -> isize {
-    sys_setsockopt()
+/// 设置套接字选项
+pub fn setsockopt(socket: usize, opt: SockOpt) -> isize {
+    let (level, name) = opt.name().level_and_name();
+    match opt {
+        SockOpt::Linger(linger) => {
+            let value = Linger {
+                l_onoff: linger.is_some() as i32,
+                l_linger: linger.map_or(0, |d| d.as_secs() as i32),
+            };
+            setsockopt_raw(socket, level, name, &value)
+        }
+        SockOpt::RecvTimeout(timeout) | SockOpt::SendTimeout(timeout) => {
+            let value = timeout.map_or(TimeVal::default(), |d| TimeVal {
+                tv_sec: d.as_secs() as usize,
+                tv_usec: d.subsec_micros() as usize,
+            });
+            setsockopt_raw(socket, level, name, &value)
+        }
+        SockOpt::ReuseAddr(v)
+        | SockOpt::KeepAlive(v)
+        | SockOpt::Broadcast(v)
+        | SockOpt::TcpNoDelay(v) => setsockopt_raw(socket, level, name, &(v as i32)),
+        SockOpt::SendBuffer(v)
+        | SockOpt::RecvBuffer(v)
+        | SockOpt::TcpMaxSeg(v)
+        | SockOpt::IpTtl(v) => setsockopt_raw(socket, level, name, &(v as i32)),
+        SockOpt::Error(_) => -22,
+    }
+}
+
+/// 读取套接字选项，失败时返回负的错误码
+pub fn getsockopt(socket: usize, name: SockOptName) -> Result<SockOpt, isize> {
+    let (level, raw_name) = name.level_and_name();
+    let opt = match name {
+        SockOptName::Linger => {
+            let value: Linger = getsockopt_raw(socket, level, raw_name)?;
+            SockOpt::Linger(
+                (value.l_onoff != 0).then(|| Duration::from_secs(value.l_linger as u64)),
+            )
+        }
+        SockOptName::RecvTimeout | SockOptName::SendTimeout => {
+            let value: TimeVal = getsockopt_raw(socket, level, raw_name)?;
+            let timeout = Duration::new(value.tv_sec as u64, value.tv_usec as u32 * 1000);
+            let timeout = (!timeout.is_zero()).then_some(timeout);
+            if name == SockOptName::RecvTimeout {
+                SockOpt::RecvTimeout(timeout)
+            } else {
+                SockOpt::SendTimeout(timeout)
+            }
+        }
+        _ => {
+            let value: i32 = getsockopt_raw(socket, level, raw_name)?;
+            match name {
+                SockOptName::ReuseAddr => SockOpt::ReuseAddr(value != 0),
+                SockOptName::KeepAlive => SockOpt::KeepAlive(value != 0),
+                SockOptName::Broadcast => SockOpt::Broadcast(value != 0),
+                SockOptName::TcpNoDelay => SockOpt::TcpNoDelay(value != 0),
+                SockOptName::SendBuffer => SockOpt::SendBuffer(value as u32),
+                SockOptName::RecvBuffer => SockOpt::RecvBuffer(value as u32),
+                SockOptName::TcpMaxSeg => SockOpt::TcpMaxSeg(value as u32),
+                SockOptName::IpTtl => SockOpt::IpTtl(value as u32),
+                SockOptName::Error => SockOpt::Error(value),
+                _ => unreachable!(),
+            }
+        }
+    };
+    Ok(opt)
 }
 
-pub fn getsockopt(socket: usize) -> isize {
-    sys_getsockopt()
+fn setsockopt_raw<T>(socket: usize, level: usize, name: usize, value: &T) -> isize {
+    sys_setsockopt(
+        socket,
+        level,
+        name,
+        value as *const T as *const u8,
+        size_of::<T>(),
+    )
+}
+
+fn getsockopt_raw<T: Default>(socket: usize, level: usize, name: usize) -> Result<T, isize> {
+    let mut value = T::default();
+    let mut len = size_of::<T>() as u32;
+    let res = sys_getsockopt(
+        socket,
+        level,
+        name,
+        &mut value as *mut T as *mut u8,
+        &mut len as *mut u32,
+    );
+    if res < 0 {
+        Err(res)
+    } else {
+        Ok(value)
+    }
 }
 
 pub fn shutdown(socket: usize, how: ShutdownFlag) -> isize {
@@ -156,23 +249,100 @@ pub enum ShutdownFlag {
     SHUTRDWR = 2,
 }
 
-// 暂时使用的是ipv4
+/// 选项所在的层级
+const SOL_SOCKET: usize = 1;
+const IPPROTO_IP: usize = 0;
+const IPPROTO_TCP: usize = 6;
+
+/// 带有取值的套接字选项，用于 [`setsockopt`] 与 [`getsockopt`]
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
-pub struct Sockaddr {
-    pub sa_family: u16,
-    pub sa_port: u16,
-    pub sa_addr: u32,
-    pub zero: [u8; 8],
-}
-
-impl Sockaddr {
-    pub fn new(sa_family: Domain, sa_addr: u32, sa_port: u16) -> Self {
-        let sa_family: u16 = sa_family as u16;
-        Sockaddr {
-            sa_family,
-            sa_port,
-            sa_addr,
-            zero: [0u8; 8],
+pub enum SockOpt {
+    /// SO_REUSEADDR
+    ReuseAddr(bool),
+    /// SO_KEEPALIVE
+    KeepAlive(bool),
+    /// SO_BROADCAST
+    Broadcast(bool),
+    /// SO_SNDBUF
+    SendBuffer(u32),
+    /// SO_RCVBUF
+    RecvBuffer(u32),
+    /// SO_LINGER，`None` 表示关闭
+    Linger(Option<Duration>),
+    /// SO_RCVTIMEO，`None` 表示不超时
+    RecvTimeout(Option<Duration>),
+    /// SO_SNDTIMEO，`None` 表示不超时
+    SendTimeout(Option<Duration>),
+    /// SO_ERROR，只读
+    Error(i32),
+    /// TCP_NODELAY
+    TcpNoDelay(bool),
+    /// TCP_MAXSEG
+    TcpMaxSeg(u32),
+    /// IP_TTL
+    IpTtl(u32),
+}
+
+/// 套接字选项的名称，用于 [`getsockopt`]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum SockOptName {
+    ReuseAddr,
+    KeepAlive,
+    Broadcast,
+    SendBuffer,
+    RecvBuffer,
+    Linger,
+    RecvTimeout,
+    SendTimeout,
+    Error,
+    TcpNoDelay,
+    TcpMaxSeg,
+    IpTtl,
+}
+
+impl SockOpt {
+    pub fn name(&self) -> SockOptName {
+        match self {
+            SockOpt::ReuseAddr(_) => SockOptName::ReuseAddr,
+            SockOpt::KeepAlive(_) => SockOptName::KeepAlive,
+            SockOpt::Broadcast(_) => SockOptName::Broadcast,
+            SockOpt::SendBuffer(_) => SockOptName::SendBuffer,
+            SockOpt::RecvBuffer(_) => SockOptName::RecvBuffer,
+            SockOpt::Linger(_) => SockOptName::Linger,
+            SockOpt::RecvTimeout(_) => SockOptName::RecvTimeout,
+            SockOpt::SendTimeout(_) => SockOptName::SendTimeout,
+            SockOpt::Error(_) => SockOptName::Error,
+            SockOpt::TcpNoDelay(_) => SockOptName::TcpNoDelay,
+            SockOpt::TcpMaxSeg(_) => SockOptName::TcpMaxSeg,
+            SockOpt::IpTtl(_) => SockOptName::IpTtl,
         }
     }
 }
+
+impl SockOptName {
+    /// 返回 `linux` 中对应的 (level, optname)
+    fn level_and_name(&self) -> (usize, usize) {
+        match self {
+            SockOptName::ReuseAddr => (SOL_SOCKET, 2),
+            SockOptName::Error => (SOL_SOCKET, 4),
+            SockOptName::Broadcast => (SOL_SOCKET, 6),
+            SockOptName::SendBuffer => (SOL_SOCKET, 7),
+            SockOptName::RecvBuffer => (SOL_SOCKET, 8),
+            SockOptName::KeepAlive => (SOL_SOCKET, 9),
+            SockOptName::Linger => (SOL_SOCKET, 13),
+            SockOptName::RecvTimeout => (SOL_SOCKET, 20),
+            SockOptName::SendTimeout => (SOL_SOCKET, 21),
+            SockOptName::IpTtl => (IPPROTO_IP, 2),
+            SockOptName::TcpNoDelay => (IPPROTO_TCP, 1),
+            SockOptName::TcpMaxSeg => (IPPROTO_TCP, 2),
+        }
+    }
+}
+
+/// 与 `linux` 中 `struct linger` 对应
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy)]
+struct Linger {
+    l_onoff: i32,
+    l_linger: i32,
+}
@@ -0,0 +1,87 @@
+use core::sync::atomic::{AtomicU32, Ordering};
+use core::time::Duration;
+
+use super::{futex_wait, futex_wake, MutexGuard};
+use crate::time::get_time_ms;
+
+/// 条件变量
+///
+/// 等待者记录下通知计数后释放锁并睡眠，每次通知都会改变计数，
+/// 因此在释放锁与睡眠之间发生的通知不会丢失。与 std 相同，等待可能被虚假唤醒，调用者需要重新检查条件。
+pub struct Condvar {
+    seq: AtomicU32,
+}
+
+/// [`Condvar::wait_timeout`] 的结果
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct WaitTimeoutResult(bool);
+
+impl WaitTimeoutResult {
+    /// 等待是否因为超时而结束
+    pub fn timed_out(&self) -> bool {
+        self.0
+    }
+}
+
+impl Condvar {
+    pub const fn new() -> Self {
+        Self {
+            seq: AtomicU32::new(0),
+        }
+    }
+
+    pub fn wait<'a, T: ?Sized>(&self, guard: MutexGuard<'a, T>) -> MutexGuard<'a, T> {
+        let seq = self.seq.load(Ordering::Relaxed);
+        let mutex = guard.mutex;
+        drop(guard);
+        futex_wait(&self.seq, seq, None);
+        mutex.lock()
+    }
+
+    /// 等待直到 `condition` 返回 false
+    pub fn wait_while<'a, T: ?Sized, F>(
+        &self,
+        mut guard: MutexGuard<'a, T>,
+        mut condition: F,
+    ) -> MutexGuard<'a, T>
+    where
+        F: FnMut(&mut T) -> bool,
+    {
+        while condition(&mut *guard) {
+            guard = self.wait(guard);
+        }
+        guard
+    }
+
+    pub fn wait_timeout<'a, T: ?Sized>(
+        &self,
+        guard: MutexGuard<'a, T>,
+        timeout: Duration,
+    ) -> (MutexGuard<'a, T>, WaitTimeoutResult) {
+        let seq = self.seq.load(Ordering::Relaxed);
+        let mutex = guard.mutex;
+        drop(guard);
+        // 内核的 futex 等待超时后同样返回 0，只能根据经过的时间判断是否超时
+        let start = get_time_ms();
+        futex_wait(&self.seq, seq, Some(timeout));
+        let elapsed = (get_time_ms() - start) as u128;
+        let guard = mutex.lock();
+        (guard, WaitTimeoutResult(elapsed >= timeout.as_millis()))
+    }
+
+    pub fn notify_one(&self) {
+        self.seq.fetch_add(1, Ordering::Relaxed);
+        futex_wake(&self.seq, 1);
+    }
+
+    pub fn notify_all(&self) {
+        self.seq.fetch_add(1, Ordering::Relaxed);
+        futex_wake(&self.seq, i32::MAX as u32);
+    }
+}
+
+impl Default for Condvar {
+    fn default() -> Self {
+        Self::new()
+    }
+}
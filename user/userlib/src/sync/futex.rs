@@ -0,0 +1,40 @@
+use core::ptr::null;
+use core::sync::atomic::AtomicU32;
+use core::time::Duration;
+
+use crate::syscall::sys_futex;
+use crate::time::TimeSpec;
+
+const FUTEX_WAIT_PRIVATE: usize = 128;
+const FUTEX_WAKE_PRIVATE: usize = 129;
+
+/// 如果 `futex` 的值仍为 `expected`，则睡眠直到被唤醒或者超时
+pub(crate) fn futex_wait(futex: &AtomicU32, expected: u32, timeout: Option<Duration>) {
+    let timeout = timeout.map(|timeout| TimeSpec {
+        tv_sec: timeout.as_secs() as usize,
+        tv_nsec: timeout.subsec_nanos() as usize,
+    });
+    let timeout = timeout
+        .as_ref()
+        .map_or(null(), |timeout| timeout as *const TimeSpec as *const u8);
+    sys_futex(
+        futex as *const AtomicU32 as *const u32,
+        FUTEX_WAIT_PRIVATE,
+        expected,
+        timeout,
+        0,
+        0,
+    );
+}
+
+/// 唤醒最多 `count` 个等待在 `futex` 上的线程，返回被唤醒的线程数
+pub(crate) fn futex_wake(futex: &AtomicU32, count: u32) -> isize {
+    sys_futex(
+        futex as *const AtomicU32 as *const u32,
+        FUTEX_WAKE_PRIVATE,
+        count,
+        null(),
+        0,
+        0,
+    )
+}
@@ -1 +1,17 @@
-pub mod mutex;
+//! 线程同步原语
+//!
+//! [`Mutex`]、[`Condvar`]、[`RwLock`] 与 [`Once`] 基于内核的 futex 实现，
+//! 没有竞争时只需一次原子操作，需要等待时线程在内核中睡眠而不是自旋。
+//! 需要自旋锁的场合(如全局分配器)仍可以使用 [`spin`]。
+mod condvar;
+mod futex;
+mod mutex;
+mod once;
+mod rwlock;
+
+pub use condvar::{Condvar, WaitTimeoutResult};
+pub(crate) use futex::{futex_wait, futex_wake};
+pub use mutex::{Mutex, MutexGuard};
+pub use once::Once;
+pub use rwlock::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+pub use spin;
@@ -0,0 +1,48 @@
+use core::sync::atomic::{AtomicU32, Ordering};
+
+use super::{futex_wait, futex_wake};
+
+const INCOMPLETE: u32 = 0;
+const RUNNING: u32 = 1;
+const COMPLETE: u32 = 2;
+
+/// 只执行一次的初始化
+///
+/// 多个线程同时调用 [`Once::call_once`] 时只有一个线程执行初始化，其它线程睡眠直到初始化完成。
+/// 初始化函数中发生 panic 时执行初始化的线程退出，之后的调用者将一直等待。
+pub struct Once {
+    state: AtomicU32,
+}
+
+impl Once {
+    pub const fn new() -> Self {
+        Self {
+            state: AtomicU32::new(INCOMPLETE),
+        }
+    }
+
+    pub fn call_once<F: FnOnce()>(&self, f: F) {
+        let mut f = Some(f);
+        loop {
+            match self.state.compare_exchange(
+                INCOMPLETE,
+                RUNNING,
+                Ordering::Acquire,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => {
+                    (f.take().unwrap())();
+                    self.state.store(COMPLETE, Ordering::Release);
+                    futex_wake(&self.state, i32::MAX as u32);
+                    return;
+                }
+                Err(COMPLETE) => return,
+                Err(_) => futex_wait(&self.state, RUNNING, None),
+            }
+        }
+    }
+
+    pub fn is_completed(&self) -> bool {
+        self.state.load(Ordering::Acquire) == COMPLETE
+    }
+}
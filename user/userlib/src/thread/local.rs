@@ -0,0 +1,142 @@
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use core::any::Any;
+use core::arch::asm;
+use core::cell::UnsafeCell;
+use core::mem;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+/// 线程控制块，tp 寄存器指向当前线程的控制块
+///
+/// 线程局部变量的值按 [`LocalKey`] 的编号保存在控制块中，线程退出时被释放。
+/// 由 [`Builder::spawn`](super::Builder::spawn) 创建的线程的控制块在 `clone` 时通过 `CLONE_SETTLS` 设置，
+/// 主线程的控制块在第一次访问线程局部变量时创建。
+struct ThreadControl {
+    slots: UnsafeCell<BTreeMap<usize, Box<dyn Any>>>,
+}
+
+/// 线程局部变量的编号从 1 开始分配，0 表示尚未分配
+static NEXT_KEY: AtomicUsize = AtomicUsize::new(1);
+
+/// 线程局部变量，每个线程在第一次访问时各自初始化一份
+pub struct LocalKey<T: 'static> {
+    init: fn() -> T,
+    key: AtomicUsize,
+}
+
+impl<T: 'static> LocalKey<T> {
+    #[doc(hidden)]
+    pub const fn new(init: fn() -> T) -> Self {
+        Self {
+            init,
+            key: AtomicUsize::new(0),
+        }
+    }
+
+    fn key(&self) -> usize {
+        let key = self.key.load(Ordering::Acquire);
+        if key != 0 {
+            return key;
+        }
+        let new = NEXT_KEY.fetch_add(1, Ordering::Relaxed);
+        match self
+            .key
+            .compare_exchange(0, new, Ordering::AcqRel, Ordering::Acquire)
+        {
+            Ok(_) => new,
+            Err(key) => key,
+        }
+    }
+
+    /// 以当前线程的值调用 `f`
+    pub fn with<F, R>(&'static self, f: F) -> R
+    where
+        F: FnOnce(&T) -> R,
+    {
+        let control = current_control();
+        let key = self.key();
+        let slot = unsafe { (*control.slots.get()).get(&key) };
+        let value = match slot {
+            Some(value) => value.downcast_ref::<T>().unwrap() as *const T,
+            None => {
+                // 初始化时可能访问其它线程局部变量，不能在此期间持有 slots 的引用
+                let value = (self.init)();
+                let slots = unsafe { &mut *control.slots.get() };
+                let value = slots
+                    .entry(key)
+                    .or_insert_with(|| Box::new(value) as Box<dyn Any>);
+                value.downcast_ref::<T>().unwrap() as *const T
+            }
+        };
+        // 值保存在独立的堆内存中，之后插入其它线程局部变量不会使引用失效
+        f(unsafe { &*value })
+    }
+}
+
+fn current_control() -> &'static ThreadControl {
+    let mut tp: usize;
+    unsafe { asm!("mv {}, tp", out(reg) tp) };
+    if tp == 0 {
+        tp = new_control();
+        unsafe { asm!("mv tp, {}", in(reg) tp) };
+    }
+    unsafe { &*(tp as *const ThreadControl) }
+}
+
+/// 为新线程创建控制块，返回作为 tp 的地址
+pub(super) fn new_control() -> usize {
+    let control = Box::new(ThreadControl {
+        slots: UnsafeCell::new(BTreeMap::new()),
+    });
+    Box::into_raw(control) as usize
+}
+
+pub(super) fn free_control(control: usize) {
+    unsafe { drop(Box::from_raw(control as *mut ThreadControl)) };
+}
+
+/// 线程退出前释放线程局部变量和控制块
+pub(super) fn destroy() {
+    let tp: usize;
+    unsafe { asm!("mv {}, tp", out(reg) tp) };
+    if tp == 0 {
+        return;
+    }
+    let control = unsafe { &*(tp as *const ThreadControl) };
+    // 析构函数中可能再次访问线程局部变量，直到没有新的值产生为止
+    loop {
+        let slots = mem::take(unsafe { &mut *control.slots.get() });
+        if slots.is_empty() {
+            break;
+        }
+        drop(slots);
+    }
+    unsafe { asm!("mv tp, zero") };
+    free_control(tp);
+}
+
+/// 定义线程局部变量，用法与 std 中的同名宏相同：
+///
+/// ```ignore
+/// thread_local! {
+///     static COUNTER: RefCell<usize> = RefCell::new(0);
+/// }
+/// COUNTER.with(|counter| *counter.borrow_mut() += 1);
+/// ```
+#[macro_export]
+macro_rules! thread_local {
+    () => {};
+    ($(#[$attr:meta])* $vis:vis static $name:ident: $t:ty = $init:expr; $($rest:tt)*) => {
+        $crate::thread_local!($(#[$attr])* $vis static $name: $t = $init);
+        $crate::thread_local!($($rest)*);
+    };
+    ($(#[$attr:meta])* $vis:vis static $name:ident: $t:ty = $init:expr) => {
+        $(#[$attr])*
+        $vis static $name: $crate::thread::LocalKey<$t> = {
+            fn __init() -> $t {
+                $init
+            }
+            $crate::thread::LocalKey::new(__init)
+        };
+    };
+}
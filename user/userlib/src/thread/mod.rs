@@ -0,0 +1,207 @@
+//! 线程
+//!
+//! [`spawn`] 通过 `clone(CLONE_VM | CLONE_THREAD | CLONE_SETTLS | CLONE_CHILD_CLEARTID ...)` 创建与当前进程共享地址空间的线程，
+//! 线程的栈通过匿名 `mmap` 分配。线程退出时内核将 [`JoinHandle`] 中记录的线程号清零并唤醒等待在该地址上的 futex，
+//! [`JoinHandle::join`] 据此等待线程结束并取回返回值。
+//!
+//! 线程局部变量通过 [`thread_local!`](crate::thread_local) 定义，见 [`LocalKey`]。
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+use core::arch::global_asm;
+use core::cell::UnsafeCell;
+use core::sync::atomic::{AtomicU32, Ordering};
+
+pub use local::LocalKey;
+
+use crate::common::FRAME_SIZE;
+use crate::ipc::{mmap, munmap, MapFlags, ProtFlags};
+use crate::process::CloneFlags;
+use crate::sync::futex_wait;
+use crate::syscall::{sys_gettid, sys_yield};
+
+mod local;
+
+global_asm!(include_str!("../__clone.asm"));
+
+extern "C" {
+    /// 在新栈 `stack` 上创建执行 `func(arg)` 的线程，`func` 返回后线程以其返回值退出
+    fn __clone(
+        func: extern "C" fn(usize) -> i32,
+        stack: usize,
+        flags: usize,
+        arg: usize,
+        ptid: usize,
+        tls: usize,
+        ctid: *mut u32,
+    ) -> isize;
+}
+
+/// 线程栈的默认大小
+const DEFAULT_STACK_SIZE: usize = 64 * 1024;
+
+pub fn m_yield() -> isize {
+    sys_yield()
+}
+
+pub fn gettid() -> isize {
+    sys_gettid()
+}
+
+/// 线程的配置
+pub struct Builder {
+    stack_size: usize,
+}
+
+impl Builder {
+    pub fn new() -> Self {
+        Self {
+            stack_size: DEFAULT_STACK_SIZE,
+        }
+    }
+
+    /// 设置线程栈的大小，按页向上对齐
+    pub fn stack_size(mut self, size: usize) -> Self {
+        self.stack_size = size;
+        self
+    }
+
+    /// 创建线程执行 `f`，失败时返回系统调用的错误码
+    pub fn spawn<F, T>(self, f: F) -> Result<JoinHandle<T>, isize>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let stack_size = (self.stack_size.max(FRAME_SIZE) + FRAME_SIZE - 1) & !(FRAME_SIZE - 1);
+        let stack = mmap(
+            0,
+            stack_size,
+            ProtFlags::PROT_READ | ProtFlags::PROT_WRITE,
+            MapFlags::MAP_PRIVATE | MapFlags::MAP_ANONYMOUS,
+            usize::MAX,
+            0,
+        );
+        if stack < 0 {
+            return Err(stack);
+        }
+        let stack = stack as usize;
+        let packet = Arc::new(Packet {
+            tid: ChildTid {
+                tid: AtomicU32::new(0),
+                _pad: 0,
+            },
+            result: UnsafeCell::new(None),
+        });
+        let their_packet = packet.clone();
+        let main: Box<dyn FnOnce() + Send> = Box::new(move || {
+            let result = f();
+            unsafe { *their_packet.result.get() = Some(result) };
+        });
+        let arg = Box::into_raw(Box::new(main)) as usize;
+        let tls = local::new_control();
+        let flags = CloneFlags::CLONE_VM
+            | CloneFlags::CLONE_FS
+            | CloneFlags::CLONE_FILES
+            | CloneFlags::CLONE_SIGHAND
+            | CloneFlags::CLONE_THREAD
+            | CloneFlags::CLONE_SYSVSEM
+            | CloneFlags::CLONE_SETTLS
+            | CloneFlags::CLONE_CHILD_CLEARTID;
+        let tid = unsafe {
+            __clone(
+                thread_start,
+                stack + stack_size,
+                flags.bits() as usize,
+                arg,
+                0,
+                tls,
+                &packet.tid.tid as *const AtomicU32 as *mut u32,
+            )
+        };
+        if tid < 0 {
+            unsafe { drop(Box::from_raw(arg as *mut Box<dyn FnOnce() + Send>)) };
+            local::free_control(tls);
+            munmap(stack, stack_size);
+            return Err(tid);
+        }
+        Ok(JoinHandle {
+            packet,
+            stack,
+            stack_size,
+            tid: tid as usize,
+        })
+    }
+}
+
+impl Default for Builder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 新线程的入口，`arg` 为 [`Builder::spawn`] 中装箱的闭包
+extern "C" fn thread_start(arg: usize) -> i32 {
+    let main = unsafe { Box::from_raw(arg as *mut Box<dyn FnOnce() + Send>) };
+    main();
+    local::destroy();
+    0
+}
+
+/// 内核在线程退出时向 `CLONE_CHILD_CLEARTID` 指定的地址写入一个 usize 的 0，因此需要保留 8 字节
+#[repr(C, align(8))]
+struct ChildTid {
+    tid: AtomicU32,
+    _pad: u32,
+}
+
+/// 线程与 [`JoinHandle`] 共享的状态
+struct Packet<T> {
+    /// 线程运行时为线程号，线程退出后为 0
+    tid: ChildTid,
+    result: UnsafeCell<Option<T>>,
+}
+
+unsafe impl<T: Send> Sync for Packet<T> {}
+
+/// 线程的句柄
+///
+/// 调用 [`JoinHandle::join`] 后线程栈才会被释放，直接丢弃句柄时线程继续运行，但它的栈不会被回收。
+pub struct JoinHandle<T> {
+    packet: Arc<Packet<T>>,
+    stack: usize,
+    stack_size: usize,
+    tid: usize,
+}
+
+impl<T> JoinHandle<T> {
+    /// 线程的线程号
+    pub fn tid(&self) -> usize {
+        self.tid
+    }
+
+    /// 线程是否已经退出
+    pub fn is_finished(&self) -> bool {
+        self.packet.tid.tid.load(Ordering::Acquire) == 0
+    }
+
+    /// 等待线程退出并返回它的返回值，线程因 panic 退出时返回 `None`
+    pub fn join(self) -> Option<T> {
+        loop {
+            let tid = self.packet.tid.tid.load(Ordering::Acquire);
+            if tid == 0 {
+                break;
+            }
+            futex_wait(&self.packet.tid.tid, tid, None);
+        }
+        munmap(self.stack, self.stack_size);
+        unsafe { (*self.packet.result.get()).take() }
+    }
+}
+
+/// 创建线程执行 `f`
+pub fn spawn<F, T>(f: F) -> JoinHandle<T>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    Builder::new().spawn(f).expect("failed to spawn thread")
+}
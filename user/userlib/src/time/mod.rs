@@ -1,37 +1,8 @@
-use crate::syscall::{sys_get_time, sys_nanosleep};
+use core::time::Duration;
 
-#[repr(C)]
-#[derive(Debug, Copy, Clone)]
-pub struct Times {
-    /// the ticks of user mode
-    pub tms_utime: usize,
-    /// the ticks of kernel mode
-    pub tms_stime: usize,
-    /// the ticks of user mode of child process
-    pub tms_cutime: usize,
-    /// the ticks of kernel mode of child process
-    pub tms_cstime: usize,
-}
+pub use abi::{TimeVal, Times};
 
-impl Times {
-    pub fn new() -> Self {
-        Self {
-            tms_utime: 0,
-            tms_stime: 0,
-            tms_cutime: 0,
-            tms_cstime: 0,
-        }
-    }
-}
-
-#[repr(C)]
-#[derive(Copy, Clone, Debug, Default)]
-pub struct TimeVal {
-    /// seconds
-    pub tv_sec: usize,
-    /// microseconds
-    pub tv_usec: usize,
-}
+use crate::syscall::{sys_clock_gettime, sys_clock_nanosleep, sys_get_time, sys_nanosleep};
 
 #[repr(C)]
 #[derive(Copy, Clone, Debug, Default)]
@@ -49,8 +20,28 @@ impl From<TimeVal> for TimeSpec {
     }
 }
 
-impl TimeVal {
-    pub fn now() -> Self {
+impl From<Duration> for TimeSpec {
+    fn from(duration: Duration) -> Self {
+        Self {
+            tv_sec: duration.as_secs() as usize,
+            tv_nsec: duration.subsec_nanos() as usize,
+        }
+    }
+}
+
+impl From<TimeSpec> for Duration {
+    fn from(ts: TimeSpec) -> Self {
+        Duration::new(ts.tv_sec as u64, ts.tv_nsec as u32)
+    }
+}
+
+/// 能够获取当前时间的时间结构
+pub trait TimeNow {
+    fn now() -> Self;
+}
+
+impl TimeNow for TimeVal {
+    fn now() -> Self {
         let mut tv = TimeVal::default();
         get_time_of_day(&mut tv);
         tv
@@ -80,3 +71,25 @@ pub fn sleep(ms: usize) {
     ts.tv_nsec = (ms % 1000) * 1000000;
     sys_nanosleep(&mut ts as *mut TimeSpec as *mut u8, 0 as *mut u8);
 }
+
+/// 时钟编号
+pub const CLOCK_REALTIME: usize = 0;
+pub const CLOCK_MONOTONIC: usize = 1;
+/// `clock_nanosleep` 的参数为绝对时间
+pub const TIMER_ABSTIME: usize = 1;
+
+pub fn clock_gettime(clock_id: usize, ts: &mut TimeSpec) -> isize {
+    sys_clock_gettime(clock_id, ts as *mut TimeSpec as *mut u8)
+}
+
+/// 睡眠到时钟 `clock_id` 的时间 `ts`(`flags` 为 [`TIMER_ABSTIME`]) 或者睡眠 `ts` 时长
+///
+/// 内核目前只支持 [`CLOCK_MONOTONIC`] 与 [`TIMER_ABSTIME`]
+pub fn clock_nanosleep(clock_id: usize, flags: usize, ts: &TimeSpec) -> isize {
+    sys_clock_nanosleep(
+        clock_id,
+        flags,
+        ts as *const TimeSpec as *const u8,
+        core::ptr::null_mut(),
+    )
+}